@@ -0,0 +1,64 @@
+//! Compares drawing a dashboard call by call with drawing it as one batch, on a screen and
+//! on a shared screen, where each call takes the lock.
+//!
+//! Run on the board with `cargo bench --bench draw_batch`. Elsewhere `libuptech.so` cannot be
+//! loaded, every call fails at the symbol lookup and the numbers mean nothing.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use uptechstar_rs::display::{Color, DrawOp, FontSize, Icon, Screen, ScreenDirection};
+
+/// A 20-element dashboard: a title, eight labelled bar gauges in two columns and a status
+/// icon, all inside the 128x64 LCD.
+fn dashboard() -> Vec<DrawOp> {
+    let mut ops = vec![
+        DrawOp::FillScreen(Color::BLACK),
+        DrawOp::SetFontSize(FontSize::Font6x8),
+        DrawOp::PutString { x: 0, y: 0, text: "ADC".to_string() },
+    ];
+    for channel in 0..8 {
+        let (x, y) = (channel / 4 * 64, 12 + channel % 4 * 12);
+        ops.push(DrawOp::PutString { x, y, text: format!("ch{}", channel) });
+        ops.push(DrawOp::FillFrame { x1: x + 24, y1: y, x2: x + 24 + channel * 4, y2: y + 5, color: Color::GREEN });
+    }
+    ops.push(DrawOp::DrawIcon { icon: Icon::Check, x: 116, y: 0 });
+    ops
+}
+
+fn draw(c: &mut Criterion) {
+    let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+    let ops = dashboard();
+
+    let mut group = c.benchmark_group("dashboard");
+    group.bench_function("apply", |b| {
+        b.iter(|| {
+            for op in black_box(&ops) {
+                screen.apply(op);
+            }
+        })
+    });
+    group.bench_function("draw_batch", |b| {
+        b.iter(|| {
+            screen.draw_batch(black_box(&ops));
+        })
+    });
+
+    let shared = screen.into_shared();
+    group.bench_function("shared_draw", |b| {
+        b.iter(|| {
+            for op in black_box(&ops) {
+                shared.draw(|screen| {
+                    screen.apply(op);
+                });
+            }
+        })
+    });
+    group.bench_function("shared_draw_batch", |b| {
+        b.iter(|| {
+            shared.draw_batch(black_box(&ops));
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, draw);
+criterion_main!(benches);
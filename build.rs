@@ -0,0 +1,90 @@
+fn main() {
+    #[cfg(feature = "bindgen")]
+    bindings::generate();
+    #[cfg(feature = "capi")]
+    header::generate();
+}
+
+/// Writes the C header declaring the `capi` exports to `OUT_DIR`, and warns if the committed
+/// copy in `include/` no longer matches it.
+#[cfg(feature = "capi")]
+mod header {
+    use std::env;
+    use std::path::PathBuf;
+
+    const HEADER: &str = "include/uptechstar.h";
+
+    pub fn generate() {
+        let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+        let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("uptechstar.h");
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-changed={}", HEADER);
+
+        let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).expect("Failed to read cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(crate_dir.join("src/capi.rs"))
+            .generate()
+            .expect("Failed to generate the C header from src/capi.rs")
+            .write_to_file(&out);
+
+        let generated = std::fs::read(&out).expect("Failed to read the generated C header");
+        if std::fs::read(crate_dir.join(HEADER)).ok() != Some(generated) {
+            println!(
+                "cargo:warning={} is out of date with src/capi.rs; regenerate it with \
+                 `cbindgen --config cbindgen.toml --output {}` or copy {}",
+                HEADER,
+                HEADER,
+                out.display()
+            );
+        }
+    }
+}
+
+/// Generates the reference FFI declarations the hand-written signatures are checked against.
+#[cfg(feature = "bindgen")]
+mod bindings {
+    use std::env;
+    use std::path::PathBuf;
+
+    /// Path of the vendor C header, which is not part of the repository.
+    const HEADER_ENV: &str = "UPTECH_HEADER";
+    /// Pre-generated bindings, for build hosts without libclang.
+    const BINDINGS_ENV: &str = "UPTECH_BINDINGS";
+
+    pub fn generate() {
+        println!("cargo:rerun-if-env-changed={}", HEADER_ENV);
+        println!("cargo:rerun-if-env-changed={}", BINDINGS_ENV);
+
+        let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bindings.rs");
+
+        if let Ok(pregenerated) = env::var(BINDINGS_ENV) {
+            println!("cargo:rerun-if-changed={}", pregenerated);
+            std::fs::copy(&pregenerated, &out).unwrap_or_else(|e| panic!("Failed to copy {}: {}", pregenerated, e));
+            return;
+        }
+
+        let header = env::var(HEADER_ENV).map(PathBuf::from).unwrap_or_else(|_| {
+            panic!(
+                "The bindgen feature needs libuptech.h from the Uptech SDK, which is not part of this \
+                 repository: set {} to its path, or {} to pre-generated bindings",
+                HEADER_ENV, BINDINGS_ENV
+            )
+        });
+        if !header.exists() {
+            panic!("{} points to {}, which does not exist", HEADER_ENV, header.display());
+        }
+        println!("cargo:rerun-if-changed={}", header.display());
+
+        bindgen::Builder::default()
+            .header(header.to_string_lossy())
+            .allowlist_function("adc_.*|ADC_.*|lcd_.*|LCD_.*|UG_.*|mpu.*|MPU_.*|dmp_.*|inv_.*|cds_servo_.*|drv_serial_.*|msleep_s|usleep_s")
+            .rust_edition(bindgen::RustEdition::Edition2024)
+            .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
+            .generate()
+            .expect("Failed to generate bindings from libuptech.h")
+            .write_to_file(&out)
+            .expect("Failed to write bindings");
+    }
+}
@@ -0,0 +1,161 @@
+/* Generated by cbindgen from src/capi.rs; do not edit. */
+
+#ifndef UPTECHSTAR_H
+#define UPTECHSTAR_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The call succeeded.
+#define UPTECH_OK 0
+
+// A hardware call returned a failure status code.
+#define UPTECH_ERR_HARDWARE -1
+
+// `libuptech.so` could not be loaded or lacks a required function.
+#define UPTECH_ERR_LIBRARY -2
+
+// The board configuration is invalid.
+#define UPTECH_ERR_CONFIG -3
+
+// Reading the configuration file failed.
+#define UPTECH_ERR_IO -4
+
+// A pointer was null, a string was not UTF-8 or a value was out of range.
+#define UPTECH_ERR_INVALID_ARGUMENT -5
+
+// [`uptech_init`] has not been called, or the board has no screen.
+#define UPTECH_ERR_NOT_INITIALIZED -6
+
+// The library panicked; it should not be used any further.
+#define UPTECH_ERR_PANIC -7
+
+// A peripheral was already initialized by another part of the program.
+#define UPTECH_ERR_ALREADY_INITIALIZED -8
+
+// Number of ADC channels in [`UptechState::adc`].
+#define UPTECH_ADC_CHANNELS 10
+
+// Background sampling of the ADC, IO pins and MPU, created by [`uptech_sampler_start`].
+typedef struct UptechSampler UptechSampler;
+
+// A combined MPU6500 reading.
+typedef struct UptechMpuFrame {
+  // Acceleration in g.
+  float accel[3];
+  // Angular velocity in degrees per second.
+  float gyro[3];
+  // Pitch, roll and yaw in degrees.
+  float attitude[3];
+} UptechMpuFrame;
+
+// A snapshot published by a sampler. Sources that have not been read are flagged by the
+// `has_*` fields.
+typedef struct UptechState {
+  // Number of snapshots published so far, starting at 1.
+  uint64_t sequence;
+  // Microseconds since the sampler was started.
+  uint64_t timestamp_us;
+  bool has_adc;
+  // Raw values of the 10 ADC channels.
+  int32_t adc[UPTECH_ADC_CHANNELS];
+  bool has_io;
+  // IO input levels, bit `n` for pin `n`.
+  uint8_t io;
+  bool has_mpu;
+  struct UptechMpuFrame mpu;
+} UptechState;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a description of the last failure on the calling thread, valid until the next
+// failing call on that thread. Empty if nothing has failed yet.
+const char *uptech_last_error(void);
+
+// Initializes the board from the TOML file at `config_path`, or with the default
+// configuration if it is null. Replaces a board initialized before.
+//
+// # Safety
+//
+// `config_path` must be null or point to a NUL-terminated string.
+int uptech_init(const char *config_path);
+
+// Releases the board initialized by [`uptech_init`]. Does nothing if there is none.
+void uptech_close(void);
+
+// Reads the raw values of all 10 ADC channels into `out`.
+//
+// # Safety
+//
+// `out` must point to an array of at least 10 `int32_t`.
+int uptech_adc_read(int32_t *out);
+
+// Reads the ADC channel called `name` in the board configuration, with its calibration
+// applied.
+//
+// # Safety
+//
+// `name` must point to a NUL-terminated string and `out` to a `float`.
+int uptech_adc_value(const char *name, float *out);
+
+// Reads the input levels of all IO pins into `out`, bit `n` for pin `n`.
+//
+// # Safety
+//
+// `out` must point to a `uint8_t`.
+int uptech_io_read(uint8_t *out);
+
+// Sets the output levels of all IO pins, bit `n` for pin `n`.
+int uptech_io_write(uint8_t levels);
+
+// Reads acceleration, angular velocity and attitude into `out`.
+//
+// # Safety
+//
+// `out` must point to an `UptechMpuFrame`.
+int uptech_mpu_read(struct UptechMpuFrame *out);
+
+// Fills the board's screen with `color`, as `0xRRGGBB`.
+int uptech_screen_fill(uint32_t color);
+
+// Draws `text` at `x`, `y` on the board's screen in `color`, as `0xRRGGBB`. Call
+// [`uptech_screen_refresh`] to show it.
+//
+// # Safety
+//
+// `text` must point to a NUL-terminated string.
+int uptech_draw_text(int32_t x, int32_t y, const char *text, uint32_t color);
+
+// Shows what has been drawn on the board's screen.
+int uptech_screen_refresh(void);
+
+// Starts sampling the ADC, IO pins and MPU `rate_hz` times per second on a background thread.
+// Returns null if `rate_hz` is not a positive, finite number.
+struct UptechSampler *uptech_sampler_start(float rate_hz);
+
+// Copies the latest snapshot of `sampler` into `out`. The snapshot's `sequence` is 0 until
+// the first one has been taken.
+//
+// # Safety
+//
+// `sampler` must come from [`uptech_sampler_start`] and not be stopped yet; `out` must point
+// to an `UptechState`.
+int uptech_sampler_latest(const struct UptechSampler *sampler, struct UptechState *out);
+
+// Stops `sampler` and frees it. Does nothing if `sampler` is null.
+//
+// # Safety
+//
+// `sampler` must come from [`uptech_sampler_start`] and must not be used afterwards.
+void uptech_sampler_stop(struct UptechSampler *sampler);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* UPTECHSTAR_H */
@@ -0,0 +1,109 @@
+//! ROS 2 bridge node for an Uptech board.
+//!
+//! Publishes `sensor_msgs/msg/Imu` on `uptech/imu` and the ADC channels as
+//! `std_msgs/msg/Int32MultiArray` on `uptech/adc` at 50 Hz, and applies
+//! `std_msgs/msg/ColorRGBA` commands on `uptech/led/0` and `uptech/led/1` and
+//! `std_msgs/msg/String` commands on `uptech/display`.
+//!
+//! Build it in a colcon workspace with `colcon-ros-cargo`, then run it with
+//! `ros2 run uptech_bridge uptech_bridge`.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rclrs::{QOS_PROFILE_DEFAULT, QOS_PROFILE_SENSOR_DATA, RclReturnCode, RclrsError};
+use uptechstar_rs::adc_io;
+use uptechstar_rs::display::{Screen, ScreenDirection};
+use uptechstar_rs::mpu;
+use uptechstar_rs::ros2::{self, CommandHandler, Imu};
+
+/// Interval between published samples.
+const PERIOD: Duration = Duration::from_millis(20);
+
+/// Frame the IMU readings are reported in.
+const IMU_FRAME: &str = "imu_link";
+
+fn main() -> Result<(), RclrsError> {
+    let context = rclrs::Context::new(std::env::args())?;
+    let node = rclrs::create_node(&context, "uptech_bridge")?;
+
+    if adc_io::adc_open() < 0 {
+        eprintln!("Failed to open ADC-IO");
+    }
+    if mpu::mpu6500_open() != 0 {
+        eprintln!("Failed to initialize the MPU6500");
+    }
+
+    let imu_publisher = node.create_publisher::<sensor_msgs::msg::Imu>(ros2::IMU_TOPIC, QOS_PROFILE_SENSOR_DATA)?;
+    let adc_publisher = node.create_publisher::<std_msgs::msg::Int32MultiArray>(ros2::ADC_TOPIC, QOS_PROFILE_SENSOR_DATA)?;
+
+    let handler = Arc::new(Mutex::new(CommandHandler::new(Screen::new(Some(ScreenDirection::Horizontal)))));
+    let mut subscriptions = Vec::new();
+    for index in 0..2 {
+        let handler = Arc::clone(&handler);
+        subscriptions.push(node.create_subscription::<std_msgs::msg::ColorRGBA, _>(
+            &format!("{}/{}", ros2::LED_TOPIC, index),
+            QOS_PROFILE_DEFAULT,
+            move |color: std_msgs::msg::ColorRGBA| {
+                let mut handler = handler.lock().unwrap_or_else(|e| e.into_inner());
+                handler.set_led(index, color.r, color.g, color.b, color.a);
+            },
+        )?);
+    }
+    let _display = node.create_subscription::<std_msgs::msg::String, _>(ros2::DISPLAY_TOPIC, QOS_PROFILE_DEFAULT, {
+        let handler = Arc::clone(&handler);
+        move |text: std_msgs::msg::String| {
+            handler.lock().unwrap_or_else(|e| e.into_inner()).show_text(&text.data);
+        }
+    })?;
+
+    let mut deadline = Instant::now();
+    while context.ok() {
+        let now = Instant::now();
+        if now >= deadline {
+            deadline += PERIOD;
+            let stamp = node.get_clock().now().nsec;
+
+            if let Ok(sample) = mpu::mpu6500_get_sample() {
+                imu_publisher.publish(imu_message(&Imu::from_sample(&sample, mpu::units()), stamp))?;
+            }
+            if let Ok(frame) = adc_io::adc_get_frame() {
+                let message = std_msgs::msg::Int32MultiArray {
+                    data: ros2::adc_data(&frame),
+                    ..Default::default()
+                };
+                adc_publisher.publish(message)?;
+            }
+            continue;
+        }
+
+        match rclrs::spin_once(Arc::clone(&node), Some(deadline - now)) {
+            Ok(()) => {}
+            Err(RclrsError::RclError {
+                code: RclReturnCode::Timeout,
+                ..
+            }) => {}
+            Err(error) => return Err(error),
+        }
+    }
+
+    adc_io::adc_close();
+    Ok(())
+}
+
+/// Fills a `sensor_msgs/msg/Imu` from the converted sample, stamped `stamp` nanoseconds.
+fn imu_message(imu: &Imu, stamp: i64) -> sensor_msgs::msg::Imu {
+    let mut message = sensor_msgs::msg::Imu::default();
+    message.header.frame_id = IMU_FRAME.into();
+    message.header.stamp.sec = stamp.div_euclid(1_000_000_000) as i32;
+    message.header.stamp.nanosec = stamp.rem_euclid(1_000_000_000) as u32;
+
+    [message.orientation.x, message.orientation.y, message.orientation.z, message.orientation.w] = imu.orientation;
+    message.orientation_covariance = imu.orientation_covariance;
+    [message.angular_velocity.x, message.angular_velocity.y, message.angular_velocity.z] = imu.angular_velocity;
+    message.angular_velocity_covariance = imu.angular_velocity_covariance;
+    [message.linear_acceleration.x, message.linear_acceleration.y, message.linear_acceleration.z] =
+        imu.linear_acceleration;
+    message.linear_acceleration_covariance = imu.linear_acceleration_covariance;
+    message
+}
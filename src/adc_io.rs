@@ -1,7 +1,55 @@
-use crate::extern_lib::LIBRARY;
-use libloading::Symbol;
-
-use log::{debug, error, info};
+use crate::backend;
+use crate::health::{self, Subsystem};
+use crate::log_limit;
+use crate::registry;
+use crate::retry;
+
+use crate::log_level::adc::{debug, info};
+use std::sync::Mutex;
+
+mod bus;
+mod dht;
+mod handle;
+#[cfg(feature = "embedded-hal")]
+mod hal;
+mod ir;
+mod keypad;
+mod motor;
+mod onewire;
+mod pin;
+mod pwm;
+pub mod sensors;
+mod shift;
+mod spi;
+mod stepper;
+mod uart;
+
+pub use bus::ParallelBus;
+pub use dht::{Dht, DhtModel, DhtReading};
+pub use handle::AdcIo;
+#[cfg(feature = "embedded-hal")]
+pub use hal::{Adc, AdcPin, HalError};
+pub use ir::{IrEvent, IrReceiver, NecDecoder};
+pub use keypad::{KeyEvent, Keypad};
+pub use motor::DcMotor;
+pub use onewire::{Ds18b20, OneWire, RomCode, crc8};
+pub use pin::{IoPin, Pin};
+pub use pwm::SoftPwm;
+pub use shift::{ShiftIn, ShiftInPin, ShiftOut, ShiftOutPin};
+pub use spi::{SoftSpi, SpiMode};
+pub use stepper::{StepMode, Stepper};
+pub use uart::SoftUart;
+
+/// The output levels last written through this module, so masked writes do not have to read
+/// them back. `None` until the first successful write.
+static OUTPUT_SHADOW: Mutex<Option<u8>> = Mutex::new(None);
+
+/// Forgets the state tied to the open ADC-IO channel, after it was closed.
+pub(crate) fn reset_channel_state() {
+    *OUTPUT_SHADOW.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    // The MPU hangs off the same channel and has to be initialized again after reopening it.
+    *registry::MPU.lock().unwrap_or_else(|e| e.into_inner()) = false;
+}
 
 /// Opens the ADC-IO plug.
 ///
@@ -19,24 +67,22 @@ use log::{debug, error, info};
 pub fn adc_open() -> i32 {
     info!("Initializing ADC-IO");
 
-    unsafe {
-        let adc_io_open: Symbol<unsafe extern "C" fn() -> i32> = LIBRARY
-            .get(b"adc_io_open")
-            .expect("Failed to load adc_io_open function");
+    let open_times = backend::current().adc_io_open();
 
-        let open_times = adc_io_open();
-
-        if open_times == -1 {
-            error!(
+    if open_times == -1 {
+        log_limit::ffi_error(
+            "adc_io_open",
+            open_times,
+            format_args!(
                 "Failed to open ADC-IO. Do check if the channel is opened by calling 'adc_io_open()' \
                  and the libuptech.so being loaded properly"
-            );
-        } else {
-            debug!("ADC-IO open {} times", open_times);
-        }
-
-        open_times
+            ),
+        );
+    } else {
+        debug!("ADC-IO open {} times", open_times);
     }
+
+    open_times
 }
 
 /// Closes the ADC-IO plug.
@@ -52,27 +98,29 @@ pub fn adc_open() -> i32 {
 ///
 /// This function uses unsafe code to interact with a C library. Ensure that the shared library ([libuptech.so](file://L:\RustProjects\uptechstar-rs\lib\libuptech.so))
 /// is properly loaded and the `adc_io_close` function is available.
+///
+/// Code that shares the peripheral with other parts of the program should hold an [`AdcIo`]
+/// handle instead, which only closes it once no handle is left.
 pub fn adc_close() -> i32 {
     info!("Closing ADC-IO");
 
-    unsafe {
-        let adc_io_close: Symbol<unsafe extern "C" fn() -> i32> = LIBRARY
-            .get(b"adc_io_close")
-            .expect("Failed to load adc_io_close function");
+    let result = backend::current().adc_io_close();
+    reset_channel_state();
 
-        let result = adc_io_close();
-
-        if result == -1 {
-            error!(
+    if result == -1 {
+        log_limit::ffi_error(
+            "adc_io_close",
+            result,
+            format_args!(
                 "Failed to close ADC-IO. Do check if the channel is opened by calling 'adc_io_open()' \
                  and the libuptech.so being loaded properly"
-            );
-        } else {
-            debug!("ADC-IO closed");
-        }
-
-        result
+            ),
+        );
+    } else {
+        debug!("ADC-IO closed");
     }
+
+    result
 }
 
 /// Retrieves all ADC channels' data.
@@ -88,28 +136,29 @@ pub fn adc_close() -> i32 {
 ///
 /// * `Result<(), &'static str>` - Returns `Ok(())` on success, or an error message on failure.
 ///
+/// Failed reads are retried according to [`retry::policy()`](crate::retry::policy) first.
+///
 /// # Safety
 ///
 /// This function uses unsafe code to interact with a C library. Ensure that the shared library ([libuptech.so](file://L:\RustProjects\uptechstar-rs\lib\libuptech.so))
 /// is properly loaded and the `ADC_GetAll` function is available.
 pub fn adc_get_all_channels(adc_data: &mut [i32; 10]) -> Result<(), &'static str> {
-    unsafe {
-        let adc_get_all: Symbol<unsafe extern "C" fn(*mut i32) -> i32> = LIBRARY
-            .get(b"ADC_GetAll")
-            .expect("Failed to load ADC_GetAll function");
-
-        let result = adc_get_all(adc_data.as_mut_ptr());
-
-        if result != 0 {
-            error!(
+    let result = retry::policy().run(|| backend::current().adc_get_all(adc_data));
+    health::record(Subsystem::AdcIo, result);
+
+    if result != 0 {
+        log_limit::ffi_error(
+            "ADC_GetAll",
+            result,
+            format_args!(
                 "Failed to get all ADC channels. Do check if the channel is opened by calling 'adc_io_open()' \
                  and the libuptech.so being loaded properly"
-            );
-            return Err("Failed to get all ADC channels");
-        }
-
-        Ok(())
+            ),
+        );
+        return Err("Failed to get all ADC channels");
     }
+
+    Ok(())
 }
 
 /// Retrieves the input levels of all IO channels.
@@ -124,7 +173,7 @@ pub fn adc_get_all_channels(adc_data: &mut [i32; 10]) -> Result<(), &'static str
 ///
 /// # Examples
 ///
-/// ```
+/// ```rust,no_run
 /// use uptechstar_rs::adc_io::io_get_all_channels;
 /// let levels = io_get_all_channels();
 /// if levels & 0b0000_0001 != 0 {
@@ -137,13 +186,22 @@ pub fn adc_get_all_channels(adc_data: &mut [i32; 10]) -> Result<(), &'static str
 /// This function uses unsafe code to interact with a C library. Ensure that the shared library ([libuptech.so](file://L:\RustProjects\uptechstar-rs\lib\libuptech.so))
 /// is properly loaded and the `adc_io_InputGetAll` function is available.
 pub fn io_get_all_channels() -> u8 {
-    unsafe {
-        let adc_io_input_get_all: Symbol<unsafe extern "C" fn() -> u8> = LIBRARY
-            .get(b"adc_io_InputGetAll")
-            .expect("Failed to load adc_io_InputGetAll function");
+    backend::current().io_get_all()
+}
 
-        adc_io_input_get_all()
-    }
+/// Retrieves the input levels of all IO channels as an [`IoFrame`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::io_get_frame;
+///
+/// if io_get_frame().level(3) {
+///     println!("IO3 is high");
+/// }
+/// ```
+pub fn io_get_frame() -> IoFrame {
+    IoFrame(io_get_all_channels())
 }
 
 /// Retrieves the level of a specific IO index.
@@ -182,7 +240,7 @@ pub fn get_io_level(index: usize) -> u8 {
 ///
 /// # Examples
 ///
-/// ```
+/// ```rust,no_run
 /// use uptechstar_rs::adc_io::set_all_io_levels;
 /// set_all_io_levels(0b0000_0001); // Set IO0 to high, others to low
 /// ```
@@ -192,22 +250,78 @@ pub fn get_io_level(index: usize) -> u8 {
 /// This function uses unsafe code to interact with a C library. Ensure that the shared library ([libuptech.so](file://L:\RustProjects\uptechstar-rs\lib\libuptech.so))
 /// is properly loaded and the `adc_io_SetAll` function is available.
 pub fn set_all_io_levels(levels: u32) -> i32 {
-    unsafe {
-        let adc_io_set_all: Symbol<unsafe extern "C" fn(u32) -> i32> = LIBRARY
-            .get(b"adc_io_SetAll")
-            .expect("Failed to load adc_io_SetAll function");
+    let mut shadow = OUTPUT_SHADOW.lock().unwrap_or_else(|e| e.into_inner());
+
+    let result = backend::current().io_set_all(levels);
+    health::record(Subsystem::AdcIo, result);
 
-        let result = adc_io_set_all(levels);
+    if result == 0 {
+        *shadow = Some(levels as u8);
+    }
 
-        if result != 0 {
-            error!(
+    if result != 0 {
+        log_limit::ffi_error(
+            "adc_io_SetAll",
+            result,
+            format_args!(
                 "Failed to set all IO level. Do check if the channel is opened by calling 'adc_io_open()' \
                  and the libuptech.so being loaded properly"
-            );
-        }
+            ),
+        );
+    }
+
+    result
+}
+
+/// Sets the levels of the pins selected by `mask`, leaving all other pins unchanged.
+///
+/// The new levels are merged into the output state last written through this module and sent
+/// in a single `adc_io_SetAll` call. The merge and the write happen under one lock, so threads
+/// driving different pins (e.g. two steppers) cannot overwrite each other's changes the way a
+/// separate read-modify-write would. Before the first write, the current state is read back
+/// with [`io_get_all_channels`].
+///
+/// # Arguments
+///
+/// * `mask` - The pins to change, one bit per IO channel.
+/// * `values` - The new levels; bits outside `mask` are ignored.
+///
+/// # Returns
+///
+/// * `i32` - Returns `0` on success, non-zero on failure.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::set_io_levels_with_mask;
+///
+/// // Drive IO0-IO3 to 0b1010 without touching IO4-IO7
+/// set_io_levels_with_mask(0b0000_1111, 0b0000_1010);
+/// ```
+pub fn set_io_levels_with_mask(mask: u8, values: u8) -> i32 {
+    let mut shadow = OUTPUT_SHADOW.lock().unwrap_or_else(|e| e.into_inner());
+
+    let current = shadow.unwrap_or_else(io_get_all_channels);
+    let levels = (current & !mask) | (values & mask);
+
+    let result = backend::current().io_set_all(levels as u32);
+    health::record(Subsystem::AdcIo, result);
 
-        result
+    if result != 0 {
+        log_limit::ffi_error(
+            "adc_io_SetAll",
+            result,
+            format_args!(
+                "Failed to set IO levels, mask: {:#010b}. Do check if the channel is opened by calling 'adc_io_open()' \
+                 and the libuptech.so being loaded properly",
+                mask
+            ),
+        );
+        return result;
     }
+
+    *shadow = Some(levels);
+    result
 }
 
 /// Flips the level of a specific IO index.
@@ -232,23 +346,30 @@ pub fn set_all_io_levels(levels: u32) -> i32 {
 /// This function uses unsafe code to interact with a C library. Ensure that the shared library ([libuptech.so](file://L:\RustProjects\uptechstar-rs\lib\libuptech.so))
 /// is properly loaded and the `adc_io_Set` function is available.
 pub fn flip_io_level(index: u32) -> i32 {
-    unsafe {
-        let adc_io_set: Symbol<unsafe extern "C" fn(u32) -> i32> = LIBRARY
-            .get(b"adc_io_Set")
-            .expect("Failed to load adc_io_Set function");
+    let mut shadow = OUTPUT_SHADOW.lock().unwrap_or_else(|e| e.into_inner());
 
-        let result = adc_io_set(index);
+    let result = backend::current().io_flip(index);
+    health::record(Subsystem::AdcIo, result);
+
+    if result == 0
+        && let Some(levels) = shadow.as_mut()
+    {
+        *levels ^= 1u8.checked_shl(index).unwrap_or(0);
+    }
 
-        if result == -1 {
-            error!(
+    if result == -1 {
+        log_limit::ffi_error(
+            "adc_io_Set",
+            result,
+            format_args!(
                 "Failed to flip IO level, index: {}. Do check if the channel is opened by calling 'adc_io_open()' \
                  and the libuptech.so being loaded properly",
                 index
-            );
-        }
-
-        result
+            ),
+        );
     }
+
+    result
 }
 
 /// Retrieves the modes of all IO channels.
@@ -263,7 +384,7 @@ pub fn flip_io_level(index: u32) -> i32 {
 ///
 /// # Examples
 ///
-/// ```
+/// ```rust,no_run
 /// use uptechstar_rs::adc_io::get_all_io_mode;
 /// let modes = get_all_io_mode();
 /// if modes & 0b0000_0001 != 0 {
@@ -276,21 +397,21 @@ pub fn flip_io_level(index: u32) -> i32 {
 /// This function uses unsafe code to interact with a C library. Ensure that the shared library ([libuptech.so](file://L:\RustProjects\uptechstar-rs\lib\libuptech.so))
 /// is properly loaded and the `adc_io_ModeGetAll` function is available.
 pub fn get_all_io_mode() -> u8 {
-    unsafe {
-        let mut buffer: u8 = 0;
-        let adc_io_mode_get_all: Symbol<unsafe extern "C" fn(*mut u8) -> i32> = LIBRARY
-            .get(b"adc_io_ModeGetAll")
-            .expect("Failed to load adc_io_ModeGetAll function");
-
-        if adc_io_mode_get_all(&mut buffer) != 0 {
-            error!(
+    let mut buffer: u8 = 0;
+
+    let code = backend::current().io_mode_get_all(&mut buffer);
+    if code != 0 {
+        log_limit::ffi_error(
+            "adc_io_ModeGetAll",
+            code,
+            format_args!(
                 "Failed to get all IO mode. Do check if the channel is opened by calling 'adc_io_open()' \
                  and the libuptech.so being loaded properly"
-            );
-        }
-
-        buffer
+            ),
+        );
     }
+
+    buffer
 }
 
 /// Sets the modes of all IO channels.
@@ -311,29 +432,29 @@ pub fn get_all_io_mode() -> u8 {
 /// This function uses unsafe code to interact with a C library. Ensure that the shared library ([libuptech.so](file://L:\RustProjects\uptechstar-rs\lib\libuptech.so))
 /// is properly loaded and the `adc_io_ModeSet` function is available.
 pub fn set_all_io_mode(mode: u8) -> i32 {
-    unsafe {
-        let adc_io_mode_set: Symbol<unsafe extern "C" fn(u32, i32) -> i32> = LIBRARY
-            .get(b"adc_io_ModeSet")
-            .expect("Failed to load adc_io_ModeSet function");
-
-        let mut failed = false;
-        for index in 0..8 {
-            if adc_io_mode_set(index, mode as i32) != 0 {
-                failed = true;
-            }
+    let backend = backend::current();
+
+    let mut failed = false;
+    for index in 0..8 {
+        if backend.io_mode_set(index, mode as i32) != 0 {
+            failed = true;
         }
+    }
 
-        if failed {
-            error!(
+    if failed {
+        log_limit::ffi_error(
+            "adc_io_ModeSet",
+            -1,
+            format_args!(
                 "Failed to set all IO mode to {}. Do check if the channel is opened by calling 'adc_io_open()' \
                  and the libuptech.so being loaded properly",
                 mode
-            );
-            return -1;
-        }
-
-        0
+            ),
+        );
+        return -1;
     }
+
+    0
 }
 
 /// Sets the mode of a specific IO index.
@@ -355,21 +476,141 @@ pub fn set_all_io_mode(mode: u8) -> i32 {
 /// This function uses unsafe code to interact with a C library. Ensure that the shared library ([libuptech.so](file://L:\RustProjects\uptechstar-rs\lib\libuptech.so))
 /// is properly loaded and the `adc_io_ModeSet` function is available.
 pub fn set_io_mode(index: u32, mode: u8) -> i32 {
-    unsafe {
-        let adc_io_mode_set: Symbol<unsafe extern "C" fn(u32, i32) -> i32> = LIBRARY
-            .get(b"adc_io_ModeSet")
-            .expect("Failed to load adc_io_ModeSet function");
-
-        let result = adc_io_mode_set(index, mode as i32);
-
-        if result != 0 {
-            error!(
+    let result = backend::current().io_mode_set(index, mode as i32);
+    health::record(Subsystem::AdcIo, result);
+
+    if result != 0 {
+        log_limit::ffi_error(
+            "adc_io_ModeSet",
+            result,
+            format_args!(
                 "Failed to set IO mode, index: {}, mode: {}. Do check if the channel is opened by calling 'adc_io_open()' \
                  and the libuptech.so being loaded properly",
                 index, mode
-            );
-        }
+            ),
+        );
+    }
+
+    result
+}
+
+/// A snapshot of all 10 ADC channels.
+///
+/// This is the value type produced by [`adc_get_frame`] and by the ADC stream of the
+/// [`Sampler`](crate::sampler::Sampler).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdcFrame(pub [i32; 10]);
+
+impl AdcFrame {
+    /// Number of ADC channels in a frame.
+    pub const CHANNELS: usize = 10;
+}
+
+/// A snapshot of the input levels of all 8 IO pins, bit `i` being pin `i`.
+///
+/// This is the value type produced by [`io_get_frame`] and by the IO readings of the
+/// [`Sampler`](crate::sampler::Sampler).
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::IoFrame;
+///
+/// let frame = IoFrame(0b0000_0101);
+/// assert!(frame.level(0));
+/// assert!(!frame.level(1));
+/// assert_eq!(frame.high_pins().collect::<Vec<_>>(), [0, 2]);
+/// assert_eq!(u8::from(frame), 5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IoFrame(pub u8);
+
+impl IoFrame {
+    /// Number of IO pins in a frame.
+    pub const PINS: u32 = 8;
+
+    /// Returns `true` if pin `index` is high.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is not in `0..8`.
+    pub fn level(self, index: u32) -> bool {
+        assert!(index < Self::PINS, "IO pin index must be in 0..8, got {}", index);
+        self.0 >> index & 1 == 1
+    }
+
+    /// Returns the indices of the high pins in ascending order.
+    pub fn high_pins(self) -> impl Iterator<Item = u32> {
+        (0..Self::PINS).filter(move |&index| self.level(index))
+    }
+}
 
-        result
+impl From<u8> for IoFrame {
+    fn from(levels: u8) -> Self {
+        IoFrame(levels)
     }
 }
+
+impl From<IoFrame> for u8 {
+    fn from(frame: IoFrame) -> Self {
+        frame.0
+    }
+}
+
+/// Retrieves all ADC channels' data as an [`AdcFrame`].
+///
+/// This is a convenience wrapper around [`adc_get_all_channels`] for callers that prefer
+/// returning values over filling a buffer.
+///
+/// # Returns
+///
+/// * `Result<AdcFrame, &'static str>` - The sampled frame on success, or an error message on failure.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::adc_get_frame;
+///
+/// if let Ok(frame) = adc_get_frame() {
+///     println!("ADC0 = {}", frame.0[0]);
+/// }
+/// ```
+pub fn adc_get_frame() -> Result<AdcFrame, &'static str> {
+    let mut frame = AdcFrame::default();
+    adc_get_all_channels(&mut frame.0)?;
+    Ok(frame)
+}
+
+/// Streams [`AdcFrame`]s at a fixed rate.
+///
+/// The channels are polled on a dedicated blocking thread, so awaiting the stream never blocks
+/// the async runtime. The thread stops once the stream is dropped. Failed reads are skipped,
+/// and frames are dropped rather than buffered when the consumer lags behind.
+///
+/// # Arguments
+///
+/// * `rate_hz` - Sampling frequency in Hz.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use tokio_stream::StreamExt;
+/// use uptechstar_rs::adc_io;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     adc_io::adc_open();
+///     let mut frames = adc_io::adc_stream(50.0);
+///     while let Some(frame) = frames.next().await {
+///         println!("{:?}: {:?}", frame.timestamp, frame.value.0);
+///     }
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub fn adc_stream(
+    rate_hz: f32,
+) -> impl tokio_stream::Stream<Item = crate::sampler::Timestamped<AdcFrame>> + Send + Unpin {
+    crate::sampler::spawn_stream("uptech-adc-stream", rate_hz, 64, || adc_get_frame().ok())
+}
@@ -0,0 +1,140 @@
+use super::{set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::warn;
+use std::ops::Range;
+
+/// A group of adjacent output pins driven as one N-bit bus.
+///
+/// Bit 0 of a value goes to the lowest pin of the group. [`write`](ParallelBus::write) changes
+/// all data pins in a single masked IO update, so the bus never shows a mix of the old and new
+/// value, and leaves the pins outside the bus untouched.
+///
+/// With a strobe pin, every write is followed by a pulse on it, as needed to latch the value
+/// into a character LCD (the HD44780 `E` pin) or a parallel DAC. Each level change is a round
+/// trip over the IO link, which takes far longer than the setup and pulse times these devices
+/// require.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, ParallelBus};
+///
+/// adc_io::adc_open();
+///
+/// // An R-2R DAC on IO0-IO3, latched by a high pulse on IO6.
+/// let mut dac = ParallelBus::new(0, 4).with_strobe(6, true);
+/// for level in 0..16 {
+///     dac.write(level);
+/// }
+/// ```
+pub struct ParallelBus {
+    pins: Range<u32>,
+    strobe: Option<(u32, bool)>,
+    value: u32,
+}
+
+impl ParallelBus {
+    /// Creates a bus of `width` pins starting at IO `first_pin` and switches them to output
+    /// mode. The pins keep their current levels until the first write.
+    ///
+    /// # Panics
+    ///
+    /// If `width` is zero or the pins do not fit in `0..8`.
+    pub fn new(first_pin: u32, width: u32) -> Self {
+        assert!(width > 0, "A parallel bus needs at least one pin");
+        assert!(
+            first_pin.checked_add(width).is_some_and(|end| end <= 8),
+            "Parallel bus IO{}..IO{} out of range 0..8",
+            first_pin,
+            first_pin.saturating_add(width)
+        );
+
+        let pins = first_pin..first_pin + width;
+        for pin in pins.clone() {
+            if set_io_mode(pin, 1) != 0 {
+                warn!("Failed to switch IO{} to output mode for the parallel bus", pin);
+            }
+        }
+
+        ParallelBus { pins, strobe: None, value: 0 }
+    }
+
+    /// Adds a strobe pin pulsed after every write: high then low if `active_high`, low then
+    /// high otherwise. The pin is switched to output mode and set to its idle level.
+    ///
+    /// # Panics
+    ///
+    /// If `pin` is not in `0..8` or belongs to the bus.
+    pub fn with_strobe(mut self, pin: u32, active_high: bool) -> Self {
+        assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+        assert!(!self.pins.contains(&pin), "Strobe IO{} is part of the parallel bus", pin);
+
+        if set_io_mode(pin, 1) != 0 {
+            warn!("Failed to switch IO{} to output mode for the bus strobe", pin);
+        }
+        let mask = 1 << pin;
+        set_io_levels_with_mask(mask, if active_high { 0 } else { mask });
+
+        self.strobe = Some((pin, active_high));
+        self
+    }
+
+    /// Returns the IO pins carrying data, lowest bit first.
+    pub fn pins(&self) -> Range<u32> {
+        self.pins.clone()
+    }
+
+    /// Returns the number of data bits.
+    pub fn width(&self) -> u32 {
+        self.pins.len() as u32
+    }
+
+    /// Returns the strobe pin, if any.
+    pub fn strobe_pin(&self) -> Option<u32> {
+        self.strobe.map(|(pin, _)| pin)
+    }
+
+    /// Returns the IO mask of the data pins.
+    pub fn mask(&self) -> u8 {
+        (((1u32 << self.width()) - 1) << self.pins.start) as u8
+    }
+
+    /// Returns the last value written.
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    /// Puts `value` on the bus and pulses the strobe pin, if any. Bits beyond the bus width are
+    /// ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `i32` - Returns `0` on success, non-zero on failure.
+    pub fn write(&mut self, value: u32) -> i32 {
+        let value = value & ((1 << self.width()) - 1);
+        let code = set_io_levels_with_mask(self.mask(), (value << self.pins.start) as u8);
+        if code != 0 {
+            return code;
+        }
+        self.value = value;
+        self.strobe()
+    }
+
+    /// Pulses the strobe pin without changing the data pins. Does nothing without a strobe pin.
+    ///
+    /// # Returns
+    ///
+    /// * `i32` - Returns `0` on success, non-zero on failure.
+    pub fn strobe(&mut self) -> i32 {
+        let Some((pin, active_high)) = self.strobe else {
+            return 0;
+        };
+
+        let mask = 1 << pin;
+        let (active, idle) = if active_high { (mask, 0) } else { (0, mask) };
+        let code = set_io_levels_with_mask(mask, active);
+        if code != 0 {
+            return code;
+        }
+        set_io_levels_with_mask(mask, idle)
+    }
+}
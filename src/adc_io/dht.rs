@@ -0,0 +1,207 @@
+use super::{io_get_all_channels, set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::{debug, warn};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to record the pin after the start signal. A full transmission takes about 5ms.
+const CAPTURE_TIME: Duration = Duration::from_millis(8);
+
+/// The sensor variant, which determines the start signal, data format and minimum interval
+/// between readings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DhtModel {
+    /// DHT11: 0–50°C, 20–90% RH, one reading per second.
+    Dht11,
+    /// DHT22 / AM2302: -40–80°C, 0–100% RH, one reading every two seconds.
+    Dht22,
+}
+
+impl DhtModel {
+    /// The shortest time the sensor needs between two readings.
+    pub fn min_interval(self) -> Duration {
+        match self {
+            DhtModel::Dht11 => Duration::from_secs(1),
+            DhtModel::Dht22 => Duration::from_secs(2),
+        }
+    }
+
+    fn start_signal(self) -> Duration {
+        match self {
+            DhtModel::Dht11 => Duration::from_millis(20),
+            DhtModel::Dht22 => Duration::from_millis(2),
+        }
+    }
+
+    /// Validates the checksum of a raw 5-byte transmission and converts it into a reading.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::adc_io::DhtModel;
+    ///
+    /// // 65.2% RH, -10.1°C
+    /// let reading = DhtModel::Dht22.parse([0x02, 0x8C, 0x80, 0x65, 0x73]).unwrap();
+    /// assert_eq!(reading.humidity, 65.2);
+    /// assert_eq!(reading.temperature, -10.1);
+    ///
+    /// assert!(DhtModel::Dht22.parse([0x02, 0x8C, 0x80, 0x65, 0x00]).is_err());
+    /// ```
+    pub fn parse(self, data: [u8; 5]) -> Result<DhtReading, &'static str> {
+        let sum = data[..4].iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        if sum != data[4] {
+            return Err("DHT checksum mismatch");
+        }
+
+        let (humidity, temperature) = match self {
+            DhtModel::Dht11 => (
+                data[0] as f32 + data[1] as f32 / 10.0,
+                (data[2] & 0x7F) as f32 + data[3] as f32 / 10.0,
+            ),
+            DhtModel::Dht22 => (
+                u16::from_be_bytes([data[0], data[1]]) as f32 / 10.0,
+                u16::from_be_bytes([data[2] & 0x7F, data[3]]) as f32 / 10.0,
+            ),
+        };
+        let temperature = if data[2] & 0x80 != 0 { -temperature } else { temperature };
+
+        Ok(DhtReading { temperature, humidity })
+    }
+}
+
+/// A temperature and relative humidity reading.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DhtReading {
+    /// Temperature in °C.
+    pub temperature: f32,
+    /// Relative humidity in %.
+    pub humidity: f32,
+}
+
+/// A DHT11 or DHT22 temperature and humidity sensor on an IO pin.
+///
+/// The single-wire protocol encodes bits in pulse widths of 26–70µs. The pin is sampled as
+/// fast as the IO link allows and every high pulse is compared with the 50µs low pulse
+/// before it, so the decoding tolerates a uniformly slow link. It still needs the link to
+/// resolve pulses of a few tens of microseconds; when it cannot, [`read`](Dht::read) fails
+/// instead of returning wrong values, since every transmission is checksummed.
+///
+/// The data pin needs a pull-up, which most sensor modules include.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, Dht, DhtModel};
+///
+/// adc_io::adc_open();
+///
+/// let mut dht = Dht::new(2, DhtModel::Dht22);
+/// match dht.read() {
+///     Ok(reading) => println!("{:.1}°C, {:.1}%", reading.temperature, reading.humidity),
+///     Err(e) => eprintln!("{}", e),
+/// }
+/// ```
+pub struct Dht {
+    pin: u32,
+    model: DhtModel,
+    last_read: Option<Instant>,
+}
+
+impl Dht {
+    /// Creates a driver for a sensor of `model` on IO `pin`.
+    ///
+    /// # Panics
+    ///
+    /// If `pin` is not in `0..8`.
+    pub fn new(pin: u32, model: DhtModel) -> Self {
+        assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+        Dht {
+            pin,
+            model,
+            last_read: None,
+        }
+    }
+
+    /// Returns the sensor model.
+    pub fn model(&self) -> DhtModel {
+        self.model
+    }
+
+    /// Triggers a measurement and decodes the sensor's answer.
+    ///
+    /// Blocks until the model's [minimum interval](DhtModel::min_interval) since the previous
+    /// reading has passed, plus the transmission time of a few milliseconds.
+    pub fn read(&mut self) -> Result<DhtReading, &'static str> {
+        if let Some(last_read) = self.last_read {
+            let ready = last_read + self.model.min_interval();
+            let now = Instant::now();
+            if ready > now {
+                thread::sleep(ready - now);
+            }
+        }
+        self.last_read = Some(Instant::now());
+
+        let pulses = self.capture()?;
+        let data = decode(&pulses)?;
+        let reading = self.model.parse(data);
+        if let Err(e) = reading {
+            warn!("{} on IO{}, raw data: {:02x?}", e, self.pin, data);
+        }
+        reading
+    }
+
+    /// Sends the start signal and records the durations of the pulses that follow.
+    fn capture(&self) -> Result<Vec<(bool, Duration)>, &'static str> {
+        let mask = 1u8 << self.pin;
+
+        if set_io_mode(self.pin, 1) != 0 || set_io_levels_with_mask(mask, 0) != 0 {
+            return Err("Failed to drive the DHT data pin");
+        }
+        thread::sleep(self.model.start_signal());
+        set_io_levels_with_mask(mask, mask);
+        if set_io_mode(self.pin, 0) != 0 {
+            return Err("Failed to release the DHT data pin");
+        }
+
+        let started = Instant::now();
+        let mut pulses = Vec::with_capacity(96);
+        let mut level = true;
+        let mut since = started;
+
+        while started.elapsed() < CAPTURE_TIME {
+            let high = io_get_all_channels() & mask != 0;
+            if high != level {
+                let now = Instant::now();
+                pulses.push((level, now - since));
+                level = high;
+                since = now;
+            }
+        }
+
+        debug!("Captured {} DHT pulses on IO{}", pulses.len(), self.pin);
+        Ok(pulses)
+    }
+}
+
+/// Decodes the last 40 high pulses, each compared with its preceding low pulse.
+fn decode(pulses: &[(bool, Duration)]) -> Result<[u8; 5], &'static str> {
+    let bits: Vec<bool> = pulses
+        .windows(2)
+        .filter(|pair| !pair[0].0 && pair[1].0)
+        .map(|pair| pair[1].1 > pair[0].1)
+        .collect();
+
+    // The sensor's 80µs response pulse comes before the data bits.
+    if bits.len() < 41 {
+        return Err("DHT sensor did not respond with a complete transmission");
+    }
+
+    let mut data = [0u8; 5];
+    for (index, &bit) in bits[bits.len() - 40..].iter().enumerate() {
+        if bit {
+            data[index / 8] |= 0x80 >> (index % 8);
+        }
+    }
+    Ok(data)
+}
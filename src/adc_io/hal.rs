@@ -0,0 +1,189 @@
+//! `embedded-hal` implementations for the pins and ADC channels of this module.
+//!
+//! Digital pins and the software SPI bus implement the `embedded-hal` 1.0 traits.
+//! `embedded-hal` 1.0 has no ADC trait, so ADC channels implement the `OneShot` trait of
+//! `embedded-hal` 0.2, which drivers for analog sensors still use.
+
+use super::{IoPin, Pin, ShiftInPin, ShiftOutPin, SoftSpi, adc_get_frame};
+use embedded_hal::digital::{self, ErrorKind, ErrorType, InputPin, OutputPin, StatefulOutputPin};
+use embedded_hal::spi::{self, Operation, SpiDevice};
+use embedded_hal_02::adc::{Channel, OneShot};
+use std::fmt;
+
+/// Error of the `embedded-hal` implementations, carrying the failed status code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HalError(pub i32);
+
+impl fmt::Display for HalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "hardware call failed with status {}", self.0)
+    }
+}
+
+impl std::error::Error for HalError {}
+
+impl digital::Error for HalError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl spi::Error for HalError {
+    fn kind(&self) -> spi::ErrorKind {
+        spi::ErrorKind::Other
+    }
+}
+
+fn check(code: i32) -> Result<(), HalError> {
+    match code {
+        0 => Ok(()),
+        code => Err(HalError(code)),
+    }
+}
+
+impl ErrorType for IoPin {
+    type Error = HalError;
+}
+
+impl InputPin for IoPin {
+    fn is_high(&mut self) -> Result<bool, HalError> {
+        Ok(Pin::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, HalError> {
+        Ok(Pin::is_low(self))
+    }
+}
+
+impl OutputPin for IoPin {
+    fn set_low(&mut self) -> Result<(), HalError> {
+        check(self.set_level(false))
+    }
+
+    fn set_high(&mut self) -> Result<(), HalError> {
+        check(self.set_level(true))
+    }
+}
+
+impl StatefulOutputPin for IoPin {
+    fn is_set_high(&mut self) -> Result<bool, HalError> {
+        Ok(Pin::is_high(self))
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, HalError> {
+        Ok(Pin::is_low(self))
+    }
+}
+
+impl ErrorType for ShiftOutPin {
+    type Error = HalError;
+}
+
+impl OutputPin for ShiftOutPin {
+    fn set_low(&mut self) -> Result<(), HalError> {
+        check(self.set_level(false))
+    }
+
+    fn set_high(&mut self) -> Result<(), HalError> {
+        check(self.set_level(true))
+    }
+}
+
+impl StatefulOutputPin for ShiftOutPin {
+    fn is_set_high(&mut self) -> Result<bool, HalError> {
+        Ok(Pin::is_high(self))
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, HalError> {
+        Ok(Pin::is_low(self))
+    }
+}
+
+impl ErrorType for ShiftInPin {
+    type Error = HalError;
+}
+
+impl InputPin for ShiftInPin {
+    fn is_high(&mut self) -> Result<bool, HalError> {
+        Ok(Pin::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, HalError> {
+        Ok(Pin::is_low(self))
+    }
+}
+
+impl spi::ErrorType for SoftSpi {
+    type Error = HalError;
+}
+
+impl SpiDevice for SoftSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), HalError> {
+        self.selected(|spi| {
+            for operation in operations {
+                match operation {
+                    Operation::Read(read) => spi.exchange(read, &[])?,
+                    Operation::Write(write) => spi.exchange(&mut [], write)?,
+                    Operation::Transfer(read, write) => spi.exchange(read, write)?,
+                    Operation::TransferInPlace(data) => spi.exchange_in_place(data)?,
+                    Operation::DelayNs(ns) => std::thread::sleep(std::time::Duration::from_nanos(*ns as u64)),
+                }
+            }
+            Ok(())
+        })
+        .map_err(|_| HalError(-1))
+    }
+}
+
+/// The board's ADC as an `embedded-hal` 0.2 `OneShot` converter.
+///
+/// Every read samples all channels and returns the requested one.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use embedded_hal_02::adc::OneShot;
+/// use uptechstar_rs::adc_io::{self, Adc, AdcPin};
+///
+/// adc_io::adc_open();
+///
+/// let mut adc = Adc::new();
+/// let mut battery = AdcPin::<0>;
+/// let raw: u16 = nb::block!(adc.read(&mut battery)).unwrap();
+/// println!("battery: {}", raw);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Adc {
+    _private: (),
+}
+
+impl Adc {
+    /// Creates a handle to the ADC. The ADC-IO peripheral must be opened with
+    /// [`adc_open`](super::adc_open) first.
+    pub fn new() -> Self {
+        Adc::default()
+    }
+}
+
+/// ADC channel `CHANNEL`, `0..10`, as an `embedded-hal` 0.2 ADC channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct AdcPin<const CHANNEL: u8>;
+
+impl<const CHANNEL: u8> Channel<Adc> for AdcPin<CHANNEL> {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        CHANNEL
+    }
+}
+
+impl<const CHANNEL: u8> OneShot<Adc, u16, AdcPin<CHANNEL>> for Adc {
+    type Error = HalError;
+
+    fn read(&mut self, _pin: &mut AdcPin<CHANNEL>) -> nb::Result<u16, HalError> {
+        const { assert!(CHANNEL < 10, "ADC channel must be in 0..10") };
+
+        let frame = adc_get_frame().map_err(|_| nb::Error::Other(HalError(-1)))?;
+        Ok(frame.0[CHANNEL as usize].clamp(0, u16::MAX as i32) as u16)
+    }
+}
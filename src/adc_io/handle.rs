@@ -0,0 +1,94 @@
+use super::{adc_close, adc_open};
+use crate::error::{Result, UptechError};
+use crate::log_level::adc::debug;
+use std::sync::Mutex;
+
+/// Handles alive and the open count the library reported when the first of them opened ADC-IO.
+struct Handles {
+    alive: usize,
+    open_count: i32,
+}
+
+static HANDLES: Mutex<Handles> = Mutex::new(Handles { alive: 0, open_count: 0 });
+
+/// A shared claim on the ADC-IO peripheral.
+///
+/// The first handle opens ADC-IO, clones share it, and only dropping the last handle closes
+/// it again. Two parts of a program, say a [`Board`](crate::board::Board) and a logger, can each
+/// hold a handle without closing the peripheral under the other.
+///
+/// Calling [`adc_close`] directly, or [`release_hardware`](crate::shutdown::release_hardware),
+/// still closes ADC-IO regardless of open handles.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, AdcIo};
+///
+/// let adc = AdcIo::open().unwrap();
+/// let logger_adc = adc.clone();
+/// assert_eq!(AdcIo::handles(), 2);
+///
+/// drop(adc);
+/// // Still open for the logger.
+/// println!("{:?}", adc_io::adc_get_frame());
+/// drop(logger_adc);
+/// ```
+#[derive(Debug)]
+pub struct AdcIo {
+    _private: (),
+}
+
+impl AdcIo {
+    /// Returns a handle, opening ADC-IO if no other handle exists.
+    ///
+    /// # Errors
+    ///
+    /// [`UptechError::Hardware`] if `adc_io_open` fails.
+    pub fn open() -> Result<Self> {
+        let mut handles = HANDLES.lock().unwrap_or_else(|e| e.into_inner());
+        if handles.alive == 0 {
+            let open_count = adc_open();
+            if open_count < 0 {
+                return Err(UptechError::Hardware {
+                    operation: "adc_io_open",
+                    code: open_count,
+                });
+            }
+            handles.open_count = open_count;
+        }
+
+        handles.alive += 1;
+        debug!("ADC-IO handle opened, {} alive", handles.alive);
+        Ok(AdcIo { _private: () })
+    }
+
+    /// Returns the number of handles alive.
+    pub fn handles() -> usize {
+        HANDLES.lock().unwrap_or_else(|e| e.into_inner()).alive
+    }
+
+    /// Returns the open count the library reported when ADC-IO was opened for the handles.
+    pub fn open_count(&self) -> i32 {
+        HANDLES.lock().unwrap_or_else(|e| e.into_inner()).open_count
+    }
+}
+
+impl Clone for AdcIo {
+    fn clone(&self) -> Self {
+        HANDLES.lock().unwrap_or_else(|e| e.into_inner()).alive += 1;
+        AdcIo { _private: () }
+    }
+}
+
+impl Drop for AdcIo {
+    fn drop(&mut self) {
+        let mut handles = HANDLES.lock().unwrap_or_else(|e| e.into_inner());
+        handles.alive -= 1;
+        if handles.alive == 0 {
+            adc_close();
+        } else {
+            debug!("ADC-IO handle dropped, {} still alive", handles.alive);
+        }
+    }
+}
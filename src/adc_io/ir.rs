@@ -0,0 +1,359 @@
+use super::{io_get_all_channels, set_io_mode};
+use crate::sampler::{Ticker, period_from_rate};
+use crate::log_level::adc::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A key press decoded from an NEC remote-control frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IrEvent {
+    /// The remote's address. 8-bit for standard NEC, 16-bit for extended NEC remotes.
+    pub address: u16,
+    /// The key code.
+    pub command: u8,
+    /// `true` for the repeat codes a remote sends while a key is held down.
+    pub repeat: bool,
+}
+
+/// Nominal NEC timings in microseconds.
+const LEADER_BURST: u32 = 9000;
+const LEADER_SPACE: u32 = 4500;
+const REPEAT_SPACE: u32 = 2250;
+const BIT_BURST: u32 = 562;
+const ZERO_SPACE: u32 = 562;
+const ONE_SPACE: u32 = 1687;
+
+/// A gap after which a repeat code no longer refers to the previous frame.
+const REPEAT_TIMEOUT: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Idle,
+    Leader,
+    LeaderSpace,
+    BitBurst { bits: u32, count: u32 },
+    BitSpace { bits: u32, count: u32 },
+}
+
+/// A decoder turning the pulse train of an NEC remote into [`IrEvent`]s.
+///
+/// Feed it the duration of every burst (carrier present, usually the pin pulled low by the
+/// receiver module) and every space in between. Timings are accepted within ±40% of nominal,
+/// which leaves room for the jitter of polling the pin over the IO link.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::adc_io::NecDecoder;
+///
+/// let us = Duration::from_micros;
+/// let mut decoder = NecDecoder::new();
+///
+/// decoder.push(true, us(9000));
+/// decoder.push(false, us(4500));
+///
+/// // Address 0x04, command 0x08, each followed by its inverse, LSB first.
+/// let mut event = None;
+/// for byte in [0x04u8, !0x04, 0x08, !0x08] {
+///     for bit in 0..8 {
+///         decoder.push(true, us(560));
+///         let space = if byte >> bit & 1 == 1 { 1690 } else { 560 };
+///         event = decoder.push(false, us(space));
+///     }
+/// }
+/// assert!(event.is_none());
+///
+/// // The trailing burst completes the frame.
+/// let event = decoder.push(true, us(560)).unwrap();
+/// assert_eq!((event.address, event.command, event.repeat), (0x04, 0x08, false));
+/// ```
+#[derive(Debug, Clone)]
+pub struct NecDecoder {
+    state: State,
+    last: Option<IrEvent>,
+}
+
+impl Default for NecDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NecDecoder {
+    /// Creates a decoder waiting for a leader burst.
+    pub fn new() -> Self {
+        NecDecoder {
+            state: State::Idle,
+            last: None,
+        }
+    }
+
+    /// Feeds one pulse, returning an event if it completed a frame or a repeat code.
+    ///
+    /// `burst` is `true` for carrier bursts and `false` for spaces.
+    pub fn push(&mut self, burst: bool, duration: Duration) -> Option<IrEvent> {
+        let micros = duration.as_micros().min(u32::MAX as u128) as u32;
+
+        if !burst && duration > REPEAT_TIMEOUT {
+            self.last = None;
+        }
+
+        let (state, event) = match (self.state, burst) {
+            (State::Idle, true) if matches(micros, LEADER_BURST) => (State::Leader, None),
+            (State::Leader, false) if matches(micros, LEADER_SPACE) => (State::LeaderSpace, None),
+            (State::Leader, false) if matches(micros, REPEAT_SPACE) => {
+                let event = self.last.map(|last| IrEvent { repeat: true, ..last });
+                (State::Idle, event)
+            }
+            (State::LeaderSpace, true) | (State::BitSpace { .. }, true) if matches(micros, BIT_BURST) => {
+                let (bits, count) = match self.state {
+                    State::BitSpace { bits, count } => (bits, count),
+                    _ => (0, 0),
+                };
+                if count == 32 {
+                    let event = frame(bits);
+                    if event.is_some() {
+                        self.last = event;
+                    }
+                    (State::Idle, event)
+                } else {
+                    (State::BitBurst { bits, count }, None)
+                }
+            }
+            (State::BitBurst { bits, count }, false) if matches(micros, ZERO_SPACE) => {
+                (State::BitSpace { bits, count: count + 1 }, None)
+            }
+            (State::BitBurst { bits, count }, false) if matches(micros, ONE_SPACE) => (
+                State::BitSpace {
+                    bits: bits | 1 << count,
+                    count: count + 1,
+                },
+                None,
+            ),
+            // Anything unexpected restarts the search; a burst may already be the next leader.
+            (_, true) if matches(micros, LEADER_BURST) => (State::Leader, None),
+            _ => (State::Idle, None),
+        };
+
+        self.state = state;
+        event
+    }
+
+    /// Discards a partially received frame and the frame that repeat codes refer to.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+fn matches(micros: u32, nominal: u32) -> bool {
+    micros >= nominal * 6 / 10 && micros <= nominal * 14 / 10
+}
+
+/// Validates the inverted copies in a 32-bit frame and extracts address and command.
+fn frame(bits: u32) -> Option<IrEvent> {
+    let [address, address_inv, command, command_inv] = bits.to_le_bytes();
+
+    if command ^ command_inv != 0xFF {
+        debug!("Dropping NEC frame {:#010x} with corrupt command", bits);
+        return None;
+    }
+
+    let address = if address ^ address_inv == 0xFF {
+        address as u16
+    } else {
+        u16::from_le_bytes([address, address_inv])
+    };
+
+    Some(IrEvent {
+        address,
+        command,
+        repeat: false,
+    })
+}
+
+/// An NEC infrared remote receiver on an IO input pin.
+///
+/// A background thread polls the pin, measures the pulses coming from a demodulating receiver
+/// module such as the VS1838B or TSOP38238, and publishes every decoded key press and repeat
+/// code to its subscribers.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, IrReceiver};
+///
+/// adc_io::adc_open();
+///
+/// let mut receiver = IrReceiver::new(5);
+/// let keys = receiver.subscribe();
+/// receiver.start();
+///
+/// for key in keys {
+///     if !key.repeat {
+///         println!("address {:#06x}, command {:#04x}", key.address, key.command);
+///     }
+/// }
+/// ```
+pub struct IrReceiver {
+    pin: u32,
+    period: Duration,
+    active_low: bool,
+    subscribers: Arc<Mutex<Vec<Sender<IrEvent>>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl IrReceiver {
+    /// Creates a stopped receiver on IO `pin`, polling at 10 kHz and expecting an active-low
+    /// receiver module.
+    ///
+    /// # Panics
+    ///
+    /// If `pin` is not in `0..8`.
+    pub fn new(pin: u32) -> Self {
+        assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+
+        IrReceiver {
+            pin,
+            period: Duration::from_micros(100),
+            active_low: true,
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Sets the pin polling rate. Pulses of the NEC protocol are 562µs long, so rates below
+    /// roughly 5 kHz cannot resolve them.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn with_sample_rate(mut self, rate_hz: f32) -> Self {
+        self.period = period_from_rate(rate_hz);
+        self
+    }
+
+    /// Sets whether the receiver module pulls the pin low during carrier bursts (the default).
+    pub fn with_active_low(mut self, active_low: bool) -> Self {
+        self.active_low = active_low;
+        self
+    }
+
+    /// Returns the IO pin being read.
+    pub fn pin(&self) -> u32 {
+        self.pin
+    }
+
+    /// Registers a new subscriber and returns its receiving end.
+    ///
+    /// Subscribing works both before and after [`start`](IrReceiver::start).
+    pub fn subscribe(&self) -> Receiver<IrEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    /// Returns `true` while the receiving thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Switches the pin to input mode and starts the receiving thread. Does nothing if it is
+    /// already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!(
+            "Starting IR receiver on IO{} at {:.1} kHz",
+            self.pin,
+            1.0 / self.period.as_secs_f32() / 1000.0
+        );
+
+        if set_io_mode(self.pin, 0) != 0 {
+            warn!("Failed to switch IO{} to input mode for the IR receiver", self.pin);
+        }
+
+        self.running.store(true, Ordering::Release);
+
+        let (pin, period, active_low) = (self.pin, self.period, self.active_low);
+        let subscribers = Arc::clone(&self.subscribers);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-ir".into())
+                .spawn(move || {
+                    let mut decoder = NecDecoder::new();
+                    let mut ticker = Ticker::new(period);
+                    let mut burst = false;
+                    let mut since = Instant::now();
+
+                    while running.load(Ordering::Acquire) {
+                        let high = (io_get_all_channels() >> pin) & 1 == 1;
+                        let now = Instant::now();
+
+                        if (high != active_low) != burst {
+                            if let Some(event) = decoder.push(burst, now - since) {
+                                debug!("IR event {:?}", event);
+                                subscribers
+                                    .lock()
+                                    .unwrap_or_else(|e| e.into_inner())
+                                    .retain(|subscriber| subscriber.send(event).is_ok());
+                            }
+                            burst = !burst;
+                            since = now;
+                        }
+
+                        ticker.wait();
+                    }
+
+                    debug!("IR receiver thread exited");
+                })
+                .expect("Failed to spawn IR receiver thread"),
+        );
+
+        self
+    }
+
+    /// Stops the receiving thread and waits for it to exit.
+    ///
+    /// Stopping closes all current subscriptions. Subscribe again before restarting.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("IR receiver on IO{} stopped", self.pin);
+        }
+
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+
+        self
+    }
+}
+
+impl Drop for IrReceiver {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,257 @@
+use super::{io_get_all_channels, set_io_levels_with_mask, set_io_mode};
+use crate::sampler::{Ticker, period_from_rate};
+use crate::log_level::adc::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A debounced key change reported by a [`Keypad`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyEvent {
+    /// Row index of the key, counted in the order the row pins were given.
+    pub row: usize,
+    /// Column index of the key, counted in the order the column pins were given.
+    pub col: usize,
+    /// The label from the keymap, if one was set.
+    pub key: Option<char>,
+    /// `true` when the key went down, `false` when it was released.
+    pub pressed: bool,
+}
+
+/// A row/column key matrix, such as a 4x4 membrane keypad, on the IO pins.
+///
+/// Row pins are outputs and column pins inputs with pull-ups. Each scan pulls one row low at a
+/// time and reads which columns follow it. A key has to read the same for the whole debounce
+/// time before a [`KeyEvent`] is published.
+///
+/// Without diodes in the matrix, three keys pressed at the corners of a rectangle make the
+/// fourth corner appear pressed too.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, Keypad};
+///
+/// adc_io::adc_open();
+///
+/// let mut keypad = Keypad::new(&[0, 1, 2, 3], &[4, 5, 6, 7]).with_keymap("123A456B789C*0#D");
+/// let events = keypad.subscribe();
+/// keypad.start();
+///
+/// for event in events.iter().filter(|event| event.pressed) {
+///     println!("{:?}", event.key);
+/// }
+/// ```
+pub struct Keypad {
+    rows: Vec<u32>,
+    cols: Vec<u32>,
+    keymap: Option<Vec<char>>,
+    period: Duration,
+    debounce: Duration,
+    subscribers: Arc<Mutex<Vec<Sender<KeyEvent>>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Keypad {
+    /// Creates a stopped keypad scanning at 100 Hz with a 20ms debounce time.
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8`, a pin is used twice, or `rows` or `cols` is empty.
+    pub fn new(rows: &[u32], cols: &[u32]) -> Self {
+        assert!(!rows.is_empty() && !cols.is_empty(), "Keypad needs at least one row and column");
+
+        let mut used = 0u8;
+        for &pin in rows.iter().chain(cols) {
+            assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+            assert!(used & 1 << pin == 0, "IO{} is used twice in the keypad", pin);
+            used |= 1 << pin;
+        }
+
+        Keypad {
+            rows: rows.to_vec(),
+            cols: cols.to_vec(),
+            keymap: None,
+            period: Duration::from_millis(10),
+            debounce: Duration::from_millis(20),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Labels the keys row by row, e.g. `"123A456B789C*0#D"` for a common 4x4 keypad.
+    ///
+    /// # Panics
+    ///
+    /// If `keymap` does not have exactly one character per key.
+    pub fn with_keymap(mut self, keymap: &str) -> Self {
+        let keymap: Vec<char> = keymap.chars().collect();
+        assert_eq!(
+            keymap.len(),
+            self.rows.len() * self.cols.len(),
+            "Keymap must have one character per key"
+        );
+        self.keymap = Some(keymap);
+        self
+    }
+
+    /// Sets how often the matrix is scanned.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn with_scan_rate(mut self, rate_hz: f32) -> Self {
+        self.period = period_from_rate(rate_hz);
+        self
+    }
+
+    /// Sets how long a key has to read the same before a change is reported.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Returns the number of rows and columns.
+    pub fn size(&self) -> (usize, usize) {
+        (self.rows.len(), self.cols.len())
+    }
+
+    /// Registers a new subscriber and returns its receiving end.
+    ///
+    /// Subscribing works both before and after [`start`](Keypad::start).
+    pub fn subscribe(&self) -> Receiver<KeyEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    /// Returns `true` while the scanning thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Configures the pin modes and starts the scanning thread. Does nothing if it is already
+    /// running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!(
+            "Starting {}x{} keypad scan at {:.1} Hz",
+            self.rows.len(),
+            self.cols.len(),
+            1.0 / self.period.as_secs_f32()
+        );
+
+        for (&pin, mode) in self.rows.iter().map(|pin| (pin, 1)).chain(self.cols.iter().map(|pin| (pin, 0))) {
+            if set_io_mode(pin, mode) != 0 {
+                warn!("Failed to set the mode of IO{} for the keypad", pin);
+            }
+        }
+
+        self.running.store(true, Ordering::Release);
+
+        let rows = self.rows.clone();
+        let cols = self.cols.clone();
+        let keymap = self.keymap.clone();
+        let (period, debounce) = (self.period, self.debounce);
+        let subscribers = Arc::clone(&self.subscribers);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-keypad".into())
+                .spawn(move || {
+                    let row_mask = rows.iter().fold(0u8, |mask, pin| mask | 1 << pin);
+                    let keys = rows.len() * cols.len();
+                    let mut stable = 0u32;
+                    let mut candidate = 0u32;
+                    let mut changed_at = vec![Instant::now(); keys];
+                    let mut ticker = Ticker::new(period);
+
+                    while running.load(Ordering::Acquire) {
+                        let mut raw = 0u32;
+                        for (row, &row_pin) in rows.iter().enumerate() {
+                            set_io_levels_with_mask(row_mask, row_mask & !(1 << row_pin));
+                            let levels = io_get_all_channels();
+                            for (col, &col_pin) in cols.iter().enumerate() {
+                                if levels & 1 << col_pin == 0 {
+                                    raw |= 1 << (row * cols.len() + col);
+                                }
+                            }
+                        }
+                        set_io_levels_with_mask(row_mask, row_mask);
+
+                        let now = Instant::now();
+                        for index in 0..keys {
+                            let bit = 1 << index;
+                            if (raw ^ candidate) & bit != 0 {
+                                candidate ^= bit;
+                                changed_at[index] = now;
+                            } else if (candidate ^ stable) & bit != 0 && now - changed_at[index] >= debounce {
+                                stable ^= bit;
+                                let event = KeyEvent {
+                                    row: index / cols.len(),
+                                    col: index % cols.len(),
+                                    key: keymap.as_ref().map(|keymap| keymap[index]),
+                                    pressed: stable & bit != 0,
+                                };
+                                debug!("Key event {:?}", event);
+                                subscribers
+                                    .lock()
+                                    .unwrap_or_else(|e| e.into_inner())
+                                    .retain(|subscriber| subscriber.send(event).is_ok());
+                            }
+                        }
+
+                        ticker.wait();
+                    }
+
+                    debug!("Keypad thread exited");
+                })
+                .expect("Failed to spawn keypad thread"),
+        );
+
+        self
+    }
+
+    /// Stops the scanning thread and waits for it to exit.
+    ///
+    /// Stopping closes all current subscriptions. Subscribe again before restarting.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Keypad scan stopped");
+        }
+
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+
+        self
+    }
+}
+
+impl Drop for Keypad {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,138 @@
+use super::{SoftPwm, set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::warn;
+
+/// A brushed DC motor on an H-bridge such as the L298N or TB6612.
+///
+/// Two IO pins select the direction (IN1/IN2 or AIN1/AIN2) and a [`SoftPwm`] on a third pin
+/// (ENA or PWMA) sets the speed. Direction changes are written to both pins in a single IO
+/// update, so the bridge never sees a half-switched state from this side.
+///
+/// The motor coasts when created and when dropped.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use std::time::Duration;
+/// use uptechstar_rs::adc_io::{self, DcMotor};
+///
+/// adc_io::adc_open();
+///
+/// let mut motor = DcMotor::new(0, 1, 2);
+/// motor.set_speed(0.6);
+/// thread::sleep(Duration::from_secs(2));
+/// motor.set_speed(-0.3);
+/// thread::sleep(Duration::from_secs(2));
+/// motor.brake();
+/// ```
+pub struct DcMotor {
+    pin_a: u32,
+    pin_b: u32,
+    pwm: SoftPwm,
+    speed: f32,
+}
+
+impl DcMotor {
+    /// Creates a coasting motor with direction pins `pin_a`/`pin_b` and speed pin `pwm_pin`,
+    /// using 100 Hz PWM.
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8`, or the same pin is used twice.
+    pub fn new(pin_a: u32, pin_b: u32, pwm_pin: u32) -> Self {
+        Self::with_pwm(pin_a, pin_b, SoftPwm::new(pwm_pin))
+    }
+
+    /// Creates a coasting motor whose speed pin is driven by `pwm`, e.g. one configured with
+    /// a different frequency. `pwm` is started if it is not running yet.
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8`, or the same pin is used twice.
+    pub fn with_pwm(pin_a: u32, pin_b: u32, mut pwm: SoftPwm) -> Self {
+        assert!(pin_a < 8 && pin_b < 8, "IO pin index must be in 0..8, got {} and {}", pin_a, pin_b);
+        assert!(
+            pin_a != pin_b && pin_a != pwm.pin() && pin_b != pwm.pin(),
+            "DcMotor pins must be distinct, got {}, {} and {}",
+            pin_a,
+            pin_b,
+            pwm.pin()
+        );
+
+        for pin in [pin_a, pin_b] {
+            if set_io_mode(pin, 1) != 0 {
+                warn!("Failed to switch IO{} to output mode for the motor", pin);
+            }
+        }
+
+        pwm.set_duty(0.0);
+        pwm.start();
+
+        let mut motor = DcMotor {
+            pin_a,
+            pin_b,
+            pwm,
+            speed: 0.0,
+        };
+        motor.coast();
+        motor
+    }
+
+    /// Returns the last speed set, from `-1.0` (full reverse) to `1.0` (full forward).
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the speed, clamped to `-1.0..=1.0`. Negative values run the motor in reverse,
+    /// `0.0` lets it coast.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_speed(&mut self, speed: f32) -> &mut Self {
+        let speed = if speed.is_nan() { 0.0 } else { speed.clamp(-1.0, 1.0) };
+
+        if speed == 0.0 {
+            return self.coast();
+        }
+
+        self.set_direction(speed > 0.0, speed < 0.0);
+        self.pwm.set_duty(speed.abs());
+        self.speed = speed;
+        self
+    }
+
+    /// Stops the motor actively by shorting its terminals through the bridge.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn brake(&mut self) -> &mut Self {
+        self.set_direction(true, true);
+        self.pwm.set_duty(1.0);
+        self.speed = 0.0;
+        self
+    }
+
+    /// Disconnects the motor so it spins down freely.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn coast(&mut self) -> &mut Self {
+        self.pwm.set_duty(0.0);
+        self.set_direction(false, false);
+        self.speed = 0.0;
+        self
+    }
+
+    fn set_direction(&self, a: bool, b: bool) {
+        let (bit_a, bit_b) = (1u8 << self.pin_a, 1u8 << self.pin_b);
+        let levels = if a { bit_a } else { 0 } | if b { bit_b } else { 0 };
+        set_io_levels_with_mask(bit_a | bit_b, levels);
+    }
+}
+
+impl Drop for DcMotor {
+    fn drop(&mut self) {
+        self.coast();
+        self.pwm.stop();
+    }
+}
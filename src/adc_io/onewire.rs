@@ -0,0 +1,445 @@
+use super::{io_get_all_channels, set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::{debug, info, warn};
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SEARCH_ROM: u8 = 0xF0;
+const MATCH_ROM: u8 = 0x55;
+const SKIP_ROM: u8 = 0xCC;
+
+const CONVERT_T: u8 = 0x44;
+const READ_SCRATCHPAD: u8 = 0xBE;
+
+/// Computes the Dallas/Maxim CRC-8 used by one-wire ROM codes and scratchpads.
+///
+/// The CRC of data followed by its own CRC byte is zero.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::crc8;
+///
+/// let rom = [0x02, 0x1C, 0xB8, 0x01, 0x00, 0x00, 0x00];
+/// assert_eq!(crc8(&rom), 0xA2);
+/// assert_eq!(crc8(&[0x02, 0x1C, 0xB8, 0x01, 0x00, 0x00, 0x00, 0xA2]), 0);
+/// ```
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        let mut byte = byte;
+        for _ in 0..8 {
+            let mix = (crc ^ byte) & 1;
+            crc >>= 1;
+            if mix != 0 {
+                crc ^= 0x8C;
+            }
+            byte >>= 1;
+        }
+    }
+    crc
+}
+
+/// The 64-bit ROM code identifying a one-wire device: family code, 48-bit serial number and
+/// CRC, in the order they are sent.
+///
+/// Displayed like the Linux `w1` driver names devices: the family code, a dash and the serial
+/// number in hex.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::RomCode;
+///
+/// let rom = RomCode([0x28, 0x3F, 0x2B, 0x1C, 0x07, 0x00, 0x00, 0xAF]);
+/// assert_eq!(rom.family(), 0x28);
+/// assert_eq!(rom.to_string(), "28-0000071c2b3f");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RomCode(pub [u8; 8]);
+
+impl RomCode {
+    /// Returns the family code, which identifies the device type.
+    pub fn family(&self) -> u8 {
+        self.0[0]
+    }
+
+    /// Returns `true` if the CRC byte matches the rest of the code.
+    pub fn is_valid(&self) -> bool {
+        crc8(&self.0) == 0
+    }
+}
+
+impl fmt::Display for RomCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}-", self.0[0])?;
+        for byte in self.0[1..7].iter().rev() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// A bit-banged one-wire bus master on an IO pin.
+///
+/// The pin works as an open-drain line: it is driven low in output mode and released by
+/// switching to input mode, letting the pull-up (4.7kΩ to 3.3V) pull it high. Devices must be
+/// powered from their VDD pin; parasite power is not supported.
+///
+/// One-wire timing is tight. Resets and 0 bits hold the line low for 480µs and 60µs, which
+/// sleeps handle well, but 1 bits and reads need the line released within 15µs after pulling
+/// it low, so every mode switch has to cross the IO link in a few microseconds. Short delays
+/// busy-wait to keep the rest as tight as possible. When the link is too slow the transfers
+/// fail their CRC checks instead of returning wrong data.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, OneWire};
+///
+/// adc_io::adc_open();
+///
+/// let mut bus = OneWire::new(3);
+/// for rom in bus.search().unwrap() {
+///     println!("Found {} (family {:#04x})", rom, rom.family());
+/// }
+/// ```
+pub struct OneWire {
+    pin: u32,
+    mask: u8,
+}
+
+impl OneWire {
+    /// Creates a bus master on IO `pin`, leaving the line released.
+    ///
+    /// # Panics
+    ///
+    /// If `pin` is not in `0..8`.
+    pub fn new(pin: u32) -> Self {
+        assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+
+        let mask = 1u8 << pin;
+        // Preload a low output level so that switching to output mode pulls the line low.
+        if set_io_mode(pin, 1) != 0 || set_io_levels_with_mask(mask, 0) != 0 || set_io_mode(pin, 0) != 0 {
+            warn!("Failed to set up IO{} for one-wire", pin);
+        }
+
+        OneWire { pin, mask }
+    }
+
+    /// Returns the IO pin of the bus.
+    pub fn pin(&self) -> u32 {
+        self.pin
+    }
+
+    fn drive_low(&self) -> Result<(), &'static str> {
+        if set_io_mode(self.pin, 1) != 0 {
+            return Err("Failed to drive the one-wire line low");
+        }
+        Ok(())
+    }
+
+    fn release(&self) -> Result<(), &'static str> {
+        if set_io_mode(self.pin, 0) != 0 {
+            return Err("Failed to release the one-wire line");
+        }
+        Ok(())
+    }
+
+    fn sample(&self) -> bool {
+        io_get_all_channels() & self.mask != 0
+    }
+
+    /// Sends a reset pulse.
+    ///
+    /// Returns `true` if at least one device answered with a presence pulse.
+    pub fn reset(&mut self) -> Result<bool, &'static str> {
+        self.drive_low()?;
+        thread::sleep(Duration::from_micros(480));
+        self.release()?;
+
+        // Devices pull the line low 15-60µs after the release, for 60-240µs.
+        let released = Instant::now();
+        let mut present = false;
+        while released.elapsed() < Duration::from_micros(300) {
+            if !self.sample() {
+                present = true;
+            }
+        }
+        thread::sleep(Duration::from_micros(180));
+
+        if !self.sample() {
+            return Err("One-wire line stuck low, check the pull-up");
+        }
+        Ok(present)
+    }
+
+    /// Writes one bit, taking a 60µs time slot.
+    pub fn write_bit(&mut self, bit: bool) -> Result<(), &'static str> {
+        let slot = Instant::now();
+        self.drive_low()?;
+        if bit {
+            self.release()?;
+            spin_until(slot + Duration::from_micros(65));
+        } else {
+            spin_until(slot + Duration::from_micros(60));
+            self.release()?;
+            spin_until(Instant::now() + Duration::from_micros(5));
+        }
+        Ok(())
+    }
+
+    /// Reads one bit, taking a 60µs time slot.
+    pub fn read_bit(&mut self) -> Result<bool, &'static str> {
+        let slot = Instant::now();
+        self.drive_low()?;
+        self.release()?;
+        let bit = self.sample();
+        spin_until(slot + Duration::from_micros(65));
+        Ok(bit)
+    }
+
+    /// Writes a byte, least significant bit first.
+    pub fn write_byte(&mut self, byte: u8) -> Result<(), &'static str> {
+        for bit in 0..8 {
+            self.write_bit(byte >> bit & 1 == 1)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a byte, least significant bit first.
+    pub fn read_byte(&mut self) -> Result<u8, &'static str> {
+        let mut byte = 0;
+        for bit in 0..8 {
+            if self.read_bit()? {
+                byte |= 1 << bit;
+            }
+        }
+        Ok(byte)
+    }
+
+    /// Resets the bus and addresses the device with `rom`; the next command goes to it alone.
+    pub fn select(&mut self, rom: &RomCode) -> Result<(), &'static str> {
+        if !self.reset()? {
+            return Err("No one-wire device present");
+        }
+        self.write_byte(MATCH_ROM)?;
+        for &byte in &rom.0 {
+            self.write_byte(byte)?;
+        }
+        Ok(())
+    }
+
+    /// Resets the bus and addresses all devices at once; the next command goes to every one.
+    pub fn skip(&mut self) -> Result<(), &'static str> {
+        if !self.reset()? {
+            return Err("No one-wire device present");
+        }
+        self.write_byte(SKIP_ROM)
+    }
+
+    /// Enumerates the ROM codes of all devices on the bus with the binary search algorithm
+    /// from Maxim application note 187.
+    ///
+    /// Returns an empty list when no device answers the reset.
+    pub fn search(&mut self) -> Result<Vec<RomCode>, &'static str> {
+        let mut roms = Vec::new();
+        let mut rom = [0u8; 8];
+        // 1-based index of the bit where the previous pass took the 0 branch last; 0 if none.
+        let mut last_discrepancy = 0;
+
+        loop {
+            if !self.reset()? {
+                break;
+            }
+            self.write_byte(SEARCH_ROM)?;
+
+            let mut last_zero = 0;
+            for index in 1..=64 {
+                let byte = (index - 1) / 8;
+                let mask = 1 << ((index - 1) % 8);
+
+                let bit = self.read_bit()?;
+                let complement = self.read_bit()?;
+                let direction = match (bit, complement) {
+                    (true, true) => return Err("One-wire devices stopped answering the search"),
+                    (bit, complement) if bit != complement => bit,
+                    _ => {
+                        let direction = if index < last_discrepancy {
+                            rom[byte] & mask != 0
+                        } else {
+                            index == last_discrepancy
+                        };
+                        if !direction {
+                            last_zero = index;
+                        }
+                        direction
+                    }
+                };
+
+                if direction {
+                    rom[byte] |= mask;
+                } else {
+                    rom[byte] &= !mask;
+                }
+                self.write_bit(direction)?;
+            }
+
+            let code = RomCode(rom);
+            if !code.is_valid() {
+                return Err("One-wire ROM code CRC mismatch");
+            }
+            debug!("Found one-wire device {} on IO{}", code, self.pin);
+            roms.push(code);
+
+            last_discrepancy = last_zero;
+            if last_discrepancy == 0 {
+                break;
+            }
+        }
+
+        info!("Found {} one-wire device(s) on IO{}", roms.len(), self.pin);
+        Ok(roms)
+    }
+}
+
+/// A DS18B20 digital thermometer on a [`OneWire`] bus.
+///
+/// [`read_temperature`](Ds18b20::read_temperature) runs a conversion and waits for it. To
+/// measure with many sensors at once, start all conversions with
+/// [`convert_all`](Ds18b20::convert_all), wait [`CONVERSION_TIME`](Ds18b20::CONVERSION_TIME)
+/// and then [`read`](Ds18b20::read) each sensor.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use uptechstar_rs::adc_io::{self, Ds18b20, OneWire};
+///
+/// adc_io::adc_open();
+///
+/// let mut bus = OneWire::new(3);
+/// let sensors = Ds18b20::find_all(&mut bus).unwrap();
+///
+/// Ds18b20::convert_all(&mut bus).unwrap();
+/// thread::sleep(Ds18b20::CONVERSION_TIME);
+/// for sensor in &sensors {
+///     println!("{}: {:.2}°C", sensor.rom(), sensor.read(&mut bus).unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ds18b20 {
+    rom: RomCode,
+}
+
+impl Ds18b20 {
+    /// The family code of DS18B20 ROM codes.
+    pub const FAMILY: u8 = 0x28;
+
+    /// The longest conversion time, at the default 12-bit resolution.
+    pub const CONVERSION_TIME: Duration = Duration::from_millis(750);
+
+    /// Creates a driver for the sensor with `rom`.
+    ///
+    /// # Panics
+    ///
+    /// If `rom` does not have the DS18B20 family code.
+    pub fn new(rom: RomCode) -> Self {
+        assert_eq!(rom.family(), Self::FAMILY, "{} is not a DS18B20", rom);
+        Ds18b20 { rom }
+    }
+
+    /// Searches `bus` for DS18B20 sensors, skipping other devices.
+    pub fn find_all(bus: &mut OneWire) -> Result<Vec<Ds18b20>, &'static str> {
+        Ok(bus
+            .search()?
+            .into_iter()
+            .filter(|rom| rom.family() == Self::FAMILY)
+            .map(Ds18b20::new)
+            .collect())
+    }
+
+    /// Returns the ROM code of the sensor.
+    pub fn rom(&self) -> RomCode {
+        self.rom
+    }
+
+    /// Starts a conversion on every sensor of `bus`.
+    pub fn convert_all(bus: &mut OneWire) -> Result<(), &'static str> {
+        bus.skip()?;
+        bus.write_byte(CONVERT_T)
+    }
+
+    /// Starts a conversion on this sensor.
+    pub fn start_conversion(&self, bus: &mut OneWire) -> Result<(), &'static str> {
+        bus.select(&self.rom)?;
+        bus.write_byte(CONVERT_T)
+    }
+
+    /// Runs a conversion and returns the temperature in °C. Blocks for
+    /// [`CONVERSION_TIME`](Ds18b20::CONVERSION_TIME).
+    pub fn read_temperature(&self, bus: &mut OneWire) -> Result<f32, &'static str> {
+        self.start_conversion(bus)?;
+        thread::sleep(Self::CONVERSION_TIME);
+        self.read(bus)
+    }
+
+    /// Returns the temperature in °C from the last conversion.
+    pub fn read(&self, bus: &mut OneWire) -> Result<f32, &'static str> {
+        let scratchpad = self.read_scratchpad(bus)?;
+        let temperature = Self::parse_scratchpad(scratchpad);
+        if let Err(e) = temperature {
+            warn!("{} from DS18B20 {}, raw data: {:02x?}", e, self.rom, scratchpad);
+        }
+        temperature
+    }
+
+    /// Reads the 9 scratchpad bytes, including the CRC.
+    pub fn read_scratchpad(&self, bus: &mut OneWire) -> Result<[u8; 9], &'static str> {
+        bus.select(&self.rom)?;
+        bus.write_byte(READ_SCRATCHPAD)?;
+
+        let mut scratchpad = [0u8; 9];
+        for byte in &mut scratchpad {
+            *byte = bus.read_byte()?;
+        }
+        Ok(scratchpad)
+    }
+
+    /// Validates the CRC of a scratchpad and converts its temperature to °C, ignoring the bits
+    /// the configured resolution leaves undefined.
+    ///
+    /// A sensor that has not finished a conversion since power-up reports 85°C.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::adc_io::Ds18b20;
+    ///
+    /// let scratchpad = [0x5E, 0xFF, 0x4B, 0x46, 0x7F, 0xFF, 0x02, 0x10, 0xB6];
+    /// assert_eq!(Ds18b20::parse_scratchpad(scratchpad), Ok(-10.125));
+    ///
+    /// assert!(Ds18b20::parse_scratchpad([0xFF; 9]).is_err());
+    /// ```
+    pub fn parse_scratchpad(scratchpad: [u8; 9]) -> Result<f32, &'static str> {
+        if scratchpad == [0xFF; 9] {
+            return Err("DS18B20 did not answer");
+        }
+        if crc8(&scratchpad) != 0 {
+            return Err("DS18B20 scratchpad CRC mismatch");
+        }
+
+        let resolution = 9 + (scratchpad[4] >> 5 & 0x03);
+        let undefined = (1i16 << (12 - resolution)) - 1;
+        let raw = i16::from_le_bytes([scratchpad[0], scratchpad[1]]) & !undefined;
+        Ok(raw as f32 / 16.0)
+    }
+}
+
+/// Busy-waits until `deadline`, for delays too short for the scheduler.
+fn spin_until(deadline: Instant) {
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
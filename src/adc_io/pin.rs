@@ -0,0 +1,107 @@
+use super::{get_io_level, set_io_levels_with_mask, set_io_mode};
+
+/// A single digital pin, either one of the 8 onboard IO channels or a virtual pin provided by
+/// an expansion such as a [shift register](super::ShiftOut).
+///
+/// Drivers written against this trait work the same on any of them.
+///
+/// Status codes follow the rest of this module: `0` on success, non-zero on failure.
+pub trait Pin {
+    /// Drives the pin high (`true`) or low (`false`).
+    fn set_level(&mut self, high: bool) -> i32;
+
+    /// Reads the pin level. Output pins report the level they are driving.
+    fn is_high(&mut self) -> bool;
+
+    /// Drives the pin high.
+    fn set_high(&mut self) -> i32 {
+        self.set_level(true)
+    }
+
+    /// Drives the pin low.
+    fn set_low(&mut self) -> i32 {
+        self.set_level(false)
+    }
+
+    /// Inverts the level the pin is currently at.
+    fn toggle(&mut self) -> i32 {
+        let high = self.is_high();
+        self.set_level(!high)
+    }
+
+    /// Reads the pin level, `true` for low.
+    fn is_low(&mut self) -> bool {
+        !self.is_high()
+    }
+}
+
+/// One of the 8 onboard IO channels as a [`Pin`].
+///
+/// Writes go through [`set_io_levels_with_mask`], so several `IoPin`s can be driven from
+/// different threads without disturbing each other.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, IoPin, Pin};
+///
+/// adc_io::adc_open();
+///
+/// let mut led = IoPin::output(3);
+/// led.set_high();
+/// led.toggle();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IoPin {
+    index: u32,
+}
+
+impl IoPin {
+    /// Wraps IO `index` without changing its mode.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is not in `0..8`.
+    pub fn new(index: u32) -> Self {
+        assert!(index < 8, "IO pin index must be in 0..8, got {}", index);
+        IoPin { index }
+    }
+
+    /// Wraps IO `index` and switches it to input mode.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is not in `0..8`.
+    pub fn input(index: u32) -> Self {
+        let pin = Self::new(index);
+        set_io_mode(index, 0);
+        pin
+    }
+
+    /// Wraps IO `index` and switches it to output mode.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is not in `0..8`.
+    pub fn output(index: u32) -> Self {
+        let pin = Self::new(index);
+        set_io_mode(index, 1);
+        pin
+    }
+
+    /// Returns the IO channel index.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+impl Pin for IoPin {
+    fn set_level(&mut self, high: bool) -> i32 {
+        let mask = 1u8 << self.index;
+        set_io_levels_with_mask(mask, if high { mask } else { 0 })
+    }
+
+    fn is_high(&mut self) -> bool {
+        get_io_level(self.index as usize) != 0
+    }
+}
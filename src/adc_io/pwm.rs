@@ -0,0 +1,193 @@
+use super::{set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Software PWM on one IO pin.
+///
+/// The IO controller has no PWM hardware, so a dedicated thread toggles the pin once per
+/// period. Every level change is a round trip over the IO serial link, which limits usable
+/// frequencies to a few hundred Hz; that is enough for motor speed control and LED dimming,
+/// but not for servos.
+///
+/// A duty cycle of exactly `0.0` or `1.0` holds the pin low or high without toggling.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, SoftPwm};
+///
+/// adc_io::adc_open();
+///
+/// let mut pwm = SoftPwm::new(4).with_frequency(100.0);
+/// pwm.start();
+/// pwm.set_duty(0.25);
+/// ```
+pub struct SoftPwm {
+    pin: u32,
+    period: Duration,
+    /// The duty cycle as the bits of an `f32`, so it can be changed without locking.
+    duty: Arc<AtomicU32>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SoftPwm {
+    /// Creates a stopped PWM output on IO `pin` at 100 Hz with a duty cycle of 0.
+    ///
+    /// # Panics
+    ///
+    /// If `pin` is not in `0..8`.
+    pub fn new(pin: u32) -> Self {
+        assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+
+        SoftPwm {
+            pin,
+            period: Duration::from_millis(10),
+            duty: Arc::new(AtomicU32::new(0.0f32.to_bits())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Sets the PWM frequency. Takes effect on the next [`start`](SoftPwm::start).
+    ///
+    /// # Panics
+    ///
+    /// If `frequency_hz` is not a positive, finite number.
+    pub fn with_frequency(mut self, frequency_hz: f32) -> Self {
+        assert!(
+            frequency_hz.is_finite() && frequency_hz > 0.0,
+            "PWM frequency must be positive, got {}",
+            frequency_hz
+        );
+        self.period = Duration::from_secs_f32(1.0 / frequency_hz);
+        self
+    }
+
+    /// Returns the IO pin driven by this output.
+    pub fn pin(&self) -> u32 {
+        self.pin
+    }
+
+    /// Returns the PWM period.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the current duty cycle.
+    pub fn duty(&self) -> f32 {
+        f32::from_bits(self.duty.load(Ordering::Relaxed))
+    }
+
+    /// Sets the duty cycle, clamped to `0.0..=1.0`. Takes effect from the next period.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_duty(&mut self, duty: f32) -> &mut Self {
+        let duty = if duty.is_nan() { 0.0 } else { duty.clamp(0.0, 1.0) };
+        self.duty.store(duty.to_bits(), Ordering::Relaxed);
+        self
+    }
+
+    /// Returns `true` while the PWM thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Switches the pin to output mode and starts the PWM thread. Does nothing if it is
+    /// already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!("Starting software PWM on IO{} at {:.1} Hz", self.pin, 1.0 / self.period.as_secs_f32());
+
+        if set_io_mode(self.pin, 1) != 0 {
+            warn!("Failed to switch IO{} to output mode for PWM", self.pin);
+        }
+
+        self.running.store(true, Ordering::Release);
+
+        let mask = 1u8 << self.pin;
+        let period = self.period;
+        let duty = Arc::clone(&self.duty);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-pwm".into())
+                .spawn(move || {
+                    let mut level = None;
+                    let mut set_level = |high: bool| {
+                        if level != Some(high) {
+                            set_io_levels_with_mask(mask, if high { mask } else { 0 });
+                            level = Some(high);
+                        }
+                    };
+
+                    let mut period_start = Instant::now();
+                    while running.load(Ordering::Acquire) {
+                        let duty = f32::from_bits(duty.load(Ordering::Relaxed));
+                        let high_time = period.mul_f32(duty);
+
+                        if duty > 0.0 {
+                            set_level(true);
+                            sleep_until(period_start + high_time);
+                        }
+                        if duty < 1.0 {
+                            set_level(false);
+                        }
+
+                        period_start += period;
+                        let now = Instant::now();
+                        if period_start > now {
+                            thread::sleep(period_start - now);
+                        } else {
+                            period_start = now;
+                        }
+                    }
+
+                    set_level(false);
+                    debug!("PWM thread exited");
+                })
+                .expect("Failed to spawn PWM thread"),
+        );
+
+        self
+    }
+
+    /// Stops the PWM thread and drives the pin low.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Software PWM on IO{} stopped", self.pin);
+        }
+
+        self
+    }
+}
+
+impl Drop for SoftPwm {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        thread::sleep(deadline - now);
+    }
+}
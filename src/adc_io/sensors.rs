@@ -0,0 +1,297 @@
+//! Conversions from raw ADC readings to physical quantities.
+//!
+//! Every sensor here is a plain struct holding its channel and circuit parameters. The pure
+//! [`convert`](AnalogSensor::convert) method turns a raw reading into a value and can be
+//! used on recorded data; [`read`](AnalogSensor::read) samples the channel first.
+//!
+//! Resistive sensors sit in a voltage divider with a fixed series resistor. Their conversion
+//! is ratiometric and does not depend on the reference voltage, only on the ADC
+//! [full scale](DEFAULT_FULL_SCALE).
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::adc_io::{self, sensors::{AnalogSensor, Thermistor, VoltageDivider}};
+//!
+//! adc_io::adc_open();
+//!
+//! let battery = VoltageDivider::new(0, 20_000.0, 10_000.0);
+//! let thermistor = Thermistor::from_beta(1, 10_000.0, 25.0, 3950.0, 10_000.0);
+//!
+//! println!("battery: {:.2} V", battery.read().unwrap());
+//! println!("motor: {:.1} °C", thermistor.read().unwrap());
+//! ```
+
+use super::adc_get_frame;
+
+/// The raw reading at the reference voltage, for the 12-bit ADC of the controller.
+pub const DEFAULT_FULL_SCALE: i32 = 4095;
+
+/// The ADC reference voltage of the controller.
+pub const DEFAULT_REFERENCE_VOLTS: f32 = 3.3;
+
+/// Offset between °C and K.
+const ZERO_CELSIUS: f32 = 273.15;
+
+/// A sensor on one ADC channel with a conversion from raw readings.
+pub trait AnalogSensor {
+    /// The ADC channel (0-9) the sensor is connected to.
+    fn channel(&self) -> usize;
+
+    /// Converts a raw reading into the sensor's quantity. Readings at the rails can yield
+    /// infinite or NaN results.
+    fn convert(&self, raw: i32) -> f32;
+
+    /// Samples all ADC channels and converts the reading of this sensor's channel.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<f32, &'static str>` - The value, or an error if the ADC read failed or the
+    ///   reading could not be converted, such as an open or shorted sensor.
+    fn read(&self) -> Result<f32, &'static str> {
+        let frame = adc_get_frame()?;
+        let raw = *frame.0.get(self.channel()).ok_or("ADC channel index out of range")?;
+
+        let value = self.convert(raw);
+        if value.is_finite() {
+            Ok(value)
+        } else {
+            Err("ADC reading at the rail, check the sensor wiring")
+        }
+    }
+}
+
+/// Which side of the divider the sensor is on; the series resistor takes the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SensorSide {
+    /// Between the ADC input and ground, with the series resistor to the reference voltage.
+    #[default]
+    Low,
+    /// Between the reference voltage and the ADC input, with the series resistor to ground.
+    High,
+}
+
+/// Returns the resistance of the sensor in a divider with `series` ohms.
+fn divider_resistance(raw: i32, full_scale: i32, series: f32, side: SensorSide) -> f32 {
+    let (raw, full_scale) = (raw as f32, full_scale as f32);
+    match side {
+        SensorSide::Low => series * raw / (full_scale - raw),
+        SensorSide::High => series * (full_scale - raw) / raw,
+    }
+}
+
+/// A resistive voltage divider scaling a higher voltage, such as a battery, into the ADC
+/// range.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::sensors::{AnalogSensor, VoltageDivider};
+///
+/// // 20k over 10k divides by three.
+/// let battery = VoltageDivider::new(0, 20_000.0, 10_000.0);
+/// assert!((battery.convert(4095) - 9.9).abs() < 1e-4);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VoltageDivider {
+    channel: usize,
+    ratio: f32,
+    reference: f32,
+    full_scale: i32,
+}
+
+impl VoltageDivider {
+    /// Creates a divider on `channel` with `r_top` ohms from the measured voltage to the ADC
+    /// input and `r_bottom` ohms from the ADC input to ground.
+    pub fn new(channel: usize, r_top: f32, r_bottom: f32) -> Self {
+        VoltageDivider {
+            channel,
+            ratio: (r_top + r_bottom) / r_bottom,
+            reference: DEFAULT_REFERENCE_VOLTS,
+            full_scale: DEFAULT_FULL_SCALE,
+        }
+    }
+
+    /// Sets the ADC reference voltage.
+    pub fn with_reference(mut self, volts: f32) -> Self {
+        self.reference = volts;
+        self
+    }
+
+    /// Sets the raw reading at the reference voltage.
+    pub fn with_full_scale(mut self, full_scale: i32) -> Self {
+        self.full_scale = full_scale;
+        self
+    }
+
+    /// Returns the factor the measured voltage is divided by.
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+}
+
+impl AnalogSensor for VoltageDivider {
+    fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Returns the measured voltage in V.
+    fn convert(&self, raw: i32) -> f32 {
+        raw as f32 / self.full_scale as f32 * self.reference * self.ratio
+    }
+}
+
+/// An NTC thermistor, converted with the Steinhart–Hart equation
+/// `1/T = A + B·ln(R) + C·ln(R)³`.
+///
+/// Most datasheets only give a B value; [`from_beta`](Thermistor::from_beta) derives the
+/// coefficients from it, which is accurate to about ±1°C within 50°C of the nominal
+/// temperature.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::sensors::{AnalogSensor, Thermistor};
+///
+/// // A 10k B3950 thermistor to ground with a 10k series resistor reads half scale at 25°C.
+/// let thermistor = Thermistor::from_beta(1, 10_000.0, 25.0, 3950.0, 10_000.0);
+/// assert!((thermistor.convert(2048) - 25.0).abs() < 0.1);
+/// assert!(thermistor.convert(1000) > 50.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Thermistor {
+    channel: usize,
+    coefficients: [f32; 3],
+    series: f32,
+    side: SensorSide,
+    full_scale: i32,
+}
+
+impl Thermistor {
+    /// Creates a thermistor on `channel` with Steinhart–Hart coefficients `a`, `b` and `c`
+    /// (for resistances in Ω and temperatures in K) and a `series` resistor in Ω.
+    pub fn new(channel: usize, a: f32, b: f32, c: f32, series: f32) -> Self {
+        Thermistor {
+            channel,
+            coefficients: [a, b, c],
+            series,
+            side: SensorSide::Low,
+            full_scale: DEFAULT_FULL_SCALE,
+        }
+    }
+
+    /// Creates a thermistor on `channel` with resistance `r0` Ω at `t0` °C, the given B value
+    /// and a `series` resistor in Ω.
+    pub fn from_beta(channel: usize, r0: f32, t0: f32, beta: f32, series: f32) -> Self {
+        let a = 1.0 / (t0 + ZERO_CELSIUS) - r0.ln() / beta;
+        Self::new(channel, a, 1.0 / beta, 0.0, series)
+    }
+
+    /// Sets which side of the divider the thermistor is on.
+    pub fn with_side(mut self, side: SensorSide) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Sets the raw reading at the reference voltage.
+    pub fn with_full_scale(mut self, full_scale: i32) -> Self {
+        self.full_scale = full_scale;
+        self
+    }
+
+    /// Returns the thermistor resistance in Ω for a raw reading.
+    pub fn resistance(&self, raw: i32) -> f32 {
+        divider_resistance(raw, self.full_scale, self.series, self.side)
+    }
+}
+
+impl AnalogSensor for Thermistor {
+    fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Returns the temperature in °C.
+    fn convert(&self, raw: i32) -> f32 {
+        let ln_r = self.resistance(raw).ln();
+        let [a, b, c] = self.coefficients;
+        1.0 / (a + b * ln_r + c * ln_r.powi(3)) - ZERO_CELSIUS
+    }
+}
+
+/// A photoresistor (LDR), approximating illuminance from the power law `R = R10·(E/10)^-γ`.
+///
+/// The defaults match the common GL5528 (about 10kΩ at 10 lux, γ = 0.7). Sample spread is
+/// large, so treat the result as an estimate unless calibrated with
+/// [`with_characteristics`](Photoresistor::with_characteristics).
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::sensors::{AnalogSensor, Photoresistor};
+///
+/// let ldr = Photoresistor::new(2, 10_000.0);
+/// assert!((ldr.convert(2048) - 10.0).abs() < 0.1);
+/// assert!(ldr.convert(500) > ldr.convert(2048));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Photoresistor {
+    channel: usize,
+    r10: f32,
+    gamma: f32,
+    series: f32,
+    side: SensorSide,
+    full_scale: i32,
+}
+
+impl Photoresistor {
+    /// Creates a photoresistor on `channel` with a `series` resistor in Ω.
+    pub fn new(channel: usize, series: f32) -> Self {
+        Photoresistor {
+            channel,
+            r10: 10_000.0,
+            gamma: 0.7,
+            series,
+            side: SensorSide::Low,
+            full_scale: DEFAULT_FULL_SCALE,
+        }
+    }
+
+    /// Sets the resistance in Ω at 10 lux and the slope γ of the datasheet's log-log curve.
+    pub fn with_characteristics(mut self, r10: f32, gamma: f32) -> Self {
+        self.r10 = r10;
+        self.gamma = gamma;
+        self
+    }
+
+    /// Sets which side of the divider the photoresistor is on.
+    pub fn with_side(mut self, side: SensorSide) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Sets the raw reading at the reference voltage.
+    pub fn with_full_scale(mut self, full_scale: i32) -> Self {
+        self.full_scale = full_scale;
+        self
+    }
+
+    /// Returns the photoresistor resistance in Ω for a raw reading.
+    pub fn resistance(&self, raw: i32) -> f32 {
+        divider_resistance(raw, self.full_scale, self.series, self.side)
+    }
+}
+
+impl AnalogSensor for Photoresistor {
+    fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Returns the illuminance in lux.
+    fn convert(&self, raw: i32) -> f32 {
+        10.0 * (self.resistance(raw) / self.r10).powf(-1.0 / self.gamma)
+    }
+}
@@ -0,0 +1,351 @@
+use super::{Pin, io_get_all_channels, set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::{error, warn};
+use std::sync::{Arc, Mutex};
+
+/// Asserts that `pins` are valid and distinct, then switches them to `mode`.
+fn setup_pins(pins: [u32; 3], mode: [u8; 3], device: &str) {
+    for (i, &pin) in pins.iter().enumerate() {
+        assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+        assert!(!pins[..i].contains(&pin), "IO{} is used twice by the {}", pin, device);
+
+        if set_io_mode(pin, mode[i]) != 0 {
+            warn!("Failed to set the mode of IO{} for the {}", pin, device);
+        }
+    }
+}
+
+struct ShiftOutState {
+    data: u8,
+    clock: u8,
+    latch: u8,
+    outputs: Vec<u8>,
+}
+
+impl ShiftOutState {
+    /// Clocks out all registers, the one furthest from the board first, and latches them.
+    fn flush(&self) -> i32 {
+        let pins = self.data | self.clock;
+
+        for &byte in self.outputs.iter().rev() {
+            for bit in (0..8).rev() {
+                let data = if byte >> bit & 1 == 1 { self.data } else { 0 };
+                let code = set_io_levels_with_mask(pins, data);
+                if code != 0 {
+                    return code;
+                }
+                let code = set_io_levels_with_mask(self.clock, self.clock);
+                if code != 0 {
+                    return code;
+                }
+            }
+        }
+
+        let code = set_io_levels_with_mask(pins | self.latch, 0);
+        if code != 0 {
+            return code;
+        }
+        let code = set_io_levels_with_mask(self.latch, self.latch);
+        if code != 0 {
+            return code;
+        }
+        set_io_levels_with_mask(self.latch, 0)
+    }
+}
+
+/// A chain of 74HC595 serial-in/parallel-out shift registers, adding 8 output pins per register
+/// for three IO pins.
+///
+/// Wire DS to `data`, SHCP to `clock` and STCP to `latch`; tie /OE low and /MR high. Further
+/// registers are daisy-chained from Q7'. Output `n` is pin Q`n % 8` of register `n / 8`,
+/// counting from the register wired to the board.
+///
+/// Every change re-sends the whole chain, two IO writes per bit, so updates take a few
+/// milliseconds per register. Use [`write`](ShiftOut::write) to change many outputs at once.
+///
+/// `ShiftOut` is a cheap handle; clones and the [`pin`](ShiftOut::pin)s drive the same chain.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, Pin, ShiftOut};
+///
+/// adc_io::adc_open();
+///
+/// let outputs = ShiftOut::new(0, 1, 2).with_chain(2);
+/// outputs.write(&[0b1010_1010, 0xFF]);
+///
+/// let mut relay = outputs.pin(9);
+/// relay.set_low();
+/// ```
+#[derive(Clone)]
+pub struct ShiftOut {
+    state: Arc<Mutex<ShiftOutState>>,
+}
+
+impl ShiftOut {
+    /// Creates a single 74HC595 on the given IO pins and clears all its outputs.
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8` or a pin is used twice.
+    pub fn new(data: u32, clock: u32, latch: u32) -> Self {
+        setup_pins([data, clock, latch], [1, 1, 1], "74HC595");
+
+        let shift_out = ShiftOut {
+            state: Arc::new(Mutex::new(ShiftOutState {
+                data: 1 << data,
+                clock: 1 << clock,
+                latch: 1 << latch,
+                outputs: vec![0],
+            })),
+        };
+        shift_out.lock().flush();
+        shift_out
+    }
+
+    /// Sets the number of daisy-chained registers and clears all their outputs.
+    ///
+    /// # Panics
+    ///
+    /// If `registers` is zero.
+    pub fn with_chain(self, registers: usize) -> Self {
+        assert!(registers > 0, "A shift register chain needs at least one register");
+        {
+            let mut state = self.lock();
+            state.outputs = vec![0; registers];
+            state.flush();
+        }
+        self
+    }
+
+    /// Returns the number of virtual output pins.
+    pub fn len(&self) -> usize {
+        self.lock().outputs.len() * 8
+    }
+
+    /// Always `false`; a chain has at least one register.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Returns the output levels, one byte per register.
+    pub fn outputs(&self) -> Vec<u8> {
+        self.lock().outputs.clone()
+    }
+
+    /// Sets all outputs at once, one byte per register. Missing bytes are left unchanged and
+    /// extra bytes ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `i32` - Returns `0` on success, non-zero on failure.
+    pub fn write(&self, outputs: &[u8]) -> i32 {
+        let mut state = self.lock();
+        for (output, &value) in state.outputs.iter_mut().zip(outputs) {
+            *output = value;
+        }
+        state.flush()
+    }
+
+    /// Sets output `index` high or low.
+    ///
+    /// # Returns
+    ///
+    /// * `i32` - Returns `0` on success, non-zero on failure.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of range.
+    pub fn set(&self, index: usize, high: bool) -> i32 {
+        let mut state = self.lock();
+        assert!(index < state.outputs.len() * 8, "Shift register output {} out of range", index);
+
+        let bit = 1 << (index % 8);
+        let output = &mut state.outputs[index / 8];
+        if high {
+            *output |= bit;
+        } else {
+            *output &= !bit;
+        }
+        state.flush()
+    }
+
+    /// Returns output `index` as a [`Pin`].
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of range.
+    pub fn pin(&self, index: usize) -> ShiftOutPin {
+        assert!(index < self.len(), "Shift register output {} out of range", index);
+        ShiftOutPin {
+            register: self.clone(),
+            index,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ShiftOutState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// One output of a [`ShiftOut`] chain.
+#[derive(Clone)]
+pub struct ShiftOutPin {
+    register: ShiftOut,
+    index: usize,
+}
+
+impl Pin for ShiftOutPin {
+    fn set_level(&mut self, high: bool) -> i32 {
+        self.register.set(self.index, high)
+    }
+
+    fn is_high(&mut self) -> bool {
+        self.register.lock().outputs[self.index / 8] >> (self.index % 8) & 1 == 1
+    }
+}
+
+struct ShiftInState {
+    load: u8,
+    clock: u8,
+    data: u8,
+    registers: usize,
+}
+
+/// A chain of 74HC165 parallel-in/serial-out shift registers, adding 8 input pins per register
+/// for three IO pins.
+///
+/// Wire /PL to `load`, CP to `clock` and Q7 to `data`; tie /CE low. Further registers feed
+/// their Q7 into DS of the previous one. Input `n` is pin D`n % 8` of register `n / 8`,
+/// counting from the register wired to the board.
+///
+/// `ShiftIn` is a cheap handle; clones and the [`pin`](ShiftIn::pin)s read the same chain.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, Pin, ShiftIn};
+///
+/// adc_io::adc_open();
+///
+/// let inputs = ShiftIn::new(4, 5, 6);
+/// println!("{:#010b}", inputs.read()[0]);
+///
+/// let mut bumper = inputs.pin(3);
+/// if bumper.is_low() {
+///     println!("Bumper pressed");
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ShiftIn {
+    state: Arc<Mutex<ShiftInState>>,
+}
+
+impl ShiftIn {
+    /// Creates a single 74HC165 on the given IO pins.
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8` or a pin is used twice.
+    pub fn new(load: u32, clock: u32, data: u32) -> Self {
+        setup_pins([load, clock, data], [1, 1, 0], "74HC165");
+        set_io_levels_with_mask(1 << load | 1 << clock, 1 << load);
+
+        ShiftIn {
+            state: Arc::new(Mutex::new(ShiftInState {
+                load: 1 << load,
+                clock: 1 << clock,
+                data: 1 << data,
+                registers: 1,
+            })),
+        }
+    }
+
+    /// Sets the number of daisy-chained registers.
+    ///
+    /// # Panics
+    ///
+    /// If `registers` is zero.
+    pub fn with_chain(self, registers: usize) -> Self {
+        assert!(registers > 0, "A shift register chain needs at least one register");
+        self.lock().registers = registers;
+        self
+    }
+
+    /// Returns the number of virtual input pins.
+    pub fn len(&self) -> usize {
+        self.lock().registers * 8
+    }
+
+    /// Always `false`; a chain has at least one register.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Latches and reads all inputs, one byte per register.
+    pub fn read(&self) -> Vec<u8> {
+        let state = self.lock();
+        let mut inputs = vec![0u8; state.registers];
+
+        set_io_levels_with_mask(state.load, 0);
+        set_io_levels_with_mask(state.load, state.load);
+
+        for input in inputs.iter_mut() {
+            for bit in (0..8).rev() {
+                if io_get_all_channels() & state.data != 0 {
+                    *input |= 1 << bit;
+                }
+                set_io_levels_with_mask(state.clock, state.clock);
+                set_io_levels_with_mask(state.clock, 0);
+            }
+        }
+
+        inputs
+    }
+
+    /// Latches the chain and returns input `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of range.
+    pub fn get(&self, index: usize) -> bool {
+        assert!(index < self.len(), "Shift register input {} out of range", index);
+        self.read()[index / 8] >> (index % 8) & 1 == 1
+    }
+
+    /// Returns input `index` as a [`Pin`].
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of range.
+    pub fn pin(&self, index: usize) -> ShiftInPin {
+        assert!(index < self.len(), "Shift register input {} out of range", index);
+        ShiftInPin {
+            register: self.clone(),
+            index,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ShiftInState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// One input of a [`ShiftIn`] chain. Reading it latches and reads the whole chain.
+#[derive(Clone)]
+pub struct ShiftInPin {
+    register: ShiftIn,
+    index: usize,
+}
+
+impl Pin for ShiftInPin {
+    /// Inputs cannot be driven; always fails with `-1`.
+    fn set_level(&mut self, _high: bool) -> i32 {
+        error!("Shift register input {} cannot be driven", self.index);
+        -1
+    }
+
+    fn is_high(&mut self) -> bool {
+        self.register.get(self.index)
+    }
+}
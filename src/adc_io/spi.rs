@@ -0,0 +1,225 @@
+use super::{io_get_all_channels, set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::{info, warn};
+use std::thread;
+use std::time::Duration;
+
+/// SPI clock polarity and phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpiMode {
+    /// Clock idles low, data is sampled on the rising edge.
+    #[default]
+    Mode0,
+    /// Clock idles low, data is sampled on the falling edge.
+    Mode1,
+    /// Clock idles high, data is sampled on the falling edge.
+    Mode2,
+    /// Clock idles high, data is sampled on the rising edge.
+    Mode3,
+}
+
+impl SpiMode {
+    /// Returns `true` if the clock idles high (CPOL = 1).
+    pub fn idle_high(self) -> bool {
+        matches!(self, SpiMode::Mode2 | SpiMode::Mode3)
+    }
+
+    /// Returns `true` if data is sampled on the second clock edge of a bit (CPHA = 1).
+    pub fn sample_on_trailing_edge(self) -> bool {
+        matches!(self, SpiMode::Mode1 | SpiMode::Mode3)
+    }
+}
+
+/// A bit-banged SPI master on four IO pins, sending the most significant bit first.
+///
+/// The chip select pin is active low and held low for each call. Every clock edge is a write
+/// over the IO link, so the bus runs at a few kHz at most; that is plenty for ADCs like the
+/// MCP3008 and for configuring radios like the nRF24L01, but too slow for displays. A clock
+/// frequency set with [`with_frequency`](SoftSpi::with_frequency) only ever slows it down
+/// further, for devices that need it.
+///
+/// With the `embedded-hal` feature, `SoftSpi` implements `embedded_hal::spi::SpiDevice`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, SoftSpi, SpiMode};
+///
+/// adc_io::adc_open();
+///
+/// // MCP3008 channel 0, single-ended
+/// let mut spi = SoftSpi::new(0, 1, 2, 3).with_mode(SpiMode::Mode0);
+/// let mut buffer = [0x01, 0x80, 0x00];
+/// spi.transfer_in_place(&mut buffer).unwrap();
+/// let value = ((buffer[1] as u16 & 0x03) << 8) | buffer[2] as u16;
+/// println!("CH0: {}", value);
+/// ```
+pub struct SoftSpi {
+    mosi: u8,
+    miso: u8,
+    sck: u8,
+    cs: u8,
+    mode: SpiMode,
+    half_period: Duration,
+}
+
+impl SoftSpi {
+    /// Creates a bus on the given IO pins in [`SpiMode::Mode0`], with the chip deselected.
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8` or a pin is used twice.
+    pub fn new(mosi: u32, miso: u32, sck: u32, cs: u32) -> Self {
+        let pins = [mosi, miso, sck, cs];
+        for (i, &pin) in pins.iter().enumerate() {
+            assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+            assert!(!pins[..i].contains(&pin), "IO{} is used twice by the SPI bus", pin);
+        }
+
+        for (pin, mode) in [(mosi, 1), (miso, 0), (sck, 1), (cs, 1)] {
+            if set_io_mode(pin, mode) != 0 {
+                warn!("Failed to set the mode of IO{} for the SPI bus", pin);
+            }
+        }
+
+        let spi = SoftSpi {
+            mosi: 1 << mosi,
+            miso: 1 << miso,
+            sck: 1 << sck,
+            cs: 1 << cs,
+            mode: SpiMode::Mode0,
+            half_period: Duration::ZERO,
+        };
+        spi.idle();
+        spi
+    }
+
+    /// Sets the clock polarity and phase.
+    pub fn with_mode(mut self, mode: SpiMode) -> Self {
+        self.mode = mode;
+        self.idle();
+        self
+    }
+
+    /// Limits the clock to `frequency_hz`.
+    ///
+    /// # Panics
+    ///
+    /// If `frequency_hz` is not a positive, finite number.
+    pub fn with_frequency(mut self, frequency_hz: f32) -> Self {
+        assert!(
+            frequency_hz.is_finite() && frequency_hz > 0.0,
+            "SPI frequency must be positive, got {}",
+            frequency_hz
+        );
+        self.half_period = Duration::from_secs_f32(0.5 / frequency_hz);
+        info!("Software SPI clock limited to {:.0} Hz", frequency_hz);
+        self
+    }
+
+    /// Returns the clock polarity and phase.
+    pub fn mode(&self) -> SpiMode {
+        self.mode
+    }
+
+    /// Deselects the chip and puts the clock at its idle level.
+    fn idle(&self) -> i32 {
+        let idle_clock = if self.mode.idle_high() { self.sck } else { 0 };
+        set_io_levels_with_mask(self.cs | self.sck, self.cs | idle_clock)
+    }
+
+    fn delay(&self) {
+        if !self.half_period.is_zero() {
+            thread::sleep(self.half_period);
+        }
+    }
+
+    fn set(&self, mask: u8, high: bool) -> Result<(), &'static str> {
+        if set_io_levels_with_mask(mask, if high { mask } else { 0 }) != 0 {
+            return Err("Failed to drive the SPI pins");
+        }
+        Ok(())
+    }
+
+    fn transfer_byte(&self, byte: u8) -> Result<u8, &'static str> {
+        let idle = self.mode.idle_high();
+        let mut received = 0u8;
+
+        for bit in (0..8).rev() {
+            let out = byte >> bit & 1 == 1;
+            let mosi = if out { self.mosi } else { 0 };
+            let sampled;
+
+            if self.mode.sample_on_trailing_edge() {
+                // The leading edge shifts the bit out, the trailing edge samples it.
+                let leading = if idle { 0 } else { self.sck };
+                if set_io_levels_with_mask(self.mosi | self.sck, mosi | leading) != 0 {
+                    return Err("Failed to drive the SPI pins");
+                }
+                self.delay();
+                self.set(self.sck, idle)?;
+                sampled = io_get_all_channels() & self.miso != 0;
+            } else {
+                self.set(self.mosi, out)?;
+                self.delay();
+                self.set(self.sck, !idle)?;
+                sampled = io_get_all_channels() & self.miso != 0;
+                self.delay();
+                self.set(self.sck, idle)?;
+            }
+
+            received = received << 1 | sampled as u8;
+            self.delay();
+        }
+        Ok(received)
+    }
+
+    /// Selects the chip, runs `body` and deselects the chip again, also when `body` fails.
+    pub(crate) fn selected<T>(&mut self, body: impl FnOnce(&mut Self) -> Result<T, &'static str>) -> Result<T, &'static str> {
+        self.set(self.cs, false)?;
+        let result = body(self);
+        let deselected = self.set(self.cs, true);
+        let value = result?;
+        deselected.map(|_| value)
+    }
+
+    /// Sends `data` and replaces every byte with the byte received while it was sent.
+    pub fn transfer_in_place(&mut self, data: &mut [u8]) -> Result<(), &'static str> {
+        self.selected(|spi| spi.exchange_in_place(data))
+    }
+
+    /// Sends `write` while receiving into `read`. The shorter buffer is padded with zeros or
+    /// discarded bytes to the length of the longer one.
+    pub fn transfer(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), &'static str> {
+        self.selected(|spi| spi.exchange(read, write))
+    }
+
+    /// Sends `data`, discarding the received bytes.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), &'static str> {
+        self.transfer(&mut [], data)
+    }
+
+    /// Fills `data` with received bytes, sending zeros.
+    pub fn read(&mut self, data: &mut [u8]) -> Result<(), &'static str> {
+        self.transfer(data, &[])
+    }
+
+    /// Like [`transfer_in_place`](Self::transfer_in_place), without touching chip select.
+    pub(crate) fn exchange_in_place(&mut self, data: &mut [u8]) -> Result<(), &'static str> {
+        for byte in data {
+            *byte = self.transfer_byte(*byte)?;
+        }
+        Ok(())
+    }
+
+    /// Like [`transfer`](Self::transfer), without touching chip select.
+    pub(crate) fn exchange(&mut self, read: &mut [u8], write: &[u8]) -> Result<(), &'static str> {
+        for index in 0..read.len().max(write.len()) {
+            let received = self.transfer_byte(write.get(index).copied().unwrap_or(0))?;
+            if let Some(byte) = read.get_mut(index) {
+                *byte = received;
+            }
+        }
+        Ok(())
+    }
+}
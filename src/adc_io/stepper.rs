@@ -0,0 +1,412 @@
+use super::{set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Coil excitation sequence of a [`Stepper`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StepMode {
+    /// Two coils energized at a time, 4 steps per cycle. Full torque.
+    Full,
+    /// Alternating one and two coils, 8 steps per cycle. Twice the resolution, smoother.
+    #[default]
+    Half,
+}
+
+impl StepMode {
+    /// The coil patterns of one cycle, bit 0 being the first of the four pins.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::adc_io::StepMode;
+    ///
+    /// assert_eq!(StepMode::Full.sequence(), &[0b0011, 0b0110, 0b1100, 0b1001]);
+    /// assert_eq!(StepMode::Half.sequence().len(), 8);
+    /// ```
+    pub fn sequence(self) -> &'static [u8] {
+        match self {
+            StepMode::Full => &[0b0011, 0b0110, 0b1100, 0b1001],
+            StepMode::Half => &[0b0001, 0b0011, 0b0010, 0b0110, 0b0100, 0b1100, 0b1000, 0b1001],
+        }
+    }
+}
+
+struct Motion {
+    position: i64,
+    target: i64,
+    /// Current speed in steps per second, always non-negative.
+    speed: f32,
+    /// Direction of the current movement: -1, 0 or 1.
+    direction: i64,
+}
+
+struct Shared {
+    motion: Mutex<Motion>,
+    /// Signalled when the target changes, the motor arrives, or the thread should exit.
+    changed: Condvar,
+    running: AtomicBool,
+}
+
+/// A unipolar stepper motor, such as the 28BYJ-48 on a ULN2003 driver board, on four IO pins.
+///
+/// Steps are generated on a dedicated timing thread with a trapezoidal speed profile:
+/// the motor accelerates up to the maximum speed and decelerates in time to stop exactly on
+/// the target, including when the target is changed or reversed mid-move. The position is
+/// counted in steps of the configured [`StepMode`] and starts at 0.
+///
+/// Coil patterns are written with [`set_io_levels_with_mask`], so the other four IO pins stay
+/// usable by other threads.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, StepMode, Stepper};
+///
+/// adc_io::adc_open();
+///
+/// let mut stepper = Stepper::new([0, 1, 2, 3])
+///     .with_mode(StepMode::Half)
+///     .with_max_speed(800.0)
+///     .with_acceleration(1500.0);
+/// stepper.start();
+///
+/// // One output shaft revolution of a 28BYJ-48 in half-step mode
+/// stepper.move_by(4096);
+/// stepper.wait();
+/// println!("Now at step {}", stepper.position());
+/// ```
+pub struct Stepper {
+    pins: [u32; 4],
+    mode: StepMode,
+    max_speed: f32,
+    acceleration: f32,
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Stepper {
+    /// Creates a stopped stepper on the given IO pins, in the order of the driver inputs
+    /// IN1 to IN4.
+    ///
+    /// Defaults to half-stepping at up to 500 steps/s with an acceleration of 1000 steps/s².
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8`.
+    pub fn new(pins: [u32; 4]) -> Self {
+        assert!(pins.iter().all(|&pin| pin < 8), "IO pin index must be in 0..8, got {:?}", pins);
+
+        Stepper {
+            pins,
+            mode: StepMode::default(),
+            max_speed: 500.0,
+            acceleration: 1000.0,
+            shared: Arc::new(Shared {
+                motion: Mutex::new(Motion {
+                    position: 0,
+                    target: 0,
+                    speed: 0.0,
+                    direction: 0,
+                }),
+                changed: Condvar::new(),
+                running: AtomicBool::new(false),
+            }),
+            thread: None,
+        }
+    }
+
+    /// Sets the excitation sequence. Takes effect on the next [`start`](Stepper::start).
+    pub fn with_mode(mut self, mode: StepMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the maximum speed in steps per second.
+    ///
+    /// # Panics
+    ///
+    /// If `steps_per_second` is not a positive, finite number.
+    pub fn with_max_speed(mut self, steps_per_second: f32) -> Self {
+        assert!(
+            steps_per_second.is_finite() && steps_per_second > 0.0,
+            "Stepper speed must be positive, got {}",
+            steps_per_second
+        );
+        self.max_speed = steps_per_second;
+        self
+    }
+
+    /// Sets the acceleration in steps per second squared. `0.0` disables ramping, so every
+    /// move runs at the maximum speed from the first step.
+    pub fn with_acceleration(mut self, steps_per_second_squared: f32) -> Self {
+        self.acceleration = steps_per_second_squared.max(0.0);
+        self
+    }
+
+    /// Returns the excitation sequence in use.
+    pub fn mode(&self) -> StepMode {
+        self.mode
+    }
+
+    /// Returns the current position in steps.
+    pub fn position(&self) -> i64 {
+        self.motion().position
+    }
+
+    /// Returns the position the motor is moving to.
+    pub fn target(&self) -> i64 {
+        self.motion().target
+    }
+
+    /// Returns the current speed in steps per second, negative when moving backwards.
+    pub fn speed(&self) -> f32 {
+        let motion = self.motion();
+        motion.speed * motion.direction as f32
+    }
+
+    /// Returns `true` while the motor has not come to rest on its target.
+    pub fn is_moving(&self) -> bool {
+        let motion = self.motion();
+        motion.position != motion.target || motion.speed > 0.0
+    }
+
+    /// Moves to an absolute position. Replaces any move in progress.
+    pub fn move_to(&self, target: i64) {
+        self.motion().target = target;
+        self.shared.changed.notify_all();
+    }
+
+    /// Moves relative to the current target.
+    pub fn move_by(&self, steps: i64) {
+        self.motion().target += steps;
+        self.shared.changed.notify_all();
+    }
+
+    /// Decelerates to a stop as quickly as the acceleration allows.
+    pub fn halt(&self) {
+        let mut motion = self.motion();
+        let stopping = if self.acceleration == 0.0 {
+            0
+        } else {
+            (motion.speed * motion.speed / (2.0 * self.acceleration)).ceil() as i64
+        };
+        motion.target = motion.position + stopping * motion.direction;
+        drop(motion);
+        self.shared.changed.notify_all();
+    }
+
+    /// Redefines the current position, e.g. after homing. Cancels any move in progress.
+    pub fn set_position(&self, position: i64) {
+        let mut motion = self.motion();
+        motion.position = position;
+        motion.target = position;
+        motion.speed = 0.0;
+        motion.direction = 0;
+        drop(motion);
+        self.shared.changed.notify_all();
+    }
+
+    /// Blocks until the motor has come to rest on its target, or the stepper is stopped.
+    pub fn wait(&self) {
+        let mut motion = self.motion();
+        while self.is_running() && (motion.position != motion.target || motion.speed > 0.0) {
+            motion = self.shared.changed.wait(motion).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Returns `true` while the timing thread is running.
+    pub fn is_running(&self) -> bool {
+        self.shared.running.load(Ordering::Acquire)
+    }
+
+    /// Switches the four pins to output mode and starts the timing thread. Does nothing if it
+    /// is already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!("Starting {:?}-step stepper on IO {:?}", self.mode, self.pins);
+
+        for &pin in &self.pins {
+            if set_io_mode(pin, 1) != 0 {
+                warn!("Failed to switch IO{} to output mode for the stepper", pin);
+            }
+        }
+
+        self.shared.running.store(true, Ordering::Release);
+
+        let driver = Driver {
+            shared: Arc::clone(&self.shared),
+            pins: self.pins,
+            sequence: self.mode.sequence(),
+            max_speed: self.max_speed,
+            acceleration: self.acceleration,
+        };
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-stepper".into())
+                .spawn(move || driver.run())
+                .expect("Failed to spawn stepper thread"),
+        );
+
+        self
+    }
+
+    /// Stops the timing thread immediately and de-energizes the coils.
+    ///
+    /// The position is kept, but a move in progress is abandoned without deceleration; use
+    /// [`halt`](Stepper::halt) and [`wait`](Stepper::wait) first to stop gently.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.shared.running.store(false, Ordering::Release);
+        self.shared.changed.notify_all();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+
+            let mut motion = self.motion();
+            motion.target = motion.position;
+            motion.speed = 0.0;
+            motion.direction = 0;
+            drop(motion);
+
+            set_io_levels_with_mask(pin_mask(&self.pins), 0);
+            info!("Stepper stopped");
+        }
+
+        self
+    }
+
+    fn motion(&self) -> std::sync::MutexGuard<'_, Motion> {
+        self.shared.motion.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for Stepper {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// State owned by the timing thread.
+struct Driver {
+    shared: Arc<Shared>,
+    pins: [u32; 4],
+    sequence: &'static [u8],
+    max_speed: f32,
+    acceleration: f32,
+}
+
+impl Driver {
+    fn run(self) {
+        let mask = pin_mask(&self.pins);
+        let mut deadline = Instant::now();
+
+        let position = self.shared.motion.lock().unwrap_or_else(|e| e.into_inner()).position;
+        set_io_levels_with_mask(mask, self.levels(position));
+
+        while self.shared.running.load(Ordering::Acquire) {
+            let mut motion = self.shared.motion.lock().unwrap_or_else(|e| e.into_inner());
+
+            if motion.position == motion.target && motion.speed == 0.0 {
+                motion.direction = 0;
+                self.shared.changed.notify_all();
+                let _unused = self.shared.changed.wait(motion).unwrap_or_else(|e| e.into_inner());
+                deadline = Instant::now();
+                continue;
+            }
+
+            let Some(interval) = self.advance(&mut motion) else {
+                continue;
+            };
+            let levels = self.levels(motion.position);
+            drop(motion);
+
+            set_io_levels_with_mask(mask, levels);
+
+            deadline += interval;
+            let now = Instant::now();
+            if deadline > now {
+                thread::sleep(deadline - now);
+            } else {
+                deadline = now;
+            }
+        }
+
+        debug!("Stepper thread exited");
+    }
+
+    /// Updates the speed for one step and takes it. Returns the time until the next step, or
+    /// `None` if the motor came to rest before reversing.
+    fn advance(&self, motion: &mut Motion) -> Option<Duration> {
+        let distance = motion.target - motion.position;
+        if motion.direction == 0 {
+            motion.direction = distance.signum();
+        }
+        // Steps left in the current direction; negative if the target is behind us
+        let to_go = distance * motion.direction;
+
+        if to_go == 0 {
+            // Arrived; the ramp has brought the speed down to a single step's worth
+            motion.speed = 0.0;
+            motion.direction = 0;
+            return None;
+        }
+
+        if self.acceleration == 0.0 {
+            if to_go < 0 {
+                motion.speed = 0.0;
+                motion.direction = 0;
+                return None;
+            }
+            motion.speed = self.max_speed;
+        } else {
+            // The speed reached after one step from rest, and the slowest step we take
+            let min_speed = (2.0 * self.acceleration).sqrt().min(self.max_speed);
+            let stopping = motion.speed * motion.speed / (2.0 * self.acceleration);
+
+            if to_go < 0 || stopping >= to_go as f32 {
+                let slower = (motion.speed * motion.speed - 2.0 * self.acceleration).max(0.0).sqrt();
+                if to_go < 0 && slower < min_speed {
+                    // Stopped past the target; turn around on the next iteration
+                    motion.speed = 0.0;
+                    motion.direction = 0;
+                    return None;
+                }
+                motion.speed = slower.max(min_speed);
+            } else {
+                motion.speed = (motion.speed * motion.speed + 2.0 * self.acceleration)
+                    .sqrt()
+                    .min(self.max_speed);
+            }
+        }
+
+        motion.position += motion.direction;
+        Some(Duration::from_secs_f32(1.0 / motion.speed))
+    }
+
+    /// The IO levels of the four pins for a given position, in the layout of `pin_mask`.
+    fn levels(&self, position: i64) -> u8 {
+        let pattern = self.sequence[position.rem_euclid(self.sequence.len() as i64) as usize];
+        self.pins
+            .iter()
+            .enumerate()
+            .filter(|(coil, _)| pattern & (1 << coil) != 0)
+            .fold(0, |levels, (_, &pin)| levels | (1 << pin))
+    }
+}
+
+fn pin_mask(pins: &[u32; 4]) -> u8 {
+    pins.iter().fold(0, |mask, &pin| mask | (1 << pin))
+}
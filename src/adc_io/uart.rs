@@ -0,0 +1,364 @@
+use super::{io_get_all_channels, set_io_levels_with_mask, set_io_mode};
+use crate::log_level::adc::{debug, info, warn};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The highest baud rate the IO link can keep up with, roughly.
+const MAX_BAUD_RATE: u32 = 9600;
+
+struct RxBuffer {
+    bytes: VecDeque<u8>,
+    capacity: usize,
+    overruns: u64,
+    framing_errors: u64,
+}
+
+struct Shared {
+    buffer: Mutex<RxBuffer>,
+    received: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, RxBuffer> {
+        self.buffer.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A software UART on two IO pins, 8N1 up to 9600 baud.
+///
+/// Frames are timed against the clock rather than by sleeping, so a slow IO link shifts the
+/// sampling points but does not add up over a frame. At 9600 baud a bit lasts 104µs, which
+/// leaves room for a few IO round trips per bit; lower rates are more forgiving. This is good
+/// enough for GPS modules and sensors that send short, checksummed messages, not for
+/// high-throughput links.
+///
+/// Received bytes are collected by a background thread into a ring buffer. When the buffer is
+/// full the oldest bytes are dropped and counted as [overruns](SoftUart::overruns). `SoftUart`
+/// implements [`io::Read`] and [`io::Write`], so it can be wrapped in an [`io::BufReader`] like
+/// a serial port.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::io::{BufRead, BufReader};
+/// use std::time::Duration;
+/// use uptechstar_rs::adc_io::{self, SoftUart};
+///
+/// adc_io::adc_open();
+///
+/// let mut uart = SoftUart::new(6, 7)
+///     .with_baud_rate(9600)
+///     .with_read_timeout(Some(Duration::from_secs(2)));
+/// uart.start();
+///
+/// for line in BufReader::new(uart).lines() {
+///     println!("{}", line.unwrap());
+/// }
+/// ```
+pub struct SoftUart {
+    tx_pin: u32,
+    rx_pin: u32,
+    bit_time: Duration,
+    read_timeout: Option<Duration>,
+    shared: Arc<Shared>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SoftUart {
+    /// Creates a stopped UART at 9600 baud sending on IO `tx` and receiving on IO `rx`, with a
+    /// 256-byte receive buffer. TX is set to its idle high level.
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8` or both pins are the same.
+    pub fn new(tx: u32, rx: u32) -> Self {
+        assert!(tx < 8 && rx < 8, "IO pin index must be in 0..8, got {} and {}", tx, rx);
+        assert_ne!(tx, rx, "SoftUart TX and RX must be different pins");
+
+        let mask = 1u8 << tx;
+        if set_io_mode(tx, 1) != 0 || set_io_levels_with_mask(mask, mask) != 0 {
+            warn!("Failed to set up IO{} as UART TX", tx);
+        }
+
+        SoftUart {
+            tx_pin: tx,
+            rx_pin: rx,
+            bit_time: bit_time(MAX_BAUD_RATE),
+            read_timeout: None,
+            shared: Arc::new(Shared {
+                buffer: Mutex::new(RxBuffer {
+                    bytes: VecDeque::with_capacity(256),
+                    capacity: 256,
+                    overruns: 0,
+                    framing_errors: 0,
+                }),
+                received: Condvar::new(),
+            }),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Sets the baud rate.
+    ///
+    /// # Panics
+    ///
+    /// If `baud_rate` is not in `1..=9600`.
+    pub fn with_baud_rate(mut self, baud_rate: u32) -> Self {
+        assert!(
+            (1..=MAX_BAUD_RATE).contains(&baud_rate),
+            "SoftUart baud rate must be in 1..={}, got {}",
+            MAX_BAUD_RATE,
+            baud_rate
+        );
+        self.bit_time = bit_time(baud_rate);
+        self
+    }
+
+    /// Sets the size of the receive buffer in bytes.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn with_buffer_size(self, capacity: usize) -> Self {
+        assert!(capacity > 0, "SoftUart receive buffer must not be empty");
+        {
+            let mut buffer = self.shared.lock();
+            buffer.capacity = capacity;
+            let len = buffer.bytes.len();
+            buffer.bytes.reserve(capacity.saturating_sub(len));
+        }
+        self
+    }
+
+    /// Sets how long [`io::Read::read`] waits for data before failing with
+    /// [`io::ErrorKind::TimedOut`]. `None`, the default, waits forever.
+    pub fn with_read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Returns the baud rate.
+    pub fn baud_rate(&self) -> u32 {
+        (1.0 / self.bit_time.as_secs_f64()).round() as u32
+    }
+
+    /// Returns the number of received bytes waiting in the buffer.
+    pub fn available(&self) -> usize {
+        self.shared.lock().bytes.len()
+    }
+
+    /// Returns the number of received bytes dropped because the buffer was full.
+    pub fn overruns(&self) -> u64 {
+        self.shared.lock().overruns
+    }
+
+    /// Returns the number of received frames dropped because of a missing stop bit.
+    pub fn framing_errors(&self) -> u64 {
+        self.shared.lock().framing_errors
+    }
+
+    /// Moves up to `buf.len()` received bytes into `buf` without waiting.
+    ///
+    /// Returns:
+    ///   The number of bytes moved.
+    pub fn read_available(&mut self, buf: &mut [u8]) -> usize {
+        let mut buffer = self.shared.lock();
+        let count = buf.len().min(buffer.bytes.len());
+        for (slot, byte) in buf.iter_mut().zip(buffer.bytes.drain(..count)) {
+            *slot = byte;
+        }
+        count
+    }
+
+    /// Sends `data`, blocking for its transmission time.
+    ///
+    /// # Returns
+    ///
+    /// * `i32` - Returns `0` on success, non-zero on failure.
+    pub fn write_bytes(&mut self, data: &[u8]) -> i32 {
+        let mask = 1u8 << self.tx_pin;
+
+        for &byte in data {
+            // Start bit, 8 data bits from the least significant, stop bit.
+            let frame = (byte as u16) << 1 | 1 << 9;
+            let start = Instant::now();
+            for bit in 0..10 {
+                spin_until(start + self.bit_time * bit);
+                let code = set_io_levels_with_mask(mask, if frame >> bit & 1 == 1 { mask } else { 0 });
+                if code != 0 {
+                    return code;
+                }
+            }
+            spin_until(start + self.bit_time * 10);
+        }
+        0
+    }
+
+    /// Returns `true` while the receive thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Switches the RX pin to input mode and starts the receive thread. Does nothing if it is
+    /// already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!("Starting software UART RX on IO{} at {} baud", self.rx_pin, self.baud_rate());
+
+        if set_io_mode(self.rx_pin, 0) != 0 {
+            warn!("Failed to switch IO{} to input mode for UART RX", self.rx_pin);
+        }
+
+        self.running.store(true, Ordering::Release);
+
+        let mask = 1u8 << self.rx_pin;
+        let bit_time = self.bit_time;
+        let shared = Arc::clone(&self.shared);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-uart-rx".into())
+                .spawn(move || {
+                    let high = || io_get_all_channels() & mask != 0;
+
+                    while running.load(Ordering::Acquire) {
+                        if high() {
+                            continue;
+                        }
+
+                        // Sample every bit in its middle, counting from the falling edge of the
+                        // start bit.
+                        let start = Instant::now();
+                        let mut byte = 0u8;
+                        for bit in 0..8 {
+                            spin_until(start + bit_time.mul_f32(1.5 + bit as f32));
+                            if high() {
+                                byte |= 1 << bit;
+                            }
+                        }
+                        spin_until(start + bit_time.mul_f32(9.5));
+                        let stop = high();
+
+                        let mut buffer = shared.lock();
+                        if stop {
+                            if buffer.bytes.len() == buffer.capacity {
+                                buffer.bytes.pop_front();
+                                buffer.overruns += 1;
+                            }
+                            buffer.bytes.push_back(byte);
+                            drop(buffer);
+                            shared.received.notify_all();
+                        } else {
+                            buffer.framing_errors += 1;
+                            drop(buffer);
+                            // A break or noise; wait for the line to go idle before the next
+                            // start bit.
+                            while running.load(Ordering::Acquire) && !high() {
+                                thread::sleep(bit_time);
+                            }
+                        }
+                    }
+
+                    debug!("UART RX thread exited");
+                })
+                .expect("Failed to spawn UART RX thread"),
+        );
+
+        self
+    }
+
+    /// Stops the receive thread, keeping the buffered bytes.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Software UART RX on IO{} stopped", self.rx_pin);
+        }
+
+        self
+    }
+}
+
+impl io::Read for SoftUart {
+    /// Waits until at least one byte was received, up to the
+    /// [read timeout](SoftUart::with_read_timeout), and moves the received bytes into `buf`.
+    ///
+    /// Fails with [`io::ErrorKind::NotConnected`] if the receive thread is not running and the
+    /// buffer is empty.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        let mut buffer = self.shared.lock();
+        while buffer.bytes.is_empty() {
+            if !self.is_running() {
+                return Err(io::Error::new(io::ErrorKind::NotConnected, "SoftUart receiver is not running"));
+            }
+            buffer = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(io::Error::new(io::ErrorKind::TimedOut, "SoftUart read timed out"));
+                    }
+                    self.shared
+                        .received
+                        .wait_timeout(buffer, deadline - now)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0
+                }
+                None => self.shared.received.wait(buffer).unwrap_or_else(|e| e.into_inner()),
+            };
+        }
+        drop(buffer);
+
+        Ok(self.read_available(buf))
+    }
+}
+
+impl io::Write for SoftUart {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.write_bytes(buf) {
+            0 => Ok(buf.len()),
+            code => Err(io::Error::other(format!("Failed to drive UART TX on IO{}, status {}", self.tx_pin, code))),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for SoftUart {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn bit_time(baud_rate: u32) -> Duration {
+    Duration::from_secs_f64(1.0 / baud_rate as f64)
+}
+
+fn spin_until(deadline: Instant) {
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
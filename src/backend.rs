@@ -0,0 +1,259 @@
+use crate::extern_lib::symbol_or_return;
+use crate::stats;
+use once_cell::sync::Lazy;
+use std::sync::{Arc, RwLock};
+
+/// Hardware backend used by the [`adc_io`](crate::adc_io) and [`mpu`](crate::mpu) wrappers.
+///
+/// Every public ADC, IO and MPU function of this crate is routed through the currently
+/// installed backend. By default this is [`FfiBackend`], which forwards to `libuptech.so`.
+/// Installing another implementation with [`set_backend`] lets the same application code run
+/// against recorded data (see [`replay`](crate::replay)) or a simulated board.
+///
+/// The methods mirror the C functions one to one, including their status-code conventions,
+/// so an implementation only has to reproduce the raw library behaviour. Logging and argument
+/// handling stay in the safe wrapper layer.
+pub trait Backend: Send + Sync {
+    /// Mirrors `adc_io_open`. Returns the open count, or `-1` on failure.
+    fn adc_io_open(&self) -> i32;
+
+    /// Mirrors `adc_io_close`. Returns `0` on success, `-1` on failure.
+    fn adc_io_close(&self) -> i32;
+
+    /// Mirrors `ADC_GetAll`. Fills all 10 channels and returns `0` on success.
+    fn adc_get_all(&self, adc_data: &mut [i32; 10]) -> i32;
+
+    /// Mirrors `adc_io_InputGetAll`. Returns the input level bitmask.
+    fn io_get_all(&self) -> u8;
+
+    /// Mirrors `adc_io_SetAll`. Returns `0` on success.
+    fn io_set_all(&self, levels: u32) -> i32;
+
+    /// Mirrors `adc_io_Set`. Returns `0` on success, `-1` on failure.
+    fn io_flip(&self, index: u32) -> i32;
+
+    /// Mirrors `adc_io_ModeGetAll`. Writes the mode bitmask and returns `0` on success.
+    fn io_mode_get_all(&self, modes: &mut u8) -> i32;
+
+    /// Mirrors `adc_io_ModeSet`. Returns `0` on success.
+    fn io_mode_set(&self, index: u32, mode: i32) -> i32;
+
+    /// Mirrors `mpu6500_dmp_init`. Returns `0` on success.
+    fn mpu_init(&self) -> i32;
+
+    /// Mirrors `mpu6500_Get_Accel`. Returns `0` on success.
+    fn mpu_get_accel(&self, accel_data: &mut [f32; 3]) -> i32;
+
+    /// Mirrors `mpu6500_Get_Gyro`. Returns `0` on success.
+    fn mpu_get_gyro(&self, gyro_data: &mut [f32; 3]) -> i32;
+
+    /// Mirrors `mpu6500_Get_Attitude`. Returns `0` on success.
+    fn mpu_get_attitude(&self, attitude_data: &mut [f32; 3]) -> i32;
+
+    /// Mirrors `mpu_get_gyro_fsr`. Returns `0` on success.
+    fn mpu_get_gyro_fsr(&self, fsr: &mut u16) -> i32;
+
+    /// Mirrors `mpu_get_accel_fsr`. Returns `0` on success.
+    fn mpu_get_accel_fsr(&self, fsr: &mut u8) -> i32;
+
+    /// Mirrors `mpu_set_gyro_fsr`, which takes an `unsigned short`. Returns `0` on success, and
+    /// `-1` if `fsr` does not fit.
+    fn mpu_set_gyro_fsr(&self, fsr: u32) -> i32;
+
+    /// Mirrors `mpu_set_accel_fsr`, which takes an `unsigned char`. Returns `0` on success, and
+    /// `-1` if `fsr` does not fit.
+    fn mpu_set_accel_fsr(&self, fsr: i32) -> i32;
+}
+
+/// The default backend, forwarding every call to the embedded `libuptech.so`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FfiBackend;
+
+impl Backend for FfiBackend {
+    fn adc_io_open(&self) -> i32 {
+        unsafe {
+            let call = stats::start("adc_io_open");
+            let adc_io_open = symbol_or_return!(adc_io_open: unsafe extern "C" fn() -> i32, -1);
+
+            call.finish(adc_io_open())
+        }
+    }
+
+    fn adc_io_close(&self) -> i32 {
+        unsafe {
+            let call = stats::start("adc_io_close");
+            let adc_io_close = symbol_or_return!(adc_io_close: unsafe extern "C" fn() -> i32, -1);
+
+            call.finish(adc_io_close())
+        }
+    }
+
+    fn adc_get_all(&self, adc_data: &mut [i32; 10]) -> i32 {
+        unsafe {
+            let call = stats::start("ADC_GetAll");
+            let adc_get_all = symbol_or_return!(ADC_GetAll: unsafe extern "C" fn(*mut i32) -> i32, -1);
+
+            call.finish(adc_get_all(adc_data.as_mut_ptr()))
+        }
+    }
+
+    fn io_get_all(&self) -> u8 {
+        unsafe {
+            let call = stats::start("adc_io_InputGetAll");
+            let adc_io_input_get_all = symbol_or_return!(adc_io_InputGetAll: unsafe extern "C" fn() -> u8, 0);
+
+            let levels = adc_io_input_get_all();
+            call.done();
+
+            levels
+        }
+    }
+
+    fn io_set_all(&self, levels: u32) -> i32 {
+        unsafe {
+            let call = stats::start("adc_io_SetAll");
+            let adc_io_set_all = symbol_or_return!(adc_io_SetAll: unsafe extern "C" fn(u32) -> i32, -1);
+
+            call.finish(adc_io_set_all(levels))
+        }
+    }
+
+    fn io_flip(&self, index: u32) -> i32 {
+        unsafe {
+            let call = stats::start("adc_io_Set");
+            let adc_io_set = symbol_or_return!(adc_io_Set: unsafe extern "C" fn(u32) -> i32, -1);
+
+            call.finish(adc_io_set(index))
+        }
+    }
+
+    fn io_mode_get_all(&self, modes: &mut u8) -> i32 {
+        unsafe {
+            let call = stats::start("adc_io_ModeGetAll");
+            let adc_io_mode_get_all = symbol_or_return!(adc_io_ModeGetAll: unsafe extern "C" fn(*mut u8) -> i32, -1);
+
+            call.finish(adc_io_mode_get_all(modes))
+        }
+    }
+
+    fn io_mode_set(&self, index: u32, mode: i32) -> i32 {
+        unsafe {
+            let call = stats::start("adc_io_ModeSet");
+            let adc_io_mode_set = symbol_or_return!(adc_io_ModeSet: unsafe extern "C" fn(u32, i32) -> i32, -1);
+
+            call.finish(adc_io_mode_set(index, mode))
+        }
+    }
+
+    fn mpu_init(&self) -> i32 {
+        unsafe {
+            let call = stats::start("mpu6500_dmp_init");
+            let mpu6500_dmp_init = symbol_or_return!(mpu6500_dmp_init: unsafe extern "C" fn() -> i32, -1);
+
+            call.finish(mpu6500_dmp_init())
+        }
+    }
+
+    fn mpu_get_accel(&self, accel_data: &mut [f32; 3]) -> i32 {
+        unsafe {
+            let call = stats::start("mpu6500_Get_Accel");
+            let mpu6500_get_accel = symbol_or_return!(mpu6500_Get_Accel: unsafe extern "C" fn(*mut f32) -> i32, -1);
+
+            call.finish(mpu6500_get_accel(accel_data.as_mut_ptr()))
+        }
+    }
+
+    fn mpu_get_gyro(&self, gyro_data: &mut [f32; 3]) -> i32 {
+        unsafe {
+            let call = stats::start("mpu6500_Get_Gyro");
+            let mpu6500_get_gyro = symbol_or_return!(mpu6500_Get_Gyro: unsafe extern "C" fn(*mut f32) -> i32, -1);
+
+            call.finish(mpu6500_get_gyro(gyro_data.as_mut_ptr()))
+        }
+    }
+
+    fn mpu_get_attitude(&self, attitude_data: &mut [f32; 3]) -> i32 {
+        unsafe {
+            let call = stats::start("mpu6500_Get_Attitude");
+            let mpu6500_get_attitude = symbol_or_return!(mpu6500_Get_Attitude: unsafe extern "C" fn(*mut f32) -> i32, -1);
+
+            call.finish(mpu6500_get_attitude(attitude_data.as_mut_ptr()))
+        }
+    }
+
+    fn mpu_get_gyro_fsr(&self, fsr: &mut u16) -> i32 {
+        unsafe {
+            let call = stats::start("mpu_get_gyro_fsr");
+            let mpu_get_gyro_fsr = symbol_or_return!(mpu_get_gyro_fsr: unsafe extern "C" fn(*mut u16) -> i32, -1);
+
+            call.finish(mpu_get_gyro_fsr(fsr))
+        }
+    }
+
+    fn mpu_get_accel_fsr(&self, fsr: &mut u8) -> i32 {
+        unsafe {
+            let call = stats::start("mpu_get_accel_fsr");
+            let mpu_get_accel_fsr = symbol_or_return!(mpu_get_accel_fsr: unsafe extern "C" fn(*mut u8) -> i32, -1);
+
+            call.finish(mpu_get_accel_fsr(fsr))
+        }
+    }
+
+    fn mpu_set_gyro_fsr(&self, fsr: u32) -> i32 {
+        let Ok(fsr) = u16::try_from(fsr) else {
+            return -1;
+        };
+        unsafe {
+            let call = stats::start("mpu_set_gyro_fsr");
+            let mpu_set_gyro_fsr = symbol_or_return!(mpu_set_gyro_fsr: unsafe extern "C" fn(u16) -> i32, -1);
+
+            call.finish(mpu_set_gyro_fsr(fsr))
+        }
+    }
+
+    fn mpu_set_accel_fsr(&self, fsr: i32) -> i32 {
+        let Ok(fsr) = u8::try_from(fsr) else {
+            return -1;
+        };
+        unsafe {
+            let call = stats::start("mpu_set_accel_fsr");
+            let mpu_set_accel_fsr = symbol_or_return!(mpu_set_accel_fsr: unsafe extern "C" fn(u8) -> i32, -1);
+
+            call.finish(mpu_set_accel_fsr(fsr))
+        }
+    }
+}
+
+/// The process-wide backend used by the safe wrappers.
+static BACKEND: Lazy<RwLock<Arc<dyn Backend>>> = Lazy::new(|| RwLock::new(Arc::new(FfiBackend)));
+
+/// Returns the currently installed backend.
+pub fn current() -> Arc<dyn Backend> {
+    BACKEND.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Installs a new backend for all subsequent ADC, IO and MPU calls.
+///
+/// # Returns
+///
+/// * `Arc<dyn Backend>` - The previously installed backend, so it can be restored later.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use uptechstar_rs::backend::{set_backend, FfiBackend};
+///
+/// let previous = set_backend(Arc::new(FfiBackend));
+/// // ...
+/// set_backend(previous);
+/// ```
+pub fn set_backend(backend: Arc<dyn Backend>) -> Arc<dyn Backend> {
+    let mut guard = BACKEND.write().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *guard, backend)
+}
+
+/// Restores the default [`FfiBackend`].
+pub fn reset_backend() {
+    set_backend(Arc::new(FfiBackend));
+}
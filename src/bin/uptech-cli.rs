@@ -0,0 +1,340 @@
+//! `uptech-cli` pokes the board's hardware from the shell.
+//!
+//! ```text
+//! uptech-cli adc read --watch
+//! uptech-cli io set 3 high
+//! uptech-cli io mode 3 output
+//! uptech-cli mpu watch --rate 20
+//! uptech-cli lcd test
+//! uptech-cli led set all ff8000
+//! uptech-cli selftest
+//! ```
+//!
+//! Every command exits with status `1` if the hardware reports a failure.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+use uptechstar_rs::adc_io;
+use uptechstar_rs::diagnostics::SelfTest;
+use uptechstar_rs::display::{Color, FontSize, Screen, ScreenDirection};
+use uptechstar_rs::mpu;
+
+#[derive(Parser)]
+#[command(name = "uptech-cli", version, about = "Inspect and drive the board's hardware")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Read the ADC channels.
+    #[command(subcommand)]
+    Adc(AdcCommand),
+    /// Read and drive the IO pins.
+    #[command(subcommand)]
+    Io(IoCommand),
+    /// Read the MPU6500.
+    #[command(subcommand)]
+    Mpu(MpuCommand),
+    /// Exercise the LCD.
+    #[command(subcommand)]
+    Lcd(LcdCommand),
+    /// Set the LEDs.
+    #[command(subcommand)]
+    Led(LedCommand),
+    /// Check the ADC, IO, MPU, LCD and LEDs.
+    Selftest {
+        /// An IO output looped back to an input by a jumper, as `OUT:IN`. Repeatable.
+        #[arg(short, long, value_parser = parse_loopback)]
+        loopback: Vec<(u32, u32)>,
+        /// Skip the built-in MPU6500 self-test.
+        #[arg(long)]
+        no_mpu_self_test: bool,
+        /// Leave the LCD alone.
+        #[arg(long)]
+        no_screen: bool,
+        /// Leave the LEDs alone.
+        #[arg(long)]
+        no_leds: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum AdcCommand {
+    /// Print all 10 channels.
+    Read {
+        /// Keep printing until interrupted.
+        #[arg(short, long)]
+        watch: bool,
+        /// Readings per second with --watch.
+        #[arg(short, long, default_value_t = 10.0)]
+        rate: f32,
+    },
+}
+
+#[derive(Subcommand)]
+enum IoCommand {
+    /// Print the level and mode of every pin.
+    Get,
+    /// Drive a pin high or low.
+    Set {
+        /// Pin index, 0-7.
+        #[arg(value_parser = clap::value_parser!(u32).range(0..8))]
+        pin: u32,
+        level: Level,
+    },
+    /// Switch a pin between input and output.
+    Mode {
+        /// Pin index, 0-7.
+        #[arg(value_parser = clap::value_parser!(u32).range(0..8))]
+        pin: u32,
+        mode: PinMode,
+    },
+}
+
+#[derive(Subcommand)]
+enum MpuCommand {
+    /// Print acceleration, angular rate and attitude until interrupted.
+    Watch {
+        /// Readings per second.
+        #[arg(short, long, default_value_t = 10.0)]
+        rate: f32,
+    },
+}
+
+#[derive(Subcommand)]
+enum LcdCommand {
+    /// Show color fills, a border and text.
+    Test {
+        #[arg(short, long, value_enum, default_value_t = Direction::Horizontal)]
+        direction: Direction,
+    },
+}
+
+#[derive(Subcommand)]
+enum LedCommand {
+    /// Set one or both LEDs to a color.
+    Set {
+        /// LED index, 0 or 1, or `all`.
+        led: String,
+        /// Color as `RRGGBB` hex, or one of red, green, blue, white, yellow, cyan, magenta and
+        /// off.
+        color: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Level {
+    #[value(alias = "1")]
+    High,
+    #[value(alias = "0")]
+    Low,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PinMode {
+    Input,
+    Output,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Direction {
+    Horizontal,
+    Vertical,
+}
+
+/// Fails with `message` if `code` is a failure status.
+fn check(code: i32, message: &str) -> Result<(), String> {
+    if code == 0 { Ok(()) } else { Err(format!("{} (status {})", message, code)) }
+}
+
+fn period(rate: f32) -> Result<Duration, String> {
+    if rate.is_finite() && rate > 0.0 {
+        Ok(Duration::from_secs_f32(1.0 / rate))
+    } else {
+        Err(format!("Rate must be positive, got {}", rate))
+    }
+}
+
+fn parse_color(name: &str) -> Result<u32, String> {
+    let color = match name.to_ascii_lowercase().as_str() {
+        "red" => Color::RED,
+        "green" => Color::GREEN,
+        "blue" => Color::BLUE,
+        "white" => Color::WHITE,
+        "yellow" => Color::YELLOW,
+        "cyan" => Color::CYAN,
+        "magenta" => Color::MAGENTA,
+        "off" | "black" => Color::BLACK,
+        hex => {
+            let hex = hex.trim_start_matches('#').trim_start_matches("0x");
+            if hex.len() != 6 {
+                return Err(format!("Unknown color '{}'", name));
+            }
+            u32::from_str_radix(hex, 16).map_err(|_| format!("Unknown color '{}'", name))?
+        }
+    };
+    Ok(color)
+}
+
+fn parse_loopback(pair: &str) -> Result<(u32, u32), String> {
+    let (output, input) = pair.split_once(':').ok_or("expected OUT:IN, e.g. 0:1")?;
+    let pin = |text: &str| text.parse::<u32>().ok().filter(|&pin| pin < 8);
+    match (pin(output), pin(input)) {
+        (Some(output), Some(input)) if output != input => Ok((output, input)),
+        _ => Err("pins must be two different indices in 0-7".to_string()),
+    }
+}
+
+fn adc(command: AdcCommand) -> Result<(), String> {
+    let AdcCommand::Read { watch, rate } = command;
+    let period = period(rate)?;
+    check(adc_io::adc_open(), "Failed to open the ADC")?;
+
+    loop {
+        let frame = adc_io::adc_get_frame()?;
+        let values: Vec<String> = frame.0.iter().map(|value| format!("{:>4}", value)).collect();
+        println!("{}", values.join(" "));
+        if !watch {
+            return Ok(());
+        }
+        thread::sleep(period);
+    }
+}
+
+fn io(command: IoCommand) -> Result<(), String> {
+    check(adc_io::adc_open(), "Failed to open the ADC and IO controller")?;
+
+    match command {
+        IoCommand::Get => {
+            let levels = adc_io::io_get_all_channels();
+            let modes = adc_io::get_all_io_mode();
+            println!("pin  level  mode");
+            for pin in 0..8 {
+                let mode = if modes >> pin & 1 == 1 { "output" } else { "input" };
+                println!("{:>3}  {:>5}  {}", pin, levels >> pin & 1, mode);
+            }
+            Ok(())
+        }
+        IoCommand::Set { pin, level } => {
+            let mask = 1u8 << pin;
+            let value = match level {
+                Level::High => mask,
+                Level::Low => 0,
+            };
+            check(adc_io::set_io_levels_with_mask(mask, value), "Failed to set the IO level")
+        }
+        IoCommand::Mode { pin, mode } => {
+            let mode = match mode {
+                PinMode::Input => 0,
+                PinMode::Output => 1,
+            };
+            check(adc_io::set_io_mode(pin, mode), "Failed to set the IO mode")
+        }
+    }
+}
+
+fn mpu(command: MpuCommand) -> Result<(), String> {
+    let MpuCommand::Watch { rate } = command;
+    let period = period(rate)?;
+    check(mpu::mpu6500_open(), "Failed to open the MPU6500")?;
+
+    loop {
+        let sample = mpu::mpu6500_get_sample().map_err(|code| format!("Failed to read the MPU6500 (status {})", code))?;
+        println!(
+            "accel {:>7.3} {:>7.3} {:>7.3} g  gyro {:>8.2} {:>8.2} {:>8.2} °/s  pitch {:>7.2} roll {:>7.2} yaw {:>7.2}",
+            sample.accel[0],
+            sample.accel[1],
+            sample.accel[2],
+            sample.gyro[0],
+            sample.gyro[1],
+            sample.gyro[2],
+            sample.attitude[0],
+            sample.attitude[1],
+            sample.attitude[2]
+        );
+        thread::sleep(period);
+    }
+}
+
+fn lcd(command: LcdCommand) -> Result<(), String> {
+    let LcdCommand::Test { direction } = command;
+    let direction = match direction {
+        Direction::Horizontal => ScreenDirection::Horizontal,
+        Direction::Vertical => ScreenDirection::Vertical,
+    };
+    let (width, height) = (direction.width(), direction.height());
+    let mut screen = Screen::new(Some(direction));
+
+    for color in [Color::RED, Color::GREEN, Color::BLUE, Color::WHITE] {
+        screen.fill_screen(color).refresh();
+        thread::sleep(Duration::from_millis(500));
+    }
+
+    screen
+        .fill_screen(Color::BLACK)
+        .draw_frame(0, 0, width - 1, height - 1, Color::WHITE)
+        .draw_line(0, 0, width - 1, height - 1, Color::GRAY)
+        .draw_line(0, height - 1, width - 1, 0, Color::GRAY)
+        .set_font_size(FontSize::Font8x12)
+        .set_fore_color(Color::YELLOW)
+        .put_string(4, 4, "uptech-cli")
+        .refresh();
+    println!("LCD test pattern shown; check for dead pixels and color order");
+    Ok(())
+}
+
+fn led(command: LedCommand) -> Result<(), String> {
+    let LedCommand::Set { led, color } = command;
+    let color = parse_color(&color)?;
+    let mut screen = Screen::new(None);
+
+    match led.as_str() {
+        "all" => screen.set_all_leds_same(color),
+        "0" => screen.set_led_0(color),
+        "1" => screen.set_led_1(color),
+        other => return Err(format!("Unknown LED '{}', expected 0, 1 or all", other)),
+    };
+    Ok(())
+}
+
+fn selftest(loopbacks: Vec<(u32, u32)>, mpu_self_test: bool, screen: bool, leds: bool) -> Result<(), String> {
+    let mut test = SelfTest::new()
+        .with_mpu_self_test(mpu_self_test)
+        .with_screen(screen.then_some(ScreenDirection::Horizontal))
+        .with_leds(leds);
+    for (output, input) in loopbacks {
+        test = test.with_loopback(output, input);
+    }
+
+    let report = test.run();
+    println!("{}", report);
+    if report.passed() { Ok(()) } else { Err("Self-test failed".to_string()) }
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Adc(command) => adc(command),
+        Command::Io(command) => io(command),
+        Command::Mpu(command) => mpu(command),
+        Command::Lcd(command) => lcd(command),
+        Command::Led(command) => led(command),
+        Command::Selftest {
+            loopback,
+            no_mpu_self_test,
+            no_screen,
+            no_leds,
+        } => selftest(loopback, !no_mpu_self_test, !no_screen, !no_leds),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
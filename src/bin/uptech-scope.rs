@@ -0,0 +1,394 @@
+//! `uptech-scope` plots live board telemetry in the terminal.
+//!
+//! It listens for the UDP frames of a `telemetry::udp::Broadcaster`, or connects to a
+//! `telemetry::websocket::WebSocketServer`, and draws the selected channels over a sliding
+//! time window:
+//!
+//! ```text
+//! uptech-scope --channels adc0,adc3,yaw --window 5
+//! uptech-scope --ws ws://192.168.1.20:9001 --channels accel_x,accel_y,accel_z
+//! ```
+//!
+//! `q` or `Esc` quits, `Space` pauses the plot and `c` clears it.
+
+use clap::Parser;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Axis, Block, Chart, Dataset, GraphType, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::Value;
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+use uptechstar_rs::adc_io::{AdcFrame, IoFrame};
+use uptechstar_rs::mpu::MpuSample;
+use uptechstar_rs::telemetry::TelemetryFrame;
+use uptechstar_rs::telemetry::udp::{self, DEFAULT_PORT};
+
+const COLORS: [Color; 7] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Green,
+    Color::Red,
+    Color::Blue,
+    Color::White,
+];
+
+#[derive(Parser)]
+#[command(name = "uptech-scope", version, about = "Plot live board telemetry in the terminal")]
+struct Args {
+    /// Listen for UDP telemetry frames on this port.
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    udp: u16,
+    /// Connect to a WebSocket telemetry server instead, e.g. ws://192.168.1.20:9001.
+    #[arg(long)]
+    ws: Option<String>,
+    /// Channels to plot: adc0-adc9, io0-io7, accel_x/y/z, gyro_x/y/z, pitch, roll, yaw or
+    /// user0, user1, ...
+    #[arg(short, long, value_delimiter = ',', default_value = "adc0")]
+    channels: Vec<Channel>,
+    /// Seconds of history to show.
+    #[arg(short, long, default_value_t = 10.0)]
+    window: f64,
+}
+
+/// A plottable value of a [`TelemetryFrame`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Channel {
+    Adc(usize),
+    Io(u8),
+    Accel(usize),
+    Gyro(usize),
+    Attitude(usize),
+    User(usize),
+}
+
+impl Channel {
+    fn value(&self, frame: &TelemetryFrame) -> Option<f64> {
+        match *self {
+            Channel::Adc(index) => frame.adc.map(|adc| adc.0[index] as f64),
+            Channel::Io(pin) => frame.io.map(|levels| u8::from(levels.level(pin as u32)) as f64),
+            Channel::Accel(axis) => frame.mpu.map(|mpu| mpu.accel[axis] as f64),
+            Channel::Gyro(axis) => frame.mpu.map(|mpu| mpu.gyro[axis] as f64),
+            Channel::Attitude(axis) => frame.mpu.map(|mpu| mpu.attitude[axis] as f64),
+            Channel::User(index) => frame.user.get(index).map(|&value| value as f64),
+        }
+    }
+}
+
+impl FromStr for Channel {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let axis = |suffix: &str| ["x", "y", "z"].iter().position(|&a| a == suffix);
+        let index = |digits: &str, count: usize| digits.parse::<usize>().ok().filter(|&index| index < count);
+
+        let channel = match name {
+            "pitch" => Some(Channel::Attitude(0)),
+            "roll" => Some(Channel::Attitude(1)),
+            "yaw" => Some(Channel::Attitude(2)),
+            _ => {
+                if let Some(digits) = name.strip_prefix("adc") {
+                    index(digits, 10).map(Channel::Adc)
+                } else if let Some(digits) = name.strip_prefix("io") {
+                    index(digits, 8).map(|pin| Channel::Io(pin as u8))
+                } else if let Some(suffix) = name.strip_prefix("accel_") {
+                    axis(suffix).map(Channel::Accel)
+                } else if let Some(suffix) = name.strip_prefix("gyro_") {
+                    axis(suffix).map(Channel::Gyro)
+                } else if let Some(digits) = name.strip_prefix("user") {
+                    index(digits, 255).map(Channel::User)
+                } else {
+                    None
+                }
+            }
+        };
+        channel.ok_or_else(|| format!("unknown channel '{}'", name))
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const AXES: [&str; 3] = ["x", "y", "z"];
+        match *self {
+            Channel::Adc(index) => write!(f, "adc{}", index),
+            Channel::Io(pin) => write!(f, "io{}", pin),
+            Channel::Accel(axis) => write!(f, "accel_{}", AXES[axis]),
+            Channel::Gyro(axis) => write!(f, "gyro_{}", AXES[axis]),
+            Channel::Attitude(axis) => f.write_str(["pitch", "roll", "yaw"][axis]),
+            Channel::User(index) => write!(f, "user{}", index),
+        }
+    }
+}
+
+enum Update {
+    Frame(TelemetryFrame),
+    Failed(String),
+}
+
+/// Receives UDP frames until the receiver fails.
+fn listen_udp(port: u16, updates: Sender<Update>) {
+    let mut receiver = match udp::Receiver::bind(port).and_then(|r| r.with_timeout(Some(Duration::from_millis(500)))) {
+        Ok(receiver) => receiver,
+        Err(e) => {
+            let _ = updates.send(Update::Failed(format!("Cannot listen on UDP port {}: {}", port, e)));
+            return;
+        }
+    };
+
+    loop {
+        match receiver.recv() {
+            Ok((frame, _)) => {
+                if updates.send(Update::Frame(frame)).is_err() {
+                    return;
+                }
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => {
+                let _ = updates.send(Update::Failed(format!("UDP receive failed: {}", e)));
+                return;
+            }
+        }
+    }
+}
+
+/// Reads JSON frames from a WebSocket server until the connection closes.
+fn listen_ws(url: &str, updates: Sender<Update>) {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let url = format!("{}{}encoding=json", url, separator);
+
+    let mut socket = match tungstenite::connect(url.as_str()) {
+        Ok((socket, _)) => socket,
+        Err(e) => {
+            let _ = updates.send(Update::Failed(format!("Cannot connect to {}: {}", url, e)));
+            return;
+        }
+    };
+
+    loop {
+        let message = match socket.read() {
+            Ok(message) => message,
+            Err(e) => {
+                let _ = updates.send(Update::Failed(format!("WebSocket closed: {}", e)));
+                return;
+            }
+        };
+        let Ok(text) = message.to_text() else {
+            continue;
+        };
+        if let Some(frame) = serde_json::from_str(text).ok().as_ref().and_then(frame_from_json)
+            && updates.send(Update::Frame(frame)).is_err()
+        {
+            return;
+        }
+    }
+}
+
+/// Converts a WebSocket state object back into a frame.
+fn frame_from_json(value: &Value) -> Option<TelemetryFrame> {
+    fn floats<const N: usize>(value: &Value) -> Option<[f32; N]> {
+        let values = value.as_array()?;
+        if values.len() != N {
+            return None;
+        }
+        let mut array = [0.0; N];
+        for (slot, value) in array.iter_mut().zip(values) {
+            *slot = value.as_f64()? as f32;
+        }
+        Some(array)
+    }
+
+    let mpu = match &value["mpu"] {
+        Value::Null => None,
+        mpu => Some(MpuSample {
+            accel: floats(&mpu["accel"])?,
+            gyro: floats(&mpu["gyro"])?,
+            attitude: floats(&mpu["attitude"])?,
+        }),
+    };
+    Some(TelemetryFrame {
+        sequence: value["sequence"].as_u64()?,
+        timestamp: Duration::from_millis(value["timestamp_ms"].as_u64()?),
+        adc: floats::<10>(&value["adc"]).map(|adc| AdcFrame(adc.map(|v| v as i32))),
+        io: value["io"].as_u64().map(|levels| IoFrame(levels as u8)),
+        mpu,
+        user: Vec::new(),
+    })
+}
+
+struct Scope {
+    channels: Vec<Channel>,
+    window: f64,
+    series: Vec<Vec<(f64, f64)>>,
+    latest: Option<TelemetryFrame>,
+    frames: u64,
+    lost: u64,
+    paused: bool,
+    status: Option<String>,
+}
+
+impl Scope {
+    fn new(channels: Vec<Channel>, window: f64) -> Self {
+        Scope {
+            series: vec![Vec::new(); channels.len()],
+            channels,
+            window,
+            latest: None,
+            frames: 0,
+            lost: 0,
+            paused: false,
+            status: None,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.series.iter_mut().for_each(Vec::clear);
+    }
+
+    fn push(&mut self, frame: TelemetryFrame) {
+        let now = frame.timestamp.as_secs_f64();
+        if let Some(latest) = &self.latest {
+            if frame.sequence > latest.sequence + 1 {
+                self.lost += frame.sequence - latest.sequence - 1;
+            }
+            // The producer restarted; its clock starts over.
+            if now < latest.timestamp.as_secs_f64() {
+                self.clear();
+            }
+        }
+        self.frames += 1;
+
+        if !self.paused {
+            for (channel, points) in self.channels.iter().zip(&mut self.series) {
+                if let Some(value) = channel.value(&frame) {
+                    points.push((now, value));
+                }
+                let stale = points.partition_point(|&(t, _)| t < now - self.window);
+                points.drain(..stale);
+            }
+        }
+        self.latest = Some(frame);
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [plot, footer] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+
+        let end = self
+            .series
+            .iter()
+            .filter_map(|points| points.last().map(|&(t, _)| t))
+            .fold(self.window, f64::max);
+        let start = end - self.window;
+
+        let (mut low, mut high) = self
+            .series
+            .iter()
+            .flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &(_, v)| (low.min(v), high.max(v)));
+        if !low.is_finite() {
+            (low, high) = (0.0, 1.0);
+        }
+        let margin = ((high - low) * 0.05).max(0.5);
+        let (low, high) = (low - margin, high + margin);
+
+        let datasets = self
+            .channels
+            .iter()
+            .zip(&self.series)
+            .enumerate()
+            .map(|(index, (channel, points))| {
+                Dataset::default()
+                    .name(channel.to_string())
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::default().fg(COLORS[index % COLORS.len()]))
+                    .data(points)
+            })
+            .collect();
+
+        let title = if self.paused { " uptech-scope (paused) " } else { " uptech-scope " };
+        let chart = Chart::new(datasets)
+            .block(Block::bordered().title(title))
+            .x_axis(
+                Axis::default()
+                    .title("s")
+                    .bounds([start, end])
+                    .labels([format!("{:.1}", start), format!("{:.1}", end)]),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([low, high])
+                    .labels([format!("{:.2}", low), format!("{:.2}", (low + high) / 2.0), format!("{:.2}", high)]),
+            );
+        frame.render_widget(chart, plot);
+
+        let mut spans = Vec::new();
+        match (&self.status, &self.latest) {
+            (Some(status), _) => spans.push(Span::styled(status.clone(), Style::default().fg(Color::Red))),
+            (None, None) => spans.push(Span::raw("Waiting for telemetry...")),
+            (None, Some(latest)) => {
+                for (index, channel) in self.channels.iter().enumerate() {
+                    let value = channel.value(latest).map_or("-".to_string(), |v| format!("{:.3}", v));
+                    spans.push(Span::styled(
+                        format!("{}={}  ", channel, value),
+                        Style::default().fg(COLORS[index % COLORS.len()]),
+                    ));
+                }
+                spans.push(Span::raw(format!("#{} frames {} lost {}", latest.sequence, self.frames, self.lost)));
+            }
+        }
+        let help = Line::from("q quit · space pause · c clear");
+        frame.render_widget(Paragraph::new(vec![Line::from(spans), help]).block(Block::bordered()), footer);
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, mut scope: Scope, updates: Receiver<Update>) -> io::Result<()> {
+    loop {
+        for update in updates.try_iter() {
+            match update {
+                Update::Frame(frame) => scope.push(frame),
+                Update::Failed(status) => scope.status = Some(status),
+            }
+        }
+
+        terminal.draw(|frame| scope.draw(frame))?;
+
+        if event::poll(Duration::from_millis(33))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char(' ') => scope.paused = !scope.paused,
+                KeyCode::Char('c') => scope.clear(),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    if !(args.window.is_finite() && args.window > 0.0) {
+        eprintln!("--window must be a positive number of seconds");
+        std::process::exit(2);
+    }
+
+    let (sender, updates) = mpsc::channel();
+    match args.ws.clone() {
+        Some(url) => thread::spawn(move || listen_ws(&url, sender)),
+        None => thread::spawn(move || listen_udp(args.udp, sender)),
+    };
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, Scope::new(args.channels, args.window), updates);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,620 @@
+//! Declarative board setup.
+//!
+//! A [`BoardConfig`] describes how a robot's board should be brought up: screen orientation and
+//! font, MPU full-scale ranges, IO pin modes, named and calibrated ADC channels and the sensor
+//! sample rate. [`Board::init`] applies it in one go, so swapping robots means swapping a
+//! config file rather than editing every binary.
+//!
+//! With the `config` feature, configurations are loaded from TOML:
+//!
+//! ```toml
+//! sample_rate = 200.0
+//!
+//! [screen]
+//! direction = "Horizontal"
+//! font = "Font8x12"
+//!
+//! [mpu]
+//! gyro_fsr = 2000
+//! accel_fsr = 8
+//!
+//! [io]
+//! modes = ["output", "output", "input", "input", "input", "input", "input", "input"]
+//!
+//! [[adc]]
+//! index = 0
+//! name = "battery"
+//! scale = 0.0041
+//!
+//! [[adc]]
+//! index = 3
+//! name = "left_ir"
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::board::{AdcChannelConfig, Board, BoardConfig, ScreenConfig};
+//! use uptechstar_rs::display::{FontSize, ScreenDirection};
+//!
+//! let config = BoardConfig {
+//!     screen: Some(ScreenConfig { direction: ScreenDirection::Horizontal, font: FontSize::Font8x12 }),
+//!     adc: vec![AdcChannelConfig::new(0, "battery").with_calibration(0.0041, 0.0)],
+//!     ..BoardConfig::default()
+//! };
+//! let mut board = Board::init(config).unwrap();
+//!
+//! println!("battery: {:.2} V", board.adc_value("battery").unwrap());
+//! if let Some(screen) = board.screen() {
+//!     screen.put_string(0, 0, "ready").refresh();
+//! }
+//! ```
+
+use crate::adc_io::{self, AdcIo};
+use crate::display::{Color, FontSize, Screen, ScreenDirection};
+use crate::error::{Result, UptechError};
+use crate::extern_lib;
+use crate::mpu;
+use crate::sampler::Sampler;
+use log::info;
+
+/// Direction of an IO pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum IoMode {
+    /// The pin is read as a digital input.
+    #[default]
+    Input,
+    /// The pin is driven as a digital output.
+    Output,
+}
+
+impl IoMode {
+    /// Returns the mode value expected by [`adc_io::set_io_mode`].
+    pub fn as_raw(self) -> u8 {
+        match self {
+            IoMode::Input => 0,
+            IoMode::Output => 1,
+        }
+    }
+}
+
+/// Screen setup applied by [`Board::init`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScreenConfig {
+    /// Display orientation.
+    pub direction: ScreenDirection,
+    /// Initial font.
+    #[cfg_attr(feature = "serde", serde(default = "default_font"))]
+    pub font: FontSize,
+}
+
+#[cfg(feature = "serde")]
+fn default_font() -> FontSize {
+    FontSize::Font12x20
+}
+
+/// MPU6500 setup applied by [`Board::init`]. Unset ranges keep the library defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MpuConfig {
+    /// Gyroscope full-scale range in °/s: 250, 500, 1000 or 2000.
+    pub gyro_fsr: Option<u32>,
+    /// Accelerometer full-scale range in g: 2, 4, 8 or 16.
+    pub accel_fsr: Option<i32>,
+}
+
+/// IO setup applied by [`Board::init`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct IoConfig {
+    /// Modes of pins 0 upwards; pins beyond the list are left untouched.
+    pub modes: Vec<IoMode>,
+}
+
+/// A named ADC channel with a linear calibration.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdcChannelConfig {
+    /// Channel index, `0..10`.
+    pub index: usize,
+    /// Name used to look the channel up.
+    pub name: String,
+    /// Multiplier applied to the raw reading.
+    #[cfg_attr(feature = "serde", serde(default = "default_scale"))]
+    pub scale: f32,
+    /// Offset added after scaling.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: f32,
+}
+
+#[cfg(feature = "serde")]
+fn default_scale() -> f32 {
+    1.0
+}
+
+impl AdcChannelConfig {
+    /// Creates an uncalibrated channel description.
+    pub fn new<S: Into<String>>(index: usize, name: S) -> Self {
+        AdcChannelConfig {
+            index,
+            name: name.into(),
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    /// Sets the linear calibration `value = raw * scale + offset`.
+    pub fn with_calibration(mut self, scale: f32, offset: f32) -> Self {
+        self.scale = scale;
+        self.offset = offset;
+        self
+    }
+
+    /// Applies the calibration to a raw reading.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uptechstar_rs::board::AdcChannelConfig;
+    ///
+    /// let battery = AdcChannelConfig::new(0, "battery").with_calibration(0.5, 1.0);
+    /// assert_eq!(battery.apply(100), 51.0);
+    /// ```
+    pub fn apply(&self, raw: i32) -> f32 {
+        raw as f32 * self.scale + self.offset
+    }
+}
+
+/// Complete description of a board setup.
+///
+/// Every section is optional: hardware without a section is not initialized by
+/// [`Board::init`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct BoardConfig {
+    /// Rate in Hz of the samplers created by [`Board::sampler`].
+    pub sample_rate: f32,
+    /// Screen setup; the screen stays closed when absent.
+    pub screen: Option<ScreenConfig>,
+    /// MPU setup; the MPU is not initialized when absent.
+    pub mpu: Option<MpuConfig>,
+    /// IO pin modes.
+    pub io: IoConfig,
+    /// Named ADC channels.
+    pub adc: Vec<AdcChannelConfig>,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig {
+            sample_rate: 100.0,
+            screen: None,
+            mpu: None,
+            io: IoConfig::default(),
+            adc: Vec::new(),
+        }
+    }
+}
+
+impl BoardConfig {
+    /// Loads and validates a TOML configuration file.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::board::{Board, BoardConfig};
+    ///
+    /// let board = Board::init(BoardConfig::from_file("board.toml").unwrap()).unwrap();
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        info!("Loading board configuration from {}", path.display());
+
+        let text = std::fs::read_to_string(path)?;
+        Self::from_toml(&text).map_err(|e| match e {
+            UptechError::Config(message) => UptechError::Config(format!("{}: {}", path.display(), message)),
+            other => other,
+        })
+    }
+
+    /// Parses and validates a TOML configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uptechstar_rs::board::{BoardConfig, IoMode};
+    ///
+    /// let config = BoardConfig::from_toml(
+    ///     r#"
+    ///     sample_rate = 50.0
+    ///
+    ///     [io]
+    ///     modes = ["output", "input"]
+    ///
+    ///     [[adc]]
+    ///     index = 2
+    ///     name = "line"
+    ///     scale = 0.5
+    ///     "#,
+    /// )
+    /// .unwrap();
+    ///
+    /// assert_eq!(config.sample_rate, 50.0);
+    /// assert_eq!(config.io.modes, [IoMode::Output, IoMode::Input]);
+    /// assert_eq!(config.adc_channel("line").unwrap().apply(10), 5.0);
+    /// assert!(config.mpu.is_none());
+    ///
+    /// assert!(BoardConfig::from_toml("[mpu]\ngyro_fsr = 300").is_err());
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let config: BoardConfig = toml::from_str(text).map_err(|e| UptechError::Config(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that every value is within the range the hardware supports.
+    pub fn validate(&self) -> Result<()> {
+        if !(self.sample_rate.is_finite() && self.sample_rate > 0.0) {
+            return Err(UptechError::Config(format!(
+                "sample_rate must be positive, got {}",
+                self.sample_rate
+            )));
+        }
+
+        if let Some(mpu) = &self.mpu {
+            if let Some(fsr) = mpu.gyro_fsr
+                && ![250, 500, 1000, 2000].contains(&fsr)
+            {
+                return Err(UptechError::Config(format!(
+                    "mpu.gyro_fsr must be 250, 500, 1000 or 2000, got {}",
+                    fsr
+                )));
+            }
+            if let Some(fsr) = mpu.accel_fsr
+                && ![2, 4, 8, 16].contains(&fsr)
+            {
+                return Err(UptechError::Config(format!(
+                    "mpu.accel_fsr must be 2, 4, 8 or 16, got {}",
+                    fsr
+                )));
+            }
+        }
+
+        if self.io.modes.len() > 8 {
+            return Err(UptechError::Config(format!(
+                "io.modes lists {} pins, the board has 8",
+                self.io.modes.len()
+            )));
+        }
+
+        for (position, channel) in self.adc.iter().enumerate() {
+            if channel.index >= 10 {
+                return Err(UptechError::Config(format!(
+                    "adc channel '{}' has index {}, valid indices are 0-9",
+                    channel.name, channel.index
+                )));
+            }
+            if self.adc[..position].iter().any(|other| other.name == channel.name) {
+                return Err(UptechError::Config(format!(
+                    "adc channel name '{}' is used more than once",
+                    channel.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks up a named ADC channel.
+    pub fn adc_channel(&self, name: &str) -> Option<&AdcChannelConfig> {
+        self.adc.iter().find(|channel| channel.name == name)
+    }
+}
+
+/// An initialized board, set up from a [`BoardConfig`].
+///
+/// The board holds an [`AdcIo`] handle, so the ADC-IO peripheral is closed again when the
+/// board is dropped, unless other handles are still alive.
+pub struct Board {
+    config: BoardConfig,
+    screen: Option<Screen>,
+    adc: AdcIo,
+}
+
+impl Board {
+    /// Validates `config` and initializes the hardware it describes.
+    ///
+    /// The ADC-IO peripheral is always opened, or shared if an [`AdcIo`] handle already exists. IO modes, the MPU and the screen are only
+    /// configured when the corresponding section is present. A screen section fails with
+    /// [`UptechError::MissingSymbol`] if the loaded library lacks any drawing function.
+    pub fn init(config: BoardConfig) -> Result<Self> {
+        config.validate()?;
+
+        let adc = AdcIo::open()?;
+
+        for (index, mode) in config.io.modes.iter().enumerate() {
+            UptechError::check("adc_io_ModeSet", adc_io::set_io_mode(index as u32, mode.as_raw()))?;
+        }
+
+        if let Some(mpu_config) = &config.mpu {
+            UptechError::check("mpu6500_dmp_init", mpu::mpu6500_open())?;
+            if let Some(fsr) = mpu_config.gyro_fsr {
+                UptechError::check("mpu_set_gyro_fsr", mpu::mpu_set_gyro_fsr(fsr))?;
+            }
+            if let Some(fsr) = mpu_config.accel_fsr {
+                UptechError::check("mpu_set_accel_fsr", mpu::mpu_set_accel_fsr(fsr))?;
+            }
+        }
+
+        if config.screen.is_some() {
+            extern_lib::require(extern_lib::DISPLAY_SYMBOLS)?;
+        }
+
+        let screen = config.screen.map(|screen_config| {
+            let mut screen = Screen::new(Some(screen_config.direction));
+            screen.set_font_size(screen_config.font);
+            screen
+        });
+
+        info!(
+            "Board initialized ({} IO modes, {} named ADC channels, mpu: {}, screen: {})",
+            config.io.modes.len(),
+            config.adc.len(),
+            config.mpu.is_some(),
+            screen.is_some()
+        );
+
+        Ok(Board {
+            config,
+            screen,
+            adc,
+        })
+    }
+
+    /// Returns the configuration the board was initialized with.
+    pub fn config(&self) -> &BoardConfig {
+        &self.config
+    }
+
+    /// Returns the screen, if the configuration has a `screen` section.
+    pub fn screen(&mut self) -> Option<&mut Screen> {
+        self.screen.as_mut()
+    }
+
+    /// Collects what [`show_boot_screen`](Self::show_boot_screen) displays.
+    pub fn boot_report(&self) -> BootReport {
+        BootReport {
+            crate_version: env!("CARGO_PKG_VERSION"),
+            library_version: extern_lib::library_version(),
+            adc_open_count: self.adc.open_count(),
+            mpu: self.config.mpu.as_ref().map(|_| mpu::is_awake()),
+            io_modes: adc_io::get_all_io_mode(),
+        }
+    }
+
+    /// Shows the crate and library versions, the ADC open count, the MPU probe result and the
+    /// IO modes on the screen and in the log, so a failed bring-up can be diagnosed on the
+    /// robot without a terminal.
+    ///
+    /// The report is drawn in the 6x8 font; the configured font is restored afterwards. Without
+    /// a screen, the report is only logged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use uptechstar_rs::board::{Board, BoardConfig, MpuConfig, ScreenConfig};
+    /// use uptechstar_rs::display::{FontSize, ScreenDirection};
+    ///
+    /// let mut board = Board::init(BoardConfig {
+    ///     screen: Some(ScreenConfig { direction: ScreenDirection::Horizontal, font: FontSize::Font8x12 }),
+    ///     mpu: Some(MpuConfig::default()),
+    ///     ..BoardConfig::default()
+    /// })
+    /// .unwrap();
+    ///
+    /// let report = board.show_boot_screen();
+    /// if report.is_healthy() {
+    ///     thread::sleep(Duration::from_secs(2));
+    /// }
+    /// ```
+    pub fn show_boot_screen(&mut self) -> BootReport {
+        let report = self.boot_report();
+        for line in report.lines() {
+            info!("{}", line);
+        }
+
+        if let Some(screen) = &mut self.screen {
+            let font = FontSize::Font6x8;
+            screen
+                .fill_screen(Color::BLACK)
+                .set_font_size(font)
+                .set_back_color(Color::BLACK);
+
+            for (row, line) in report.lines().iter().enumerate() {
+                let color = if row == BootReport::MPU_LINE && !report.is_healthy() {
+                    Color::RED
+                } else {
+                    Color::WHITE
+                };
+                screen
+                    .set_fore_color(color)
+                    .put_string(0, row as i32 * font.row_height(), line);
+            }
+
+            screen.refresh();
+            if let Some(screen_config) = &self.config.screen {
+                screen.set_font_size(screen_config.font);
+            }
+        }
+
+        report
+    }
+
+    /// Puts the configured MPU and screen to sleep, for battery-powered loggers that only
+    /// sample now and then. The ADC-IO peripheral stays open.
+    ///
+    /// Call [`wake`](Self::wake) before reading the MPU or drawing again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use uptechstar_rs::board::{Board, BoardConfig};
+    ///
+    /// let mut board = Board::init(BoardConfig::default()).unwrap();
+    /// loop {
+    ///     board.wake().unwrap();
+    ///     println!("{:?}", board.adc_values().unwrap());
+    ///     board.low_power().unwrap();
+    ///     thread::sleep(Duration::from_secs(60));
+    /// }
+    /// ```
+    pub fn low_power(&mut self) -> Result<()> {
+        if self.config.mpu.is_some() {
+            UptechError::check("mpu_set_sensors", mpu::sleep())?;
+        }
+        if let Some(screen) = &mut self.screen {
+            screen.sleep();
+        }
+
+        info!("Board entered low power mode");
+        Ok(())
+    }
+
+    /// Wakes the MPU and the screen from [`low_power`](Self::low_power).
+    ///
+    /// The screen comes back blank and has to be redrawn.
+    pub fn wake(&mut self) -> Result<()> {
+        if self.config.mpu.is_some() {
+            UptechError::check("mpu_set_sensors", mpu::wake())?;
+        }
+        if let Some(screen) = &mut self.screen {
+            screen.wake();
+        }
+
+        info!("Board woke from low power mode");
+        Ok(())
+    }
+
+    /// Reads all named ADC channels and returns their calibrated values in configuration order.
+    pub fn adc_values(&self) -> Result<Vec<(&str, f32)>> {
+        let frame = read_adc_frame()?;
+
+        Ok(self
+            .config
+            .adc
+            .iter()
+            .map(|channel| (channel.name.as_str(), channel.apply(frame.0[channel.index])))
+            .collect())
+    }
+
+    /// Reads one named ADC channel and returns its calibrated value.
+    pub fn adc_value(&self, name: &str) -> Result<f32> {
+        let channel = self
+            .config
+            .adc_channel(name)
+            .ok_or_else(|| UptechError::Config(format!("no adc channel named '{}'", name)))?;
+
+        Ok(channel.apply(read_adc_frame()?.0[channel.index]))
+    }
+
+    /// Creates a stopped [`Sampler`] at the configured rate, sampling every configured source.
+    ///
+    /// ADC and IO are always enabled; the MPU only if the configuration has an `mpu` section.
+    pub fn sampler(&self) -> Sampler {
+        Sampler::new(self.config.sample_rate)
+            .with_adc(true)
+            .with_io(true)
+            .with_mpu(self.config.mpu.is_some())
+    }
+}
+
+/// Diagnostics collected at startup, see [`Board::show_boot_screen`].
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::board::BootReport;
+///
+/// let report = BootReport {
+///     crate_version: "0.3.0",
+///     library_version: Some("1.2.0"),
+///     adc_open_count: 1,
+///     mpu: Some(Err(-1)),
+///     io_modes: 0b0000_0011,
+/// };
+///
+/// assert!(!report.is_healthy());
+/// assert_eq!(
+///     report.lines(),
+///     ["uptechstar-rs 0.3.0", "libuptech 1.2.0", "ADC opened 1x", "MPU: error -1", "IO0-7: OOIIIIII"]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BootReport {
+    /// Version of this crate.
+    pub crate_version: &'static str,
+    /// Version of the loaded `libuptech.so`, see [`extern_lib::library_version`].
+    pub library_version: Option<&'static str>,
+    /// How often the library reported the ADC-IO peripheral opened when the board's
+    /// [`AdcIo`] handle, or the handle it shares, opened it.
+    pub adc_open_count: i32,
+    /// Whether the MPU reports its sensors powered on, `None` if it is not configured.
+    pub mpu: Option<std::result::Result<bool, i32>>,
+    /// IO pin modes, one bit per pin with IO0 in bit 0; set bits are outputs.
+    pub io_modes: u8,
+}
+
+impl BootReport {
+    /// Row of the MPU status in [`lines`](Self::lines).
+    const MPU_LINE: usize = 3;
+
+    /// Characters per row of the 6x8 font on the horizontal screen.
+    const COLUMNS: usize = 21;
+
+    /// Returns `false` if the MPU probe failed or the MPU is asleep.
+    pub fn is_healthy(&self) -> bool {
+        !matches!(self.mpu, Some(Err(_)) | Some(Ok(false)))
+    }
+
+    /// Returns the report as shown on the screen, one row per line.
+    pub fn lines(&self) -> Vec<String> {
+        let library = self.library_version.unwrap_or("not loaded");
+        let mpu = match self.mpu {
+            None => "off".to_string(),
+            Some(Ok(true)) => "ok".to_string(),
+            Some(Ok(false)) => "asleep".to_string(),
+            Some(Err(code)) => format!("error {}", code),
+        };
+        let io: String = (0..8)
+            .map(|pin| if self.io_modes & (1 << pin) != 0 { 'O' } else { 'I' })
+            .collect();
+
+        vec![
+            format!("uptechstar-rs {}", self.crate_version),
+            // Checksums of unversioned builds are longer than a row.
+            format!("libuptech {}", library).chars().take(Self::COLUMNS).collect(),
+            format!("ADC opened {}x", self.adc_open_count),
+            format!("MPU: {}", mpu),
+            format!("IO0-7: {}", io),
+        ]
+    }
+}
+
+fn read_adc_frame() -> Result<adc_io::AdcFrame> {
+    adc_io::adc_get_frame().map_err(|_| UptechError::Hardware {
+        operation: "ADC_GetAll",
+        code: -1,
+    })
+}
@@ -0,0 +1,17 @@
+//! Guided sensor calibration.
+//!
+//! Each calibration comes as plain logic that records readings and computes the result, and as
+//! a [`Pager`](crate::display::Pager) page that walks through the steps with the pager buttons
+//! and stores the result in a [`Settings`](crate::settings::Settings) store, so it is kept
+//! across restarts when the store was opened from a file.
+//!
+//! - [`LineCalibration`] / [`LineCalibrationWizard`] - Thresholds of reflective line sensors
+//!   from readings over the white and the black surface
+//! - [`ImuCalibrator`] / [`ImuCalibrationWizard`] - Accelerometer offsets and scales from six
+//!   positions and the gyro bias at rest, stored as an [`ImuCalibration`](crate::mpu::ImuCalibration)
+
+mod imu;
+mod line;
+
+pub use imu::{ImuCalibrationWizard, ImuCalibrator, POSITIONS};
+pub use line::{LineCalibration, LineCalibrationWizard, Surface};
@@ -0,0 +1,460 @@
+use crate::display::{Color, FontSize, Nav, Page, Screen};
+use crate::mpu::{self, Axis, ImuCalibration};
+use crate::settings::Settings;
+use log::{info, warn};
+use std::time::{Duration, Instant};
+
+/// The six positions of the accelerometer calibration, named by the axis pointing up, in the
+/// order the wizard asks for them.
+pub const POSITIONS: [Axis; 6] = [Axis::Z, Axis::NegZ, Axis::X, Axis::NegX, Axis::Y, Axis::NegY];
+
+/// Share of the measured acceleration that must lie along the axis that should point up.
+const UPRIGHT: f32 = 0.9;
+
+/// A running mean of vector readings.
+#[derive(Debug, Clone, Copy, Default)]
+struct Mean {
+    sum: [f64; 3],
+    count: u32,
+}
+
+impl Mean {
+    fn add(&mut self, value: [f32; 3]) {
+        for (sum, value) in self.sum.iter_mut().zip(value) {
+            *sum += value as f64;
+        }
+        self.count += 1;
+    }
+
+    fn value(&self) -> Option<[f32; 3]> {
+        (self.count > 0).then(|| self.sum.map(|sum| (sum / self.count as f64) as f32))
+    }
+}
+
+/// Collects the readings of a six-position accelerometer calibration and a stationary gyro
+/// bias capture, and computes an [`ImuCalibration`] from them.
+///
+/// The accelerometer is held still with each of its axes pointing up and then down, see
+/// [`POSITIONS`]. The mean readings along that axis give its offset, halfway between them, and
+/// its scale, which stretches their span to 2 g. The gyro bias is the mean angular rate of
+/// the robot at rest. Readings are taken in the robot frame, after the
+/// [mounting](crate::mpu::set_mounting), and without any previous calibration applied.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::calibration::ImuCalibrator;
+/// use uptechstar_rs::mpu::Axis;
+///
+/// let mut calibrator = ImuCalibrator::new();
+/// calibrator.record_gyro([0.5, -0.25, 0.0]);
+/// calibrator.record_gyro([0.5, -0.75, 0.0]);
+///
+/// assert!(calibrator.record_position(Axis::X, [1.25, 0.0, 0.0]));
+/// assert!(calibrator.record_position(Axis::NegX, [-0.75, 0.0, 0.0]));
+/// assert!(calibrator.record_position(Axis::Y, [0.0, 0.5, 0.0]));
+/// assert!(calibrator.record_position(Axis::NegY, [0.0, -0.5, 0.0]));
+/// assert!(calibrator.record_position(Axis::Z, [0.0, 0.0, 1.0]));
+/// // Lying on the wrong side is not counted.
+/// assert!(!calibrator.record_position(Axis::NegZ, [0.0, 0.0, 1.0]));
+/// assert_eq!(calibrator.result(), None);
+///
+/// assert!(calibrator.record_position(Axis::NegZ, [0.0, 0.0, -1.0]));
+/// let calibration = calibrator.result().unwrap();
+/// assert_eq!(calibration.gyro_bias, [0.5, -0.5, 0.0]);
+/// assert_eq!(calibration.accel_offset, [0.25, 0.0, 0.0]);
+/// assert_eq!(calibration.accel_scale, [1.0, 2.0, 1.0]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ImuCalibrator {
+    gyro: Mean,
+    accel: [Mean; 6],
+}
+
+impl ImuCalibrator {
+    /// Creates a calibrator without readings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an angular rate in degrees per second, read while the robot is at rest.
+    pub fn record_gyro(&mut self, gyro: [f32; 3]) {
+        self.gyro.add(gyro);
+    }
+
+    /// Adds an acceleration in g, read while `up` points up.
+    ///
+    /// Returns:
+    ///   `false` if the reading shows that `up` does not point up; it is not counted then.
+    pub fn record_position(&mut self, up: Axis, accel: [f32; 3]) -> bool {
+        let along: f32 = up.unit().iter().zip(accel).map(|(unit, value)| unit * value).sum();
+        let norm = accel.iter().map(|value| value * value).sum::<f32>().sqrt();
+        if along <= UPRIGHT * norm || norm == 0.0 {
+            return false;
+        }
+        self.accel[position(up)].add(accel);
+        true
+    }
+
+    /// Returns the number of gyro readings.
+    pub fn gyro_samples(&self) -> u32 {
+        self.gyro.count
+    }
+
+    /// Returns the number of readings counted with `up` pointing up.
+    pub fn position_samples(&self, up: Axis) -> u32 {
+        self.accel[position(up)].count
+    }
+
+    /// Forgets the readings with `up` pointing up, to record the position again.
+    pub fn clear_position(&mut self, up: Axis) {
+        self.accel[position(up)] = Mean::default();
+    }
+
+    /// Forgets every reading.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the calibration, or `None` while the gyro or one of the six positions has no
+    /// readings, or if an axis read less along its up than along its down direction.
+    pub fn result(&self) -> Option<ImuCalibration> {
+        let mut calibration = ImuCalibration {
+            gyro_bias: self.gyro.value()?,
+            ..ImuCalibration::IDENTITY
+        };
+        for (axis, (up, down)) in [(Axis::X, Axis::NegX), (Axis::Y, Axis::NegY), (Axis::Z, Axis::NegZ)]
+            .into_iter()
+            .enumerate()
+        {
+            let up = self.accel[position(up)].value()?[axis];
+            let down = self.accel[position(down)].value()?[axis];
+            if up <= down {
+                return None;
+            }
+            calibration.accel_offset[axis] = (up + down) / 2.0;
+            calibration.accel_scale[axis] = 2.0 / (up - down);
+        }
+        Some(calibration)
+    }
+}
+
+/// Index of `up` in [`POSITIONS`].
+fn position(up: Axis) -> usize {
+    POSITIONS.iter().position(|&axis| axis == up).unwrap_or_default()
+}
+
+/// Instruction for a position, e.g. `+X axis up`.
+fn axis_name(up: Axis) -> &'static str {
+    match up {
+        Axis::X => "+X axis up",
+        Axis::NegX => "-X axis up",
+        Axis::Y => "+Y axis up",
+        Axis::NegY => "-Y axis up",
+        Axis::Z => "+Z up (level)",
+        Axis::NegZ => "-Z up (flipped)",
+    }
+}
+
+/// The step the wizard is at. Positions are indices into [`POSITIONS`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    /// Waiting for `Select` to start.
+    Idle,
+    /// Asking to keep the robot still for the gyro bias.
+    PlaceGyro,
+    /// Recording the gyro since the instant.
+    RecordGyro(Instant),
+    /// Asking to turn the robot into a position.
+    Place(usize),
+    /// Recording a position since the instant.
+    Record(usize, Instant),
+    /// Showing the result.
+    Review,
+}
+
+/// Everything that is drawn, to skip renders without changes.
+#[derive(PartialEq)]
+struct Snapshot {
+    lines: Vec<String>,
+    progress: Option<i32>,
+}
+
+/// A [`Page`] guiding through an [`ImuCalibrator`] with the pager buttons.
+///
+/// While idle, the buttons switch pages as usual and [`Nav::Select`] starts the calibration.
+/// The page first asks to keep the robot still to capture the gyro bias, then to turn it into
+/// each of the six [`POSITIONS`] in turn, recording a few seconds after every `Select` with a
+/// progress bar. A position recorded the wrong way up is asked for again. `Previous` cancels
+/// at any prompt.
+///
+/// While it runs, the wizard switches off the calibration applied to the readings. At the end
+/// it shows the result: `Select` [saves](ImuCalibration::save) it to the settings and
+/// [applies](crate::mpu::set_calibration) it, `Next` starts over and `Previous` restores the
+/// previous calibration.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use uptechstar_rs::calibration::ImuCalibrationWizard;
+/// use uptechstar_rs::display::{Color, Pager, Screen, ScreenDirection};
+/// use uptechstar_rs::mpu::{self, ImuCalibration};
+/// use uptechstar_rs::settings::Settings;
+/// use uptechstar_rs::{adc_io, events};
+///
+/// adc_io::adc_open();
+/// mpu::mpu6500_open();
+/// let settings = Settings::new();
+/// // Apply the calibration of an earlier run.
+/// mpu::set_calibration(ImuCalibration::load(&settings));
+///
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+/// let mut pager = Pager::new(Color::BLACK)
+///     .with_page("imu", ImuCalibrationWizard::new(settings.clone()))
+///     .with_buttons(0, 1, 2);
+/// pager.run(&mut screen, &events::subscribe(), Duration::from_millis(20));
+/// ```
+pub struct ImuCalibrationWizard {
+    calibrator: ImuCalibrator,
+    settings: Settings,
+    step: Step,
+    /// The calibration applied before the wizard started, restored on cancel.
+    previous: ImuCalibration,
+    /// The last position ended without a reading the right way up.
+    misplaced: bool,
+    duration: Duration,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    drawn: Option<Snapshot>,
+}
+
+impl ImuCalibrationWizard {
+    /// Creates a wizard storing the calibration in `settings`.
+    pub fn new(settings: Settings) -> Self {
+        ImuCalibrationWizard {
+            calibrator: ImuCalibrator::new(),
+            settings,
+            step: Step::Idle,
+            previous: ImuCalibration::IDENTITY,
+            misplaced: false,
+            duration: Duration::from_secs(3),
+            font: FontSize::Font6x8,
+            color: Color::WHITE,
+            background: Color::BLACK,
+            drawn: None,
+        }
+    }
+
+    /// Sets how long the gyro and each position are recorded. The default is 3 seconds.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the font. The default is [`FontSize::Font6x8`].
+    pub fn with_font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the text and background colors. The default is white on black.
+    pub fn with_colors(mut self, color: u32, background: u32) -> Self {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Returns the calibrator with the readings recorded so far.
+    pub fn calibrator(&self) -> &ImuCalibrator {
+        &self.calibrator
+    }
+
+    /// Returns `true` while the wizard has the buttons.
+    pub fn is_active(&self) -> bool {
+        self.step != Step::Idle
+    }
+
+    /// Takes a reading while recording, moving on once the time is up.
+    fn sample(&mut self) {
+        match self.step {
+            Step::RecordGyro(started) => {
+                let mut gyro = [0.0f32; 3];
+                match mpu::read_gyro(&mut gyro) {
+                    0 => self.calibrator.record_gyro(gyro),
+                    code => warn!("IMU calibration skipped a gyro reading: {}", code),
+                }
+                if started.elapsed() >= self.duration {
+                    self.step = Step::Place(0);
+                }
+            }
+            Step::Record(index, started) => {
+                let mut accel = [0.0f32; 3];
+                match mpu::read_accel(&mut accel) {
+                    0 => {
+                        self.calibrator.record_position(POSITIONS[index], accel);
+                    }
+                    code => warn!("IMU calibration skipped an accel reading: {}", code),
+                }
+                if started.elapsed() >= self.duration {
+                    self.misplaced = self.calibrator.position_samples(POSITIONS[index]) == 0;
+                    self.step = if self.misplaced {
+                        Step::Place(index)
+                    } else if index + 1 < POSITIONS.len() {
+                        Step::Place(index + 1)
+                    } else {
+                        Step::Review
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn progress(&self, started: Instant) -> Option<i32> {
+        let progress = started.elapsed().as_secs_f32() / self.duration.as_secs_f32().max(f32::EPSILON);
+        Some((progress.min(1.0) * 100.0) as i32)
+    }
+
+    /// The lines of text of the current step, and the recording progress in percent.
+    fn snapshot(&self) -> Snapshot {
+        let steps = POSITIONS.len() + 1;
+        let (lines, progress) = match self.step {
+            Step::Idle => (vec!["IMU calibration".to_string(), "Select: start".to_string()], None),
+            Step::PlaceGyro => (
+                vec![
+                    format!("Step 1/{}: gyro", steps),
+                    "Keep robot still".to_string(),
+                    "Select: record".to_string(),
+                    "Prev: cancel".to_string(),
+                ],
+                None,
+            ),
+            Step::RecordGyro(started) => (
+                vec![format!("Step 1/{}: gyro", steps), "Recording...".to_string()],
+                self.progress(started),
+            ),
+            Step::Place(index) => {
+                let mut lines = vec![
+                    format!("Step {}/{}: accel", index + 2, steps),
+                    format!("Turn {}", axis_name(POSITIONS[index])),
+                    "Select: record".to_string(),
+                    "Prev: cancel".to_string(),
+                ];
+                if self.misplaced {
+                    lines.push("Wrong side up, again".to_string());
+                }
+                (lines, None)
+            }
+            Step::Record(index, started) => (
+                vec![
+                    format!("Step {}/{}: accel", index + 2, steps),
+                    "Hold still...".to_string(),
+                ],
+                self.progress(started),
+            ),
+            Step::Review => match self.calibrator.result() {
+                Some(calibration) => {
+                    let row = |values: [f32; 3], decimals: usize| {
+                        values.iter().map(|value| format!("{:7.*}", decimals, value)).collect::<String>()
+                    };
+                    (
+                        vec![
+                            "Gyro bias (dps)".to_string(),
+                            row(calibration.gyro_bias, 2),
+                            "Accel offset (g)".to_string(),
+                            row(calibration.accel_offset, 3),
+                            "Accel scale".to_string(),
+                            row(calibration.accel_scale, 3),
+                            "Sel:save Next:redo".to_string(),
+                        ],
+                        None,
+                    )
+                }
+                None => (
+                    vec!["Calibration failed".to_string(), "Next:redo Prev:cancel".to_string()],
+                    None,
+                ),
+            },
+        };
+        Snapshot { lines, progress }
+    }
+
+    fn restart(&mut self) {
+        self.calibrator.clear();
+        self.misplaced = false;
+        self.step = Step::PlaceGyro;
+    }
+
+    /// Leaves the wizard, applying `calibration`.
+    fn finish(&mut self, calibration: ImuCalibration) {
+        mpu::set_calibration(calibration);
+        self.step = Step::Idle;
+    }
+}
+
+impl Page for ImuCalibrationWizard {
+    fn render(&mut self, screen: &mut Screen) {
+        self.sample();
+
+        let snapshot = self.snapshot();
+        if self.drawn.as_ref() == Some(&snapshot) {
+            return;
+        }
+
+        let previous_font = screen.font();
+        let row_height = self.font.row_height();
+        let (width, _) = screen.size();
+        screen
+            .fill_screen(self.background)
+            .set_font_size(self.font)
+            .set_fore_color(self.color)
+            .set_back_color(self.background);
+        for (row, line) in snapshot.lines.iter().enumerate() {
+            screen.put_string(0, row as i32 * row_height, line);
+        }
+        if let Some(progress) = snapshot.progress {
+            let y = snapshot.lines.len() as i32 * row_height + 2;
+            screen.draw_frame(0, y, width - 1, y + 7, self.color);
+            if progress > 0 {
+                screen.fill_frame(1, y + 1, 1 + (width - 3) * progress / 100, y + 6, self.color);
+            }
+        }
+
+        screen.set_font_size(previous_font).refresh();
+        self.drawn = Some(snapshot);
+    }
+
+    fn on_enter(&mut self) {
+        self.drawn = None;
+    }
+
+    fn handle_nav(&mut self, nav: Nav) -> bool {
+        match (self.step, nav) {
+            (Step::Idle, Nav::Select) => {
+                // Record what the sensor reads, not what the old calibration makes of it.
+                self.previous = mpu::set_calibration(ImuCalibration::IDENTITY);
+                self.restart();
+            }
+            (Step::Idle, _) => return false,
+            (Step::PlaceGyro, Nav::Select) => self.step = Step::RecordGyro(Instant::now()),
+            (Step::Place(index), Nav::Select) => {
+                self.calibrator.clear_position(POSITIONS[index]);
+                self.step = Step::Record(index, Instant::now());
+            }
+            (Step::PlaceGyro | Step::Place(_) | Step::Review, Nav::Previous) => self.finish(self.previous),
+            (Step::Review, Nav::Select) => match self.calibrator.result() {
+                Some(calibration) => {
+                    calibration.save(&self.settings);
+                    info!("IMU calibration stored");
+                    self.finish(calibration);
+                }
+                None => self.restart(),
+            },
+            (Step::Review, Nav::Next) => self.restart(),
+            _ => {}
+        }
+        true
+    }
+}
@@ -0,0 +1,398 @@
+use crate::adc_io::{self, AdcFrame};
+use crate::display::{Color, FontSize, Nav, Page, Screen};
+use crate::settings::{Key, Settings};
+use log::{info, warn};
+use std::time::{Duration, Instant};
+
+/// A surface line sensors are calibrated on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Surface {
+    White,
+    Black,
+}
+
+impl Surface {
+    fn name(&self) -> &'static str {
+        match self {
+            Surface::White => "WHITE",
+            Surface::Black => "BLACK",
+        }
+    }
+}
+
+/// A line sensor: its ADC channel, the setting its threshold is stored in and the readings
+/// seen on each surface.
+struct Sensor {
+    channel: usize,
+    key: Key<i64>,
+    white: Option<(i32, i32)>,
+    black: Option<(i32, i32)>,
+}
+
+impl Sensor {
+    fn range(&self, surface: Surface) -> Option<(i32, i32)> {
+        match surface {
+            Surface::White => self.white,
+            Surface::Black => self.black,
+        }
+    }
+}
+
+/// Thresholds of reflective line sensors, from the lowest and highest reading of each sensor
+/// over the white and over the black surface.
+///
+/// A threshold lies halfway between the two ranges. If the ranges overlap the sensor cannot
+/// tell the surfaces apart, usually because it is mounted too high or was not over the
+/// surface the whole time, and it gets no threshold.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::AdcFrame;
+/// use uptechstar_rs::calibration::{LineCalibration, Surface};
+/// use uptechstar_rs::settings::{Key, Settings};
+///
+/// const LEFT: Key<i64> = Key::new("line.left", 2048).with_range(0.0, 4095.0, 16.0);
+/// const RIGHT: Key<i64> = Key::new("line.right", 2048).with_range(0.0, 4095.0, 16.0);
+///
+/// let mut calibration = LineCalibration::new().with_sensor(0, LEFT).with_sensor(1, RIGHT);
+///
+/// for (left, right) in [(3500, 3000), (3300, 2500)] {
+///     calibration.record(Surface::White, &AdcFrame([left, right, 0, 0, 0, 0, 0, 0, 0, 0]));
+/// }
+/// for (left, right) in [(500, 2600), (700, 2400)] {
+///     calibration.record(Surface::Black, &AdcFrame([left, right, 0, 0, 0, 0, 0, 0, 0, 0]));
+/// }
+///
+/// assert_eq!(calibration.range(0, Surface::White), Some((3300, 3500)));
+/// assert_eq!(calibration.threshold(0), Some(2000));
+/// // The right sensor read 2500 on white and 2600 on black.
+/// assert_eq!(calibration.threshold(1), None);
+///
+/// let settings = Settings::new();
+/// assert_eq!(calibration.save(&settings), 1);
+/// assert_eq!(settings.get(&LEFT), 2000);
+/// ```
+#[derive(Default)]
+pub struct LineCalibration {
+    sensors: Vec<Sensor>,
+}
+
+impl LineCalibration {
+    /// Creates a calibration without sensors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the sensor on ADC `channel`, whose threshold is stored in `key`.
+    ///
+    /// # Panics
+    ///
+    /// If `channel` is not below 10.
+    pub fn with_sensor(mut self, channel: usize, key: Key<i64>) -> Self {
+        assert!(channel < AdcFrame::CHANNELS, "ADC channel must be below 10, got {}", channel);
+        self.sensors.push(Sensor {
+            channel,
+            key,
+            white: None,
+            black: None,
+        });
+        self
+    }
+
+    /// Returns the number of sensors.
+    pub fn sensors(&self) -> usize {
+        self.sensors.len()
+    }
+
+    /// Widens the range of every sensor on `surface` to include its reading in `frame`.
+    pub fn record(&mut self, surface: Surface, frame: &AdcFrame) {
+        for sensor in &mut self.sensors {
+            let value = frame.0[sensor.channel];
+            let range = match surface {
+                Surface::White => &mut sensor.white,
+                Surface::Black => &mut sensor.black,
+            };
+            *range = Some(match *range {
+                Some((min, max)) => (min.min(value), max.max(value)),
+                None => (value, value),
+            });
+        }
+    }
+
+    /// Forgets the readings on `surface`, to record it again.
+    pub fn clear(&mut self, surface: Surface) {
+        for sensor in &mut self.sensors {
+            match surface {
+                Surface::White => sensor.white = None,
+                Surface::Black => sensor.black = None,
+            }
+        }
+    }
+
+    /// Returns the lowest and highest reading of sensor `index`, in the order sensors were
+    /// added, on `surface`.
+    pub fn range(&self, index: usize, surface: Surface) -> Option<(i32, i32)> {
+        self.sensors.get(index)?.range(surface)
+    }
+
+    /// Returns the threshold of sensor `index`, or `None` if a surface was not recorded or
+    /// the ranges of the surfaces overlap.
+    pub fn threshold(&self, index: usize) -> Option<i32> {
+        let sensor = self.sensors.get(index)?;
+        let (white, black) = (sensor.white?, sensor.black?);
+        let (low, high) = if white.1 < black.0 {
+            (white.1, black.0)
+        } else if black.1 < white.0 {
+            (black.1, white.0)
+        } else {
+            return None;
+        };
+        Some((low + high) / 2)
+    }
+
+    /// Stores every threshold that could be computed in its setting.
+    ///
+    /// Returns:
+    ///   The number of thresholds stored.
+    pub fn save(&self, settings: &Settings) -> usize {
+        let mut saved = 0;
+        for (index, sensor) in self.sensors.iter().enumerate() {
+            match self.threshold(index) {
+                Some(threshold) => {
+                    settings.set(&sensor.key, threshold as i64);
+                    saved += 1;
+                }
+                None => warn!("No threshold for '{}': readings missing or overlapping", sensor.key.name()),
+            }
+        }
+        info!("Stored {} of {} line sensor thresholds", saved, self.sensors.len());
+        saved
+    }
+}
+
+/// The step the wizard is at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    /// Waiting for `Select` to start.
+    Idle,
+    /// Asking to put the sensors over `Surface`.
+    Place(Surface),
+    /// Recording the surface since the instant.
+    Record(Surface, Instant),
+    /// Showing the thresholds.
+    Review,
+}
+
+/// Everything that is drawn, to skip renders without changes.
+#[derive(PartialEq)]
+struct Snapshot {
+    lines: Vec<String>,
+    progress: Option<i32>,
+}
+
+/// A [`Page`] walking through a [`LineCalibration`] with the pager buttons.
+///
+/// While idle, the buttons switch pages as usual and [`Nav::Select`] starts the calibration.
+/// The page then asks to put the sensors over the white surface and, after `Select`, records
+/// the ADC for a few seconds while the robot is slid around on it, then does the same over the
+/// black surface. Readings are taken once per render, so a fast render interval gives more of
+/// them. Finally every sensor's threshold is shown: `Select` stores them in the settings,
+/// `Next` starts over and `Previous` leaves without storing anything.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use uptechstar_rs::adc_io;
+/// use uptechstar_rs::calibration::{LineCalibration, LineCalibrationWizard};
+/// use uptechstar_rs::display::{Color, Pager, Screen, ScreenDirection};
+/// use uptechstar_rs::events;
+/// use uptechstar_rs::settings::{Key, Settings};
+///
+/// const LEFT: Key<i64> = Key::new("line.left", 2048).with_range(0.0, 4095.0, 16.0);
+/// const RIGHT: Key<i64> = Key::new("line.right", 2048).with_range(0.0, 4095.0, 16.0);
+///
+/// adc_io::adc_open();
+/// // With the `config` feature, `Settings::open` keeps the thresholds in a file.
+/// let settings = Settings::new();
+/// let calibration = LineCalibration::new().with_sensor(0, LEFT).with_sensor(1, RIGHT);
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+///
+/// let mut pager = Pager::new(Color::BLACK)
+///     .with_page("line", LineCalibrationWizard::new(calibration, settings.clone()))
+///     .with_page("settings", settings.editor())
+///     .with_buttons(0, 1, 2);
+/// pager.run(&mut screen, &events::subscribe(), Duration::from_millis(20));
+/// ```
+pub struct LineCalibrationWizard {
+    calibration: LineCalibration,
+    settings: Settings,
+    step: Step,
+    duration: Duration,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    drawn: Option<Snapshot>,
+}
+
+impl LineCalibrationWizard {
+    /// Creates a wizard for `calibration`, storing the thresholds in `settings`.
+    pub fn new(calibration: LineCalibration, settings: Settings) -> Self {
+        LineCalibrationWizard {
+            calibration,
+            settings,
+            step: Step::Idle,
+            duration: Duration::from_secs(3),
+            font: FontSize::Font6x8,
+            color: Color::WHITE,
+            background: Color::BLACK,
+            drawn: None,
+        }
+    }
+
+    /// Sets how long each surface is recorded. The default is 3 seconds.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the font. The default is [`FontSize::Font6x8`].
+    pub fn with_font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the text and background colors. The default is white on black.
+    pub fn with_colors(mut self, color: u32, background: u32) -> Self {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Returns the calibration with the readings recorded so far.
+    pub fn calibration(&self) -> &LineCalibration {
+        &self.calibration
+    }
+
+    /// Returns `true` while the wizard has the buttons.
+    pub fn is_active(&self) -> bool {
+        self.step != Step::Idle
+    }
+
+    /// Records the current ADC frame while a surface is being recorded, moving on once its
+    /// time is up.
+    fn sample(&mut self) {
+        let Step::Record(surface, started) = self.step else {
+            return;
+        };
+        match adc_io::adc_get_frame() {
+            Ok(frame) => self.calibration.record(surface, &frame),
+            Err(e) => warn!("Line calibration skipped a reading: {}", e),
+        }
+        if started.elapsed() >= self.duration {
+            self.step = match surface {
+                Surface::White => Step::Place(Surface::Black),
+                Surface::Black => Step::Review,
+            };
+        }
+    }
+
+    /// The lines of text of the current step, and the recording progress in percent.
+    fn snapshot(&self) -> Snapshot {
+        let (lines, progress) = match self.step {
+            Step::Idle => (vec!["Line calibration".to_string(), "Select: start".to_string()], None),
+            Step::Place(surface) => (
+                vec![
+                    format!("Put sensors on {}", surface.name()),
+                    "Select: record".to_string(),
+                    "Prev: cancel".to_string(),
+                ],
+                None,
+            ),
+            Step::Record(surface, started) => {
+                let progress = started.elapsed().as_secs_f32() / self.duration.as_secs_f32().max(f32::EPSILON);
+                (
+                    vec![format!("Recording {}", surface.name()), "Slide robot around".to_string()],
+                    Some((progress.min(1.0) * 100.0) as i32),
+                )
+            }
+            Step::Review => {
+                let mut lines: Vec<String> = self.calibration.sensors.iter().enumerate()
+                    .map(|(index, sensor)| {
+                        match self.calibration.threshold(index) {
+                            Some(threshold) => format!("ADC{}: {}", sensor.channel, threshold),
+                            None => format!("ADC{}: overlap", sensor.channel),
+                        }
+                    })
+                    .collect();
+                lines.push("Sel:save Next:redo".to_string());
+                (lines, None)
+            }
+        };
+        Snapshot { lines, progress }
+    }
+
+    fn restart(&mut self) {
+        self.calibration.clear(Surface::White);
+        self.calibration.clear(Surface::Black);
+        self.step = Step::Place(Surface::White);
+    }
+}
+
+impl Page for LineCalibrationWizard {
+    fn render(&mut self, screen: &mut Screen) {
+        self.sample();
+
+        let snapshot = self.snapshot();
+        if self.drawn.as_ref() == Some(&snapshot) {
+            return;
+        }
+
+        let previous_font = screen.font();
+        let row_height = self.font.row_height();
+        let (width, _) = screen.size();
+        screen
+            .fill_screen(self.background)
+            .set_font_size(self.font)
+            .set_fore_color(self.color)
+            .set_back_color(self.background);
+        for (row, line) in snapshot.lines.iter().enumerate() {
+            screen.put_string(0, row as i32 * row_height, line);
+        }
+        if let Some(progress) = snapshot.progress {
+            let y = snapshot.lines.len() as i32 * row_height + 2;
+            screen.draw_frame(0, y, width - 1, y + 7, self.color);
+            if progress > 0 {
+                screen.fill_frame(1, y + 1, 1 + (width - 3) * progress / 100, y + 6, self.color);
+            }
+        }
+
+        screen.set_font_size(previous_font).refresh();
+        self.drawn = Some(snapshot);
+    }
+
+    fn on_enter(&mut self) {
+        self.drawn = None;
+    }
+
+    fn handle_nav(&mut self, nav: Nav) -> bool {
+        match (self.step, nav) {
+            (Step::Idle, Nav::Select) => self.restart(),
+            (Step::Idle, _) => return false,
+            (Step::Place(surface), Nav::Select) => {
+                self.calibration.clear(surface);
+                self.step = Step::Record(surface, Instant::now());
+            }
+            (Step::Place(_), Nav::Previous) => self.step = Step::Idle,
+            (Step::Review, Nav::Select) => {
+                self.calibration.save(&self.settings);
+                self.step = Step::Idle;
+            }
+            (Step::Review, Nav::Next) => self.restart(),
+            (Step::Review, Nav::Previous) => self.step = Step::Idle,
+            _ => {}
+        }
+        true
+    }
+}
@@ -0,0 +1,427 @@
+//! C interface for C and C++ programs.
+//!
+//! Built as a shared library with
+//!
+//! ```text
+//! cargo rustc --release --lib --features capi --crate-type cdylib
+//! ```
+//!
+//! the crate exports the functions of this module under their own names, declared in
+//! `include/uptechstar.h`. Building the `capi` feature generates the header with `cbindgen` into
+//! the build's `OUT_DIR` and warns if the committed copy differs from it; after changing this
+//! module, update the copy with
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/uptechstar.h
+//! ```
+//!
+//! Functions return [`UPTECH_OK`] or one of the negative `UPTECH_ERR_*` codes; after a failure,
+//! [`uptech_last_error`] describes it. A panic inside the library is reported as
+//! [`UPTECH_ERR_PANIC`] instead of unwinding into C.
+//!
+//! ```c
+//! #include <stdio.h>
+//! #include "uptechstar.h"
+//!
+//! int main(void) {
+//!     if (uptech_init("board.toml") != UPTECH_OK) {
+//!         fprintf(stderr, "init failed: %s\n", uptech_last_error());
+//!         return 1;
+//!     }
+//!
+//!     UptechSampler *sampler = uptech_sampler_start(200.0f);
+//!     UptechState state;
+//!     for (int i = 0; i < 1000; i++) {
+//!         if (uptech_sampler_latest(sampler, &state) == UPTECH_OK && state.has_mpu) {
+//!             printf("yaw %.1f\n", state.mpu.attitude[2]);
+//!         }
+//!     }
+//!
+//!     uptech_sampler_stop(sampler);
+//!     uptech_close();
+//!     return 0;
+//! }
+//! ```
+
+use crate::adc_io;
+use crate::board::{Board, BoardConfig};
+use crate::error::UptechError;
+use crate::mpu::{self, MpuFrame};
+use crate::telemetry::{BoardState, StateHub, StateReader};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+/// The call succeeded.
+pub const UPTECH_OK: c_int = 0;
+/// A hardware call returned a failure status code.
+pub const UPTECH_ERR_HARDWARE: c_int = -1;
+/// `libuptech.so` could not be loaded or lacks a required function.
+pub const UPTECH_ERR_LIBRARY: c_int = -2;
+/// The board configuration is invalid.
+pub const UPTECH_ERR_CONFIG: c_int = -3;
+/// Reading the configuration file failed.
+pub const UPTECH_ERR_IO: c_int = -4;
+/// A pointer was null, a string was not UTF-8 or a value was out of range.
+pub const UPTECH_ERR_INVALID_ARGUMENT: c_int = -5;
+/// [`uptech_init`] has not been called, or the board has no screen.
+pub const UPTECH_ERR_NOT_INITIALIZED: c_int = -6;
+/// The library panicked; it should not be used any further.
+pub const UPTECH_ERR_PANIC: c_int = -7;
+/// A peripheral was already initialized by another part of the program.
+pub const UPTECH_ERR_ALREADY_INITIALIZED: c_int = -8;
+
+/// Number of ADC channels in [`UptechState::adc`].
+pub const UPTECH_ADC_CHANNELS: usize = 10;
+
+/// A combined MPU6500 reading.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UptechMpuFrame {
+    /// Acceleration in g.
+    pub accel: [f32; 3],
+    /// Angular velocity in degrees per second.
+    pub gyro: [f32; 3],
+    /// Pitch, roll and yaw in degrees.
+    pub attitude: [f32; 3],
+}
+
+impl From<MpuFrame> for UptechMpuFrame {
+    fn from(frame: MpuFrame) -> Self {
+        UptechMpuFrame {
+            accel: frame.accel,
+            gyro: frame.gyro,
+            attitude: frame.attitude,
+        }
+    }
+}
+
+/// A snapshot published by a sampler. Sources that have not been read are flagged by the
+/// `has_*` fields.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UptechState {
+    /// Number of snapshots published so far, starting at 1.
+    pub sequence: u64,
+    /// Microseconds since the sampler was started.
+    pub timestamp_us: u64,
+    pub has_adc: bool,
+    /// Raw values of the 10 ADC channels.
+    pub adc: [i32; UPTECH_ADC_CHANNELS],
+    pub has_io: bool,
+    /// IO input levels, bit `n` for pin `n`.
+    pub io: u8,
+    pub has_mpu: bool,
+    pub mpu: UptechMpuFrame,
+}
+
+impl From<&BoardState> for UptechState {
+    fn from(state: &BoardState) -> Self {
+        UptechState {
+            sequence: state.sequence,
+            timestamp_us: state.timestamp.as_micros() as u64,
+            has_adc: state.adc.is_some(),
+            adc: state.adc.map(|frame| frame.0).unwrap_or_default(),
+            has_io: state.io.is_some(),
+            io: state.io.map(u8::from).unwrap_or_default(),
+            has_mpu: state.mpu.is_some(),
+            mpu: state.mpu.map(UptechMpuFrame::from).unwrap_or_default(),
+        }
+    }
+}
+
+/// Background sampling of the ADC, IO pins and MPU, created by [`uptech_sampler_start`].
+pub struct UptechSampler {
+    hub: StateHub,
+    reader: StateReader,
+}
+
+static BOARD: Mutex<Option<Board>> = Mutex::new(None);
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+struct Failure {
+    code: c_int,
+    message: String,
+}
+
+impl Failure {
+    fn new(code: c_int, message: impl Into<String>) -> Self {
+        Failure {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<UptechError> for Failure {
+    fn from(e: UptechError) -> Self {
+        let code = match e {
+            UptechError::Hardware { .. } => UPTECH_ERR_HARDWARE,
+            UptechError::LibraryLoad(_) | UptechError::MissingSymbol(_) => UPTECH_ERR_LIBRARY,
+            UptechError::Config(_) => UPTECH_ERR_CONFIG,
+            UptechError::Io(_) => UPTECH_ERR_IO,
+            UptechError::AlreadyInitialized(_) => UPTECH_ERR_ALREADY_INITIALIZED,
+        };
+        Failure::new(code, e.to_string())
+    }
+}
+
+fn set_last_error(message: &str) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+}
+
+/// Runs `call`, turning failures and panics into status codes.
+fn guard<F: FnOnce() -> Result<(), Failure>>(call: F) -> c_int {
+    match panic::catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(())) => UPTECH_OK,
+        Ok(Err(failure)) => {
+            set_last_error(&failure.message);
+            failure.code
+        }
+        Err(_) => {
+            set_last_error("panic inside uptechstar-rs");
+            UPTECH_ERR_PANIC
+        }
+    }
+}
+
+fn with_board<T>(use_board: impl FnOnce(&mut Board) -> Result<T, Failure>) -> Result<T, Failure> {
+    let mut board = BOARD.lock().unwrap_or_else(|e| e.into_inner());
+    match board.as_mut() {
+        Some(board) => use_board(board),
+        None => Err(Failure::new(UPTECH_ERR_NOT_INITIALIZED, "uptech_init has not been called")),
+    }
+}
+
+/// # Safety
+///
+/// `text` must be null or point to a NUL-terminated string.
+unsafe fn str_arg<'a>(text: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if text.is_null() {
+        return Err(Failure::new(UPTECH_ERR_INVALID_ARGUMENT, format!("{} is null", name)));
+    }
+    unsafe { CStr::from_ptr(text) }
+        .to_str()
+        .map_err(|_| Failure::new(UPTECH_ERR_INVALID_ARGUMENT, format!("{} is not UTF-8", name)))
+}
+
+/// # Safety
+///
+/// `out` must be null or point to a valid `T`.
+unsafe fn out_arg<'a, T>(out: *mut T) -> Result<&'a mut T, Failure> {
+    unsafe { out.as_mut() }.ok_or_else(|| Failure::new(UPTECH_ERR_INVALID_ARGUMENT, "output pointer is null"))
+}
+
+fn check(operation: &'static str, code: i32) -> Result<(), Failure> {
+    UptechError::check(operation, code).map_err(Failure::from)
+}
+
+/// Returns a description of the last failure on the calling thread, valid until the next
+/// failing call on that thread. Empty if nothing has failed yet.
+#[unsafe(no_mangle)]
+pub extern "C" fn uptech_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Initializes the board from the TOML file at `config_path`, or with the default
+/// configuration if it is null. Replaces a board initialized before.
+///
+/// # Safety
+///
+/// `config_path` must be null or point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uptech_init(config_path: *const c_char) -> c_int {
+    guard(|| {
+        let config = if config_path.is_null() {
+            BoardConfig::default()
+        } else {
+            BoardConfig::from_file(unsafe { str_arg(config_path, "config_path") }?)?
+        };
+
+        let mut board = BOARD.lock().unwrap_or_else(|e| e.into_inner());
+        board.take();
+        *board = Some(Board::init(config)?);
+        Ok(())
+    })
+}
+
+/// Releases the board initialized by [`uptech_init`]. Does nothing if there is none.
+#[unsafe(no_mangle)]
+pub extern "C" fn uptech_close() {
+    guard(|| {
+        BOARD.lock().unwrap_or_else(|e| e.into_inner()).take();
+        Ok(())
+    });
+}
+
+/// Reads the raw values of all 10 ADC channels into `out`.
+///
+/// # Safety
+///
+/// `out` must point to an array of at least 10 `int32_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uptech_adc_read(out: *mut i32) -> c_int {
+    guard(|| {
+        let out = unsafe { out_arg(out.cast::<[i32; UPTECH_ADC_CHANNELS]>()) }?;
+        *out = adc_io::adc_get_frame().map_err(|e| Failure::new(UPTECH_ERR_HARDWARE, e))?.0;
+        Ok(())
+    })
+}
+
+/// Reads the ADC channel called `name` in the board configuration, with its calibration
+/// applied.
+///
+/// # Safety
+///
+/// `name` must point to a NUL-terminated string and `out` to a `float`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uptech_adc_value(name: *const c_char, out: *mut f32) -> c_int {
+    guard(|| {
+        let name = unsafe { str_arg(name, "name") }?;
+        let out = unsafe { out_arg(out) }?;
+        *out = with_board(|board| Ok(board.adc_value(name)?))?;
+        Ok(())
+    })
+}
+
+/// Reads the input levels of all IO pins into `out`, bit `n` for pin `n`.
+///
+/// # Safety
+///
+/// `out` must point to a `uint8_t`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uptech_io_read(out: *mut u8) -> c_int {
+    guard(|| {
+        *unsafe { out_arg(out) }? = adc_io::io_get_frame().0;
+        Ok(())
+    })
+}
+
+/// Sets the output levels of all IO pins, bit `n` for pin `n`.
+#[unsafe(no_mangle)]
+pub extern "C" fn uptech_io_write(levels: u8) -> c_int {
+    guard(|| check("adc_io_SetAll", adc_io::set_all_io_levels(levels as u32)))
+}
+
+/// Reads acceleration, angular velocity and attitude into `out`.
+///
+/// # Safety
+///
+/// `out` must point to an `UptechMpuFrame`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uptech_mpu_read(out: *mut UptechMpuFrame) -> c_int {
+    guard(|| {
+        let out = unsafe { out_arg(out) }?;
+        *out = mpu::mpu6500_get_sample()
+            .map_err(|code| Failure::new(UPTECH_ERR_HARDWARE, format!("MPU6500 read failed with status {}", code)))?
+            .into();
+        Ok(())
+    })
+}
+
+/// Fills the board's screen with `color`, as `0xRRGGBB`.
+#[unsafe(no_mangle)]
+pub extern "C" fn uptech_screen_fill(color: u32) -> c_int {
+    guard(|| {
+        with_board(|board| {
+            screen(board)?.fill_screen(color);
+            Ok(())
+        })
+    })
+}
+
+/// Draws `text` at `x`, `y` on the board's screen in `color`, as `0xRRGGBB`. Call
+/// [`uptech_screen_refresh`] to show it.
+///
+/// # Safety
+///
+/// `text` must point to a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uptech_draw_text(x: i32, y: i32, text: *const c_char, color: u32) -> c_int {
+    guard(|| {
+        let text = unsafe { str_arg(text, "text") }?;
+        with_board(|board| {
+            screen(board)?.set_fore_color(color).put_string(x, y, text);
+            Ok(())
+        })
+    })
+}
+
+/// Shows what has been drawn on the board's screen.
+#[unsafe(no_mangle)]
+pub extern "C" fn uptech_screen_refresh() -> c_int {
+    guard(|| {
+        with_board(|board| {
+            screen(board)?.refresh();
+            Ok(())
+        })
+    })
+}
+
+fn screen(board: &mut Board) -> Result<&mut crate::display::Screen, Failure> {
+    board
+        .screen()
+        .ok_or_else(|| Failure::new(UPTECH_ERR_NOT_INITIALIZED, "the board configuration has no screen"))
+}
+
+/// Starts sampling the ADC, IO pins and MPU `rate_hz` times per second on a background thread.
+/// Returns null if `rate_hz` is not a positive, finite number.
+#[unsafe(no_mangle)]
+pub extern "C" fn uptech_sampler_start(rate_hz: f32) -> *mut UptechSampler {
+    let mut sampler = None;
+    let code = guard(|| {
+        if !(rate_hz.is_finite() && rate_hz > 0.0) {
+            return Err(Failure::new(UPTECH_ERR_INVALID_ARGUMENT, "rate_hz must be positive"));
+        }
+
+        let mut hub = StateHub::new(rate_hz).with_adc(true).with_io(true).with_mpu(true);
+        hub.start();
+        let reader = hub.reader();
+        sampler = Some(Box::new(UptechSampler { hub, reader }));
+        Ok(())
+    });
+
+    match sampler {
+        Some(sampler) if code == UPTECH_OK => Box::into_raw(sampler),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Copies the latest snapshot of `sampler` into `out`. The snapshot's `sequence` is 0 until
+/// the first one has been taken.
+///
+/// # Safety
+///
+/// `sampler` must come from [`uptech_sampler_start`] and not be stopped yet; `out` must point
+/// to an `UptechState`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uptech_sampler_latest(sampler: *const UptechSampler, out: *mut UptechState) -> c_int {
+    guard(|| {
+        let sampler = unsafe { sampler.as_ref() }
+            .ok_or_else(|| Failure::new(UPTECH_ERR_INVALID_ARGUMENT, "sampler is null"))?;
+        *unsafe { out_arg(out) }? = UptechState::from(&sampler.reader.latest());
+        Ok(())
+    })
+}
+
+/// Stops `sampler` and frees it. Does nothing if `sampler` is null.
+///
+/// # Safety
+///
+/// `sampler` must come from [`uptech_sampler_start`] and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn uptech_sampler_stop(sampler: *mut UptechSampler) {
+    if sampler.is_null() {
+        return;
+    }
+    guard(|| {
+        let mut sampler = unsafe { Box::from_raw(sampler) };
+        sampler.hub.stop();
+        Ok(())
+    });
+}
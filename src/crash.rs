@@ -0,0 +1,342 @@
+//! Crash dumps for postmortem analysis.
+//!
+//! A failure in the field rarely happens with a debugger attached. A [`CrashRecorder`] keeps the
+//! last board states published by a [`StateHub`](crate::telemetry::StateHub) and writes them,
+//! together with the [health report](crate::health::report), the
+//! [library capabilities](crate::extern_lib::capabilities) and the board configuration, to a
+//! plain text file:
+//!
+//! - on demand, with [`CrashRecorder::dump`], for example when a control loop gives up on an
+//!   error, and
+//! - when the program panics, once [`CrashRecorder::install_panic_hook`] has been called.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::board::{Board, BoardConfig};
+//! use uptechstar_rs::crash::CrashRecorder;
+//! use uptechstar_rs::telemetry::StateHub;
+//!
+//! let config = BoardConfig::default();
+//! let board = Board::init(config.clone()).unwrap();
+//!
+//! let mut hub = StateHub::new(100.0).with_adc(true).with_mpu(true);
+//! hub.start();
+//!
+//! let mut recorder = CrashRecorder::new("/var/log/robot")
+//!     .with_capacity(500)
+//!     .with_config(config)
+//!     .with_state(hub.reader());
+//! recorder.start().install_panic_hook();
+//!
+//! if let Err(e) = board.adc_value("battery") {
+//!     let path = recorder.dump(&format!("battery read failed: {}", e)).unwrap();
+//!     eprintln!("wrote {}", path.display());
+//! }
+//! ```
+
+use crate::board::BoardConfig;
+use crate::extern_lib::{self, Capabilities};
+use crate::health::{self, HealthReport};
+use crate::telemetry::{BoardState, StateReader, TelemetryFrame};
+use log::{debug, error, info};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the recorder thread looks for a new board state. Faster hubs are thinned out.
+const POLL_PERIOD: Duration = Duration::from_millis(5);
+
+/// Everything known about the board at the time of a failure.
+///
+/// The [`Display`](fmt::Display) output is the content of a crash dump file.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::IoFrame;
+/// use uptechstar_rs::crash::CrashDump;
+/// use uptechstar_rs::telemetry::TelemetryFrame;
+///
+/// let mut dump = CrashDump::capture("motor stalled");
+/// dump.frames.push(TelemetryFrame { sequence: 41, io: Some(IoFrame(0b11)), ..Default::default() });
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dump.write_to(dir.path()).unwrap();
+/// let text = std::fs::read_to_string(&path).unwrap();
+/// assert!(text.contains("reason: motor stalled"));
+/// assert!(text.contains("sequence: 41"));
+///
+/// // Written again in the same millisecond, the dump gets a file of its own.
+/// assert_ne!(dump.write_to(dir.path()).unwrap(), path);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrashDump {
+    /// Why the dump was written, e.g. the panic message.
+    pub reason: String,
+    /// When the dump was captured.
+    pub time: SystemTime,
+    /// The latest board state, if a state hub is recorded.
+    pub state: Option<BoardState>,
+    /// The recorded board states, oldest first.
+    pub frames: Vec<TelemetryFrame>,
+    /// Failure counters per subsystem.
+    pub health: HealthReport,
+    /// What the loaded `libuptech.so` supports.
+    pub capabilities: Capabilities,
+    /// The board configuration, if one was given.
+    pub config: Option<BoardConfig>,
+}
+
+impl CrashDump {
+    /// Captures the health report and library capabilities now, without state or frames.
+    pub fn capture(reason: &str) -> Self {
+        CrashDump {
+            reason: reason.to_string(),
+            time: SystemTime::now(),
+            state: None,
+            frames: Vec::new(),
+            health: health::report(),
+            capabilities: extern_lib::capabilities().clone(),
+            config: None,
+        }
+    }
+
+    /// Writes the dump to a new `crash-<unix time in ms>.txt` file in `dir`, creating `dir`
+    /// if needed, and returns the file's path. Existing files are never overwritten: if the
+    /// name is taken, as by another dump in the same millisecond, `-1`, `-2`, ... is appended.
+    pub fn write_to<P: AsRef<Path>>(&self, dir: P) -> io::Result<PathBuf> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+
+        let millis = self.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        for attempt in 0u32.. {
+            let path = match attempt {
+                0 => dir.join(format!("crash-{}.txt", millis)),
+                _ => dir.join(format!("crash-{}-{}.txt", millis, attempt)),
+            };
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(self.to_string().as_bytes())?;
+                    return Ok(path);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!("Ran out of crash dump file names")
+    }
+}
+
+impl fmt::Display for CrashDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let since_epoch = self.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+        writeln!(f, "uptechstar-rs {} crash dump", env!("CARGO_PKG_VERSION"))?;
+        writeln!(f, "reason: {}", self.reason)?;
+        writeln!(f, "time: {}.{:03}", since_epoch.as_secs(), since_epoch.subsec_millis())?;
+        writeln!(
+            f,
+            "library: {} ({})",
+            extern_lib::library_version().unwrap_or("unknown version"),
+            extern_lib::library_checksum().unwrap_or("no checksum")
+        )?;
+
+        writeln!(f, "\n[health]\n{:#?}", self.health)?;
+        writeln!(f, "\n[capabilities]\n{:#?}", self.capabilities)?;
+        match &self.config {
+            Some(config) => writeln!(f, "\n[config]\n{:#?}", config)?,
+            None => writeln!(f, "\n[config]\nnone")?,
+        }
+        match &self.state {
+            Some(state) => writeln!(f, "\n[state]\n{:#?}", state)?,
+            None => writeln!(f, "\n[state]\nnone")?,
+        }
+
+        writeln!(f, "\n[frames] {} recorded, oldest first", self.frames.len())?;
+        for frame in &self.frames {
+            writeln!(f, "{:?}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the recent history of a state hub and writes [`CrashDump`]s.
+pub struct CrashRecorder {
+    dir: PathBuf,
+    capacity: usize,
+    config: Option<BoardConfig>,
+    state: Option<StateReader>,
+    frames: Arc<Mutex<VecDeque<TelemetryFrame>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CrashRecorder {
+    /// Creates a stopped recorder writing dumps to `dir` and keeping the last 200 states.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        CrashRecorder {
+            dir: dir.into(),
+            capacity: 200,
+            config: None,
+            state: None,
+            frames: Arc::new(Mutex::new(VecDeque::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Sets how many of the most recent states are kept.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "Crash recorder capacity must be positive");
+        self.capacity = capacity;
+        self
+    }
+
+    /// Includes `config` in every dump.
+    pub fn with_config(mut self, config: BoardConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Records the states published through `state`.
+    pub fn with_state(mut self, state: StateReader) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Returns `true` while the recording thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts recording states in the background. Does nothing if already running or if no
+    /// [state](CrashRecorder::with_state) is set.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        let Some(state) = self.state.clone() else {
+            return self;
+        };
+        if self.is_running() {
+            return self;
+        }
+
+        self.running.store(true, Ordering::Release);
+
+        let capacity = self.capacity;
+        let frames = Arc::clone(&self.frames);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-crash-recorder".into())
+                .spawn(move || {
+                    let mut last_sequence = 0;
+
+                    while running.load(Ordering::Acquire) {
+                        let snapshot = state.latest();
+                        if snapshot.sequence != last_sequence {
+                            last_sequence = snapshot.sequence;
+
+                            let mut frames = frames.lock().unwrap_or_else(|e| e.into_inner());
+                            if frames.len() == capacity {
+                                frames.pop_front();
+                            }
+                            frames.push_back(TelemetryFrame::from(&snapshot));
+                        }
+
+                        thread::sleep(POLL_PERIOD);
+                    }
+
+                    debug!("Crash recorder thread exited");
+                })
+                .expect("Failed to spawn crash recorder thread"),
+        );
+
+        info!("Recording the last {} board states for crash dumps in {}", capacity, self.dir.display());
+        self
+    }
+
+    /// Stops recording. The states recorded so far are kept.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+
+        self
+    }
+
+    /// Captures a dump with the recorded states, without writing it.
+    pub fn snapshot(&self, reason: &str) -> CrashDump {
+        capture(reason, self.config.as_ref(), self.state.as_ref(), &self.frames)
+    }
+
+    /// Writes a dump to the recorder's directory and returns the file's path.
+    pub fn dump(&self, reason: &str) -> io::Result<PathBuf> {
+        let path = self.snapshot(reason).write_to(&self.dir)?;
+        info!("Crash dump written to {}", path.display());
+        Ok(path)
+    }
+
+    /// Makes a panic anywhere in the program write a dump before the previously installed
+    /// hook, by default the one printing the panic message, runs. The hook keeps working after
+    /// the recorder is dropped, but no new states are recorded then.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn install_panic_hook(&mut self) -> &mut Self {
+        let dir = self.dir.clone();
+        let config = self.config.clone();
+        let state = self.state.clone();
+        let frames = Arc::clone(&self.frames);
+        let previous = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |panic| {
+            let dump = capture(&panic.to_string(), config.as_ref(), state.as_ref(), &frames);
+            match dump.write_to(&dir) {
+                Ok(path) => error!("Crash dump written to {}", path.display()),
+                Err(e) => error!("Failed to write crash dump to {}: {}", dir.display(), e),
+            }
+            previous(panic);
+        }));
+
+        self
+    }
+}
+
+impl Drop for CrashRecorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn capture(
+    reason: &str,
+    config: Option<&BoardConfig>,
+    state: Option<&StateReader>,
+    frames: &Mutex<VecDeque<TelemetryFrame>>,
+) -> CrashDump {
+    CrashDump {
+        state: state.map(StateReader::latest),
+        frames: frames.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect(),
+        config: config.cloned(),
+        ..CrashDump::capture(reason)
+    }
+}
@@ -0,0 +1,461 @@
+//! Sharing one board between several processes over a Unix domain socket.
+//!
+//! `libuptech.so` keeps its `adc_io_open` state per process, so two applications (say, a UI
+//! and a control loop) opening the board independently end up fighting over it. A [`Daemon`]
+//! owns the hardware instead and answers requests from any number of [`DaemonClient`]s.
+//!
+//! [`DaemonClient`] implements [`Backend`], so a client process only installs it once with
+//! [`backend::set_backend`] and keeps using the regular
+//! [`adc_io`](crate::adc_io) and [`mpu`](crate::mpu) functions.
+//!
+//! # Protocol
+//!
+//! Every message is a frame made of a little-endian `u32` payload length followed by the
+//! payload. A request payload is a one-byte opcode followed by its arguments; a response
+//! payload is the `i32` status code returned by the hardware call followed by any output
+//! values. All numbers are little-endian.
+//!
+//! | Opcode | Request             | Response payload after the status |
+//! |--------|---------------------|-----------------------------------|
+//! | `0`    | ping                | -                                 |
+//! | `1`    | ADC get all         | 10 × `i32`                        |
+//! | `2`    | IO get all          | `u8` levels                       |
+//! | `3`    | IO set all `u32`    | -                                 |
+//! | `4`    | IO flip `u32`       | -                                 |
+//! | `5`    | IO mode get all     | `u8` modes                        |
+//! | `6`    | IO mode set `u32 i32` | -                               |
+//! | `7`    | MPU init            | -                                 |
+//! | `8`    | MPU accel           | 3 × `f32`                         |
+//! | `9`    | MPU gyro            | 3 × `f32`                         |
+//! | `10`   | MPU attitude        | 3 × `f32`                         |
+//! | `11`   | MPU gyro FSR        | `u16`                             |
+//! | `12`   | MPU accel FSR       | `u8`                              |
+//! | `13`   | MPU set gyro FSR `u32` | -                              |
+//! | `14`   | MPU set accel FSR `i32` | -                             |
+//!
+//! Unknown opcodes and malformed requests are answered with status `-1`.
+//!
+//! # Examples
+//!
+//! The daemon process:
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::daemon::{DEFAULT_SOCKET_PATH, Daemon};
+//!
+//! Daemon::bind(DEFAULT_SOCKET_PATH).unwrap().run().unwrap();
+//! ```
+//!
+//! Each client process:
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use uptechstar_rs::adc_io;
+//! use uptechstar_rs::backend;
+//! use uptechstar_rs::daemon::{DEFAULT_SOCKET_PATH, DaemonClient};
+//!
+//! let client = DaemonClient::connect(DEFAULT_SOCKET_PATH).unwrap();
+//! backend::set_backend(Arc::new(client));
+//!
+//! let mut adc_data = [0; 10];
+//! adc_io::adc_get_all_channels(&mut adc_data).unwrap();
+//! ```
+
+use crate::backend::{self, Backend};
+use log::{debug, error, info, warn};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+
+/// Socket path used by convention when none is configured.
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/uptech.sock";
+
+/// Largest payload either side accepts; every message of the protocol is far smaller.
+const MAX_FRAME: u32 = 256;
+
+/// Serializes hardware access across client connections.
+static HARDWARE: Mutex<()> = Mutex::new(());
+
+const PING: u8 = 0;
+const ADC_GET_ALL: u8 = 1;
+const IO_GET_ALL: u8 = 2;
+const IO_SET_ALL: u8 = 3;
+const IO_FLIP: u8 = 4;
+const IO_MODE_GET_ALL: u8 = 5;
+const IO_MODE_SET: u8 = 6;
+const MPU_INIT: u8 = 7;
+const MPU_ACCEL: u8 = 8;
+const MPU_GYRO: u8 = 9;
+const MPU_ATTITUDE: u8 = 10;
+const MPU_GYRO_FSR: u8 = 11;
+const MPU_ACCEL_FSR: u8 = 12;
+const MPU_SET_GYRO_FSR: u8 = 13;
+const MPU_SET_ACCEL_FSR: u8 = 14;
+
+fn write_frame(stream: &mut UnixStream, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload);
+    stream.write_all(&frame)
+}
+
+fn read_frame(stream: &mut UnixStream) -> io::Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+
+    let length = u32::from_le_bytes(length);
+    if length > MAX_FRAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds the {} byte limit", length, MAX_FRAME),
+        ));
+    }
+
+    let mut payload = vec![0u8; length as usize];
+    stream.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// Reads little-endian values off the front of a payload.
+struct Cursor<'a>(&'a [u8]);
+
+impl Cursor<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, tail) = self.0.split_first_chunk::<N>()?;
+        self.0 = tail;
+        Some(*head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.take().map(i32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+}
+
+/// Owns the board and serves [`DaemonClient`] requests on a Unix socket.
+///
+/// The daemon forwards requests to the backend installed in its own process, which is the
+/// real hardware unless configured otherwise. Calls from different clients are serialized.
+pub struct Daemon {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl Daemon {
+    /// Binds the daemon socket at `path`, replacing a stale socket file left by a previous run.
+    ///
+    /// Fails with [`io::ErrorKind::AddrInUse`] if another daemon answers on `path`, and with
+    /// [`io::ErrorKind::AlreadyExists`] if something other than a socket is there.
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+            if !metadata.file_type().is_socket() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("another daemon is already listening on {}", path.display()),
+                ));
+            }
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        info!("Board daemon listening on {}", path.display());
+
+        Ok(Daemon { listener, path })
+    }
+
+    /// Returns the socket path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Opens the ADC/IO peripheral and serves clients until accepting a connection fails.
+    ///
+    /// Each client is handled on its own thread. The peripheral is closed before returning.
+    pub fn run(&self) -> io::Result<()> {
+        if backend::current().adc_io_open() < 0 {
+            warn!("Daemon failed to open the ADC/IO peripheral");
+        }
+
+        let result = self.accept_loop();
+
+        backend::current().adc_io_close();
+        result
+    }
+
+    fn accept_loop(&self) -> io::Result<()> {
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            debug!("Daemon client connected");
+
+            thread::Builder::new()
+                .name("uptech-daemon-client".into())
+                .spawn(move || {
+                    if let Err(e) = serve(stream) {
+                        error!("Daemon client failed: {}", e);
+                    }
+                    debug!("Daemon client disconnected");
+                })
+                .expect("Failed to spawn daemon client thread");
+        }
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Answers requests on one connection until the client hangs up.
+fn serve(mut stream: UnixStream) -> io::Result<()> {
+    loop {
+        let request = match read_frame(&mut stream) {
+            Ok(request) => request,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let response = {
+            let _guard = HARDWARE.lock().unwrap_or_else(|e| e.into_inner());
+            dispatch(&*backend::current(), &request).unwrap_or_else(|| (-1i32).to_le_bytes().to_vec())
+        };
+        write_frame(&mut stream, &response)?;
+    }
+}
+
+/// Executes one request, returning `None` if it is malformed.
+fn dispatch(backend: &dyn Backend, request: &[u8]) -> Option<Vec<u8>> {
+    let mut args = Cursor(request);
+    let opcode = args.u8()?;
+
+    let mut response = Vec::with_capacity(44);
+    let status = match opcode {
+        PING => 0,
+        ADC_GET_ALL => {
+            let mut adc_data = [0; 10];
+            let status = backend.adc_get_all(&mut adc_data);
+            adc_data.iter().for_each(|value| response.extend_from_slice(&value.to_le_bytes()));
+            status
+        }
+        IO_GET_ALL => {
+            response.push(backend.io_get_all());
+            0
+        }
+        IO_SET_ALL => backend.io_set_all(args.u32()?),
+        IO_FLIP => backend.io_flip(args.u32()?),
+        IO_MODE_GET_ALL => {
+            let mut modes = 0;
+            let status = backend.io_mode_get_all(&mut modes);
+            response.push(modes);
+            status
+        }
+        IO_MODE_SET => backend.io_mode_set(args.u32()?, args.i32()?),
+        MPU_INIT => backend.mpu_init(),
+        MPU_ACCEL | MPU_GYRO | MPU_ATTITUDE => {
+            let mut data = [0.0; 3];
+            let status = match opcode {
+                MPU_ACCEL => backend.mpu_get_accel(&mut data),
+                MPU_GYRO => backend.mpu_get_gyro(&mut data),
+                _ => backend.mpu_get_attitude(&mut data),
+            };
+            data.iter().for_each(|value| response.extend_from_slice(&value.to_le_bytes()));
+            status
+        }
+        MPU_GYRO_FSR => {
+            let mut fsr = 0;
+            let status = backend.mpu_get_gyro_fsr(&mut fsr);
+            response.extend_from_slice(&fsr.to_le_bytes());
+            status
+        }
+        MPU_ACCEL_FSR => {
+            let mut fsr = 0;
+            let status = backend.mpu_get_accel_fsr(&mut fsr);
+            response.push(fsr);
+            status
+        }
+        MPU_SET_GYRO_FSR => backend.mpu_set_gyro_fsr(args.u32()?),
+        MPU_SET_ACCEL_FSR => backend.mpu_set_accel_fsr(args.i32()?),
+        _ => return None,
+    };
+
+    response.splice(0..0, status.to_le_bytes());
+    Some(response)
+}
+
+/// A [`Backend`] forwarding every call to a [`Daemon`].
+///
+/// `adc_io_open` and `adc_io_close` are answered locally, since the daemon keeps the
+/// peripheral open for its whole lifetime. Transport failures are logged and reported as
+/// status `-1` (or all-low levels for `io_get_all`).
+pub struct DaemonClient {
+    stream: Mutex<UnixStream>,
+}
+
+impl DaemonClient {
+    /// Connects to the daemon listening at `path` and checks that it responds.
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let client = DaemonClient {
+            stream: Mutex::new(UnixStream::connect(path.as_ref())?),
+        };
+        client.request(&[PING])?;
+
+        info!("Connected to board daemon at {}", path.as_ref().display());
+        Ok(client)
+    }
+
+    /// Sends one request and returns the status code and the remaining response payload.
+    fn request(&self, request: &[u8]) -> io::Result<(i32, Vec<u8>)> {
+        let mut stream = self.stream.lock().unwrap_or_else(|e| e.into_inner());
+        write_frame(&mut stream, request)?;
+        let mut response = read_frame(&mut stream)?;
+
+        let status = Cursor(&response)
+            .i32()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "truncated daemon response"))?;
+        response.drain(..4);
+        Ok((status, response))
+    }
+
+    /// Sends a request, decoding its output with `decode` when the call succeeded.
+    fn call<F: FnOnce(&mut Cursor) -> Option<()>>(&self, request: &[u8], decode: F) -> i32 {
+        match self.request(request) {
+            Ok((status, payload)) => {
+                if decode(&mut Cursor(&payload)).is_none() {
+                    error!("Malformed daemon response to opcode {}", request[0]);
+                    return -1;
+                }
+                status
+            }
+            Err(e) => {
+                error!("Board daemon request {} failed: {}", request[0], e);
+                -1
+            }
+        }
+    }
+
+    fn call_with(&self, opcode: u8, args: &[&[u8]]) -> i32 {
+        let request: Vec<u8> = std::iter::once(opcode)
+            .chain(args.iter().flat_map(|arg| arg.iter().copied()))
+            .collect();
+        self.call(&request, |_| Some(()))
+    }
+
+    fn call_f32x3(&self, opcode: u8, data: &mut [f32; 3]) -> i32 {
+        self.call(&[opcode], |payload| {
+            for value in data.iter_mut() {
+                *value = payload.f32()?;
+            }
+            Some(())
+        })
+    }
+}
+
+impl Backend for DaemonClient {
+    fn adc_io_open(&self) -> i32 {
+        1
+    }
+
+    fn adc_io_close(&self) -> i32 {
+        0
+    }
+
+    fn adc_get_all(&self, adc_data: &mut [i32; 10]) -> i32 {
+        self.call(&[ADC_GET_ALL], |payload| {
+            for value in adc_data.iter_mut() {
+                *value = payload.i32()?;
+            }
+            Some(())
+        })
+    }
+
+    fn io_get_all(&self) -> u8 {
+        let mut levels = 0;
+        self.call(&[IO_GET_ALL], |payload| {
+            levels = payload.u8()?;
+            Some(())
+        });
+        levels
+    }
+
+    fn io_set_all(&self, levels: u32) -> i32 {
+        self.call_with(IO_SET_ALL, &[&levels.to_le_bytes()])
+    }
+
+    fn io_flip(&self, index: u32) -> i32 {
+        self.call_with(IO_FLIP, &[&index.to_le_bytes()])
+    }
+
+    fn io_mode_get_all(&self, modes: &mut u8) -> i32 {
+        self.call(&[IO_MODE_GET_ALL], |payload| {
+            *modes = payload.u8()?;
+            Some(())
+        })
+    }
+
+    fn io_mode_set(&self, index: u32, mode: i32) -> i32 {
+        self.call_with(IO_MODE_SET, &[&index.to_le_bytes(), &mode.to_le_bytes()])
+    }
+
+    fn mpu_init(&self) -> i32 {
+        self.call_with(MPU_INIT, &[])
+    }
+
+    fn mpu_get_accel(&self, accel_data: &mut [f32; 3]) -> i32 {
+        self.call_f32x3(MPU_ACCEL, accel_data)
+    }
+
+    fn mpu_get_gyro(&self, gyro_data: &mut [f32; 3]) -> i32 {
+        self.call_f32x3(MPU_GYRO, gyro_data)
+    }
+
+    fn mpu_get_attitude(&self, attitude_data: &mut [f32; 3]) -> i32 {
+        self.call_f32x3(MPU_ATTITUDE, attitude_data)
+    }
+
+    fn mpu_get_gyro_fsr(&self, fsr: &mut u16) -> i32 {
+        self.call(&[MPU_GYRO_FSR], |payload| {
+            *fsr = payload.u16()?;
+            Some(())
+        })
+    }
+
+    fn mpu_get_accel_fsr(&self, fsr: &mut u8) -> i32 {
+        self.call(&[MPU_ACCEL_FSR], |payload| {
+            *fsr = payload.u8()?;
+            Some(())
+        })
+    }
+
+    fn mpu_set_gyro_fsr(&self, fsr: u32) -> i32 {
+        self.call_with(MPU_SET_GYRO_FSR, &[&fsr.to_le_bytes()])
+    }
+
+    fn mpu_set_accel_fsr(&self, fsr: i32) -> i32 {
+        self.call_with(MPU_SET_ACCEL_FSR, &[&fsr.to_le_bytes()])
+    }
+}
@@ -0,0 +1,617 @@
+//! Turnkey data acquisition sessions.
+//!
+//! A [`Session`] bundles everything a recording needs: the sources to read (selected ADC
+//! channels, the IO levels, the MPU6500), a sample rate, when to start and stop, and where the
+//! records go. Each tick the sources are read together into one [`TelemetryFrame`], so a CSV
+//! row or a binary frame holds a consistent snapshot of all of them.
+//!
+//! # Examples
+//!
+//! Record two line sensors and the MPU at 100 Hz for 10 seconds, starting once the robot
+//! leaves the start box and sensor 3 sees the line:
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use uptechstar_rs::daq::{Session, Sink, Trigger};
+//!
+//! let mut session = Session::new(100.0, Sink::Csv("run.csv".into()))
+//!     .with_adc_channels(&[2, 3])
+//!     .with_mpu(true)
+//!     .with_start_trigger(Trigger::AdcBelow { channel: 3, level: 1000 })
+//!     .with_duration(Duration::from_secs(10));
+//!
+//! session.start().unwrap();
+//! session.wait().unwrap();
+//! println!("{} records", session.records());
+//! ```
+
+use crate::adc_io::{self, AdcFrame, IoFrame};
+use crate::error::{Result, UptechError};
+use crate::mpu;
+use crate::sampler::{Ticker, period_from_rate};
+use crate::telemetry::TelemetryFrame;
+use log::{debug, error, info, warn};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often file sinks are flushed to disk while recording.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where the records of a [`Session`] go.
+///
+/// File sinks are created, or truncated, when the session starts.
+#[derive(Debug, Clone)]
+pub enum Sink {
+    /// A CSV file with a header row and one column per selected ADC channel, the IO levels as
+    /// a bitmask and the nine MPU values, as enabled. A source whose read failed during a tick
+    /// leaves its columns empty.
+    Csv(PathBuf),
+    /// A file of binary [`TelemetryFrame`]s, readable with
+    /// [`telemetry::frame::read_frames`](crate::telemetry::frame::read_frames) and replayable
+    /// with [`ReplayBackend`](crate::replay::ReplayBackend).
+    Frames(PathBuf),
+    /// [`TelemetryFrame`] datagrams to a UDP address, as received by `uptech-scope` and
+    /// [`telemetry::udp::Receiver`](crate::telemetry::udp::Receiver).
+    Udp(SocketAddr),
+    /// The frames themselves, for processing in the same program.
+    Channel(Sender<TelemetryFrame>),
+}
+
+/// A condition on a record, deciding when a [`Session`] starts or stops recording.
+///
+/// A condition on a source that is not enabled, or whose read failed, is not met.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::{AdcFrame, IoFrame};
+/// use uptechstar_rs::daq::Trigger;
+/// use uptechstar_rs::telemetry::TelemetryFrame;
+///
+/// let frame = TelemetryFrame {
+///     adc: Some(AdcFrame([0, 3000, 0, 0, 0, 0, 0, 0, 0, 0])),
+///     io: Some(IoFrame(0b0100)),
+///     ..Default::default()
+/// };
+///
+/// assert!(Trigger::AdcAbove { channel: 1, level: 2048 }.is_met(&frame));
+/// assert!(!Trigger::AdcBelow { channel: 1, level: 2048 }.is_met(&frame));
+/// assert!(Trigger::IoHigh(2).is_met(&frame));
+/// assert!(Trigger::custom(|frame| frame.mpu.is_none()).is_met(&frame));
+/// ```
+#[derive(Clone)]
+pub enum Trigger {
+    /// ADC `channel` reads more than `level`.
+    AdcAbove { channel: usize, level: i32 },
+    /// ADC `channel` reads less than `level`.
+    AdcBelow { channel: usize, level: i32 },
+    /// The IO pin is high.
+    IoHigh(u8),
+    /// The IO pin is low.
+    IoLow(u8),
+    /// Any condition, see [`custom`](Self::custom).
+    Custom(Arc<dyn Fn(&TelemetryFrame) -> bool + Send + Sync>),
+}
+
+impl Trigger {
+    /// Wraps a closure as a trigger.
+    pub fn custom<F: Fn(&TelemetryFrame) -> bool + Send + Sync + 'static>(condition: F) -> Self {
+        Trigger::Custom(Arc::new(condition))
+    }
+
+    /// Returns `true` if `frame` meets the condition.
+    pub fn is_met(&self, frame: &TelemetryFrame) -> bool {
+        match self {
+            Trigger::AdcAbove { channel, level } => frame.adc.is_some_and(|adc| adc.0[*channel] > *level),
+            Trigger::AdcBelow { channel, level } => frame.adc.is_some_and(|adc| adc.0[*channel] < *level),
+            Trigger::IoHigh(pin) => frame.io.is_some_and(|levels| levels.level(*pin as u32)),
+            Trigger::IoLow(pin) => frame.io.is_some_and(|levels| !levels.level(*pin as u32)),
+            Trigger::Custom(condition) => condition(frame),
+        }
+    }
+}
+
+/// What a [`Session`] is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionState {
+    /// Not started yet, or stopped.
+    Idle,
+    /// Reading the sources, waiting for the start trigger.
+    Armed,
+    /// Writing records.
+    Recording,
+    /// Paused; no sources are read.
+    Paused,
+    /// Ended by its duration or stop trigger.
+    Finished,
+}
+
+/// What to read and when to record, shared with the recording thread.
+#[derive(Clone)]
+struct Config {
+    period: Duration,
+    channels: Vec<usize>,
+    io: bool,
+    mpu: bool,
+    duration: Option<Duration>,
+    start_trigger: Option<Trigger>,
+    stop_trigger: Option<Trigger>,
+}
+
+impl Config {
+    /// Fails if `trigger` reads a source that is not recorded.
+    fn check(&self, trigger: &Trigger) -> Result<()> {
+        match *trigger {
+            Trigger::AdcAbove { channel, .. } | Trigger::AdcBelow { channel, .. } => {
+                if self.channels.is_empty() {
+                    return Err(UptechError::Config(format!(
+                        "trigger on ADC channel {} needs ADC recording, enable it with `with_adc_channels`",
+                        channel
+                    )));
+                }
+                if channel >= AdcFrame::CHANNELS {
+                    return Err(UptechError::Config(format!("trigger ADC channel must be below 10, got {}", channel)));
+                }
+            }
+            Trigger::IoHigh(pin) | Trigger::IoLow(pin) => {
+                if !self.io {
+                    return Err(UptechError::Config(format!(
+                        "trigger on IO pin {} needs IO recording, enable it with `with_io`",
+                        pin
+                    )));
+                }
+                if pin as u32 >= IoFrame::PINS {
+                    return Err(UptechError::Config(format!("trigger IO pin must be in 0..8, got {}", pin)));
+                }
+            }
+            Trigger::Custom(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// Progress of the recording thread.
+#[derive(Default)]
+struct Status {
+    triggered: bool,
+    finished: bool,
+    records: u64,
+}
+
+/// A data acquisition session: sources, a sample rate, start and stop conditions and a
+/// [`Sink`], recorded on a background thread.
+///
+/// Without a start trigger recording begins right away, and without a duration or stop
+/// trigger it goes on until [`stop`](Self::stop). The duration counts from the start trigger
+/// and leaves out paused time. A failed read leaves its source out of the tick's record;
+/// the wrapper functions already log it.
+///
+/// Binary frames and UDP telemetry always carry all 10 ADC channels, as the frame format
+/// has no per-channel flags; the channel selection shapes the CSV columns. Triggers can use
+/// any channel as long as ADC recording is enabled, and [`start`](Self::start) fails if one
+/// reads a source that is not recorded.
+///
+/// Stopping or dropping the session ends the recording and flushes the sink. Starting it
+/// again begins a new recording, overwriting a file sink. Configuring a running session does
+/// not change the current recording, only the next one.
+pub struct Session {
+    config: Arc<Config>,
+    sink: Sink,
+    running: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    status: Arc<Mutex<Status>>,
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl Session {
+    /// Creates a session sampling at `rate_hz` into `sink`, with no sources enabled.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn new(rate_hz: f32, sink: Sink) -> Self {
+        Session {
+            config: Arc::new(Config {
+                period: period_from_rate(rate_hz),
+                channels: Vec::new(),
+                io: false,
+                mpu: false,
+                duration: None,
+                start_trigger: None,
+                stop_trigger: None,
+            }),
+            sink,
+            running: Arc::new(AtomicBool::new(false)),
+            paused: Arc::new(AtomicBool::new(false)),
+            status: Arc::new(Mutex::new(Status::default())),
+            thread: None,
+        }
+    }
+
+    fn config(&mut self) -> &mut Config {
+        Arc::make_mut(&mut self.config)
+    }
+
+    /// Records the ADC channels `channels`, in this order in the CSV columns.
+    ///
+    /// # Panics
+    ///
+    /// If a channel is not below 10.
+    pub fn with_adc_channels(mut self, channels: &[usize]) -> Self {
+        if let Some(channel) = channels.iter().find(|&&channel| channel >= AdcFrame::CHANNELS) {
+            panic!("ADC channel must be below 10, got {}", channel);
+        }
+        self.config().channels = channels.to_vec();
+        self
+    }
+
+    /// Enables or disables recording of the IO input levels.
+    pub fn with_io(mut self, enabled: bool) -> Self {
+        self.config().io = enabled;
+        self
+    }
+
+    /// Enables or disables recording of the MPU6500.
+    pub fn with_mpu(mut self, enabled: bool) -> Self {
+        self.config().mpu = enabled;
+        self
+    }
+
+    /// Ends the recording once it has run for `duration`.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.config().duration = Some(duration);
+        self
+    }
+
+    /// Starts recording with the first tick meeting `trigger`, which is the first record.
+    pub fn with_start_trigger(mut self, trigger: Trigger) -> Self {
+        self.config().start_trigger = Some(trigger);
+        self
+    }
+
+    /// Ends the recording with the first record meeting `trigger`, which is still written.
+    pub fn with_stop_trigger(mut self, trigger: Trigger) -> Self {
+        self.config().stop_trigger = Some(trigger);
+        self
+    }
+
+    /// Returns what the session is doing.
+    pub fn state(&self) -> SessionState {
+        let status = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        if status.finished {
+            SessionState::Finished
+        } else if !self.running.load(Ordering::Acquire) {
+            SessionState::Idle
+        } else if self.paused.load(Ordering::Acquire) {
+            SessionState::Paused
+        } else if status.triggered {
+            SessionState::Recording
+        } else {
+            SessionState::Armed
+        }
+    }
+
+    /// Returns the number of records written since the session started.
+    pub fn records(&self) -> u64 {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).records
+    }
+
+    /// Opens the sink and starts the recording thread. Does nothing if it is already running.
+    ///
+    /// # Errors
+    ///
+    /// - [`UptechError::Config`] if a trigger reads an ADC channel
+    ///   or an IO pin that is not recorded, as it could never be met.
+    /// - [`UptechError::Io`] if the sink cannot be opened.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::mpsc;
+    /// use uptechstar_rs::UptechError;
+    /// use uptechstar_rs::daq::{Session, Sink, Trigger};
+    ///
+    /// let (frames, _) = mpsc::channel();
+    /// let mut session = Session::new(50.0, Sink::Channel(frames))
+    ///     .with_mpu(true)
+    ///     .with_start_trigger(Trigger::AdcAbove { channel: 1, level: 2048 });
+    ///
+    /// assert!(matches!(session.start(), Err(UptechError::Config(_))));
+    /// ```
+    pub fn start(&mut self) -> Result<()> {
+        if self.thread.is_some() {
+            return Ok(());
+        }
+
+        for trigger in [&self.config.start_trigger, &self.config.stop_trigger].into_iter().flatten() {
+            self.config.check(trigger)?;
+        }
+        let writer = Writer::open(&self.sink, &self.config)?;
+        info!(
+            "Starting acquisition at {:.1} Hz into {:?} (adc: {:?}, io: {}, mpu: {})",
+            1.0 / self.config.period.as_secs_f32(),
+            self.sink,
+            self.config.channels,
+            self.config.io,
+            self.config.mpu
+        );
+
+        *self.status.lock().unwrap_or_else(|e| e.into_inner()) = Status::default();
+        self.paused.store(false, Ordering::Release);
+        self.running.store(true, Ordering::Release);
+
+        let config = Arc::clone(&self.config);
+        let running = Arc::clone(&self.running);
+        let paused = Arc::clone(&self.paused);
+        let status = Arc::clone(&self.status);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-daq".into())
+                .spawn(move || {
+                    let result = record(&config, writer, &running, &paused, &status);
+                    if let Err(e) = &result {
+                        error!("Acquisition failed: {}", e);
+                    }
+                    running.store(false, Ordering::Release);
+                    result
+                })
+                .expect("Failed to spawn acquisition thread"),
+        );
+        Ok(())
+    }
+
+    /// Pauses the recording; the sources are not read until [`resume`](Self::resume).
+    pub fn pause(&self) {
+        if self.running.load(Ordering::Acquire) && !self.paused.swap(true, Ordering::AcqRel) {
+            info!("Acquisition paused");
+        }
+    }
+
+    /// Resumes a paused recording.
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::AcqRel) {
+            info!("Acquisition resumed");
+        }
+    }
+
+    /// Ends the recording and waits for the sink to be flushed.
+    ///
+    /// # Errors
+    ///
+    /// [`UptechError::Io`] if writing to the sink failed during
+    /// the recording.
+    pub fn stop(&mut self) -> Result<()> {
+        self.running.store(false, Ordering::Release);
+        self.wait()
+    }
+
+    /// Waits until the recording ends by its duration or stop trigger. Returns right away if
+    /// it is not running.
+    ///
+    /// # Errors
+    ///
+    /// [`UptechError::Io`] if writing to the sink failed during
+    /// the recording.
+    pub fn wait(&mut self) -> Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        let result = thread.join().unwrap_or_else(|_| Err(io::Error::other("acquisition thread panicked")));
+        info!("Acquisition ended after {} records", self.records());
+        Ok(result?)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Err(e) = self.stop() {
+            warn!("{}", e);
+        }
+    }
+}
+
+/// The recording loop of [`Session::start`].
+fn record(
+    config: &Config,
+    mut writer: Writer,
+    running: &AtomicBool,
+    paused: &AtomicBool,
+    status: &Mutex<Status>,
+) -> io::Result<()> {
+    let mut ticker = Ticker::new(config.period);
+    let started = Instant::now();
+    let mut last_tick = started;
+    let mut recorded = Duration::ZERO;
+    let mut triggered = config.start_trigger.is_none();
+    let mut sequence = 0;
+    status.lock().unwrap_or_else(|e| e.into_inner()).triggered = triggered;
+
+    while running.load(Ordering::Acquire) {
+        let now = Instant::now();
+        let tick = now - last_tick;
+        last_tick = now;
+        if paused.load(Ordering::Acquire) {
+            ticker.wait();
+            continue;
+        }
+
+        let frame = TelemetryFrame {
+            sequence,
+            timestamp: started.elapsed(),
+            adc: (!config.channels.is_empty()).then(adc_io::adc_get_frame).and_then(|adc| adc.ok()),
+            io: config.io.then(adc_io::io_get_frame),
+            mpu: config.mpu.then(mpu::mpu6500_get_sample).and_then(|mpu| mpu.ok()),
+            user: Vec::new(),
+        };
+
+        if triggered {
+            recorded += tick;
+            if config.duration.is_some_and(|duration| recorded >= duration) {
+                break;
+            }
+        } else if config.start_trigger.as_ref().is_some_and(|trigger| trigger.is_met(&frame)) {
+            info!("Acquisition triggered");
+            triggered = true;
+            status.lock().unwrap_or_else(|e| e.into_inner()).triggered = true;
+        } else {
+            ticker.wait();
+            continue;
+        }
+
+        writer.write(config, &frame)?;
+        sequence += 1;
+        status.lock().unwrap_or_else(|e| e.into_inner()).records = sequence;
+
+        if config.stop_trigger.as_ref().is_some_and(|trigger| trigger.is_met(&frame)) {
+            info!("Acquisition stop trigger met");
+            break;
+        }
+        ticker.wait();
+    }
+
+    writer.flush()?;
+    if running.load(Ordering::Acquire) {
+        status.lock().unwrap_or_else(|e| e.into_inner()).finished = true;
+    }
+    debug!("Acquisition thread exited");
+    Ok(())
+}
+
+/// An opened [`Sink`].
+enum Writer {
+    Csv { file: BufWriter<File>, line: String, last_flush: Instant },
+    Frames { file: BufWriter<File>, bytes: Vec<u8>, last_flush: Instant },
+    Udp { socket: UdpSocket, target: SocketAddr, bytes: Vec<u8>, failing: bool },
+    Channel(Sender<TelemetryFrame>),
+}
+
+impl Writer {
+    fn open(sink: &Sink, config: &Config) -> io::Result<Self> {
+        Ok(match sink {
+            Sink::Csv(path) => {
+                let mut file = BufWriter::new(File::create(path)?);
+                writeln!(file, "{}", csv_header(config))?;
+                Writer::Csv {
+                    file,
+                    line: String::with_capacity(256),
+                    last_flush: Instant::now(),
+                }
+            }
+            Sink::Frames(path) => Writer::Frames {
+                file: BufWriter::new(File::create(path)?),
+                bytes: Vec::with_capacity(128),
+                last_flush: Instant::now(),
+            },
+            Sink::Udp(target) => {
+                let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+                socket.set_broadcast(true)?;
+                Writer::Udp {
+                    socket,
+                    target: *target,
+                    bytes: Vec::with_capacity(128),
+                    failing: false,
+                }
+            }
+            Sink::Channel(sender) => Writer::Channel(sender.clone()),
+        })
+    }
+
+    fn write(&mut self, config: &Config, frame: &TelemetryFrame) -> io::Result<()> {
+        match self {
+            Writer::Csv { file, line, last_flush } => {
+                line.clear();
+                format_csv(line, config, frame);
+                line.push('\n');
+                file.write_all(line.as_bytes())?;
+                flush_if_due(file, last_flush)
+            }
+            Writer::Frames { file, bytes, last_flush } => {
+                bytes.clear();
+                frame.encode_into(bytes);
+                file.write_all(bytes)?;
+                flush_if_due(file, last_flush)
+            }
+            Writer::Udp { socket, target, bytes, failing } => {
+                bytes.clear();
+                frame.encode_into(bytes);
+                match socket.send_to(bytes, *target) {
+                    Ok(_) => *failing = false,
+                    // Only log the first failure of a run, not one per tick.
+                    Err(e) if !*failing => {
+                        warn!("Failed to send acquisition frame to {}: {}", target, e);
+                        *failing = true;
+                    }
+                    Err(_) => {}
+                }
+                Ok(())
+            }
+            Writer::Channel(sender) => sender
+                .send(frame.clone())
+                .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "acquisition receiver dropped")),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Writer::Csv { file, .. } | Writer::Frames { file, .. } => file.flush(),
+            Writer::Udp { .. } | Writer::Channel(_) => Ok(()),
+        }
+    }
+}
+
+fn flush_if_due(file: &mut BufWriter<File>, last_flush: &mut Instant) -> io::Result<()> {
+    if last_flush.elapsed() >= FLUSH_INTERVAL {
+        *last_flush = Instant::now();
+        file.flush()?;
+    }
+    Ok(())
+}
+
+fn csv_header(config: &Config) -> String {
+    let mut header = "timestamp_us".to_string();
+    for channel in &config.channels {
+        let _ = write!(header, ",adc{}", channel);
+    }
+    if config.io {
+        header.push_str(",io");
+    }
+    if config.mpu {
+        header.push_str(",accel_x,accel_y,accel_z,gyro_x,gyro_y,gyro_z,pitch,roll,yaw");
+    }
+    header
+}
+
+fn format_csv(line: &mut String, config: &Config, frame: &TelemetryFrame) {
+    let _ = write!(line, "{}", frame.timestamp.as_micros());
+    for &channel in &config.channels {
+        line.push(',');
+        if let Some(adc) = frame.adc {
+            let _ = write!(line, "{}", adc.0[channel]);
+        }
+    }
+    if config.io {
+        line.push(',');
+        if let Some(io) = frame.io {
+            let _ = write!(line, "{}", io.0);
+        }
+    }
+    if config.mpu {
+        match frame.mpu {
+            Some(mpu) => {
+                for value in mpu.accel.iter().chain(&mpu.gyro).chain(&mpu.attitude) {
+                    let _ = write!(line, ",{}", value);
+                }
+            }
+            None => line.push_str(",,,,,,,,,"),
+        }
+    }
+}
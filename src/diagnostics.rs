@@ -0,0 +1,396 @@
+//! Hardware self-test for production QA.
+//!
+//! [`run_self_test`] checks the ADC and IO controller, the MPU6500, the LCD and the LEDs of a
+//! freshly assembled board and returns a [`SelfTestReport`] with one [`Check`] per step. With
+//! the LCD enabled the report is also rendered as a summary page, so a board on the bench can
+//! be judged without a terminal.
+//!
+//! Only the ADC, IO and MPU checks can tell a failure by themselves. The LCD and LEDs are
+//! driven through color sequences and reported as [`Outcome::Inspect`] for the operator to
+//! confirm. IO pins are tested by looping an output back to an input, which needs a jumper
+//! wire per pair; configure the pairs with [`SelfTest::with_loopback`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::diagnostics::SelfTest;
+//!
+//! // Jumpers between IO0 and IO1, and between IO2 and IO3.
+//! let report = SelfTest::new().with_loopback(0, 1).with_loopback(2, 3).run();
+//! println!("{}", report);
+//! std::process::exit(if report.passed() { 0 } else { 1 });
+//! ```
+
+use crate::adc_io;
+use crate::display::{Color, FontSize, Screen, ScreenDirection};
+use crate::extern_lib::symbol_or_return;
+use crate::mpu;
+use crate::stats;
+use log::info;
+use std::ffi::c_long;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Expected `WHO_AM_I` register value of the MPU6500.
+const MPU6500_ID: u8 = 0x70;
+
+/// How long every color of the LCD and LED sequences stays on.
+const COLOR_STEP: Duration = Duration::from_millis(300);
+
+/// The result of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Outcome {
+    /// The hardware behaved as expected.
+    Passed,
+    /// The hardware failed or returned implausible values.
+    Failed,
+    /// The check was not run, because it is not configured or an earlier check failed.
+    Skipped,
+    /// The hardware was driven without error, but only a person can tell whether it worked.
+    Inspect,
+}
+
+impl Outcome {
+    fn label(self) -> &'static str {
+        match self {
+            Outcome::Passed => "PASS",
+            Outcome::Failed => "FAIL",
+            Outcome::Skipped => "SKIP",
+            Outcome::Inspect => "LOOK",
+        }
+    }
+}
+
+/// One step of a self-test.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Check {
+    /// Dotted name of the check, such as `adc.read` or `io.loopback.0-1`.
+    pub name: String,
+    /// Whether the check passed.
+    pub outcome: Outcome,
+    /// What was measured, or why the check failed or was skipped.
+    pub detail: String,
+}
+
+/// The results of a [`SelfTest`] run.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelfTestReport {
+    /// The checks in the order they ran.
+    pub checks: Vec<Check>,
+    /// How long the run took.
+    pub duration: Duration,
+}
+
+impl SelfTestReport {
+    /// Returns `true` if no check failed. Skipped and inspected checks do not count as failures.
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Returns the failed checks.
+    pub fn failures(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|check| check.outcome == Outcome::Failed)
+    }
+
+    /// Returns the check named `name`.
+    pub fn get(&self, name: &str) -> Option<&Check> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    /// Draws a summary page: the overall result followed by one line per check, as many as
+    /// fit. Failed checks are listed first.
+    ///
+    /// Returns:
+    ///   The screen for chainable calls.
+    pub fn render<'a>(&self, screen: &'a mut Screen) -> &'a mut Screen {
+        let font = FontSize::Font6x8;
+        let height = screen.size().1;
+        let (title, color) = if self.passed() { ("SELF-TEST PASS", Color::GREEN) } else { ("SELF-TEST FAIL", Color::RED) };
+
+        screen
+            .fill_screen(Color::BLACK)
+            .set_back_color(Color::BLACK)
+            .set_font_size(font)
+            .set_fore_color(color)
+            .put_string(0, 0, title);
+
+        let mut checks: Vec<&Check> = self.failures().collect();
+        checks.extend(self.checks.iter().filter(|check| check.outcome != Outcome::Failed));
+
+        let rows = (height / font.row_height() - 1).max(0) as usize;
+        for (row, check) in checks.iter().take(rows).enumerate() {
+            let color = match check.outcome {
+                Outcome::Passed => Color::GREEN,
+                Outcome::Failed => Color::RED,
+                Outcome::Skipped => Color::GRAY,
+                Outcome::Inspect => Color::YELLOW,
+            };
+            screen
+                .set_fore_color(color)
+                .put_string(0, (row as i32 + 1) * font.row_height(), &format!("{} {}", check.outcome.label(), check.name));
+        }
+
+        screen.refresh()
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{}  {:<18} {}", check.outcome.label(), check.name, check.detail)?;
+        }
+        let failed = self.failures().count();
+        write!(
+            f,
+            "{} checks, {} failed, {:.1}s",
+            self.checks.len(),
+            failed,
+            self.duration.as_secs_f32()
+        )
+    }
+}
+
+/// Configures which parts of the board a self-test exercises.
+#[derive(Debug, Clone)]
+pub struct SelfTest {
+    loopbacks: Vec<(u32, u32)>,
+    mpu_self_test: bool,
+    screen: Option<ScreenDirection>,
+    leds: bool,
+}
+
+impl Default for SelfTest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SelfTest {
+    /// Creates a self-test of everything but the IO pins, showing the LCD pattern and summary
+    /// in horizontal direction.
+    pub fn new() -> Self {
+        SelfTest {
+            loopbacks: Vec::new(),
+            mpu_self_test: true,
+            screen: Some(ScreenDirection::Horizontal),
+            leds: true,
+        }
+    }
+
+    /// Tests IO `output` driving IO `input` through a jumper wire. The modes of all pins are
+    /// restored afterwards.
+    ///
+    /// # Panics
+    ///
+    /// If a pin index is not in `0..8` or both pins are the same.
+    pub fn with_loopback(mut self, output: u32, input: u32) -> Self {
+        assert!(output < 8 && input < 8, "IO pin index must be in 0..8, got {} and {}", output, input);
+        assert_ne!(output, input, "Loopback output and input must be different pins");
+        self.loopbacks.push((output, input));
+        self
+    }
+
+    /// Enables or disables the built-in MPU6500 self-test, which reconfigures the sensor for
+    /// its measurement and needs the board to lie still. Enabled by default.
+    pub fn with_mpu_self_test(mut self, enabled: bool) -> Self {
+        self.mpu_self_test = enabled;
+        self
+    }
+
+    /// Sets the direction to open the LCD in for the test pattern and the summary, or skips
+    /// the LCD with `None`.
+    pub fn with_screen(mut self, direction: Option<ScreenDirection>) -> Self {
+        self.screen = direction;
+        self
+    }
+
+    /// Enables or disables the LED color sequence. Enabled by default.
+    pub fn with_leds(mut self, enabled: bool) -> Self {
+        self.leds = enabled;
+        self
+    }
+
+    /// Runs all configured checks.
+    pub fn run(&self) -> SelfTestReport {
+        info!("Running hardware self-test");
+        let started = Instant::now();
+        let mut report = SelfTestReport::default();
+        let mut push = |name: &str, outcome: Outcome, detail: String| {
+            report.checks.push(Check {
+                name: name.to_string(),
+                outcome,
+                detail,
+            })
+        };
+
+        // ADC and IO
+        let adc_open = adc_io::adc_open();
+        if adc_open == 0 {
+            push("adc.open", Outcome::Passed, String::new());
+            match adc_io::adc_get_frame() {
+                Ok(frame) => push("adc.read", Outcome::Passed, format!("{:?}", frame.0)),
+                Err(e) => push("adc.read", Outcome::Failed, e.to_string()),
+            }
+            if self.loopbacks.is_empty() {
+                push("io.loopback", Outcome::Skipped, "No jumpers configured".into());
+            }
+            for &(output, input) in &self.loopbacks {
+                let (outcome, detail) = loopback(output, input);
+                push(&format!("io.loopback.{}-{}", output, input), outcome, detail);
+            }
+        } else {
+            push("adc.open", Outcome::Failed, format!("Status {}", adc_open));
+            push("adc.read", Outcome::Skipped, "ADC not open".into());
+            push("io.loopback", Outcome::Skipped, "IO controller not open".into());
+        }
+
+        // MPU
+        let mpu_open = mpu::mpu6500_open();
+        if mpu_open == 0 {
+            push("mpu.open", Outcome::Passed, String::new());
+            match who_am_i() {
+                Ok(MPU6500_ID) => push("mpu.probe", Outcome::Passed, format!("WHO_AM_I = {:#04x}", MPU6500_ID)),
+                Ok(id) => push(
+                    "mpu.probe",
+                    Outcome::Failed,
+                    format!("WHO_AM_I = {:#04x}, expected {:#04x}", id, MPU6500_ID),
+                ),
+                Err(code) => push("mpu.probe", Outcome::Failed, format!("Register read failed with status {}", code)),
+            }
+            match mpu::mpu6500_get_sample() {
+                Ok(sample) => {
+                    let g = sample.accel.iter().map(|a| a * a).sum::<f32>().sqrt();
+                    let outcome = if (0.8..=1.2).contains(&g) { Outcome::Passed } else { Outcome::Failed };
+                    push("mpu.accel", outcome, format!("|a| = {:.2} g at rest", g));
+                }
+                Err(code) => push("mpu.accel", Outcome::Failed, format!("Read failed with status {}", code)),
+            }
+            if self.mpu_self_test {
+                let (outcome, detail) = mpu_self_test();
+                push("mpu.self_test", outcome, detail);
+            }
+        } else {
+            push("mpu.open", Outcome::Failed, format!("Status {}", mpu_open));
+            push("mpu.probe", Outcome::Skipped, "MPU not open".into());
+            push("mpu.accel", Outcome::Skipped, "MPU not open".into());
+        }
+
+        // LCD and LEDs
+        let mut screen = self.screen.map(|direction| Screen::new(Some(direction)));
+        match &mut screen {
+            Some(screen) => {
+                let (width, height) = screen.size();
+                for color in [Color::RED, Color::GREEN, Color::BLUE, Color::WHITE] {
+                    screen.fill_screen(color).refresh();
+                    thread::sleep(COLOR_STEP);
+                }
+                screen
+                    .fill_screen(Color::BLACK)
+                    .draw_frame(0, 0, width - 1, height - 1, Color::WHITE)
+                    .refresh();
+                thread::sleep(COLOR_STEP);
+                push("lcd.pattern", Outcome::Inspect, "Red, green, blue and white fills, then a border".into());
+            }
+            None => push("lcd.pattern", Outcome::Skipped, "Disabled".into()),
+        }
+
+        if self.leds {
+            let mut leds = Screen::new(None);
+            for color in [Color::RED, Color::GREEN, Color::BLUE] {
+                leds.set_all_leds_same(color);
+                thread::sleep(COLOR_STEP);
+            }
+            leds.set_all_leds_off();
+            push("led.colors", Outcome::Inspect, "Both LEDs red, green, then blue".into());
+        } else {
+            push("led.colors", Outcome::Skipped, "Disabled".into());
+        }
+
+        report.duration = started.elapsed();
+        info!(
+            "Self-test finished: {} checks, {} failed",
+            report.checks.len(),
+            report.failures().count()
+        );
+
+        if let Some(screen) = &mut screen {
+            report.render(screen);
+        }
+        report
+    }
+}
+
+/// Runs the default [`SelfTest`]: ADC, MPU, LCD and LEDs, without IO loopbacks.
+pub fn run_self_test() -> SelfTestReport {
+    SelfTest::new().run()
+}
+
+/// Drives `output` high and low and checks that `input` follows.
+fn loopback(output: u32, input: u32) -> (Outcome, String) {
+    let modes = adc_io::get_all_io_mode();
+    let result = (|| {
+        if adc_io::set_io_mode(output, 1) != 0 || adc_io::set_io_mode(input, 0) != 0 {
+            return (Outcome::Failed, "Failed to set the pin modes".to_string());
+        }
+        for level in [true, false] {
+            let mask = 1u8 << output;
+            if adc_io::set_io_levels_with_mask(mask, if level { mask } else { 0 }) != 0 {
+                return (Outcome::Failed, format!("Failed to drive IO{}", output));
+            }
+            thread::sleep(Duration::from_millis(5));
+            let read = adc_io::io_get_all_channels() >> input & 1 == 1;
+            if read != level {
+                return (
+                    Outcome::Failed,
+                    format!("IO{} read {} while IO{} was driven {}", input, read as u8, output, level as u8),
+                );
+            }
+        }
+        (Outcome::Passed, format!("IO{} follows IO{}", input, output))
+    })();
+    adc_io::set_all_io_mode(modes);
+    result
+}
+
+/// Reads the `WHO_AM_I` register. `mpu::read_register` is only public with the `raw` feature.
+fn who_am_i() -> Result<u8, i32> {
+    unsafe {
+        let call = stats::start("mpu6500_read_byte");
+        let mpu6500_read_byte = symbol_or_return!(mpu6500_read_byte: unsafe extern "C" fn(u8) -> i32, Err(-1));
+
+        match call.finish(mpu6500_read_byte(0x75)) {
+            value @ 0..=0xff => Ok(value as u8),
+            code => Err(code.min(-1)),
+        }
+    }
+}
+
+/// Runs the InvenSense self-test of the gyroscope and accelerometer.
+fn mpu_self_test() -> (Outcome, String) {
+    let mut gyro: [c_long; 3] = [0; 3];
+    let mut accel: [c_long; 3] = [0; 3];
+
+    let result = unsafe {
+        let call = stats::start("mpu_run_6500_self_test");
+        let run = symbol_or_return!(
+            mpu_run_6500_self_test: unsafe extern "C" fn(*mut c_long, *mut c_long, u8) -> i32,
+            (Outcome::Failed, "mpu_run_6500_self_test is not available".to_string())
+        );
+        call.finish(run(gyro.as_mut_ptr(), accel.as_mut_ptr(), 0))
+    };
+
+    // Bit 0 is set when the gyroscope passed, bit 1 when the accelerometer did.
+    let outcome = if result >= 0 && result & 0b11 == 0b11 { Outcome::Passed } else { Outcome::Failed };
+    let detail = match (result & 1 != 0, result & 2 != 0) {
+        _ if result < 0 => format!("Failed with status {}", result),
+        (true, true) => "Gyroscope and accelerometer passed".to_string(),
+        (false, true) => "Gyroscope failed".to_string(),
+        (true, false) => "Accelerometer failed".to_string(),
+        (false, false) => "Gyroscope and accelerometer failed".to_string(),
+    };
+    (outcome, detail)
+}
@@ -1,11 +1,42 @@
-use crate::extern_lib::LIBRARY;
-use libloading::Symbol;
-
-use log::info;
+use crate::error::{Result, UptechError};
+use crate::extern_lib::symbol_or_return;
+use crate::registry;
+use crate::stats;
+
+use crate::log_level::display::{info, warn};
+use std::ffi::c_char;
+
+mod clip;
+mod dimmer;
+mod framebuffer;
+mod hd44780;
+mod icons;
+mod layout;
+mod led;
+mod neopixel;
+mod pager;
+mod render;
+mod shared;
+mod text;
+pub mod scene;
+pub mod widgets;
+
+pub use dimmer::LedDimmer;
+pub use framebuffer::{BufferedScreen, FrameBuffer, Span};
+pub use hd44780::Hd44780;
+pub use icons::Icon;
+pub use led::{LedCorrection, led_correction, set_led_brightness, set_led_correction};
+pub use neopixel::NeoPixelStrip;
+pub use pager::{Nav, Page, Pager, Transition};
+pub use render::{DrawOp, RenderQueue, RenderThread};
+pub use scene::Rect;
+pub use shared::SharedScreen;
+pub use text::ellipsize;
 
 
 /// All supported screen direction enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ScreenDirection {
     Vertical = 1,
     Horizontal = 2,
@@ -31,6 +62,7 @@ impl ScreenDirection {
 
 /// All supported font size enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FontSize {
     Font4x6 = 0,
     Font5x8 = 1,
@@ -50,6 +82,25 @@ pub enum FontSize {
 }
 
 impl FontSize {
+    /// All fonts, from the smallest to the largest.
+    pub const ALL: [FontSize; 15] = [
+        FontSize::Font4x6,
+        FontSize::Font5x8,
+        FontSize::Font5x12,
+        FontSize::Font6x8,
+        FontSize::Font6x10,
+        FontSize::Font7x12,
+        FontSize::Font8x8,
+        FontSize::Font8x12,
+        FontSize::Font8x14,
+        FontSize::Font10x16,
+        FontSize::Font12x16,
+        FontSize::Font12x20,
+        FontSize::Font16x26,
+        FontSize::Font22x36,
+        FontSize::Font24x40,
+    ];
+
     /// Returns the row height of the current font size.
     pub fn row_height(&self) -> i32 {
         match self {
@@ -91,6 +142,25 @@ impl FontSize {
             FontSize::Font24x40 => 24,
         }
     }
+
+    /// Returns the width and height in pixels `text` takes up in this font, with a line per
+    /// `'\n'` and as wide as the longest line.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::FontSize;
+    ///
+    /// assert_eq!(FontSize::Font8x12.measure_text("Speed"), (40, 12));
+    /// assert_eq!(FontSize::Font6x8.measure_text("L: 12\nR: 100"), (36, 16));
+    /// assert_eq!(FontSize::Font6x8.measure_text(""), (0, 8));
+    /// ```
+    pub fn measure_text(&self, text: &str) -> (i32, i32) {
+        let (lines, longest) = text
+            .split('\n')
+            .fold((0, 0), |(lines, longest), line| (lines + 1, longest.max(line.chars().count() as i32)));
+        (longest * self.column_width(), lines * self.row_height())
+    }
 }
 
 /// All supported color display on the led/lcd
@@ -113,6 +183,32 @@ impl Color {
         ((r as u32) << 16) + ((g as u32) << 8) + (b as u32)
     }
 
+    /// Mixes `foreground` over `background` with an opacity of `alpha`, from 0 (only the
+    /// background) to 255 (only the foreground).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::Color;
+    ///
+    /// assert_eq!(Color::blend(Color::BLACK, Color::WHITE, 255), Color::WHITE);
+    /// assert_eq!(Color::blend(Color::BLUE, Color::RED, 128), Color::new_color(128, 0, 127));
+    /// ```
+    pub const fn blend(background: u32, foreground: u32, alpha: u8) -> u32 {
+        let alpha = alpha as u32;
+        let mut result = 0;
+        let mut shift = 0;
+        while shift < 24 {
+            let back = (background >> shift) & 0xFF;
+            let fore = (foreground >> shift) & 0xFF;
+            // Rounded division by 255.
+            let mixed = (fore * alpha + back * (255 - alpha) + 127) / 255;
+            result |= mixed << shift;
+            shift += 8;
+        }
+        result
+    }
+
     pub const WHITE: u32 = Self::new_color(255, 255, 255);
     pub const GRAY: u32 = Self::new_color(128, 128, 128);
     pub const BLACK: u32 = Self::new_color(0, 0, 0);
@@ -149,31 +245,78 @@ impl Color {
 pub struct Screen {
     font_size: FontSize,
     screen_dir: Option<ScreenDirection>,
+    /// Direction drawing coordinates are laid out for, see [`set_layout`](Screen::set_layout).
+    layout: Option<ScreenDirection>,
+    asleep: bool,
+    /// Last color passed to [`set_fore_color`](Screen::set_fore_color).
+    fore_color: u32,
+    /// Last color passed to [`set_back_color`](Screen::set_back_color).
+    back_color: u32,
+    /// Clip rectangles, each already intersected with the one below.
+    clips: Vec<Rect>,
+    /// States saved by [`save_state`](Screen::save_state).
+    states: Vec<clip::DrawState>,
 }
 
 impl Screen {
     /// Initializes the Screen struct.
     ///
+    /// If another `Screen` already opened the LCD, the new one draws on it in its current
+    /// direction instead of opening and clearing it again.
+    ///
     /// Parameters:
     ///     screen_dir: The direction to open the screen in. None for no initialization.
     ///
     /// Returns:
     ///     A new Screen instance
     pub fn new(screen_dir: Option<ScreenDirection>) -> Self {
+        let open_dir = registry::lcd();
         let mut screen = Screen {
             font_size: FontSize::Font12x20,
-            screen_dir,
+            screen_dir: open_dir.or(screen_dir),
+            layout: None,
+            asleep: false,
+            fore_color: Color::WHITE,
+            back_color: Color::BLACK,
+            clips: Vec::new(),
+            states: Vec::new(),
         };
 
-        if let Some(dir) = screen_dir {
-            screen.open(dir).fill_screen(Color::BLACK).refresh();
+        match (screen_dir, open_dir) {
+            (Some(dir), None) => {
+                screen.open(dir).fill_screen(Color::BLACK).refresh();
+            }
+            (Some(dir), Some(open_dir)) => {
+                if dir != open_dir {
+                    warn!("LCD is already open {:?}, not reopening it {:?}", open_dir, dir);
+                }
+                info!("Sharing the LCD opened {:?}", open_dir);
+            }
+            (None, _) => {}
         }
 
         screen
     }
 
+    /// Opens the LCD in `direction` like [`new`](Self::new), but fails instead of sharing an LCD
+    /// that is already open.
+    ///
+    /// # Errors
+    ///
+    /// [`UptechError::AlreadyInitialized`] if another `Screen` opened the LCD and has not closed
+    /// it.
+    pub fn try_new(direction: ScreenDirection) -> Result<Self> {
+        if registry::lcd().is_some() {
+            return Err(UptechError::AlreadyInitialized("lcd"));
+        }
+        Ok(Screen::new(Some(direction)))
+    }
+
     /// Open the LCD and set the displaying direction.
     ///
+    /// Unlike [`new`](Self::new), this always runs the LCD initialization, so it can change the
+    /// direction of an open screen.
+    ///
     /// Args:
     ///   direction: Display direction; Vertical or Horizontal.
     ///
@@ -183,13 +326,14 @@ impl Screen {
         info!("Open LCD with direction: {:?}", direction);
 
         unsafe {
-            let lcd_open: Symbol<unsafe extern "C" fn(i32) -> i32> = LIBRARY
-                .get(b"lcd_open")
-                .expect("Failed to load lcd_open function");
+            let call = stats::start("lcd_open");
+            let lcd_open = symbol_or_return!(lcd_open: unsafe extern "C" fn(i32) -> i32, self);
 
             lcd_open(direction as i32);
+            call.done();
         }
 
+        registry::set_lcd(Some(direction));
         self.screen_dir = Some(direction);
         self
     }
@@ -202,27 +346,75 @@ impl Screen {
         info!("Closing LCD");
 
         unsafe {
-            let lcd_close: Symbol<unsafe extern "C" fn() -> i32> = LIBRARY
-                .get(b"lcd_close")
-                .expect("Failed to load lcd_close function");
+            let call = stats::start("lcd_close");
+            let lcd_close = symbol_or_return!(lcd_close: unsafe extern "C" fn() -> i32, self);
 
             lcd_close();
+            call.done();
+        }
+        registry::set_lcd(None);
+
+        self
+    }
+
+    /// Puts the LCD to sleep to save power, for example between the updates of a data logger.
+    ///
+    /// `libuptech` has no backlight control, so this shuts the LCD controller down like
+    /// [`close`](Self::close) but remembers the direction to reopen it in. Don't draw until
+    /// [`wake`](Self::wake) is called. Does nothing if the screen is already asleep.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn sleep(&mut self) -> &mut Self {
+        if self.asleep {
+            return self;
         }
 
+        self.close();
+        self.asleep = true;
         self
     }
 
+    /// Wakes the LCD from [`sleep`](Self::sleep), reopening it in its previous direction.
+    ///
+    /// The displayed content is lost while asleep; redraw it and [`refresh`](Self::refresh)
+    /// after waking. Does nothing if the screen is awake or was never opened.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn wake(&mut self) -> &mut Self {
+        if !self.asleep {
+            return self;
+        }
+
+        if let Some(dir) = self.screen_dir {
+            self.open(dir);
+        }
+        self.asleep = false;
+        self
+    }
+
+    /// Returns whether the LCD is asleep, see [`sleep`](Self::sleep).
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+
+    /// The font set by [`set_font_size`](Self::set_font_size).
+    pub(crate) fn font(&self) -> FontSize {
+        self.font_size
+    }
+
     /// Refresh the screen, printing the display data from the cache onto the screen.
     ///
     /// Returns:
     ///   Self for chainable calls.
     pub fn refresh(&mut self) -> &mut Self {
         unsafe {
-            let lcd_refresh: Symbol<unsafe extern "C" fn() -> i32> = LIBRARY
-                .get(b"LCD_Refresh")
-                .expect("Failed to load LCD_Refresh function");
+            let call = stats::start("LCD_Refresh");
+            let lcd_refresh = symbol_or_return!(LCD_Refresh: unsafe extern "C" fn() -> i32, self);
 
             lcd_refresh();
+            call.done();
         }
 
         self
@@ -239,11 +431,11 @@ impl Screen {
         self.font_size = font_size;
 
         unsafe {
-            let lcd_set_font: Symbol<unsafe extern "C" fn(i32) -> i32> = LIBRARY
-                .get(b"LCD_SetFont")
-                .expect("Failed to load LCD_SetFont function");
+            let call = stats::start("LCD_SetFont");
+            let lcd_set_font = symbol_or_return!(LCD_SetFont: unsafe extern "C" fn(i32) -> i32, self);
 
             lcd_set_font(font_size as i32);
+            call.done();
         }
 
         self
@@ -257,12 +449,14 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn set_fore_color(&mut self, color: u32) -> &mut Self {
+        self.fore_color = color;
+
         unsafe {
-            let ug_set_forecolor: Symbol<unsafe extern "C" fn(u32) -> i32> = LIBRARY
-                .get(b"UG_SetForecolor")
-                .expect("Failed to load UG_SetForecolor function");
+            let call = stats::start("UG_SetForecolor");
+            let ug_set_forecolor = symbol_or_return!(UG_SetForecolor: unsafe extern "C" fn(u32) -> i32, self);
 
             ug_set_forecolor(color);
+            call.done();
         }
 
         self
@@ -276,12 +470,14 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn set_back_color(&mut self, color: u32) -> &mut Self {
+        self.back_color = color;
+
         unsafe {
-            let ug_set_backcolor: Symbol<unsafe extern "C" fn(u32) -> i32> = LIBRARY
-                .get(b"UG_SetBackcolor")
-                .expect("Failed to load UG_SetBackcolor function");
+            let call = stats::start("UG_SetBackcolor");
+            let ug_set_backcolor = symbol_or_return!(UG_SetBackcolor: unsafe extern "C" fn(u32) -> i32, self);
 
             ug_set_backcolor(color);
+            call.done();
         }
 
         self
@@ -289,6 +485,8 @@ impl Screen {
 
     /// Set the LED color at a specific index.
     ///
+    /// The color is adjusted by the [LED correction](set_led_correction) first.
+    ///
     /// Parameters:
     ///     index: The index of the LED to set the color for (0 or 1).
     ///     color: The color to set for the LED.
@@ -296,14 +494,7 @@ impl Screen {
     /// Returns:
     ///     Self for method chaining.
     pub fn set_led_color(&mut self, index: i32, color: u32) -> &mut Self {
-        unsafe {
-            let adc_led_set: Symbol<unsafe extern "C" fn(i32, u32) -> i32> = LIBRARY
-                .get(b"adc_led_set")
-                .expect("Failed to load adc_led_set function");
-
-            adc_led_set(index, color);
-        }
-
+        led::write_led(index, color);
         self
     }
 
@@ -376,12 +567,16 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn fill_screen(&mut self, color: u32) -> &mut Self {
+        if let Some(clip) = self.clip() {
+            return self.fill_frame(clip.x, clip.y, clip.right(), clip.bottom(), color);
+        }
+
         unsafe {
-            let ug_fill_screen: Symbol<unsafe extern "C" fn(u32) -> i32> = LIBRARY
-                .get(b"UG_FillScreen")
-                .expect("Failed to load UG_FillScreen function");
+            let call = stats::start("UG_FillScreen");
+            let ug_fill_screen = symbol_or_return!(UG_FillScreen: unsafe extern "C" fn(u32) -> i32, self);
 
             ug_fill_screen(color);
+            call.done();
         }
 
         self
@@ -397,14 +592,19 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn put_string(&mut self, x: i32, y: i32, display_string: &str) -> &mut Self {
+        if self.clip_text(x, y, display_string) {
+            return self;
+        }
+
         let c_string = std::ffi::CString::new(display_string).expect("CString::new failed");
 
         unsafe {
-            let ug_put_string: Symbol<unsafe extern "C" fn(i32, i32, *const i8) -> i32> = LIBRARY
-                .get(b"UG_PutString")
-                .expect("Failed to load UG_PutString function");
+            let call = stats::start("UG_PutString");
+            let ug_put_string = symbol_or_return!(UG_PutString: unsafe extern "C" fn(i32, i32, *const c_char) -> i32, self);
 
-            ug_put_string(x, y, c_string.as_ptr() as *const i8);
+            let (x, y) = self.to_panel(x, y);
+            ug_put_string(x, y, c_string.as_ptr());
+            call.done();
         }
 
         self
@@ -433,12 +633,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn fill_frame(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &mut Self {
+        if self.clip_frame(x1, y1, x2, y2, color) {
+            return self;
+        }
+
         unsafe {
-            let ug_fill_frame: Symbol<unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_FillFrame")
-                .expect("Failed to load UG_FillFrame function");
+            let call = stats::start("UG_FillFrame");
+            let ug_fill_frame = symbol_or_return!(UG_FillFrame: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32, self);
 
+            let [x1, y1, x2, y2] = self.to_panel_rect(x1, y1, x2, y2);
             ug_fill_frame(x1, y1, x2, y2, color);
+            call.done();
         }
 
         self
@@ -457,12 +662,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn fill_round_frame(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, r: i32, color: u32) -> &mut Self {
+        if self.clip_round_frame(x1, y1, x2, y2, r, color, true) {
+            return self;
+        }
+
         unsafe {
-            let ug_fill_round_frame: Symbol<unsafe extern "C" fn(i32, i32, i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_FillRoundFrame")
-                .expect("Failed to load UG_FillRoundFrame function");
+            let call = stats::start("UG_FillRoundFrame");
+            let ug_fill_round_frame = symbol_or_return!(UG_FillRoundFrame: unsafe extern "C" fn(i32, i32, i32, i32, i32, u32) -> i32, self);
 
-            ug_fill_round_frame(x1, y1, x2, y2, r, color);
+            let [x1, y1, x2, y2] = self.to_panel_rect(x1, y1, x2, y2);
+            ug_fill_round_frame(x1, y1, x2, y2, self.to_panel_len(r), color);
+            call.done();
         }
 
         self
@@ -479,12 +689,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn fill_circle(&mut self, x0: i32, y0: i32, r: i32, color: u32) -> &mut Self {
+        if self.clip_disc(x0, y0, r, color) {
+            return self;
+        }
+
         unsafe {
-            let ug_fill_circle: Symbol<unsafe extern "C" fn(i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_FillCircle")
-                .expect("Failed to load UG_FillCircle function");
+            let call = stats::start("UG_FillCircle");
+            let ug_fill_circle = symbol_or_return!(UG_FillCircle: unsafe extern "C" fn(i32, i32, i32, u32) -> i32, self);
 
-            ug_fill_circle(x0, y0, r, color);
+            let (x0, y0) = self.to_panel(x0, y0);
+            ug_fill_circle(x0, y0, self.to_panel_len(r), color);
+            call.done();
         }
 
         self
@@ -502,12 +717,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn draw_mesh(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &mut Self {
+        if self.clip_mesh(x1, y1, x2, y2, color) {
+            return self;
+        }
+
         unsafe {
-            let ug_draw_mesh: Symbol<unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_DrawMesh")
-                .expect("Failed to load UG_DrawMesh function");
+            let call = stats::start("UG_DrawMesh");
+            let ug_draw_mesh = symbol_or_return!(UG_DrawMesh: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32, self);
 
+            let [x1, y1, x2, y2] = self.to_panel_rect(x1, y1, x2, y2);
             ug_draw_mesh(x1, y1, x2, y2, color);
+            call.done();
         }
 
         self
@@ -525,12 +745,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn draw_frame(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &mut Self {
+        if self.clip_outline(x1, y1, x2, y2, color) {
+            return self;
+        }
+
         unsafe {
-            let ug_draw_frame: Symbol<unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_DrawFrame")
-                .expect("Failed to load UG_DrawFrame function");
+            let call = stats::start("UG_DrawFrame");
+            let ug_draw_frame = symbol_or_return!(UG_DrawFrame: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32, self);
 
+            let [x1, y1, x2, y2] = self.to_panel_rect(x1, y1, x2, y2);
             ug_draw_frame(x1, y1, x2, y2, color);
+            call.done();
         }
 
         self
@@ -549,12 +774,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn draw_round_frame(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, r: i32, color: u32) -> &mut Self {
+        if self.clip_round_frame(x1, y1, x2, y2, r, color, false) {
+            return self;
+        }
+
         unsafe {
-            let ug_draw_round_frame: Symbol<unsafe extern "C" fn(i32, i32, i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_DrawRoundFrame")
-                .expect("Failed to load UG_DrawRoundFrame function");
+            let call = stats::start("UG_DrawRoundFrame");
+            let ug_draw_round_frame = symbol_or_return!(UG_DrawRoundFrame: unsafe extern "C" fn(i32, i32, i32, i32, i32, u32) -> i32, self);
 
-            ug_draw_round_frame(x1, y1, x2, y2, r, color);
+            let [x1, y1, x2, y2] = self.to_panel_rect(x1, y1, x2, y2);
+            ug_draw_round_frame(x1, y1, x2, y2, self.to_panel_len(r), color);
+            call.done();
         }
 
         self
@@ -570,12 +800,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn draw_pixel(&mut self, x0: i32, y0: i32, color: u32) -> &mut Self {
+        if self.clip_pixel(x0, y0) {
+            return self;
+        }
+
         unsafe {
-            let ug_draw_pixel: Symbol<unsafe extern "C" fn(i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_DrawPixel")
-                .expect("Failed to load UG_DrawPixel function");
+            let call = stats::start("UG_DrawPixel");
+            let ug_draw_pixel = symbol_or_return!(UG_DrawPixel: unsafe extern "C" fn(i32, i32, u32) -> i32, self);
 
+            let (x0, y0) = self.to_panel(x0, y0);
             ug_draw_pixel(x0, y0, color);
+            call.done();
         }
 
         self
@@ -592,12 +827,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn draw_circle(&mut self, x0: i32, y0: i32, r: i32, color: u32) -> &mut Self {
+        if self.clip_arc(x0, y0, r, 0xFF, color) {
+            return self;
+        }
+
         unsafe {
-            let ug_draw_circle: Symbol<unsafe extern "C" fn(i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_DrawCircle")
-                .expect("Failed to load UG_DrawCircle function");
+            let call = stats::start("UG_DrawCircle");
+            let ug_draw_circle = symbol_or_return!(UG_DrawCircle: unsafe extern "C" fn(i32, i32, i32, u32) -> i32, self);
 
-            ug_draw_circle(x0, y0, r, color);
+            let (x0, y0) = self.to_panel(x0, y0);
+            ug_draw_circle(x0, y0, self.to_panel_len(r), color);
+            call.done();
         }
 
         self
@@ -615,12 +855,17 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn draw_arc(&mut self, x0: i32, y0: i32, r: i32, s: i32, color: u32) -> &mut Self {
+        if self.clip_arc(x0, y0, r, s, color) {
+            return self;
+        }
+
         unsafe {
-            let ug_draw_arc: Symbol<unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_DrawArc")
-                .expect("Failed to load UG_DrawArc function");
+            let call = stats::start("UG_DrawArc");
+            let ug_draw_arc = symbol_or_return!(UG_DrawArc: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32, self);
 
-            ug_draw_arc(x0, y0, r, s, color);
+            let (x0, y0) = self.to_panel(x0, y0);
+            ug_draw_arc(x0, y0, self.to_panel_len(r), s, color);
+            call.done();
         }
 
         self
@@ -638,14 +883,131 @@ impl Screen {
     /// Returns:
     ///   Self for chainable calls.
     pub fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &mut Self {
+        if self.clip_segment(x1, y1, x2, y2, color) {
+            return self;
+        }
+
         unsafe {
-            let ug_draw_line: Symbol<unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32> = LIBRARY
-                .get(b"UG_DrawLine")
-                .expect("Failed to load UG_DrawLine function");
+            let call = stats::start("UG_DrawLine");
+            let ug_draw_line = symbol_or_return!(UG_DrawLine: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32, self);
 
+            let (x1, y1) = self.to_panel(x1, y1);
+            let (x2, y2) = self.to_panel(x2, y2);
             ug_draw_line(x1, y1, x2, y2, color);
+            call.done();
         }
 
         self
     }
-}
\ No newline at end of file
+}
+/// A drawing job executed on the display worker thread.
+#[cfg(feature = "async")]
+type DrawJob = Box<dyn FnOnce(&mut Screen) + Send>;
+
+/// Async front-end for a [`Screen`].
+///
+/// The screen is moved onto a dedicated blocking worker thread. Drawing is submitted as
+/// closures which run there in submission order, so async tasks never block the runtime on
+/// slow LCD I/O and never need ad-hoc `spawn_blocking` wrappers.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::display::{AsyncScreen, Color, Screen, ScreenDirection};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let screen = AsyncScreen::new(Screen::new(Some(ScreenDirection::Horizontal)));
+///
+///     screen
+///         .submit(|screen| {
+///             screen.fill_screen(Color::BLACK).put_string(0, 0, "Hello").refresh();
+///         })
+///         .await;
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub struct AsyncScreen {
+    jobs: Option<std::sync::mpsc::Sender<DrawJob>>,
+    worker: Option<std::thread::JoinHandle<Screen>>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncScreen {
+    /// Moves `screen` onto a new display worker thread.
+    pub fn new(mut screen: Screen) -> Self {
+        let (jobs, receiver) = std::sync::mpsc::channel::<DrawJob>();
+
+        let worker = std::thread::Builder::new()
+            .name("uptech-display".into())
+            .spawn(move || {
+                for job in receiver {
+                    job(&mut screen);
+                }
+                screen
+            })
+            .expect("Failed to spawn display worker thread");
+
+        AsyncScreen {
+            jobs: Some(jobs),
+            worker: Some(worker),
+        }
+    }
+
+    /// Runs `draw` on the worker thread and resolves with its result once it has finished.
+    ///
+    /// # Panics
+    ///
+    /// If the worker thread has terminated because a previously submitted closure panicked.
+    pub async fn submit<F, R>(&self, draw: F) -> R
+    where
+        F: FnOnce(&mut Screen) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (done, result) = tokio::sync::oneshot::channel();
+        self.enqueue(move |screen| {
+            let _ = done.send(draw(screen));
+        });
+
+        result.await.expect("Display worker thread terminated")
+    }
+
+    /// Queues `draw` without waiting for it to run.
+    pub fn submit_detached<F>(&self, draw: F)
+    where
+        F: FnOnce(&mut Screen) + Send + 'static,
+    {
+        self.enqueue(draw);
+    }
+
+    /// Stops the worker thread after all queued jobs have run and returns the screen.
+    pub fn into_inner(mut self) -> Screen {
+        self.jobs.take();
+        self.worker
+            .take()
+            .expect("Display worker already joined")
+            .join()
+            .expect("Display worker thread panicked")
+    }
+
+    fn enqueue<F>(&self, draw: F)
+    where
+        F: FnOnce(&mut Screen) + Send + 'static,
+    {
+        if let Some(jobs) = &self.jobs
+            && jobs.send(Box::new(draw)).is_err()
+        {
+            crate::log_level::display::error!("Display worker thread terminated, drawing job discarded");
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for AsyncScreen {
+    fn drop(&mut self) {
+        self.jobs.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
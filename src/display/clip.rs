@@ -0,0 +1,381 @@
+use super::framebuffer::FrameBuffer;
+use super::{FontSize, Rect, Screen};
+use crate::log_level::display::warn;
+
+/// Colors and font saved by [`Screen::save_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct DrawState {
+    fore_color: u32,
+    back_color: u32,
+    font_size: FontSize,
+}
+
+/// Where a shape lies relative to the clip rectangle.
+enum Coverage {
+    Inside,
+    Partial(Rect),
+    Outside,
+}
+
+impl Screen {
+    /// Restricts all drawing to `rect`, within the current clip rectangle, until the matching
+    /// [`pop_clip`](Self::pop_clip).
+    ///
+    /// uGUI has no clipping of its own, so it is done before the calls reach the library:
+    /// rectangles and lines are cut to size, circles, arcs and rounded frames that cross the
+    /// edge are drawn pixel by pixel, and text is drawn only for the characters that fit
+    /// entirely. [`refresh`](Self::refresh) is not affected.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::display::{Color, Rect, Screen, ScreenDirection};
+    ///
+    /// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+    ///
+    /// // A gauge that cannot paint outside of its box.
+    /// screen
+    ///     .push_clip(Rect::new(0, 48, 64, 16))
+    ///     .fill_circle(32, 64, 20, Color::GREEN)
+    ///     .pop_clip()
+    ///     .refresh();
+    /// ```
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn push_clip(&mut self, rect: Rect) -> &mut Self {
+        let clip = match self.clip() {
+            Some(current) => current.intersection(&rect),
+            None => rect,
+        };
+        self.clips.push(clip);
+        self
+    }
+
+    /// Restores the clip rectangle in effect before the last [`push_clip`](Self::push_clip).
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn pop_clip(&mut self) -> &mut Self {
+        if self.clips.pop().is_none() {
+            warn!("pop_clip() without a matching push_clip()");
+        }
+        self
+    }
+
+    /// Returns the area drawing is restricted to, or `None` if it is not restricted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::{Rect, Screen};
+    ///
+    /// let mut screen = Screen::new(None);
+    /// screen.push_clip(Rect::new(0, 0, 64, 64)).push_clip(Rect::new(32, 16, 64, 8));
+    /// assert_eq!(screen.clip(), Some(Rect::new(32, 16, 32, 8)));
+    ///
+    /// screen.pop_clip().pop_clip();
+    /// assert_eq!(screen.clip(), None);
+    /// ```
+    pub fn clip(&self) -> Option<Rect> {
+        self.clips.last().copied()
+    }
+
+    /// Saves the foreground and background colors and the font, to be put back by
+    /// [`restore_state`](Self::restore_state).
+    ///
+    /// uGUI keeps them as global state, so a helper that changes the font changes it for
+    /// everything drawn afterwards. Saving and restoring around the helper keeps its changes
+    /// local.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::display::{Color, FontSize, Screen, ScreenDirection};
+    ///
+    /// fn draw_badge(screen: &mut Screen, text: &str) {
+    ///     screen
+    ///         .save_state()
+    ///         .set_font_size(FontSize::Font6x8)
+    ///         .set_fore_color(Color::BLACK)
+    ///         .set_back_color(Color::YELLOW)
+    ///         .put_string(100, 0, text)
+    ///         .restore_state();
+    /// }
+    ///
+    /// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+    /// screen.set_font_size(FontSize::Font8x12);
+    /// draw_badge(&mut screen, "REC");
+    /// // Still in the 8x12 font.
+    /// screen.put_string(0, 20, "Recording").refresh();
+    /// ```
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn save_state(&mut self) -> &mut Self {
+        self.states.push(DrawState {
+            fore_color: self.fore_color,
+            back_color: self.back_color,
+            font_size: self.font_size,
+        });
+        self
+    }
+
+    /// Puts back the colors and font of the last [`save_state`](Self::save_state).
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn restore_state(&mut self) -> &mut Self {
+        let Some(state) = self.states.pop() else {
+            warn!("restore_state() without a matching save_state()");
+            return self;
+        };
+
+        if state.font_size != self.font_size {
+            self.set_font_size(state.font_size);
+        }
+        if state.fore_color != self.fore_color {
+            self.set_fore_color(state.fore_color);
+        }
+        if state.back_color != self.back_color {
+            self.set_back_color(state.back_color);
+        }
+        self
+    }
+
+    /// Runs `draw` between [`save_state`](Self::save_state) and
+    /// [`restore_state`](Self::restore_state), and with `clip` pushed if given.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn scoped<F: FnOnce(&mut Screen)>(&mut self, clip: Option<Rect>, draw: F) -> &mut Self {
+        self.save_state();
+        if let Some(rect) = clip {
+            self.push_clip(rect);
+        }
+        draw(self);
+        if clip.is_some() {
+            self.pop_clip();
+        }
+        self.restore_state()
+    }
+
+    /// Runs `draw` with clipping turned off, for the clipped variants to call uGUI.
+    fn unclipped(&mut self, draw: impl FnOnce(&mut Screen)) {
+        let clips = std::mem::take(&mut self.clips);
+        draw(self);
+        self.clips = clips;
+    }
+
+    fn coverage(&self, bounds: Rect) -> Coverage {
+        let Some(clip) = self.clip() else {
+            return Coverage::Inside;
+        };
+
+        let visible = clip.intersection(&bounds);
+        if visible.is_empty() {
+            Coverage::Outside
+        } else if visible == bounds {
+            Coverage::Inside
+        } else {
+            Coverage::Partial(clip)
+        }
+    }
+
+    /// Clips `fill_frame` and `fill_screen`.
+    ///
+    /// Returns:
+    ///   `true` if the call was handled and uGUI must not be called with the original
+    ///   arguments.
+    pub(super) fn clip_frame(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> bool {
+        match self.coverage(Rect::from_corners(x1, y1, x2, y2)) {
+            Coverage::Inside => false,
+            Coverage::Outside => true,
+            Coverage::Partial(clip) => {
+                let visible = clip.intersection(&Rect::from_corners(x1, y1, x2, y2));
+                self.unclipped(|screen| {
+                    screen.fill_frame(visible.x, visible.y, visible.right(), visible.bottom(), color);
+                });
+                true
+            }
+        }
+    }
+
+    /// Clips `draw_frame` into its four edges.
+    pub(super) fn clip_outline(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> bool {
+        match self.coverage(Rect::from_corners(x1, y1, x2, y2)) {
+            Coverage::Inside => false,
+            Coverage::Outside => true,
+            Coverage::Partial(_) => {
+                self.fill_frame(x1, y1, x2, y1, color)
+                    .fill_frame(x1, y2, x2, y2, color)
+                    .fill_frame(x1, y1, x1, y2, color)
+                    .fill_frame(x2, y1, x2, y2, color);
+                true
+            }
+        }
+    }
+
+    /// Clips `draw_pixel`.
+    pub(super) fn clip_pixel(&self, x: i32, y: i32) -> bool {
+        self.clip().is_some_and(|clip| !clip.contains(x, y))
+    }
+
+    /// Clips `draw_line` to the segment inside the clip rectangle.
+    pub(super) fn clip_segment(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> bool {
+        match self.coverage(Rect::from_corners(x1, y1, x2, y2)) {
+            Coverage::Inside => false,
+            Coverage::Outside => true,
+            Coverage::Partial(clip) => {
+                if let Some([x1, y1, x2, y2]) = clip.clip_line(x1 as f32, y1 as f32, x2 as f32, y2 as f32) {
+                    self.unclipped(|screen| {
+                        screen.draw_line(x1, y1, x2, y2, color);
+                    });
+                }
+                true
+            }
+        }
+    }
+
+    /// Clips circles and arcs by plotting the octants selected by `sectors` pixel by pixel,
+    /// with the sector bits of `UG_DrawArc`.
+    pub(super) fn clip_arc(&mut self, x0: i32, y0: i32, r: i32, sectors: i32, color: u32) -> bool {
+        match self.coverage(Rect::new(x0 - r, y0 - r, 2 * r + 1, 2 * r + 1)) {
+            Coverage::Inside => false,
+            Coverage::Outside => true,
+            Coverage::Partial(_) => {
+                FrameBuffer::circle_octant(r, |far, near| {
+                    // Counterclockwise from the right, two octants per quadrant.
+                    let points = [
+                        (far, -near),
+                        (near, -far),
+                        (-near, -far),
+                        (-far, -near),
+                        (-far, near),
+                        (-near, far),
+                        (near, far),
+                        (far, near),
+                    ];
+                    for (bit, (dx, dy)) in points.into_iter().enumerate() {
+                        if sectors & (1 << bit) != 0 {
+                            self.draw_pixel(x0 + dx, y0 + dy, color);
+                        }
+                    }
+                });
+                true
+            }
+        }
+    }
+
+    /// Clips `fill_circle` into clipped horizontal runs.
+    pub(super) fn clip_disc(&mut self, x0: i32, y0: i32, r: i32, color: u32) -> bool {
+        match self.coverage(Rect::new(x0 - r, y0 - r, 2 * r + 1, 2 * r + 1)) {
+            Coverage::Inside => false,
+            Coverage::Outside => true,
+            Coverage::Partial(_) => {
+                FrameBuffer::circle_octant(r, |far, near| {
+                    self.fill_frame(x0 - far, y0 + near, x0 + far, y0 + near, color)
+                        .fill_frame(x0 - far, y0 - near, x0 + far, y0 - near, color)
+                        .fill_frame(x0 - near, y0 + far, x0 + near, y0 + far, color)
+                        .fill_frame(x0 - near, y0 - far, x0 + near, y0 - far, color);
+                });
+                true
+            }
+        }
+    }
+
+    /// Clips `draw_round_frame` and, with `filled`, `fill_round_frame`.
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn clip_round_frame(
+        &mut self,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        r: i32,
+        color: u32,
+        filled: bool,
+    ) -> bool {
+        let bounds = Rect::from_corners(x1, y1, x2, y2);
+        match self.coverage(bounds) {
+            Coverage::Inside => false,
+            Coverage::Outside => true,
+            Coverage::Partial(_) => {
+                let (left, top, right, bottom) = (bounds.x, bounds.y, bounds.right(), bounds.bottom());
+                let r = r.min(bounds.width / 2).min(bounds.height / 2).max(0);
+                // Centers of the left/right and top/bottom corner arcs.
+                let (cl, cr, ct, cb) = (left + r, right - r, top + r, bottom - r);
+
+                if filled {
+                    self.fill_frame(left, ct, right, cb, color);
+                    FrameBuffer::circle_octant(r, |far, near| {
+                        self.fill_frame(cl - near, ct - far, cr + near, ct - far, color)
+                            .fill_frame(cl - far, ct - near, cr + far, ct - near, color)
+                            .fill_frame(cl - near, cb + far, cr + near, cb + far, color)
+                            .fill_frame(cl - far, cb + near, cr + far, cb + near, color);
+                    });
+                } else {
+                    self.draw_line(cl, top, cr, top, color)
+                        .draw_line(cl, bottom, cr, bottom, color)
+                        .draw_line(left, ct, left, cb, color)
+                        .draw_line(right, ct, right, cb, color);
+                    FrameBuffer::circle_octant(r, |far, near| {
+                        for (dx, dy) in [(near, far), (far, near)] {
+                            self.draw_pixel(cr + dx, ct - dy, color)
+                                .draw_pixel(cr + dx, cb + dy, color)
+                                .draw_pixel(cl - dx, cb + dy, color)
+                                .draw_pixel(cl - dx, ct - dy, color);
+                        }
+                    });
+                }
+                true
+            }
+        }
+    }
+
+    /// Clips `draw_mesh`, which sets every second pixel of every second row.
+    pub(super) fn clip_mesh(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> bool {
+        let bounds = Rect::from_corners(x1, y1, x2, y2);
+        match self.coverage(bounds) {
+            Coverage::Inside => false,
+            Coverage::Outside => true,
+            Coverage::Partial(_) => {
+                for y in (bounds.y..=bounds.bottom()).step_by(2) {
+                    for x in (bounds.x..=bounds.right()).step_by(2) {
+                        self.draw_pixel(x, y, color);
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Clips `put_string` to the characters whose cells lie entirely inside the clip
+    /// rectangle. Only single lines are supported.
+    pub(super) fn clip_text(&mut self, x: i32, y: i32, text: &str) -> bool {
+        let (column, row) = (self.font_size.column_width(), self.font_size.row_height());
+        let width = text.chars().count() as i32 * column;
+        let clip = match self.coverage(Rect::new(x, y, width, row)) {
+            Coverage::Inside => return false,
+            Coverage::Outside => return true,
+            Coverage::Partial(clip) => clip,
+        };
+
+        // Characters fully inside are consecutive; draw them with one call.
+        let visible: Vec<(usize, char)> = text
+            .chars()
+            .enumerate()
+            .filter(|&(index, _)| {
+                let cell = Rect::new(x + index as i32 * column, y, column, row);
+                clip.intersection(&cell) == cell
+            })
+            .collect();
+        if let Some(&(first, _)) = visible.first() {
+            let shown: String = visible.iter().map(|&(_, c)| c).collect();
+            self.unclipped(|screen| {
+                screen.put_string(x + first as i32 * column, y, &shown);
+            });
+        }
+        true
+    }
+}
@@ -0,0 +1,211 @@
+use super::led::write_led;
+use crate::log_level::display::{debug, info};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Brightness control for the onboard LEDs by rapid on/off modulation.
+///
+/// `adc_led_set` only takes a color, and scaling the color down shifts its hue once small
+/// channel values round off. The dimmer instead runs a thread that switches the LEDs between
+/// their full color and off once per period, keeping them on for `brightness` of the time.
+/// At the default 100 Hz the flicker is invisible.
+///
+/// Each switch is a round trip over the IO link, two per LED and period, so very high refresh
+/// rates load the link noticeably. A brightness of exactly `0.0` or `1.0` holds the LEDs off
+/// or on without switching.
+///
+/// While the dimmer runs it owns the LEDs: colors set with
+/// [`Screen::set_led_color`](super::Screen::set_led_color) are overwritten within a period.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::display::{Color, LedDimmer};
+///
+/// let mut dimmer = LedDimmer::new().with_refresh_rate(200.0);
+/// dimmer.set_color(0, Color::GREEN).set_color(1, Color::RED).set_brightness(0.1);
+/// dimmer.start();
+/// ```
+pub struct LedDimmer {
+    period: Duration,
+    colors: Arc<[AtomicU32; 2]>,
+    /// The brightness as the bits of an `f32`, so it can be changed without locking.
+    brightness: Arc<AtomicU32>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl LedDimmer {
+    /// Creates a stopped dimmer refreshing at 100 Hz, with both LEDs off and full brightness.
+    pub fn new() -> Self {
+        LedDimmer {
+            period: Duration::from_millis(10),
+            colors: Arc::new([AtomicU32::new(0), AtomicU32::new(0)]),
+            brightness: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Sets how many on/off cycles run per second. Takes effect on the next
+    /// [`start`](LedDimmer::start).
+    ///
+    /// # Panics
+    ///
+    /// If `refresh_hz` is not a positive, finite number.
+    pub fn with_refresh_rate(mut self, refresh_hz: f32) -> Self {
+        assert!(
+            refresh_hz.is_finite() && refresh_hz > 0.0,
+            "LED refresh rate must be positive, got {}",
+            refresh_hz
+        );
+        self.period = Duration::from_secs_f32(1.0 / refresh_hz);
+        self
+    }
+
+    /// Returns the modulation period.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the color of LED `index` at full brightness.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is not 0 or 1.
+    pub fn color(&self, index: usize) -> u32 {
+        self.colors[index].load(Ordering::Relaxed)
+    }
+
+    /// Sets the color of LED `index` at full brightness. Takes effect from the next period.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is not 0 or 1.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_color(&mut self, index: usize, color: u32) -> &mut Self {
+        self.colors[index].store(color, Ordering::Relaxed);
+        self
+    }
+
+    /// Returns the current brightness.
+    pub fn brightness(&self) -> f32 {
+        f32::from_bits(self.brightness.load(Ordering::Relaxed))
+    }
+
+    /// Sets the share of each period the LEDs are on, clamped to `0.0..=1.0`. Takes effect from
+    /// the next period.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_brightness(&mut self, brightness: f32) -> &mut Self {
+        let brightness = if brightness.is_nan() { 0.0 } else { brightness.clamp(0.0, 1.0) };
+        self.brightness.store(brightness.to_bits(), Ordering::Relaxed);
+        self
+    }
+
+    /// Returns `true` while the modulation thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts the modulation thread. Does nothing if it is already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!("Starting LED dimmer at {:.1} Hz", 1.0 / self.period.as_secs_f32());
+        self.running.store(true, Ordering::Release);
+
+        let period = self.period;
+        let colors = Arc::clone(&self.colors);
+        let brightness = Arc::clone(&self.brightness);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-led-dimmer".into())
+                .spawn(move || {
+                    let mut shown = [None; 2];
+                    let mut show = |on: bool| {
+                        for (index, shown) in shown.iter_mut().enumerate() {
+                            let color = if on { colors[index].load(Ordering::Relaxed) } else { 0 };
+                            if *shown != Some(color) {
+                                write_led(index as i32, color);
+                                *shown = Some(color);
+                            }
+                        }
+                    };
+
+                    let mut period_start = Instant::now();
+                    while running.load(Ordering::Acquire) {
+                        let brightness = f32::from_bits(brightness.load(Ordering::Relaxed));
+
+                        if brightness > 0.0 {
+                            show(true);
+                            sleep_until(period_start + period.mul_f32(brightness));
+                        }
+                        if brightness < 1.0 {
+                            show(false);
+                        }
+
+                        period_start += period;
+                        let now = Instant::now();
+                        if period_start > now {
+                            thread::sleep(period_start - now);
+                        } else {
+                            period_start = now;
+                        }
+                    }
+
+                    show(false);
+                    debug!("LED dimmer thread exited");
+                })
+                .expect("Failed to spawn LED dimmer thread"),
+        );
+
+        self
+    }
+
+    /// Stops the modulation thread and turns both LEDs off.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("LED dimmer stopped");
+        }
+
+        self
+    }
+}
+
+impl Default for LedDimmer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for LedDimmer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        thread::sleep(deadline - now);
+    }
+}
@@ -0,0 +1,469 @@
+use super::{Color, Screen, ScreenDirection};
+use std::collections::HashMap;
+
+/// A horizontal run of pixels of one color, drawn with a single `UG_FillFrame` call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// X coordinate of the leftmost pixel.
+    pub x: i32,
+    /// Row of the run.
+    pub y: i32,
+    /// Number of pixels, at least 1.
+    pub len: i32,
+    /// Color of every pixel of the run.
+    pub color: u32,
+}
+
+/// An in-memory image of the LCD in the 24-bit `0xRRGGBB` format of [`Color`](super::Color).
+///
+/// The drawing methods mirror those of [`Screen`] and clip to the buffer; coordinates of
+/// rectangles are inclusive, like in uGUI.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::{Color, FrameBuffer, Span};
+///
+/// let previous = FrameBuffer::new(128, 64, Color::BLACK);
+/// let mut next = previous.clone();
+/// next.fill_frame(10, 5, 19, 6, Color::RED).draw_pixel(0, 0, Color::WHITE);
+///
+/// let spans = next.diff(&previous);
+/// assert_eq!(spans.len(), 3);
+/// assert_eq!(spans[1], Span { x: 10, y: 5, len: 10, color: Color::RED });
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBuffer {
+    width: i32,
+    height: i32,
+    pixels: Vec<u32>,
+}
+
+impl FrameBuffer {
+    /// Creates a `width` × `height` buffer filled with `color`.
+    pub fn new(width: i32, height: i32, color: u32) -> Self {
+        let (width, height) = (width.max(0), height.max(0));
+        FrameBuffer {
+            width,
+            height,
+            pixels: vec![color; (width * height) as usize],
+        }
+    }
+
+    /// Creates a buffer of the size of the LCD in `direction`, filled with `color`.
+    pub fn for_direction(direction: ScreenDirection, color: u32) -> Self {
+        Self::new(direction.width(), direction.height(), color)
+    }
+
+    /// Returns the width in pixels.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Returns the height in pixels.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if (0..self.width).contains(&x) && (0..self.height).contains(&y) {
+            Some((y * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the color at `(x, y)`, or `None` outside the buffer.
+    pub fn pixel(&self, x: i32, y: i32) -> Option<u32> {
+        self.index(x, y).map(|index| self.pixels[index])
+    }
+
+    /// Returns the pixels row by row.
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// Fills the whole buffer with `color`.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn fill_screen(&mut self, color: u32) -> &mut Self {
+        self.pixels.fill(color);
+        self
+    }
+
+    /// Sets the pixel at `(x, y)`. Pixels outside the buffer are ignored.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn draw_pixel(&mut self, x: i32, y: i32, color: u32) -> &mut Self {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = color;
+        }
+        self
+    }
+
+    /// Fills the rectangle between the corners `(x1, y1)` and `(x2, y2)`, inclusive.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn fill_frame(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &mut Self {
+        let (left, right) = (x1.min(x2).max(0), x1.max(x2).min(self.width - 1));
+        let (top, bottom) = (y1.min(y2).max(0), y1.max(y2).min(self.height - 1));
+        if left > right || top > bottom {
+            return self;
+        }
+
+        for y in top..=bottom {
+            let start = (y * self.width) as usize;
+            self.pixels[start + left as usize..=start + right as usize].fill(color);
+        }
+        self
+    }
+
+    /// Blends `color` with an opacity of `alpha` over the pixel at `(x, y)`, see
+    /// [`Color::blend`]. Pixels outside the buffer are ignored.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: u32, alpha: u8) -> &mut Self {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index] = Color::blend(self.pixels[index], color, alpha);
+        }
+        self
+    }
+
+    /// Blends `color` with an opacity of `alpha` over the rectangle between the corners
+    /// `(x1, y1)` and `(x2, y2)`, inclusive.
+    ///
+    /// # Examples
+    ///
+    /// Dimming the screen behind a dialog:
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::{Color, FrameBuffer};
+    ///
+    /// let mut frame = FrameBuffer::new(128, 64, Color::WHITE);
+    /// frame
+    ///     .blend_frame(0, 0, 127, 63, Color::BLACK, 192)
+    ///     .fill_frame(24, 16, 103, 47, Color::GRAY);
+    ///
+    /// assert_eq!(frame.pixel(0, 0), Some(Color::new_color(63, 63, 63)));
+    /// assert_eq!(frame.pixel(64, 32), Some(Color::GRAY));
+    /// ```
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn blend_frame(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32, alpha: u8) -> &mut Self {
+        let (left, right) = (x1.min(x2).max(0), x1.max(x2).min(self.width - 1));
+        let (top, bottom) = (y1.min(y2).max(0), y1.max(y2).min(self.height - 1));
+        if left > right || top > bottom {
+            return self;
+        }
+
+        for y in top..=bottom {
+            let start = (y * self.width) as usize;
+            for pixel in &mut self.pixels[start + left as usize..=start + right as usize] {
+                *pixel = Color::blend(*pixel, color, alpha);
+            }
+        }
+        self
+    }
+
+    /// Blends all of `overlay` with an opacity of `alpha` over this buffer, with its top-left
+    /// corner at `(x, y)`. Pixels of `overlay` with the color `transparent` are skipped, so
+    /// overlays can have any shape.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn composite(&mut self, overlay: &FrameBuffer, x: i32, y: i32, alpha: u8, transparent: Option<u32>) -> &mut Self {
+        for oy in 0..overlay.height {
+            for ox in 0..overlay.width {
+                let color = overlay.pixels[(oy * overlay.width + ox) as usize];
+                if Some(color) != transparent {
+                    self.blend_pixel(x + ox, y + oy, color, alpha);
+                }
+            }
+        }
+        self
+    }
+
+    /// Draws the outline of the rectangle between `(x1, y1)` and `(x2, y2)`, inclusive.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn draw_frame(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &mut Self {
+        self.fill_frame(x1, y1, x2, y1, color)
+            .fill_frame(x1, y2, x2, y2, color)
+            .fill_frame(x1, y1, x1, y2, color)
+            .fill_frame(x2, y1, x2, y2, color)
+    }
+
+    /// Draws a line from `(x1, y1)` to `(x2, y2)`.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &mut Self {
+        let (dx, dy) = ((x2 - x1).abs(), -(y2 - y1).abs());
+        let (step_x, step_y) = ((x2 - x1).signum(), (y2 - y1).signum());
+        let (mut x, mut y, mut error) = (x1, y1, dx + dy);
+
+        loop {
+            self.draw_pixel(x, y, color);
+            if x == x2 && y == y2 {
+                return self;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Calls `plot(dx, dy)` for the first octant of a circle of radius `r`.
+    pub(super) fn circle_octant(r: i32, mut plot: impl FnMut(i32, i32)) {
+        let (mut dx, mut dy, mut error) = (r, 0, 1 - r);
+        while dx >= dy {
+            plot(dx, dy);
+            dy += 1;
+            if error < 0 {
+                error += 2 * dy + 1;
+            } else {
+                dx -= 1;
+                error += 2 * (dy - dx) + 1;
+            }
+        }
+    }
+
+    /// Draws the outline of a circle around `(x0, y0)`.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn draw_circle(&mut self, x0: i32, y0: i32, r: i32, color: u32) -> &mut Self {
+        Self::circle_octant(r, |dx, dy| {
+            for (x, y) in [(dx, dy), (dy, dx), (-dy, dx), (-dx, dy), (-dx, -dy), (-dy, -dx), (dy, -dx), (dx, -dy)] {
+                self.draw_pixel(x0 + x, y0 + y, color);
+            }
+        });
+        self
+    }
+
+    /// Fills a circle around `(x0, y0)`.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn fill_circle(&mut self, x0: i32, y0: i32, r: i32, color: u32) -> &mut Self {
+        Self::circle_octant(r, |dx, dy| {
+            self.fill_frame(x0 - dx, y0 + dy, x0 + dx, y0 + dy, color)
+                .fill_frame(x0 - dx, y0 - dy, x0 + dx, y0 - dy, color)
+                .fill_frame(x0 - dy, y0 + dx, x0 + dy, y0 + dx, color)
+                .fill_frame(x0 - dy, y0 - dx, x0 + dy, y0 - dx, color);
+        });
+        self
+    }
+
+    /// Returns the spans that turn `previous` into this buffer, row by row.
+    ///
+    /// A span may include unchanged pixels between two changed ones when they already have
+    /// its color, so that one call covers both. Both buffers must have the same size.
+    ///
+    /// # Panics
+    ///
+    /// If the sizes differ.
+    pub fn diff(&self, previous: &FrameBuffer) -> Vec<Span> {
+        assert!(
+            self.width == previous.width && self.height == previous.height,
+            "frame buffer sizes differ: {}x{} and {}x{}",
+            self.width,
+            self.height,
+            previous.width,
+            previous.height
+        );
+        self.runs(|index| self.pixels[index] != previous.pixels[index])
+    }
+
+    /// Returns the spans that draw the whole buffer.
+    pub fn spans(&self) -> Vec<Span> {
+        self.runs(|_| true)
+    }
+
+    fn runs(&self, changed: impl Fn(usize) -> bool) -> Vec<Span> {
+        let mut spans = Vec::new();
+
+        for y in 0..self.height {
+            let row = (y * self.width) as usize;
+            let mut x = 0;
+            while x < self.width {
+                if !changed(row + x as usize) {
+                    x += 1;
+                    continue;
+                }
+
+                let color = self.pixels[row + x as usize];
+                let start = x;
+                let mut last_changed = x;
+                x += 1;
+                while x < self.width && self.pixels[row + x as usize] == color {
+                    if changed(row + x as usize) {
+                        last_changed = x;
+                    }
+                    x += 1;
+                }
+
+                spans.push(Span {
+                    x: start,
+                    y,
+                    len: last_changed - start + 1,
+                    color,
+                });
+                x = last_changed + 1;
+            }
+        }
+
+        spans
+    }
+
+    /// Returns the color most pixels have.
+    fn dominant_color(&self) -> u32 {
+        let mut counts = HashMap::new();
+        for &color in &self.pixels {
+            *counts.entry(color).or_insert(0usize) += 1;
+        }
+        counts.into_iter().max_by_key(|&(_, count)| count).map_or(0, |(color, _)| color)
+    }
+}
+
+/// A [`Screen`] with a front and a back [`FrameBuffer`], for flicker-free animation.
+///
+/// Frames are drawn into the back buffer with [`frame_mut`](Self::frame_mut). On
+/// [`refresh`](Self::refresh), only the pixels that differ from the front buffer, the frame
+/// currently on the LCD, are sent, merged into horizontal runs of one `UG_FillFrame` call
+/// each. An unchanged frame costs no drawing calls at all, and the LCD never shows a
+/// half-cleared scene.
+///
+/// The buffers know nothing about text and other drawing done on the [`Screen`] directly.
+/// Call [`invalidate`](Self::invalidate) after such drawing, or after waking the screen, to
+/// send the complete frame on the next refresh.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::display::{BufferedScreen, Color, Screen, ScreenDirection};
+///
+/// let mut screen = BufferedScreen::new(Screen::new(Some(ScreenDirection::Horizontal)));
+///
+/// for x in 0..118 {
+///     screen
+///         .frame_mut()
+///         .fill_screen(Color::BLACK)
+///         .fill_circle(x + 5, 32, 5, Color::GREEN);
+///     screen.refresh();
+/// }
+/// ```
+pub struct BufferedScreen {
+    screen: Screen,
+    front: Option<FrameBuffer>,
+    back: FrameBuffer,
+}
+
+impl BufferedScreen {
+    /// Wraps `screen`, sized like [`Screen::size`].
+    ///
+    /// The first [`refresh`](Self::refresh) sends the complete frame.
+    pub fn new(screen: Screen) -> Self {
+        let (width, height) = screen.size();
+        BufferedScreen {
+            screen,
+            front: None,
+            back: FrameBuffer::new(width, height, super::Color::BLACK),
+        }
+    }
+
+    /// Returns the back buffer, the frame being drawn.
+    pub fn frame(&self) -> &FrameBuffer {
+        &self.back
+    }
+
+    /// Returns the back buffer for drawing the next frame.
+    pub fn frame_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.back
+    }
+
+    /// Returns the wrapped screen, for text and other drawing the buffers do not cover.
+    pub fn screen(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
+
+    /// Forgets what the LCD shows, so the next [`refresh`](Self::refresh) sends the complete
+    /// frame.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn invalidate(&mut self) -> &mut Self {
+        self.front = None;
+        self
+    }
+
+    /// Sends the changes of the back buffer to the LCD and refreshes it.
+    ///
+    /// After an [`invalidate`](Self::invalidate), the screen is cleared with the most common
+    /// color of the frame first, and only the remaining pixels are drawn.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn refresh(&mut self) -> &mut Self {
+        let spans = match &self.front {
+            Some(front) => self.back.diff(front),
+            None => {
+                let background = self.back.dominant_color();
+                self.screen.fill_screen(background);
+                self.back
+                    .diff(&FrameBuffer::new(self.back.width, self.back.height, background))
+            }
+        };
+
+        for span in &spans {
+            if span.len == 1 {
+                self.screen.draw_pixel(span.x, span.y, span.color);
+            } else {
+                self.screen
+                    .fill_frame(span.x, span.y, span.x + span.len - 1, span.y, span.color);
+            }
+        }
+        self.screen.refresh();
+
+        match &mut self.front {
+            Some(front) => front.pixels.copy_from_slice(&self.back.pixels),
+            None => self.front = Some(self.back.clone()),
+        }
+        self
+    }
+
+    /// [Rotates](Screen::rotate) the screen and resizes the back buffer to match, keeping the
+    /// part of the frame that still fits at the same coordinates. The next
+    /// [`refresh`](Self::refresh) sends the complete frame.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn rotate(&mut self, direction: ScreenDirection) -> &mut Self {
+        self.screen.rotate(direction);
+
+        let (width, height) = self.screen.size();
+        if (width, height) != (self.back.width, self.back.height) {
+            let mut back = FrameBuffer::new(width, height, self.back.dominant_color());
+            back.composite(&self.back, 0, 0, u8::MAX, None);
+            self.back = back;
+        }
+        self.invalidate()
+    }
+
+    /// Returns the wrapped screen.
+    pub fn into_inner(self) -> Screen {
+        self.screen
+    }
+}
@@ -0,0 +1,273 @@
+use crate::adc_io::{ParallelBus, set_io_levels_with_mask, set_io_mode};
+use crate::log_level::display::{info, warn};
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+const CLEAR_DISPLAY: u8 = 0x01;
+const RETURN_HOME: u8 = 0x02;
+const ENTRY_MODE_SET: u8 = 0x04;
+const DISPLAY_CONTROL: u8 = 0x08;
+const FUNCTION_SET: u8 = 0x20;
+const SET_CGRAM_ADDRESS: u8 = 0x40;
+const SET_DDRAM_ADDRESS: u8 = 0x80;
+
+/// Display control flags.
+const DISPLAY_ON: u8 = 0x04;
+const CURSOR_ON: u8 = 0x02;
+const BLINK_ON: u8 = 0x01;
+
+/// A HD44780 compatible character LCD, such as the common 1602 and 2004 modules, in 4-bit
+/// mode.
+///
+/// Wire D4-D7 to a 4-pin [`ParallelBus`] whose strobe pin is `E` (active high), `RS` to another
+/// IO pin and tie `RW` low; D0-D3 stay unconnected. That takes 6 of the 8 IO pins.
+///
+/// The driver keeps track of the cursor, so [`write_str`](Hd44780::write_str) wraps long text
+/// to the next row and moves there on `'\n'`, which the controller does not do by itself.
+/// Characters outside printable ASCII are shown as `?`, except the codes 0-7 of
+/// [custom characters](Hd44780::create_char).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io::{self, ParallelBus};
+/// use uptechstar_rs::display::Hd44780;
+///
+/// adc_io::adc_open();
+///
+/// let bus = ParallelBus::new(0, 4).with_strobe(4, true);
+/// let mut lcd = Hd44780::new(bus, 5, 16, 2);
+/// lcd.write_str("Battery 7.4V\nAuto mode");
+/// lcd.set_cursor(14, 0).write_str("OK");
+/// ```
+pub struct Hd44780 {
+    bus: ParallelBus,
+    rs: u8,
+    columns: u8,
+    rows: u8,
+    control: u8,
+    cursor: (u8, u8),
+}
+
+impl Hd44780 {
+    /// Initializes a display of `columns` x `rows` characters on `bus` and register select pin
+    /// `rs`, then clears it. The display is on, with the cursor hidden.
+    ///
+    /// # Panics
+    ///
+    /// If `bus` is not 4 pins wide or has no strobe pin, `rs` is not in `0..8` or is used by
+    /// the bus, or the size is not 1-40 columns by 1-2 rows or 1-20 columns by 3-4 rows.
+    pub fn new(bus: ParallelBus, rs: u32, columns: u8, rows: u8) -> Self {
+        assert_eq!(bus.width(), 4, "HD44780 needs a 4-pin bus for D4-D7");
+        assert!(bus.strobe_pin().is_some(), "HD44780 needs the bus strobe on the E pin");
+        assert!(rs < 8, "IO pin index must be in 0..8, got {}", rs);
+        assert!(
+            !bus.pins().contains(&rs) && bus.strobe_pin() != Some(rs),
+            "HD44780 RS pin IO{} is used by the bus",
+            rs
+        );
+        assert!(
+            (1..=40).contains(&columns) && (1..=4).contains(&rows) && (rows <= 2 || columns <= 20),
+            "Unsupported HD44780 size {}x{}",
+            columns,
+            rows
+        );
+
+        if set_io_mode(rs, 1) != 0 {
+            warn!("Failed to switch IO{} to output mode for the HD44780 RS pin", rs);
+        }
+
+        let mut lcd = Hd44780 {
+            bus,
+            rs: 1 << rs,
+            columns,
+            rows,
+            control: DISPLAY_ON,
+            cursor: (0, 0),
+        };
+        lcd.init();
+        info!("HD44780 {}x{} initialized", columns, rows);
+        lcd
+    }
+
+    /// Runs the initialization by instruction sequence from the datasheet, which brings the
+    /// controller into 4-bit mode from any state.
+    fn init(&mut self) {
+        thread::sleep(Duration::from_millis(50));
+        set_io_levels_with_mask(self.rs, 0);
+
+        for delay in [4100, 100, 100] {
+            self.bus.write(0x3);
+            thread::sleep(Duration::from_micros(delay));
+        }
+        self.bus.write(0x2);
+
+        let lines = if self.rows > 1 { 0x08 } else { 0x00 };
+        self.command(FUNCTION_SET | lines);
+        self.command(DISPLAY_CONTROL);
+        self.clear();
+        self.command(ENTRY_MODE_SET | 0x02);
+        self.command(DISPLAY_CONTROL | self.control);
+    }
+
+    /// Returns the size as `(columns, rows)`.
+    pub fn size(&self) -> (u8, u8) {
+        (self.columns, self.rows)
+    }
+
+    /// Returns the cursor position as `(column, row)`.
+    pub fn cursor(&self) -> (u8, u8) {
+        self.cursor
+    }
+
+    /// Clears the display and moves the cursor to the top left.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn clear(&mut self) -> &mut Self {
+        self.command(CLEAR_DISPLAY);
+        thread::sleep(Duration::from_micros(1600));
+        self.cursor = (0, 0);
+        self
+    }
+
+    /// Moves the cursor to the top left and undoes any display shift.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn home(&mut self) -> &mut Self {
+        self.command(RETURN_HOME);
+        thread::sleep(Duration::from_micros(1600));
+        self.cursor = (0, 0);
+        self
+    }
+
+    /// Moves the cursor, clamped to the display size.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_cursor(&mut self, column: u8, row: u8) -> &mut Self {
+        let column = column.min(self.columns - 1);
+        let row = row.min(self.rows - 1);
+        // Rows 2 and 3 continue the DDRAM lines of rows 0 and 1.
+        let line_start = [0x00, 0x40, self.columns, 0x40 + self.columns][row as usize];
+        self.command(SET_DDRAM_ADDRESS | (line_start + column));
+        self.cursor = (column, row);
+        self
+    }
+
+    /// Turns the display on or off, keeping its contents.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_display(&mut self, on: bool) -> &mut Self {
+        self.set_control(DISPLAY_ON, on)
+    }
+
+    /// Shows or hides the underline cursor.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn show_cursor(&mut self, show: bool) -> &mut Self {
+        self.set_control(CURSOR_ON, show)
+    }
+
+    /// Turns blinking of the character at the cursor on or off.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_blink(&mut self, blink: bool) -> &mut Self {
+        self.set_control(BLINK_ON, blink)
+    }
+
+    fn set_control(&mut self, flag: u8, on: bool) -> &mut Self {
+        if on {
+            self.control |= flag;
+        } else {
+            self.control &= !flag;
+        }
+        self.command(DISPLAY_CONTROL | self.control);
+        self
+    }
+
+    /// Defines custom character `code` (0-7) from 8 rows of 5 pixels, the least significant bit
+    /// on the right. Write it with `char::from(code)`. The cursor position is kept.
+    ///
+    /// # Panics
+    ///
+    /// If `code` is not in `0..8`.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn create_char(&mut self, code: u8, rows: [u8; 8]) -> &mut Self {
+        assert!(code < 8, "HD44780 custom character code must be in 0..8, got {}", code);
+
+        self.command(SET_CGRAM_ADDRESS | (code << 3));
+        for row in rows {
+            self.data(row & 0x1F);
+        }
+        let (column, row) = self.cursor;
+        self.set_cursor(column, row)
+    }
+
+    /// Writes `text` at the cursor, wrapping at the end of a row and starting a new row on
+    /// `'\n'`. Text past the last row wraps to the first.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn write_str(&mut self, text: &str) -> &mut Self {
+        for ch in text.chars() {
+            if ch == '\n' {
+                self.next_row();
+                continue;
+            }
+
+            let code = match ch {
+                '\0'..='\x07' | ' '..='~' => ch as u8,
+                _ => b'?',
+            };
+            self.data(code);
+
+            self.cursor.0 += 1;
+            if self.cursor.0 == self.columns {
+                self.next_row();
+            }
+        }
+        self
+    }
+
+    fn next_row(&mut self) {
+        let row = (self.cursor.1 + 1) % self.rows;
+        self.set_cursor(0, row);
+    }
+
+    fn command(&mut self, byte: u8) -> i32 {
+        self.send(byte, false)
+    }
+
+    fn data(&mut self, byte: u8) -> i32 {
+        self.send(byte, true)
+    }
+
+    /// Sends a byte as two nibbles, high nibble first. Every IO update takes longer than the
+    /// 37µs most instructions need, so no extra delay is required.
+    fn send(&mut self, byte: u8, data: bool) -> i32 {
+        let code = set_io_levels_with_mask(self.rs, if data { self.rs } else { 0 });
+        if code != 0 {
+            return code;
+        }
+        let code = self.bus.write((byte >> 4) as u32);
+        if code != 0 {
+            return code;
+        }
+        self.bus.write((byte & 0x0F) as u32)
+    }
+}
+
+impl fmt::Write for Hd44780 {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        Hd44780::write_str(self, text);
+        Ok(())
+    }
+}
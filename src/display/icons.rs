@@ -0,0 +1,178 @@
+use super::Screen;
+
+/// Built-in 8x8 status symbols, drawn with [`Screen::draw_icon`].
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::Icon;
+///
+/// assert_eq!(Icon::battery(80), Icon::Battery75);
+/// assert_eq!(Icon::wifi(-90), Icon::Wifi1);
+///
+/// // Each row is a byte, the most significant bit on the left.
+/// assert_eq!(Icon::ArrowUp.bitmap()[0], 0b0001_1000);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Icon {
+    Battery0,
+    Battery25,
+    Battery50,
+    Battery75,
+    Battery100,
+    /// No connection.
+    WifiOff,
+    /// Weak signal.
+    Wifi1,
+    Wifi2,
+    /// Full signal.
+    Wifi3,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    Warning,
+    Play,
+    Pause,
+    Stop,
+    Check,
+    Cross,
+}
+
+/// A battery with `filled` of its 4 segments filled.
+const fn battery(filled: usize) -> [u8; 8] {
+    const SEGMENTS: [u8; 5] = [0x00, 0x40, 0x60, 0x70, 0x78];
+    let fill = SEGMENTS[filled];
+    [0x00, 0xFC, 0x84 | fill, 0x86 | fill, 0x86 | fill, 0x84 | fill, 0xFC, 0x00]
+}
+
+/// Rows of the Wi-Fi arcs, outermost arc first.
+const WIFI_OUTER: [u8; 8] = [0x3C, 0x42, 0x81, 0x00, 0x00, 0x00, 0x00, 0x00];
+const WIFI_MIDDLE: [u8; 8] = [0x00, 0x00, 0x18, 0x24, 0x00, 0x00, 0x00, 0x00];
+const WIFI_DOT: [u8; 8] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00];
+
+const fn combine(a: [u8; 8], b: [u8; 8]) -> [u8; 8] {
+    let mut rows = [0; 8];
+    let mut row = 0;
+    while row < 8 {
+        rows[row] = a[row] | b[row];
+        row += 1;
+    }
+    rows
+}
+
+const fn flip_vertical(rows: [u8; 8]) -> [u8; 8] {
+    [rows[7], rows[6], rows[5], rows[4], rows[3], rows[2], rows[1], rows[0]]
+}
+
+const ARROW_UP: [u8; 8] = [0x18, 0x3C, 0x7E, 0xFF, 0x18, 0x18, 0x18, 0x18];
+const ARROW_LEFT: [u8; 8] = [0x10, 0x30, 0x70, 0xFF, 0xFF, 0x70, 0x30, 0x10];
+const WIFI_FULL: [u8; 8] = combine(combine(WIFI_OUTER, WIFI_MIDDLE), WIFI_DOT);
+/// A diagonal from the top right to the bottom left corner.
+const SLASH: [u8; 8] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80];
+
+impl Icon {
+    /// Width and height of every icon in pixels.
+    pub const SIZE: i32 = 8;
+
+    /// Returns the battery icon closest to `percent`.
+    pub fn battery(percent: u8) -> Icon {
+        match percent {
+            0..=12 => Icon::Battery0,
+            13..=37 => Icon::Battery25,
+            38..=62 => Icon::Battery50,
+            63..=87 => Icon::Battery75,
+            _ => Icon::Battery100,
+        }
+    }
+
+    /// Returns the Wi-Fi icon for a signal strength of `rssi` dBm, or [`Icon::WifiOff`] for
+    /// `i32::MIN`, which marks a missing connection.
+    pub fn wifi(rssi: i32) -> Icon {
+        match rssi {
+            i32::MIN => Icon::WifiOff,
+            rssi if rssi >= -60 => Icon::Wifi3,
+            rssi if rssi >= -75 => Icon::Wifi2,
+            _ => Icon::Wifi1,
+        }
+    }
+
+    /// Returns the pixels, one byte per row from the top with the most significant bit on the
+    /// left.
+    pub const fn bitmap(self) -> [u8; 8] {
+        match self {
+            Icon::Battery0 => battery(0),
+            Icon::Battery25 => battery(1),
+            Icon::Battery50 => battery(2),
+            Icon::Battery75 => battery(3),
+            Icon::Battery100 => battery(4),
+            Icon::WifiOff => combine(WIFI_FULL, SLASH),
+            Icon::Wifi1 => WIFI_DOT,
+            Icon::Wifi2 => combine(WIFI_MIDDLE, WIFI_DOT),
+            Icon::Wifi3 => WIFI_FULL,
+            Icon::ArrowUp => ARROW_UP,
+            Icon::ArrowDown => flip_vertical(ARROW_UP),
+            Icon::ArrowLeft => ARROW_LEFT,
+            Icon::ArrowRight => [0x08, 0x0C, 0x0E, 0xFF, 0xFF, 0x0E, 0x0C, 0x08],
+            Icon::Warning => [0x18, 0x3C, 0x24, 0x66, 0x66, 0xFF, 0xE7, 0xFF],
+            Icon::Play => [0x60, 0x78, 0x7E, 0x7F, 0x7F, 0x7E, 0x78, 0x60],
+            Icon::Pause => [0x66; 8],
+            Icon::Stop => [0x00, 0x7E, 0x7E, 0x7E, 0x7E, 0x7E, 0x7E, 0x00],
+            Icon::Check => [0x01, 0x03, 0x06, 0x8C, 0xD8, 0x70, 0x20, 0x00],
+            Icon::Cross => [0xC3, 0xE7, 0x7E, 0x3C, 0x3C, 0x7E, 0xE7, 0xC3],
+        }
+    }
+
+    /// Returns the horizontal runs of set pixels as `(x, y, length)`.
+    pub(crate) fn runs(self) -> impl Iterator<Item = (i32, i32, i32)> {
+        self.bitmap().into_iter().enumerate().flat_map(|(y, row)| {
+            let mut runs = Vec::new();
+            let mut x = 0;
+            while x < 8 {
+                if row & (0x80 >> x) == 0 {
+                    x += 1;
+                    continue;
+                }
+                let start = x;
+                while x < 8 && row & (0x80 >> x) != 0 {
+                    x += 1;
+                }
+                runs.push((start, y as i32, x - start));
+            }
+            runs
+        })
+    }
+}
+
+impl Screen {
+    /// Draws `icon` with its top-left corner at `(x, y)` in the foreground color, leaving the
+    /// pixels around the symbol untouched.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::display::{Color, Icon, Screen, ScreenDirection};
+    ///
+    /// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+    /// screen
+    ///     .set_fore_color(Color::GREEN)
+    ///     .draw_icon(Icon::battery(72), 118, 0)
+    ///     .draw_icon(Icon::wifi(-68), 108, 0)
+    ///     .refresh();
+    /// ```
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn draw_icon(&mut self, icon: Icon, x: i32, y: i32) -> &mut Self {
+        let color = self.fore_color;
+        for (dx, dy, length) in icon.runs() {
+            if length == 1 {
+                self.draw_pixel(x + dx, y + dy, color);
+            } else {
+                self.draw_line(x + dx, y + dy, x + dx + length - 1, y + dy, color);
+            }
+        }
+        self
+    }
+}
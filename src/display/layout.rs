@@ -0,0 +1,131 @@
+use super::{Screen, ScreenDirection};
+use crate::log_level::display::info;
+
+impl Screen {
+    /// Returns the width and height in pixels that drawing coordinates refer to: those of the
+    /// [layout](Self::set_layout) if one is set, otherwise those of the direction the LCD was
+    /// opened in, or the horizontal size if it was never opened.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::{Screen, ScreenDirection};
+    ///
+    /// let mut screen = Screen::new(None);
+    /// assert_eq!(screen.size(), (128, 64));
+    ///
+    /// screen.set_layout(Some(ScreenDirection::Vertical));
+    /// assert_eq!(screen.size(), (64, 128));
+    /// ```
+    pub fn size(&self) -> (i32, i32) {
+        let direction = self.layout.or(self.screen_dir);
+        direction.map_or((128, 64), |direction| (direction.width(), direction.height()))
+    }
+
+    /// Returns the width and height of the LCD in the direction it was opened in.
+    pub(super) fn panel_size(&self) -> (i32, i32) {
+        self.screen_dir
+            .map_or((128, 64), |direction| (direction.width(), direction.height()))
+    }
+
+    /// Lays out all drawing for `layout`, or draws in LCD coordinates with `None` (the default).
+    ///
+    /// With a layout, coordinates and lengths are scaled from the layout's size to the size of
+    /// the opened LCD before they reach uGUI, so a UI written for one direction keeps its
+    /// proportions in the other. Glyphs keep their size; pick a font that fits both.
+    /// Clip rectangles are given in layout coordinates too.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::display::{Color, Screen, ScreenDirection};
+    ///
+    /// // The dashboard was designed for the horizontal screen.
+    /// let mut screen = Screen::new(Some(ScreenDirection::Vertical));
+    /// screen.set_layout(Some(ScreenDirection::Horizontal));
+    ///
+    /// // The left half of the horizontal layout becomes the top half of the vertical panel.
+    /// screen.fill_frame(0, 0, 63, 63, Color::BLUE).refresh();
+    /// ```
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_layout(&mut self, layout: Option<ScreenDirection>) -> &mut Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Returns the layout set with [`set_layout`](Self::set_layout).
+    pub fn layout(&self) -> Option<ScreenDirection> {
+        self.layout
+    }
+
+    /// Reopens the LCD in `direction` and clears it with the background color.
+    ///
+    /// uGUI lays out its framebuffer for the new direction when the LCD is opened, so
+    /// whatever was drawn is lost: redraw, then [`refresh`](Self::refresh). The font and
+    /// colors are set again. Does nothing if the LCD is already open in `direction`.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn rotate(&mut self, direction: ScreenDirection) -> &mut Self {
+        if self.screen_dir == Some(direction) {
+            return self;
+        }
+
+        info!("Rotating LCD to {:?}", direction);
+        let (font_size, fore_color, back_color) = (self.font_size, self.fore_color, self.back_color);
+        self.open(direction)
+            .set_font_size(font_size)
+            .set_fore_color(fore_color)
+            .set_back_color(back_color)
+            .fill_screen(back_color)
+    }
+
+    /// Scales a point from layout to LCD coordinates.
+    pub(super) fn to_panel(&self, x: i32, y: i32) -> (i32, i32) {
+        let Some((layout, panel)) = self.scaling() else {
+            return (x, y);
+        };
+        (x * panel.0 / layout.0, y * panel.1 / layout.1)
+    }
+
+    /// Scales the corners of a rectangle from layout to LCD coordinates, keeping adjacent
+    /// rectangles adjacent.
+    pub(super) fn to_panel_rect(&self, x1: i32, y1: i32, x2: i32, y2: i32) -> [i32; 4] {
+        let Some((layout, panel)) = self.scaling() else {
+            return [x1, y1, x2, y2];
+        };
+        let (x1, y1) = self.to_panel(x1, y1);
+        [x1, y1, (x2 + 1) * panel.0 / layout.0 - 1, (y2 + 1) * panel.1 / layout.1 - 1]
+    }
+
+    /// Scales a radius from layout to LCD pixels, by the smaller factor so circles stay
+    /// inside the area they were laid out in.
+    pub(super) fn to_panel_len(&self, length: i32) -> i32 {
+        let Some((layout, panel)) = self.scaling() else {
+            return length;
+        };
+        (length * panel.0 / layout.0).min(length * panel.1 / layout.1)
+    }
+
+    /// Scales a width and height from LCD pixels to layout units, rounding up so the
+    /// extent still covers what was drawn.
+    pub(super) fn to_layout_size(&self, width: i32, height: i32) -> (i32, i32) {
+        let Some((layout, panel)) = self.scaling() else {
+            return (width, height);
+        };
+        (
+            (width * layout.0 + panel.0 - 1) / panel.0,
+            (height * layout.1 + panel.1 - 1) / panel.1,
+        )
+    }
+
+    /// Returns the layout and LCD sizes if they differ.
+    fn scaling(&self) -> Option<((i32, i32), (i32, i32))> {
+        let layout = self.layout?;
+        let layout = (layout.width(), layout.height());
+        let panel = self.panel_size();
+        (layout != panel).then_some((layout, panel))
+    }
+}
@@ -0,0 +1,130 @@
+use crate::extern_lib::symbol_or_return;
+use crate::stats;
+use crate::log_level::display::info;
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+/// Brightness and gamma applied to the colors of the onboard LEDs by
+/// [`Screen::set_led_color`](super::Screen::set_led_color).
+///
+/// The LEDs' light output is linear in the PWM duty cycle, but perceived brightness is not:
+/// without correction, a fade from full to half brightness barely looks dimmer, while the
+/// last few steps to black look like jumps. With a gamma above 1, every channel is mapped as
+/// `255 * (brightness * value / 255) ^ gamma`, so equal steps in value look like equal steps in
+/// brightness.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::{Color, LedCorrection};
+///
+/// // No correction by default.
+/// assert_eq!(LedCorrection::default().apply(Color::new_color(255, 128, 0)), Color::new_color(255, 128, 0));
+///
+/// // Looks half as bright as full red.
+/// let half = LedCorrection::perceptual().with_brightness(0.5);
+/// assert_eq!(half.apply(Color::RED), Color::new_color(55, 0, 0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LedCorrection {
+    /// Factor all channels are scaled by before gamma correction, from 0 to 1.
+    pub brightness: f32,
+    /// Exponent of the gamma curve; 1 is linear.
+    pub gamma: f32,
+}
+
+impl LedCorrection {
+    /// No correction: colors are sent as they are.
+    pub const LINEAR: LedCorrection = LedCorrection {
+        brightness: 1.0,
+        gamma: 1.0,
+    };
+
+    /// The common gamma of 2.2 at full brightness.
+    pub fn perceptual() -> Self {
+        LedCorrection {
+            brightness: 1.0,
+            gamma: 2.2,
+        }
+    }
+
+    /// Sets the brightness, clamped to `0.0..=1.0`.
+    pub fn with_brightness(mut self, brightness: f32) -> Self {
+        self.brightness = if brightness.is_nan() { 0.0 } else { brightness.clamp(0.0, 1.0) };
+        self
+    }
+
+    /// Corrects a `0xRRGGBB` color.
+    pub fn apply(&self, color: u32) -> u32 {
+        if *self == Self::LINEAR {
+            return color;
+        }
+
+        let channel = |shift: u32| {
+            let value = ((color >> shift) & 0xFF) as f32 / 255.0;
+            let corrected = (value * self.brightness).powf(self.gamma) * 255.0;
+            (corrected.round() as u32).min(255) << shift
+        };
+        channel(16) | channel(8) | channel(0)
+    }
+}
+
+impl Default for LedCorrection {
+    fn default() -> Self {
+        Self::LINEAR
+    }
+}
+
+static CORRECTION: Lazy<RwLock<LedCorrection>> = Lazy::new(|| RwLock::new(LedCorrection::LINEAR));
+
+/// Returns the correction applied to LED colors.
+pub fn led_correction() -> LedCorrection {
+    *CORRECTION.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets the correction applied by every later [`set_led_color`](super::Screen::set_led_color)
+/// call. Returns the previous setting.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use std::time::Duration;
+/// use uptechstar_rs::display::{self, Color, LedCorrection, Screen};
+///
+/// display::set_led_correction(LedCorrection::perceptual());
+///
+/// // A breathing effect that fades evenly.
+/// let mut screen = Screen::new(None);
+/// for step in (0..=50).chain((0..50).rev()) {
+///     display::set_led_brightness(step as f32 / 50.0);
+///     screen.set_led_0(Color::CYAN);
+///     thread::sleep(Duration::from_millis(20));
+/// }
+/// ```
+pub fn set_led_correction(correction: LedCorrection) -> LedCorrection {
+    info!("LED correction set to {:?}", correction);
+    let mut guard = CORRECTION.write().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *guard, correction)
+}
+
+/// Sets only the brightness of the [LED correction](set_led_correction), clamped to
+/// `0.0..=1.0`. Returns the previous brightness.
+pub fn set_led_brightness(brightness: f32) -> f32 {
+    let mut guard = CORRECTION.write().unwrap_or_else(|e| e.into_inner());
+    let previous = guard.brightness;
+    *guard = guard.with_brightness(brightness);
+    previous
+}
+
+/// Sends `color` to LED `index`, after the [LED correction](set_led_correction). Returns the
+/// status code of `adc_led_set`.
+pub(super) fn write_led(index: i32, color: u32) -> i32 {
+    unsafe {
+        let call = stats::start("adc_led_set");
+        let adc_led_set = symbol_or_return!(adc_led_set: unsafe extern "C" fn(i32, u32) -> i32, -1);
+
+        call.finish(adc_led_set(index, led_correction().apply(color)))
+    }
+}
@@ -0,0 +1,252 @@
+use crate::adc_io::{set_io_levels_with_mask, set_io_mode};
+use crate::log_level::display::{error, info, warn};
+use std::time::{Duration, Instant};
+
+/// SPI clock for the SPI-assisted output: three SPI bits per WS2812 bit gives the 800 kHz
+/// data rate, with 417ns per SPI bit.
+#[cfg(target_os = "linux")]
+const SPI_SPEED_HZ: u32 = 2_400_000;
+
+/// Zero bytes sent after a frame over SPI, holding the line low for the >280µs latch time of
+/// newer WS2812B revisions.
+#[cfg(target_os = "linux")]
+const SPI_RESET_BYTES: usize = 90;
+
+enum Output {
+    Io(u8),
+    #[cfg(target_os = "linux")]
+    Spi(std::fs::File),
+}
+
+/// A strip of WS2812 / NeoPixel RGB LEDs.
+///
+/// Colors are stored in the same 24-bit `0xRRGGBB` format as [`Color`](super::Color) and sent
+/// in the GRB order the LEDs expect, scaled by the strip brightness. Changes are buffered
+/// until [`show`](NeoPixelStrip::show).
+///
+/// WS2812 timing needs a precision of about 150ns:
+///
+/// - [`spi`](NeoPixelStrip::spi) drives the strip from a Linux `spidev` MOSI pin, encoding
+///   every data bit as three SPI bits. This is reliable and the recommended setup.
+/// - [`new`](NeoPixelStrip::new) bit-bangs an IO pin. Each level change is a round trip over
+///   the IO link, far slower than the protocol allows, so this only works with the few LED
+///   variants that tolerate stretched pulses, and even then usually just for the first LEDs.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::display::{Color, NeoPixelStrip};
+///
+/// let mut strip = NeoPixelStrip::spi("/dev/spidev1.0", 8).unwrap().with_brightness(0.2);
+/// strip.fill(Color::BLUE);
+/// strip.set(0, Color::RED);
+/// strip.show();
+/// ```
+pub struct NeoPixelStrip {
+    output: Output,
+    pixels: Vec<u32>,
+    brightness: f32,
+    last_show: Option<Instant>,
+}
+
+impl NeoPixelStrip {
+    /// Creates a strip of `len` LEDs bit-banged on IO `pin`, all off.
+    ///
+    /// # Panics
+    ///
+    /// If `pin` is not in `0..8`.
+    pub fn new(pin: u32, len: usize) -> Self {
+        assert!(pin < 8, "IO pin index must be in 0..8, got {}", pin);
+
+        if set_io_mode(pin, 1) != 0 {
+            warn!("Failed to switch IO{} to output mode for the LED strip", pin);
+        }
+        set_io_levels_with_mask(1 << pin, 0);
+
+        Self::with_output(Output::Io(1 << pin), len)
+    }
+
+    /// Creates a strip of `len` LEDs on the MOSI pin of the SPI device at `path`, e.g.
+    /// `/dev/spidev1.0`, all off.
+    #[cfg(target_os = "linux")]
+    pub fn spi(path: &str, len: usize) -> std::io::Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        /// `_IOW('k', 4, u32)` from `linux/spi/spidev.h`.
+        const SPI_IOC_WR_MAX_SPEED_HZ: u32 = 0x4004_6b04;
+
+        let file = std::fs::OpenOptions::new().write(true).open(path)?;
+        let speed = SPI_SPEED_HZ;
+        if unsafe { libc::ioctl(file.as_raw_fd(), SPI_IOC_WR_MAX_SPEED_HZ as _, &speed) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        info!("Driving {} LEDs over {} at {} Hz", len, path, speed);
+        Ok(Self::with_output(Output::Spi(file), len))
+    }
+
+    fn with_output(output: Output, len: usize) -> Self {
+        NeoPixelStrip {
+            output,
+            pixels: vec![0; len],
+            brightness: 1.0,
+            last_show: None,
+        }
+    }
+
+    /// Sets the brightness all colors are scaled by, clamped to `0.0..=1.0`.
+    pub fn with_brightness(mut self, brightness: f32) -> Self {
+        self.set_brightness(brightness);
+        self
+    }
+
+    /// Sets the brightness all colors are scaled by, clamped to `0.0..=1.0`. Takes effect on
+    /// the next [`show`](NeoPixelStrip::show).
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_brightness(&mut self, brightness: f32) -> &mut Self {
+        self.brightness = if brightness.is_nan() { 0.0 } else { brightness.clamp(0.0, 1.0) };
+        self
+    }
+
+    /// Returns the brightness.
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    /// Returns the number of LEDs.
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    /// Returns `true` for a strip without LEDs.
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Returns the buffered colors.
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    /// Returns the buffered colors for modification.
+    pub fn pixels_mut(&mut self) -> &mut [u32] {
+        &mut self.pixels
+    }
+
+    /// Sets LED `index` to `color`. Indices past the end are ignored.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set(&mut self, index: usize, color: u32) -> &mut Self {
+        if let Some(pixel) = self.pixels.get_mut(index) {
+            *pixel = color;
+        }
+        self
+    }
+
+    /// Sets every LED to `color`.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn fill(&mut self, color: u32) -> &mut Self {
+        self.pixels.fill(color);
+        self
+    }
+
+    /// Turns every LED off. Takes effect on the next [`show`](NeoPixelStrip::show).
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn clear(&mut self) -> &mut Self {
+        self.fill(0)
+    }
+
+    /// Sends the buffered colors to the strip.
+    ///
+    /// # Returns
+    ///
+    /// * `i32` - Returns `0` on success, non-zero on failure.
+    pub fn show(&mut self) -> i32 {
+        // The strip latches after the line stays low for a while; make sure the previous
+        // frame has been latched before the next one starts.
+        if let Some(last_show) = self.last_show {
+            let latched = last_show + Duration::from_micros(300);
+            let now = Instant::now();
+            if latched > now {
+                std::thread::sleep(latched - now);
+            }
+        }
+
+        let data = encode(&self.pixels, self.brightness);
+        let result = match &mut self.output {
+            Output::Io(mask) => bit_bang(*mask, &data),
+            #[cfg(target_os = "linux")]
+            Output::Spi(file) => {
+                use std::io::Write;
+
+                match file.write_all(&spi_encode(&data)) {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        error!("Failed to write the LED strip frame: {}", e);
+                        -1
+                    }
+                }
+            }
+        };
+
+        self.last_show = Some(Instant::now());
+        result
+    }
+}
+
+/// Converts `0xRRGGBB` colors into the GRB byte stream of the LEDs, scaled by `brightness`.
+fn encode(pixels: &[u32], brightness: f32) -> Vec<u8> {
+    let scale = |channel: u32| ((channel & 0xFF) as f32 * brightness).round() as u8;
+
+    pixels
+        .iter()
+        .flat_map(|&color| [scale(color >> 8), scale(color >> 16), scale(color)])
+        .collect()
+}
+
+/// Expands every data bit into the SPI bit pattern `110` (one) or `100` (zero), followed by
+/// the latch time.
+#[cfg(target_os = "linux")]
+fn spi_encode(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(data.len() * 3 + SPI_RESET_BYTES);
+
+    for &byte in data {
+        let mut bits = 0u32;
+        for bit in (0..8).rev() {
+            bits = bits << 3 | if byte >> bit & 1 == 1 { 0b110 } else { 0b100 };
+        }
+        encoded.extend_from_slice(&bits.to_be_bytes()[1..]);
+    }
+
+    encoded.resize(encoded.len() + SPI_RESET_BYTES, 0);
+    encoded
+}
+
+/// Sends `data` MSB first by toggling the IO pin in `mask`, a short high pulse for a zero and
+/// a long one for a one.
+fn bit_bang(mask: u8, data: &[u8]) -> i32 {
+    for &byte in data {
+        for bit in (0..8).rev() {
+            let code = set_io_levels_with_mask(mask, mask);
+            if code != 0 {
+                return code;
+            }
+            if byte >> bit & 1 == 1 {
+                // Hold the line high for a second write's worth of time.
+                set_io_levels_with_mask(mask, mask);
+            }
+            let code = set_io_levels_with_mask(mask, 0);
+            if code != 0 {
+                return code;
+            }
+        }
+    }
+    0
+}
@@ -0,0 +1,318 @@
+use super::scene::Scene;
+use super::{Color, Screen};
+use crate::events::Event;
+use crate::log_level::display::{debug, warn};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One page of a [`Pager`].
+///
+/// Closures taking a `&mut Screen` are pages that draw over the previous frame on every
+/// render and refresh the screen afterwards; they clear what they need to themselves. A
+/// [`Scene`] is a page that only redraws what changed.
+pub trait Page: Send {
+    /// Draws the page, called on every [`Pager::render`] while it is shown.
+    fn render(&mut self, screen: &mut Screen);
+
+    /// Called when the page is shown, before its first render. The screen has been cleared.
+    fn on_enter(&mut self) {}
+
+    /// Called on [`Nav::Select`] while the page is shown.
+    fn on_select(&mut self) {}
+
+    /// Offered every navigation input before the pager acts on it, for pages with their own
+    /// controls such as the [settings editor](crate::settings::SettingsEditor).
+    ///
+    /// Returns:
+    ///   `true` if the page used the input; the pager then ignores it.
+    fn handle_nav(&mut self, _nav: Nav) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(&mut Screen) + Send> Page for F {
+    fn render(&mut self, screen: &mut Screen) {
+        self(screen);
+        screen.refresh();
+    }
+}
+
+impl Page for Scene {
+    fn render(&mut self, screen: &mut Screen) {
+        Scene::render(self, screen);
+    }
+
+    fn on_enter(&mut self) {
+        self.invalidate();
+    }
+}
+
+/// A navigation input of a [`Pager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Nav {
+    /// Show the next page, wrapping around after the last.
+    Next,
+    /// Show the previous page, wrapping around before the first.
+    Previous,
+    /// Trigger the current page's [`on_select`](Page::on_select).
+    Select,
+}
+
+/// How a [`Pager`] switches between pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transition {
+    /// Clear the screen and draw the new page at once.
+    #[default]
+    Cut,
+    /// Wipe the old page off column by column over the given time, in the direction of
+    /// navigation, then draw the new page.
+    Wipe(Duration),
+}
+
+/// Maps a press of one of the `[next, previous, select]` button pins to its [`Nav`].
+pub(crate) fn button_nav(buttons: Option<[u32; 3]>, event: &Event) -> Option<Nav> {
+    let (Some([next, previous, select]), Event::ButtonPressed { pin }) = (buttons, event) else {
+        return None;
+    };
+
+    match *pin {
+        pin if pin == next => Some(Nav::Next),
+        pin if pin == previous => Some(Nav::Previous),
+        pin if pin == select => Some(Nav::Select),
+        _ => None,
+    }
+}
+
+/// Number of steps of a [`Transition::Wipe`].
+const WIPE_STEPS: i32 = 8;
+
+/// Multiple named pages, one shown at a time, navigated with buttons.
+///
+/// Typical competition robots have a setup, a telemetry and a run page. The pager draws the
+/// current page on [`render`](Self::render) and switches pages on [`Nav`] inputs, which
+/// [`handle_event`](Self::handle_event) derives from the button events of the event bus.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use uptechstar_rs::display::{Color, Pager, Screen, ScreenDirection, Transition};
+/// use uptechstar_rs::events;
+///
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+///
+/// let mut pager = Pager::new(Color::BLACK)
+///     .with_page("setup", |screen: &mut Screen| {
+///         screen.put_string(0, 0, "Setup");
+///     })
+///     .with_page("telemetry", |screen: &mut Screen| {
+///         screen.put_string(0, 0, "Telemetry");
+///     })
+///     .with_buttons(0, 1, 2)
+///     .with_transition(Transition::Wipe(Duration::from_millis(80)));
+///
+/// // Buttons on IO 0, 1 and 2 publish ButtonPressed events on the global bus.
+/// pager.run(&mut screen, &events::subscribe(), Duration::from_millis(100));
+/// ```
+pub struct Pager {
+    background: u32,
+    pages: Vec<(String, Box<dyn Page>)>,
+    current: usize,
+    /// Page shown on screen, `None` before the first render.
+    shown: Option<usize>,
+    direction: Nav,
+    transition: Transition,
+    buttons: Option<[u32; 3]>,
+}
+
+impl Pager {
+    /// Creates a pager without pages on a `background` colored screen.
+    pub fn new(background: u32) -> Self {
+        Pager {
+            background,
+            pages: Vec::new(),
+            current: 0,
+            shown: None,
+            direction: Nav::Next,
+            transition: Transition::Cut,
+            buttons: None,
+        }
+    }
+
+    /// Adds a page after the existing ones. The first page added is shown first.
+    pub fn with_page<S: Into<String>, P: Page + 'static>(mut self, name: S, page: P) -> Self {
+        self.add_page(name, page);
+        self
+    }
+
+    /// Adds a page after the existing ones.
+    pub fn add_page<S: Into<String>, P: Page + 'static>(&mut self, name: S, page: P) -> &mut Self {
+        self.pages.push((name.into(), Box::new(page)));
+        self
+    }
+
+    /// Sets how pages are switched. The default is [`Transition::Cut`].
+    pub fn with_transition(mut self, transition: Transition) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    /// Maps presses of the buttons on IO pins `next`, `previous` and `select` to navigation in
+    /// [`handle_event`](Self::handle_event).
+    pub fn with_buttons(mut self, next: u32, previous: u32, select: u32) -> Self {
+        self.buttons = Some([next, previous, select]);
+        self
+    }
+
+    /// Returns the number of pages.
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns `true` if no pages were added.
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    /// Returns the name of the current page, or `None` without pages.
+    pub fn current(&self) -> Option<&str> {
+        self.pages.get(self.current).map(|(name, _)| name.as_str())
+    }
+
+    /// Switches to the page named `name` on the next render.
+    ///
+    /// Returns:
+    ///   `false` if there is no such page.
+    pub fn show(&mut self, name: &str) -> bool {
+        match self.pages.iter().position(|(page, _)| page == name) {
+            Some(index) => {
+                self.direction = if index < self.current { Nav::Previous } else { Nav::Next };
+                self.current = index;
+                true
+            }
+            None => {
+                warn!("No page named '{}'", name);
+                false
+            }
+        }
+    }
+
+    /// Applies a navigation input, unless the current page [handles](Page::handle_nav) it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::{Color, Nav, Pager, Screen};
+    ///
+    /// let mut pager = Pager::new(Color::BLACK)
+    ///     .with_page("setup", |_: &mut Screen| {})
+    ///     .with_page("run", |_: &mut Screen| {});
+    ///
+    /// pager.navigate(Nav::Next);
+    /// assert_eq!(pager.current(), Some("run"));
+    /// pager.navigate(Nav::Next);
+    /// assert_eq!(pager.current(), Some("setup"));
+    /// ```
+    pub fn navigate(&mut self, nav: Nav) {
+        let count = self.pages.len();
+        if count == 0 || self.pages[self.current].1.handle_nav(nav) {
+            return;
+        }
+
+        match nav {
+            Nav::Next => self.current = (self.current + 1) % count,
+            Nav::Previous => self.current = (self.current + count - 1) % count,
+            Nav::Select => {
+                self.pages[self.current].1.on_select();
+                return;
+            }
+        }
+        self.direction = nav;
+        debug!("Pager switched to '{}'", self.pages[self.current].0);
+    }
+
+    /// Navigates if `event` is a press of one of the [buttons](Self::with_buttons).
+    ///
+    /// Returns:
+    ///   `true` if the event was used.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match button_nav(self.buttons, event) {
+            Some(nav) => {
+                self.navigate(nav);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Draws the current page, switching pages with the transition first if needed.
+    pub fn render(&mut self, screen: &mut Screen) {
+        if self.pages.is_empty() {
+            return;
+        }
+
+        if self.shown != Some(self.current) {
+            if self.shown.is_some() {
+                self.transition_out(screen);
+            }
+            screen.fill_screen(self.background);
+            self.pages[self.current].1.on_enter();
+            self.shown = Some(self.current);
+        }
+
+        self.pages[self.current].1.render(screen);
+    }
+
+    fn transition_out(&self, screen: &mut Screen) {
+        let Transition::Wipe(duration) = self.transition else {
+            return;
+        };
+
+        let (width, height) = screen.size();
+        let step_width = (width + WIPE_STEPS - 1) / WIPE_STEPS;
+        let delay = duration / WIPE_STEPS as u32;
+
+        for step in 0..WIPE_STEPS {
+            let x1 = match self.direction {
+                Nav::Previous => (width - (step + 1) * step_width).max(0),
+                _ => step * step_width,
+            };
+            screen
+                .fill_frame(x1, 0, x1 + step_width - 1, height - 1, self.background)
+                .refresh();
+            thread::sleep(delay);
+        }
+    }
+
+    /// Renders pages and handles `events` until the sender side of `events` is dropped.
+    ///
+    /// The current page is rendered every `period`, and right after every navigation input.
+    pub fn run(&mut self, screen: &mut Screen, events: &Receiver<Event>, period: Duration) {
+        let mut next_render = Instant::now();
+
+        loop {
+            let now = Instant::now();
+            if now >= next_render {
+                self.render(screen);
+                next_render = now + period;
+            }
+
+            match events.recv_timeout(next_render.saturating_duration_since(Instant::now())) {
+                Ok(event) => {
+                    if self.handle_event(&event) {
+                        next_render = Instant::now();
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+    }
+}
+
+impl Default for Pager {
+    fn default() -> Self {
+        Pager::new(Color::BLACK)
+    }
+}
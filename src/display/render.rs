@@ -0,0 +1,465 @@
+use super::{FontSize, Icon, Screen};
+use crate::extern_lib::symbol_or_return;
+use crate::log_level::display::{debug, error, info};
+use crate::stats;
+use once_cell::sync::Lazy;
+use std::ffi::{CString, c_char};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// One drawing call of a [`Screen`], as data.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawOp {
+    FillScreen(u32),
+    SetForeColor(u32),
+    SetBackColor(u32),
+    SetFontSize(FontSize),
+    PutString { x: i32, y: i32, text: String },
+    FillFrame { x1: i32, y1: i32, x2: i32, y2: i32, color: u32 },
+    DrawFrame { x1: i32, y1: i32, x2: i32, y2: i32, color: u32 },
+    DrawLine { x1: i32, y1: i32, x2: i32, y2: i32, color: u32 },
+    DrawPixel { x: i32, y: i32, color: u32 },
+    DrawCircle { x0: i32, y0: i32, r: i32, color: u32 },
+    FillCircle { x0: i32, y0: i32, r: i32, color: u32 },
+    DrawIcon { icon: Icon, x: i32, y: i32 },
+}
+
+impl Screen {
+    /// Executes a recorded drawing call.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn apply(&mut self, op: &DrawOp) -> &mut Self {
+        match *op {
+            DrawOp::FillScreen(color) => self.fill_screen(color),
+            DrawOp::SetForeColor(color) => self.set_fore_color(color),
+            DrawOp::SetBackColor(color) => self.set_back_color(color),
+            DrawOp::SetFontSize(font) => self.set_font_size(font),
+            DrawOp::PutString { x, y, ref text } => self.put_string(x, y, text),
+            DrawOp::FillFrame { x1, y1, x2, y2, color } => self.fill_frame(x1, y1, x2, y2, color),
+            DrawOp::DrawFrame { x1, y1, x2, y2, color } => self.draw_frame(x1, y1, x2, y2, color),
+            DrawOp::DrawLine { x1, y1, x2, y2, color } => self.draw_line(x1, y1, x2, y2, color),
+            DrawOp::DrawPixel { x, y, color } => self.draw_pixel(x, y, color),
+            DrawOp::DrawCircle { x0, y0, r, color } => self.draw_circle(x0, y0, r, color),
+            DrawOp::FillCircle { x0, y0, r, color } => self.fill_circle(x0, y0, r, color),
+            DrawOp::DrawIcon { icon, x, y } => self.draw_icon(icon, x, y),
+        }
+    }
+
+    /// Executes a slice of recorded drawing calls.
+    ///
+    /// Each drawing method of the screen looks up its C function and updates the
+    /// [FFI statistics](crate::stats) on every call. A batch looks the functions up once per
+    /// process and is accounted as a single `draw_batch` call, which adds up for dashboards
+    /// redrawing dozens of elements per frame. Coordinates are scaled to the
+    /// [layout](Screen::set_layout) like those of the drawing methods. While a
+    /// [clip](Screen::push_clip) is active, or if the library lacks one of the functions, the
+    /// calls are executed one by one with [`apply`](Screen::apply) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::display::{Color, DrawOp, FontSize, Screen, ScreenDirection};
+    ///
+    /// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+    /// let mut frame = vec![DrawOp::FillScreen(Color::BLACK), DrawOp::SetFontSize(FontSize::Font6x8)];
+    /// for (row, value) in [12, 480, 7].into_iter().enumerate() {
+    ///     let y = row as i32 * 10;
+    ///     frame.push(DrawOp::PutString { x: 0, y, text: format!("ch{}: {:>4}", row, value) });
+    ///     frame.push(DrawOp::FillFrame { x1: 60, y1: y, x2: 60 + value / 8, y2: y + 6, color: Color::GREEN });
+    /// }
+    ///
+    /// screen.draw_batch(&frame).refresh();
+    /// ```
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn draw_batch(&mut self, ops: &[DrawOp]) -> &mut Self {
+        let primitives = match &*PRIMITIVES {
+            Some(primitives) if self.clip().is_none() => primitives,
+            _ => {
+                for op in ops {
+                    self.apply(op);
+                }
+                return self;
+            }
+        };
+
+        let call = stats::start("draw_batch");
+        for op in ops {
+            unsafe { self.execute(primitives, op) };
+        }
+        call.done();
+
+        self
+    }
+
+    /// Executes `op` through the resolved `primitives`, without clipping.
+    unsafe fn execute(&mut self, primitives: &Primitives, op: &DrawOp) {
+        unsafe {
+            match *op {
+                DrawOp::FillScreen(color) => {
+                    (primitives.fill_screen)(color);
+                }
+                DrawOp::SetForeColor(color) => {
+                    self.fore_color = color;
+                    (primitives.set_fore_color)(color);
+                }
+                DrawOp::SetBackColor(color) => {
+                    self.back_color = color;
+                    (primitives.set_back_color)(color);
+                }
+                DrawOp::SetFontSize(font) => {
+                    self.font_size = font;
+                    (primitives.set_font)(font as i32);
+                }
+                DrawOp::PutString { x, y, ref text } => {
+                    let text = CString::new(text.as_str()).expect("CString::new failed");
+                    let (x, y) = self.to_panel(x, y);
+                    (primitives.put_string)(x, y, text.as_ptr());
+                }
+                DrawOp::FillFrame { x1, y1, x2, y2, color } => {
+                    let [x1, y1, x2, y2] = self.to_panel_rect(x1, y1, x2, y2);
+                    (primitives.fill_frame)(x1, y1, x2, y2, color);
+                }
+                DrawOp::DrawFrame { x1, y1, x2, y2, color } => {
+                    let [x1, y1, x2, y2] = self.to_panel_rect(x1, y1, x2, y2);
+                    (primitives.draw_frame)(x1, y1, x2, y2, color);
+                }
+                DrawOp::DrawLine { x1, y1, x2, y2, color } => {
+                    self.execute_line(primitives, x1, y1, x2, y2, color);
+                }
+                DrawOp::DrawPixel { x, y, color } => {
+                    let (x, y) = self.to_panel(x, y);
+                    (primitives.draw_pixel)(x, y, color);
+                }
+                DrawOp::DrawCircle { x0, y0, r, color } => {
+                    let (x0, y0) = self.to_panel(x0, y0);
+                    (primitives.draw_circle)(x0, y0, self.to_panel_len(r), color);
+                }
+                DrawOp::FillCircle { x0, y0, r, color } => {
+                    let (x0, y0) = self.to_panel(x0, y0);
+                    (primitives.fill_circle)(x0, y0, self.to_panel_len(r), color);
+                }
+                DrawOp::DrawIcon { icon, x, y } => {
+                    let color = self.fore_color;
+                    for (dx, dy, length) in icon.runs() {
+                        if length == 1 {
+                            let (x, y) = self.to_panel(x + dx, y + dy);
+                            (primitives.draw_pixel)(x, y, color);
+                        } else {
+                            self.execute_line(primitives, x + dx, y + dy, x + dx + length - 1, y + dy, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws a line in layout coordinates through the resolved `primitives`, like
+    /// [`draw_line`](Screen::draw_line).
+    unsafe fn execute_line(&self, primitives: &Primitives, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) {
+        let (x1, y1) = self.to_panel(x1, y1);
+        let (x2, y2) = self.to_panel(x2, y2);
+        unsafe { (primitives.draw_line)(x1, y1, x2, y2, color) };
+    }
+
+    /// Moves the screen onto a render thread refreshing every `period`, see [`RenderThread`].
+    pub fn spawn_renderer(self, period: Duration) -> RenderThread {
+        RenderThread::spawn(self, period)
+    }
+}
+
+/// The C functions behind the [`DrawOp`]s, looked up once for [`Screen::draw_batch`].
+struct Primitives {
+    fill_screen: unsafe extern "C" fn(u32) -> i32,
+    set_fore_color: unsafe extern "C" fn(u32) -> i32,
+    set_back_color: unsafe extern "C" fn(u32) -> i32,
+    set_font: unsafe extern "C" fn(i32) -> i32,
+    put_string: unsafe extern "C" fn(i32, i32, *const c_char) -> i32,
+    fill_frame: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32,
+    draw_frame: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32,
+    draw_line: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32,
+    draw_pixel: unsafe extern "C" fn(i32, i32, u32) -> i32,
+    draw_circle: unsafe extern "C" fn(i32, i32, i32, u32) -> i32,
+    fill_circle: unsafe extern "C" fn(i32, i32, i32, u32) -> i32,
+}
+
+impl Primitives {
+    /// Looks up every function, or returns `None` if one is missing. The library stays loaded
+    /// for the life of the process, so the function pointers stay valid.
+    fn resolve() -> Option<Self> {
+        unsafe {
+            Some(Primitives {
+                fill_screen: *symbol_or_return!(UG_FillScreen: unsafe extern "C" fn(u32) -> i32, None),
+                set_fore_color: *symbol_or_return!(UG_SetForecolor: unsafe extern "C" fn(u32) -> i32, None),
+                set_back_color: *symbol_or_return!(UG_SetBackcolor: unsafe extern "C" fn(u32) -> i32, None),
+                set_font: *symbol_or_return!(LCD_SetFont: unsafe extern "C" fn(i32) -> i32, None),
+                put_string: *symbol_or_return!(UG_PutString: unsafe extern "C" fn(i32, i32, *const c_char) -> i32, None),
+                fill_frame: *symbol_or_return!(UG_FillFrame: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32, None),
+                draw_frame: *symbol_or_return!(UG_DrawFrame: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32, None),
+                draw_line: *symbol_or_return!(UG_DrawLine: unsafe extern "C" fn(i32, i32, i32, i32, u32) -> i32, None),
+                draw_pixel: *symbol_or_return!(UG_DrawPixel: unsafe extern "C" fn(i32, i32, u32) -> i32, None),
+                draw_circle: *symbol_or_return!(UG_DrawCircle: unsafe extern "C" fn(i32, i32, i32, u32) -> i32, None),
+                fill_circle: *symbol_or_return!(UG_FillCircle: unsafe extern "C" fn(i32, i32, i32, u32) -> i32, None),
+            })
+        }
+    }
+}
+
+static PRIMITIVES: Lazy<Option<Primitives>> = Lazy::new(Primitives::resolve);
+
+enum Command {
+    Op(DrawOp),
+    Run(Box<dyn FnOnce(&mut Screen) + Send>),
+    /// Ends the render loop, after the commands queued before it.
+    Stop,
+}
+
+/// A handle queueing drawing on a [`RenderThread`].
+///
+/// It has the drawing methods of [`Screen`], but they only send the call to the render thread
+/// and return at once. Handles are cheap to clone and can be moved into other threads.
+#[derive(Clone)]
+pub struct RenderQueue {
+    commands: Sender<Command>,
+}
+
+impl RenderQueue {
+    fn send(&self, command: Command) -> &Self {
+        if self.commands.send(command).is_err() {
+            error!("Render thread terminated, drawing discarded");
+        }
+        self
+    }
+
+    /// Queues a recorded drawing call.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn push(&self, op: DrawOp) -> &Self {
+        self.send(Command::Op(op))
+    }
+
+    /// Queues `draw` to run on the render thread, for drawing without a [`DrawOp`], such as
+    /// widgets.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn run<F: FnOnce(&mut Screen) + Send + 'static>(&self, draw: F) -> &Self {
+        self.send(Command::Run(Box::new(draw)))
+    }
+
+    /// Queues [`Screen::fill_screen`].
+    pub fn fill_screen(&self, color: u32) -> &Self {
+        self.push(DrawOp::FillScreen(color))
+    }
+
+    /// Queues [`Screen::set_fore_color`].
+    pub fn set_fore_color(&self, color: u32) -> &Self {
+        self.push(DrawOp::SetForeColor(color))
+    }
+
+    /// Queues [`Screen::set_back_color`].
+    pub fn set_back_color(&self, color: u32) -> &Self {
+        self.push(DrawOp::SetBackColor(color))
+    }
+
+    /// Queues [`Screen::set_font_size`].
+    pub fn set_font_size(&self, font: FontSize) -> &Self {
+        self.push(DrawOp::SetFontSize(font))
+    }
+
+    /// Queues [`Screen::put_string`].
+    pub fn put_string<S: Into<String>>(&self, x: i32, y: i32, text: S) -> &Self {
+        self.push(DrawOp::PutString { x, y, text: text.into() })
+    }
+
+    /// Queues [`Screen::fill_frame`].
+    pub fn fill_frame(&self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &Self {
+        self.push(DrawOp::FillFrame { x1, y1, x2, y2, color })
+    }
+
+    /// Queues [`Screen::draw_frame`].
+    pub fn draw_frame(&self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &Self {
+        self.push(DrawOp::DrawFrame { x1, y1, x2, y2, color })
+    }
+
+    /// Queues [`Screen::draw_line`].
+    pub fn draw_line(&self, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> &Self {
+        self.push(DrawOp::DrawLine { x1, y1, x2, y2, color })
+    }
+
+    /// Queues [`Screen::draw_pixel`].
+    pub fn draw_pixel(&self, x: i32, y: i32, color: u32) -> &Self {
+        self.push(DrawOp::DrawPixel { x, y, color })
+    }
+
+    /// Queues [`Screen::draw_circle`].
+    pub fn draw_circle(&self, x0: i32, y0: i32, r: i32, color: u32) -> &Self {
+        self.push(DrawOp::DrawCircle { x0, y0, r, color })
+    }
+
+    /// Queues [`Screen::fill_circle`].
+    pub fn fill_circle(&self, x0: i32, y0: i32, r: i32, color: u32) -> &Self {
+        self.push(DrawOp::FillCircle { x0, y0, r, color })
+    }
+
+    /// Queues [`Screen::draw_icon`].
+    pub fn draw_icon(&self, icon: Icon, x: i32, y: i32) -> &Self {
+        self.push(DrawOp::DrawIcon { icon, x, y })
+    }
+}
+
+/// A thread that owns a [`Screen`], executes the drawing queued through [`RenderQueue`]s and
+/// refreshes the LCD at a fixed cadence.
+///
+/// Every drawing call is an FFI round trip and a refresh takes milliseconds, which shows up as
+/// jitter when a control loop draws its own status. With a render thread, the loop only sends
+/// the calls over a channel. The LCD is refreshed once per period, and only if something was
+/// drawn since the last refresh.
+///
+/// The thread stops, and returns the screen, on [`stop`](Self::stop); dropping the render
+/// thread stops it as well.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use uptechstar_rs::display::{Color, Screen, ScreenDirection};
+///
+/// let renderer = Screen::new(Some(ScreenDirection::Horizontal)).spawn_renderer(Duration::from_millis(50));
+/// let display = renderer.queue();
+///
+/// for tick in 0u32.. {
+///     // ... control loop work ...
+///     display
+///         .fill_frame(0, 0, 127, 11, Color::BLACK)
+///         .put_string(0, 0, format!("tick {}", tick));
+/// #   break;
+/// }
+///
+/// let screen = renderer.stop();
+/// ```
+pub struct RenderThread {
+    queue: RenderQueue,
+    frames: Arc<AtomicU64>,
+    thread: Option<JoinHandle<Screen>>,
+}
+
+impl RenderThread {
+    /// Moves `screen` onto a new render thread refreshing at most every `period`.
+    pub fn spawn(mut screen: Screen, period: Duration) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let frames = Arc::new(AtomicU64::new(0));
+        let counter = frames.clone();
+
+        info!("Starting render thread with a {:?} period", period);
+        let thread = thread::Builder::new()
+            .name("uptech-render".into())
+            .spawn(move || {
+                render_loop(&mut screen, &receiver, period, &counter);
+                screen
+            })
+            .expect("Failed to spawn render thread");
+
+        RenderThread {
+            queue: RenderQueue { commands },
+            frames,
+            thread: Some(thread),
+        }
+    }
+
+    /// Returns a handle for queueing drawing.
+    pub fn queue(&self) -> RenderQueue {
+        self.queue.clone()
+    }
+
+    /// Returns the number of LCD refreshes so far.
+    pub fn frames(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+
+    /// Executes the drawing queued so far, refreshes the LCD a last time if needed and returns
+    /// the screen. Drawing queued afterwards through remaining [`RenderQueue`]s is discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use uptechstar_rs::display::Screen;
+    ///
+    /// let renderer = Screen::new(None).spawn_renderer(Duration::from_millis(20));
+    /// let display = renderer.queue();
+    ///
+    /// // Returns even though `display` is still alive.
+    /// let _screen = renderer.stop();
+    /// drop(display);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If a closure queued with [`RenderQueue::run`] panicked on the render thread.
+    pub fn stop(mut self) -> Screen {
+        self.join().expect("Render thread already joined")
+    }
+
+    fn join(&mut self) -> Option<Screen> {
+        let thread = self.thread.take()?;
+        // The loop ends once it sees this message; later ones from other handles are dropped.
+        let _ = self.queue.commands.send(Command::Stop);
+        let (sender, _) = mpsc::channel();
+        self.queue.commands = sender;
+        Some(thread.join().expect("Render thread panicked"))
+    }
+}
+
+impl Drop for RenderThread {
+    fn drop(&mut self) {
+        if self.thread.is_some() && !thread::panicking() {
+            self.join();
+        }
+    }
+}
+
+fn render_loop(screen: &mut Screen, commands: &Receiver<Command>, period: Duration, frames: &AtomicU64) {
+    let mut dirty = false;
+    let mut next_refresh = Instant::now() + period;
+
+    loop {
+        let timeout = next_refresh.saturating_duration_since(Instant::now());
+        match commands.recv_timeout(timeout) {
+            Ok(Command::Op(op)) => {
+                screen.apply(&op);
+                dirty = true;
+            }
+            Ok(Command::Run(draw)) => {
+                draw(screen);
+                dirty = true;
+            }
+            Ok(Command::Stop) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        if now >= next_refresh {
+            if dirty {
+                screen.refresh();
+                frames.fetch_add(1, Ordering::Relaxed);
+                dirty = false;
+            }
+            next_refresh = (next_refresh + period).max(now);
+        }
+    }
+
+    if dirty {
+        screen.refresh();
+        frames.fetch_add(1, Ordering::Relaxed);
+    }
+    debug!("Render thread stopped after {} frames", frames.load(Ordering::Relaxed));
+}
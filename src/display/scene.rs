@@ -0,0 +1,570 @@
+//! Retained-mode drawing.
+//!
+//! Instead of redrawing the whole screen every frame, a [`Scene`] keeps a tree of nodes:
+//! text, shapes, groups and custom [`Widget`]s. Changing a node through [`Scene::update`]
+//! marks it dirty, and [`Scene::render`] only clears and redraws the areas that changed,
+//! together with the nodes overlapping them.
+//!
+//! Nodes are drawn in tree order: a parent before its children, siblings in the order they
+//! were added. Hiding a group hides everything in it.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::display::scene::{Node, Scene};
+//! use uptechstar_rs::display::{Color, FontSize, Screen, ScreenDirection};
+//!
+//! let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+//! let mut scene = Scene::new(Color::BLACK);
+//!
+//! scene.add(Node::text(0, 0, "Battery", FontSize::Font6x8, Color::WHITE));
+//! let value = scene.add(Node::text(0, 10, "--", FontSize::Font8x12, Color::GREEN));
+//!
+//! for millivolts in [7400, 7390, 7385] {
+//!     scene.update(value, |node| node.set_text(format!("{:.2} V", millivolts as f32 / 1000.0)));
+//!     // Only the value is cleared and redrawn.
+//!     scene.render(&mut screen);
+//! }
+//! ```
+
+use super::{FontSize, Screen};
+use std::any::Any;
+
+/// A rectangle in screen coordinates.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::Rect;
+///
+/// let a = Rect::new(0, 0, 10, 10);
+/// let b = Rect::new(5, 5, 10, 10);
+/// assert!(a.intersects(&b));
+/// assert_eq!(a.union(&b), Rect::new(0, 0, 15, 15));
+/// assert!(!a.intersects(&Rect::new(10, 0, 5, 5)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    /// X coordinate of the left edge.
+    pub x: i32,
+    /// Y coordinate of the top edge.
+    pub y: i32,
+    /// Width in pixels.
+    pub width: i32,
+    /// Height in pixels.
+    pub height: i32,
+}
+
+impl Rect {
+    /// Creates a rectangle from its top-left corner and size.
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Rect { x, y, width, height }
+    }
+
+    /// Creates the rectangle spanning the corners `(x1, y1)` and `(x2, y2)`, inclusive.
+    pub fn from_corners(x1: i32, y1: i32, x2: i32, y2: i32) -> Self {
+        Rect::new(x1.min(x2), y1.min(y2), (x2 - x1).abs() + 1, (y2 - y1).abs() + 1)
+    }
+
+    /// X coordinate of the rightmost column, inclusive.
+    pub fn right(&self) -> i32 {
+        self.x + self.width - 1
+    }
+
+    /// Y coordinate of the bottom row, inclusive.
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height - 1
+    }
+
+    /// Returns `true` if the rectangle covers no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    /// Returns `true` if `(x, y)` lies inside the rectangle.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+
+    /// Returns `true` if the rectangles share at least one pixel.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    /// Returns the area covered by both rectangles, empty if they do not overlap.
+    pub fn intersection(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        Rect::new(x, y, (right - x).max(0), (bottom - y).max(0))
+    }
+
+    /// Returns the smallest rectangle covering both. Empty rectangles are ignored.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        Rect::new(x, y, right - x, bottom - y)
+    }
+
+    /// Clips the line from `(x1, y1)` to `(x2, y2)` to the inside of the rectangle
+    /// (Liang-Barsky), returning the rounded end points as `[x1, y1, x2, y2]`.
+    pub(crate) fn clip_line(&self, x1: f32, y1: f32, x2: f32, y2: f32) -> Option<[i32; 4]> {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let (mut t0, mut t1) = (0.0f32, 1.0f32);
+
+        for (p, q) in [
+            (-dx, x1 - self.x as f32),
+            (dx, self.right() as f32 - x1),
+            (-dy, y1 - self.y as f32),
+            (dy, self.bottom() as f32 - y1),
+        ] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+
+        (t0 <= t1).then(|| {
+            [
+                (x1 + t0 * dx).round() as i32,
+                (y1 + t0 * dy).round() as i32,
+                (x1 + t1 * dx).round() as i32,
+                (y1 + t1 * dy).round() as i32,
+            ]
+        })
+    }
+}
+
+/// A custom node of a [`Scene`].
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::scene::Widget;
+/// use uptechstar_rs::display::{Color, Rect, Screen};
+///
+/// struct Gauge {
+///     level: f32,
+/// }
+///
+/// impl Widget for Gauge {
+///     fn bounds(&self) -> Rect {
+///         Rect::new(0, 56, 128, 8)
+///     }
+///
+///     fn draw(&self, screen: &mut Screen) {
+///         let width = (self.level.clamp(0.0, 1.0) * 126.0) as i32;
+///         screen.draw_frame(0, 56, 127, 63, Color::WHITE);
+///         if width > 0 {
+///             screen.fill_frame(1, 57, width, 62, Color::GREEN);
+///         }
+///     }
+/// }
+/// ```
+pub trait Widget: Any + Send {
+    /// Returns the area the widget draws into. Drawing outside of it leaves stale pixels.
+    fn bounds(&self) -> Rect;
+
+    /// Draws the widget. The area has already been cleared to the scene background.
+    fn draw(&self, screen: &mut Screen);
+}
+
+/// The content of a [`Scene`] node.
+pub enum Node {
+    /// A single line of text on the scene background.
+    Text {
+        x: i32,
+        y: i32,
+        text: String,
+        font: FontSize,
+        color: u32,
+    },
+    /// A rectangle, filled or outlined.
+    Rect { rect: Rect, color: u32, filled: bool },
+    /// A circle, filled or outlined.
+    Circle {
+        x: i32,
+        y: i32,
+        r: i32,
+        color: u32,
+        filled: bool,
+    },
+    /// A straight line.
+    Line {
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        color: u32,
+    },
+    /// Draws nothing itself; groups children so they can be hidden or removed together.
+    Group,
+    /// A custom widget.
+    Widget(Box<dyn Widget>),
+}
+
+impl Node {
+    /// Creates a text node.
+    pub fn text<S: Into<String>>(x: i32, y: i32, text: S, font: FontSize, color: u32) -> Self {
+        Node::Text {
+            x,
+            y,
+            text: text.into(),
+            font,
+            color,
+        }
+    }
+
+    /// Creates a filled rectangle node.
+    pub fn filled_rect(rect: Rect, color: u32) -> Self {
+        Node::Rect {
+            rect,
+            color,
+            filled: true,
+        }
+    }
+
+    /// Creates a widget node.
+    pub fn widget<W: Widget + 'static>(widget: W) -> Self {
+        Node::Widget(Box::new(widget))
+    }
+
+    /// Returns the widget of a widget node if it is a `W`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::scene::{Node, Scene, Widget};
+    /// use uptechstar_rs::display::{Color, Rect, Screen};
+    ///
+    /// struct Gauge {
+    ///     level: f32,
+    /// }
+    ///
+    /// impl Widget for Gauge {
+    ///     fn bounds(&self) -> Rect {
+    ///         Rect::new(0, 56, 128, 8)
+    ///     }
+    ///
+    ///     fn draw(&self, screen: &mut Screen) {
+    ///         screen.fill_frame(0, 56, (self.level * 127.0) as i32, 63, Color::GREEN);
+    ///     }
+    /// }
+    ///
+    /// let mut scene = Scene::new(Color::BLACK);
+    /// let gauge = scene.add(Node::widget(Gauge { level: 0.0 }));
+    /// scene.update(gauge, |node| {
+    ///     if let Some(gauge) = node.widget_mut::<Gauge>() {
+    ///         gauge.level = 0.8;
+    ///     }
+    /// });
+    /// ```
+    pub fn widget_mut<W: Widget>(&mut self) -> Option<&mut W> {
+        match self {
+            Node::Widget(widget) => (&mut **widget as &mut dyn Any).downcast_mut::<W>(),
+            _ => None,
+        }
+    }
+
+    /// Replaces the text of a text node. Other nodes are left unchanged.
+    pub fn set_text<S: Into<String>>(&mut self, value: S) {
+        if let Node::Text { text, .. } = self {
+            *text = value.into();
+        }
+    }
+
+    /// Replaces the color of a node. Groups and widgets are left unchanged.
+    pub fn set_color(&mut self, value: u32) {
+        match self {
+            Node::Text { color, .. } | Node::Rect { color, .. } | Node::Circle { color, .. } | Node::Line { color, .. } => {
+                *color = value
+            }
+            Node::Group | Node::Widget(_) => {}
+        }
+    }
+
+    /// Returns the area the node draws into; empty for groups.
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Node::Text { x, y, text, font, .. } => {
+                let (width, height) = font.measure_text(text);
+                Rect::new(*x, *y, width, height)
+            }
+            Node::Rect { rect, .. } => *rect,
+            Node::Circle { x, y, r, .. } => Rect::new(x - r, y - r, 2 * r + 1, 2 * r + 1),
+            Node::Line { x1, y1, x2, y2, .. } => Rect::from_corners(*x1, *y1, *x2, *y2),
+            Node::Group => Rect::default(),
+            Node::Widget(widget) => widget.bounds(),
+        }
+    }
+
+    fn draw(&self, screen: &mut Screen, background: u32) {
+        match self {
+            Node::Text { x, y, text, font, color } => {
+                let previous_font = screen.font_size;
+                screen
+                    .set_font_size(*font)
+                    .set_fore_color(*color)
+                    .set_back_color(background)
+                    .put_string(*x, *y, text)
+                    .set_font_size(previous_font);
+            }
+            Node::Rect { rect, color, filled } => {
+                if *filled {
+                    screen.fill_frame(rect.x, rect.y, rect.right(), rect.bottom(), *color);
+                } else {
+                    screen.draw_frame(rect.x, rect.y, rect.right(), rect.bottom(), *color);
+                }
+            }
+            Node::Circle { x, y, r, color, filled } => {
+                if *filled {
+                    screen.fill_circle(*x, *y, *r, *color);
+                } else {
+                    screen.draw_circle(*x, *y, *r, *color);
+                }
+            }
+            Node::Line { x1, y1, x2, y2, color } => {
+                screen.draw_line(*x1, *y1, *x2, *y2, *color);
+            }
+            Node::Group => {}
+            Node::Widget(widget) => widget.draw(screen),
+        }
+    }
+}
+
+/// Handle of a node in a [`Scene`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+struct Slot {
+    node: Node,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    visible: bool,
+    dirty: bool,
+    /// Where the node is currently drawn on the screen, if anywhere.
+    drawn: Option<Rect>,
+}
+
+/// A tree of [`Node`]s rendered with minimal redraws.
+pub struct Scene {
+    background: u32,
+    slots: Vec<Option<Slot>>,
+    roots: Vec<NodeId>,
+    /// Areas of removed nodes still to be cleared.
+    removed: Vec<Rect>,
+    full_redraw: bool,
+}
+
+impl Scene {
+    /// Creates an empty scene on a `background` colored screen.
+    pub fn new(background: u32) -> Self {
+        Scene {
+            background,
+            slots: Vec::new(),
+            roots: Vec::new(),
+            removed: Vec::new(),
+            full_redraw: true,
+        }
+    }
+
+    fn insert(&mut self, parent: Option<NodeId>, node: Node) -> NodeId {
+        let id = NodeId(self.slots.len());
+        self.slots.push(Some(Slot {
+            node,
+            parent,
+            children: Vec::new(),
+            visible: true,
+            dirty: true,
+            drawn: None,
+        }));
+        id
+    }
+
+    /// Adds a top-level node drawn above all existing ones.
+    pub fn add(&mut self, node: Node) -> NodeId {
+        let id = self.insert(None, node);
+        self.roots.push(id);
+        id
+    }
+
+    /// Adds a node as the last child of `parent`.
+    ///
+    /// # Panics
+    ///
+    /// If `parent` was removed.
+    pub fn add_child(&mut self, parent: NodeId, node: Node) -> NodeId {
+        assert!(self.slot(parent).is_some(), "parent node {:?} was removed", parent);
+        let id = self.insert(Some(parent), node);
+        if let Some(slot) = self.slot_mut(parent) {
+            slot.children.push(id);
+        }
+        id
+    }
+
+    fn slot(&self, id: NodeId) -> Option<&Slot> {
+        self.slots.get(id.0).and_then(Option::as_ref)
+    }
+
+    fn slot_mut(&mut self, id: NodeId) -> Option<&mut Slot> {
+        self.slots.get_mut(id.0).and_then(Option::as_mut)
+    }
+
+    /// Returns a node, or `None` if it was removed.
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.slot(id).map(|slot| &slot.node)
+    }
+
+    /// Changes a node and marks it dirty. Does nothing if the node was removed.
+    pub fn update<F: FnOnce(&mut Node)>(&mut self, id: NodeId, change: F) {
+        if let Some(slot) = self.slot_mut(id) {
+            change(&mut slot.node);
+            slot.dirty = true;
+        }
+    }
+
+    /// Marks a node dirty, for example a widget whose state changed outside the scene.
+    pub fn mark_dirty(&mut self, id: NodeId) {
+        if let Some(slot) = self.slot_mut(id) {
+            slot.dirty = true;
+        }
+    }
+
+    /// Shows or hides a node and its children.
+    pub fn set_visible(&mut self, id: NodeId, visible: bool) {
+        if let Some(slot) = self.slot_mut(id)
+            && slot.visible != visible
+        {
+            slot.visible = visible;
+            slot.dirty = true;
+        }
+    }
+
+    /// Removes a node and its children. Their area is cleared on the next render.
+    pub fn remove(&mut self, id: NodeId) {
+        let Some(slot) = self.slots.get_mut(id.0).and_then(Option::take) else {
+            return;
+        };
+
+        match slot.parent {
+            Some(parent) => {
+                if let Some(parent) = self.slot_mut(parent) {
+                    parent.children.retain(|&child| child != id);
+                }
+            }
+            None => self.roots.retain(|&root| root != id),
+        }
+
+        self.removed.extend(slot.drawn);
+        for child in slot.children {
+            self.remove(child);
+        }
+    }
+
+    /// Makes the next [`render`](Self::render) clear the screen and draw every node, for
+    /// example after something else drew on the screen.
+    pub fn invalidate(&mut self) {
+        self.full_redraw = true;
+    }
+
+    /// Returns the nodes in drawing order, with whether they and all their ancestors are
+    /// visible and whether they or an ancestor are dirty.
+    fn traverse(&self) -> Vec<(NodeId, bool, bool)> {
+        let mut order = Vec::with_capacity(self.slots.len());
+        let mut stack: Vec<(NodeId, bool, bool)> = self.roots.iter().rev().map(|&id| (id, true, false)).collect();
+
+        while let Some((id, parent_visible, parent_dirty)) = stack.pop() {
+            let Some(slot) = self.slot(id) else { continue };
+            let visible = parent_visible && slot.visible;
+            let dirty = parent_dirty || slot.dirty;
+            order.push((id, visible, dirty));
+            stack.extend(slot.children.iter().rev().map(|&child| (child, visible, dirty)));
+        }
+
+        order
+    }
+
+    /// Clears and redraws the changed parts of the scene and refreshes the screen.
+    ///
+    /// The first render, and the first after [`invalidate`](Self::invalidate), clears the
+    /// whole screen. Text is drawn with the scene background as its back color; the font of
+    /// `screen` is restored afterwards, its colors are not.
+    ///
+    /// Returns:
+    ///   The number of nodes drawn.
+    pub fn render(&mut self, screen: &mut Screen) -> usize {
+        let order = self.traverse();
+        let mut redraw = vec![false; order.len()];
+        let mut damage = std::mem::take(&mut self.removed);
+
+        if self.full_redraw {
+            screen.fill_screen(self.background);
+            damage.clear();
+            redraw.iter_mut().zip(&order).for_each(|(redraw, &(_, visible, _))| *redraw = visible);
+        } else {
+            for &(id, visible, dirty) in &order {
+                let Some(slot) = self.slot(id) else { continue };
+                if dirty {
+                    damage.extend(slot.drawn);
+                    if visible {
+                        damage.push(slot.node.bounds());
+                    }
+                }
+            }
+            damage.retain(|rect| !rect.is_empty());
+
+            // Redrawing a node can cover nodes above it, which then need redrawing too.
+            let mut grown = true;
+            while grown {
+                grown = false;
+                for (index, &(id, visible, _)) in order.iter().enumerate() {
+                    let Some(slot) = self.slot(id) else { continue };
+                    let bounds = slot.node.bounds();
+                    if !visible || redraw[index] || !damage.iter().any(|rect| rect.intersects(&bounds)) {
+                        continue;
+                    }
+                    redraw[index] = true;
+                    damage.push(bounds);
+                    grown = true;
+                }
+            }
+
+            for rect in &damage {
+                screen.fill_frame(rect.x, rect.y, rect.right(), rect.bottom(), self.background);
+            }
+        }
+
+        let background = self.background;
+        let mut drawn = 0;
+        for (index, &(id, visible, _)) in order.iter().enumerate() {
+            let Some(slot) = self.slot_mut(id) else { continue };
+            if redraw[index] {
+                slot.node.draw(screen, background);
+                slot.drawn = Some(slot.node.bounds()).filter(|rect| !rect.is_empty());
+                drawn += 1;
+            } else if !visible {
+                slot.drawn = None;
+            }
+            slot.dirty = false;
+        }
+
+        self.full_redraw = false;
+        screen.refresh();
+        drawn
+    }
+}
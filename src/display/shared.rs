@@ -0,0 +1,142 @@
+use super::{DrawOp, Screen};
+use crate::log_level::display::debug;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A drawing command queued on a [`SharedScreen`].
+type Command = Box<dyn FnOnce(&mut Screen) + Send>;
+
+/// A [`Screen`] shared between threads.
+///
+/// uGUI keeps the colors, the font and the frame being drawn as global state, so two threads
+/// drawing at the same time garble each other's output. `SharedScreen` serializes all access
+/// to one screen:
+///
+/// - [`draw`](Self::draw) draws right away, waiting for other threads to finish first.
+/// - [`queue`](Self::queue) only records the drawing and returns at once. Queued commands run
+///   in order on the next [`refresh`](Self::refresh), followed by a single LCD refresh for all
+///   of them.
+///
+/// Every closure runs between [`save_state`](Screen::save_state) and
+/// [`restore_state`](Screen::restore_state), so the colors and font one thread sets never leak
+/// into another thread's drawing. `SharedScreen` is a cheap handle; clones share the screen.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use std::time::Duration;
+/// use uptechstar_rs::display::{Color, Screen, ScreenDirection};
+///
+/// let screen = Screen::new(Some(ScreenDirection::Horizontal)).into_shared();
+///
+/// let logger = screen.clone();
+/// thread::spawn(move || {
+///     for line in 0.. {
+///         logger.queue(move |screen| {
+///             screen.set_fore_color(Color::GRAY).put_string(0, 48, &format!("log #{}", line));
+///         });
+///         thread::sleep(Duration::from_millis(300));
+///     }
+/// });
+///
+/// loop {
+///     screen.queue(|screen| {
+///         screen.set_fore_color(Color::WHITE).put_string(0, 0, "UI");
+///     });
+///     // Runs the UI and logger commands, then refreshes once.
+///     screen.refresh();
+///     thread::sleep(Duration::from_millis(50));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SharedScreen {
+    screen: Arc<Mutex<Screen>>,
+    /// Kept apart from the screen so queueing never waits for drawing.
+    commands: Arc<Mutex<Vec<Command>>>,
+}
+
+impl Screen {
+    /// Turns the screen into a [`SharedScreen`] for drawing from several threads.
+    pub fn into_shared(self) -> SharedScreen {
+        SharedScreen {
+            screen: Arc::new(Mutex::new(self)),
+            commands: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl SharedScreen {
+    fn lock_screen(&self) -> MutexGuard<'_, Screen> {
+        self.screen.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_commands(&self) -> MutexGuard<'_, Vec<Command>> {
+        self.commands.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Runs `draw` on the screen as soon as no other thread is drawing, and returns its
+    /// result. The LCD is not refreshed.
+    pub fn draw<F, R>(&self, draw: F) -> R
+    where
+        F: FnOnce(&mut Screen) -> R,
+    {
+        let mut screen = self.lock_screen();
+        screen.save_state();
+        let result = draw(&mut screen);
+        screen.restore_state();
+        result
+    }
+
+    /// Executes `ops` with [`Screen::draw_batch`] as soon as no other thread is drawing,
+    /// locking the screen once for the whole batch. The LCD is not refreshed.
+    pub fn draw_batch(&self, ops: &[DrawOp]) {
+        self.draw(|screen| {
+            screen.draw_batch(ops);
+        });
+    }
+
+    /// Queues `draw` to run on the next [`refresh`](Self::refresh).
+    pub fn queue<F>(&self, draw: F)
+    where
+        F: FnOnce(&mut Screen) + Send + 'static,
+    {
+        self.lock_commands().push(Box::new(draw));
+    }
+
+    /// Returns the number of queued commands.
+    pub fn pending(&self) -> usize {
+        self.lock_commands().len()
+    }
+
+    /// Runs the queued commands in the order they were queued, then refreshes the LCD once.
+    ///
+    /// Returns:
+    ///   The number of commands that ran.
+    pub fn refresh(&self) -> usize {
+        let commands = std::mem::take(&mut *self.lock_commands());
+        let count = commands.len();
+
+        let mut screen = self.lock_screen();
+        for command in commands {
+            screen.save_state();
+            command(&mut screen);
+            screen.restore_state();
+        }
+        screen.refresh();
+
+        if count > 0 {
+            debug!("Shared screen ran {} queued commands", count);
+        }
+        count
+    }
+
+    /// Returns the screen if this is the last handle, discarding queued commands, or the handle
+    /// otherwise.
+    pub fn try_into_inner(self) -> Result<Screen, SharedScreen> {
+        let SharedScreen { screen, commands } = self;
+        match Arc::try_unwrap(screen) {
+            Ok(screen) => Ok(screen.into_inner().unwrap_or_else(|e| e.into_inner())),
+            Err(screen) => Err(SharedScreen { screen, commands }),
+        }
+    }
+}
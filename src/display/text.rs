@@ -0,0 +1,145 @@
+use super::{FontSize, Screen};
+use std::borrow::Cow;
+
+/// Marks the end of a truncated string. uGUI fonts have no `…` glyph, so three dots are used.
+const ELLIPSIS: &str = "...";
+
+/// Shortens `text` to at most `max_chars` characters, ending it with `...` if anything was cut.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::ellipsize;
+///
+/// assert_eq!(ellipsize("Battery", 10), "Battery");
+/// assert_eq!(ellipsize("Temperature", 8), "Tempe...");
+/// assert_eq!(ellipsize("Temperature", 2), "Te");
+/// ```
+pub fn ellipsize(text: &str, max_chars: usize) -> Cow<'_, str> {
+    if text.chars().count() <= max_chars {
+        return Cow::Borrowed(text);
+    }
+
+    let ellipsis = ELLIPSIS.len();
+    if max_chars <= ellipsis {
+        return text.chars().take(max_chars).collect::<String>().into();
+    }
+    let mut shortened: String = text.chars().take(max_chars - ellipsis).collect();
+    shortened.push_str(ELLIPSIS);
+    shortened.into()
+}
+
+impl Screen {
+    /// Returns the width and height `text` takes up in the current font, see
+    /// [`FontSize::measure_text`](super::FontSize::measure_text).
+    ///
+    /// The size is in the units drawing coordinates use, so with a
+    /// [layout](Self::set_layout) it is converted from the LCD pixels the glyphs cover to
+    /// layout units, rounded up.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::{FontSize, Screen, ScreenDirection};
+    ///
+    /// let mut screen = Screen::new(None);
+    /// screen.set_font_size(FontSize::Font8x12);
+    /// assert_eq!(screen.measure_text("READY"), (40, 12));
+    ///
+    /// // Laid out at half the width and twice the height of the horizontal LCD.
+    /// screen.set_layout(Some(ScreenDirection::Vertical));
+    /// assert_eq!(screen.measure_text("READY"), (20, 24));
+    /// ```
+    ///
+    /// Centering a label:
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::display::{Screen, ScreenDirection};
+    ///
+    /// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+    /// let (width, height) = screen.measure_text("READY");
+    /// let (screen_width, screen_height) = screen.size();
+    /// screen
+    ///     .put_string((screen_width - width) / 2, (screen_height - height) / 2, "READY")
+    ///     .refresh();
+    /// ```
+    pub fn measure_text(&self, text: &str) -> (i32, i32) {
+        let (width, height) = self.font_size.measure_text(text);
+        self.to_layout_size(width, height)
+    }
+
+    /// Returns how many characters of the current font fit on a line of the LCD. Glyphs keep
+    /// their size under a [layout](Self::set_layout), so this does not depend on it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::display::{FontSize, Screen, ScreenDirection};
+    ///
+    /// let mut screen = Screen::new(None);
+    /// screen.set_font_size(FontSize::Font8x12);
+    /// assert_eq!(screen.chars_per_line(), 16);
+    ///
+    /// screen.set_layout(Some(ScreenDirection::Vertical));
+    /// assert_eq!(screen.chars_per_line(), 16);
+    /// ```
+    pub fn chars_per_line(&self) -> usize {
+        (self.panel_size().0 / self.font_size.column_width()).max(0) as usize
+    }
+
+    /// Places a single line of text at `(x, y)` without letting it grow wider than
+    /// `max_width`, in the units of drawing coordinates like `x`.
+    ///
+    /// Text that is too wide for the current font is drawn in the widest smaller font it fits
+    /// into, among those no taller than the current one; the current font is kept for
+    /// subsequent drawing. If no font is narrow enough, the text is [ellipsized](ellipsize) in
+    /// the current font instead.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::display::{FontSize, Screen, ScreenDirection};
+    ///
+    /// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+    /// screen.set_font_size(FontSize::Font8x12);
+    ///
+    /// // Two 64-pixel columns of the dashboard.
+    /// screen
+    ///     .put_string_fit(0, 0, 64, "Left distance")
+    ///     .put_string_fit(64, 0, 64, "Right")
+    ///     .refresh();
+    /// ```
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn put_string_fit(&mut self, x: i32, y: i32, max_width: i32, text: &str) -> &mut Self {
+        let font = self.font_size;
+        // Glyphs are measured in LCD pixels, so compare against the width the column covers there.
+        let [left, _, right, _] = self.to_panel_rect(x, y, x + max_width - 1, y);
+        let max_width = right - left + 1;
+        if font.measure_text(text).0 <= max_width {
+            return self.put_string(x, y, text);
+        }
+
+        let smaller = FontSize::ALL
+            .into_iter()
+            .filter(|candidate| {
+                candidate.column_width() < font.column_width()
+                    && candidate.row_height() <= font.row_height()
+                    && candidate.measure_text(text).0 <= max_width
+            })
+            .max_by_key(|candidate| (candidate.column_width(), candidate.row_height()));
+
+        match smaller {
+            Some(smaller) => self
+                .save_state()
+                .set_font_size(smaller)
+                .put_string(x, y, text)
+                .restore_state(),
+            None => {
+                let max_chars = (max_width / font.column_width()).max(0) as usize;
+                self.put_string(x, y, &ellipsize(text, max_chars))
+            }
+        }
+    }
+}
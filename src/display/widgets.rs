@@ -0,0 +1,22 @@
+//! Reusable UI components.
+//!
+//! Widgets draw into a box of the screen and implement [`Widget`](super::scene::Widget), so
+//! they can be drawn directly or placed in a [`Scene`](super::scene::Scene).
+
+mod attitude;
+mod channel_bars;
+mod chart;
+mod histogram;
+mod input;
+mod scrolling_text;
+mod timer;
+mod value_display;
+
+pub use attitude::{ArtificialHorizon, CompassRose};
+pub use channel_bars::ChannelBars;
+pub use chart::ChartAxes;
+pub use histogram::Histogram;
+pub use input::{DEFAULT_CHARSET, InputResponse, NumberSpinner, TextInput};
+pub use scrolling_text::ScrollingText;
+pub use timer::{Countdown, Stopwatch, TimerEvent};
+pub use value_display::ValueDisplay;
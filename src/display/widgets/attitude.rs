@@ -0,0 +1,391 @@
+use crate::display::scene::Widget;
+use crate::display::{Color, FontSize, Rect, Screen};
+
+/// A line segment as `[x1, y1, x2, y2]`.
+type Segment = [i32; 4];
+
+/// A letter and the top-left corner it is drawn at.
+type Letter = (char, i32, i32);
+
+fn draw_segments(screen: &mut Screen, segments: &[Segment], color: u32) {
+    for &[x1, y1, x2, y2] in segments {
+        screen.draw_line(x1, y1, x2, y2, color);
+    }
+}
+
+/// Pitch of the ladder rungs drawn above and below the horizon, in degrees.
+const LADDER: [f32; 4] = [-20.0, -10.0, 10.0, 20.0];
+
+/// An artificial horizon showing pitch and roll, the attitude indicator of an aircraft
+/// cockpit.
+///
+/// The horizon line and a pitch ladder every 10° move behind a fixed aircraft symbol in the
+/// middle of the box: pitching up moves the horizon down, rolling tilts it. Attitudes are in
+/// degrees, as reported by [`mpu6500_get_attitude`](crate::mpu::mpu6500_get_attitude) with
+/// the default units.
+///
+/// [`render`](Self::render) only erases and redraws the lines that moved, which is fast enough
+/// to follow the MPU at the refresh rate of the screen.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::widgets::ArtificialHorizon;
+///
+/// let mut horizon = ArtificialHorizon::new(0, 0, 61);
+///
+/// // Level: a horizontal line through the middle of the box.
+/// assert_eq!(horizon.horizon(), Some([0, 30, 60, 30]));
+///
+/// // Nose up by 10°: the horizon moves down by 10° × 1 pixel.
+/// horizon.set_attitude([10.0, 0.0, 0.0]);
+/// assert_eq!(horizon.horizon(), Some([0, 40, 60, 40]));
+/// ```
+///
+/// Following the MPU:
+///
+/// ```rust,no_run
+/// use uptechstar_rs::display::widgets::ArtificialHorizon;
+/// use uptechstar_rs::display::{Screen, ScreenDirection};
+/// use uptechstar_rs::mpu;
+///
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+/// let mut horizon = ArtificialHorizon::new(32, 0, 64);
+/// mpu::mpu6500_open();
+///
+/// loop {
+///     let mut attitude = [0.0f32; 3];
+///     if mpu::mpu6500_get_attitude(&mut attitude) == 0 {
+///         horizon.set_attitude(attitude);
+///     }
+///     if horizon.render(&mut screen) {
+///         screen.refresh();
+///     }
+/// }
+/// ```
+pub struct ArtificialHorizon {
+    bounds: Rect,
+    pitch: f32,
+    roll: f32,
+    pixels_per_degree: f32,
+    color: u32,
+    marker_color: u32,
+    background: u32,
+    /// Lines shown by the last render, `None` if nothing was drawn yet.
+    drawn: Option<Vec<Segment>>,
+}
+
+impl ArtificialHorizon {
+    /// Creates a square indicator `size` pixels wide at `(x, y)`, showing ±30° of pitch.
+    pub fn new(x: i32, y: i32, size: i32) -> Self {
+        ArtificialHorizon {
+            bounds: Rect::new(x, y, size, size),
+            pitch: 0.0,
+            roll: 0.0,
+            pixels_per_degree: size as f32 / 60.0,
+            color: Color::WHITE,
+            marker_color: Color::YELLOW,
+            background: Color::BLACK,
+            drawn: None,
+        }
+    }
+
+    /// Sets how far the horizon moves per degree of pitch. The default shows ±30° in the box.
+    pub fn with_pitch_scale(mut self, pixels_per_degree: f32) -> Self {
+        self.pixels_per_degree = pixels_per_degree;
+        self
+    }
+
+    /// Sets the colors of the horizon and ladder, of the aircraft symbol and of the background.
+    /// The default is white and yellow on black.
+    pub fn with_colors(mut self, color: u32, marker_color: u32, background: u32) -> Self {
+        self.color = color;
+        self.marker_color = marker_color;
+        self.background = background;
+        self
+    }
+
+    /// Shows the pitch and roll of a `[pitch, roll, yaw]` attitude in degrees.
+    pub fn set_attitude(&mut self, attitude: [f32; 3]) {
+        self.pitch = attitude[0];
+        self.roll = attitude[1];
+    }
+
+    /// Center of the box.
+    fn center(&self) -> (f32, f32) {
+        (
+            self.bounds.x as f32 + (self.bounds.width - 1) as f32 / 2.0,
+            self.bounds.y as f32 + (self.bounds.height - 1) as f32 / 2.0,
+        )
+    }
+
+    /// The line for `pitch` degrees, `half_length` pixels to each side, clipped to the box.
+    fn pitch_line(&self, pitch: f32, half_length: f32) -> Option<Segment> {
+        let (cx, cy) = self.center();
+        let (sin, cos) = self.roll.to_radians().sin_cos();
+        // Rolling right raises the right side of the horizon; nose up moves it down.
+        let offset = (self.pitch - pitch) * self.pixels_per_degree;
+        let (mx, my) = (cx + sin * offset, cy + cos * offset);
+
+        self.bounds.clip_line(
+            mx - cos * half_length,
+            my + sin * half_length,
+            mx + cos * half_length,
+            my - sin * half_length,
+        )
+    }
+
+    /// Returns the horizon line as `[x1, y1, x2, y2]`, or `None` if it is outside the box.
+    pub fn horizon(&self) -> Option<Segment> {
+        let diagonal = (self.bounds.width + self.bounds.height) as f32;
+        self.pitch_line(0.0, diagonal)
+    }
+
+    /// The horizon and ladder lines for the current attitude.
+    fn segments(&self) -> Vec<Segment> {
+        let rung = self.bounds.width as f32 / 6.0;
+        self.horizon()
+            .into_iter()
+            .chain(LADDER.iter().filter_map(|&pitch| self.pitch_line(pitch, rung)))
+            .collect()
+    }
+
+    /// Draws the fixed aircraft symbol and the border.
+    fn draw_fixed(&self, screen: &mut Screen) {
+        let (cx, cy) = self.center();
+        let (cx, cy) = (cx.round() as i32, cy.round() as i32);
+        let wing = self.bounds.width / 5;
+        let gap = self.bounds.width / 12;
+
+        screen
+            .draw_line(cx - gap - wing, cy, cx - gap, cy, self.marker_color)
+            .draw_line(cx + gap, cy, cx + gap + wing, cy, self.marker_color)
+            .fill_circle(cx, cy, 1, self.marker_color)
+            .draw_frame(
+                self.bounds.x,
+                self.bounds.y,
+                self.bounds.right(),
+                self.bounds.bottom(),
+                self.color,
+            );
+    }
+
+    /// Redraws the lines that moved since the last render, or the whole indicator the first
+    /// time. The screen is not refreshed.
+    ///
+    /// Returns:
+    ///   `true` if anything was drawn.
+    pub fn render(&mut self, screen: &mut Screen) -> bool {
+        let segments = self.segments();
+        match &self.drawn {
+            Some(drawn) if *drawn == segments => return false,
+            Some(drawn) => {
+                draw_segments(screen, drawn, self.background);
+                draw_segments(screen, &segments, self.color);
+                self.draw_fixed(screen);
+            }
+            None => self.draw(screen),
+        }
+
+        self.drawn = Some(segments);
+        true
+    }
+}
+
+impl Widget for ArtificialHorizon {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let bounds = self.bounds;
+        screen.fill_frame(bounds.x, bounds.y, bounds.right(), bounds.bottom(), self.background);
+        draw_segments(screen, &self.segments(), self.color);
+        self.draw_fixed(screen);
+    }
+}
+
+/// Cardinal directions of a [`CompassRose`] and their bearings.
+const CARDINALS: [(char, f32); 4] = [('N', 0.0), ('E', 90.0), ('S', 180.0), ('W', 270.0)];
+
+/// A heading indicator: a rose with the cardinal directions and a tick every 30° that turns so
+/// the current heading is at the top, with the heading in degrees in the middle.
+///
+/// The heading is the yaw of the attitude in degrees, clockwise from the direction the MPU
+/// faced when the DMP started or [`zero_yaw`](crate::mpu::zero_yaw) was called. Like
+/// [`ArtificialHorizon`], [`render`](Self::render) only redraws what moved.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::widgets::CompassRose;
+///
+/// let mut compass = CompassRose::new(32, 32, 30);
+///
+/// compass.set_attitude([0.0, 0.0, -90.0]);
+/// assert_eq!(compass.heading(), 270);
+/// assert_eq!(compass.label(), "270");
+/// ```
+pub struct CompassRose {
+    cx: i32,
+    cy: i32,
+    radius: i32,
+    heading: f32,
+    color: u32,
+    north_color: u32,
+    background: u32,
+    /// Ticks, letter positions and label of the last render.
+    drawn: Option<(Vec<Segment>, Vec<Letter>, String)>,
+}
+
+impl CompassRose {
+    /// Creates a rose of `radius` pixels centered on `(cx, cy)`.
+    pub fn new(cx: i32, cy: i32, radius: i32) -> Self {
+        CompassRose {
+            cx,
+            cy,
+            radius,
+            heading: 0.0,
+            color: Color::WHITE,
+            north_color: Color::RED,
+            background: Color::BLACK,
+            drawn: None,
+        }
+    }
+
+    /// Sets the colors of the rose, of the `N` and of the background. The default is white and
+    /// red on black.
+    pub fn with_colors(mut self, color: u32, north_color: u32, background: u32) -> Self {
+        self.color = color;
+        self.north_color = north_color;
+        self.background = background;
+        self
+    }
+
+    /// Shows the yaw of a `[pitch, roll, yaw]` attitude in degrees as the heading.
+    pub fn set_attitude(&mut self, attitude: [f32; 3]) {
+        self.heading = attitude[2].rem_euclid(360.0);
+    }
+
+    /// Returns the heading in whole degrees, `0..360`.
+    pub fn heading(&self) -> u32 {
+        self.heading.round() as u32 % 360
+    }
+
+    /// Returns the text in the middle of the rose.
+    pub fn label(&self) -> String {
+        format!("{:03}", self.heading())
+    }
+
+    /// The point at `bearing` degrees and `distance` pixels from the center, with the heading
+    /// at the top.
+    fn point(&self, bearing: f32, distance: f32) -> (f32, f32) {
+        let (sin, cos) = (bearing - self.heading).to_radians().sin_cos();
+        (self.cx as f32 + sin * distance, self.cy as f32 - cos * distance)
+    }
+
+    fn ticks(&self) -> Vec<Segment> {
+        (0..12)
+            .map(|tick| {
+                let bearing = tick as f32 * 30.0;
+                let (x1, y1) = self.point(bearing, (self.radius - 4) as f32);
+                let (x2, y2) = self.point(bearing, (self.radius - 1) as f32);
+                [x1.round() as i32, y1.round() as i32, x2.round() as i32, y2.round() as i32]
+            })
+            .collect()
+    }
+
+    /// Top-left corners of the cardinal letters.
+    fn letters(&self) -> Vec<Letter> {
+        let font = FontSize::Font6x8;
+        let distance = (self.radius - 5 - font.row_height() / 2) as f32;
+        CARDINALS
+            .iter()
+            .map(|&(letter, bearing)| {
+                let (x, y) = self.point(bearing, distance);
+                (
+                    letter,
+                    x.round() as i32 - font.column_width() / 2,
+                    y.round() as i32 - font.row_height() / 2,
+                )
+            })
+            .collect()
+    }
+
+    fn draw_letters(&self, screen: &mut Screen, letters: &[Letter], label: &str) {
+        let font = FontSize::Font6x8;
+        let previous_font = screen.font_size;
+        screen.set_font_size(font).set_back_color(self.background);
+
+        for &(letter, x, y) in letters {
+            let color = if letter == 'N' { self.north_color } else { self.color };
+            screen.set_fore_color(color).put_string(x, y, &letter.to_string());
+        }
+
+        let (width, _) = font.measure_text(label);
+        screen
+            .set_fore_color(self.color)
+            .put_string(self.cx - width / 2, self.cy - font.row_height() / 2, label)
+            .set_font_size(previous_font);
+    }
+
+    /// Draws the outline and the lubber line marking the heading.
+    fn draw_fixed(&self, screen: &mut Screen) {
+        let top = self.cy - self.radius;
+        screen
+            .draw_circle(self.cx, self.cy, self.radius, self.color)
+            .draw_line(self.cx, top - 3, self.cx, top + 2, self.north_color);
+    }
+
+    /// Redraws the parts of the rose that moved since the last render, or all of it the first
+    /// time. The screen is not refreshed.
+    ///
+    /// Returns:
+    ///   `true` if anything was drawn.
+    pub fn render(&mut self, screen: &mut Screen) -> bool {
+        let state = (self.ticks(), self.letters(), self.label());
+        match &self.drawn {
+            Some(drawn) if *drawn == state => return false,
+            Some((ticks, letters, _)) => {
+                let font = FontSize::Font6x8;
+                draw_segments(screen, ticks, self.background);
+                for &(_, x, y) in letters {
+                    screen.fill_frame(
+                        x,
+                        y,
+                        x + font.column_width() - 1,
+                        y + font.row_height() - 1,
+                        self.background,
+                    );
+                }
+                draw_segments(screen, &state.0, self.color);
+                self.draw_letters(screen, &state.1, &state.2);
+                self.draw_fixed(screen);
+            }
+            None => self.draw(screen),
+        }
+
+        self.drawn = Some(state);
+        true
+    }
+}
+
+impl Widget for CompassRose {
+    fn bounds(&self) -> Rect {
+        // Including the lubber line above the circle.
+        Rect::new(
+            self.cx - self.radius,
+            self.cy - self.radius - 3,
+            2 * self.radius + 1,
+            2 * self.radius + 4,
+        )
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let bounds = self.bounds();
+        screen.fill_frame(bounds.x, bounds.y, bounds.right(), bounds.bottom(), self.background);
+        draw_segments(screen, &self.ticks(), self.color);
+        self.draw_letters(screen, &self.letters(), &self.label());
+        self.draw_fixed(screen);
+    }
+}
@@ -0,0 +1,275 @@
+use super::ChartAxes;
+use crate::adc_io::AdcFrame;
+use crate::display::scene::Widget;
+use crate::display::{Color, FontSize, Rect, Screen};
+use crate::sampler::Reading;
+
+/// Number of ADC channels shown.
+const CHANNELS: usize = 10;
+
+/// Font of the channel numbers under the bars.
+const LABEL_FONT: FontSize = FontSize::Font6x8;
+
+/// The 10 ADC channels as vertical bars with their channel numbers underneath, for checking
+/// sensor wiring at a glance.
+///
+/// Each bar spans the range of its channel, `0..=4095` unless configured otherwise, and readings
+/// outside of it are clamped. [`render`](Self::render) only draws the part of each bar that
+/// grew or shrank since the last call.
+///
+/// [Axes](Self::with_axes) label the bars with the common range of the channels, or in percent
+/// if the ranges differ.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::AdcFrame;
+/// use uptechstar_rs::display::widgets::ChannelBars;
+///
+/// let mut bars = ChannelBars::new(0, 0, 128, 64).with_range(3, 1000, 2000);
+///
+/// bars.set_frame(&AdcFrame([4095, 0, 2048, 1500, 0, 0, 0, 0, 0, 0]));
+/// assert_eq!(bars.level(0), 1.0);
+/// assert_eq!(bars.level(3), 0.5);
+/// ```
+///
+/// With a labeled value axis and gridlines:
+///
+/// ```rust
+/// use uptechstar_rs::display::Rect;
+/// use uptechstar_rs::display::widgets::{ChannelBars, ChartAxes};
+///
+/// let bars = ChannelBars::new(0, 0, 128, 64).with_axes(ChartAxes::new().with_axes(0, 4).with_grid(1, 4));
+/// // "4095" and the axis line take the left 18 pixels, the channel numbers the bottom 9 rows.
+/// assert_eq!(bars.plot_area(), Rect::new(18, 0, 110, 54));
+/// ```
+///
+/// Fed from a [`Sampler`](crate::sampler::Sampler):
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io;
+/// use uptechstar_rs::display::widgets::ChannelBars;
+/// use uptechstar_rs::display::{Screen, ScreenDirection};
+/// use uptechstar_rs::sampler::Sampler;
+///
+/// adc_io::adc_open();
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+/// let mut bars = ChannelBars::new(0, 0, 128, 64);
+///
+/// let mut sampler = Sampler::new(20.0).with_adc(true);
+/// let readings = sampler.subscribe();
+/// sampler.start();
+///
+/// for reading in readings {
+///     if bars.update(&reading.value) && bars.render(&mut screen) {
+///         screen.refresh();
+///     }
+/// }
+/// ```
+pub struct ChannelBars {
+    bounds: Rect,
+    values: [i32; CHANNELS],
+    ranges: [(i32, i32); CHANNELS],
+    bar_color: u32,
+    label_color: u32,
+    background: u32,
+    axes: ChartAxes,
+    /// Bar heights in pixels shown by the last render, `None` if nothing was drawn yet.
+    drawn: Option<[i32; CHANNELS]>,
+}
+
+impl ChannelBars {
+    /// Creates the bars in a box of `width` by `height` pixels at `(x, y)`. The plot area needs
+    /// to be at least 60 pixels wide for the channel numbers.
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        ChannelBars {
+            bounds: Rect::new(x, y, width, height),
+            values: [0; CHANNELS],
+            ranges: [(0, 4095); CHANNELS],
+            bar_color: Color::GREEN,
+            label_color: Color::WHITE,
+            background: Color::BLACK,
+            axes: ChartAxes::new(),
+            drawn: None,
+        }
+    }
+
+    /// Sets the readings of `channel` shown as an empty and a full bar.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` is not below 10.
+    pub fn with_range(mut self, channel: usize, min: i32, max: i32) -> Self {
+        self.ranges[channel] = (min, max);
+        self
+    }
+
+    /// Sets the range of every channel, see [`with_range`](Self::with_range).
+    pub fn with_ranges(mut self, min: i32, max: i32) -> Self {
+        self.ranges = [(min, max); CHANNELS];
+        self
+    }
+
+    /// Sets the colors of the bars, the channel numbers and the background. The default is
+    /// green and white on black.
+    pub fn with_colors(mut self, bar_color: u32, label_color: u32, background: u32) -> Self {
+        self.bar_color = bar_color;
+        self.label_color = label_color;
+        self.background = background;
+        self
+    }
+
+    /// Sets the axes, gridlines and legend drawn around the bars. The default draws none.
+    /// Tick labels are only put on the value axis; the channel numbers label the other one.
+    pub fn with_axes(mut self, axes: ChartAxes) -> Self {
+        self.axes = axes;
+        self
+    }
+
+    /// Shows the readings of `frame`.
+    pub fn set_frame(&mut self, frame: &AdcFrame) {
+        self.values = frame.0;
+    }
+
+    /// Shows the readings of an ADC sampler reading; other readings are ignored.
+    ///
+    /// Returns:
+    ///   `true` if `reading` was an ADC frame.
+    pub fn update(&mut self, reading: &Reading) -> bool {
+        match reading {
+            Reading::Adc(frame) => {
+                self.set_frame(frame);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns how full the bar of `channel` is, from 0 to 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel` is not below 10.
+    pub fn level(&self, channel: usize) -> f32 {
+        let (min, max) = self.ranges[channel];
+        if max == min {
+            return 0.0;
+        }
+        ((self.values[channel] - min) as f32 / (max - min) as f32).clamp(0.0, 1.0)
+    }
+
+    /// Returns the area the bars are drawn in, above the channel numbers and inside the axes.
+    pub fn plot_area(&self) -> Rect {
+        self.axes.plot_area(self.chart_area(), None, self.value_range())
+    }
+
+    /// The box above the channel numbers, holding the bars and their axes.
+    fn chart_area(&self) -> Rect {
+        let mut area = self.bounds;
+        area.height -= LABEL_FONT.row_height() + 1;
+        area
+    }
+
+    /// The range the value axis is labeled with: the range of every channel if they share
+    /// one, otherwise percent.
+    fn value_range(&self) -> (f32, f32) {
+        let (min, max) = self.ranges[0];
+        if self.ranges.iter().all(|&range| range == (min, max)) {
+            (min as f32, max as f32)
+        } else {
+            (0.0, 100.0)
+        }
+    }
+
+    /// The area of the bar of `channel`: left and right column, and the bottom row.
+    fn column(&self, channel: usize) -> (i32, i32, i32) {
+        let plot = self.plot_area();
+        let pitch = plot.width / CHANNELS as i32;
+        let left = plot.x + channel as i32 * pitch;
+        // One pixel of space between neighboring bars.
+        let right = left + (pitch - 2).max(0);
+        (left, right, plot.bottom())
+    }
+
+    /// Bar heights in pixels for the current readings.
+    fn heights(&self) -> [i32; CHANNELS] {
+        let full = self.plot_area().height;
+        std::array::from_fn(|channel| (self.level(channel) * full as f32).round() as i32)
+    }
+
+    /// Draws the bar of `channel` between the heights `from` and `to` in `color`.
+    fn draw_bar(&self, screen: &mut Screen, channel: usize, from: i32, to: i32, color: u32) {
+        if from == to {
+            return;
+        }
+        let (left, right, bottom) = self.column(channel);
+        let (low, high) = (from.min(to), from.max(to));
+        screen.fill_frame(left, bottom - high + 1, right, bottom - low, color);
+    }
+
+    /// Redraws the bars that changed since the last render, or everything the first time.
+    /// The screen is not refreshed.
+    ///
+    /// Returns:
+    ///   `true` if anything was drawn.
+    pub fn render(&mut self, screen: &mut Screen) -> bool {
+        let heights = self.heights();
+        let Some(drawn) = self.drawn else {
+            self.draw(screen);
+            self.drawn = Some(heights);
+            return true;
+        };
+        if drawn == heights {
+            return false;
+        }
+
+        let plot = self.plot_area();
+        for channel in 0..CHANNELS {
+            let (old, new) = (drawn[channel], heights[channel]);
+            if new > old {
+                self.draw_bar(screen, channel, old, new, self.bar_color);
+            } else if new < old {
+                self.draw_bar(screen, channel, old, new, self.background);
+                // Put back the gridlines the shrinking bar uncovered.
+                let (left, right, bottom) = self.column(channel);
+                screen.push_clip(Rect::from_corners(left, bottom - old + 1, right, bottom - new));
+                self.axes.draw_grid(screen, plot);
+                screen.pop_clip();
+            }
+        }
+
+        self.drawn = Some(heights);
+        true
+    }
+}
+
+impl Widget for ChannelBars {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let bounds = self.bounds;
+        let previous_font = screen.font_size;
+        screen
+            .fill_frame(bounds.x, bounds.y, bounds.right(), bounds.bottom(), self.background)
+            .set_back_color(self.background);
+        self.axes.draw(screen, self.chart_area(), None, self.value_range());
+
+        screen
+            .set_font_size(LABEL_FONT)
+            .set_fore_color(self.label_color)
+            .set_back_color(self.background);
+
+        let label_y = bounds.bottom() - LABEL_FONT.row_height() + 1;
+        for (channel, height) in self.heights().into_iter().enumerate() {
+            self.draw_bar(screen, channel, 0, height, self.bar_color);
+
+            let (left, right, _) = self.column(channel);
+            let label_x = (left + right + 1 - LABEL_FONT.column_width()) / 2;
+            screen.put_string(label_x, label_y, &channel.to_string());
+        }
+
+        screen.set_font_size(previous_font);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::display::scene::Rect;
+use crate::display::{Color, FontSize, Screen};
+
+/// Font of tick labels and legend entries.
+const LABEL_FONT: FontSize = FontSize::Font4x6;
+
+/// Side of the color swatch in front of each legend entry.
+const SWATCH: i32 = 4;
+
+/// Axes, tick labels, gridlines and a legend around the plot area of a chart, so that a
+/// screenshot of the plot can be read on its own.
+///
+/// Everything is off by default and each chart takes its own `ChartAxes`. The chart asks
+/// [`plot_area`](Self::plot_area) where its data goes, draws the decorations and then its data
+/// on top. Labels use the smallest font, [`FontSize::Font4x6`].
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::widgets::ChartAxes;
+/// use uptechstar_rs::display::{Color, Rect};
+///
+/// let axes = ChartAxes::new()
+///     .with_axes(0, 2)
+///     .with_grid(4, 2)
+///     .with_legend("left", Color::GREEN);
+///
+/// // The legend takes the top row, the labels "0" to "4095" the left 16 pixels plus a gap
+/// // and the axis line.
+/// let area = axes.plot_area(Rect::new(0, 0, 128, 64), None, (0.0, 4095.0));
+/// assert_eq!(area, Rect::new(18, 7, 110, 56));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChartAxes {
+    axes: bool,
+    x_ticks: usize,
+    y_ticks: usize,
+    grid: Option<(usize, usize)>,
+    legend: Vec<(String, u32)>,
+    axis_color: u32,
+    grid_color: u32,
+}
+
+impl Default for ChartAxes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChartAxes {
+    /// Creates decorations that draw nothing and leave the whole box to the plot.
+    pub fn new() -> Self {
+        ChartAxes {
+            axes: false,
+            x_ticks: 0,
+            y_ticks: 0,
+            grid: None,
+            legend: Vec::new(),
+            axis_color: Color::WHITE,
+            grid_color: Color::GRAY,
+        }
+    }
+
+    /// Draws axis lines along the left and bottom of the plot, labeled at `x_ticks` and
+    /// `y_ticks` evenly spaced intervals. Zero leaves an axis unlabeled; charts without a
+    /// numeric X range never label it.
+    pub fn with_axes(mut self, x_ticks: usize, y_ticks: usize) -> Self {
+        self.axes = true;
+        self.x_ticks = x_ticks;
+        self.y_ticks = y_ticks;
+        self
+    }
+
+    /// Divides the plot into `columns` by `rows` cells with dotted lines drawn by
+    /// [`Screen::draw_mesh`].
+    pub fn with_grid(mut self, columns: usize, rows: usize) -> Self {
+        self.grid = Some((columns, rows));
+        self
+    }
+
+    /// Adds an entry to the legend row above the plot: a swatch of `color` followed by `name`.
+    /// Entries that do not fit into the width of the chart are left out.
+    pub fn with_legend<S: Into<String>>(mut self, name: S, color: u32) -> Self {
+        self.legend.push((name.into(), color));
+        self
+    }
+
+    /// Sets the colors of axis lines and labels, and of the grid. The default is white and gray.
+    pub fn with_colors(mut self, axis_color: u32, grid_color: u32) -> Self {
+        self.axis_color = axis_color;
+        self.grid_color = grid_color;
+        self
+    }
+
+    /// Returns the part of `bounds` left for the data once the legend, the labels and the axis
+    /// lines have taken their space.
+    ///
+    /// Args:
+    ///   bounds: The box of the chart.
+    ///   x_range: The values at the left and right edge of the plot, `None` if the X axis
+    ///     is not numeric.
+    ///   y_range: The values at the bottom and top edge of the plot.
+    pub fn plot_area(&self, bounds: Rect, x_range: Option<(f32, f32)>, y_range: (f32, f32)) -> Rect {
+        let mut area = bounds;
+        if !self.legend.is_empty() {
+            let height = LABEL_FONT.row_height() + 1;
+            area.y += height;
+            area.height -= height;
+        }
+        if self.axes {
+            let labels = self.y_labels(y_range);
+            let label_width = labels.iter().map(|label| LABEL_FONT.measure_text(label).0).max();
+            // The axis line, plus a blank column between it and the labels.
+            let left = label_width.map_or(1, |width| width + 2);
+            let bottom = match x_range {
+                Some(_) if self.x_ticks > 0 => LABEL_FONT.row_height() + 2,
+                _ => 1,
+            };
+            area.x += left;
+            area.width -= left;
+            area.height -= bottom;
+        }
+        area.width = area.width.max(0);
+        area.height = area.height.max(0);
+        area
+    }
+
+    /// Draws the legend, the axes with their labels and the grid for a plot in `bounds`.
+    /// The plot area itself is not cleared.
+    pub(super) fn draw(&self, screen: &mut Screen, bounds: Rect, x_range: Option<(f32, f32)>, y_range: (f32, f32)) {
+        let plot = self.plot_area(bounds, x_range, y_range);
+        let previous_font = screen.font_size;
+        screen.set_font_size(LABEL_FONT).set_fore_color(self.axis_color);
+
+        self.draw_legend(screen, bounds);
+        self.draw_grid(screen, plot);
+        if self.axes {
+            screen
+                .draw_line(plot.x - 1, plot.y, plot.x - 1, plot.bottom() + 1, self.axis_color)
+                .draw_line(plot.x - 1, plot.bottom() + 1, plot.right(), plot.bottom() + 1, self.axis_color);
+
+            let label_height = LABEL_FONT.row_height();
+            for (tick, label) in self.y_labels(y_range).iter().enumerate() {
+                let y = plot.bottom() - tick_offset(tick, self.y_ticks, plot.height);
+                let (width, _) = LABEL_FONT.measure_text(label);
+                let top = (y - label_height / 2).clamp(bounds.y, (bounds.bottom() - label_height + 1).max(bounds.y));
+                screen.put_string(plot.x - 2 - width, top, label);
+            }
+
+            if let Some(range) = x_range {
+                for (tick, label) in labels(range, self.x_ticks).iter().enumerate() {
+                    let x = plot.x + tick_offset(tick, self.x_ticks, plot.width);
+                    let (width, _) = LABEL_FONT.measure_text(label);
+                    let left = (x - width / 2).clamp(bounds.x, (bounds.right() - width + 1).max(bounds.x));
+                    screen.put_string(left, plot.bottom() + 3, label);
+                }
+            }
+        }
+
+        screen.set_font_size(previous_font);
+    }
+
+    /// Draws the dotted gridlines inside `plot`. Charts that erase parts of their plot call
+    /// this again, clipped to the erased area.
+    pub(super) fn draw_grid(&self, screen: &mut Screen, plot: Rect) {
+        let Some((columns, rows)) = self.grid else {
+            return;
+        };
+        if plot.is_empty() {
+            return;
+        }
+
+        for column in 1..columns {
+            let x = plot.x + tick_offset(column, columns, plot.width);
+            screen.draw_mesh(x, plot.y, x, plot.bottom(), self.grid_color);
+        }
+        for row in 1..rows {
+            let y = plot.bottom() - tick_offset(row, rows, plot.height);
+            screen.draw_mesh(plot.x, y, plot.right(), y, self.grid_color);
+        }
+    }
+
+    fn draw_legend(&self, screen: &mut Screen, bounds: Rect) {
+        let mut x = bounds.x;
+        for (name, color) in &self.legend {
+            let (width, _) = LABEL_FONT.measure_text(name);
+            if x + SWATCH + 1 + width - 1 > bounds.right() {
+                break;
+            }
+            screen
+                .fill_frame(x, bounds.y + 1, x + SWATCH - 1, bounds.y + SWATCH, *color)
+                .put_string(x + SWATCH + 1, bounds.y, name);
+            x += SWATCH + 1 + width + LABEL_FONT.column_width();
+        }
+    }
+
+    fn y_labels(&self, y_range: (f32, f32)) -> Vec<String> {
+        if self.axes { labels(y_range, self.y_ticks) } else { Vec::new() }
+    }
+}
+
+/// Pixels from the start of a span of `length` pixels to tick `tick` of `ticks` intervals.
+fn tick_offset(tick: usize, ticks: usize, length: i32) -> i32 {
+    (tick as i32 * (length - 1)) / ticks.max(1) as i32
+}
+
+/// Labels of the `ticks + 1` tick positions from `min` to `max`, with as many decimals as the
+/// step between them needs.
+fn labels((min, max): (f32, f32), ticks: usize) -> Vec<String> {
+    if ticks == 0 {
+        return Vec::new();
+    }
+    let step = (max - min) / ticks as f32;
+    let decimals = if step.abs() >= 1.0 || step == 0.0 {
+        0
+    } else {
+        (-step.abs().log10()).ceil().clamp(0.0, 3.0) as usize
+    };
+    (0..=ticks)
+        .map(|tick| format!("{:.*}", decimals, min + step * tick as f32))
+        .collect()
+}
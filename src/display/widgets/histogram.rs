@@ -0,0 +1,317 @@
+use super::ChartAxes;
+use crate::display::scene::Widget;
+use crate::display::{Color, Rect, Screen};
+use crate::sampler::Reading;
+
+/// A histogram of a stream of values, by default the readings of one ADC channel.
+///
+/// Values are counted into equally wide buckets between a minimum and a maximum, and values
+/// outside of that range into the first or last bucket. The bars are scaled to the fullest
+/// bucket. Moving a line sensor over the field shows the white and the black surface as two
+/// separate clusters, and [`split`](Self::split) finds the threshold between them.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::widgets::Histogram;
+///
+/// let mut histogram = Histogram::new(0, 0, 128, 64).with_buckets(8, 0.0, 4096.0);
+///
+/// for value in [400.0, 450.0, 500.0, 3400.0, 3450.0, 3500.0] {
+///     histogram.add(value);
+/// }
+/// assert_eq!(histogram.counts(), &[3, 0, 0, 0, 0, 0, 3, 0]);
+/// assert_eq!(histogram.bucket_range(6), (3072.0, 3584.0));
+///
+/// // Halfway through the empty buckets between the clusters.
+/// assert_eq!(histogram.split(), Some(1792.0));
+/// ```
+///
+/// Fed from a [`Sampler`](crate::sampler::Sampler), with labeled axes:
+///
+/// ```rust,no_run
+/// use uptechstar_rs::adc_io;
+/// use uptechstar_rs::display::widgets::{ChartAxes, Histogram};
+/// use uptechstar_rs::display::{Screen, ScreenDirection};
+/// use uptechstar_rs::sampler::Sampler;
+///
+/// adc_io::adc_open();
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+/// let mut histogram = Histogram::new(0, 0, 128, 64)
+///     .with_channel(2)
+///     .with_axes(ChartAxes::new().with_axes(4, 2));
+///
+/// let mut sampler = Sampler::new(50.0).with_adc(true);
+/// let readings = sampler.subscribe();
+/// sampler.start();
+///
+/// for reading in readings {
+///     if histogram.update(&reading.value) && histogram.render(&mut screen) {
+///         screen.refresh();
+///     }
+/// }
+/// ```
+pub struct Histogram {
+    bounds: Rect,
+    min: f32,
+    max: f32,
+    counts: Vec<u32>,
+    channel: usize,
+    bar_color: u32,
+    background: u32,
+    axes: ChartAxes,
+    /// Fullest bucket and bar heights in pixels shown by the last render, `None` if nothing
+    /// was drawn yet.
+    drawn: Option<(u32, Vec<i32>)>,
+}
+
+impl Histogram {
+    /// Creates an empty histogram in a box of `width` by `height` pixels at `(x, y)`, with 32
+    /// buckets over the ADC range `0..4096` of channel 0.
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Histogram {
+            bounds: Rect::new(x, y, width, height),
+            min: 0.0,
+            max: 4096.0,
+            counts: vec![0; 32],
+            channel: 0,
+            bar_color: Color::CYAN,
+            background: Color::BLACK,
+            axes: ChartAxes::new(),
+            drawn: None,
+        }
+    }
+
+    /// Sets `count` buckets equally dividing `min..max`, and clears the histogram.
+    ///
+    /// # Panics
+    ///
+    /// If `count` is zero or `max` is not above `min`.
+    pub fn with_buckets(mut self, count: usize, min: f32, max: f32) -> Self {
+        assert!(count > 0, "Histogram needs at least one bucket");
+        assert!(max > min, "Histogram range must not be empty, got {}..{}", min, max);
+        self.counts = vec![0; count];
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// Sets the ADC channel [`update`](Self::update) counts. The default is channel 0.
+    ///
+    /// # Panics
+    ///
+    /// If `channel` is not below 10.
+    pub fn with_channel(mut self, channel: usize) -> Self {
+        assert!(channel < 10, "ADC channel must be below 10, got {}", channel);
+        self.channel = channel;
+        self
+    }
+
+    /// Sets the colors of the bars and the background. The default is cyan on black.
+    pub fn with_colors(mut self, bar_color: u32, background: u32) -> Self {
+        self.bar_color = bar_color;
+        self.background = background;
+        self
+    }
+
+    /// Sets the axes, gridlines and legend drawn around the bars. The default draws none.
+    /// The X axis is labeled with the values, the Y axis with the count of the fullest bucket.
+    pub fn with_axes(mut self, axes: ChartAxes) -> Self {
+        self.axes = axes;
+        self
+    }
+
+    /// Counts `value`. Values that are not finite are ignored.
+    pub fn add(&mut self, value: f32) {
+        if !value.is_finite() {
+            return;
+        }
+        let bucket = self.bucket_of(value);
+        self.counts[bucket] += 1;
+    }
+
+    /// Counts the configured ADC channel of an ADC sampler reading; other readings are ignored.
+    ///
+    /// Returns:
+    ///   `true` if `reading` was an ADC frame.
+    pub fn update(&mut self, reading: &Reading) -> bool {
+        match reading {
+            Reading::Adc(frame) => {
+                self.add(frame.0[self.channel] as f32);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forgets every value counted so far.
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+    }
+
+    /// Returns the count of every bucket, from the lowest values to the highest.
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    /// Returns the number of values counted.
+    pub fn total(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the index of the bucket `value` is counted in.
+    pub fn bucket_of(&self, value: f32) -> usize {
+        let buckets = self.counts.len();
+        let position = (value - self.min) / (self.max - self.min) * buckets as f32;
+        (position.max(0.0) as usize).min(buckets - 1)
+    }
+
+    /// Returns the lowest and highest value of bucket `index`, upper bound exclusive.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is not below the number of buckets.
+    pub fn bucket_range(&self, index: usize) -> (f32, f32) {
+        assert!(index < self.counts.len(), "Bucket {} out of range", index);
+        let width = (self.max - self.min) / self.counts.len() as f32;
+        (self.min + width * index as f32, self.min + width * (index + 1) as f32)
+    }
+
+    /// Returns the threshold that best separates the values into a low and a high cluster,
+    /// by Otsu's method: the bucket boundary maximizing the variance between the two sides.
+    /// If several boundaries are equally good, as across empty buckets, the value halfway
+    /// between the first and the last of them is returned.
+    ///
+    /// Returns:
+    ///   `None` if no two buckets hold values.
+    pub fn split(&self) -> Option<f32> {
+        if self.counts.iter().filter(|&&count| count > 0).count() < 2 {
+            return None;
+        }
+
+        let centers: Vec<f32> = (0..self.counts.len())
+            .map(|index| {
+                let (low, high) = self.bucket_range(index);
+                (low + high) / 2.0
+            })
+            .collect();
+        let total = self.total() as f32;
+        let sum: f32 = self.counts.iter().zip(&centers).map(|(&count, center)| count as f32 * center).sum();
+
+        let (mut low_count, mut low_sum) = (0.0, 0.0);
+        let mut variances = Vec::with_capacity(self.counts.len() - 1);
+        for (&count, center) in self.counts.iter().zip(&centers).take(self.counts.len() - 1) {
+            low_count += count as f32;
+            low_sum += count as f32 * center;
+            let high_count = total - low_count;
+            let variance = if low_count == 0.0 || high_count == 0.0 {
+                0.0
+            } else {
+                let difference = low_sum / low_count - (sum - low_sum) / high_count;
+                low_count * high_count * difference * difference
+            };
+            variances.push(variance);
+        }
+
+        let best = variances.iter().copied().fold(0.0, f32::max);
+        let is_best = |variance: &f32| *variance >= best * (1.0 - 1e-6);
+        // Boundary `i` lies above bucket `i`.
+        let first = variances.iter().position(is_best)?;
+        let last = variances.iter().rposition(is_best)?;
+        Some((self.bucket_range(first).1 + self.bucket_range(last).1) / 2.0)
+    }
+
+    /// The count of the fullest bucket, at least 1 so that an empty histogram has a scale.
+    fn peak(&self) -> u32 {
+        self.counts.iter().copied().max().unwrap_or(0).max(1)
+    }
+
+    fn plot_area(&self) -> Rect {
+        self.axes.plot_area(self.bounds, Some((self.min, self.max)), (0.0, self.peak() as f32))
+    }
+
+    /// The left and right column of the bar of bucket `index` in `plot`.
+    fn column(&self, plot: Rect, index: usize) -> (i32, i32) {
+        let buckets = self.counts.len() as i32;
+        let left = plot.x + index as i32 * plot.width / buckets;
+        let right = plot.x + (index as i32 + 1) * plot.width / buckets - 1;
+        // One pixel of space between neighboring bars if they are wide enough to spare it.
+        let gap = if plot.width / buckets >= 3 { 1 } else { 0 };
+        (left, right - gap)
+    }
+
+    /// Bar heights in pixels for the current counts.
+    fn heights(&self, plot: Rect) -> Vec<i32> {
+        let peak = self.peak() as f32;
+        self.counts
+            .iter()
+            .map(|&count| (count as f32 / peak * plot.height as f32).round() as i32)
+            .collect()
+    }
+
+    /// Redraws the bars that changed since the last render, or everything if this is the
+    /// first render or the fullest bucket changed the scale. The screen is not refreshed.
+    ///
+    /// Returns:
+    ///   `true` if anything was drawn.
+    pub fn render(&mut self, screen: &mut Screen) -> bool {
+        let plot = self.plot_area();
+        let peak = self.peak();
+        let heights = self.heights(plot);
+        let Some((drawn_peak, drawn)) = &self.drawn else {
+            self.draw(screen);
+            self.drawn = Some((peak, heights));
+            return true;
+        };
+        if *drawn_peak != peak || drawn.len() != heights.len() {
+            self.draw(screen);
+            self.drawn = Some((peak, heights));
+            return true;
+        }
+        if *drawn == heights {
+            return false;
+        }
+
+        for (index, (&old, &new)) in drawn.iter().zip(&heights).enumerate() {
+            let (left, right) = self.column(plot, index);
+            if new > old {
+                screen.fill_frame(left, plot.bottom() - new + 1, right, plot.bottom() - old, self.bar_color);
+            } else if new < old {
+                let erased = Rect::from_corners(left, plot.bottom() - old + 1, right, plot.bottom() - new);
+                screen
+                    .fill_frame(erased.x, erased.y, erased.right(), erased.bottom(), self.background)
+                    .push_clip(erased);
+                self.axes.draw_grid(screen, plot);
+                screen.pop_clip();
+            }
+        }
+
+        self.drawn = Some((peak, heights));
+        true
+    }
+}
+
+impl Widget for Histogram {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let bounds = self.bounds;
+        let plot = self.plot_area();
+        screen
+            .save_state()
+            .fill_frame(bounds.x, bounds.y, bounds.right(), bounds.bottom(), self.background)
+            .set_back_color(self.background);
+        self.axes.draw(screen, bounds, Some((self.min, self.max)), (0.0, self.peak() as f32));
+
+        for (index, height) in self.heights(plot).into_iter().enumerate() {
+            if height > 0 {
+                let (left, right) = self.column(plot, index);
+                screen.fill_frame(left, plot.bottom() - height + 1, right, plot.bottom(), self.bar_color);
+            }
+        }
+
+        screen.restore_state();
+    }
+}
@@ -0,0 +1,337 @@
+use crate::display::pager::button_nav;
+use crate::display::scene::Widget;
+use crate::display::{Color, FontSize, Nav, Rect, Screen};
+use crate::events::Event;
+
+/// How an input widget reacted to a navigation input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputResponse {
+    /// The input was not for this widget or had no effect, e.g. stepping past the maximum.
+    Ignored,
+    /// The value changed; the widget should be redrawn.
+    Changed,
+    /// The user confirmed the value.
+    Confirmed,
+}
+
+/// Draws `text` into `bounds` with the given font and colors, restoring the screen font.
+fn draw_text(screen: &mut Screen, bounds: Rect, text: &str, font: FontSize, color: u32, background: u32) {
+    let previous_font = screen.font_size;
+    screen
+        .fill_frame(bounds.x, bounds.y, bounds.right(), bounds.bottom(), background)
+        .set_font_size(font)
+        .set_fore_color(color)
+        .set_back_color(background)
+        .put_string(bounds.x, bounds.y, text)
+        .set_font_size(previous_font);
+}
+
+/// A number edited with up/down buttons within a range, for tuning gains and thresholds on
+/// the robot.
+///
+/// [`Nav::Next`] steps up, [`Nav::Previous`] steps down and [`Nav::Select`] confirms.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::Nav;
+/// use uptechstar_rs::display::widgets::{InputResponse, NumberSpinner};
+///
+/// let mut kp = NumberSpinner::new(0, 0, "Kp", 1.0, 0.0, 2.0, 0.5).with_decimals(1);
+///
+/// kp.navigate(Nav::Next);
+/// kp.navigate(Nav::Next);
+/// kp.navigate(Nav::Next);
+/// assert_eq!(kp.value(), 2.0);
+/// assert_eq!(kp.text(), "Kp: 2.0");
+/// assert_eq!(kp.navigate(Nav::Select), InputResponse::Confirmed);
+/// ```
+pub struct NumberSpinner {
+    x: i32,
+    y: i32,
+    label: String,
+    value: f32,
+    min: f32,
+    max: f32,
+    step: f32,
+    decimals: usize,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    buttons: Option<[u32; 3]>,
+}
+
+impl NumberSpinner {
+    /// Creates a spinner at `(x, y)` showing `value` in `min..=max`, changed by `step`.
+    pub fn new<S: Into<String>>(x: i32, y: i32, label: S, value: f32, min: f32, max: f32, step: f32) -> Self {
+        NumberSpinner {
+            x,
+            y,
+            label: label.into(),
+            value: value.clamp(min, max),
+            min,
+            max,
+            step,
+            decimals: 2,
+            font: FontSize::Font8x12,
+            color: Color::WHITE,
+            background: Color::BLACK,
+            buttons: None,
+        }
+    }
+
+    /// Sets the number of decimal places shown. The default is 2.
+    pub fn with_decimals(mut self, decimals: usize) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Sets the font. The default is [`FontSize::Font8x12`].
+    pub fn with_font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the text and background colors. The default is white on black.
+    pub fn with_colors(mut self, color: u32, background: u32) -> Self {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Maps presses of the buttons on IO pins `up`, `down` and `select` to navigation in
+    /// [`handle_event`](Self::handle_event).
+    pub fn with_buttons(mut self, up: u32, down: u32, select: u32) -> Self {
+        self.buttons = Some([up, down, select]);
+        self
+    }
+
+    /// Returns the current value.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Sets the value, clamped to the range.
+    pub fn set_value(&mut self, value: f32) {
+        self.value = value.clamp(self.min, self.max);
+    }
+
+    /// Returns the label and value as shown.
+    pub fn text(&self) -> String {
+        format!("{}: {:.*}", self.label, self.decimals, self.value)
+    }
+
+    /// Applies a navigation input.
+    pub fn navigate(&mut self, nav: Nav) -> InputResponse {
+        let previous = self.value;
+        match nav {
+            Nav::Next => self.set_value(self.value + self.step),
+            Nav::Previous => self.set_value(self.value - self.step),
+            Nav::Select => return InputResponse::Confirmed,
+        }
+
+        // Keep repeated steps from accumulating rounding errors.
+        let scale = 10f32.powi(self.decimals as i32);
+        self.value = ((self.value * scale).round() / scale).clamp(self.min, self.max);
+
+        if self.value == previous {
+            InputResponse::Ignored
+        } else {
+            InputResponse::Changed
+        }
+    }
+
+    /// Navigates if `event` is a press of one of the [buttons](Self::with_buttons).
+    pub fn handle_event(&mut self, event: &Event) -> InputResponse {
+        button_nav(self.buttons, event).map_or(InputResponse::Ignored, |nav| self.navigate(nav))
+    }
+}
+
+impl Widget for NumberSpinner {
+    fn bounds(&self) -> Rect {
+        let width = self.text().chars().count() as i32 + 1;
+        Rect::new(self.x, self.y, width * self.font.column_width(), self.font.row_height())
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        draw_text(screen, self.bounds(), &self.text(), self.font, self.color, self.background);
+    }
+}
+
+/// Characters offered by a [`TextInput`] unless configured otherwise.
+pub const DEFAULT_CHARSET: &str = " ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_.";
+
+/// How the end marker of a [`TextInput`] is drawn.
+const END_MARKER: char = '<';
+
+/// A short string entered one character at a time with up/down buttons.
+///
+/// [`Nav::Next`] and [`Nav::Previous`] cycle the character under the cursor through the
+/// character set, followed by an end marker drawn as `<`. [`Nav::Select`] moves the cursor to
+/// the next position; selecting the end marker, or the last allowed position, confirms the
+/// text up to the cursor.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::Nav;
+/// use uptechstar_rs::display::widgets::{InputResponse, TextInput};
+///
+/// let mut name = TextInput::new(0, 0, 8).with_charset("AB");
+///
+/// name.navigate(Nav::Next); // the end marker becomes 'A'
+/// name.navigate(Nav::Next); // 'B'
+/// name.navigate(Nav::Select);
+/// name.navigate(Nav::Next); // 'A'
+/// name.navigate(Nav::Select);
+/// assert_eq!(name.navigate(Nav::Select), InputResponse::Confirmed);
+/// assert_eq!(name.text(), "BA");
+/// ```
+pub struct TextInput {
+    x: i32,
+    y: i32,
+    charset: Vec<char>,
+    /// Entered characters as charset indices; `charset.len()` is the end marker.
+    chars: Vec<usize>,
+    cursor: usize,
+    max_len: usize,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    buttons: Option<[u32; 3]>,
+}
+
+impl TextInput {
+    /// Creates an empty input at `(x, y)` for up to `max_len` characters.
+    pub fn new(x: i32, y: i32, max_len: usize) -> Self {
+        let charset: Vec<char> = DEFAULT_CHARSET.chars().collect();
+        TextInput {
+            x,
+            y,
+            chars: vec![charset.len()],
+            charset,
+            cursor: 0,
+            max_len: max_len.max(1),
+            font: FontSize::Font8x12,
+            color: Color::WHITE,
+            background: Color::BLACK,
+            buttons: None,
+        }
+    }
+
+    /// Sets the characters to choose from, in cycling order. Duplicates are removed. The text
+    /// entered so far is cleared.
+    pub fn with_charset(mut self, charset: &str) -> Self {
+        self.charset.clear();
+        for c in charset.chars() {
+            if !self.charset.contains(&c) {
+                self.charset.push(c);
+            }
+        }
+        self.chars = vec![self.charset.len()];
+        self.cursor = 0;
+        self
+    }
+
+    /// Starts editing `text`. Characters outside the character set are replaced by its first
+    /// character.
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.chars = text
+            .chars()
+            .take(self.max_len)
+            .map(|c| self.charset.iter().position(|&option| option == c).unwrap_or(0))
+            .collect();
+        if self.chars.len() < self.max_len {
+            self.chars.push(self.charset.len());
+        }
+        self.cursor = 0;
+        self
+    }
+
+    /// Sets the font. The default is [`FontSize::Font8x12`].
+    pub fn with_font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the text and background colors. The default is white on black.
+    pub fn with_colors(mut self, color: u32, background: u32) -> Self {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Maps presses of the buttons on IO pins `up`, `down` and `select` to navigation in
+    /// [`handle_event`](Self::handle_event).
+    pub fn with_buttons(mut self, up: u32, down: u32, select: u32) -> Self {
+        self.buttons = Some([up, down, select]);
+        self
+    }
+
+    /// Returns the text before the end marker.
+    pub fn text(&self) -> String {
+        self.chars
+            .iter()
+            .take_while(|&&index| index < self.charset.len())
+            .map(|&index| self.charset[index])
+            .collect()
+    }
+
+    /// Returns the position of the cursor.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Applies a navigation input.
+    pub fn navigate(&mut self, nav: Nav) -> InputResponse {
+        // The character set plus the end marker.
+        let options = self.charset.len() + 1;
+        let current = self.chars[self.cursor];
+
+        match nav {
+            Nav::Next => self.chars[self.cursor] = (current + 1) % options,
+            Nav::Previous => self.chars[self.cursor] = (current + options - 1) % options,
+            Nav::Select => {
+                if current == self.charset.len() || self.cursor + 1 == self.max_len {
+                    return InputResponse::Confirmed;
+                }
+                self.cursor += 1;
+                if self.cursor == self.chars.len() {
+                    self.chars.push(self.charset.len());
+                }
+            }
+        }
+        InputResponse::Changed
+    }
+
+    /// Navigates if `event` is a press of one of the [buttons](Self::with_buttons).
+    pub fn handle_event(&mut self, event: &Event) -> InputResponse {
+        button_nav(self.buttons, event).map_or(InputResponse::Ignored, |nav| self.navigate(nav))
+    }
+}
+
+impl Widget for TextInput {
+    fn bounds(&self) -> Rect {
+        // One more row of pixels for the cursor underline.
+        Rect::new(
+            self.x,
+            self.y,
+            self.max_len as i32 * self.font.column_width(),
+            self.font.row_height() + 2,
+        )
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let shown: String = self
+            .chars
+            .iter()
+            .map(|&index| self.charset.get(index).copied().unwrap_or(END_MARKER))
+            .collect();
+        draw_text(screen, self.bounds(), &shown, self.font, self.color, self.background);
+
+        let column = self.font.column_width();
+        let x = self.x + self.cursor as i32 * column;
+        let y = self.y + self.font.row_height() + 1;
+        screen.draw_line(x, y, x + column - 1, y, self.color);
+    }
+}
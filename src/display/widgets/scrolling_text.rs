@@ -0,0 +1,200 @@
+use crate::display::scene::Widget;
+use crate::display::{Color, FontSize, Rect, Screen};
+use std::time::{Duration, Instant};
+
+/// A single line of text that scrolls horizontally when it is wider than its box, for long
+/// network names or error messages on the narrow panel.
+///
+/// uGUI cannot clip text, so the text moves one character at a time: each step redraws the
+/// characters that fit into the box, and nothing outside of it. Text that fits is drawn once
+/// and stays put.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::display::widgets::ScrollingText;
+/// use uptechstar_rs::display::{Color, FontSize};
+///
+/// // 6 characters of 8 pixels fit into 48 pixels.
+/// let mut text = ScrollingText::new(0, 0, 48, "Connected to Robotics-Lab", FontSize::Font8x8, Color::WHITE)
+///     .with_speed(16.0);
+/// assert_eq!(text.visible_text(), "Connec");
+///
+/// // Two characters further after one second.
+/// assert!(text.advance(Duration::from_secs(1)));
+/// assert_eq!(text.visible_text(), "nnecte");
+/// ```
+///
+/// On the screen:
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use std::time::Duration;
+/// use uptechstar_rs::display::widgets::ScrollingText;
+/// use uptechstar_rs::display::{Color, FontSize, Screen, ScreenDirection};
+///
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+/// let mut ssid = ScrollingText::new(0, 0, 128, "Robotics-Lab-5GHz-Guest-Network", FontSize::Font8x12, Color::WHITE);
+///
+/// loop {
+///     if ssid.render(&mut screen) {
+///         screen.refresh();
+///     }
+///     thread::sleep(Duration::from_millis(20));
+/// }
+/// ```
+pub struct ScrollingText {
+    x: i32,
+    y: i32,
+    width: i32,
+    text: Vec<char>,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    speed: f32,
+    gap: usize,
+    /// Scrolled distance in pixels.
+    offset: f32,
+    last_render: Option<Instant>,
+    /// First character shown by the last render, `None` if nothing was drawn yet.
+    drawn: Option<usize>,
+}
+
+impl ScrollingText {
+    /// Creates a text box `width` pixels wide at `(x, y)`, scrolling at 30 pixels per second.
+    pub fn new<S: AsRef<str>>(x: i32, y: i32, width: i32, text: S, font: FontSize, color: u32) -> Self {
+        ScrollingText {
+            x,
+            y,
+            width,
+            text: text.as_ref().chars().collect(),
+            font,
+            color,
+            background: Color::BLACK,
+            speed: 30.0,
+            gap: 3,
+            offset: 0.0,
+            last_render: None,
+            drawn: None,
+        }
+    }
+
+    /// Sets the scrolling speed in pixels per second.
+    pub fn with_speed(mut self, pixels_per_second: f32) -> Self {
+        self.speed = pixels_per_second.max(0.0);
+        self
+    }
+
+    /// Sets the number of blank characters between the end of the text and its next start.
+    /// The default is 3.
+    pub fn with_gap(mut self, characters: usize) -> Self {
+        self.gap = characters;
+        self
+    }
+
+    /// Sets the color the box is cleared with. The default is black.
+    pub fn with_background(mut self, color: u32) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Replaces the text and scrolls back to its start.
+    pub fn set_text<S: AsRef<str>>(&mut self, text: S) {
+        let text: Vec<char> = text.as_ref().chars().collect();
+        if text != self.text {
+            self.text = text;
+            self.offset = 0.0;
+            self.drawn = None;
+        }
+    }
+
+    /// Number of characters that fit into the box.
+    fn columns(&self) -> usize {
+        (self.width / self.font.column_width()).max(0) as usize
+    }
+
+    /// Returns `true` if the text is wider than the box and scrolls.
+    pub fn scrolls(&self) -> bool {
+        self.text.len() > self.columns()
+    }
+
+    /// Index of the first character shown.
+    fn first_char(&self) -> usize {
+        if !self.scrolls() {
+            return 0;
+        }
+        let cycle = self.text.len() + self.gap;
+        (self.offset / self.font.column_width() as f32) as usize % cycle
+    }
+
+    /// Moves the text by `elapsed` at the configured speed.
+    ///
+    /// Returns:
+    ///   `true` if the visible characters changed.
+    pub fn advance(&mut self, elapsed: Duration) -> bool {
+        if !self.scrolls() {
+            return false;
+        }
+
+        let before = self.first_char();
+        let cycle_width = ((self.text.len() + self.gap) as i32 * self.font.column_width()) as f32;
+        self.offset = (self.offset + self.speed * elapsed.as_secs_f32()) % cycle_width;
+        self.first_char() != before
+    }
+
+    /// Returns the characters currently shown, padded with blanks to the box width while
+    /// the gap scrolls through.
+    pub fn visible_text(&self) -> String {
+        if !self.scrolls() {
+            return self.text.iter().collect();
+        }
+
+        let first = self.first_char();
+        (first..first + self.columns())
+            .map(|index| index % (self.text.len() + self.gap))
+            .map(|index| self.text.get(index).copied().unwrap_or(' '))
+            .collect()
+    }
+
+    /// Advances by the time since the last call and redraws the box if the text moved or was
+    /// never drawn. The screen is not refreshed.
+    ///
+    /// Returns:
+    ///   `true` if the box was redrawn.
+    pub fn render(&mut self, screen: &mut Screen) -> bool {
+        let now = Instant::now();
+        if let Some(last) = self.last_render {
+            self.advance(now - last);
+        }
+        self.last_render = Some(now);
+
+        let first = self.first_char();
+        if self.drawn == Some(first) {
+            return false;
+        }
+
+        self.draw(screen);
+        self.drawn = Some(first);
+        true
+    }
+}
+
+impl Widget for ScrollingText {
+    fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.font.row_height())
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let bounds = self.bounds();
+        let previous_font = screen.font_size;
+
+        screen
+            .fill_frame(bounds.x, bounds.y, bounds.right(), bounds.bottom(), self.background)
+            .set_font_size(self.font)
+            .set_fore_color(self.color)
+            .set_back_color(self.background)
+            .put_string(self.x, self.y, &self.visible_text())
+            .set_font_size(previous_font);
+    }
+}
@@ -0,0 +1,489 @@
+use crate::display::scene::Widget;
+use crate::display::{Color, FontSize, Rect, Screen};
+use crate::events::{self, Event, EventBus};
+use std::time::{Duration, Instant};
+
+/// Published by a [`Countdown`] or [`Stopwatch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimerEvent {
+    /// A countdown reached zero.
+    Expired {
+        /// The name of the countdown.
+        timer: String,
+    },
+    /// A lap of a stopwatch was taken.
+    Lap {
+        /// The name of the stopwatch.
+        timer: String,
+        /// The number of the lap, starting at 1.
+        lap: usize,
+        /// The time of this lap alone.
+        split: Duration,
+        /// The time since the stopwatch started.
+        total: Duration,
+    },
+}
+
+/// Formats `time` as `MM:SS`, rounding partial seconds up so zero is only shown at the end.
+fn minutes_seconds_ceil(time: Duration) -> String {
+    let seconds = time.as_millis().div_ceil(1000);
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Formats `time` as `MM:SS.t`.
+fn minutes_seconds_tenths(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    format!("{:02}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+/// The running state shared by both timers.
+struct Clock {
+    elapsed: Duration,
+    running: bool,
+    last_update: Option<Instant>,
+}
+
+impl Clock {
+    fn new() -> Self {
+        Clock {
+            elapsed: Duration::ZERO,
+            running: false,
+            last_update: None,
+        }
+    }
+
+    fn start(&mut self) {
+        self.running = true;
+        self.last_update = Some(Instant::now());
+    }
+
+    fn pause(&mut self) {
+        self.catch_up();
+        self.running = false;
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.last_update = self.running.then(Instant::now);
+    }
+
+    /// Advances by `elapsed` in place of the time since the last update, which is no longer
+    /// counted.
+    fn advance(&mut self, elapsed: Duration) {
+        if self.running {
+            self.elapsed += elapsed;
+        }
+        self.last_update = None;
+    }
+
+    /// Advances by the time since the last update.
+    fn catch_up(&mut self) {
+        let now = Instant::now();
+        if let (true, Some(last)) = (self.running, self.last_update) {
+            self.elapsed += now - last;
+        }
+        self.last_update = Some(now);
+    }
+}
+
+/// A match timer counting down to zero in big digits, which publishes
+/// [`TimerEvent::Expired`] once when it gets there.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::display::widgets::{Countdown, TimerEvent};
+///
+/// let mut timer = Countdown::new(4, 12, "match", Duration::from_secs(90));
+/// assert_eq!(timer.text(), "01:30");
+///
+/// timer.start();
+/// assert_eq!(timer.advance(Duration::from_millis(89_500)), None);
+/// assert_eq!(timer.text(), "00:01");
+/// assert_eq!(timer.advance(Duration::from_secs(1)), Some(TimerEvent::Expired { timer: "match".into() }));
+/// assert_eq!(timer.text(), "00:00");
+/// assert!(timer.is_expired());
+/// ```
+///
+/// On the screen, stopping the robot when time is up:
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use uptechstar_rs::display::widgets::{Countdown, TimerEvent};
+/// use uptechstar_rs::display::{Color, Screen, ScreenDirection};
+/// use uptechstar_rs::events::{self, Event};
+///
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+/// let events = events::subscribe();
+/// let mut timer = Countdown::new(4, 12, "match", Duration::from_secs(180))
+///     .with_warning(Duration::from_secs(10), Color::RED);
+/// timer.start();
+///
+/// loop {
+///     if timer.render(&mut screen) {
+///         screen.refresh();
+///     }
+///     if let Ok(Event::Timer(TimerEvent::Expired { .. })) = events.try_recv() {
+///         break;
+///     }
+///     std::thread::sleep(Duration::from_millis(50));
+/// }
+/// ```
+pub struct Countdown {
+    x: i32,
+    y: i32,
+    name: String,
+    duration: Duration,
+    clock: Clock,
+    expired: bool,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    warning: Option<(Duration, u32)>,
+    bus: EventBus,
+    /// Text and color of the last render, `None` if nothing was drawn yet.
+    drawn: Option<(String, u32)>,
+}
+
+impl Countdown {
+    /// Creates a stopped countdown from `duration` at `(x, y)`, publishing on the
+    /// [process-wide bus](crate::events::global).
+    pub fn new<S: Into<String>>(x: i32, y: i32, name: S, duration: Duration) -> Self {
+        Countdown {
+            x,
+            y,
+            name: name.into(),
+            duration,
+            clock: Clock::new(),
+            expired: false,
+            font: FontSize::Font24x40,
+            color: Color::WHITE,
+            background: Color::BLACK,
+            warning: None,
+            bus: events::global().clone(),
+            drawn: None,
+        }
+    }
+
+    /// Sets the font. The default is [`FontSize::Font24x40`], whose `MM:SS` fills the width of
+    /// the horizontal screen.
+    pub fn with_font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the text and background colors. The default is white on black.
+    pub fn with_colors(mut self, color: u32, background: u32) -> Self {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Draws the time in `color` once no more than `remaining` is left.
+    pub fn with_warning(mut self, remaining: Duration, color: u32) -> Self {
+        self.warning = Some((remaining, color));
+        self
+    }
+
+    /// Publishes on `bus` instead of the process-wide bus.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Starts or resumes counting down.
+    pub fn start(&mut self) {
+        if !self.clock.running {
+            self.clock.start();
+        }
+    }
+
+    /// Stops counting down until the next [`start`](Self::start).
+    pub fn pause(&mut self) {
+        self.clock.pause();
+    }
+
+    /// Goes back to the full duration, running if it was running.
+    pub fn reset(&mut self) {
+        self.clock.reset();
+        self.expired = false;
+    }
+
+    /// Returns `true` while counting down.
+    pub fn is_running(&self) -> bool {
+        self.clock.running && !self.expired
+    }
+
+    /// Returns `true` once zero was reached.
+    pub fn is_expired(&self) -> bool {
+        self.expired
+    }
+
+    /// Returns the time left.
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.clock.elapsed)
+    }
+
+    /// Returns the time left as shown, `MM:SS`.
+    pub fn text(&self) -> String {
+        minutes_seconds_ceil(self.remaining())
+    }
+
+    /// Returns the color the time is drawn in.
+    pub fn color(&self) -> u32 {
+        match self.warning {
+            Some((remaining, color)) if self.remaining() <= remaining => color,
+            _ => self.color,
+        }
+    }
+
+    /// Counts down by `elapsed` if running, in place of the time since the last update.
+    /// Nothing is published.
+    ///
+    /// Returns:
+    ///   [`TimerEvent::Expired`] if zero was reached by this call.
+    pub fn advance(&mut self, elapsed: Duration) -> Option<TimerEvent> {
+        self.clock.advance(elapsed);
+        self.check_expired()
+    }
+
+    /// Counts down by the time since the last call and publishes [`TimerEvent::Expired`] on
+    /// the bus when zero is reached.
+    ///
+    /// Returns:
+    ///   The published event, if any.
+    pub fn update(&mut self) -> Option<TimerEvent> {
+        self.clock.catch_up();
+        let event = self.check_expired()?;
+        self.bus.publish(Event::Timer(event.clone()));
+        Some(event)
+    }
+
+    /// Runs [`update`](Self::update) and redraws the time if what is shown changed or was
+    /// never drawn. The screen is not refreshed.
+    ///
+    /// Returns:
+    ///   `true` if the time was redrawn.
+    pub fn render(&mut self, screen: &mut Screen) -> bool {
+        self.update();
+
+        let shown = (self.text(), self.color());
+        if self.drawn.as_ref() == Some(&shown) {
+            return false;
+        }
+        self.draw(screen);
+        self.drawn = Some(shown);
+        true
+    }
+
+    fn check_expired(&mut self) -> Option<TimerEvent> {
+        if self.expired || self.clock.elapsed < self.duration {
+            return None;
+        }
+        self.expired = true;
+        self.clock.running = false;
+        Some(TimerEvent::Expired { timer: self.name.clone() })
+    }
+}
+
+impl Widget for Countdown {
+    fn bounds(&self) -> Rect {
+        let (width, height) = self.font.measure_text("00:00");
+        Rect::new(self.x, self.y, width, height)
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        draw_time(screen, self.bounds(), &self.text(), self.font, self.color(), self.background);
+    }
+}
+
+/// A stopwatch in big digits that records laps and publishes a [`TimerEvent::Lap`] for each.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::display::widgets::{Stopwatch, TimerEvent};
+/// use uptechstar_rs::events::EventBus;
+///
+/// let mut watch = Stopwatch::new(0, 20, "track").with_bus(EventBus::new());
+/// watch.start();
+/// watch.advance(Duration::from_millis(12_340));
+/// assert_eq!(watch.text(), "00:12.3");
+///
+/// watch.lap();
+/// watch.advance(Duration::from_secs(65));
+/// let event = watch.lap();
+/// assert_eq!(
+///     event,
+///     TimerEvent::Lap {
+///         timer: "track".into(),
+///         lap: 2,
+///         split: Duration::from_secs(65),
+///         total: Duration::from_millis(77_340),
+///     }
+/// );
+/// assert_eq!(watch.text(), "01:17.3");
+/// ```
+pub struct Stopwatch {
+    x: i32,
+    y: i32,
+    name: String,
+    clock: Clock,
+    laps: Vec<Duration>,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    bus: EventBus,
+    drawn: Option<String>,
+}
+
+impl Stopwatch {
+    /// Creates a stopped stopwatch at `(x, y)`, publishing on the
+    /// [process-wide bus](crate::events::global).
+    pub fn new<S: Into<String>>(x: i32, y: i32, name: S) -> Self {
+        Stopwatch {
+            x,
+            y,
+            name: name.into(),
+            clock: Clock::new(),
+            laps: Vec::new(),
+            font: FontSize::Font16x26,
+            color: Color::WHITE,
+            background: Color::BLACK,
+            bus: events::global().clone(),
+            drawn: None,
+        }
+    }
+
+    /// Sets the font. The default is [`FontSize::Font16x26`], whose `MM:SS.t` fits the width of
+    /// the horizontal screen.
+    pub fn with_font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the text and background colors. The default is white on black.
+    pub fn with_colors(mut self, color: u32, background: u32) -> Self {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Publishes on `bus` instead of the process-wide bus.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Starts or resumes timing.
+    pub fn start(&mut self) {
+        if !self.clock.running {
+            self.clock.start();
+        }
+    }
+
+    /// Stops timing until the next [`start`](Self::start).
+    pub fn pause(&mut self) {
+        self.clock.pause();
+    }
+
+    /// Goes back to zero and forgets the laps, running if it was running.
+    pub fn reset(&mut self) {
+        self.clock.reset();
+        self.laps.clear();
+    }
+
+    /// Returns `true` while timing.
+    pub fn is_running(&self) -> bool {
+        self.clock.running
+    }
+
+    /// Returns the time measured so far.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.elapsed
+    }
+
+    /// Returns the total time at each lap taken.
+    pub fn laps(&self) -> &[Duration] {
+        &self.laps
+    }
+
+    /// Returns the time as shown, `MM:SS.t`.
+    pub fn text(&self) -> String {
+        minutes_seconds_tenths(self.clock.elapsed)
+    }
+
+    /// Times by `elapsed` if running, in place of the time since the last update, for tests
+    /// and simulations.
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.clock.advance(elapsed);
+    }
+
+    /// Times by the time since the last call.
+    pub fn update(&mut self) {
+        self.clock.catch_up();
+    }
+
+    /// Records a lap at the current time and publishes it on the bus.
+    ///
+    /// Returns:
+    ///   The published [`TimerEvent::Lap`].
+    pub fn lap(&mut self) -> TimerEvent {
+        self.clock.catch_up();
+        let total = self.clock.elapsed;
+        let split = total - self.laps.last().copied().unwrap_or_default();
+        self.laps.push(total);
+
+        let event = TimerEvent::Lap {
+            timer: self.name.clone(),
+            lap: self.laps.len(),
+            split,
+            total,
+        };
+        self.bus.publish(Event::Timer(event.clone()));
+        event
+    }
+
+    /// Runs [`update`](Self::update) and redraws the time if what is shown changed or was
+    /// never drawn. The screen is not refreshed.
+    ///
+    /// Returns:
+    ///   `true` if the time was redrawn.
+    pub fn render(&mut self, screen: &mut Screen) -> bool {
+        self.update();
+
+        let shown = self.text();
+        if self.drawn.as_ref() == Some(&shown) {
+            return false;
+        }
+        self.draw(screen);
+        self.drawn = Some(shown);
+        true
+    }
+}
+
+impl Widget for Stopwatch {
+    fn bounds(&self) -> Rect {
+        let (width, height) = self.font.measure_text("00:00.0");
+        Rect::new(self.x, self.y, width, height)
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        draw_time(screen, self.bounds(), &self.text(), self.font, self.color, self.background);
+    }
+}
+
+/// Clears `bounds` and draws `text` into it, restoring the screen's colors and font.
+fn draw_time(screen: &mut Screen, bounds: Rect, text: &str, font: FontSize, color: u32, background: u32) {
+    screen
+        .save_state()
+        .fill_frame(bounds.x, bounds.y, bounds.right(), bounds.bottom(), background)
+        .set_font_size(font)
+        .set_fore_color(color)
+        .set_back_color(background)
+        .put_string(bounds.x, bounds.y, text)
+        .restore_state();
+}
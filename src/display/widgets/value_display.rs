@@ -0,0 +1,181 @@
+use crate::display::scene::Widget;
+use crate::display::{Color, FontSize, Rect, Screen, ellipsize};
+
+/// A labeled number with a fixed number of decimals and an optional unit, for telemetry pages.
+///
+/// The label is drawn on the left and the value right-aligned in the box. Values below the
+/// [minimum](Self::with_min) or above the [maximum](Self::with_max) are drawn in their own
+/// color, and [smoothing](Self::with_smoothing) keeps a noisy sensor from making the last
+/// digit flicker.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::Color;
+/// use uptechstar_rs::display::widgets::ValueDisplay;
+///
+/// let mut battery = ValueDisplay::new(0, 0, 128, "Battery")
+///     .with_unit(" V")
+///     .with_decimals(2)
+///     .with_min(7.0, Color::RED)
+///     .with_smoothing(0.5);
+/// assert_eq!(battery.value_text(), "--");
+///
+/// battery.update(8.0);
+/// battery.update(7.0);
+/// assert_eq!(battery.value_text(), "7.50 V");
+/// assert_eq!(battery.value_color(), Color::WHITE);
+///
+/// battery.update(6.0);
+/// assert_eq!(battery.value_text(), "6.75 V");
+/// assert_eq!(battery.value_color(), Color::RED);
+/// ```
+pub struct ValueDisplay {
+    x: i32,
+    y: i32,
+    width: i32,
+    label: String,
+    unit: String,
+    decimals: usize,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    min: Option<(f32, u32)>,
+    max: Option<(f32, u32)>,
+    smoothing: Option<f32>,
+    value: Option<f32>,
+}
+
+impl ValueDisplay {
+    /// Creates a display `width` pixels wide at `(x, y)` with one decimal and no value yet.
+    pub fn new<S: Into<String>>(x: i32, y: i32, width: i32, label: S) -> Self {
+        ValueDisplay {
+            x,
+            y,
+            width,
+            label: label.into(),
+            unit: String::new(),
+            decimals: 1,
+            font: FontSize::Font8x12,
+            color: Color::WHITE,
+            background: Color::BLACK,
+            min: None,
+            max: None,
+            smoothing: None,
+            value: None,
+        }
+    }
+
+    /// Sets the text appended to the value, including any space, e.g. `" V"` or `"°"`.
+    pub fn with_unit<S: Into<String>>(mut self, unit: S) -> Self {
+        self.unit = unit.into();
+        self
+    }
+
+    /// Sets the number of decimal places shown. The default is 1.
+    pub fn with_decimals(mut self, decimals: usize) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Sets the font. The default is [`FontSize::Font8x12`].
+    pub fn with_font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the text and background colors. The default is white on black.
+    pub fn with_colors(mut self, color: u32, background: u32) -> Self {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Draws values below `min` in `color`.
+    pub fn with_min(mut self, min: f32, color: u32) -> Self {
+        self.min = Some((min, color));
+        self
+    }
+
+    /// Draws values above `max` in `color`.
+    pub fn with_max(mut self, max: f32, color: u32) -> Self {
+        self.max = Some((max, color));
+        self
+    }
+
+    /// Smooths updates exponentially: each shown value moves the fraction `alpha` of the way
+    /// towards the new reading. Smaller values are steadier but slower.
+    ///
+    /// # Panics
+    ///
+    /// If `alpha` is not in `0.0 < alpha <= 1.0`.
+    pub fn with_smoothing(mut self, alpha: f32) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0, "Smoothing factor must be in (0.0, 1.0], got {}", alpha);
+        self.smoothing = Some(alpha);
+        self
+    }
+
+    /// Takes a new reading. Readings that are not finite are ignored.
+    pub fn update(&mut self, reading: f32) {
+        if !reading.is_finite() {
+            return;
+        }
+
+        self.value = Some(match (self.value, self.smoothing) {
+            (Some(value), Some(alpha)) => value + alpha * (reading - value),
+            _ => reading,
+        });
+    }
+
+    /// Forgets the value, so `--` is shown until the next [`update`](Self::update).
+    pub fn clear(&mut self) {
+        self.value = None;
+    }
+
+    /// Returns the value as shown, after smoothing.
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+
+    /// Returns the value with its unit as shown, or `--` without a value.
+    pub fn value_text(&self) -> String {
+        match self.value {
+            Some(value) => format!("{:.*}{}", self.decimals, value, self.unit),
+            None => "--".to_string(),
+        }
+    }
+
+    /// Returns the color the value is drawn in.
+    pub fn value_color(&self) -> u32 {
+        match (self.value, self.min, self.max) {
+            (Some(value), Some((min, color)), _) if value < min => color,
+            (Some(value), _, Some((max, color))) if value > max => color,
+            _ => self.color,
+        }
+    }
+}
+
+impl Widget for ValueDisplay {
+    fn bounds(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.font.row_height())
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let bounds = self.bounds();
+        let value = self.value_text();
+        let (value_width, _) = self.font.measure_text(&value);
+        // The label gets what the value and a blank column leave of the box.
+        let label_columns = ((self.width - value_width) / self.font.column_width() - 1).max(0) as usize;
+
+        screen
+            .save_state()
+            .fill_frame(bounds.x, bounds.y, bounds.right(), bounds.bottom(), self.background)
+            .set_font_size(self.font)
+            .set_back_color(self.background)
+            .set_fore_color(self.color)
+            .put_string(self.x, self.y, &ellipsize(&self.label, label_columns))
+            .set_fore_color(self.value_color())
+            .put_string((self.x + self.width - value_width).max(self.x), self.y, &value)
+            .restore_state();
+    }
+}
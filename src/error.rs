@@ -0,0 +1,72 @@
+use std::fmt;
+use std::io;
+
+/// Errors reported by the higher-level APIs of this crate.
+///
+/// The low-level wrappers in [`adc_io`](crate::adc_io), [`mpu`](crate::mpu) and
+/// [`display`](crate::display) keep returning the raw status codes of `libuptech.so`; this
+/// type is used where several operations are combined, such as [`Board`](crate::board::Board)
+/// initialization.
+#[derive(Debug)]
+pub enum UptechError {
+    /// A hardware call returned a failure status code.
+    Hardware {
+        /// Name of the failed operation, e.g. `"adc_io_open"`.
+        operation: &'static str,
+        /// The status code it returned.
+        code: i32,
+    },
+    /// The embedded `libuptech.so` could not be loaded.
+    LibraryLoad(String),
+    /// The loaded `libuptech.so` does not export a required function, typically because it
+    /// is an older or incompatible build.
+    MissingSymbol(&'static str),
+    /// A configuration value is missing, malformed or out of range.
+    Config(String),
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// A peripheral was already initialized by another part of the program, e.g. `"lcd"`.
+    AlreadyInitialized(&'static str),
+}
+
+/// Shorthand for results carrying an [`UptechError`].
+pub type Result<T> = std::result::Result<T, UptechError>;
+
+impl UptechError {
+    /// Turns a C-style status code into a result, treating any non-zero code as a failure.
+    pub(crate) fn check(operation: &'static str, code: i32) -> Result<()> {
+        if code == 0 {
+            Ok(())
+        } else {
+            Err(UptechError::Hardware { operation, code })
+        }
+    }
+}
+
+impl fmt::Display for UptechError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UptechError::Hardware { operation, code } => write!(f, "{} failed with status {}", operation, code),
+            UptechError::LibraryLoad(message) => write!(f, "{}", message),
+            UptechError::MissingSymbol(name) => write!(f, "libuptech.so does not export '{}'", name),
+            UptechError::Config(message) => write!(f, "invalid configuration: {}", message),
+            UptechError::Io(e) => write!(f, "I/O error: {}", e),
+            UptechError::AlreadyInitialized(peripheral) => write!(f, "{} is already initialized", peripheral),
+        }
+    }
+}
+
+impl std::error::Error for UptechError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UptechError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for UptechError {
+    fn from(error: io::Error) -> Self {
+        UptechError::Io(error)
+    }
+}
@@ -0,0 +1,240 @@
+//! A publish/subscribe bus for events from inputs, sensors and the UI.
+//!
+//! Subsystems publish [`Event`]s to an [`EventBus`] and consumers subscribe to it, so the parts
+//! of a program do not need to know about each other. Every subscriber receives every event
+//! published after it subscribed; subscribers that dropped their receiver are removed on the
+//! next publish.
+//!
+//! Most programs use the process-wide bus through [`publish`] and [`subscribe`]. The
+//! existing input drivers report on their own channels, which [`EventBus::forward`] connects
+//! to a bus.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::adc_io::{self, Keypad};
+//! use uptechstar_rs::events::{self, Event};
+//!
+//! adc_io::adc_open();
+//!
+//! let mut keypad = Keypad::new(&[0, 1, 2, 3], &[4, 5, 6, 7]);
+//! events::global().forward(keypad.subscribe());
+//! keypad.start();
+//!
+//! for event in events::subscribe() {
+//!     match event {
+//!         Event::Key(key) if key.pressed => println!("key {:?}", key.key),
+//!         Event::ThresholdCrossed { channel, value, .. } => println!("ADC{} at {}", channel, value),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+
+use crate::adc_io::{IrEvent, KeyEvent};
+use crate::display::widgets::TimerEvent;
+use crate::health::HealthEvent;
+use crate::input::GamepadEvent;
+use crate::mpu::gestures::Gesture;
+use crate::settings::SettingValue;
+use log::debug;
+use once_cell::sync::Lazy;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// An event published on an [`EventBus`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Event {
+    /// A button on an IO pin went down.
+    ButtonPressed {
+        /// The IO pin.
+        pin: u32,
+    },
+    /// A button on an IO pin was released.
+    ButtonReleased {
+        /// The IO pin.
+        pin: u32,
+    },
+    /// An ADC channel crossed a configured threshold.
+    ThresholdCrossed {
+        /// The ADC channel.
+        channel: usize,
+        /// The reading that crossed the threshold.
+        value: i32,
+        /// `true` if the reading rose above the threshold, `false` if it fell below.
+        rising: bool,
+    },
+    /// The DMP of the MPU6500 detected a tap.
+    DmpTap {
+        /// The axis and sign of the tap, as reported by the DMP.
+        direction: u8,
+        /// The number of consecutive taps.
+        count: u8,
+    },
+    /// The board is in free fall, see [`MotionEvents`](crate::mpu::MotionEvents).
+    FreeFall,
+    /// The board was hit or hit something, see [`MotionEvents`](crate::mpu::MotionEvents).
+    Impact {
+        /// Peak acceleration magnitude in g.
+        g: f32,
+    },
+    /// The board is being shaken, see [`MotionEvents`](crate::mpu::MotionEvents).
+    Shake,
+    /// A gesture was recognized, see [`GestureRecognizer`](crate::mpu::gestures::GestureRecognizer).
+    Gesture(Gesture),
+    /// A menu entry was chosen.
+    MenuSelected {
+        /// Index of the entry.
+        index: usize,
+    },
+    /// A key of a [`Keypad`](crate::adc_io::Keypad) changed.
+    Key(KeyEvent),
+    /// An infrared remote key was received by an [`IrReceiver`](crate::adc_io::IrReceiver).
+    Ir(IrEvent),
+    /// A subsystem was re-initialized, see [`health`](crate::health).
+    Health(HealthEvent),
+    /// A value of a [`Settings`](crate::settings::Settings) store changed.
+    SettingChanged {
+        /// Name of the setting.
+        key: String,
+        /// The new value.
+        value: SettingValue,
+    },
+    /// A gamepad button or axis changed, see [`input`](crate::input).
+    Gamepad(GamepadEvent),
+    /// A [`Countdown`](crate::display::widgets::Countdown) expired or a
+    /// [`Stopwatch`](crate::display::widgets::Stopwatch) lap was taken.
+    Timer(TimerEvent),
+    /// A frame was received by a `serial::SerialLink` (`serial` feature).
+    SerialFrame {
+        /// The path of the port, such as `/dev/ttyUSB0`.
+        port: String,
+        /// The frame without its delimiter or length prefix.
+        data: Vec<u8>,
+    },
+    /// An application-defined event.
+    Custom(String),
+}
+
+impl From<KeyEvent> for Event {
+    fn from(event: KeyEvent) -> Self {
+        Event::Key(event)
+    }
+}
+
+impl From<IrEvent> for Event {
+    fn from(event: IrEvent) -> Self {
+        Event::Ir(event)
+    }
+}
+
+impl From<Gesture> for Event {
+    fn from(gesture: Gesture) -> Self {
+        Event::Gesture(gesture)
+    }
+}
+
+impl From<GamepadEvent> for Event {
+    fn from(event: GamepadEvent) -> Self {
+        Event::Gamepad(event)
+    }
+}
+
+impl From<TimerEvent> for Event {
+    fn from(event: TimerEvent) -> Self {
+        Event::Timer(event)
+    }
+}
+
+impl From<HealthEvent> for Event {
+    fn from(event: HealthEvent) -> Self {
+        Event::Health(event)
+    }
+}
+
+/// A set of subscribers that published events are fanned out to.
+///
+/// `EventBus` is a cheap handle; clones publish to and subscribe on the same bus.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::events::{Event, EventBus};
+///
+/// let bus = EventBus::new();
+/// let events = bus.subscribe();
+///
+/// bus.publish(Event::MenuSelected { index: 2 });
+/// assert_eq!(events.try_recv(), Ok(Event::MenuSelected { index: 2 }));
+/// ```
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<Event>>>>,
+}
+
+impl EventBus {
+    /// Creates a bus without subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends `event` to every subscriber.
+    pub fn publish(&self, event: Event) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        debug!("Publishing {:?} to {} subscribers", event, subscribers.len());
+        subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// Registers a new subscriber and returns its receiving end.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    /// Returns the number of subscribers, including ones that disconnected since the last
+    /// publish.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Publishes everything received on `receiver` on this bus, from a background thread that
+    /// exits once the sending side is closed.
+    pub fn forward<T>(&self, receiver: Receiver<T>) -> JoinHandle<()>
+    where
+        T: Into<Event> + Send + 'static,
+    {
+        let bus = self.clone();
+        thread::Builder::new()
+            .name("uptech-events".into())
+            .spawn(move || {
+                for event in receiver {
+                    bus.publish(event.into());
+                }
+                debug!("Event forwarding thread exited");
+            })
+            .expect("Failed to spawn event forwarding thread")
+    }
+}
+
+static GLOBAL: Lazy<EventBus> = Lazy::new(EventBus::new);
+
+/// Returns the process-wide bus.
+pub fn global() -> &'static EventBus {
+    &GLOBAL
+}
+
+/// Publishes `event` on the process-wide bus.
+pub fn publish(event: Event) {
+    GLOBAL.publish(event);
+}
+
+/// Subscribes to the process-wide bus.
+pub fn subscribe() -> Receiver<Event> {
+    GLOBAL.subscribe()
+}
@@ -1,8 +1,20 @@
-use libloading::Library;
+//! Loading of the embedded `libuptech.so` and probing of what it provides.
+//!
+//! Different board images ship different builds of the vendor library. [`capabilities`]
+//! reports which groups of functions the loaded build exports, so applications can check up
+//! front instead of failing on the first call into a missing symbol.
+
+use crate::error::{Result, UptechError};
+use libloading::{Library, Symbol};
+use log::{debug, warn};
+#[cfg(not(feature = "system-lib"))]
 use std::io::Write;
+#[cfg(not(feature = "system-lib"))]
 use tempfile::NamedTempFile;
 
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
+use sha2::{Digest, Sha256};
+use std::ffi::{CStr, c_char};
 
 /// Global library instance for the Uptech hardware library.
 ///
@@ -12,16 +24,17 @@ use once_cell::sync::Lazy;
 ///
 /// # Library Loading Process
 ///
-/// The library loading follows a secure 4-step process:
-///
 /// 1. **Resource Extraction**: The compiled `.so` library is embedded as a byte array
 ///    using `include_bytes!` macro, ensuring the library is bundled with the executable.
 ///
-/// 2. **Temporary File Creation**: A secure temporary file is created using `NamedTempFile`
-///    to store the extracted library bytes. This ensures proper cleanup and security.
+/// 2. **In-Memory Loading** (Linux): The bytes are written to an anonymous `memfd_create`
+///    file, which is sealed against further modification and loaded through
+///    `/proc/self/fd/<fd>`. Nothing touches the disk, so this works on read-only root
+///    filesystems and leaves no window in which another process could swap the file.
 ///
-/// 3. **Library Writing**: The embedded library bytes are written to the temporary file,
-///    creating a valid shared object file that can be loaded by the system.
+/// 3. **Temporary File Fallback**: If `memfd_create` is unavailable (older kernels, non-Linux
+///    targets), the bytes are written to a `NamedTempFile` instead, which is loaded and then
+///    deleted again.
 ///
 /// 4. **Dynamic Loading**: The library is loaded using `libloading::Library`, providing
 ///    access to all exported functions and symbols.
@@ -32,9 +45,10 @@ use once_cell::sync::Lazy;
 /// ensuring that the library is loaded exactly once regardless of concurrent access
 /// from multiple threads.
 ///
-/// # Panic Conditions
+/// # Failure Conditions
 ///
-/// The library initialization will panic in the following scenarios:
+/// Loading fails, and every lookup through [`symbol`] reports
+/// [`UptechError::LibraryLoad`], in the following scenarios:
 /// - Failed to create a temporary file (system resource exhaustion, permissions)
 /// - Failed to write library bytes to temporary file (disk space, I/O errors)
 /// - Failed to load the shared library (missing dependencies, architecture mismatch)
@@ -47,44 +61,460 @@ use once_cell::sync::Lazy;
 /// - LED control functions (`adc_led_set`)
 /// - Font and color management
 ///
-/// # Example
+/// Functions are looked up with [`symbol`], or with `symbol_or_return!` inside wrappers that
+/// report failures through a status code.
+///
+/// # System Library
+///
+/// With the `system-lib` feature, nothing is embedded. The library is opened from the path in
+/// the `UPTECH_LIB_PATH` environment variable if set, or as `libuptech.so` through the
+/// dynamic linker's usual search path otherwise.
+///
+/// # Platform Support
+///
+/// Currently supports Linux-based systems with the Uptech hardware platform.
+/// The embedded library is architecture-specific and compiled for the target platform.
+static LIBRARY: Lazy<std::result::Result<Library, String>> = Lazy::new(load);
+
+/// Environment variable overriding where the `system-lib` feature loads the library from.
+#[cfg(feature = "system-lib")]
+pub const LIB_PATH_ENV: &str = "UPTECH_LIB_PATH";
+
+/// Loads the system-installed library from `UPTECH_LIB_PATH`, or by name through the
+/// dynamic linker's search path.
+#[cfg(feature = "system-lib")]
+fn load() -> std::result::Result<Library, String> {
+    let path = std::env::var_os(LIB_PATH_ENV).unwrap_or_else(|| "libuptech.so".into());
+    debug!("Loading system libuptech.so from {:?}", path);
+
+    let library =
+        unsafe { Library::new(&path) }.map_err(|e| format!("Failed to load library from {:?}: {}", path, e))?;
+
+    // A bare name is resolved by the dynamic linker, so look up which file it actually mapped.
+    let resolved = if std::path::Path::new(&path).components().count() > 1 {
+        Some(std::path::PathBuf::from(&path))
+    } else {
+        mapped_library_path()
+    };
+    match resolved.map(|resolved| std::fs::read(&resolved).map_err(|e| (resolved, e))) {
+        Some(Ok(bytes)) => verify_checksum(&bytes),
+        Some(Err((resolved, e))) => warn!("Cannot verify {}: {}", resolved.display(), e),
+        None => warn!("Cannot locate the loaded libuptech.so to verify its checksum"),
+    }
+
+    Ok(library)
+}
+
+/// Finds the file backing the mapped `libuptech.so` in `/proc/self/maps`.
+#[cfg(feature = "system-lib")]
+fn mapped_library_path() -> Option<std::path::PathBuf> {
+    let maps = std::fs::read_to_string("/proc/self/maps").ok()?;
+    maps.lines()
+        .filter_map(|line| line.split_whitespace().nth(5))
+        .find(|path| path.ends_with("/libuptech.so") || path.contains("/libuptech.so."))
+        .map(std::path::PathBuf::from)
+}
+
+/// Loads the library embedded in the binary.
+#[cfg(not(feature = "system-lib"))]
+fn load() -> std::result::Result<Library, String> {
+    let so_bytes: &[u8] = include_bytes!("../lib/libuptech.so");
+    verify_checksum(so_bytes);
+
+    #[cfg(target_os = "linux")]
+    match load_from_memfd(so_bytes) {
+        Ok(library) => return Ok(library),
+        Err(e) => debug!("Loading libuptech.so from memory failed ({}), using a temp file", e),
+    }
+
+    load_from_temp_file(so_bytes)
+}
+
+/// Loads the library from a sealed anonymous in-memory file.
+#[cfg(all(target_os = "linux", not(feature = "system-lib")))]
+fn load_from_memfd(so_bytes: &[u8]) -> std::result::Result<Library, String> {
+    use std::fs::File;
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    let fd = unsafe { libc::memfd_create(c"libuptech.so".as_ptr(), libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING) };
+    if fd < 0 {
+        return Err(format!("memfd_create: {}", std::io::Error::last_os_error()));
+    }
+    let mut file = unsafe { File::from_raw_fd(fd) };
+
+    file.write_all(so_bytes)
+        .map_err(|e| format!("Failed to write .so to memfd: {}", e))?;
+
+    let seals = libc::F_SEAL_SEAL | libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE;
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_ADD_SEALS, seals) } < 0 {
+        return Err(format!("Failed to seal memfd: {}", std::io::Error::last_os_error()));
+    }
+
+    // The loader keeps its own mapping, so the descriptor can be closed once loaded.
+    let path = format!("/proc/self/fd/{}", file.as_raw_fd());
+    unsafe { Library::new(&path) }.map_err(|e| format!("Failed to load library: {}", e))
+}
+
+/// Loads the library from a temporary file that is deleted again once loaded.
+#[cfg(not(feature = "system-lib"))]
+fn load_from_temp_file(so_bytes: &[u8]) -> std::result::Result<Library, String> {
+    let mut tmp_file: NamedTempFile =
+        NamedTempFile::new().map_err(|e| format!("Failed to create temp file: {}", e))?;
+    tmp_file
+        .write_all(so_bytes)
+        .map_err(|e| format!("Failed to write .so to temp file: {}", e))?;
+
+    let so_path = tmp_file.into_temp_path();
+
+    unsafe { Library::new(so_path.as_os_str()) }.map_err(|e| format!("Failed to load library: {}", e))
+}
+
+/// SHA-256 digests of `libuptech.so` builds the wrappers in this crate are known to match.
+const KNOWN_GOOD_SHA256: &[&str] = &[
+    // Build bundled in `lib/` since the first release.
+    "75dfa819e8e43d786453150c9536d14747a5594686fdccf5a64278ca597ddf22",
+];
+
+/// Symbols that builds with version information are expected to export, each returning a
+/// NUL-terminated version string.
+const VERSION_SYMBOLS: &[&str] = &["uptech_version", "libuptech_version"];
+
+/// Hex SHA-256 of the library bytes, recorded while loading.
+static CHECKSUM: OnceCell<String> = OnceCell::new();
+
+/// Records the checksum of the library about to be loaded and warns about unknown builds.
+fn verify_checksum(so_bytes: &[u8]) {
+    let checksum: String = Sha256::digest(so_bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    if KNOWN_GOOD_SHA256.contains(&checksum.as_str()) {
+        debug!("libuptech.so checksum {} verified", checksum);
+    } else {
+        warn!(
+            "libuptech.so checksum {} does not match any known build; \
+             function signatures may have drifted from these wrappers",
+            checksum
+        );
+    }
+
+    let _ = CHECKSUM.set(checksum);
+}
+
+/// Returns the hex SHA-256 of the loaded library.
+///
+/// Returns `None` if the library could not be read, e.g. because it failed to load.
+pub fn library_checksum() -> Option<&'static str> {
+    Lazy::force(&LIBRARY);
+    CHECKSUM.get().map(String::as_str)
+}
+
+/// Returns `true` if the loaded library is a build these wrappers are known to match.
+pub fn is_known_build() -> bool {
+    library_checksum().is_some_and(|checksum| KNOWN_GOOD_SHA256.contains(&checksum))
+}
+
+static VERSION: Lazy<Option<String>> = Lazy::new(|| {
+    let library = LIBRARY.as_ref().ok()?;
+
+    for name in VERSION_SYMBOLS {
+        let version = unsafe {
+            let Ok(function) = library.get::<unsafe extern "C" fn() -> *const c_char>(name.as_bytes()) else {
+                continue;
+            };
+            let version = function();
+            if version.is_null() {
+                continue;
+            }
+            CStr::from_ptr(version).to_string_lossy().into_owned()
+        };
+        return Some(version);
+    }
+
+    library_checksum().map(|checksum| format!("sha256:{}", checksum))
+});
+
+/// Returns a version identifier for the loaded library.
+///
+/// This is the version string reported by the library itself if it exports one, and
+/// `sha256:<checksum>` otherwise. Returns `None` if the library could not be loaded.
+///
+/// # Examples
 ///
 /// ```rust,no_run
-/// use crate::extern_lib::LIBRARY;
+/// use uptechstar_rs::extern_lib::{is_known_build, library_version};
+///
+/// println!("libuptech {}", library_version().unwrap_or("not loaded"));
+/// if !is_known_build() {
+///     eprintln!("warning: untested libuptech build");
+/// }
+/// ```
+pub fn library_version() -> Option<&'static str> {
+    VERSION.as_deref()
+}
+
+/// Looks up a function exported by the embedded library.
+///
+/// # Errors
+///
+/// [`UptechError::LibraryLoad`] if the library could not be loaded, or
+/// [`UptechError::MissingSymbol`] if it does not export `name`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use crate::extern_lib::symbol;
 /// use libloading::Symbol;
 ///
 /// unsafe {
-///     let lcd_open: Symbol<unsafe extern "C" fn(i32) -> i32> = LIBRARY
-///         .get(b"lcd_open")
-///         .expect("Failed to load lcd_open function");
-///     
+///     let lcd_open: Symbol<unsafe extern "C" fn(i32) -> i32> = symbol("lcd_open")?;
+///
 ///     lcd_open(1); // Open LCD in vertical mode
 /// }
 /// ```
 ///
 /// # Safety
 ///
-/// This static variable is marked as `unsafe` due to the inherent risks of dynamic
-/// library loading and FFI operations. All function calls through this library
-/// must be wrapped in `unsafe` blocks and proper error handling should be implemented.
+/// `T` must match the actual signature of the exported function.
+pub(crate) unsafe fn symbol<T>(name: &'static str) -> Result<Symbol<'static, T>> {
+    let library = LIBRARY
+        .as_ref()
+        .map_err(|e| UptechError::LibraryLoad(e.clone()))?;
+
+    unsafe { library.get(name.as_bytes()) }.map_err(|_| UptechError::MissingSymbol(name))
+}
+
+/// Looks up a library function with the given signature, or logs the lookup error and returns
+/// `$fallback` from the enclosing function.
 ///
-/// # Platform Support
+/// Used by wrappers that report failures through C-style status codes, so that an
+/// incompatible library degrades into failed calls instead of aborting the process.
 ///
-/// Currently supports Linux-based systems with the Uptech hardware platform.
-/// The embedded library is architecture-specific and compiled for the target platform.
-pub(crate) static LIBRARY: Lazy<Library> = Lazy::new(|| unsafe {
-    // Step 1: Read the .so bytes from resources
-    let so_bytes = include_bytes!("../lib/libuptech.so");
+/// With the `bindgen` feature, the signature is also checked at compile time against the
+/// declaration generated from `libuptech.h`.
+macro_rules! symbol_or_return {
+    ($name:ident: $signature:ty, $fallback:expr) => {{
+        #[cfg(feature = "bindgen")]
+        const _: $signature = $crate::ffi::$name;
 
-    // Step 2: Create a temporary file and write the .so content
-    let mut tmp_file: NamedTempFile = NamedTempFile::new().expect("Failed to create temp file");
-    tmp_file.write_all(so_bytes).expect("Failed to write .so to temp file");
+        match $crate::extern_lib::symbol::<$signature>(stringify!($name)) {
+            Ok(function) => function,
+            Err(e) => {
+                log::error!("{}", e);
+                return $fallback;
+            }
+        }
+    }};
+}
 
-    // Step 3: Get the temporary file path
-    let so_path = tmp_file.into_temp_path();
+pub(crate) use symbol_or_return;
 
-    // Step 4: Load the .so library
-    Library::new(so_path.as_os_str()).expect("Failed to load library")
-});
+/// Symbols backing the [`adc_io`](crate::adc_io) wrappers.
+const ADC_IO_SYMBOLS: &[&str] = &[
+    "adc_io_open",
+    "adc_io_close",
+    "ADC_GetAll",
+    "adc_io_InputGetAll",
+    "adc_io_SetAll",
+    "adc_io_Set",
+    "adc_io_ModeGetAll",
+    "adc_io_ModeSet",
+];
+
+/// Symbols backing the MPU6500 reads in [`mpu`](crate::mpu).
+const MPU_SYMBOLS: &[&str] = &[
+    "mpu6500_dmp_init",
+    "mpu6500_Get_Accel",
+    "mpu6500_Get_Gyro",
+    "mpu6500_Get_Attitude",
+];
+
+/// Symbols backing the full-scale range getters and setters in [`mpu`](crate::mpu).
+const MPU_FSR_SYMBOLS: &[&str] = &[
+    "mpu_get_gyro_fsr",
+    "mpu_get_accel_fsr",
+    "mpu_set_gyro_fsr",
+    "mpu_set_accel_fsr",
+];
+
+/// Symbols backing the LCD drawing methods of [`Screen`](crate::display::Screen).
+pub(crate) const DISPLAY_SYMBOLS: &[&str] = &[
+    "lcd_open",
+    "lcd_close",
+    "LCD_Refresh",
+    "LCD_SetFont",
+    "UG_SetForecolor",
+    "UG_SetBackcolor",
+    "UG_FillScreen",
+    "UG_PutString",
+    "UG_FillFrame",
+    "UG_FillRoundFrame",
+    "UG_FillCircle",
+    "UG_DrawMesh",
+    "UG_DrawFrame",
+    "UG_DrawRoundFrame",
+    "UG_DrawPixel",
+    "UG_DrawCircle",
+    "UG_DrawArc",
+    "UG_DrawLine",
+];
+
+/// Symbols backing the LED methods of [`Screen`](crate::display::Screen).
+const LED_SYMBOLS: &[&str] = &["adc_led_set"];
+
+/// Which parts of this crate the loaded `libuptech.so` supports.
+///
+/// Each flag is `true` only if every symbol its wrappers use is exported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The library itself could be loaded. All other flags are `false` otherwise.
+    pub loaded: bool,
+    /// ADC reads and IO level/mode control.
+    pub adc_io: bool,
+    /// MPU6500 initialization and accelerometer, gyroscope and attitude reads.
+    pub mpu: bool,
+    /// MPU6500 full-scale range configuration.
+    pub mpu_fsr: bool,
+    /// LCD and drawing functions.
+    pub display: bool,
+    /// The two RGB LEDs.
+    pub leds: bool,
+    missing: Vec<&'static str>,
+    load_error: Option<String>,
+}
+
+impl Capabilities {
+    fn probe() -> Self {
+        let library = match &*LIBRARY {
+            Ok(library) => library,
+            Err(e) => {
+                return Capabilities {
+                    loaded: false,
+                    adc_io: false,
+                    mpu: false,
+                    mpu_fsr: false,
+                    display: false,
+                    leds: false,
+                    missing: Vec::new(),
+                    load_error: Some(e.clone()),
+                };
+            }
+        };
+
+        let mut missing = Vec::new();
+        let mut group = |symbols: &[&'static str]| {
+            let before = missing.len();
+            missing.extend(
+                symbols
+                    .iter()
+                    .filter(|symbol| unsafe { library.get::<*const ()>(symbol.as_bytes()) }.is_err()),
+            );
+            missing.len() == before
+        };
+
+        let capabilities = Capabilities {
+            loaded: true,
+            adc_io: group(ADC_IO_SYMBOLS),
+            mpu: group(MPU_SYMBOLS),
+            mpu_fsr: group(MPU_FSR_SYMBOLS),
+            display: group(DISPLAY_SYMBOLS),
+            leds: group(LED_SYMBOLS),
+            missing,
+            load_error: None,
+        };
+
+        if !capabilities.missing.is_empty() {
+            warn!(
+                "libuptech.so is missing {} symbol(s): {}",
+                capabilities.missing.len(),
+                capabilities.missing.join(", ")
+            );
+        }
+
+        capabilities
+    }
+
+    /// Returns `true` if the library loaded and exports every symbol this crate uses.
+    pub fn is_complete(&self) -> bool {
+        self.loaded && self.missing.is_empty()
+    }
+
+    /// Returns the symbols used by this crate that the library does not export.
+    pub fn missing_symbols(&self) -> &[&'static str] {
+        &self.missing
+    }
+
+    /// Returns why the library could not be loaded, if it could not.
+    pub fn load_error(&self) -> Option<&str> {
+        self.load_error.as_deref()
+    }
+
+    /// Returns an error describing the first incompatibility, if any.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::UptechError;
+    /// use uptechstar_rs::extern_lib::capabilities;
+    ///
+    /// match capabilities().ensure_complete() {
+    ///     Ok(()) => {}
+    ///     Err(UptechError::MissingSymbol(name)) => eprintln!("libuptech.so is too old: no {}", name),
+    ///     Err(e) => eprintln!("{}", e),
+    /// }
+    /// ```
+    pub fn ensure_complete(&self) -> Result<()> {
+        if let Some(e) = &self.load_error {
+            return Err(UptechError::LibraryLoad(e.clone()));
+        }
+        match self.missing.first() {
+            Some(name) => Err(UptechError::MissingSymbol(name)),
+            None => Ok(()),
+        }
+    }
+}
+
+static CAPABILITIES: Lazy<Capabilities> = Lazy::new(Capabilities::probe);
+
+/// Probes the embedded library and reports what it supports.
+///
+/// Probing happens once; later calls return the cached report. Unlike the hardware wrappers,
+/// this never panics, even if the library cannot be loaded at all.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::extern_lib::capabilities;
+///
+/// let caps = capabilities();
+/// if !caps.is_complete() {
+///     eprintln!("Incompatible libuptech.so, missing: {:?}", caps.missing_symbols());
+/// }
+/// if caps.mpu {
+///     uptechstar_rs::mpu::mpu6500_open();
+/// }
+/// ```
+pub fn capabilities() -> &'static Capabilities {
+    &CAPABILITIES
+}
+
+/// Checks that the loaded library exports all of `symbols`.
+pub(crate) fn require(symbols: &[&'static str]) -> Result<()> {
+    for name in symbols {
+        unsafe { symbol::<*const ()>(name) }?;
+    }
+    Ok(())
+}
+
+/// Returns `true` if the loaded library exports `name`.
+///
+/// Returns `false` if the library cannot be loaded.
+pub fn has_symbol(name: &str) -> bool {
+    match &*LIBRARY {
+        Ok(library) => unsafe { library.get::<*const ()>(name.as_bytes()) }.is_ok(),
+        Err(_) => false,
+    }
+}
 
 
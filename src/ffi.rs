@@ -0,0 +1,9 @@
+//! Declarations generated by bindgen from `libuptech.h`.
+//!
+//! Nothing here is called or linked: the library is still opened at runtime by
+//! [`extern_lib`](crate::extern_lib). The declarations only serve as the reference every
+//! hand-written signature is checked against, so a header change that is not mirrored in the
+//! wrappers fails the build instead of corrupting arguments at runtime.
+#![allow(non_upper_case_globals, non_camel_case_types, non_snake_case, dead_code)]
+
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
@@ -0,0 +1,297 @@
+//! GPS positioning from NMEA 0183 receivers.
+//!
+//! Most GPS modules print NMEA sentences over a 9600 baud serial line. [`Gps`] reads them from
+//! any byte stream, a [`SoftUart`](crate::adc_io::SoftUart) on the IO header or a `/dev/tty*`
+//! device, on a background thread and keeps the latest [`Fix`]. Attach it to a
+//! [`StateHub`](crate::telemetry::StateHub) with
+//! [`with_gps`](crate::telemetry::StateHub::with_gps) to publish the fix along with the other
+//! sensors.
+//!
+//! Only the `GGA` and `RMC` sentences are used, from any talker (`GP`, `GN`, `GL`, ...);
+//! together they carry everything in a [`Fix`]. [`NmeaParser`] decodes them without any I/O.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use uptechstar_rs::gps::Gps;
+//!
+//! // Configure the port first, e.g. `stty -F /dev/ttyUSB0 9600 raw`.
+//! let gps = Gps::open("/dev/ttyUSB0").unwrap();
+//! loop {
+//!     if let Some(fix) = gps.latest() {
+//!         println!("{:.6}, {:.6} ±{:.1} HDOP at {}", fix.lat, fix.lon, fix.hdop, fix.time);
+//!     }
+//!     std::thread::sleep(Duration::from_secs(1));
+//! }
+//! ```
+
+use log::{debug, info, warn};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+
+/// Meters per second in one knot.
+const KNOT: f32 = 0.514_444;
+
+/// A UTC time of day as reported by the receiver.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UtcTime {
+    pub hour: u8,
+    pub minute: u8,
+    /// Seconds including the fraction, `0.0..60.0`.
+    pub second: f32,
+}
+
+impl fmt::Display for UtcTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}:{:05.2}Z", self.hour, self.minute, self.second)
+    }
+}
+
+/// A position fix.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fix {
+    /// Latitude in degrees, positive north.
+    pub lat: f64,
+    /// Longitude in degrees, positive east.
+    pub lon: f64,
+    /// Speed over ground in m/s.
+    pub speed: f32,
+    /// Course over ground in degrees from true north, if the receiver is moving.
+    pub course: Option<f32>,
+    /// Altitude above mean sea level in meters, once a `GGA` sentence reported it.
+    pub altitude: Option<f32>,
+    /// Horizontal dilution of precision; below 2 is good, above 5 is poor.
+    pub hdop: f32,
+    /// Number of satellites used for the fix.
+    pub satellites: u8,
+    /// Time of the fix.
+    pub time: UtcTime,
+}
+
+/// Decodes `GGA` and `RMC` sentences into a [`Fix`].
+///
+/// The fix is built up from both sentence types, since neither has all fields. It is cleared
+/// when the receiver reports that it lost the fix.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::gps::NmeaParser;
+///
+/// let mut parser = NmeaParser::new();
+/// parser.push_line("$GPGGA,123519.00,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*69").unwrap();
+/// let fix = parser
+///     .push_line("$GNRMC,123519.00,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W,A*37")
+///     .unwrap()
+///     .unwrap();
+///
+/// assert!((fix.lat - 48.1173).abs() < 1e-6);
+/// assert!((fix.lon - 11.516667).abs() < 1e-6);
+/// assert_eq!((fix.satellites, fix.hdop, fix.altitude), (8, 0.9, Some(545.4)));
+/// assert!((fix.speed - 11.52).abs() < 0.01);
+/// assert_eq!(fix.time.to_string(), "12:35:19.00Z");
+///
+/// // Lost fix
+/// assert_eq!(parser.push_line("$GPGGA,123520.00,,,,,0,00,99.99,,,,,,*61"), Ok(None));
+/// assert!(parser.push_line("$GPGGA,123520.00,,,,,0,00,99.99,,,,,,*00").is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NmeaParser {
+    fix: Option<Fix>,
+}
+
+impl NmeaParser {
+    /// Creates a parser without a fix.
+    pub fn new() -> Self {
+        NmeaParser::default()
+    }
+
+    /// Returns the current fix.
+    pub fn fix(&self) -> Option<Fix> {
+        self.fix
+    }
+
+    /// Decodes one sentence, with or without the line ending.
+    ///
+    /// Returns the updated fix after a `GGA` or `RMC` sentence, `None` after a sentence
+    /// reporting no fix or any other sentence, and an error for malformed sentences or a wrong
+    /// checksum. A sentence without a checksum is accepted.
+    pub fn push_line(&mut self, line: &str) -> Result<Option<Fix>, &'static str> {
+        let line = line.trim_end();
+        let body = line.strip_prefix('$').ok_or("NMEA sentence does not start with '$'")?;
+        let body = match body.split_once('*') {
+            Some((body, checksum)) => {
+                let expected = u8::from_str_radix(checksum, 16).map_err(|_| "Malformed NMEA checksum")?;
+                if body.bytes().fold(0, |sum, byte| sum ^ byte) != expected {
+                    return Err("NMEA checksum mismatch");
+                }
+                body
+            }
+            None => body,
+        };
+
+        let fields: Vec<&str> = body.split(',').collect();
+        let kind = fields[0].get(2..).ok_or("Malformed NMEA address")?;
+        match kind {
+            "GGA" => self.gga(&fields),
+            "RMC" => self.rmc(&fields),
+            _ => Ok(None),
+        }
+    }
+
+    /// `$--GGA,time,lat,N,lon,E,quality,satellites,hdop,altitude,M,...`
+    fn gga(&mut self, fields: &[&str]) -> Result<Option<Fix>, &'static str> {
+        if fields.len() < 10 {
+            return Err("Truncated GGA sentence");
+        }
+        if fields[6].is_empty() || fields[6] == "0" {
+            self.fix = None;
+            return Ok(None);
+        }
+
+        let mut fix = self.fix.unwrap_or_default();
+        fix.time = parse_time(fields[1])?;
+        fix.lat = parse_coordinate(fields[2], fields[3], 2)?;
+        fix.lon = parse_coordinate(fields[4], fields[5], 3)?;
+        fix.satellites = fields[7].parse().unwrap_or(0);
+        fix.hdop = fields[8].parse().map_err(|_| "Malformed GGA HDOP")?;
+        fix.altitude = fields[9].parse().ok();
+        self.fix = Some(fix);
+        Ok(Some(fix))
+    }
+
+    /// `$--RMC,time,status,lat,N,lon,E,speed,course,date,...`
+    fn rmc(&mut self, fields: &[&str]) -> Result<Option<Fix>, &'static str> {
+        if fields.len() < 9 {
+            return Err("Truncated RMC sentence");
+        }
+        if fields[2] != "A" {
+            self.fix = None;
+            return Ok(None);
+        }
+
+        let mut fix = self.fix.unwrap_or_default();
+        fix.time = parse_time(fields[1])?;
+        fix.lat = parse_coordinate(fields[3], fields[4], 2)?;
+        fix.lon = parse_coordinate(fields[5], fields[6], 3)?;
+        fix.speed = fields[7].parse::<f32>().map_err(|_| "Malformed RMC speed")? * KNOT;
+        fix.course = fields[8].parse().ok();
+        self.fix = Some(fix);
+        Ok(Some(fix))
+    }
+}
+
+/// Parses `hhmmss.ss`.
+fn parse_time(field: &str) -> Result<UtcTime, &'static str> {
+    let error = "Malformed NMEA time";
+    if field.len() < 6 || !field.is_ascii() {
+        return Err(error);
+    }
+    Ok(UtcTime {
+        hour: field[0..2].parse().map_err(|_| error)?,
+        minute: field[2..4].parse().map_err(|_| error)?,
+        second: field[4..].parse().map_err(|_| error)?,
+    })
+}
+
+/// Parses `ddmm.mmmm` (latitude, 2 degree digits) or `dddmm.mmmm` (longitude, 3 degree
+/// digits) with its hemisphere into signed degrees.
+fn parse_coordinate(field: &str, hemisphere: &str, degree_digits: usize) -> Result<f64, &'static str> {
+    let error = "Malformed NMEA coordinate";
+    if field.len() <= degree_digits || !field.is_ascii() {
+        return Err(error);
+    }
+    let degrees: f64 = field[..degree_digits].parse().map_err(|_| error)?;
+    let minutes: f64 = field[degree_digits..].parse().map_err(|_| error)?;
+    let value = degrees + minutes / 60.0;
+
+    match hemisphere {
+        "N" | "E" => Ok(value),
+        "S" | "W" => Ok(-value),
+        _ => Err(error),
+    }
+}
+
+/// A GPS receiver read on a background thread.
+///
+/// The thread stops when the stream ends or fails. Dropping the `Gps` detaches the thread,
+/// which exits with the stream; a blocking device read can keep it alive until then.
+pub struct Gps {
+    fix: Arc<RwLock<Option<Fix>>>,
+    thread: JoinHandle<()>,
+}
+
+impl Gps {
+    /// Opens a serial device such as `/dev/ttyS1` or `/dev/ttyUSB0` and reads it.
+    ///
+    /// The device must already be configured for the receiver's baud rate in raw mode.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        info!("Reading GPS from {}", path.display());
+        Ok(Self::spawn(file))
+    }
+
+    /// Reads NMEA sentences from `source` on a new thread.
+    pub fn spawn<R: Read + Send + 'static>(source: R) -> Self {
+        let fix = Arc::new(RwLock::new(None));
+        let latest = Arc::clone(&fix);
+
+        let thread = thread::Builder::new()
+            .name("uptech-gps".into())
+            .spawn(move || {
+                let mut parser = NmeaParser::new();
+                let mut reader = BufReader::new(source);
+                let mut line = Vec::new();
+
+                loop {
+                    // On a timeout the bytes read so far stay in `line`, and reading continues.
+                    match reader.read_until(b'\n', &mut line) {
+                        Ok(0) => break,
+                        Ok(_) => {}
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted || e.kind() == io::ErrorKind::TimedOut => continue,
+                        Err(e) => {
+                            warn!("GPS stream failed: {}", e);
+                            break;
+                        }
+                    }
+
+                    // Serial noise can produce invalid UTF-8, which fails the checksum anyway.
+                    if let Ok(text) = std::str::from_utf8(&line) {
+                        match parser.push_line(text) {
+                            Ok(_) => *latest.write().unwrap_or_else(|e| e.into_inner()) = parser.fix(),
+                            Err(e) => debug!("Skipping NMEA sentence: {}", e),
+                        }
+                    }
+                    line.clear();
+                }
+
+                debug!("GPS thread exited");
+            })
+            .expect("Failed to spawn GPS thread");
+
+        Gps { fix, thread }
+    }
+
+    /// Returns the latest fix, or `None` while the receiver has none.
+    pub fn latest(&self) -> Option<Fix> {
+        *self.fix.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns `true` while the reading thread is running.
+    pub fn is_running(&self) -> bool {
+        !self.thread.is_finished()
+    }
+
+    /// Returns the shared slot the latest fix is written to.
+    pub(crate) fn shared(&self) -> Arc<RwLock<Option<Fix>>> {
+        Arc::clone(&self.fix)
+    }
+}
@@ -0,0 +1,273 @@
+//! Failure tracking and automatic recovery of the hardware subsystems.
+//!
+//! Every ADC/IO and MPU wrapper reports its final status code (after
+//! [retries](crate::retry)) here. The resulting counters are available as a [`HealthReport`]
+//! through [`report`].
+//!
+//! A serial link that dropped out or an MPU that lost its DMP configuration does not come back
+//! on its own. With [`set_recovery_threshold`], the subsystem is re-initialized
+//! (`adc_io_open` or `mpu6500_dmp_init`) every time its consecutive failures reach the
+//! threshold, and the outcome is published as a [`HealthEvent`] to every [`subscribe`]r.
+//!
+//! Reaching the threshold for the first time also marks the subsystem as lost
+//! ([`HealthEvent::SensorLost`]) until one of its calls succeeds again
+//! ([`HealthEvent::SensorRecovered`]). A re-initialized MPU gets back the full scale ranges
+//! set with [`mpu_set_gyro_fsr`](crate::mpu::mpu_set_gyro_fsr) and
+//! [`mpu_set_accel_fsr`](crate::mpu::mpu_set_accel_fsr), so a brown-out or a loose cable
+//! does not require restarting the program.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::thread;
+//! use uptechstar_rs::health::{self, HealthEvent};
+//!
+//! health::set_recovery_threshold(Some(10));
+//!
+//! let events = health::subscribe();
+//! thread::spawn(move || {
+//!     for event in events {
+//!         match event {
+//!             HealthEvent::SensorLost { subsystem, .. } => eprintln!("{:?} stopped responding", subsystem),
+//!             HealthEvent::SensorRecovered { subsystem } => println!("{:?} is back", subsystem),
+//!             HealthEvent::RecoveryFailed { subsystem, code } => {
+//!                 eprintln!("{:?} could not be re-initialized: {}", subsystem, code)
+//!             }
+//!             _ => {}
+//!         }
+//!     }
+//! });
+//! ```
+
+use crate::adc_io;
+use crate::backend;
+use crate::mpu;
+use log::{error, info, warn};
+use once_cell::sync::Lazy;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// A hardware subsystem whose health is tracked separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Subsystem {
+    /// The ADC and IO controller, sharing one serial channel.
+    AdcIo,
+    /// The MPU6500 motion sensor.
+    Mpu,
+}
+
+/// Counters of one subsystem.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SubsystemHealth {
+    /// Calls that failed since the last successful one.
+    pub consecutive_failures: u32,
+    /// All calls made so far.
+    pub total_calls: u64,
+    /// All failed calls so far.
+    pub total_failures: u64,
+    /// Successful automatic re-initializations.
+    pub recoveries: u64,
+    /// When the most recent failure happened.
+    pub last_failure: Option<Instant>,
+}
+
+impl SubsystemHealth {
+    /// Returns `true` if the most recent call succeeded, or no call was made yet.
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures == 0
+    }
+}
+
+/// A snapshot of all subsystem counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HealthReport {
+    /// The ADC and IO controller.
+    pub adc_io: SubsystemHealth,
+    /// The MPU6500.
+    pub mpu: SubsystemHealth,
+}
+
+impl HealthReport {
+    /// Returns the counters of `subsystem`.
+    pub fn get(&self, subsystem: Subsystem) -> &SubsystemHealth {
+        match subsystem {
+            Subsystem::AdcIo => &self.adc_io,
+            Subsystem::Mpu => &self.mpu,
+        }
+    }
+
+    /// Returns `true` if every subsystem is healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.adc_io.is_healthy() && self.mpu.is_healthy()
+    }
+}
+
+/// Published to [`subscribe`]rs when a subsystem is lost, re-initialized or back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HealthEvent {
+    /// The failure threshold was reached for the first time since the subsystem last worked.
+    SensorLost {
+        /// The failing subsystem.
+        subsystem: Subsystem,
+        /// Its consecutive failures at this point.
+        consecutive_failures: u32,
+    },
+    /// The failure threshold was reached and re-initialization is starting.
+    Recovering {
+        /// The failing subsystem.
+        subsystem: Subsystem,
+        /// Its consecutive failures at this point.
+        consecutive_failures: u32,
+    },
+    /// Re-initialization succeeded.
+    Recovered {
+        /// The recovered subsystem.
+        subsystem: Subsystem,
+    },
+    /// Re-initialization failed; it is tried again after another threshold of failures.
+    RecoveryFailed {
+        /// The failing subsystem.
+        subsystem: Subsystem,
+        /// The status code of the failed initialization call.
+        code: i32,
+    },
+    /// A call succeeded again after the subsystem was lost.
+    SensorRecovered {
+        /// The subsystem that works again.
+        subsystem: Subsystem,
+    },
+}
+
+struct Monitor {
+    report: HealthReport,
+    threshold: Option<u32>,
+    recovering: [bool; 2],
+    lost: [bool; 2],
+    subscribers: Vec<Sender<HealthEvent>>,
+}
+
+impl Monitor {
+    fn entry(&mut self, subsystem: Subsystem) -> &mut SubsystemHealth {
+        match subsystem {
+            Subsystem::AdcIo => &mut self.report.adc_io,
+            Subsystem::Mpu => &mut self.report.mpu,
+        }
+    }
+
+    fn publish(&mut self, event: HealthEvent) {
+        self.subscribers.retain(|subscriber| subscriber.send(event).is_ok());
+    }
+}
+
+static MONITOR: Lazy<Mutex<Monitor>> = Lazy::new(|| {
+    Mutex::new(Monitor {
+        report: HealthReport::default(),
+        threshold: None,
+        recovering: [false; 2],
+        lost: [false; 2],
+        subscribers: Vec::new(),
+    })
+});
+
+/// Returns the current counters of all subsystems.
+pub fn report() -> HealthReport {
+    MONITOR.lock().unwrap_or_else(|e| e.into_inner()).report
+}
+
+/// Clears all counters.
+pub fn reset() {
+    MONITOR.lock().unwrap_or_else(|e| e.into_inner()).report = HealthReport::default();
+}
+
+/// Enables automatic re-initialization after `threshold` consecutive failures, or disables it
+/// with `None` (the default).
+///
+/// # Returns
+///
+/// * `Option<u32>` - The previous threshold.
+pub fn set_recovery_threshold(threshold: Option<u32>) -> Option<u32> {
+    let mut monitor = MONITOR.lock().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut monitor.threshold, threshold.map(|t| t.max(1)))
+}
+
+/// Registers a new subscriber for [`HealthEvent`]s and returns its receiving end.
+pub fn subscribe() -> Receiver<HealthEvent> {
+    let (sender, receiver) = mpsc::channel();
+    MONITOR
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .subscribers
+        .push(sender);
+    receiver
+}
+
+/// Records the status code of a call to `subsystem`, recovering it if the threshold is reached.
+pub(crate) fn record(subsystem: Subsystem, code: i32) {
+    let consecutive_failures = {
+        let mut monitor = MONITOR.lock().unwrap_or_else(|e| e.into_inner());
+        let threshold = monitor.threshold;
+        let entry = monitor.entry(subsystem);
+
+        entry.total_calls += 1;
+        if code == 0 {
+            entry.consecutive_failures = 0;
+            if std::mem::take(&mut monitor.lost[subsystem as usize]) {
+                info!("{:?} responds again", subsystem);
+                monitor.publish(HealthEvent::SensorRecovered { subsystem });
+            }
+            return;
+        }
+        entry.total_failures += 1;
+        entry.consecutive_failures += 1;
+        entry.last_failure = Some(Instant::now());
+        let consecutive_failures = entry.consecutive_failures;
+
+        match threshold {
+            Some(threshold) if consecutive_failures.is_multiple_of(threshold) && !monitor.recovering[subsystem as usize] => {
+                monitor.recovering[subsystem as usize] = true;
+                if !std::mem::replace(&mut monitor.lost[subsystem as usize], true) {
+                    monitor.publish(HealthEvent::SensorLost { subsystem, consecutive_failures });
+                }
+                monitor.publish(HealthEvent::Recovering { subsystem, consecutive_failures });
+                consecutive_failures
+            }
+            _ => return,
+        }
+    };
+
+    warn!("{:?} failed {} times in a row, re-initializing", subsystem, consecutive_failures);
+    let code = recover(subsystem);
+
+    let mut monitor = MONITOR.lock().unwrap_or_else(|e| e.into_inner());
+    monitor.recovering[subsystem as usize] = false;
+    if code == 0 {
+        info!("{:?} re-initialized", subsystem);
+        let entry = monitor.entry(subsystem);
+        entry.recoveries += 1;
+        entry.consecutive_failures = 0;
+        monitor.publish(HealthEvent::Recovered { subsystem });
+    } else {
+        error!("Failed to re-initialize {:?}, status {}", subsystem, code);
+        monitor.publish(HealthEvent::RecoveryFailed { subsystem, code });
+    }
+}
+
+/// Re-runs the initialization of `subsystem` and restores its settings, returning `0` on
+/// success.
+fn recover(subsystem: Subsystem) -> i32 {
+    let backend = backend::current();
+    match subsystem {
+        Subsystem::AdcIo => {
+            backend.adc_io_close();
+            adc_io::reset_channel_state();
+            if backend.adc_io_open() < 0 { -1 } else { 0 }
+        }
+        Subsystem::Mpu => match backend.mpu_init() {
+            0 => mpu::reapply_settings(),
+            code => code,
+        },
+    }
+}
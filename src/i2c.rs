@@ -0,0 +1,185 @@
+//! Access to external I2C devices on the board's sensor bus.
+//!
+//! `libuptech.so` talks to the MPU6500 through register-oriented I2C helpers that take the
+//! device address as a parameter. This module exposes them for other devices on the same bus,
+//! such as environmental sensors, range finders or small OLED panels.
+//!
+//! Every transfer addresses a register: writes send the register followed by the data, reads
+//! send the register and then read back the data with a repeated start. Plain reads without a
+//! register are not possible.
+//!
+//! With the `embedded-hal` feature, [`I2cBus`] implements `embedded_hal::i2c::I2c`, so
+//! existing device drivers can run on the bus unchanged, as long as they only use register
+//! transfers.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::i2c;
+//!
+//! // Read the chip id of a BME280 at 0x76.
+//! let mut id = [0u8; 1];
+//! if i2c::read_registers(0x76, 0xd0, &mut id) == 0 {
+//!     println!("chip id: {:#04x}", id[0]);
+//! }
+//! ```
+
+use crate::extern_lib::symbol_or_return;
+use crate::stats;
+#[cfg(feature = "embedded-hal")]
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, Operation, SevenBitAddress};
+use log::error;
+#[cfg(feature = "embedded-hal")]
+use std::fmt;
+
+/// Maximum number of data bytes per transfer, limited by the `u8` length of the C API.
+pub const MAX_TRANSFER: usize = u8::MAX as usize;
+
+/// Reads `data.len()` bytes starting at `register` of the device at the 7-bit `address`.
+///
+/// Returns:
+///   0 on success, or the error code of the transfer. Transfers longer than [`MAX_TRANSFER`]
+///   fail with -1.
+pub fn read_registers(address: u8, register: u8, data: &mut [u8]) -> i32 {
+    if data.len() > MAX_TRANSFER {
+        error!("I2C read of {} bytes exceeds the limit of {}", data.len(), MAX_TRANSFER);
+        return -1;
+    }
+
+    unsafe {
+        let call = stats::start("MPU_I2C_readBytes");
+        let i2c_read_bytes = symbol_or_return!(MPU_I2C_readBytes: unsafe extern "C" fn(u8, u8, u8, *mut u8) -> i32, -1);
+
+        call.finish(i2c_read_bytes(address, register, data.len() as u8, data.as_mut_ptr()))
+    }
+}
+
+/// Writes `data` starting at `register` of the device at the 7-bit `address`.
+///
+/// Returns:
+///   0 on success, or the error code of the transfer. Transfers longer than [`MAX_TRANSFER`]
+///   fail with -1.
+pub fn write_registers(address: u8, register: u8, data: &[u8]) -> i32 {
+    if data.len() > MAX_TRANSFER {
+        error!("I2C write of {} bytes exceeds the limit of {}", data.len(), MAX_TRANSFER);
+        return -1;
+    }
+
+    unsafe {
+        let call = stats::start("MPU_I2C_writeBytes");
+        let i2c_write_bytes = symbol_or_return!(MPU_I2C_writeBytes: unsafe extern "C" fn(u8, u8, u8, *const u8) -> i32, -1);
+
+        call.finish(i2c_write_bytes(address, register, data.len() as u8, data.as_ptr()))
+    }
+}
+
+/// Reads one register of the device at the 7-bit `address`.
+///
+/// Returns:
+///   The register value, or the error code of the transfer.
+pub fn read_register(address: u8, register: u8) -> Result<u8, i32> {
+    let mut value = [0u8; 1];
+    match read_registers(address, register, &mut value) {
+        0 => Ok(value[0]),
+        code => Err(code),
+    }
+}
+
+/// Writes one register of the device at the 7-bit `address`.
+///
+/// Returns:
+///   0 on success, or the error code of the transfer.
+pub fn write_register(address: u8, register: u8, value: u8) -> i32 {
+    write_registers(address, register, &[value])
+}
+
+/// The board's sensor I2C bus as a value, for drivers that take ownership of their bus.
+///
+/// The handle carries no state, so one can be created for every driver. The bus is shared with
+/// the MPU6500; avoid long transfers while sampling it at a high rate.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[cfg(feature = "embedded-hal")]
+/// # {
+/// use embedded_hal::i2c::I2c;
+/// use uptechstar_rs::i2c::I2cBus;
+///
+/// let mut bus = I2cBus::new();
+/// let mut id = [0u8; 1];
+/// bus.write_read(0x76, &[0xd0], &mut id).unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct I2cBus {
+    _private: (),
+}
+
+impl I2cBus {
+    /// Creates a handle to the bus.
+    pub fn new() -> Self {
+        I2cBus::default()
+    }
+}
+
+/// Errors of the [`embedded_hal::i2c::I2c`] implementation of [`I2cBus`].
+#[cfg(feature = "embedded-hal")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum I2cError {
+    /// The transfer failed with this status code, e.g. because no device acknowledged.
+    Transfer(i32),
+    /// The transaction has a shape the register-oriented C API cannot perform, such as a
+    /// plain read or an empty write.
+    Unsupported,
+}
+
+#[cfg(feature = "embedded-hal")]
+impl fmt::Display for I2cError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            I2cError::Transfer(code) => write!(f, "I2C transfer failed with status {}", code),
+            I2cError::Unsupported => write!(f, "I2C transaction is not a register read or write"),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl std::error::Error for I2cError {}
+
+#[cfg(feature = "embedded-hal")]
+impl embedded_hal::i2c::Error for I2cError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+#[cfg(feature = "embedded-hal")]
+impl ErrorType for I2cBus {
+    type Error = I2cError;
+}
+
+#[cfg(feature = "embedded-hal")]
+fn check(code: i32) -> Result<(), I2cError> {
+    match code {
+        0 => Ok(()),
+        code => Err(I2cError::Transfer(code)),
+    }
+}
+
+/// Supports the transactions register-based drivers use:
+/// - a single write, whose first byte is the register and the rest the data
+/// - a one-byte write of the register followed by a single read
+///
+/// Everything else fails with [`I2cError::Unsupported`].
+#[cfg(feature = "embedded-hal")]
+impl I2c<SevenBitAddress> for I2cBus {
+    fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+        match operations {
+            [] => Ok(()),
+            [Operation::Write([register, data @ ..])] => check(write_registers(address, *register, data)),
+            [Operation::Write([register]), Operation::Read(data)] => check(read_registers(address, *register, data)),
+            _ => Err(I2cError::Unsupported),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Gamepads and joysticks.
+//!
+//! A USB gamepad plugged into the board shows up as a Linux input device. `Gamepad` (`gamepad`
+//! feature) reads it on a background thread and reports [`GamepadEvent`]s, which
+//! [`EventBus::forward`](crate::events::EventBus::forward) puts on the event bus like the other
+//! input drivers. Buttons and axes are named after their position on an Xbox-style pad, as in
+//! the Linux gamepad specification, so programs work with any pad the kernel supports.
+//!
+//! Stick axes range from -1 to 1, with up and right positive; triggers range from 0 to 1.
+
+use std::collections::{HashMap, HashSet};
+
+/// A gamepad button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GamepadButton {
+    /// The lower face button, A on Xbox pads.
+    South,
+    /// The right face button, B on Xbox pads.
+    East,
+    /// The upper face button, Y on Xbox pads.
+    North,
+    /// The left face button, X on Xbox pads.
+    West,
+    LeftBumper,
+    RightBumper,
+    /// The left trigger of pads that report it as a button.
+    LeftTrigger,
+    /// The right trigger of pads that report it as a button.
+    RightTrigger,
+    Select,
+    Start,
+    /// The logo button in the middle.
+    Mode,
+    /// Pressing the left stick.
+    LeftStick,
+    /// Pressing the right stick.
+    RightStick,
+    DpadUp,
+    DpadDown,
+    DpadLeft,
+    DpadRight,
+    /// Any other button, with its Linux key code.
+    Other(u16),
+}
+
+impl GamepadButton {
+    /// Maps a Linux `BTN_*` key code.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::input::GamepadButton;
+    ///
+    /// assert_eq!(GamepadButton::from_code(0x130), GamepadButton::South);
+    /// assert_eq!(GamepadButton::from_code(0x2c0), GamepadButton::Other(0x2c0));
+    /// ```
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            0x130 => GamepadButton::South,
+            0x131 => GamepadButton::East,
+            0x133 => GamepadButton::North,
+            0x134 => GamepadButton::West,
+            0x136 => GamepadButton::LeftBumper,
+            0x137 => GamepadButton::RightBumper,
+            0x138 => GamepadButton::LeftTrigger,
+            0x139 => GamepadButton::RightTrigger,
+            0x13a => GamepadButton::Select,
+            0x13b => GamepadButton::Start,
+            0x13c => GamepadButton::Mode,
+            0x13d => GamepadButton::LeftStick,
+            0x13e => GamepadButton::RightStick,
+            0x220 => GamepadButton::DpadUp,
+            0x221 => GamepadButton::DpadDown,
+            0x222 => GamepadButton::DpadLeft,
+            0x223 => GamepadButton::DpadRight,
+            other => GamepadButton::Other(other),
+        }
+    }
+}
+
+/// A gamepad axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+    /// The D-pad of pads that report it as a hat, -1, 0 or 1.
+    DpadX,
+    /// The D-pad of pads that report it as a hat, -1, 0 or 1.
+    DpadY,
+    /// Any other axis, with its Linux axis code.
+    Other(u16),
+}
+
+impl GamepadAxis {
+    /// Maps a Linux `ABS_*` axis code.
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            0x00 => GamepadAxis::LeftX,
+            0x01 => GamepadAxis::LeftY,
+            0x02 => GamepadAxis::LeftTrigger,
+            0x03 => GamepadAxis::RightX,
+            0x04 => GamepadAxis::RightY,
+            0x05 => GamepadAxis::RightTrigger,
+            0x10 => GamepadAxis::DpadX,
+            0x11 => GamepadAxis::DpadY,
+            other => GamepadAxis::Other(other),
+        }
+    }
+
+    /// Returns `true` for axes that Linux reports with down or left as the maximum.
+    fn is_inverted(self) -> bool {
+        matches!(self, GamepadAxis::LeftY | GamepadAxis::RightY | GamepadAxis::DpadY)
+    }
+
+    /// Scales a raw reading from `min..=max` to -1..1, or 0..1 for triggers, with readings
+    /// within `deadzone` of the center reported as 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::input::GamepadAxis;
+    ///
+    /// assert_eq!(GamepadAxis::LeftX.normalize(32767, -32768, 32767, 0.1), 1.0);
+    /// assert_eq!(GamepadAxis::LeftX.normalize(1000, -32768, 32767, 0.1), 0.0);
+    /// // Pushed all the way up.
+    /// assert_eq!(GamepadAxis::LeftY.normalize(-32768, -32768, 32767, 0.1), 1.0);
+    /// assert_eq!(GamepadAxis::RightTrigger.normalize(255, 0, 255, 0.0), 1.0);
+    /// ```
+    pub fn normalize(self, value: i32, min: i32, max: i32, deadzone: f32) -> f32 {
+        if max <= min {
+            return 0.0;
+        }
+
+        let unit = (value - min) as f32 / (max - min) as f32;
+        let mut scaled = match self {
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => unit,
+            _ => unit * 2.0 - 1.0,
+        };
+        if self.is_inverted() {
+            scaled = -scaled;
+        }
+
+        let scaled = scaled.clamp(-1.0, 1.0);
+        if scaled.abs() < deadzone { 0.0 } else { scaled }
+    }
+}
+
+/// A change reported by a gamepad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GamepadEvent {
+    /// A button went down or up.
+    Button {
+        button: GamepadButton,
+        pressed: bool,
+    },
+    /// An axis moved.
+    Axis {
+        axis: GamepadAxis,
+        /// The normalized position, see the [module documentation](self).
+        value: f32,
+    },
+    /// The gamepad was unplugged; no further events follow.
+    Disconnected,
+}
+
+/// The current buttons and axes of a gamepad, built up from its [`GamepadEvent`]s.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::input::{GamepadAxis, GamepadButton, GamepadEvent, GamepadState};
+///
+/// let mut state = GamepadState::default();
+/// state.apply(&GamepadEvent::Button { button: GamepadButton::South, pressed: true });
+/// state.apply(&GamepadEvent::Axis { axis: GamepadAxis::LeftY, value: 0.5 });
+///
+/// assert!(state.is_pressed(GamepadButton::South));
+/// assert_eq!(state.axis(GamepadAxis::LeftY), 0.5);
+/// assert_eq!(state.axis(GamepadAxis::RightX), 0.0);
+///
+/// state.apply(&GamepadEvent::Disconnected);
+/// assert!(!state.is_connected());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadState {
+    pressed: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+    connected: bool,
+}
+
+impl Default for GamepadState {
+    fn default() -> Self {
+        GamepadState {
+            pressed: HashSet::new(),
+            axes: HashMap::new(),
+            connected: true,
+        }
+    }
+}
+
+impl GamepadState {
+    /// Updates the state with `event`. A disconnect releases everything.
+    pub fn apply(&mut self, event: &GamepadEvent) {
+        match *event {
+            GamepadEvent::Button { button, pressed: true } => {
+                self.pressed.insert(button);
+            }
+            GamepadEvent::Button { button, pressed: false } => {
+                self.pressed.remove(&button);
+            }
+            GamepadEvent::Axis { axis, value } => {
+                self.axes.insert(axis, value);
+            }
+            GamepadEvent::Disconnected => {
+                self.pressed.clear();
+                self.axes.clear();
+                self.connected = false;
+            }
+        }
+    }
+
+    /// Returns `true` while `button` is held down.
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Returns the position of `axis`, 0 if it has not moved yet.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    /// Returns `false` once the gamepad has been unplugged.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+}
+
+#[cfg(all(feature = "gamepad", target_os = "linux"))]
+mod gamepad;
+
+#[cfg(all(feature = "gamepad", target_os = "linux"))]
+pub use gamepad::Gamepad;
@@ -0,0 +1,259 @@
+use super::{GamepadAxis, GamepadButton, GamepadEvent, GamepadState};
+use evdev::{Device, EventSummary, KeyCode};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// How long the reading thread waits for input before checking whether it should stop.
+const POLL_TIMEOUT_MS: libc::c_int = 100;
+
+/// A gamepad or joystick read through Linux `evdev`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::events::{self, Event};
+/// use uptechstar_rs::input::{Gamepad, GamepadAxis, GamepadButton, GamepadEvent};
+///
+/// let mut pad = Gamepad::find().unwrap().with_deadzone(0.1);
+/// events::global().forward(pad.subscribe());
+/// pad.start();
+///
+/// for event in events::subscribe() {
+///     match event {
+///         Event::Gamepad(GamepadEvent::Button { button: GamepadButton::Start, pressed: true }) => break,
+///         Event::Gamepad(GamepadEvent::Axis { .. }) => {
+///             let state = pad.state();
+///             let (speed, turn) = (state.axis(GamepadAxis::LeftY), state.axis(GamepadAxis::RightX));
+///             println!("drive {:.2} turn {:.2}", speed, turn);
+///         }
+///         _ => {}
+///     }
+/// }
+/// ```
+pub struct Gamepad {
+    path: PathBuf,
+    name: String,
+    deadzone: Option<f32>,
+    device: Option<Device>,
+    state: Arc<Mutex<GamepadState>>,
+    subscribers: Arc<Mutex<Vec<Sender<GamepadEvent>>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Gamepad {
+    /// Opens the input device at `path`, such as `/dev/input/event3`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let device = Device::open(&path)?;
+        let name = device.name().unwrap_or("unknown gamepad").to_string();
+
+        Ok(Gamepad {
+            path,
+            name,
+            deadzone: None,
+            device: Some(device),
+            state: Arc::new(Mutex::new(GamepadState::default())),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        })
+    }
+
+    /// Opens the first input device that has gamepad buttons.
+    ///
+    /// # Errors
+    ///
+    /// [`ErrorKind::NotFound`] if no gamepad is plugged in or readable.
+    pub fn find() -> io::Result<Self> {
+        for (path, device) in evdev::enumerate() {
+            let is_gamepad = device
+                .supported_keys()
+                .is_some_and(|keys| keys.contains(KeyCode::BTN_SOUTH) || keys.contains(KeyCode::BTN_TRIGGER));
+            if is_gamepad {
+                info!("Found gamepad '{}' at {}", device.name().unwrap_or("unknown"), path.display());
+                return Gamepad::open(path);
+            }
+        }
+        Err(io::Error::new(ErrorKind::NotFound, "No gamepad found in /dev/input"))
+    }
+
+    /// Reports axis positions within `deadzone` of the center as 0, instead of the dead zone
+    /// the device declares.
+    ///
+    /// # Panics
+    ///
+    /// If `deadzone` is not in `0.0..1.0`.
+    pub fn with_deadzone(mut self, deadzone: f32) -> Self {
+        assert!((0.0..1.0).contains(&deadzone), "Gamepad dead zone must be in 0.0..1.0, got {}", deadzone);
+        self.deadzone = Some(deadzone);
+        self
+    }
+
+    /// Returns the device path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the name the device reports, such as `"Xbox Wireless Controller"`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the current buttons and axes.
+    pub fn state(&self) -> GamepadState {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Registers a new subscriber and returns its receiving end.
+    ///
+    /// Subscribing works both before and after [`start`](Gamepad::start).
+    pub fn subscribe(&self) -> Receiver<GamepadEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    /// Returns `true` while the reading thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts reading the device in the background. Does nothing if it is already running or
+    /// if the gamepad was stopped before; open it again to restart.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+        let Some(mut device) = self.device.take() else {
+            warn!("Gamepad '{}' was stopped; open it again to restart", self.name);
+            return self;
+        };
+
+        // Raw axis ranges and the device's own dead zone, by axis code.
+        let ranges: HashMap<u16, (i32, i32, i32)> = match device.get_absinfo() {
+            Ok(axes) => axes
+                .map(|(code, info)| (code.0, (info.minimum(), info.maximum(), info.flat())))
+                .collect(),
+            Err(e) => {
+                warn!("Failed to read the axis ranges of gamepad '{}': {}", self.name, e);
+                HashMap::new()
+            }
+        };
+        if let Err(e) = device.set_nonblocking(true) {
+            warn!("Failed to make gamepad '{}' non-blocking: {}", self.name, e);
+        }
+
+        info!("Reading gamepad '{}' at {}", self.name, self.path.display());
+        self.running.store(true, Ordering::Release);
+
+        let deadzone = self.deadzone;
+        let state = Arc::clone(&self.state);
+        let subscribers = Arc::clone(&self.subscribers);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-gamepad".into())
+                .spawn(move || {
+                    let publish = |event: GamepadEvent| {
+                        state.lock().unwrap_or_else(|e| e.into_inner()).apply(&event);
+                        subscribers
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .retain(|subscriber| subscriber.send(event).is_ok());
+                    };
+
+                    while running.load(Ordering::Acquire) {
+                        let mut poll = libc::pollfd {
+                            fd: device.as_raw_fd(),
+                            events: libc::POLLIN,
+                            revents: 0,
+                        };
+                        if unsafe { libc::poll(&mut poll, 1, POLL_TIMEOUT_MS) } <= 0 {
+                            continue;
+                        }
+
+                        let events = match device.fetch_events() {
+                            Ok(events) => events,
+                            Err(e) if e.kind() == ErrorKind::WouldBlock => continue,
+                            Err(e) => {
+                                warn!("Gamepad disconnected: {}", e);
+                                publish(GamepadEvent::Disconnected);
+                                break;
+                            }
+                        };
+
+                        for event in events {
+                            let event = match event.destructure() {
+                                // Value 2 is key repeat, which gamepads do not need.
+                                EventSummary::Key(_, code, value @ (0 | 1)) => GamepadEvent::Button {
+                                    button: GamepadButton::from_code(code.0),
+                                    pressed: value == 1,
+                                },
+                                EventSummary::AbsoluteAxis(_, code, value) => {
+                                    let axis = GamepadAxis::from_code(code.0);
+                                    let (min, max, flat) = ranges.get(&code.0).copied().unwrap_or((-1, 1, 0));
+                                    let deadzone = deadzone.unwrap_or(flat as f32 / (max - min).max(1) as f32 * 2.0);
+                                    GamepadEvent::Axis {
+                                        axis,
+                                        value: axis.normalize(value, min, max, deadzone),
+                                    }
+                                }
+                                _ => continue,
+                            };
+                            debug!("Gamepad event {:?}", event);
+                            publish(event);
+                        }
+                    }
+
+                    running.store(false, Ordering::Release);
+                    debug!("Gamepad thread exited");
+                })
+                .expect("Failed to spawn gamepad thread"),
+        );
+
+        self
+    }
+
+    /// Stops the reading thread and waits for it to exit.
+    ///
+    /// Stopping closes the device and all current subscriptions.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Gamepad '{}' stopped", self.name);
+        }
+
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+
+        self
+    }
+}
+
+impl Drop for Gamepad {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -71,6 +71,61 @@
 //! }
 //! ```
 //!
+//! ## Cargo Features
+//!
+//! All optional functionality is disabled by default:
+//!
+//! - **`async`**: Tokio-friendly sensor streams ([`mpu::accel_stream()`](mpu),
+//!   [`adc_io::adc_stream()`](adc_io)), [`display::AsyncScreen`](display) for submitting
+//!   drawing work to a dedicated worker thread, and a `tokio::sync::watch` receiver on
+//!   [`telemetry::StateHub`]
+//! - **`bindgen`**: Generate declarations from `libuptech.h` at build time (set
+//!   `UPTECH_HEADER` to the header of the Uptech SDK, which is not part of this repository;
+//!   requires libclang) and check every hand-written FFI signature against them.
+//!   `UPTECH_BINDINGS` may point to pre-generated bindings instead
+//! - **`capi`**: `capi` module exporting board init, ADC/IO/MPU reads, text drawing and
+//!   background sampling to C and C++, declared in the generated `include/uptechstar.h`
+//!   (implies `config`)
+//! - **`cli`**: The `uptech-cli` binary for reading the ADC, IO and MPU, driving IO pins and
+//!   LEDs, showing an LCD test pattern and running a self-test from the shell
+//! - **`config`**: `board::BoardConfig::from_file()` for loading board setups from TOML, and
+//!   `settings::Settings::open()` for persisting settings to a TOML file (implies `serde`)
+//! - **`embedded-hal`**: `embedded-hal` traits for running platform-agnostic drivers on the
+//!   board: `I2c` for `i2c::I2cBus`, `InputPin`/`OutputPin` for `adc_io::IoPin` and the shift
+//!   register pins, `SpiDevice` for `adc_io::SoftSpi`, and the 0.2 `OneShot` ADC trait for
+//!   `adc_io::AdcPin`
+//! - **`fft`**: `mpu::analysis::spectrum()` and `dominant_frequency()` for vibration spectra,
+//!   using `rustfft`
+//! - **`gamepad`**: `input::Gamepad` for reading USB gamepads through Linux `evdev` and
+//!   forwarding their buttons and axes to the event bus
+//! - **`http`**: `telemetry::serve_http()` JSON endpoints for ADC, IO and MPU data, plus
+//!   `POST` endpoints for setting IO levels and LED colors
+//! - **`mqtt`**: `telemetry::mqtt` publisher sending ADC, IO and attitude JSON to an MQTT broker
+//! - **`python`**: The `uptechstar` Python extension module with `Board`, `Screen`, ADC/IO and
+//!   MPU functions, built with `maturin build --release` (implies `config`)
+//! - **`raw`**: `raw` module with `unsafe` bindings for every supported `libuptech.so` export,
+//!   for functions the safe API does not wrap yet, and `mpu::read_register()` /
+//!   `mpu::write_register()` for configuring the MPU6500 directly
+//! - **`ros2`**: `ros2` module converting MPU samples to `sensor_msgs/Imu` fields and ADC frames
+//!   to `Int32MultiArray` data, and applying LED and display commands, used by the `rclrs`
+//!   bridge node in `ros2/uptech_bridge`
+//! - **`rt`**: `rt` module for moving threads to `SCHED_FIFO` real-time scheduling on Linux,
+//!   and `with_realtime_priority()` on the sampler and the rate scheduler
+//! - **`serial`**: `serial` module for talking to motor controllers and other devices on
+//!   `/dev/ttyS*` and `/dev/ttyUSB*` ports, using `serialport`
+//! - **`scope`**: The `uptech-scope` binary, which plots channels received from
+//!   `telemetry::udp` or `telemetry::websocket` in the terminal (implies `websocket`)
+//! - **`serde`**: `Serialize`/`Deserialize` for sample and configuration types such as
+//!   `AdcFrame`, `MpuSample`, `BoardState`, `Timestamped`, `Reading` and `LogFormat`
+//! - **`system-lib`**: Do not embed `libuptech.so`; load it from `UPTECH_LIB_PATH` or the
+//!   system library path at runtime instead
+//! - **`tracing`**: `TRACE`-level `tracing` spans around every FFI call, with the subsystem,
+//!   function, duration and return code, for profiling with `tracing-subscriber`
+//! - **`uom`**: `mpu::quantities` reading acceleration, angular rate and attitude as typed
+//!   `uom` quantities
+//! - **`websocket`**: `telemetry::websocket` server pushing live board state to browser
+//!   dashboards as JSON or CBOR frames
+//!
 //! ## Module Overview
 //!
 //! ### [`adc_io`] - ADC and GPIO Operations
@@ -83,9 +138,25 @@
 //!
 //! Key functions:
 //! - [`adc_io::adc_open()`] / [`adc_io::adc_close()`] - System initialization
+//! - [`adc_io::AdcIo`] - Clonable handle that closes ADC-IO when the last clone is dropped
 //! - [`adc_io::adc_get_all_channels()`] - Read all ADC channels
+//! - [`adc_io::AdcFrame`] / [`adc_io::IoFrame`] - Fixed-size, `Copy` snapshots of the ADC channels and IO levels
 //! - [`adc_io::set_all_io_levels()`] - Control GPIO output levels
 //! - [`adc_io::set_io_mode()`] - Configure I/O pin modes
+//! - [`adc_io::set_io_levels_with_mask()`] - Change some output pins without touching the rest
+//! - [`adc_io::Pin`] - Common API of onboard ([`adc_io::IoPin`]) and expansion pins
+//! - [`adc_io::ShiftOut`] / [`adc_io::ShiftIn`] - 74HC595/74HC165 shift register expansion
+//! - [`adc_io::ParallelBus`] - Adjacent output pins written as one N-bit value, with an optional strobe pin
+//! - [`adc_io::Stepper`] - Four-pin stepper motor driver with speed ramping
+//! - [`adc_io::SoftPwm`] - Software PWM on an IO pin
+//! - [`adc_io::DcMotor`] - H-bridge DC motor with signed speed and braking
+//! - [`adc_io::IrReceiver`] - NEC infrared remote decoder on an input pin
+//! - [`adc_io::Keypad`] - Debounced row/column keypad matrix scanning
+//! - [`adc_io::Dht`] - DHT11/DHT22 temperature and humidity sensor
+//! - [`adc_io::OneWire`] / [`adc_io::Ds18b20`] - Bit-banged one-wire bus with device search and DS18B20 thermometers
+//! - [`adc_io::SoftSpi`] - Bit-banged SPI master in all four modes
+//! - [`adc_io::SoftUart`] - Software serial port up to 9600 baud with a buffered receive thread
+//! - [`adc_io::sensors`] - Thermistor, photoresistor and voltage divider conversions
 //!
 //! ### [`display`] - LCD Display Control
 //!
@@ -98,6 +169,23 @@
 //!
 //! Key features:
 //! - [`display::Screen`] - Main display interface struct
+//! - [`display::Screen::try_new()`] - Open the LCD, failing if another `Screen` already did
+//! - [`display::Screen::size()`] / [`display::Screen::rotate()`] / [`display::Screen::set_layout()`] - Screen size, changing direction, and UIs laid out for either direction
+//! - [`display::Screen::measure_text()`] / [`display::Screen::chars_per_line()`] - Text size in the active font, for centering and truncation
+//! - [`display::Screen::put_string_fit()`] - A line of text kept within a width by a smaller font or an ellipsis
+//! - [`display::Screen::push_clip()`] / [`display::Screen::save_state()`] - Clip rectangles and a color/font stack for self-contained widgets
+//! - [`display::SharedScreen`] - One screen drawn from several threads, with queued commands batched per refresh
+//! - [`display::RenderThread`] - Render thread owning the screen; control loops queue [`display::DrawOp`]s through a [`display::RenderQueue`] and the LCD refreshes at a fixed cadence
+//! - [`display::Screen::draw_batch()`] - Execute a slice of [`display::DrawOp`]s with one symbol lookup and one statistics update
+//! - [`display::BufferedScreen`] - Double-buffered drawing that only sends changed pixel runs
+//! - [`display::scene`] - Retained-mode node tree that only redraws what changed
+//! - [`display::Pager`] - Named pages with transitions, navigated by button events
+//! - [`display::widgets`] - Scrolling text, number spinners, text input, attitude and heading indicators, ADC bar graphs with optional axes, gridlines and legends, value histograms for picking sensor thresholds, labeled values with units and smoothing, countdowns and stopwatches
+//! - [`display::Icon`] - Battery, Wi-Fi, arrow, warning and media symbols for status bars
+//! - [`display::LedDimmer`] - Dims the onboard LEDs by switching them on and off at a fixed refresh rate
+//! - [`display::set_led_correction()`] - Gamma correction and global brightness for the onboard LEDs
+//! - [`display::NeoPixelStrip`] - WS2812 LED strip over SPI or a bit-banged IO pin
+//! - [`display::Hd44780`] - Character LCD in 4-bit mode on an [`adc_io::ParallelBus`]
 //! - Multiple font sizes and color support
 //! - Hardware-accelerated graphics operations
 //! - Flexible screen orientation control
@@ -111,10 +199,150 @@
 //! - Hardware sensor fusion algorithms
 //!
 //! Key functions:
-//! - [`mpu::mpu6500_open()`] - Initialize MPU6500 with DMP
+//! - [`mpu::mpu6500_open()`] - Initialize MPU6500 with DMP, once until ADC-IO is closed
+//! - [`mpu::mpu6500_try_open()`] - Initialize MPU6500, failing if it already is
 //! - [`mpu::mpu6500_get_accel()`] - Read acceleration data
 //! - [`mpu::mpu6500_get_gyro()`] - Read angular velocity data
 //! - [`mpu::mpu6500_get_attitude()`] - Get computed orientation angles
+//! - [`mpu::wait_data_ready()`] - Block until the MPU6500 has a new sample
+//! - [`mpu::read_fifo()`] - Drain all samples buffered by the DMP, with derived timestamps
+//! - [`mpu::sleep()`] / [`mpu::wake()`] - Duty-cycle the sensors on battery power
+//! - [`mpu::set_mounting()`] - Report readings in the robot's frame for sideways or upside-down boards
+//! - [`mpu::set_calibration()`] / [`mpu::ImuCalibration`] - Accelerometer offset and scale and gyro bias corrections applied to every reading
+//! - [`mpu::zero_yaw()`] - Re-zero the heading, e.g. at the start line
+//! - [`mpu::set_units()`] - Report in m/s², rad/s or radians instead of g, °/s and degrees
+//! - [`mpu::MotionEvents`] - Free-fall, impact and shake events on the event bus
+//! - [`mpu::gestures`] - Tilt, flip, rotation and recorded gestures on the event bus
+//! - [`mpu::DeadReckoning`] - Experimental short-horizon velocity and position estimate
+//! - [`mpu::Pedometer`] - Step count and cadence from the DMP or the accelerometer
+//! - [`mpu::analysis`] - RMS, peak detection and (with `fft`) spectra for vibration monitoring
+//!
+//! ### [`board`] - Board Setup
+//!
+//! - [`board::BoardConfig`] - Screen, MPU, IO and named ADC channel setup in one place
+//! - [`board::Board::init()`] - Apply a configuration and own the initialized hardware
+//! - [`board::Board::low_power()`] - Put the MPU and screen to sleep between samples
+//! - [`board::Board::show_boot_screen()`] - Versions, ADC, MPU and IO diagnostics on the screen at startup
+//! - [`UptechError`] - Error type of the higher-level APIs
+//!
+//! ### [`i2c`] - External I2C Devices
+//!
+//! - [`i2c::read_registers()`] / [`i2c::write_registers()`] - Register transfers to other devices on the MPU6500's bus
+//! - [`i2c::I2cBus`] - The bus as an `embedded-hal` I2C implementation (with `embedded-hal`)
+//!
+//! ### [`extern_lib`] - Library Compatibility
+//!
+//! - [`extern_lib::capabilities()`] - Report which function groups the loaded library exports
+//! - [`extern_lib::library_version()`] - Identify the loaded build by version string or SHA-256
+//!
+//! ### [`backend`] - Hardware Backends
+//!
+//! All ADC, IO and MPU wrappers dispatch through a pluggable [`backend::Backend`]:
+//! - [`backend::FfiBackend`] - The default, forwarding to `libuptech.so`
+//! - [`backend::set_backend()`] - Swap in a recorded or simulated board at runtime
+//!
+//! ### [`replay`] - Session Recording and Replay
+//!
+//! - [`replay::Recorder`] - Capture timestamped ADC, IO and MPU samples to a binary file
+//! - [`replay::ReplayBackend`] - Feed a captured session back through the same APIs
+//!
+//! ### [`diagnostics`] - Self-Test
+//!
+//! - [`diagnostics::run_self_test()`] - Check the ADC, IO, MPU, LCD and LEDs of a board and
+//!   show the results on screen
+//! - [`diagnostics::SelfTest`] - Configure IO loopback jumpers and which parts to exercise
+//!
+//! ### [`crash`] - Postmortem Dumps
+//!
+//! - [`crash::CrashRecorder`] - Keep the last board states and write them with the health
+//!   report, library capabilities and configuration on demand or on panic
+//! - [`crash::CrashDump`] - One dump, written as plain text
+//!
+//! ### [`shutdown`] - Graceful Exit
+//!
+//! - [`run()`] - Run an application until Ctrl-C or `SIGTERM`, then turn the LEDs off, blank
+//!   and close the LCD and close ADC-IO
+//! - [`ShutdownToken`] - The flag threads poll to stop in time
+//!
+//! ### [`watchdog`] - Hang Protection
+//!
+//! - [`watchdog::Watchdog`] - Feed `/dev/watchdog` only while heartbeats, the state hub and the
+//!   subsystem health counters look good, so a hung control program resets the board
+//! - [`watchdog::Heartbeat`] - A liveness signal beaten by the control loop
+//!
+//! ### [`retry`] and [`health`] - Transient Failures
+//!
+//! - [`retry::RetryPolicy`] - Attempts and backoff for MPU and ADC reads
+//! - [`retry::set_policy()`] / [`retry::with_policy()`] - Process-wide and per-call policies
+//! - [`health::report()`] - Consecutive and total failures per subsystem
+//! - [`health::set_recovery_threshold()`] - Re-initialize a subsystem that keeps failing, restoring
+//!   the MPU ranges, with `SensorLost`/`SensorRecovered` events
+//!
+//! ### [`stats`] - FFI Metrics
+//!
+//! - [`stats::snapshot()`] - Calls, errors and latency histogram per C function
+//! - [`log_limit::set_interval()`] - How often the same hardware failure may be logged
+//! - [`set_log_level()`] - Log verbosity of the ADC/IO, MPU and display modules
+//!
+//! ### [`sampler`], [`logging`] and [`daq`] - Data Acquisition
+//!
+//! - [`sampler::Sampler`] - One background thread polling ADC, IO and MPU at a fixed rate
+//! - [`logging::SensorLogger`] - Append sampler readings to rotating CSV, JSON-Lines or binary frame files
+//! - [`daq::Session`] - A turnkey datalogger: selected sources, a rate, start/stop triggers or a duration, and a CSV, binary frame, UDP or channel sink, with pause and resume
+//!
+//! ### [`scheduler`] - Control Loops
+//!
+//! - [`scheduler::RateScheduler`] - Run closures at fixed rates with overrun and jitter statistics
+//! - `rt::promote_current_thread()` - `SCHED_FIFO` priority for control loops (`rt` feature)
+//!
+//! ### [`events`] - Event Bus
+//!
+//! - [`events::Event`] - Button, threshold, tap, motion, menu, keypad, remote, gamepad and timer events
+//! - [`events::publish()`] / [`events::subscribe()`] - The process-wide bus
+//! - [`events::EventBus::forward()`] - Connect an input driver's channel to a bus
+//! - [`input::GamepadEvent`] / [`input::GamepadState`] - USB gamepads, read by `input::Gamepad`
+//!   (`gamepad` feature)
+//!
+//! ### [`settings`] - Tuning Parameters
+//!
+//! - [`settings::Key`] - A named, typed parameter with a default and an optional range
+//! - [`settings::Settings`] - Current values with change events and (with `config`) a TOML file
+//! - [`settings::SettingsEditor`] - A pager page for editing the values with three buttons
+//!
+//! ### [`calibration`] - Guided Calibration
+//!
+//! - [`calibration::LineCalibration`] / [`calibration::LineCalibrationWizard`] - Line sensor thresholds from white and black readings, stored as settings
+//! - [`calibration::ImuCalibrator`] / [`calibration::ImuCalibrationWizard`] - Six-position accelerometer and gyro bias calibration, stored as settings
+//!
+//! ### [`telemetry`] - State Publication
+//!
+//! - [`telemetry::StateHub`] - Share one sampling thread between many consumers
+//! - [`telemetry::BoardState`] - The latest ADC, IO, MPU and GPS snapshot
+//! - [`telemetry::TelemetryFrame`] - Compact binary snapshot shared by logs, replay and
+//!   network transports
+//! - [`telemetry::udp`] - Broadcast frames on the local network and receive them on a laptop
+//! - `telemetry::shm` - Latest frame in `/dev/shm` for dashboards in other processes (Linux)
+//!
+//! ### [`gps`] - Positioning
+//!
+//! - [`gps::Gps`] - Read an NMEA receiver from a serial device or [`adc_io::SoftUart`]
+//! - [`gps::NmeaParser`] - Decode `GGA` and `RMC` sentences into a [`gps::Fix`]
+//!
+//! ### `serial` - Serial Ports
+//!
+//! With the `serial` feature, `serial::SerialLink` opens a system serial port, splits the
+//! received bytes into lines, delimited or length-prefixed frames and publishes them on the
+//! event bus.
+//!
+//! ### [`daemon`] - Multi-Process Access
+//!
+//! - [`daemon::Daemon`] - Own the board and serve requests over a Unix domain socket
+//! - [`daemon::DaemonClient`] - A backend that forwards every call to the daemon
+//!
+//! ### `raw` - Unchecked Bindings
+//!
+//! With the `raw` feature, `raw` exposes the C functions of `libuptech.so` one-to-one as
+//! `unsafe fn`s, including DMP tap and pedometer control, register access and bus servos.
 //!
 //! ## Safety Considerations
 //!
@@ -134,6 +362,10 @@
 //! - **Debug**: Detailed operational information
 //! - **Error**: Hardware failures and communication issues
 //!
+//! The ADC/IO, MPU and display modules log to the targets `uptech::adc`, `uptech::mpu` and
+//! `uptech::display`, each of which can be quieted with [`set_log_level()`]. Repeated hardware
+//! failures are [rate limited](log_limit).
+//!
 //! Enable logging in your application by configuring a logging implementation that works
 //! with the `log` crate:
 //!
@@ -179,7 +411,52 @@
 //!
 //! This project is licensed under the MIT License - see the LICENSE file for details.
 
-mod extern_lib;
 pub mod adc_io;
+pub mod backend;
+pub mod board;
+pub mod calibration;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(unix)]
+pub mod crash;
+#[cfg(unix)]
+pub mod daemon;
+pub mod daq;
+pub mod diagnostics;
 pub mod display;
-pub mod mpu;
\ No newline at end of file
+mod error;
+#[cfg(feature = "bindgen")]
+mod ffi;
+pub mod events;
+pub mod extern_lib;
+pub mod gps;
+pub mod health;
+pub mod i2c;
+pub mod input;
+pub mod log_level;
+pub mod log_limit;
+pub mod logging;
+pub mod mpu;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "raw")]
+pub mod raw;
+mod registry;
+pub mod replay;
+pub mod retry;
+#[cfg(feature = "ros2")]
+pub mod ros2;
+#[cfg(all(feature = "rt", target_os = "linux"))]
+pub mod rt;
+pub mod sampler;
+pub mod scheduler;
+#[cfg(feature = "serial")]
+pub mod serial;
+pub mod settings;
+pub mod shutdown;
+pub mod stats;
+pub mod telemetry;
+pub mod watchdog;
+pub use error::{Result, UptechError};
+pub use log_level::{LogTarget, set_log_level};
+pub use shutdown::{ShutdownToken, run};
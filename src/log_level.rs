@@ -0,0 +1,130 @@
+//! Log verbosity per subsystem.
+//!
+//! Messages of the [`adc_io`](crate::adc_io), [`mpu`](crate::mpu) and
+//! [`display`](crate::display) modules are logged with the targets `uptech::adc`,
+//! `uptech::mpu` and `uptech::display`, so loggers that filter by target can tell them apart,
+//! e.g. `RUST_LOG=info,uptech::display=warn` with `env_logger`.
+//!
+//! [`set_log_level`] filters them inside this crate instead, independent of the logger and of
+//! any filter it was configured with.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use log::LevelFilter;
+//! use uptechstar_rs::{LogTarget, set_log_level};
+//!
+//! // Keep MPU errors, but stop logging every LCD call.
+//! set_log_level(LogTarget::Display, LevelFilter::Warn);
+//! assert_eq!(uptechstar_rs::log_level::log_level(LogTarget::Display), LevelFilter::Warn);
+//! assert_eq!(uptechstar_rs::log_level::log_level(LogTarget::Mpu), LevelFilter::Trace);
+//! ```
+
+use log::{Level, LevelFilter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A group of this crate's log messages with its own log target and level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogTarget {
+    /// ADC reads, IO pins and the drivers built on them, target `uptech::adc`.
+    Adc,
+    /// The MPU6500 and motion processing, target `uptech::mpu`.
+    Mpu,
+    /// The LCD, LEDs and display helpers, target `uptech::display`.
+    Display,
+}
+
+impl LogTarget {
+    /// Returns the `log` target of the messages, e.g. `"uptech::adc"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LogTarget::Adc => "uptech::adc",
+            LogTarget::Mpu => "uptech::mpu",
+            LogTarget::Display => "uptech::display",
+        }
+    }
+}
+
+static LEVELS: [AtomicUsize; 3] = [
+    AtomicUsize::new(LevelFilter::Trace as usize),
+    AtomicUsize::new(LevelFilter::Trace as usize),
+    AtomicUsize::new(LevelFilter::Trace as usize),
+];
+
+/// Sets the most verbose level logged for `target` and returns the previous one.
+/// [`LevelFilter::Off`] silences the target. The default, [`LevelFilter::Trace`], leaves the
+/// filtering to the logger.
+pub fn set_log_level(target: LogTarget, level: LevelFilter) -> LevelFilter {
+    from_usize(LEVELS[target as usize].swap(level as usize, Ordering::Relaxed))
+}
+
+/// Returns the most verbose level logged for `target`.
+pub fn log_level(target: LogTarget) -> LevelFilter {
+    from_usize(LEVELS[target as usize].load(Ordering::Relaxed))
+}
+
+/// Returns `true` if a message at `level` for `target` passes this crate's filter.
+pub(crate) fn enabled(target: LogTarget, level: Level) -> bool {
+    level as usize <= LEVELS[target as usize].load(Ordering::Relaxed)
+}
+
+fn from_usize(level: usize) -> LevelFilter {
+    LevelFilter::iter().nth(level).unwrap_or(LevelFilter::Trace)
+}
+
+/// Logs through `log` with the target and level filter of a [`LogTarget`].
+macro_rules! log_to {
+    ($target:expr, $level:expr, $($arg:tt)+) => {{
+        let target: $crate::log_level::LogTarget = $target;
+        let level: log::Level = $level;
+        if $crate::log_level::enabled(target, level) {
+            log::log!(target: target.name(), level, $($arg)+);
+        }
+    }};
+}
+
+pub(crate) use log_to;
+
+/// Defines `trace!` to `error!` replacements logging to one [`LogTarget`]. `$d` is a literal
+/// `$`, which nested macro definitions need for their own metavariables.
+macro_rules! target_macros {
+    ($d:tt $target:ident) => {
+        macro_rules! trace {
+            ($d($d arg:tt)+) => { $crate::log_level::log_to!($crate::log_level::LogTarget::$target, log::Level::Trace, $d($d arg)+) };
+        }
+        macro_rules! debug {
+            ($d($d arg:tt)+) => { $crate::log_level::log_to!($crate::log_level::LogTarget::$target, log::Level::Debug, $d($d arg)+) };
+        }
+        macro_rules! info {
+            ($d($d arg:tt)+) => { $crate::log_level::log_to!($crate::log_level::LogTarget::$target, log::Level::Info, $d($d arg)+) };
+        }
+        macro_rules! warning {
+            ($d($d arg:tt)+) => { $crate::log_level::log_to!($crate::log_level::LogTarget::$target, log::Level::Warn, $d($d arg)+) };
+        }
+        macro_rules! error {
+            ($d($d arg:tt)+) => { $crate::log_level::log_to!($crate::log_level::LogTarget::$target, log::Level::Error, $d($d arg)+) };
+        }
+
+        // A plain `warn` would be ambiguous with the built-in `#[warn]` attribute here.
+        pub(crate) use {debug, error, info, trace, warning as warn};
+    };
+}
+
+/// Logging macros for the [`adc_io`](crate::adc_io) modules.
+#[allow(unused_imports, unused_macros)]
+pub(crate) mod adc {
+    target_macros!($ Adc);
+}
+
+/// Logging macros for the [`mpu`](crate::mpu) modules.
+#[allow(unused_imports, unused_macros)]
+pub(crate) mod mpu {
+    target_macros!($ Mpu);
+}
+
+/// Logging macros for the [`display`](crate::display) modules.
+#[allow(unused_imports, unused_macros)]
+pub(crate) mod display {
+    target_macros!($ Display);
+}
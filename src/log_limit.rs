@@ -0,0 +1,100 @@
+//! Rate limiting for repeated hardware error messages.
+//!
+//! When a sensor drops out, a sampler polling it at 1kHz would log the same failure a thousand
+//! times per second. The wrappers in [`adc_io`](crate::adc_io) and [`mpu`](crate::mpu) therefore
+//! log a failure of a C function with a given status code at most once per
+//! [interval](set_interval). Identical failures in between are counted and
+//! reported with the next message that gets through.
+//!
+//! The messages carry the structured fields `subsystem` (`adc_io`, `mpu` or `display`),
+//! `function`, `code` and, after suppressions, `suppressed`, for loggers that support the `log`
+//! crate's key-values.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use uptechstar_rs::log_limit;
+//!
+//! // Log every failure, e.g. while debugging wiring on the bench.
+//! let previous = log_limit::set_interval(Duration::ZERO);
+//! assert_eq!(previous, Duration::from_secs(1));
+//! assert_eq!(log_limit::interval(), Duration::ZERO);
+//! ```
+
+use crate::log_level::{LogTarget, log_to};
+use log::Level;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+struct Limiter {
+    interval: Duration,
+    /// When each `(function, code)` pair was last logged and how often it was suppressed since.
+    seen: HashMap<(&'static str, i32), (Instant, u64)>,
+    suppressed: u64,
+}
+
+static LIMITER: Lazy<Mutex<Limiter>> = Lazy::new(|| {
+    Mutex::new(Limiter {
+        interval: Duration::from_secs(1),
+        seen: HashMap::new(),
+        suppressed: 0,
+    })
+});
+
+/// Sets the minimum time between two messages about the same failure and returns the previous
+/// interval. [`Duration::ZERO`] disables rate limiting. The default is one second.
+pub fn set_interval(interval: Duration) -> Duration {
+    std::mem::replace(&mut LIMITER.lock().unwrap_or_else(|e| e.into_inner()).interval, interval)
+}
+
+/// Returns the minimum time between two messages about the same failure.
+pub fn interval() -> Duration {
+    LIMITER.lock().unwrap_or_else(|e| e.into_inner()).interval
+}
+
+/// Returns how many error messages have been suppressed since the program started.
+pub fn suppressed() -> u64 {
+    LIMITER.lock().unwrap_or_else(|e| e.into_inner()).suppressed
+}
+
+/// Logs that the C `function` failed with `code`, unless the same failure was logged less than
+/// an interval ago.
+pub(crate) fn ffi_error(function: &'static str, code: i32, message: fmt::Arguments<'_>) {
+    let suppressed = {
+        let mut limiter = LIMITER.lock().unwrap_or_else(|e| e.into_inner());
+        let interval = limiter.interval;
+        let now = Instant::now();
+
+        match limiter.seen.get_mut(&(function, code)) {
+            Some((last, count)) if now.duration_since(*last) < interval => {
+                *count += 1;
+                limiter.suppressed += 1;
+                return;
+            }
+            Some((last, count)) => {
+                *last = now;
+                std::mem::take(count)
+            }
+            None => {
+                limiter.seen.insert((function, code), (now, 0));
+                0
+            }
+        }
+    };
+
+    let subsystem = crate::stats::subsystem(function);
+    let target = match subsystem {
+        "adc_io" => LogTarget::Adc,
+        "mpu" => LogTarget::Mpu,
+        _ => LogTarget::Display,
+    };
+    if suppressed == 0 {
+        log_to!(target, Level::Error, subsystem, function, code; "{}", message);
+    } else {
+        log_to!(target, Level::Error, subsystem, function, code, suppressed; "{} ({} identical errors suppressed)", message, suppressed);
+    }
+}
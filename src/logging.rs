@@ -0,0 +1,357 @@
+//! CSV, JSON-Lines and binary data logging for sensor readings.
+//!
+//! [`SensorLogger`] turns the [`Reading`] stream of a [`Sampler`] into rows of a CSV,
+//! JSON-Lines or [`TelemetryFrame`] file. Every row carries the monotonic sampler timestamp in microseconds, files
+//! can be rotated once they reach a size limit, and flushing is configurable through
+//! [`FlushPolicy`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use uptechstar_rs::logging::{FlushPolicy, LogFormat, SensorLogger};
+//! use uptechstar_rs::sampler::Sampler;
+//!
+//! let mut sampler = Sampler::new(50.0).with_adc(true).with_mpu(true);
+//!
+//! let logger = SensorLogger::create("run.csv", LogFormat::Csv)
+//!     .unwrap()
+//!     .with_max_file_size(10 * 1024 * 1024, 5)
+//!     .with_flush_policy(FlushPolicy::Interval(Duration::from_secs(1)))
+//!     .spawn(&sampler);
+//!
+//! sampler.start();
+//! std::thread::sleep(Duration::from_secs(60));
+//! sampler.stop();
+//!
+//! logger.join().unwrap();
+//! ```
+
+use crate::adc_io::IoFrame;
+use crate::sampler::{Reading, Sampler, Timestamped};
+use crate::telemetry::TelemetryFrame;
+use log::{error, info};
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Header row written at the top of every CSV file.
+const CSV_HEADER: &str = "timestamp_us,source,\
+adc0,adc1,adc2,adc3,adc4,adc5,adc6,adc7,adc8,adc9,\
+io,\
+accel_x,accel_y,accel_z,gyro_x,gyro_y,gyro_z,pitch,roll,yaw";
+
+/// Output file format of a [`SensorLogger`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogFormat {
+    /// Comma-separated values with a fixed header. Columns not provided by a row's source are
+    /// left empty.
+    Csv,
+    /// One JSON object per line, e.g. `{"timestamp_us":1200,"source":"io","io":5}`.
+    JsonLines,
+    /// One binary [`TelemetryFrame`] per reading, readable with
+    /// [`telemetry::frame::read_frames`](crate::telemetry::frame::read_frames).
+    Frames,
+}
+
+/// When buffered rows are written through to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlushPolicy {
+    /// Flush after every row.
+    EveryRow,
+    /// Flush after every `n` rows.
+    EveryRows(usize),
+    /// Flush when at least the given time has passed since the last flush.
+    Interval(Duration),
+    /// Only flush on [`SensorLogger::flush`], rotation and drop.
+    Manual,
+}
+
+/// Appends sensor readings to a CSV or JSON-Lines file.
+pub struct SensorLogger {
+    path: PathBuf,
+    format: LogFormat,
+    writer: BufWriter<File>,
+    written: u64,
+    max_file_size: Option<u64>,
+    max_files: usize,
+    flush_policy: FlushPolicy,
+    rows_since_flush: usize,
+    last_flush: Instant,
+    line: String,
+    frame: Vec<u8>,
+}
+
+impl SensorLogger {
+    /// Creates (or truncates) the log file at `path`.
+    ///
+    /// The default configuration never rotates and flushes every 100 rows.
+    pub fn create<P: AsRef<Path>>(path: P, format: LogFormat) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        info!("Logging sensor data to {} as {:?}", path.display(), format);
+
+        let mut logger = SensorLogger {
+            writer: BufWriter::new(File::create(&path)?),
+            path,
+            format,
+            written: 0,
+            max_file_size: None,
+            max_files: 1,
+            flush_policy: FlushPolicy::EveryRows(100),
+            rows_since_flush: 0,
+            last_flush: Instant::now(),
+            line: String::with_capacity(256),
+            frame: Vec::new(),
+        };
+        logger.write_header()?;
+
+        Ok(logger)
+    }
+
+    /// Enables size-based rotation.
+    ///
+    /// When a row would grow the file beyond `max_bytes`, the file is renamed to `<path>.1`
+    /// (shifting older files to `<path>.2` and so on, keeping at most `max_files` rotated
+    /// files) and a fresh file is started.
+    pub fn with_max_file_size(mut self, max_bytes: u64, max_files: usize) -> Self {
+        self.max_file_size = Some(max_bytes);
+        self.max_files = max_files.max(1);
+        self
+    }
+
+    /// Sets the flush policy.
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// Returns the path of the active log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends one reading as a row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use uptechstar_rs::adc_io::IoFrame;
+    /// use uptechstar_rs::logging::{LogFormat, SensorLogger};
+    /// use uptechstar_rs::sampler::{Reading, Timestamped};
+    ///
+    /// let dir = tempfile::tempdir().unwrap();
+    /// let path = dir.path().join("io.jsonl");
+    ///
+    /// let mut logger = SensorLogger::create(&path, LogFormat::JsonLines).unwrap();
+    /// logger
+    ///     .write(&Timestamped { timestamp: Duration::from_micros(1200), value: Reading::Io(IoFrame(5)) })
+    ///     .unwrap();
+    /// logger.flush().unwrap();
+    ///
+    /// let contents = std::fs::read_to_string(&path).unwrap();
+    /// assert_eq!(contents, "{\"timestamp_us\":1200,\"source\":\"io\",\"io\":5}\n");
+    /// ```
+    pub fn write(&mut self, reading: &Timestamped<Reading>) -> io::Result<()> {
+        self.line.clear();
+        self.frame.clear();
+        match self.format {
+            LogFormat::Csv => format_csv(&mut self.line, reading),
+            LogFormat::JsonLines => format_json(&mut self.line, reading),
+            LogFormat::Frames => TelemetryFrame::from(reading).encode_into(&mut self.frame),
+        }
+        if self.format != LogFormat::Frames {
+            self.line.push('\n');
+        }
+        let len = (self.line.len() + self.frame.len()) as u64;
+
+        if let Some(max) = self.max_file_size
+            && self.written > 0
+            && self.written + len > max
+        {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(self.line.as_bytes())?;
+        self.writer.write_all(&self.frame)?;
+        self.written += len;
+        self.rows_since_flush += 1;
+
+        let due = match self.flush_policy {
+            FlushPolicy::EveryRow => true,
+            FlushPolicy::EveryRows(n) => self.rows_since_flush >= n,
+            FlushPolicy::Interval(interval) => self.last_flush.elapsed() >= interval,
+            FlushPolicy::Manual => false,
+        };
+        if due {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes all buffered rows to disk.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.rows_since_flush = 0;
+        self.last_flush = Instant::now();
+        self.writer.flush()
+    }
+
+    /// Logs every reading received on `readings` until the channel disconnects.
+    pub fn run(mut self, readings: Receiver<Timestamped<Reading>>) -> io::Result<()> {
+        for reading in readings {
+            self.write(&reading)?;
+        }
+        self.flush()
+    }
+
+    /// Subscribes to `sampler` and logs its readings on a dedicated thread.
+    ///
+    /// The thread finishes once the sampler is stopped or dropped; join the returned handle to
+    /// make sure every row has been flushed.
+    pub fn spawn(self, sampler: &Sampler) -> JoinHandle<io::Result<()>> {
+        let readings = sampler.subscribe();
+
+        thread::Builder::new()
+            .name("uptech-logger".into())
+            .spawn(move || {
+                let path = self.path.clone();
+                let result = self.run(readings);
+                if let Err(e) = &result {
+                    error!("Sensor logger for {} failed: {}", path.display(), e);
+                }
+                result
+            })
+            .expect("Failed to spawn logger thread")
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if self.format == LogFormat::Csv {
+            writeln!(self.writer, "{}", CSV_HEADER)?;
+            self.written += CSV_HEADER.len() as u64 + 1;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+
+        let rotated = |index: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", index));
+            PathBuf::from(name)
+        };
+
+        for index in (1..self.max_files).rev() {
+            let from = rotated(index);
+            if from.exists() {
+                fs::rename(&from, rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(1))?;
+
+        info!("Rotated sensor log {}", self.path.display());
+
+        self.writer = BufWriter::new(File::create(&self.path)?);
+        self.written = 0;
+        self.write_header()
+    }
+}
+
+impl Drop for SensorLogger {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+fn source_name(reading: &Reading) -> &'static str {
+    match reading {
+        Reading::Adc(_) => "adc",
+        Reading::Io(_) => "io",
+        Reading::Mpu(_) => "mpu",
+    }
+}
+
+/// Commas for the cells a CSV row leaves empty, sliced to length so rows need no allocation.
+const EMPTY_CELLS: &str = ",,,,,,,,,,,";
+
+fn format_csv(line: &mut String, reading: &Timestamped<Reading>) {
+    let _ = write!(
+        line,
+        "{},{}",
+        reading.timestamp.as_micros(),
+        source_name(&reading.value)
+    );
+
+    match &reading.value {
+        Reading::Adc(frame) => {
+            for value in frame.0 {
+                let _ = write!(line, ",{}", value);
+            }
+            line.push_str(&EMPTY_CELLS[..10]);
+        }
+        Reading::Io(IoFrame(levels)) => {
+            line.push_str(&EMPTY_CELLS[..10]);
+            let _ = write!(line, ",{}", levels);
+            line.push_str(&EMPTY_CELLS[..9]);
+        }
+        Reading::Mpu(sample) => {
+            line.push_str(&EMPTY_CELLS[..11]);
+            for value in sample.accel.iter().chain(&sample.gyro).chain(&sample.attitude) {
+                let _ = write!(line, ",{}", value);
+            }
+        }
+    }
+}
+
+fn format_json(line: &mut String, reading: &Timestamped<Reading>) {
+    fn array<T: Copy + Into<f64>>(line: &mut String, values: &[T]) {
+        line.push('[');
+        for (index, value) in values.iter().enumerate() {
+            if index > 0 {
+                line.push(',');
+            }
+            let value: f64 = (*value).into();
+            if value.is_finite() {
+                let _ = write!(line, "{}", value);
+            } else {
+                // JSON has no representation for NaN or infinities.
+                line.push_str("null");
+            }
+        }
+        line.push(']');
+    }
+
+    let _ = write!(
+        line,
+        "{{\"timestamp_us\":{},\"source\":\"{}\"",
+        reading.timestamp.as_micros(),
+        source_name(&reading.value)
+    );
+
+    match &reading.value {
+        Reading::Adc(frame) => {
+            line.push_str(",\"adc\":");
+            array(line, &frame.0);
+        }
+        Reading::Io(IoFrame(levels)) => {
+            let _ = write!(line, ",\"io\":{}", levels);
+        }
+        Reading::Mpu(sample) => {
+            line.push_str(",\"accel\":");
+            array(line, &sample.accel);
+            line.push_str(",\"gyro\":");
+            array(line, &sample.gyro);
+            line.push_str(",\"attitude\":");
+            array(line, &sample.attitude);
+        }
+    }
+
+    line.push('}');
+}
@@ -1,7 +1,44 @@
-use crate::extern_lib::LIBRARY;
-use libloading::Symbol;
+use crate::backend;
+use crate::error::UptechError;
+use crate::health::{self, Subsystem};
+use crate::log_limit;
+use crate::registry;
+use crate::retry;
 
-use log::{error, info};
+use crate::log_level::mpu::info;
+
+pub mod analysis;
+pub mod gestures;
+mod calibration;
+mod data_ready;
+mod dead_reckoning;
+mod fifo;
+mod heading;
+mod motion;
+mod mounting;
+mod pedometer;
+mod power;
+#[cfg(feature = "uom")]
+pub mod quantities;
+#[cfg(feature = "raw")]
+mod registers;
+mod restore;
+mod units;
+
+pub use calibration::{ImuCalibration, calibration, set_calibration};
+pub use data_ready::{INT_STATUS_DATA_READY, INT_STATUS_DMP, int_status, wait_data_ready};
+pub use dead_reckoning::{DeadReckoning, DriftEstimate, NavState};
+pub use fifo::{fifo_rate, read_fifo, reset_fifo};
+pub use heading::{heading_offset, set_heading_offset, zero_yaw};
+pub use motion::MotionEvents;
+pub use mounting::{Axis, Orientation, mounting, set_mounting};
+pub use pedometer::{Pedometer, StepSource};
+pub use power::{is_awake, sleep, wake};
+#[cfg(feature = "raw")]
+pub use registers::{read_register, update_register, write_register};
+pub use units::{AccelUnit, AngleUnit, AngularRateUnit, Units, set_units, units};
+
+pub(crate) use restore::reapply_settings;
 
 /// Initializes the MPU6500 6-axis motion processing unit with Digital Motion Processor (DMP).
 ///
@@ -50,7 +87,9 @@ use log::{error, info};
 /// # Thread Safety
 ///
 /// This function is thread-safe and can be called from multiple threads simultaneously.
-/// However, it's recommended to initialize the sensor only once during application startup.
+/// The initialization only runs once: later calls return `0` and leave the running sensor and
+/// its configured ranges alone until ADC-IO is closed. Use [`mpu6500_try_open`] to detect a
+/// sensor that is already initialized.
 ///
 /// # Examples
 ///
@@ -78,23 +117,46 @@ use log::{error, info};
 /// // Proceed with sensor operations
 /// ```
 pub fn mpu6500_open() -> i32 {
+    let mut initialized = registry::MPU.lock().unwrap_or_else(|e| e.into_inner());
+    if *initialized {
+        info!("MPU6500 is already initialized, keeping its configuration");
+        return 0;
+    }
+
     info!("Initializing MPU6500 6-axis motion processing unit...");
 
-    unsafe {
-        let mpu6500_dmp_init: Symbol<unsafe extern "C" fn() -> i32> = LIBRARY
-            .get(b"mpu6500_dmp_init")
-            .expect("Failed to load mpu6500_dmp_init function");
+    let result = backend::current().mpu_init();
 
-        let result = mpu6500_dmp_init();
+    if result != 0 {
+        log_limit::ffi_error(
+            "mpu6500_dmp_init",
+            result,
+            format_args!(
+                "Failed to initialize MPU6500. Do check if the channel is opened by calling 'adc_io_open()' and the libuptech.so being loaded properly"
+            ),
+        );
+        return result;
+    }
 
-        if result != 0 {
-            error!("Failed to initialize MPU6500. Do check if the channel is opened by calling 'adc_io_open()' and the libuptech.so being loaded properly");
-            return result;
-        }
+    restore::forget();
+    *initialized = true;
+    info!("MPU6500 initialized successfully with DMP enabled");
+    result
+}
 
-        info!("MPU6500 initialized successfully with DMP enabled");
-        result
+/// Initializes the MPU6500 like [`mpu6500_open`], but fails instead of returning early if it
+/// is already initialized.
+///
+/// # Errors
+///
+/// - [`UptechError::AlreadyInitialized`] if the sensor was initialized before and ADC-IO has
+///   not been closed since.
+/// - [`UptechError::Hardware`] if `mpu6500_dmp_init` fails.
+pub fn mpu6500_try_open() -> crate::Result<()> {
+    if registry::mpu() {
+        return Err(UptechError::AlreadyInitialized("mpu6500"));
     }
+    UptechError::check("mpu6500_dmp_init", mpu6500_open())
 }
 
 /// Retrieves real-time acceleration data from the MPU6500 3-axis accelerometer.
@@ -102,6 +164,8 @@ pub fn mpu6500_open() -> i32 {
 /// This function reads the current acceleration values from the MPU6500's built-in accelerometer
 /// across all three axes (X, Y, Z). The data is automatically calibrated and converted to
 /// floating-point values representing acceleration in units of gravitational force (g).
+/// The axes are those of the robot if a mounting was configured with [`set_mounting`], and
+/// the values are converted if other [`units`] were configured.
 ///
 /// # Parameters
 ///
@@ -131,6 +195,10 @@ pub fn mpu6500_open() -> i32 {
 ///   - ±8g: -8.0 to +8.0g (default)
 ///   - ±16g: -16.0 to +16.0g
 ///
+/// # Retries
+///
+/// Failed reads are retried according to [`retry::policy()`](crate::retry::policy).
+///
 /// # Returns
 ///
 /// - `0` on successful data retrieval
@@ -200,13 +268,24 @@ pub fn mpu6500_open() -> i32 {
 /// }
 /// ```
 pub fn mpu6500_get_accel(accel_data: &mut [f32; 3]) -> i32 {
-    unsafe {
-        let mpu6500_get_accel: Symbol<unsafe extern "C" fn(*mut f32) -> i32> = LIBRARY
-            .get(b"mpu6500_Get_Accel")
-            .expect("Failed to load mpu6500_Get_Accel function");
+    let result = read_accel(accel_data);
+    if result == 0 {
+        *accel_data = units().convert_accel(*accel_data);
+    }
+
+    result
+}
 
-        mpu6500_get_accel(accel_data.as_mut_ptr())
+/// Reads the acceleration in g, in the mounting frame and calibrated but ignoring [`set_units`].
+pub(crate) fn read_accel(accel_data: &mut [f32; 3]) -> i32 {
+    let result = retry::policy().run(|| backend::current().mpu_get_accel(accel_data));
+    health::record(Subsystem::Mpu, result);
+    if result == 0 {
+        mounting::remap_in_place(accel_data);
+        calibration::correct_accel_in_place(accel_data);
     }
+
+    result
 }
 
 /// Retrieves real-time angular velocity data from the MPU6500 3-axis gyroscope.
@@ -214,6 +293,8 @@ pub fn mpu6500_get_accel(accel_data: &mut [f32; 3]) -> i32 {
 /// This function reads the current rotational rates from the MPU6500's built-in gyroscope
 /// across all three axes (X, Y, Z). The data represents angular velocity measurements
 /// in degrees per second, providing precise information about rotational motion.
+/// The axes are those of the robot if a mounting was configured with [`set_mounting`], and
+/// the values are converted if other [`units`] were configured.
 ///
 /// # Parameters
 ///
@@ -243,6 +324,10 @@ pub fn mpu6500_get_accel(accel_data: &mut [f32; 3]) -> i32 {
 ///   - ±1000°/s: -1000 to +1000 degrees per second
 ///   - ±2000°/s: -2000 to +2000 degrees per second (default, full range)
 ///
+/// # Retries
+///
+/// Failed reads are retried according to [`retry::policy()`](crate::retry::policy).
+///
 /// # Returns
 ///
 /// - `0` on successful data retrieval
@@ -334,13 +419,24 @@ pub fn mpu6500_get_accel(accel_data: &mut [f32; 3]) -> i32 {
 /// }
 /// ```
 pub fn mpu6500_get_gyro(gyro_data: &mut [f32; 3]) -> i32 {
-    unsafe {
-        let mpu6500_get_gyro: Symbol<unsafe extern "C" fn(*mut f32) -> i32> = LIBRARY
-            .get(b"mpu6500_Get_Gyro")
-            .expect("Failed to load mpu6500_Get_Gyro function");
+    let result = read_gyro(gyro_data);
+    if result == 0 {
+        *gyro_data = units().convert_gyro(*gyro_data);
+    }
 
-        mpu6500_get_gyro(gyro_data.as_mut_ptr())
+    result
+}
+
+/// Reads the angular rates in °/s, in the mounting frame and calibrated but ignoring [`set_units`].
+pub(crate) fn read_gyro(gyro_data: &mut [f32; 3]) -> i32 {
+    let result = retry::policy().run(|| backend::current().mpu_get_gyro(gyro_data));
+    health::record(Subsystem::Mpu, result);
+    if result == 0 {
+        mounting::remap_in_place(gyro_data);
+        calibration::correct_gyro_in_place(gyro_data);
     }
+
+    result
 }
 
 /// Retrieves real-time attitude data (orientation angles) from the MPU6500 Digital Motion Processor.
@@ -348,7 +444,10 @@ pub fn mpu6500_get_gyro(gyro_data: &mut [f32; 3]) -> i32 {
 /// This function reads the computed attitude angles from the MPU6500's onboard Digital Motion
 /// Processor (DMP), which performs sensor fusion of accelerometer and gyroscope data to provide
 /// accurate 3D orientation information. The DMP eliminates the need for manual sensor fusion
-/// calculations and provides drift-compensated attitude estimates.
+/// calculations and provides drift-compensated attitude estimates. The angles describe the
+/// robot rather than the board if a mounting was configured with [`set_mounting`], and the
+/// yaw is relative to the heading set with [`zero_yaw`] or [`set_heading_offset`]. The angles
+/// are converted if other [`units`] were configured.
 ///
 /// # Parameters
 ///
@@ -391,6 +490,10 @@ pub fn mpu6500_get_gyro(gyro_data: &mut [f32; 3]) -> i32 {
 /// - **Real-time Processing**: Hardware-accelerated calculations at high sample rates
 /// - **Temperature Compensation**: Automatic adjustment for temperature variations
 ///
+/// # Retries
+///
+/// Failed reads are retried according to [`retry::policy()`](crate::retry::policy).
+///
 /// # Returns
 ///
 /// - `0` on successful data retrieval
@@ -508,13 +611,25 @@ pub fn mpu6500_get_gyro(gyro_data: &mut [f32; 3]) -> i32 {
 /// }
 /// ```
 pub fn mpu6500_get_attitude(attitude_data: &mut [f32; 3]) -> i32 {
-    unsafe {
-        let mpu6500_get_attitude: Symbol<unsafe extern "C" fn(*mut f32) -> i32> = LIBRARY
-            .get(b"mpu6500_Get_Attitude")
-            .expect("Failed to load mpu6500_Get_Attitude function");
+    let result = read_attitude(attitude_data);
+    if result == 0 {
+        *attitude_data = units().convert_attitude(*attitude_data);
+    }
 
-        mpu6500_get_attitude(attitude_data.as_mut_ptr())
+    result
+}
+
+/// Reads the attitude in degrees, in the mounting frame and relative to the heading offset
+/// but ignoring [`set_units`].
+pub(crate) fn read_attitude(attitude_data: &mut [f32; 3]) -> i32 {
+    let result = retry::policy().run(|| backend::current().mpu_get_attitude(attitude_data));
+    health::record(Subsystem::Mpu, result);
+    if result == 0 {
+        mounting::remap_attitude_in_place(attitude_data);
+        heading::apply_offset(attitude_data);
     }
+
+    result
 }
 
 /// Retrieves the current Full Scale Range (FSR) configuration of the MPU6500 gyroscope.
@@ -660,15 +775,9 @@ pub fn mpu6500_get_attitude(attitude_data: &mut [f32; 3]) -> i32 {
 /// println!("Resolution: {:.4}°/s per bit", 1.0 / sensitivity);
 /// ```
 pub fn mpu_get_gyro_fsr() -> u16 {
-    unsafe {
-        let mut fsr_value: u16 = 0;
-        let mpu_get_gyro_fsr: Symbol<unsafe extern "C" fn(*mut u16) -> i32> = LIBRARY
-            .get(b"mpu_get_gyro_fsr")
-            .expect("Failed to load mpu_get_gyro_fsr function");
-
-        mpu_get_gyro_fsr(&mut fsr_value);
-        fsr_value
-    }
+    let mut fsr_value: u16 = 0;
+    backend::current().mpu_get_gyro_fsr(&mut fsr_value);
+    fsr_value
 }
 
 /// Retrieves the current Full Scale Range (FSR) configuration of the MPU6500 accelerometer.
@@ -855,15 +964,9 @@ pub fn mpu_get_gyro_fsr() -> u16 {
 /// }
 /// ```
 pub fn mpu_get_accel_fsr() -> u8 {
-    unsafe {
-        let mut fsr_value: u8 = 0;
-        let mpu_get_accel_fsr: Symbol<unsafe extern "C" fn(*mut u8) -> i32> = LIBRARY
-            .get(b"mpu_get_accel_fsr")
-            .expect("Failed to load mpu_get_accel_fsr function");
-
-        mpu_get_accel_fsr(&mut fsr_value);
-        fsr_value
-    }
+    let mut fsr_value: u8 = 0;
+    backend::current().mpu_get_accel_fsr(&mut fsr_value);
+    fsr_value
 }
 
 /// Configures the Full Scale Range (FSR) for the MPU6500 gyroscope sensor.
@@ -873,6 +976,9 @@ pub fn mpu_get_accel_fsr() -> u8 {
 /// measurements. The FSR configuration is a critical parameter that should be chosen based
 /// on the expected rotational speeds in your application.
 ///
+/// A range set successfully is set again when [`health`] re-initializes the
+/// sensor after persistent failures; [`mpu6500_open`] goes back to the default.
+///
 /// # Parameters
 ///
 /// - `fsr`: The desired gyroscope full-scale range in degrees per second
@@ -1082,13 +1188,12 @@ pub fn mpu_get_accel_fsr() -> u8 {
 /// }
 /// ```
 pub fn mpu_set_gyro_fsr(fsr: u32) -> i32 {
-    unsafe {
-        let mpu_set_gyro_fsr: Symbol<unsafe extern "C" fn(u32) -> i32> = LIBRARY
-            .get(b"mpu_set_gyro_fsr")
-            .expect("Failed to load mpu_set_gyro_fsr function");
-
-        mpu_set_gyro_fsr(fsr)
+    let result = backend::current().mpu_set_gyro_fsr(fsr);
+    if result == 0 {
+        restore::remember_gyro_fsr(fsr);
     }
+
+    result
 }
 
 /// Configures the Full Scale Range (FSR) for the MPU6500 accelerometer sensor.
@@ -1098,6 +1203,9 @@ pub fn mpu_set_gyro_fsr(fsr: u32) -> i32 {
 /// of all acceleration measurements. Proper FSR selection is crucial for optimizing measurement
 /// precision while ensuring the sensor can capture the expected acceleration range.
 ///
+/// Like the gyroscope range, a range set successfully survives automatic re-initialization by
+/// [`health`].
+///
 /// # Parameters
 ///
 /// - `fsr`: The desired accelerometer full-scale range in gravitational units (g)
@@ -1338,11 +1446,151 @@ pub fn mpu_set_gyro_fsr(fsr: u32) -> i32 {
 /// }
 /// ```
 pub fn mpu_set_accel_fsr(fsr: i32) -> i32 {
-    unsafe {
-        let mpu_set_accel_fsr: Symbol<unsafe extern "C" fn(i32) -> i32> = LIBRARY
-            .get(b"mpu_set_accel_fsr")
-            .expect("Failed to load mpu_set_accel_fsr function");
+    let result = backend::current().mpu_set_accel_fsr(fsr);
+    if result == 0 {
+        restore::remember_accel_fsr(fsr);
+    }
+
+    result
+}
+/// A combined reading of all MPU6500 outputs taken at (nearly) the same instant.
+///
+/// All arrays use the same axis layout as [`mpu6500_get_accel`], [`mpu6500_get_gyro`] and
+/// [`mpu6500_get_attitude`] respectively.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MpuSample {
+    /// Acceleration in g.
+    pub accel: [f32; 3],
+    /// Angular velocity in degrees per second.
+    pub gyro: [f32; 3],
+    /// Pitch, roll and yaw in degrees.
+    pub attitude: [f32; 3],
+}
+
+/// [`MpuSample`] under the name of the other fixed-size frame types,
+/// [`AdcFrame`](crate::adc_io::AdcFrame) and [`IoFrame`](crate::adc_io::IoFrame).
+pub type MpuFrame = MpuSample;
+
+/// Reads acceleration, angular velocity and attitude in one call.
+///
+/// # Returns
+///
+/// - `Ok(MpuSample)` when all three reads succeed
+/// - `Err(code)` with the error code of the first failing read
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::mpu::mpu6500_get_sample;
+///
+/// match mpu6500_get_sample() {
+///     Ok(sample) => println!("Yaw: {:.1}°", sample.attitude[2]),
+///     Err(code) => eprintln!("Failed to read MPU6500: {}", code),
+/// }
+/// ```
+pub fn mpu6500_get_sample() -> Result<MpuSample, i32> {
+    read_sample().map(|sample| units().convert_sample(sample))
+}
+
+/// Reads a sample in the sensor's units, ignoring [`set_units`].
+pub(crate) fn read_sample() -> Result<MpuSample, i32> {
+    let mut sample = MpuSample::default();
+
+    let result = read_accel(&mut sample.accel);
+    if result != 0 {
+        return Err(result);
+    }
 
-        mpu_set_accel_fsr(fsr)
+    let result = read_gyro(&mut sample.gyro);
+    if result != 0 {
+        return Err(result);
     }
-}
\ No newline at end of file
+
+    let result = read_attitude(&mut sample.attitude);
+    if result != 0 {
+        return Err(result);
+    }
+
+    Ok(sample)
+}
+
+/// Streams acceleration readings at a fixed rate.
+///
+/// The MPU6500 is polled on a dedicated blocking thread, so awaiting the stream never blocks
+/// the async runtime. The thread stops once the stream is dropped. Failed reads are skipped,
+/// and samples are dropped rather than buffered when the consumer lags behind.
+///
+/// # Parameters
+///
+/// - `rate_hz`: Sampling frequency in Hz.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use tokio_stream::StreamExt;
+/// use uptechstar_rs::mpu;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut accel = mpu::accel_stream(100.0);
+///     while let Some(sample) = accel.next().await {
+///         println!("{:?}: {:?}", sample.timestamp, sample.value);
+///     }
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub fn accel_stream(
+    rate_hz: f32,
+) -> impl tokio_stream::Stream<Item = crate::sampler::Timestamped<[f32; 3]>> + Send + Unpin {
+    crate::sampler::spawn_stream("uptech-accel-stream", rate_hz, 64, || {
+        let mut accel_data = [0.0f32; 3];
+        (mpu6500_get_accel(&mut accel_data) == 0).then_some(accel_data)
+    })
+}
+
+/// Streams combined [`MpuSample`]s at a fixed rate.
+///
+/// Behaves like [`accel_stream`], reading acceleration, angular velocity and attitude each tick.
+#[cfg(feature = "async")]
+pub fn sample_stream(
+    rate_hz: f32,
+) -> impl tokio_stream::Stream<Item = crate::sampler::Timestamped<MpuSample>> + Send + Unpin {
+    crate::sampler::spawn_stream("uptech-mpu-stream", rate_hz, 64, || mpu6500_get_sample().ok())
+}
+
+/// Streams combined [`MpuSample`]s as the MPU6500 produces them.
+///
+/// Instead of polling at a fixed rate, the stream thread waits with [`wait_data_ready`] and
+/// reads one sample per data-ready signal, so the stream runs at the output rate of the
+/// sensor without reading any sample twice. If the interrupt status cannot be read, the
+/// thread retries every 100ms.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use tokio_stream::StreamExt;
+/// use uptechstar_rs::mpu;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     mpu::mpu6500_open();
+///
+///     let mut samples = mpu::data_ready_stream();
+///     while let Some(sample) = samples.next().await {
+///         println!("{:?}: {:?}", sample.timestamp, sample.value.attitude);
+///     }
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub fn data_ready_stream()
+-> impl tokio_stream::Stream<Item = crate::sampler::Timestamped<MpuSample>> + Send + Unpin {
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
+
+    let pace = || {
+        if wait_data_ready(TIMEOUT).is_err() {
+            std::thread::sleep(TIMEOUT);
+        }
+    };
+    crate::sampler::spawn_paced_stream("uptech-mpu-stream", 64, pace, || mpu6500_get_sample().ok())
+}
@@ -0,0 +1,325 @@
+//! Vibration analysis over buffered accelerometer samples.
+//!
+//! [`VibrationBuffer`] keeps the most recent accelerometer samples, for example fed from
+//! [`mpu6500_get_accel`](super::mpu6500_get_accel) or a [`Sampler`](crate::sampler::Sampler).
+//! The functions here compute the usual machinery-monitoring figures from such a window:
+//! the RMS level, peaks, and with the `fft` feature the frequency spectrum.
+//!
+//! All functions work on plain slices, so recorded data can be analyzed the same way.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::thread;
+//! use std::time::Duration;
+//! use uptechstar_rs::mpu::{self, analysis::{self, VibrationBuffer}};
+//!
+//! let mut buffer = VibrationBuffer::new(512);
+//! let mut accel = [0.0f32; 3];
+//! while !buffer.is_full() {
+//!     if mpu::mpu6500_get_accel(&mut accel) == 0 {
+//!         buffer.push(accel);
+//!     }
+//!     thread::sleep(Duration::from_millis(1));
+//! }
+//!
+//! // Remove gravity before judging the vibration level.
+//! let z = analysis::detrend(&buffer.axis(2));
+//! println!("Z RMS: {:.3} g", analysis::rms(&z));
+//! ```
+
+use std::collections::VecDeque;
+
+/// Returns the root mean square of `samples`, or `0.0` for an empty slice.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::mpu::analysis::rms;
+///
+/// assert_eq!(rms(&[3.0, -3.0, 3.0, -3.0]), 3.0);
+/// ```
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Returns `samples` with their mean removed, such as the constant 1 g of gravity.
+pub fn detrend(samples: &[f32]) -> Vec<f32> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    samples.iter().map(|x| x - mean).collect()
+}
+
+/// A local maximum found by [`find_peaks`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Peak {
+    /// Position in the analyzed slice.
+    pub index: usize,
+    /// The sample value.
+    pub value: f32,
+}
+
+/// Finds local maxima of at least `threshold` that are at least `min_distance` samples apart.
+///
+/// Where peaks are closer, the higher one is kept. Plateaus count once, at their first sample.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::mpu::analysis::find_peaks;
+///
+/// let samples = [0.0, 2.0, 0.0, 1.5, 0.0, 0.2, 3.0, 0.0];
+/// let peaks = find_peaks(&samples, 1.0, 3);
+///
+/// let indices: Vec<usize> = peaks.iter().map(|peak| peak.index).collect();
+/// assert_eq!(indices, [1, 6]);
+/// ```
+pub fn find_peaks(samples: &[f32], threshold: f32, min_distance: usize) -> Vec<Peak> {
+    let mut candidates: Vec<Peak> = Vec::new();
+
+    for index in 0..samples.len() {
+        let value = samples[index];
+        if value < threshold || (index > 0 && samples[index - 1] >= value) {
+            continue;
+        }
+
+        let falling = samples[index + 1..]
+            .iter()
+            .find(|&&next| next != value)
+            .is_none_or(|&next| next < value);
+        if falling {
+            candidates.push(Peak { index, value });
+        }
+    }
+
+    // Keep the highest peaks, dropping lower ones within `min_distance` of them.
+    candidates.sort_by(|a, b| b.value.total_cmp(&a.value));
+    let mut kept: Vec<Peak> = Vec::new();
+    for peak in candidates {
+        if kept.iter().all(|other| other.index.abs_diff(peak.index) >= min_distance) {
+            kept.push(peak);
+        }
+    }
+
+    kept.sort_by_key(|peak| peak.index);
+    kept
+}
+
+/// A running RMS over the last `window` samples.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::mpu::analysis::WindowedRms;
+///
+/// let mut rms = WindowedRms::new(2);
+/// rms.push(10.0);
+/// rms.push(2.0);
+/// assert_eq!(rms.push(-2.0), 2.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WindowedRms {
+    window: usize,
+    samples: VecDeque<f32>,
+    sum_squares: f64,
+}
+
+impl WindowedRms {
+    /// Creates an empty window of `window` samples.
+    ///
+    /// # Panics
+    ///
+    /// If `window` is zero.
+    pub fn new(window: usize) -> Self {
+        assert!(window > 0, "RMS window must not be empty");
+        WindowedRms {
+            window,
+            samples: VecDeque::with_capacity(window),
+            sum_squares: 0.0,
+        }
+    }
+
+    /// Adds a sample, dropping the oldest one if the window is full, and returns the RMS.
+    pub fn push(&mut self, sample: f32) -> f32 {
+        if self.samples.len() == self.window
+            && let Some(oldest) = self.samples.pop_front()
+        {
+            self.sum_squares -= (oldest as f64).powi(2);
+        }
+        self.samples.push_back(sample);
+        self.sum_squares += (sample as f64).powi(2);
+        self.value()
+    }
+
+    /// Returns the RMS of the samples in the window, or `0.0` if it is empty.
+    pub fn value(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        // Rounding in the running sum can leave it slightly negative.
+        (self.sum_squares.max(0.0) / self.samples.len() as f64).sqrt() as f32
+    }
+
+    /// Returns `true` once `window` samples have been pushed.
+    pub fn is_full(&self) -> bool {
+        self.samples.len() == self.window
+    }
+
+    /// Empties the window.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.sum_squares = 0.0;
+    }
+}
+
+/// The most recent accelerometer samples, up to a fixed capacity.
+#[derive(Debug, Clone)]
+pub struct VibrationBuffer {
+    capacity: usize,
+    samples: VecDeque<[f32; 3]>,
+}
+
+impl VibrationBuffer {
+    /// Creates an empty buffer keeping `capacity` samples.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "Vibration buffer capacity must not be zero");
+        VibrationBuffer {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Adds an X/Y/Z sample, dropping the oldest one if the buffer is full.
+    pub fn push(&mut self, accel: [f32; 3]) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(accel);
+    }
+
+    /// Returns the number of buffered samples.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns `true` if no samples are buffered.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns `true` once the buffer holds `capacity` samples.
+    pub fn is_full(&self) -> bool {
+        self.samples.len() == self.capacity
+    }
+
+    /// Empties the buffer.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Returns one axis (0 = X, 1 = Y, 2 = Z), oldest sample first.
+    ///
+    /// # Panics
+    ///
+    /// If `axis` is not in `0..3`.
+    pub fn axis(&self, axis: usize) -> Vec<f32> {
+        assert!(axis < 3, "Axis must be in 0..3, got {}", axis);
+        self.samples.iter().map(|sample| sample[axis]).collect()
+    }
+
+    /// Returns the magnitude of each sample, oldest first.
+    pub fn magnitude(&self) -> Vec<f32> {
+        self.samples
+            .iter()
+            .map(|[x, y, z]| (x * x + y * y + z * z).sqrt())
+            .collect()
+    }
+
+    /// Returns the RMS of each axis after removing its mean.
+    pub fn rms(&self) -> [f32; 3] {
+        [0, 1, 2].map(|axis| rms(&detrend(&self.axis(axis))))
+    }
+}
+
+/// One frequency bin of a [`spectrum`].
+#[cfg(feature = "fft")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpectrumBin {
+    /// Center frequency in Hz.
+    pub frequency: f32,
+    /// Amplitude of the component at this frequency, in the unit of the samples.
+    pub amplitude: f32,
+}
+
+/// Computes the one-sided amplitude spectrum of `samples` taken at `sample_rate` Hz.
+///
+/// The mean is removed and a Hann window applied before the transform, and the amplitudes
+/// are corrected for the window, so a pure sine yields its own amplitude at its frequency.
+/// The bins are `sample_rate / samples.len()` Hz apart, from 0 Hz up to the Nyquist
+/// frequency.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::mpu::analysis::spectrum;
+///
+/// let rate = 1000.0;
+/// let samples: Vec<f32> = (0..1000)
+///     .map(|i| 1.0 + 0.5 * (2.0 * std::f32::consts::PI * 50.0 * i as f32 / rate).sin())
+///     .collect();
+///
+/// let bins = spectrum(&samples, rate);
+/// let peak = bins.iter().max_by(|a, b| a.amplitude.total_cmp(&b.amplitude)).unwrap();
+/// assert_eq!(peak.frequency, 50.0);
+/// assert!((peak.amplitude - 0.5).abs() < 0.01);
+/// ```
+#[cfg(feature = "fft")]
+pub fn spectrum(samples: &[f32], sample_rate: f32) -> Vec<SpectrumBin> {
+    use rustfft::{FftPlanner, num_complex::Complex};
+
+    let len = samples.len();
+    if len == 0 {
+        return Vec::new();
+    }
+
+    let hann = |i: usize| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / len as f32).cos();
+    let mut buffer: Vec<Complex<f32>> = detrend(samples)
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| Complex::new(x * hann(i), 0.0))
+        .collect();
+
+    FftPlanner::new().plan_fft_forward(len).process(&mut buffer);
+
+    // A Hann window halves the amplitude; the one-sided spectrum doubles all bins but DC.
+    let scale = 2.0 * 2.0 / len as f32;
+    buffer[..len / 2 + 1]
+        .iter()
+        .enumerate()
+        .map(|(bin, value)| SpectrumBin {
+            frequency: bin as f32 * sample_rate / len as f32,
+            amplitude: value.norm() * if bin == 0 { scale / 2.0 } else { scale },
+        })
+        .collect()
+}
+
+/// Returns the strongest non-DC bin of the [`spectrum`] of `samples`, or `None` if there are
+/// fewer than two samples.
+#[cfg(feature = "fft")]
+pub fn dominant_frequency(samples: &[f32], sample_rate: f32) -> Option<SpectrumBin> {
+    spectrum(samples, sample_rate)
+        .into_iter()
+        .skip(1)
+        .max_by(|a, b| a.amplitude.total_cmp(&b.amplitude))
+}
@@ -0,0 +1,153 @@
+use crate::log_level::mpu::info;
+use crate::settings::{Key, Settings};
+use std::sync::RwLock;
+
+const ACCEL_OFFSET: [Key<f32>; 3] = [
+    Key::new("imu.accel_offset_x", 0.0),
+    Key::new("imu.accel_offset_y", 0.0),
+    Key::new("imu.accel_offset_z", 0.0),
+];
+const ACCEL_SCALE: [Key<f32>; 3] = [
+    Key::new("imu.accel_scale_x", 1.0),
+    Key::new("imu.accel_scale_y", 1.0),
+    Key::new("imu.accel_scale_z", 1.0),
+];
+const GYRO_BIAS: [Key<f32>; 3] = [
+    Key::new("imu.gyro_bias_x", 0.0),
+    Key::new("imu.gyro_bias_y", 0.0),
+    Key::new("imu.gyro_bias_z", 0.0),
+];
+
+/// Corrections for the accelerometer and gyroscope readings, in the robot frame.
+///
+/// A corrected acceleration is `(raw - accel_offset) * accel_scale` per axis, in g, and a
+/// corrected angular rate `raw - gyro_bias`, in degrees per second. The
+/// [IMU wizard](crate::calibration::ImuCalibrationWizard) measures them.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::mpu::ImuCalibration;
+/// use uptechstar_rs::settings::Settings;
+///
+/// let calibration = ImuCalibration {
+///     accel_offset: [0.02, 0.0, -0.25],
+///     accel_scale: [1.0, 1.0, 0.5],
+///     gyro_bias: [0.5, -0.25, 0.0],
+/// };
+/// assert_eq!(calibration.correct_accel([0.02, 0.0, 1.75]), [0.0, 0.0, 1.0]);
+/// assert_eq!(calibration.correct_gyro([0.5, 0.0, 0.0]), [0.0, 0.25, 0.0]);
+///
+/// let settings = Settings::new();
+/// calibration.save(&settings);
+/// assert_eq!(ImuCalibration::load(&settings), calibration);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImuCalibration {
+    /// Acceleration read at rest with no gravity along each axis, in g.
+    pub accel_offset: [f32; 3],
+    /// Factor turning the span between +1 g and -1 g into exactly 2 g, per axis.
+    pub accel_scale: [f32; 3],
+    /// Angular rate read at rest, in degrees per second.
+    pub gyro_bias: [f32; 3],
+}
+
+impl Default for ImuCalibration {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl ImuCalibration {
+    /// No correction at all.
+    pub const IDENTITY: ImuCalibration = ImuCalibration {
+        accel_offset: [0.0; 3],
+        accel_scale: [1.0; 3],
+        gyro_bias: [0.0; 3],
+    };
+
+    /// Returns `true` if the readings are left unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    /// Corrects an acceleration in g.
+    pub fn correct_accel(&self, accel: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|axis| (accel[axis] - self.accel_offset[axis]) * self.accel_scale[axis])
+    }
+
+    /// Corrects an angular rate in degrees per second.
+    pub fn correct_gyro(&self, gyro: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|axis| gyro[axis] - self.gyro_bias[axis])
+    }
+
+    /// Reads the calibration from the `imu.*` settings of `settings`, using no correction for
+    /// the values that are missing.
+    pub fn load(settings: &Settings) -> Self {
+        ImuCalibration {
+            accel_offset: ACCEL_OFFSET.map(|key| settings.get(&key)),
+            accel_scale: ACCEL_SCALE.map(|key| settings.get(&key)),
+            gyro_bias: GYRO_BIAS.map(|key| settings.get(&key)),
+        }
+    }
+
+    /// Stores the calibration in the `imu.accel_offset_x` ... `imu.gyro_bias_z` settings of
+    /// `settings`, registering them if needed.
+    pub fn save(&self, settings: &Settings) {
+        for (keys, values) in [
+            (&ACCEL_OFFSET, self.accel_offset),
+            (&ACCEL_SCALE, self.accel_scale),
+            (&GYRO_BIAS, self.gyro_bias),
+        ] {
+            for (key, value) in keys.iter().zip(values) {
+                settings.set(key, value);
+            }
+        }
+    }
+}
+
+static CALIBRATION: RwLock<ImuCalibration> = RwLock::new(ImuCalibration::IDENTITY);
+
+/// Returns the calibration applied to the MPU6500 readings.
+pub fn calibration() -> ImuCalibration {
+    *CALIBRATION.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets the calibration applied to the MPU6500 readings, returning the previous one.
+///
+/// [`mpu6500_get_accel`](super::mpu6500_get_accel),
+/// [`mpu6500_get_gyro`](super::mpu6500_get_gyro) and [`read_fifo`](super::read_fifo) apply it
+/// after the
+/// [mounting](super::set_mounting), so calibrate again after changing the mounting.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::mpu::{self, ImuCalibration};
+/// use uptechstar_rs::settings::Settings;
+///
+/// # let settings = Settings::new();
+/// mpu::set_calibration(ImuCalibration::load(&settings));
+/// ```
+pub fn set_calibration(calibration: ImuCalibration) -> ImuCalibration {
+    info!("MPU6500 calibration set to {:?}", calibration);
+    let mut guard = CALIBRATION.write().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *guard, calibration)
+}
+
+/// Applies the calibration to an acceleration reading in place.
+pub(crate) fn correct_accel_in_place(data: &mut [f32; 3]) {
+    let calibration = calibration();
+    if !calibration.is_identity() {
+        *data = calibration.correct_accel(*data);
+    }
+}
+
+/// Applies the calibration to an angular rate reading in place.
+pub(crate) fn correct_gyro_in_place(data: &mut [f32; 3]) {
+    let calibration = calibration();
+    if !calibration.is_identity() {
+        *data = calibration.correct_gyro(*data);
+    }
+}
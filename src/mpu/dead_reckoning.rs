@@ -0,0 +1,361 @@
+use super::mounting::to_world;
+use super::{MpuSample, read_sample};
+use crate::sampler::{Ticker, period_from_rate};
+use crate::log_level::mpu::{debug, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Standard gravity in m/s², converting the accelerometer readings in g.
+const STANDARD_GRAVITY: f32 = 9.80665;
+
+/// Velocity and position estimated by a [`DeadReckoning`], in the world frame of the DMP
+/// attitude: X and Y horizontal, Z up.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NavState {
+    /// Velocity in m/s.
+    pub velocity: [f32; 3],
+    /// Position in m, relative to the start or the last reset.
+    pub position: [f32; 3],
+    /// `true` while the board is detected as standing still and the velocity is held at zero.
+    pub stationary: bool,
+}
+
+/// How far a [`DeadReckoning`] estimate has probably drifted.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DriftEstimate {
+    /// Acceleration offset in m/s² measured while stationary and removed from later
+    /// integration, per world axis.
+    pub bias: [f32; 3],
+    /// The velocity in m/s that was left when the board last came to rest, i.e. the error
+    /// accumulated over the previous motion.
+    pub last_correction: f32,
+    /// Expected position error in m of the current motion, extrapolated from how fast the
+    /// previous motion drifted.
+    pub position_error: f32,
+    /// Time since the last zero-velocity update.
+    pub since_update: Duration,
+}
+
+fn norm(v: [f32; 3]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// The integration state, shared with the polling thread.
+#[derive(Debug, Clone)]
+struct Integrator {
+    still_accel: f32,
+    still_gyro: f32,
+    still_duration: Duration,
+
+    last_timestamp: Option<Duration>,
+    still_since: Option<Duration>,
+    moving_since: Duration,
+    state: NavState,
+    drift: DriftEstimate,
+    drift_rate: f32,
+}
+
+impl Integrator {
+    /// Weight of each stationary sample in the bias estimate.
+    const BIAS_SMOOTHING: f32 = 0.02;
+
+    fn reset(&mut self) {
+        self.last_timestamp = None;
+        self.still_since = None;
+        self.moving_since = Duration::ZERO;
+        self.state = NavState::default();
+        self.drift.last_correction = 0.0;
+        self.drift.position_error = 0.0;
+        self.drift.since_update = Duration::ZERO;
+        self.drift_rate = 0.0;
+    }
+
+    fn update(&mut self, sample: &MpuSample, timestamp: Duration) -> NavState {
+        let Some(last) = self.last_timestamp.replace(timestamp) else {
+            self.moving_since = timestamp;
+            return self.state;
+        };
+        let dt = timestamp.saturating_sub(last).as_secs_f32();
+
+        let world = to_world(sample.accel, sample.attitude);
+        let linear = [world[0], world[1], world[2] - 1.0].map(|a| a * STANDARD_GRAVITY);
+
+        let still = norm(linear) < self.still_accel * STANDARD_GRAVITY && norm(sample.gyro) < self.still_gyro;
+        let still_for = if still {
+            timestamp.saturating_sub(*self.still_since.get_or_insert(timestamp))
+        } else {
+            self.still_since = None;
+            Duration::ZERO
+        };
+
+        if still_for >= self.still_duration {
+            if !self.state.stationary {
+                self.zero_velocity_update(timestamp);
+            }
+            for (bias, a) in self.drift.bias.iter_mut().zip(linear) {
+                *bias += Self::BIAS_SMOOTHING * (a - *bias);
+            }
+            self.drift.since_update = Duration::ZERO;
+            return self.state;
+        }
+
+        if self.state.stationary {
+            self.state.stationary = false;
+            self.moving_since = last;
+        }
+
+        let NavState { velocity, position, .. } = &mut self.state;
+        for (axis, a) in linear.into_iter().enumerate() {
+            let previous = velocity[axis];
+            velocity[axis] += (a - self.drift.bias[axis]) * dt;
+            position[axis] += (previous + velocity[axis]) / 2.0 * dt;
+        }
+
+        self.drift.since_update = timestamp.saturating_sub(self.moving_since);
+        let t = self.drift.since_update.as_secs_f32();
+        self.drift.position_error = 0.5 * self.drift_rate * t * t;
+        self.state
+    }
+
+    /// Zeroes the velocity once the board comes to rest, taking the remaining velocity as the
+    /// error of the motion that just ended.
+    fn zero_velocity_update(&mut self, timestamp: Duration) {
+        let moved = timestamp.saturating_sub(self.moving_since).as_secs_f32();
+        let error = self.state.velocity;
+
+        // The error built up over the whole motion; assuming it grew linearly, the position
+        // is off by half of it over the motion time.
+        for (position, error) in self.state.position.iter_mut().zip(error) {
+            *position -= error * moved / 2.0;
+        }
+
+        self.drift.last_correction = norm(error);
+        if moved > 0.0 {
+            self.drift_rate = self.drift.last_correction / moved;
+        }
+        self.drift.position_error = 0.0;
+        self.state.velocity = [0.0; 3];
+        self.state.stationary = true;
+
+        debug!(
+            "Zero-velocity update after {:.2}s of motion, correcting {:.3} m/s",
+            moved, self.drift.last_correction
+        );
+    }
+}
+
+/// An experimental velocity and position estimate from the IMU alone.
+///
+/// The acceleration is rotated into the world frame with the DMP attitude, gravity removed,
+/// and the rest integrated twice. Integration errors grow quickly, so the estimate is only
+/// useful over a few seconds of motion, such as one maneuver. Whenever the board stands still
+/// for 100ms, the velocity is reset to zero (a zero-velocity update), the position corrected
+/// for the drift this reveals, and the acceleration measured at rest removed as bias from
+/// the following motion. [`drift`](DeadReckoning::drift) reports the size of these errors.
+///
+/// The DMP yaw drifts as well, so over longer runs the estimated direction of travel turns
+/// away from the real one.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use std::time::Duration;
+/// use uptechstar_rs::mpu::{self, DeadReckoning};
+///
+/// mpu::mpu6500_open();
+///
+/// let mut odometry = DeadReckoning::new();
+/// odometry.start();
+///
+/// loop {
+///     thread::sleep(Duration::from_millis(100));
+///     let state = odometry.state();
+///     let drift = odometry.drift();
+///     println!(
+///         "x {:.2} m, y {:.2} m (±{:.2} m)",
+///         state.position[0], state.position[1], drift.position_error
+///     );
+/// }
+/// ```
+///
+/// Integrating recorded samples, here one second of speeding up and one of slowing down at
+/// 1 m/s²:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::mpu::{DeadReckoning, MpuSample};
+///
+/// let mut odometry = DeadReckoning::new();
+/// let mut state = Default::default();
+///
+/// for i in 0..600u64 {
+///     let ax = match i {
+///         100..300 => 1.0,
+///         300..500 => -1.0,
+///         _ => 0.0,
+///     } / 9.80665;
+///     let sample = MpuSample { accel: [ax, 0.0, 1.0], ..Default::default() };
+///     state = odometry.update(&sample, Duration::from_millis(i * 5));
+/// }
+///
+/// assert!(state.stationary);
+/// assert!((state.position[0] - 1.0).abs() < 0.02);
+/// ```
+pub struct DeadReckoning {
+    period: Duration,
+    integrator: Arc<Mutex<Integrator>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for DeadReckoning {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeadReckoning {
+    /// Creates a stopped estimator at the origin, polling at 200 Hz once started.
+    pub fn new() -> Self {
+        DeadReckoning {
+            period: Duration::from_millis(5),
+            integrator: Arc::new(Mutex::new(Integrator {
+                still_accel: 0.05,
+                still_gyro: 5.0,
+                still_duration: Duration::from_millis(100),
+                last_timestamp: None,
+                still_since: None,
+                moving_since: Duration::ZERO,
+                state: NavState::default(),
+                drift: DriftEstimate::default(),
+                drift_rate: 0.0,
+            })),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Treats the board as standing still once the acceleration without gravity stays below
+    /// `accel_g` and the angular rate below `gyro_dps` for `duration`.
+    pub fn with_zero_velocity(self, accel_g: f32, gyro_dps: f32, duration: Duration) -> Self {
+        {
+            let mut integrator = self.lock();
+            integrator.still_accel = accel_g;
+            integrator.still_gyro = gyro_dps;
+            integrator.still_duration = duration;
+        }
+        self
+    }
+
+    /// Sets the IMU polling rate. Integration errors grow at lower rates.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn with_rate(mut self, rate_hz: f32) -> Self {
+        self.period = period_from_rate(rate_hz);
+        self
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Integrator> {
+        self.integrator.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Integrates one sample, taken at `timestamp` since an arbitrary start, and returns the
+    /// new estimate.
+    ///
+    /// This is what the background thread does; don't mix both.
+    pub fn update(&mut self, sample: &MpuSample, timestamp: Duration) -> NavState {
+        self.lock().update(sample, timestamp)
+    }
+
+    /// Returns the current estimate.
+    pub fn state(&self) -> NavState {
+        self.lock().state
+    }
+
+    /// Returns the current drift estimate.
+    pub fn drift(&self) -> DriftEstimate {
+        self.lock().drift
+    }
+
+    /// Moves the origin to the current position and zeroes the velocity. The learned bias is
+    /// kept.
+    pub fn reset(&self) {
+        self.lock().reset();
+        debug!("Dead reckoning reset");
+    }
+
+    /// Returns `true` while the polling thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Resets the estimate and starts polling the IMU. Does nothing if it is already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!("Starting dead reckoning at {:.1} Hz", 1.0 / self.period.as_secs_f32());
+        self.reset();
+        self.running.store(true, Ordering::Release);
+
+        let period = self.period;
+        let integrator = Arc::clone(&self.integrator);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-dead-reckoning".into())
+                .spawn(move || {
+                    let started = Instant::now();
+                    let mut ticker = Ticker::new(period);
+
+                    while running.load(Ordering::Acquire) {
+                        if let Ok(sample) = read_sample() {
+                            integrator
+                                .lock()
+                                .unwrap_or_else(|e| e.into_inner())
+                                .update(&sample, started.elapsed());
+                        }
+                        ticker.wait();
+                    }
+
+                    debug!("Dead reckoning thread exited");
+                })
+                .expect("Failed to spawn dead reckoning thread"),
+        );
+
+        self
+    }
+
+    /// Stops the polling thread and waits for it to exit. The estimate is kept.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Dead reckoning stopped");
+        }
+
+        self
+    }
+}
+
+impl Drop for DeadReckoning {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,234 @@
+use super::{MpuSample, calibration, heading, mounting, units};
+use crate::extern_lib::symbol_or_return;
+use crate::sampler::Timestamped;
+use crate::stats;
+use crate::log_level::mpu::{debug, warn};
+use once_cell::sync::Lazy;
+use std::ffi::{c_long, c_ulong};
+use std::time::{Duration, Instant};
+
+/// `sensors` bits of a DMP packet.
+const INV_XYZ_GYRO: i16 = 0x70;
+const INV_XYZ_ACCEL: i16 = 0x08;
+const INV_WXYZ_QUAT: i16 = 0x100;
+
+/// The motion driver resets the FIFO and returns this when it overflowed.
+const FIFO_OVERFLOW: i32 = -2;
+
+/// Upper bound of packets drained per call; the 1 KiB FIFO holds fewer than 40 DMP packets.
+const MAX_PACKETS: usize = 64;
+
+/// Reference point of the FIFO timestamps.
+static EPOCH: Lazy<Instant> = Lazy::new(Instant::now);
+
+/// Scale factors from raw FIFO values to g and °/s.
+struct Sensitivity {
+    accel: f32,
+    gyro: f32,
+}
+
+fn sensitivity() -> Result<Sensitivity, i32> {
+    unsafe {
+        let call = stats::start("mpu_get_accel_sens");
+        let mpu_get_accel_sens = symbol_or_return!(mpu_get_accel_sens: unsafe extern "C" fn(*mut u16) -> i32, Err(-1));
+        let mut accel: u16 = 0;
+        let result = call.finish(mpu_get_accel_sens(&mut accel));
+        if result != 0 || accel == 0 {
+            return Err(result.min(-1));
+        }
+
+        let call = stats::start("mpu_get_gyro_sens");
+        let mpu_get_gyro_sens = symbol_or_return!(mpu_get_gyro_sens: unsafe extern "C" fn(*mut f32) -> i32, Err(-1));
+        let mut gyro: f32 = 0.0;
+        let result = call.finish(mpu_get_gyro_sens(&mut gyro));
+        if result != 0 || gyro <= 0.0 {
+            return Err(result.min(-1));
+        }
+
+        Ok(Sensitivity { accel: accel as f32, gyro })
+    }
+}
+
+/// Returns the rate in Hz at which the DMP writes packets to the FIFO.
+///
+/// Returns:
+///   The rate, or the error code of the read.
+pub fn fifo_rate() -> Result<u16, i32> {
+    unsafe {
+        let call = stats::start("dmp_get_fifo_rate");
+        let dmp_get_fifo_rate = symbol_or_return!(dmp_get_fifo_rate: unsafe extern "C" fn(*mut u16) -> i32, Err(-1));
+
+        let mut rate: u16 = 0;
+        match call.finish(dmp_get_fifo_rate(&mut rate)) {
+            0 if rate > 0 => Ok(rate),
+            0 => Err(-1),
+            code => Err(code),
+        }
+    }
+}
+
+/// Discards everything buffered in the FIFO, for example before starting to read it after a
+/// pause.
+///
+/// Returns:
+///   0 on success, or the error code of the motion driver.
+pub fn reset_fifo() -> i32 {
+    unsafe {
+        let call = stats::start("mpu_reset_fifo");
+        let mpu_reset_fifo = symbol_or_return!(mpu_reset_fifo: unsafe extern "C" fn() -> i32, -1);
+
+        call.finish(mpu_reset_fifo())
+    }
+}
+
+/// A raw DMP packet.
+#[derive(Default)]
+struct Packet {
+    gyro: [i16; 3],
+    accel: [i16; 3],
+    quat: [c_long; 4],
+    sensors: i16,
+}
+
+/// Pops one packet. Returns the packet and whether more are buffered.
+fn read_packet() -> Result<(Packet, bool), i32> {
+    unsafe {
+        let call = stats::start("dmp_read_fifo");
+        let dmp_read_fifo = symbol_or_return!(
+            dmp_read_fifo: unsafe extern "C" fn(*mut i16, *mut i16, *mut c_long, *mut c_ulong, *mut i16, *mut u8) -> i32,
+            Err(-1)
+        );
+
+        let mut packet = Packet::default();
+        let mut timestamp: c_ulong = 0;
+        let mut more: u8 = 0;
+        let result = call.finish(dmp_read_fifo(
+            packet.gyro.as_mut_ptr(),
+            packet.accel.as_mut_ptr(),
+            packet.quat.as_mut_ptr(),
+            &mut timestamp,
+            &mut packet.sensors,
+            &mut more,
+        ));
+
+        if result != 0 {
+            return Err(result);
+        }
+        Ok((packet, more != 0))
+    }
+}
+
+/// Converts a Q30 quaternion to pitch about X, roll about Y and yaw about Z in degrees.
+fn quaternion_to_attitude(quat: [c_long; 4]) -> [f32; 3] {
+    let [w, x, y, z] = quat.map(|q| q as f64 / (1u64 << 30) as f64);
+
+    let pitch = (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y));
+    let roll = (2.0 * (w * y - x * z)).clamp(-1.0, 1.0).asin();
+    let yaw = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+    [pitch, roll, yaw].map(|angle| angle.to_degrees() as f32)
+}
+
+impl Packet {
+    fn to_sample(&self, sensitivity: &Sensitivity) -> MpuSample {
+        let mut sample = MpuSample::default();
+
+        if self.sensors & INV_XYZ_ACCEL != 0 {
+            sample.accel = self.accel.map(|raw| raw as f32 / sensitivity.accel);
+            mounting::remap_in_place(&mut sample.accel);
+            calibration::correct_accel_in_place(&mut sample.accel);
+        }
+        if self.sensors & INV_XYZ_GYRO != 0 {
+            sample.gyro = self.gyro.map(|raw| raw as f32 / sensitivity.gyro);
+            mounting::remap_in_place(&mut sample.gyro);
+            calibration::correct_gyro_in_place(&mut sample.gyro);
+        }
+        if self.sensors & INV_WXYZ_QUAT != 0 {
+            sample.attitude = quaternion_to_attitude(self.quat);
+            mounting::remap_attitude_in_place(&mut sample.attitude);
+            heading::apply_offset(&mut sample.attitude);
+        }
+
+        sample
+    }
+}
+
+/// Drains all samples the DMP buffered in the MPU6500 FIFO since the last read and appends
+/// them to `samples`, oldest first.
+///
+/// Unlike polling [`mpu6500_get_sample`](super::mpu6500_get_sample), no samples are lost
+/// while the calling thread is busy, as long as it comes back before the FIFO fills up with
+/// about 36 packets. Each packet is converted like the other reads: mounting, heading offset
+/// and [units](super::set_units) are applied, and the attitude is computed from the DMP
+/// quaternion.
+///
+/// The FIFO does not record when packets were written. The timestamps are derived from the
+/// time of the read and the [`fifo_rate`], counting back from the newest packet, and measure
+/// the time since the first FIFO read of the process.
+///
+/// # Returns
+///
+/// - `Ok(count)` with the number of samples appended. An empty FIFO reads as `Ok(0)`.
+/// - `Err(-2)` if the FIFO overflowed; the motion driver reset it and the buffered samples
+///   are lost.
+/// - `Err(code)` if the sensitivity, the rate or a packet could not be read.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use std::time::Duration;
+/// use uptechstar_rs::mpu;
+///
+/// mpu::mpu6500_open();
+/// mpu::reset_fifo();
+///
+/// let mut samples = Vec::new();
+/// loop {
+///     thread::sleep(Duration::from_millis(50));
+///     match mpu::read_fifo(&mut samples) {
+///         Ok(count) => println!("{} new samples", count),
+///         Err(-2) => eprintln!("FIFO overflow, samples lost"),
+///         Err(code) => eprintln!("FIFO read failed: {}", code),
+///     }
+///     for sample in samples.drain(..) {
+///         println!("{:?}: {:?}", sample.timestamp, sample.value.accel);
+///     }
+/// }
+/// ```
+pub fn read_fifo(samples: &mut Vec<Timestamped<MpuSample>>) -> Result<usize, i32> {
+    let epoch = *EPOCH;
+    let sensitivity = sensitivity()?;
+    let period = Duration::from_secs(1) / fifo_rate()? as u32;
+    let units = units();
+
+    let mut packets = Vec::new();
+    loop {
+        match read_packet() {
+            Ok((packet, more)) => {
+                packets.push(packet);
+                if !more {
+                    break;
+                }
+            }
+            Err(FIFO_OVERFLOW) => {
+                warn!("MPU6500 FIFO overflowed and was reset");
+                return Err(FIFO_OVERFLOW);
+            }
+            // The motion driver reports an empty FIFO like a failed read.
+            Err(_) => break,
+        }
+        if packets.len() == MAX_PACKETS {
+            debug!("Stopping FIFO read after {} packets", MAX_PACKETS);
+            break;
+        }
+    }
+
+    let now = epoch.elapsed();
+    let count = packets.len();
+    samples.extend(packets.iter().enumerate().map(|(index, packet)| Timestamped {
+        timestamp: now.saturating_sub(period * (count - 1 - index) as u32),
+        value: units.convert_sample(packet.to_sample(&sensitivity)),
+    }));
+
+    Ok(count)
+}
@@ -0,0 +1,518 @@
+//! Recognition of tilt, flip and rotation gestures, and of user-recorded motions.
+//!
+//! A [`GestureRecognizer`] watches [`MpuSample`]s and reports [`Gesture`]s:
+//!
+//! - **Tilts** when the board leans further than 30° to one side, once per tilt. The board has
+//!   to come back within half the angle before the next tilt counts.
+//! - **Flips** when the board turns upside down or back.
+//! - **Rotations** when the board turns 90° about its Z axis without pausing.
+//! - **Templates**: any motion that resembles a recorded [`GestureTemplate`], compared with
+//!   dynamic time warping ([`dtw_distance`]), so the same gesture performed faster or slower
+//!   still matches.
+//!
+//! The directions follow the axes of the MPU6500: leaning so the +Y axis rises is a right
+//! tilt, leaning so the −X axis rises a forward tilt, and a positive Z rate is a
+//! counterclockwise rotation seen from above. Boards mounted differently see the directions
+//! swapped.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use uptechstar_rs::events::{self, Event};
+//! use uptechstar_rs::mpu::{self, gestures::{Gesture, GestureRecognizer, GestureTemplate}};
+//!
+//! mpu::mpu6500_open();
+//!
+//! // Record a template: two seconds of samples at the recognizer's rate of 50 Hz.
+//! println!("Perform the gesture now");
+//! let mut recorded = Vec::new();
+//! for _ in 0..100 {
+//!     if let Ok(sample) = mpu::mpu6500_get_sample() {
+//!         recorded.push(sample);
+//!     }
+//!     std::thread::sleep(Duration::from_millis(20));
+//! }
+//!
+//! let events = events::subscribe();
+//! let mut gestures = GestureRecognizer::new()
+//!     .with_tilt(20.0)
+//!     .with_template(GestureTemplate::new("wave", &recorded));
+//! gestures.start();
+//!
+//! for event in events {
+//!     match event {
+//!         Event::Gesture(Gesture::Template(name)) => println!("Recognized {}", name),
+//!         Event::Gesture(gesture) => println!("{:?}", gesture),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+
+use super::{MpuSample, read_sample};
+use crate::events::{self, Event, EventBus};
+use crate::sampler::{Ticker, period_from_rate};
+use crate::log_level::mpu::{debug, info};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Angular rates are divided by this before template matching, so a fast turn in °/s weighs
+/// about as much as a swing of the acceleration in g.
+const GYRO_SCALE: f32 = 100.0;
+
+/// Angular rate in °/s above which the board counts as moving for template matching.
+const ACTIVITY_DPS: f32 = 60.0;
+
+/// A recognized gesture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Gesture {
+    /// The board leaned to the left.
+    TiltLeft,
+    /// The board leaned to the right.
+    TiltRight,
+    /// The board leaned forward.
+    TiltForward,
+    /// The board leaned back.
+    TiltBack,
+    /// The board was turned upside down, or back upright.
+    Flip,
+    /// The board turned clockwise about its Z axis, seen from above.
+    RotateClockwise,
+    /// The board turned counterclockwise about its Z axis, seen from above.
+    RotateCounterClockwise,
+    /// A motion matched the [`GestureTemplate`] of this name.
+    Template(String),
+}
+
+/// A recorded motion to recognize.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GestureTemplate {
+    name: String,
+    features: Vec<[f32; 6]>,
+}
+
+impl GestureTemplate {
+    /// Creates a template from samples of the gesture. Idle samples before and after the
+    /// motion are trimmed.
+    ///
+    /// The samples should be taken at the rate of the recognizer, 50 Hz by default.
+    pub fn new(name: impl Into<String>, samples: &[MpuSample]) -> Self {
+        let active = |sample: &MpuSample| gyro_magnitude(sample) > ACTIVITY_DPS;
+        let first = samples.iter().position(active).unwrap_or(0);
+        let last = samples.iter().rposition(active).map_or(samples.len(), |i| i + 1);
+
+        GestureTemplate {
+            name: name.into(),
+            features: samples[first..last.max(first)].iter().map(features).collect(),
+        }
+    }
+
+    /// Returns the name reported in [`Gesture::Template`].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of samples after trimming.
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Returns `true` if the recording contained no motion.
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+}
+
+fn gyro_magnitude(sample: &MpuSample) -> f32 {
+    sample.gyro.iter().map(|g| g * g).sum::<f32>().sqrt()
+}
+
+fn features(sample: &MpuSample) -> [f32; 6] {
+    let [ax, ay, az] = sample.accel;
+    let [gx, gy, gz] = sample.gyro.map(|g| g / GYRO_SCALE);
+    [ax, ay, az, gx, gy, gz]
+}
+
+/// Returns the dynamic time warping distance between two sequences: the mean Euclidean
+/// distance between matched elements along the cheapest alignment.
+///
+/// Unlike a sample-by-sample comparison, sequences that differ only in speed come out close.
+/// Returns infinity if either sequence is empty.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::mpu::gestures::dtw_distance;
+///
+/// let slow = [[0.0], [0.0], [1.0], [1.0], [2.0], [2.0], [1.0], [0.0]];
+/// let fast = [[0.0], [1.0], [2.0], [1.0], [0.0]];
+/// let other = [[0.0], [-1.0], [-2.0], [-1.0], [0.0]];
+///
+/// assert_eq!(dtw_distance(&slow, &fast), 0.0);
+/// assert!(dtw_distance(&slow, &other) > 1.0);
+/// ```
+pub fn dtw_distance<const N: usize>(a: &[[f32; N]], b: &[[f32; N]]) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return f32::INFINITY;
+    }
+
+    let distance = |x: &[f32; N], y: &[f32; N]| x.iter().zip(y).map(|(x, y)| (x - y).powi(2)).sum::<f32>().sqrt();
+
+    // Each cell holds the cost and length of the cheapest path ending there; two rows suffice.
+    let mut previous = vec![(f32::INFINITY, 0u32); b.len() + 1];
+    let mut current = previous.clone();
+    previous[0] = (0.0, 0);
+
+    for x in a {
+        current[0] = (f32::INFINITY, 0);
+        for (j, y) in b.iter().enumerate() {
+            let (cost, steps) = [previous[j], previous[j + 1], current[j]]
+                .into_iter()
+                .min_by(|p, q| p.0.total_cmp(&q.0))
+                .expect("three candidates");
+            current[j + 1] = (cost + distance(x, y), steps + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    let (cost, steps) = previous[b.len()];
+    cost / steps as f32
+}
+
+/// Thresholds and the state of the running recognition.
+#[derive(Debug, Clone)]
+struct Detector {
+    tilt_deg: f32,
+    flip_g: f32,
+    rotation_deg: f32,
+    rotation_dps: f32,
+    templates: Vec<GestureTemplate>,
+    max_distance: f32,
+
+    last_timestamp: Option<Duration>,
+    tilt: Option<Gesture>,
+    upside_down: Option<bool>,
+    rotation: f32,
+    segment: Vec<[f32; 6]>,
+    quiet_since: Option<Duration>,
+    quiet_samples: usize,
+}
+
+impl Detector {
+    /// Pause that ends a motion before it is compared with the templates.
+    const SEGMENT_PAUSE: Duration = Duration::from_millis(150);
+    /// Motions longer than this are dropped instead of compared.
+    const MAX_SEGMENT: usize = 500;
+
+    fn reset(&mut self) {
+        self.last_timestamp = None;
+        self.tilt = None;
+        self.upside_down = None;
+        self.rotation = 0.0;
+        self.segment.clear();
+        self.quiet_since = None;
+        self.quiet_samples = 0;
+    }
+
+    fn process(&mut self, sample: &MpuSample, timestamp: Duration) -> Vec<Gesture> {
+        let dt = self
+            .last_timestamp
+            .replace(timestamp)
+            .map_or(0.0, |last| timestamp.saturating_sub(last).as_secs_f32());
+        let mut detected = Vec::new();
+
+        let [ax, ay, az] = sample.accel;
+
+        // Flips
+        match self.upside_down {
+            None => self.upside_down = Some(az < 0.0),
+            Some(false) if az < -self.flip_g => {
+                self.upside_down = Some(true);
+                detected.push(Gesture::Flip);
+            }
+            Some(true) if az > self.flip_g => {
+                self.upside_down = Some(false);
+                detected.push(Gesture::Flip);
+            }
+            _ => {}
+        }
+
+        // Tilts, only while upright
+        if self.upside_down == Some(false) {
+            let about_x = ay.atan2(az).to_degrees();
+            let about_y = (-ax).atan2((ay * ay + az * az).sqrt()).to_degrees();
+            let (angle, positive, negative) = if about_x.abs() >= about_y.abs() {
+                (about_x, Gesture::TiltRight, Gesture::TiltLeft)
+            } else {
+                (about_y, Gesture::TiltForward, Gesture::TiltBack)
+            };
+
+            if angle.abs() < self.tilt_deg / 2.0 {
+                self.tilt = None;
+            } else if angle.abs() >= self.tilt_deg && self.tilt.is_none() {
+                let tilt = if angle > 0.0 { positive } else { negative };
+                self.tilt = Some(tilt.clone());
+                detected.push(tilt);
+            }
+        }
+
+        // Rotations
+        let rate = sample.gyro[2];
+        if rate.abs() < self.rotation_dps {
+            self.rotation = 0.0;
+        } else {
+            self.rotation += rate * dt;
+            if self.rotation.abs() >= self.rotation_deg {
+                detected.push(if self.rotation > 0.0 {
+                    Gesture::RotateCounterClockwise
+                } else {
+                    Gesture::RotateClockwise
+                });
+                self.rotation = 0.0;
+            }
+        }
+
+        // Templates
+        if !self.templates.is_empty() {
+            if gyro_magnitude(sample) > ACTIVITY_DPS {
+                self.quiet_since = None;
+                self.quiet_samples = 0;
+                self.segment.push(features(sample));
+            } else if !self.segment.is_empty() {
+                let since = *self.quiet_since.get_or_insert(timestamp);
+                self.quiet_samples += 1;
+                self.segment.push(features(sample));
+                if timestamp.saturating_sub(since) >= Self::SEGMENT_PAUSE {
+                    detected.extend(self.match_segment());
+                    self.segment.clear();
+                    self.quiet_since = None;
+                    self.quiet_samples = 0;
+                }
+            }
+            if self.segment.len() > Self::MAX_SEGMENT {
+                debug!("Dropping gesture segment longer than {} samples", Self::MAX_SEGMENT);
+                self.segment.clear();
+                self.quiet_since = None;
+                self.quiet_samples = 0;
+            }
+        }
+
+        detected
+    }
+
+    /// Compares the finished motion with all templates and returns the closest match.
+    fn match_segment(&self) -> Option<Gesture> {
+        // The trailing pause is not part of the motion.
+        let motion = &self.segment[..self.segment.len() - self.quiet_samples];
+
+        let (template, distance) = self
+            .templates
+            .iter()
+            .filter(|template| !template.is_empty())
+            .map(|template| (template, dtw_distance(motion, &template.features)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+        debug!("Closest gesture template '{}' at distance {:.3}", template.name, distance);
+
+        (distance <= self.max_distance).then(|| Gesture::Template(template.name.clone()))
+    }
+}
+
+/// Recognizes gestures in the IMU data and publishes them as [`Event::Gesture`].
+///
+/// A background thread polls [`mpu6500_get_sample`](super::mpu6500_get_sample) at 50 Hz by default. Without the thread,
+/// [`process`](GestureRecognizer::process) runs the recognition on given samples.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::mpu::{MpuSample, gestures::{Gesture, GestureRecognizer}};
+///
+/// let mut gestures = GestureRecognizer::new();
+/// let mut detected = Vec::new();
+///
+/// // Lying flat, then turning at 180 °/s for 0.6 s.
+/// for i in 0..40u64 {
+///     let sample = MpuSample {
+///         accel: [0.0, 0.0, 1.0],
+///         gyro: [0.0, 0.0, if i >= 10 { 180.0 } else { 0.0 }],
+///         attitude: [0.0; 3],
+///     };
+///     detected.extend(gestures.process(&sample, Duration::from_millis(i * 20)));
+/// }
+///
+/// assert_eq!(detected, [Gesture::RotateCounterClockwise]);
+/// ```
+pub struct GestureRecognizer {
+    detector: Detector,
+    period: Duration,
+    bus: EventBus,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GestureRecognizer {
+    /// Creates a stopped recognizer with the default thresholds and no templates, publishing
+    /// on the [process-wide bus](crate::events::global).
+    pub fn new() -> Self {
+        GestureRecognizer {
+            detector: Detector {
+                tilt_deg: 30.0,
+                flip_g: 0.7,
+                rotation_deg: 90.0,
+                rotation_dps: 45.0,
+                templates: Vec::new(),
+                max_distance: 0.3,
+                last_timestamp: None,
+                tilt: None,
+                upside_down: None,
+                rotation: 0.0,
+                segment: Vec::new(),
+                quiet_since: None,
+                quiet_samples: 0,
+            },
+            period: Duration::from_millis(20),
+            bus: events::global().clone(),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Reports tilts beyond `angle_deg` from level.
+    pub fn with_tilt(mut self, angle_deg: f32) -> Self {
+        self.detector.tilt_deg = angle_deg;
+        self
+    }
+
+    /// Reports flips once the Z axis reads beyond `threshold_g` the other way up.
+    pub fn with_flip(mut self, threshold_g: f32) -> Self {
+        self.detector.flip_g = threshold_g;
+        self
+    }
+
+    /// Reports rotations of `angle_deg` about the Z axis while turning faster than
+    /// `min_rate_dps`. Slower turns restart the count.
+    pub fn with_rotation(mut self, angle_deg: f32, min_rate_dps: f32) -> Self {
+        self.detector.rotation_deg = angle_deg;
+        self.detector.rotation_dps = min_rate_dps;
+        self
+    }
+
+    /// Adds a recorded gesture to recognize.
+    pub fn with_template(mut self, template: GestureTemplate) -> Self {
+        self.detector.templates.push(template);
+        self
+    }
+
+    /// Sets the largest [`dtw_distance`] at which a motion still matches a template. The
+    /// default of 0.3 tolerates moderate variations; raise it if recorded gestures are missed,
+    /// lower it if unrelated motions match.
+    pub fn with_max_distance(mut self, distance: f32) -> Self {
+        self.detector.max_distance = distance;
+        self
+    }
+
+    /// Sets the IMU polling rate. Templates must be recorded at the same rate.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn with_rate(mut self, rate_hz: f32) -> Self {
+        self.period = period_from_rate(rate_hz);
+        self
+    }
+
+    /// Publishes on `bus` instead of the process-wide bus.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Feeds one sample, taken at `timestamp` since an arbitrary start, and returns the
+    /// gestures it completes. Nothing is published.
+    pub fn process(&mut self, sample: &MpuSample, timestamp: Duration) -> Vec<Gesture> {
+        self.detector.process(sample, timestamp)
+    }
+
+    /// Returns `true` while the recognition thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts polling the IMU and publishing gestures. Does nothing if it is already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!(
+            "Starting gesture recognition at {:.1} Hz with {} templates",
+            1.0 / self.period.as_secs_f32(),
+            self.detector.templates.len()
+        );
+        self.running.store(true, Ordering::Release);
+
+        let mut detector = self.detector.clone();
+        detector.reset();
+        let period = self.period;
+        let bus = self.bus.clone();
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-gestures".into())
+                .spawn(move || {
+                    let started = Instant::now();
+                    let mut ticker = Ticker::new(period);
+
+                    while running.load(Ordering::Acquire) {
+                        if let Ok(sample) = read_sample() {
+                            for gesture in detector.process(&sample, started.elapsed()) {
+                                debug!("Gesture {:?}", gesture);
+                                bus.publish(Event::Gesture(gesture));
+                            }
+                        }
+                        ticker.wait();
+                    }
+
+                    debug!("Gesture recognition thread exited");
+                })
+                .expect("Failed to spawn gesture recognition thread"),
+        );
+
+        self
+    }
+
+    /// Stops the recognition thread and waits for it to exit.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Gesture recognition stopped");
+        }
+
+        self
+    }
+}
+
+impl Drop for GestureRecognizer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,80 @@
+use super::read_attitude;
+use crate::log_level::mpu::info;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// The heading offset in degrees, stored as `f32` bits.
+static OFFSET: AtomicU32 = AtomicU32::new(0);
+
+/// Wraps `degrees` into `-180.0..180.0`.
+pub(crate) fn wrap_degrees(degrees: f32) -> f32 {
+    (degrees + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Returns the yaw in degrees that is subtracted from attitude readings.
+pub fn heading_offset() -> f32 {
+    f32::from_bits(OFFSET.load(Ordering::Relaxed))
+}
+
+/// Sets the yaw in degrees that is subtracted from subsequent
+/// [`mpu6500_get_attitude`](super::mpu6500_get_attitude) readings, returning the previous
+/// offset.
+///
+/// The corrected yaw wraps around into `-180.0..180.0`, so an offset of 170° turns a raw yaw
+/// of −170° into 20°. An offset of zero reports the yaw of the DMP unchanged.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::mpu;
+///
+/// // The field is rotated 90° against the DMP's reference.
+/// mpu::set_heading_offset(90.0);
+/// ```
+pub fn set_heading_offset(degrees: f32) -> f32 {
+    let degrees = wrap_degrees(degrees);
+    info!("MPU6500 heading offset set to {:.1}°", degrees);
+    f32::from_bits(OFFSET.swap(degrees.to_bits(), Ordering::Relaxed))
+}
+
+/// Makes the current heading read as a yaw of zero, for example with the robot aligned at the
+/// start line.
+///
+/// Returns:
+///   0 on success, or the error code of the attitude read, in which case the offset is
+///   unchanged.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::mpu;
+///
+/// mpu::mpu6500_open();
+///
+/// // Robot placed at the start line, pointing straight ahead.
+/// if mpu::zero_yaw() != 0 {
+///     eprintln!("Failed to zero the yaw");
+/// }
+///
+/// let mut attitude = [0.0f32; 3];
+/// mpu::mpu6500_get_attitude(&mut attitude);
+/// println!("Heading relative to the start: {:.1}°", attitude[2]);
+/// ```
+pub fn zero_yaw() -> i32 {
+    let mut attitude = [0.0f32; 3];
+    let result = read_attitude(&mut attitude);
+    if result != 0 {
+        return result;
+    }
+
+    // The reading already has the current offset removed.
+    set_heading_offset(heading_offset() + attitude[2]);
+    0
+}
+
+/// Removes the heading offset from an attitude reading in place.
+pub(crate) fn apply_offset(attitude: &mut [f32; 3]) {
+    let offset = heading_offset();
+    if offset != 0.0 {
+        attitude[2] = wrap_degrees(attitude[2] - offset);
+    }
+}
@@ -0,0 +1,282 @@
+use super::read_accel;
+use crate::events::{self, Event, EventBus};
+use crate::sampler::{Ticker, period_from_rate};
+use crate::log_level::mpu::{debug, info};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Thresholds and the state of the running detection.
+#[derive(Debug, Clone)]
+struct Detector {
+    free_fall_g: f32,
+    free_fall_duration: Duration,
+    impact_g: f32,
+    shake_g: f32,
+    shake_count: usize,
+    shake_window: Duration,
+
+    falling_since: Option<Duration>,
+    free_fall_reported: bool,
+    impact_peak: Option<f32>,
+    shake_above: bool,
+    swings: VecDeque<Duration>,
+}
+
+impl Detector {
+    fn reset(&mut self) {
+        self.falling_since = None;
+        self.free_fall_reported = false;
+        self.impact_peak = None;
+        self.shake_above = false;
+        self.swings.clear();
+    }
+
+    fn process(&mut self, accel: [f32; 3], timestamp: Duration) -> Vec<Event> {
+        let magnitude = accel.iter().map(|a| a * a).sum::<f32>().sqrt();
+        let mut detected = Vec::new();
+
+        if magnitude < self.free_fall_g {
+            let since = *self.falling_since.get_or_insert(timestamp);
+            if !self.free_fall_reported && timestamp - since >= self.free_fall_duration {
+                self.free_fall_reported = true;
+                detected.push(Event::FreeFall);
+            }
+        } else {
+            self.falling_since = None;
+            self.free_fall_reported = false;
+        }
+
+        if magnitude > self.impact_g {
+            self.impact_peak = Some(self.impact_peak.map_or(magnitude, |peak| peak.max(magnitude)));
+        } else if let Some(g) = self.impact_peak.take() {
+            detected.push(Event::Impact { g });
+        }
+
+        let above = magnitude > self.shake_g;
+        if above && !self.shake_above {
+            self.swings.push_back(timestamp);
+        }
+        self.shake_above = above;
+        while self.swings.front().is_some_and(|&swing| timestamp - swing > self.shake_window) {
+            self.swings.pop_front();
+        }
+        if self.swings.len() >= self.shake_count {
+            self.swings.clear();
+            detected.push(Event::Shake);
+        }
+
+        detected
+    }
+}
+
+/// Detects free fall, impacts and shaking in the accelerometer stream and publishes them as
+/// [`Event::FreeFall`], [`Event::Impact`] and [`Event::Shake`].
+///
+/// All detection works on the magnitude of the acceleration, so it does not depend on how the
+/// board is mounted:
+///
+/// - **Free fall**: the magnitude stays below 0.3 g for 100ms. Reported once per fall.
+/// - **Impact**: the magnitude exceeds 3 g. Reported with the peak magnitude once it drops
+///   below the threshold again.
+/// - **Shake**: the magnitude rises above 1.8 g four times within one second.
+///
+/// A background thread polls [`mpu6500_get_accel`](super::mpu6500_get_accel) at 200 Hz by default. Short impacts can fall
+/// between two samples at lower rates.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::events::{self, Event};
+/// use uptechstar_rs::mpu::{self, MotionEvents};
+///
+/// mpu::mpu6500_open();
+///
+/// let events = events::subscribe();
+/// let mut motion = MotionEvents::new().with_impact(4.0);
+/// motion.start();
+///
+/// for event in events {
+///     match event {
+///         Event::FreeFall => println!("Falling!"),
+///         Event::Impact { g } => println!("Crash with {:.1} g", g),
+///         Event::Shake => println!("Shaken"),
+///         _ => {}
+///     }
+/// }
+/// ```
+///
+/// Detection can also run on recorded samples without the background thread:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::events::Event;
+/// use uptechstar_rs::mpu::MotionEvents;
+///
+/// let mut motion = MotionEvents::new();
+/// let mut detected = Vec::new();
+/// for i in 0..20 {
+///     let at = Duration::from_millis(i * 10);
+///     detected.extend(motion.process([0.0, 0.0, 0.05], at));
+/// }
+/// detected.extend(motion.process([0.0, 0.0, 5.0], Duration::from_millis(200)));
+/// detected.extend(motion.process([0.0, 0.0, 1.0], Duration::from_millis(210)));
+///
+/// assert_eq!(detected, [Event::FreeFall, Event::Impact { g: 5.0 }]);
+/// ```
+pub struct MotionEvents {
+    detector: Detector,
+    period: Duration,
+    bus: EventBus,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for MotionEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MotionEvents {
+    /// Creates a stopped detector with the default thresholds, publishing on the
+    /// [process-wide bus](crate::events::global).
+    pub fn new() -> Self {
+        MotionEvents {
+            detector: Detector {
+                free_fall_g: 0.3,
+                free_fall_duration: Duration::from_millis(100),
+                impact_g: 3.0,
+                shake_g: 1.8,
+                shake_count: 4,
+                shake_window: Duration::from_secs(1),
+                falling_since: None,
+                free_fall_reported: false,
+                impact_peak: None,
+                shake_above: false,
+                swings: VecDeque::new(),
+            },
+            period: Duration::from_millis(5),
+            bus: events::global().clone(),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Reports free fall once the magnitude stays below `threshold_g` for `duration`.
+    pub fn with_free_fall(mut self, threshold_g: f32, duration: Duration) -> Self {
+        self.detector.free_fall_g = threshold_g;
+        self.detector.free_fall_duration = duration;
+        self
+    }
+
+    /// Reports impacts whose magnitude exceeds `threshold_g`.
+    pub fn with_impact(mut self, threshold_g: f32) -> Self {
+        self.detector.impact_g = threshold_g;
+        self
+    }
+
+    /// Reports shaking once the magnitude rises above `threshold_g` `count` times within
+    /// `window`.
+    pub fn with_shake(mut self, threshold_g: f32, count: usize, window: Duration) -> Self {
+        self.detector.shake_g = threshold_g;
+        self.detector.shake_count = count.max(1);
+        self.detector.shake_window = window;
+        self
+    }
+
+    /// Sets the accelerometer polling rate.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn with_rate(mut self, rate_hz: f32) -> Self {
+        self.period = period_from_rate(rate_hz);
+        self
+    }
+
+    /// Publishes on `bus` instead of the process-wide bus.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Feeds one accelerometer sample in g, taken at `timestamp` since an arbitrary start, and
+    /// returns the events it completes. Nothing is published.
+    pub fn process(&mut self, accel: [f32; 3], timestamp: Duration) -> Vec<Event> {
+        self.detector.process(accel, timestamp)
+    }
+
+    /// Returns `true` while the detection thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts polling the accelerometer and publishing events. Does nothing if it is already
+    /// running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!("Starting motion event detection at {:.1} Hz", 1.0 / self.period.as_secs_f32());
+        self.running.store(true, Ordering::Release);
+
+        let mut detector = self.detector.clone();
+        detector.reset();
+        let period = self.period;
+        let bus = self.bus.clone();
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-motion".into())
+                .spawn(move || {
+                    let started = std::time::Instant::now();
+                    let mut ticker = Ticker::new(period);
+                    let mut accel = [0.0f32; 3];
+
+                    while running.load(Ordering::Acquire) {
+                        if read_accel(&mut accel) == 0 {
+                            for event in detector.process(accel, started.elapsed()) {
+                                debug!("Motion event {:?}", event);
+                                bus.publish(event);
+                            }
+                        }
+                        ticker.wait();
+                    }
+
+                    debug!("Motion event thread exited");
+                })
+                .expect("Failed to spawn motion event thread"),
+        );
+
+        self
+    }
+
+    /// Stops the detection thread and waits for it to exit.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Motion event detection stopped");
+        }
+
+        self
+    }
+}
+
+impl Drop for MotionEvents {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,182 @@
+use crate::log_level::mpu::info;
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+type Matrix = [[f32; 3]; 3];
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// A signed axis of the MPU6500.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Axis {
+    /// The +X axis.
+    X,
+    /// The −X axis.
+    NegX,
+    /// The +Y axis.
+    Y,
+    /// The −Y axis.
+    NegY,
+    /// The +Z axis.
+    Z,
+    /// The −Z axis.
+    NegZ,
+}
+
+impl Axis {
+    pub(crate) fn unit(self) -> [f32; 3] {
+        match self {
+            Axis::X => [1.0, 0.0, 0.0],
+            Axis::NegX => [-1.0, 0.0, 0.0],
+            Axis::Y => [0.0, 1.0, 0.0],
+            Axis::NegY => [0.0, -1.0, 0.0],
+            Axis::Z => [0.0, 0.0, 1.0],
+            Axis::NegZ => [0.0, 0.0, -1.0],
+        }
+    }
+}
+
+/// How the board is mounted in the robot, see [`set_mounting`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Orientation {
+    /// The sensor axes are the robot axes.
+    #[default]
+    Identity,
+    /// The sensor axes pointing along the robot's X, Y and Z axes, in that order.
+    ///
+    /// The axes must form a right-handed frame, as any physical mounting does.
+    Axes([Axis; 3]),
+    /// A rotation matrix taking sensor vectors to robot vectors. Use this for boards mounted
+    /// at an angle other than a multiple of 90°.
+    Matrix([[f32; 3]; 3]),
+}
+
+impl Orientation {
+    /// The board is mounted upside down, turned over its X axis.
+    pub const UPSIDE_DOWN: Orientation = Orientation::Axes([Axis::X, Axis::NegY, Axis::NegZ]);
+
+    /// Returns the rotation matrix taking sensor vectors to robot vectors.
+    pub fn matrix(&self) -> [[f32; 3]; 3] {
+        match *self {
+            Orientation::Identity => IDENTITY,
+            Orientation::Axes(axes) => axes.map(Axis::unit),
+            Orientation::Matrix(matrix) => matrix,
+        }
+    }
+
+    /// Returns `true` for the identity mounting, which needs no remapping.
+    pub fn is_identity(&self) -> bool {
+        self.matrix() == IDENTITY
+    }
+
+    /// Converts an acceleration or angular rate from the sensor frame to the robot frame.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::mpu::{Axis, Orientation};
+    ///
+    /// // Standing on its edge: the sensor's +Z points forward, its +X up.
+    /// let mounting = Orientation::Axes([Axis::Z, Axis::Y, Axis::X]);
+    /// assert_eq!(mounting.remap([1.0, 0.0, 0.0]), [0.0, 0.0, 1.0]);
+    /// ```
+    pub fn remap(&self, vector: [f32; 3]) -> [f32; 3] {
+        mul_vector(&self.matrix(), vector)
+    }
+
+    /// Converts pitch, roll and yaw in degrees, as read by
+    /// [`mpu6500_get_attitude`](super::mpu6500_get_attitude), from the sensor frame to the
+    /// robot frame.
+    pub fn remap_attitude(&self, attitude: [f32; 3]) -> [f32; 3] {
+        let sensor = attitude_matrix(attitude);
+        let mounting = self.matrix();
+
+        // The robot's orientation is the sensor's orientation undone by the mounting.
+        let mut robot = [[0.0f32; 3]; 3];
+        for (row, sensor_row) in robot.iter_mut().zip(sensor) {
+            for (value, mounting_row) in row.iter_mut().zip(mounting) {
+                *value = sensor_row.iter().zip(mounting_row).map(|(a, b)| a * b).sum();
+            }
+        }
+
+        let pitch = robot[2][1].atan2(robot[2][2]);
+        let roll = (-robot[2][0]).clamp(-1.0, 1.0).asin();
+        let yaw = robot[1][0].atan2(robot[0][0]);
+        [pitch, roll, yaw].map(f32::to_degrees)
+    }
+}
+
+fn mul_vector(matrix: &Matrix, vector: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row.iter().zip(vector).map(|(a, b)| a * b).sum())
+}
+
+/// Returns the rotation matrix taking sensor vectors to the world frame for pitch about X,
+/// roll about Y and yaw about Z in degrees, applied in the order yaw, roll, pitch.
+pub(crate) fn attitude_matrix(attitude: [f32; 3]) -> [[f32; 3]; 3] {
+    let [pitch, roll, yaw] = attitude.map(f32::to_radians);
+    let (sx, cx) = pitch.sin_cos();
+    let (sy, cy) = roll.sin_cos();
+    let (sz, cz) = yaw.sin_cos();
+
+    // Rz(yaw) * Ry(roll) * Rx(pitch)
+    [
+        [cz * cy, cz * sy * sx - sz * cx, cz * sy * cx + sz * sx],
+        [sz * cy, sz * sy * sx + cz * cx, sz * sy * cx - cz * sx],
+        [-sy, cy * sx, cy * cx],
+    ]
+}
+
+/// Rotates `vector` from the sensor frame into the world frame of `attitude`.
+pub(crate) fn to_world(vector: [f32; 3], attitude: [f32; 3]) -> [f32; 3] {
+    mul_vector(&attitude_matrix(attitude), vector)
+}
+
+static MOUNTING: Lazy<RwLock<Orientation>> = Lazy::new(|| RwLock::new(Orientation::Identity));
+
+/// Returns the mounting applied to the MPU6500 readings.
+pub fn mounting() -> Orientation {
+    *MOUNTING.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets how the board is mounted, returning the previous setting.
+///
+/// From then on, [`mpu6500_get_accel`](super::mpu6500_get_accel),
+/// [`mpu6500_get_gyro`](super::mpu6500_get_gyro) and
+/// [`mpu6500_get_attitude`](super::mpu6500_get_attitude) report in the robot's frame, and
+/// with them everything built on these reads. A level robot then reads +1 g on Z and zero
+/// pitch and roll however the board is mounted.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::mpu::{self, Orientation};
+///
+/// mpu::set_mounting(Orientation::UPSIDE_DOWN);
+///
+/// let mut accel = [0.0f32; 3];
+/// mpu::mpu6500_get_accel(&mut accel);
+/// println!("Z: {:.2} g", accel[2]); // about +1.0 on a level robot
+/// ```
+pub fn set_mounting(orientation: Orientation) -> Orientation {
+    info!("MPU6500 mounting set to {:?}", orientation);
+    let mut guard = MOUNTING.write().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *guard, orientation)
+}
+
+/// Applies the mounting to a vector reading in place.
+pub(crate) fn remap_in_place(data: &mut [f32; 3]) {
+    let mounting = mounting();
+    if !mounting.is_identity() {
+        *data = mounting.remap(*data);
+    }
+}
+
+/// Applies the mounting to an attitude reading in place.
+pub(crate) fn remap_attitude_in_place(data: &mut [f32; 3]) {
+    let mounting = mounting();
+    if !mounting.is_identity() {
+        *data = mounting.remap_attitude(*data);
+    }
+}
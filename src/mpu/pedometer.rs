@@ -0,0 +1,406 @@
+use super::read_accel;
+use crate::extern_lib::symbol_or_return;
+use crate::sampler::{Ticker, period_from_rate};
+use crate::stats;
+use crate::log_level::mpu::{debug, info};
+use std::collections::VecDeque;
+use std::ffi::c_ulong;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Where a [`Pedometer`] gets its steps from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepSource {
+    /// Peak detection on the accelerometer magnitude, done in this crate.
+    Accelerometer,
+    /// The step counter of the DMP firmware, read through `dmp_get_pedometer_step_count`.
+    Dmp,
+}
+
+/// Reads the DMP step counter, or `None` if the call fails.
+fn read_dmp_steps() -> Option<u64> {
+    unsafe {
+        let call = stats::start("dmp_get_pedometer_step_count");
+        let dmp_get_pedometer_step_count =
+            symbol_or_return!(dmp_get_pedometer_step_count: unsafe extern "C" fn(*mut c_ulong) -> i32, None);
+
+        let mut count: c_ulong = 0;
+        (call.finish(dmp_get_pedometer_step_count(&mut count)) == 0).then_some(count as u64)
+    }
+}
+
+/// Finds steps as peaks of the accelerometer magnitude above its slowly tracked mean.
+#[derive(Debug, Clone)]
+struct StepDetector {
+    threshold_g: f32,
+    min_interval: Duration,
+
+    last_timestamp: Option<Duration>,
+    smoothed: f32,
+    baseline: f32,
+    armed: bool,
+    last_step: Option<Duration>,
+}
+
+impl StepDetector {
+    /// Time constant of the smoothing that removes sensor noise.
+    const SMOOTHING: Duration = Duration::from_millis(40);
+    /// Time constant of the baseline that follows gravity and slow tilts.
+    const BASELINE: Duration = Duration::from_secs(1);
+
+    fn reset(&mut self) {
+        self.last_timestamp = None;
+        self.armed = false;
+        self.last_step = None;
+    }
+
+    fn process(&mut self, accel: [f32; 3], timestamp: Duration) -> bool {
+        let magnitude = accel.iter().map(|a| a * a).sum::<f32>().sqrt();
+
+        let Some(last) = self.last_timestamp.replace(timestamp) else {
+            self.smoothed = magnitude;
+            self.baseline = magnitude;
+            return false;
+        };
+        let dt = timestamp.saturating_sub(last).as_secs_f32();
+        let alpha = |tau: Duration| 1.0 - (-dt / tau.as_secs_f32()).exp();
+        self.smoothed += alpha(Self::SMOOTHING) * (magnitude - self.smoothed);
+        self.baseline += alpha(Self::BASELINE) * (magnitude - self.baseline);
+
+        // A step is the rise above the threshold; the signal has to swing back below the
+        // baseline before the next one counts.
+        let deviation = self.smoothed - self.baseline;
+        if deviation < 0.0 {
+            self.armed = true;
+            return false;
+        }
+        if !self.armed || deviation < self.threshold_g {
+            return false;
+        }
+        if self.last_step.is_some_and(|step| timestamp.saturating_sub(step) < self.min_interval) {
+            return false;
+        }
+
+        self.armed = false;
+        self.last_step = Some(timestamp);
+        true
+    }
+}
+
+/// Counted steps and the detection state, shared with the polling thread.
+#[derive(Debug)]
+struct StepState {
+    detector: StepDetector,
+    dmp_count: Option<u64>,
+    steps: u64,
+    recent: VecDeque<Duration>,
+    since: Option<Duration>,
+    now: Duration,
+}
+
+impl StepState {
+    fn reset(&mut self) {
+        self.detector.reset();
+        self.dmp_count = None;
+        self.steps = 0;
+        self.recent.clear();
+        self.since = None;
+        self.now = Duration::ZERO;
+    }
+
+    fn record(&mut self, steps: u64, timestamp: Duration, window: Duration) {
+        self.since.get_or_insert(timestamp);
+        self.now = self.now.max(timestamp);
+        self.steps += steps;
+        self.recent.extend((0..steps).map(|_| timestamp));
+        while self.recent.front().is_some_and(|&step| self.now.saturating_sub(step) > window) {
+            self.recent.pop_front();
+        }
+    }
+
+    fn process_accel(&mut self, accel: [f32; 3], timestamp: Duration, window: Duration) -> bool {
+        let step = self.detector.process(accel, timestamp);
+        self.record(step as u64, timestamp, window);
+        step
+    }
+
+    fn process_dmp(&mut self, count: u64, timestamp: Duration, window: Duration) {
+        // The first reading after a reset only sets the reference. A lower count means the
+        // DMP counter was reset elsewhere.
+        let steps = match self.dmp_count.replace(count) {
+            Some(previous) if count >= previous => count - previous,
+            _ => 0,
+        };
+        self.record(steps, timestamp, window);
+    }
+}
+
+/// Counts steps of a walking or running robot or person carrying the board.
+///
+/// Two sources are available:
+///
+/// - [`Pedometer::new`] detects steps in software, as peaks of the accelerometer magnitude
+///   above its running mean. It polls [`mpu6500_get_accel`](super::mpu6500_get_accel) at 50 Hz and works regardless of
+///   how the board is mounted.
+/// - [`Pedometer::dmp`] reads the step counter the DMP firmware keeps on its own. It needs
+///   almost no CPU, but the DMP only starts counting after several consecutive steps and
+///   its counter is shared by all users of the MPU.
+///
+/// Both count the steps since [`start`](Pedometer::start) or the last
+/// [`reset`](Pedometer::reset), and report the cadence over the last ten seconds.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use std::time::Duration;
+/// use uptechstar_rs::mpu::{self, Pedometer};
+///
+/// mpu::mpu6500_open();
+///
+/// let mut pedometer = Pedometer::new();
+/// pedometer.start();
+///
+/// loop {
+///     thread::sleep(Duration::from_secs(1));
+///     println!("{} steps, {:.0} steps/min", pedometer.steps(), pedometer.cadence());
+/// }
+/// ```
+///
+/// Counting can also run on recorded samples without the background thread:
+///
+/// ```rust
+/// use std::f32::consts::PI;
+/// use std::time::Duration;
+/// use uptechstar_rs::mpu::Pedometer;
+///
+/// let mut pedometer = Pedometer::new();
+///
+/// // Ten seconds of walking at two steps per second, sampled at 50 Hz.
+/// for i in 0..500u64 {
+///     let t = i as f32 / 50.0;
+///     let z = 1.0 + 0.4 * (2.0 * PI * 2.0 * t).sin();
+///     pedometer.process([0.0, 0.0, z], Duration::from_millis(i * 20));
+/// }
+///
+/// assert!((19..=20).contains(&pedometer.steps()));
+/// assert!((pedometer.cadence() - 120.0).abs() < 10.0);
+/// ```
+pub struct Pedometer {
+    source: StepSource,
+    period: Duration,
+    window: Duration,
+    state: Arc<Mutex<StepState>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Default for Pedometer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pedometer {
+    fn from_source(source: StepSource, period: Duration) -> Self {
+        Pedometer {
+            source,
+            period,
+            window: Duration::from_secs(10),
+            state: Arc::new(Mutex::new(StepState {
+                detector: StepDetector {
+                    threshold_g: 0.15,
+                    min_interval: Duration::from_millis(250),
+                    last_timestamp: None,
+                    smoothed: 0.0,
+                    baseline: 0.0,
+                    armed: false,
+                    last_step: None,
+                },
+                dmp_count: None,
+                steps: 0,
+                recent: VecDeque::new(),
+                since: None,
+                now: Duration::ZERO,
+            })),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Creates a stopped pedometer detecting steps in the accelerometer data.
+    pub fn new() -> Self {
+        Self::from_source(StepSource::Accelerometer, Duration::from_millis(20))
+    }
+
+    /// Creates a stopped pedometer reading the DMP step counter, polled five times a second.
+    pub fn dmp() -> Self {
+        Self::from_source(StepSource::Dmp, Duration::from_millis(200))
+    }
+
+    /// Counts a step once the smoothed magnitude rises `threshold_g` above its mean. The
+    /// default of 0.15 g suits a hand-held board; lower it for smooth gaits.
+    ///
+    /// Only used by the accelerometer source.
+    pub fn with_threshold(self, threshold_g: f32) -> Self {
+        self.lock().detector.threshold_g = threshold_g;
+        self
+    }
+
+    /// Ignores steps closer than `interval` to the previous one. The default of 250ms allows
+    /// up to 240 steps/min.
+    ///
+    /// Only used by the accelerometer source.
+    pub fn with_min_interval(self, interval: Duration) -> Self {
+        self.lock().detector.min_interval = interval;
+        self
+    }
+
+    /// Sets the polling rate of the accelerometer or the DMP counter.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn with_rate(mut self, rate_hz: f32) -> Self {
+        self.period = period_from_rate(rate_hz);
+        self
+    }
+
+    /// Sets the time span [`cadence`](Pedometer::cadence) is averaged over.
+    pub fn with_cadence_window(mut self, window: Duration) -> Self {
+        self.window = window.max(Duration::from_secs(1));
+        self
+    }
+
+    /// Returns where the steps come from.
+    pub fn source(&self) -> StepSource {
+        self.source
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StepState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Feeds one accelerometer sample in g, taken at `timestamp` since an arbitrary start, and
+    /// returns `true` if it completes a step.
+    ///
+    /// This is what the background thread of the accelerometer source does; don't mix both.
+    pub fn process(&mut self, accel: [f32; 3], timestamp: Duration) -> bool {
+        let window = self.window;
+        self.lock().process_accel(accel, timestamp, window)
+    }
+
+    /// Returns the number of steps since the start or the last [`reset`](Pedometer::reset).
+    pub fn steps(&self) -> u64 {
+        self.lock().steps
+    }
+
+    /// Returns the current cadence in steps per minute, averaged over the cadence window or
+    /// the time since the start if that is shorter.
+    pub fn cadence(&self) -> f32 {
+        let state = self.lock();
+        let Some(since) = state.since else {
+            return 0.0;
+        };
+        let span = state.now.saturating_sub(since).min(self.window);
+        if span.is_zero() {
+            return 0.0;
+        }
+        state.recent.len() as f32 * 60.0 / span.as_secs_f32()
+    }
+
+    /// Sets the step count back to zero and clears the cadence.
+    ///
+    /// The DMP counter itself is left untouched; counting continues from its current value.
+    pub fn reset(&self) {
+        self.lock().reset();
+        debug!("Pedometer reset");
+    }
+
+    /// Returns `true` while the polling thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Resets the count and starts polling the step source. Does nothing if it is already
+    /// running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!("Starting {:?} pedometer at {:.1} Hz", self.source, 1.0 / self.period.as_secs_f32());
+        self.reset();
+        self.running.store(true, Ordering::Release);
+
+        let source = self.source;
+        let period = self.period;
+        let window = self.window;
+        let state = Arc::clone(&self.state);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-pedometer".into())
+                .spawn(move || {
+                    let started = Instant::now();
+                    let mut ticker = Ticker::new(period);
+                    let mut accel = [0.0f32; 3];
+
+                    while running.load(Ordering::Acquire) {
+                        let timestamp = started.elapsed();
+                        match source {
+                            StepSource::Accelerometer => {
+                                if read_accel(&mut accel) == 0 {
+                                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                                    if state.process_accel(accel, timestamp, window) {
+                                        debug!("Step {} at {:?}", state.steps, timestamp);
+                                    }
+                                }
+                            }
+                            StepSource::Dmp => {
+                                if let Some(count) = read_dmp_steps() {
+                                    state
+                                        .lock()
+                                        .unwrap_or_else(|e| e.into_inner())
+                                        .process_dmp(count, timestamp, window);
+                                }
+                            }
+                        }
+                        ticker.wait();
+                    }
+
+                    debug!("Pedometer thread exited");
+                })
+                .expect("Failed to spawn pedometer thread"),
+        );
+
+        self
+    }
+
+    /// Stops the polling thread and waits for it to exit. The count is kept.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Pedometer stopped");
+        }
+
+        self
+    }
+}
+
+impl Drop for Pedometer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,85 @@
+use super::fifo::reset_fifo;
+use crate::extern_lib::symbol_or_return;
+use crate::stats;
+use crate::log_level::mpu::info;
+
+/// `sensors` bits of the motion driver.
+const INV_XYZ_GYRO: u8 = 0x70;
+const INV_XYZ_ACCEL: u8 = 0x08;
+
+fn set_sensors(sensors: u8) -> i32 {
+    unsafe {
+        let call = stats::start("mpu_set_sensors");
+        let mpu_set_sensors = symbol_or_return!(mpu_set_sensors: unsafe extern "C" fn(u8) -> i32, -1);
+
+        call.finish(mpu_set_sensors(sensors))
+    }
+}
+
+/// Puts the MPU6500 into sleep mode, turning off the accelerometer, the gyroscope and the
+/// DMP clock. The chip then draws a few µA instead of about 3.5mA.
+///
+/// Reads fail or return stale data until [`wake`] is called. The DMP keeps its firmware and
+/// configuration while asleep.
+///
+/// Returns:
+///   0 on success, or the error code of the motion driver.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::thread;
+/// use std::time::Duration;
+/// use uptechstar_rs::mpu;
+///
+/// mpu::mpu6500_open();
+///
+/// // Log one sample a minute and sleep in between.
+/// loop {
+///     mpu::wake();
+///     thread::sleep(Duration::from_millis(50)); // let the sensors settle
+///     if let Ok(sample) = mpu::mpu6500_get_sample() {
+///         println!("{:?}", sample);
+///     }
+///     mpu::sleep();
+///     thread::sleep(Duration::from_secs(60));
+/// }
+/// ```
+pub fn sleep() -> i32 {
+    info!("Putting MPU6500 to sleep");
+    set_sensors(0)
+}
+
+/// Wakes the MPU6500 from [`sleep`], turning the accelerometer and the gyroscope back on,
+/// and discards the FIFO contents from before the sleep.
+///
+/// The gyroscope needs about 35ms to start up, so the first readings after waking are
+/// inaccurate.
+///
+/// Returns:
+///   0 on success, or the error code of the motion driver.
+pub fn wake() -> i32 {
+    info!("Waking MPU6500");
+    let result = set_sensors(INV_XYZ_GYRO | INV_XYZ_ACCEL);
+    if result != 0 {
+        return result;
+    }
+    reset_fifo()
+}
+
+/// Returns whether the MPU6500 is powered on, i.e. not in [`sleep`] mode.
+///
+/// Returns:
+///   The power state, or the error code of the read.
+pub fn is_awake() -> Result<bool, i32> {
+    unsafe {
+        let call = stats::start("mpu_get_power_state");
+        let mpu_get_power_state = symbol_or_return!(mpu_get_power_state: unsafe extern "C" fn(*mut u8) -> i32, Err(-1));
+
+        let mut power_on: u8 = 0;
+        match call.finish(mpu_get_power_state(&mut power_on)) {
+            0 => Ok(power_on != 0),
+            code => Err(code),
+        }
+    }
+}
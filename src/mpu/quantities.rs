@@ -0,0 +1,56 @@
+//! MPU6500 readings as typed quantities from the [`uom`] crate.
+//!
+//! The quantities carry their unit, so they are independent of [`set_units`](super::set_units).
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uom::si::{acceleration::meter_per_second_squared, angle::radian};
+//! use uptechstar_rs::mpu::quantities;
+//!
+//! if let Ok([_, _, z]) = quantities::accel() {
+//!     println!("Z: {:.2} m/s²", z.get::<meter_per_second_squared>());
+//! }
+//! if let Ok([_, _, yaw]) = quantities::attitude() {
+//!     println!("Yaw: {:.3} rad", yaw.get::<radian>());
+//! }
+//! ```
+
+use uom::si::acceleration::standard_gravity;
+use uom::si::angle::degree;
+use uom::si::angular_velocity::degree_per_second;
+use uom::si::f32::{Acceleration, Angle, AngularVelocity};
+
+/// Reads the acceleration, like [`mpu6500_get_accel`](super::mpu6500_get_accel).
+///
+/// Returns the error code of the read on failure.
+pub fn accel() -> Result<[Acceleration; 3], i32> {
+    let mut accel = [0.0f32; 3];
+    match super::read_accel(&mut accel) {
+        0 => Ok(accel.map(Acceleration::new::<standard_gravity>)),
+        code => Err(code),
+    }
+}
+
+/// Reads the angular rates, like [`mpu6500_get_gyro`](super::mpu6500_get_gyro).
+///
+/// Returns the error code of the read on failure.
+pub fn gyro() -> Result<[AngularVelocity; 3], i32> {
+    let mut gyro = [0.0f32; 3];
+    match super::read_gyro(&mut gyro) {
+        0 => Ok(gyro.map(AngularVelocity::new::<degree_per_second>)),
+        code => Err(code),
+    }
+}
+
+/// Reads pitch, roll and yaw, like
+/// [`mpu6500_get_attitude`](super::mpu6500_get_attitude).
+///
+/// Returns the error code of the read on failure.
+pub fn attitude() -> Result<[Angle; 3], i32> {
+    let mut attitude = [0.0f32; 3];
+    match super::read_attitude(&mut attitude) {
+        0 => Ok(attitude.map(Angle::new::<degree>)),
+        code => Err(code),
+    }
+}
@@ -0,0 +1,78 @@
+use crate::extern_lib::symbol_or_return;
+use crate::stats;
+use crate::log_level::mpu::debug;
+
+/// Reads one register of the MPU6500.
+///
+/// Register addresses are those of the MPU-6500 register map, e.g. `0x75` for `WHO_AM_I`.
+///
+/// Returns:
+///   The register value, or the error code of the read.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::mpu;
+///
+/// mpu::mpu6500_open();
+/// match mpu::read_register(0x75) {
+///     Ok(who_am_i) => println!("WHO_AM_I = {:#04x}", who_am_i),
+///     Err(code) => eprintln!("Register read failed: {}", code),
+/// }
+/// ```
+pub fn read_register(register: u8) -> Result<u8, i32> {
+    unsafe {
+        let call = stats::start("mpu6500_read_byte");
+        let mpu6500_read_byte = symbol_or_return!(mpu6500_read_byte: unsafe extern "C" fn(u8) -> i32, Err(-1));
+
+        match call.finish(mpu6500_read_byte(register)) {
+            value @ 0..=0xff => Ok(value as u8),
+            code => Err(code.min(-1)),
+        }
+    }
+}
+
+/// Writes one register of the MPU6500.
+///
+/// This bypasses the motion driver, which caches parts of the chip configuration. Writing
+/// registers it manages, such as the full-scale ranges, the sample rate or the power
+/// management, leaves that cache stale and can break the DMP until the next
+/// [`mpu6500_open`](super::mpu6500_open).
+///
+/// Returns:
+///   0 on success, or the error code of the write.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::mpu;
+///
+/// mpu::mpu6500_open();
+///
+/// // Wake-on-motion threshold of 4 mg/LSB × 40 = 160 mg.
+/// mpu::write_register(0x1f, 40);
+/// // Compare each sample against the previous one.
+/// mpu::update_register(0x69, 0xc0, 0xc0);
+/// ```
+pub fn write_register(register: u8, value: u8) -> i32 {
+    debug!("MPU6500 register {:#04x} <- {:#04x}", register, value);
+
+    unsafe {
+        let call = stats::start("mpu6500_write_byte");
+        let mpu6500_write_byte = symbol_or_return!(mpu6500_write_byte: unsafe extern "C" fn(u8, u8) -> i32, -1);
+
+        call.finish(mpu6500_write_byte(register, value))
+    }
+}
+
+/// Sets the bits of `register` selected by `mask` to those of `value`, leaving the other
+/// bits unchanged.
+///
+/// Returns:
+///   0 on success, or the error code of the read or the write.
+pub fn update_register(register: u8, mask: u8, value: u8) -> i32 {
+    match read_register(register) {
+        Ok(current) => write_register(register, (current & !mask) | (value & mask)),
+        Err(code) => code,
+    }
+}
@@ -0,0 +1,54 @@
+use crate::backend;
+use crate::log_level::mpu::{info, warn};
+use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
+
+/// The gyroscope range last set successfully, 0 if the DMP default is in use.
+static GYRO_FSR: AtomicU32 = AtomicU32::new(0);
+/// The accelerometer range last set successfully, 0 if the DMP default is in use.
+static ACCEL_FSR: AtomicI32 = AtomicI32::new(0);
+
+pub(super) fn remember_gyro_fsr(fsr: u32) {
+    GYRO_FSR.store(fsr, Ordering::Relaxed);
+}
+
+pub(super) fn remember_accel_fsr(fsr: i32) {
+    ACCEL_FSR.store(fsr, Ordering::Relaxed);
+}
+
+/// Forgets the configured ranges after an explicit initialization restored the defaults.
+pub(super) fn forget() {
+    GYRO_FSR.store(0, Ordering::Relaxed);
+    ACCEL_FSR.store(0, Ordering::Relaxed);
+}
+
+/// Sets the ranges configured before the MPU6500 was re-initialized again, returning `0` on
+/// success or the first failing status code.
+///
+/// The heading offset, mounting and units are applied in software and survive a
+/// re-initialization without this.
+pub(crate) fn reapply_settings() -> i32 {
+    let backend = backend::current();
+
+    let gyro_fsr = GYRO_FSR.load(Ordering::Relaxed);
+    if gyro_fsr != 0 {
+        let result = backend.mpu_set_gyro_fsr(gyro_fsr);
+        if result != 0 {
+            warn!("Failed to restore gyroscope FSR ±{}°/s, status {}", gyro_fsr, result);
+            return result;
+        }
+    }
+
+    let accel_fsr = ACCEL_FSR.load(Ordering::Relaxed);
+    if accel_fsr != 0 {
+        let result = backend.mpu_set_accel_fsr(accel_fsr);
+        if result != 0 {
+            warn!("Failed to restore accelerometer FSR ±{}g, status {}", accel_fsr, result);
+            return result;
+        }
+    }
+
+    if gyro_fsr != 0 || accel_fsr != 0 {
+        info!("Restored MPU6500 full scale ranges after re-initialization");
+    }
+    0
+}
@@ -0,0 +1,138 @@
+use super::MpuSample;
+use crate::log_level::mpu::info;
+use once_cell::sync::Lazy;
+use std::sync::RwLock;
+
+/// Standard gravity in m/s².
+const STANDARD_GRAVITY: f32 = 9.80665;
+
+/// Unit of accelerations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccelUnit {
+    /// Multiples of standard gravity, as read from the MPU6500.
+    #[default]
+    G,
+    /// Meters per second squared.
+    MetersPerSecondSquared,
+}
+
+/// Unit of angular rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AngularRateUnit {
+    /// Degrees per second, as read from the MPU6500.
+    #[default]
+    DegreesPerSecond,
+    /// Radians per second.
+    RadiansPerSecond,
+}
+
+/// Unit of attitude angles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AngleUnit {
+    /// Degrees, as read from the MPU6500.
+    #[default]
+    Degrees,
+    /// Radians.
+    Radians,
+}
+
+/// The units the MPU6500 readings are reported in, see [`set_units`].
+///
+/// The default keeps the units of the sensor: g, °/s and degrees.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::mpu::Units;
+///
+/// let accel = Units::SI.convert_accel([0.0, 0.0, 1.0]);
+/// assert_eq!(accel, [0.0, 0.0, 9.80665]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Units {
+    /// Unit of [`mpu6500_get_accel`](super::mpu6500_get_accel).
+    pub accel: AccelUnit,
+    /// Unit of [`mpu6500_get_gyro`](super::mpu6500_get_gyro).
+    pub gyro: AngularRateUnit,
+    /// Unit of [`mpu6500_get_attitude`](super::mpu6500_get_attitude).
+    pub angle: AngleUnit,
+}
+
+impl Units {
+    /// m/s², rad/s and radians.
+    pub const SI: Units = Units {
+        accel: AccelUnit::MetersPerSecondSquared,
+        gyro: AngularRateUnit::RadiansPerSecond,
+        angle: AngleUnit::Radians,
+    };
+
+    /// Converts an acceleration in g to these units.
+    pub fn convert_accel(&self, accel: [f32; 3]) -> [f32; 3] {
+        match self.accel {
+            AccelUnit::G => accel,
+            AccelUnit::MetersPerSecondSquared => accel.map(|a| a * STANDARD_GRAVITY),
+        }
+    }
+
+    /// Converts an angular rate in °/s to these units.
+    pub fn convert_gyro(&self, gyro: [f32; 3]) -> [f32; 3] {
+        match self.gyro {
+            AngularRateUnit::DegreesPerSecond => gyro,
+            AngularRateUnit::RadiansPerSecond => gyro.map(f32::to_radians),
+        }
+    }
+
+    /// Converts attitude angles in degrees to these units.
+    pub fn convert_attitude(&self, attitude: [f32; 3]) -> [f32; 3] {
+        match self.angle {
+            AngleUnit::Degrees => attitude,
+            AngleUnit::Radians => attitude.map(f32::to_radians),
+        }
+    }
+
+    /// Converts all readings of a sample in the sensor's units to these units.
+    pub fn convert_sample(&self, sample: MpuSample) -> MpuSample {
+        MpuSample {
+            accel: self.convert_accel(sample.accel),
+            gyro: self.convert_gyro(sample.gyro),
+            attitude: self.convert_attitude(sample.attitude),
+        }
+    }
+}
+
+static UNITS: Lazy<RwLock<Units>> = Lazy::new(|| RwLock::new(Units::default()));
+
+/// Returns the units the MPU6500 readings are reported in.
+pub fn units() -> Units {
+    *UNITS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Sets the units of [`mpu6500_get_accel`](super::mpu6500_get_accel),
+/// [`mpu6500_get_gyro`](super::mpu6500_get_gyro),
+/// [`mpu6500_get_attitude`](super::mpu6500_get_attitude) and everything reporting their
+/// readings, such as [`mpu6500_get_sample`](super::mpu6500_get_sample) and the samplers.
+/// Returns the previous setting.
+///
+/// The detectors in this module, such as [`MotionEvents`](super::MotionEvents), keep working
+/// in the sensor's units; their thresholds are unaffected.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use uptechstar_rs::mpu::{self, Units};
+///
+/// mpu::set_units(Units::SI);
+///
+/// let mut gyro = [0.0f32; 3];
+/// mpu::mpu6500_get_gyro(&mut gyro);
+/// println!("Yaw rate: {:.3} rad/s", gyro[2]);
+/// ```
+pub fn set_units(units: Units) -> Units {
+    info!("MPU6500 units set to {:?}", units);
+    let mut guard = UNITS.write().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *guard, units)
+}
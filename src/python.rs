@@ -0,0 +1,322 @@
+//! Python bindings, built into the `uptechstar` extension module with `maturin`.
+//!
+//! The module mirrors the safe Rust API closely enough that scripts ported from the original
+//! Python library only need new names:
+//!
+//! ```python
+//! import uptechstar as ut
+//!
+//! board = ut.Board("board.toml")
+//! print(board.adc_value("battery"))
+//!
+//! screen = ut.Screen(ut.HORIZONTAL)
+//! screen.fill_screen(ut.rgb(0, 0, 0)).put_string(0, 0, "hello").refresh()
+//!
+//! ut.mpu_open()
+//! print(ut.mpu_sample().attitude)
+//! ```
+//!
+//! Failing hardware calls raise `uptechstar.UptechError`; invalid pin indices and font sizes
+//! raise `ValueError`.
+
+use crate::adc_io::{self, AdcFrame, IoFrame};
+use crate::board::{Board, BoardConfig};
+use crate::display::{Color, FontSize, Screen, ScreenDirection};
+use crate::mpu;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+
+create_exception!(uptechstar, UptechError, PyException, "A hardware call or the board setup failed.");
+
+fn to_py(e: crate::UptechError) -> PyErr {
+    UptechError::new_err(e.to_string())
+}
+
+fn check(operation: &'static str, code: i32) -> PyResult<()> {
+    crate::UptechError::check(operation, code).map_err(to_py)
+}
+
+fn pin(index: usize) -> PyResult<usize> {
+    if index < IoFrame::PINS as usize {
+        Ok(index)
+    } else {
+        Err(PyValueError::new_err(format!("IO pin index must be in 0..8, got {}", index)))
+    }
+}
+
+/// An initialized board.
+#[pyclass(name = "Board", unsendable)]
+struct PyBoard(Board);
+
+#[pymethods]
+impl PyBoard {
+    /// Initializes the board from a TOML configuration file, or with the default
+    /// configuration if no path is given.
+    #[new]
+    #[pyo3(signature = (config_path = None))]
+    fn new(config_path: Option<&str>) -> PyResult<Self> {
+        let config = match config_path {
+            Some(path) => BoardConfig::from_file(path).map_err(to_py)?,
+            None => BoardConfig::default(),
+        };
+        Board::init(config).map(PyBoard).map_err(to_py)
+    }
+
+    /// Reads a named ADC channel with its calibration applied.
+    fn adc_value(&self, name: &str) -> PyResult<f32> {
+        self.0.adc_value(name).map_err(to_py)
+    }
+
+    /// Reads all named ADC channels as `(name, value)` pairs.
+    fn adc_values(&self) -> PyResult<Vec<(String, f32)>> {
+        let values = self.0.adc_values().map_err(to_py)?;
+        Ok(values.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+    }
+
+    /// Puts the MPU and the screen to sleep.
+    fn low_power(&mut self) -> PyResult<()> {
+        self.0.low_power().map_err(to_py)
+    }
+
+    /// Wakes the MPU and the screen.
+    fn wake(&mut self) -> PyResult<()> {
+        self.0.wake().map_err(to_py)
+    }
+
+    /// Returns the lines of the boot report.
+    fn boot_report(&self) -> Vec<String> {
+        self.0.boot_report().lines()
+    }
+
+    /// Shows the boot report on the screen and returns its lines.
+    fn show_boot_screen(&mut self) -> Vec<String> {
+        self.0.show_boot_screen().lines()
+    }
+}
+
+/// The LCD and the two LEDs. Drawing methods return the screen for chained calls.
+#[pyclass(name = "Screen", unsendable)]
+struct PyScreen(Screen);
+
+#[pymethods]
+impl PyScreen {
+    /// Opens the LCD in `direction`, `VERTICAL` or `HORIZONTAL`, or only the LEDs if no
+    /// direction is given.
+    #[new]
+    #[pyo3(signature = (direction = None))]
+    fn new(direction: Option<u8>) -> PyResult<Self> {
+        let direction = match direction {
+            None => None,
+            Some(1) => Some(ScreenDirection::Vertical),
+            Some(2) => Some(ScreenDirection::Horizontal),
+            Some(other) => return Err(PyValueError::new_err(format!("Unknown screen direction {}", other))),
+        };
+        Ok(PyScreen(Screen::new(direction)))
+    }
+
+    fn refresh(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.0.refresh();
+        slf
+    }
+
+    fn close(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.0.close();
+        slf
+    }
+
+    /// Selects one of the 15 fonts, from 0 (4x6) to 14 (24x40).
+    fn set_font_size(mut slf: PyRefMut<'_, Self>, font: usize) -> PyResult<PyRefMut<'_, Self>> {
+        let font = *FontSize::ALL
+            .get(font)
+            .ok_or_else(|| PyValueError::new_err(format!("Font size must be in 0..15, got {}", font)))?;
+        slf.0.set_font_size(font);
+        Ok(slf)
+    }
+
+    fn set_fore_color(mut slf: PyRefMut<'_, Self>, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.set_fore_color(color);
+        slf
+    }
+
+    fn set_back_color(mut slf: PyRefMut<'_, Self>, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.set_back_color(color);
+        slf
+    }
+
+    fn set_led_color(mut slf: PyRefMut<'_, Self>, index: i32, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.set_led_color(index, color);
+        slf
+    }
+
+    fn set_all_leds_off(mut slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf.0.set_all_leds_off();
+        slf
+    }
+
+    fn fill_screen(mut slf: PyRefMut<'_, Self>, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.fill_screen(color);
+        slf
+    }
+
+    fn put_string<'py>(mut slf: PyRefMut<'py, Self>, x: i32, y: i32, text: &str) -> PyRefMut<'py, Self> {
+        slf.0.put_string(x, y, text);
+        slf
+    }
+
+    fn print<'py>(mut slf: PyRefMut<'py, Self>, text: &str) -> PyRefMut<'py, Self> {
+        slf.0.print(text);
+        slf
+    }
+
+    fn fill_frame(mut slf: PyRefMut<'_, Self>, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.fill_frame(x1, y1, x2, y2, color);
+        slf
+    }
+
+    fn draw_frame(mut slf: PyRefMut<'_, Self>, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.draw_frame(x1, y1, x2, y2, color);
+        slf
+    }
+
+    fn draw_line(mut slf: PyRefMut<'_, Self>, x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.draw_line(x1, y1, x2, y2, color);
+        slf
+    }
+
+    fn draw_pixel(mut slf: PyRefMut<'_, Self>, x: i32, y: i32, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.draw_pixel(x, y, color);
+        slf
+    }
+
+    fn draw_circle(mut slf: PyRefMut<'_, Self>, x: i32, y: i32, r: i32, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.draw_circle(x, y, r, color);
+        slf
+    }
+
+    fn fill_circle(mut slf: PyRefMut<'_, Self>, x: i32, y: i32, r: i32, color: u32) -> PyRefMut<'_, Self> {
+        slf.0.fill_circle(x, y, r, color);
+        slf
+    }
+}
+
+/// A combined MPU6500 reading.
+#[pyclass(name = "MpuSample", frozen, get_all)]
+struct PyMpuSample {
+    /// Acceleration in g.
+    accel: [f32; 3],
+    /// Angular velocity in degrees per second.
+    gyro: [f32; 3],
+    /// Pitch, roll and yaw in degrees.
+    attitude: [f32; 3],
+}
+
+#[pymethods]
+impl PyMpuSample {
+    fn __repr__(&self) -> String {
+        format!("MpuSample(accel={:?}, gyro={:?}, attitude={:?})", self.accel, self.gyro, self.attitude)
+    }
+}
+
+/// Packs red, green and blue components into a color value.
+#[pyfunction]
+fn rgb(r: u8, g: u8, b: u8) -> u32 {
+    Color::new_color(r, g, b)
+}
+
+/// Opens the ADC-IO peripheral and returns how often it has been opened.
+#[pyfunction]
+fn adc_open() -> PyResult<i32> {
+    let count = adc_io::adc_open();
+    if count < 0 {
+        return Err(UptechError::new_err(format!("adc_io_open failed with status {}", count)));
+    }
+    Ok(count)
+}
+
+/// Closes the ADC-IO peripheral.
+#[pyfunction]
+fn adc_close() -> PyResult<()> {
+    check("adc_io_close", adc_io::adc_close())
+}
+
+/// Reads all 10 ADC channels.
+#[pyfunction]
+fn adc_read() -> PyResult<[i32; AdcFrame::CHANNELS]> {
+    adc_io::adc_get_frame()
+        .map(|frame| frame.0)
+        .map_err(UptechError::new_err)
+}
+
+/// Reads the levels of all IO pins as a bitmask.
+#[pyfunction]
+fn io_read() -> u8 {
+    adc_io::io_get_frame().0
+}
+
+/// Reads the level of one IO pin.
+#[pyfunction]
+fn io_level(index: usize) -> PyResult<u8> {
+    Ok(adc_io::get_io_level(pin(index)?))
+}
+
+/// Sets the output levels of all IO pins from a bitmask.
+#[pyfunction]
+fn set_io_levels(levels: u32) -> PyResult<()> {
+    check("adc_io_SetAll", adc_io::set_all_io_levels(levels))
+}
+
+/// Toggles the output level of one IO pin.
+#[pyfunction]
+fn flip_io(index: usize) -> PyResult<()> {
+    check("adc_io_Set", adc_io::flip_io_level(pin(index)? as u32))
+}
+
+/// Sets the mode of one IO pin.
+#[pyfunction]
+fn set_io_mode(index: usize, mode: u8) -> PyResult<()> {
+    check("adc_io_ModeSet", adc_io::set_io_mode(pin(index)? as u32, mode))
+}
+
+/// Initializes the MPU6500.
+#[pyfunction]
+fn mpu_open() -> PyResult<()> {
+    check("mpu6500_dmp_init", mpu::mpu6500_open())
+}
+
+/// Reads acceleration, angular velocity and attitude in one call.
+#[pyfunction]
+fn mpu_sample() -> PyResult<PyMpuSample> {
+    match mpu::mpu6500_get_sample() {
+        Ok(sample) => Ok(PyMpuSample {
+            accel: sample.accel,
+            gyro: sample.gyro,
+            attitude: sample.attitude,
+        }),
+        Err(code) => Err(UptechError::new_err(format!("MPU6500 read failed with status {}", code))),
+    }
+}
+
+#[pymodule]
+fn uptechstar(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("UptechError", m.py().get_type::<UptechError>())?;
+    m.add("VERTICAL", ScreenDirection::Vertical as u8)?;
+    m.add("HORIZONTAL", ScreenDirection::Horizontal as u8)?;
+
+    m.add_class::<PyBoard>()?;
+    m.add_class::<PyScreen>()?;
+    m.add_class::<PyMpuSample>()?;
+
+    m.add_function(wrap_pyfunction!(rgb, m)?)?;
+    m.add_function(wrap_pyfunction!(adc_open, m)?)?;
+    m.add_function(wrap_pyfunction!(adc_close, m)?)?;
+    m.add_function(wrap_pyfunction!(adc_read, m)?)?;
+    m.add_function(wrap_pyfunction!(io_read, m)?)?;
+    m.add_function(wrap_pyfunction!(io_level, m)?)?;
+    m.add_function(wrap_pyfunction!(set_io_levels, m)?)?;
+    m.add_function(wrap_pyfunction!(flip_io, m)?)?;
+    m.add_function(wrap_pyfunction!(set_io_mode, m)?)?;
+    m.add_function(wrap_pyfunction!(mpu_open, m)?)?;
+    m.add_function(wrap_pyfunction!(mpu_sample, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,362 @@
+//! Unchecked bindings to the functions exported by `libuptech.so`.
+//!
+//! Every function in this module forwards directly to the C symbol of the same name, using the
+//! argument types of the vendor headers (`int` → `i32`, `unsigned char` → `u8`,
+//! `long` → [`c_long`], ...).
+//! They exist for calls the safe API does not cover yet, such as DMP tap detection, raw register
+//! access or the bus servo driver, so they can be used without forking the crate.
+//!
+//! The only checking performed is the symbol lookup: each call returns
+//! [`UptechError::LibraryLoad`](crate::UptechError::LibraryLoad) or
+//! [`UptechError::MissingSymbol`](crate::UptechError::MissingSymbol) instead of calling into a
+//! library that is absent or too old. The C return value itself is passed through untouched.
+//!
+//! # Safety
+//!
+//! The caller is responsible for everything the C library assumes:
+//! - pointers must be valid for the number of elements the function reads or writes
+//!   (e.g. 10 `i32`s for [`ADC_GetAll`], 3 `f32`s for [`mpu6500_Get_Accel`])
+//! - strings must be NUL-terminated
+//! - the relevant device must have been opened first
+//!   ([`adc_io_open`], [`mpu6500_open`], [`lcd_open`], ...)
+//! - calls must not race with the safe wrappers of this crate operating on the same device
+//!
+//! # Coverage
+//!
+//! Not bound here:
+//! - the uGUI window, button, textbox and image APIs (`UG_Window*`, `UG_Button*`, ...), which
+//!   take pointers to C structs whose layout is not part of the public headers
+//! - uGUI's internal `_UG_*` helpers and the library's private helpers (signal handlers,
+//!   serial port setup, timers)
+//! - the MPU calibration file functions, whose argument layout is undocumented
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::raw;
+//!
+//! fn main() -> uptechstar_rs::Result<()> {
+//!     unsafe {
+//!         raw::mpu6500_open()?;
+//!         // Register 0x75 is WHO_AM_I
+//!         let who_am_i = raw::mpu6500_read_byte(0x75)?;
+//!         println!("WHO_AM_I = {:#04x}", who_am_i);
+//!
+//!         let mut steps = 0;
+//!         if raw::dmp_get_pedometer_step_count(&mut steps)? == 0 {
+//!             println!("{} steps", steps);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+#![allow(non_snake_case)]
+
+use std::ffi::{c_char, c_long, c_ulong, c_void};
+
+use libloading::Symbol;
+
+use crate::extern_lib::symbol;
+use crate::Result;
+
+/// Callback invoked by the DMP on a tap, with the tap direction and count.
+pub type TapCallback = Option<unsafe extern "C" fn(direction: u8, count: u8)>;
+
+/// Callback invoked by the DMP when the Android-style screen orientation changes.
+pub type OrientationCallback = Option<unsafe extern "C" fn(orientation: u8)>;
+
+macro_rules! bindings {
+    (@ret) => { () };
+    (@ret $ret:ty) => { $ret };
+    ($(
+        $(#[$meta:meta])*
+        fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;
+    )*) => {$(
+        $(#[$meta])*
+        ///
+        /// # Safety
+        ///
+        /// See the [module documentation](self#safety).
+        pub unsafe fn $name($($arg: $ty),*) -> Result<bindings!(@ret $($ret)?)> {
+            #[cfg(feature = "bindgen")]
+            const _: unsafe extern "C" fn($($ty),*) $(-> $ret)? = crate::ffi::$name;
+
+            let function: Symbol<unsafe extern "C" fn($($ty),*) $(-> $ret)?> = unsafe { symbol(stringify!($name))? };
+            Ok(unsafe { function($($arg),*) })
+        }
+    )*};
+}
+
+// ADC and IO
+bindings! {
+    /// Opens the ADC/IO serial channel. Returns a negative value on failure.
+    fn adc_io_open() -> i32;
+    /// Closes the ADC/IO serial channel.
+    fn adc_io_close() -> i32;
+    /// Reads all 10 ADC channels into `buffer`.
+    fn ADC_GetAll(buffer: *mut i32) -> i32;
+    /// Returns the input levels of the 8 IO pins as a bit mask.
+    fn adc_io_InputGetAll() -> u8;
+    /// Sets the output levels of all 8 IO pins from a bit mask.
+    fn adc_io_SetAll(levels: u32) -> i32;
+    /// Toggles the output level of one IO pin.
+    fn adc_io_Set(index: u32) -> i32;
+    /// Writes the mode bit mask of the 8 IO pins to `modes`.
+    fn adc_io_ModeGetAll(modes: *mut u8) -> i32;
+    /// Sets the mode of one IO pin (0 = input, 1 = output).
+    fn adc_io_ModeSet(index: u32, mode: i32) -> i32;
+    /// Sets the modes of all 8 IO pins from a bit mask.
+    fn adc_io_ModeSetAll(modes: u8) -> i32;
+    /// Sets LED `index` (0 or 1) to a packed RGB color.
+    fn adc_led_set(index: i32, color: u32) -> i32;
+}
+
+// LCD and uGUI
+bindings! {
+    /// Opens the LCD with the given orientation.
+    fn lcd_open(direction: i32) -> i32;
+    /// Closes the LCD.
+    fn lcd_close() -> i32;
+    /// Pushes the frame buffer to the panel.
+    fn LCD_Refresh() -> i32;
+    /// Selects the font used by [`UG_PutString`].
+    fn LCD_SetFont(font: i32) -> i32;
+    /// Selects the active drawing layer (0–4).
+    fn LCD_SetLayer(layer: i32);
+    /// Writes one pixel directly to the frame buffer, bypassing uGUI.
+    fn LCD_SetPixel(x: i32, y: i32, color: u32);
+    /// Sets the text foreground color.
+    fn UG_SetForecolor(color: u32) -> i32;
+    /// Sets the text background color.
+    fn UG_SetBackcolor(color: u32) -> i32;
+    /// Returns the width of the display in pixels.
+    fn UG_GetXDim() -> i16;
+    /// Returns the height of the display in pixels.
+    fn UG_GetYDim() -> i16;
+    /// Fills the whole screen with one color.
+    fn UG_FillScreen(color: u32) -> i32;
+    /// Draws a NUL-terminated string at the given position.
+    fn UG_PutString(x: i32, y: i32, text: *const c_char) -> i32;
+    /// Sets the area used by the uGUI console.
+    fn UG_ConsoleSetArea(x1: i16, y1: i16, x2: i16, y2: i16);
+    /// Appends a NUL-terminated string to the uGUI console, scrolling as needed.
+    fn UG_ConsolePutString(text: *const c_char);
+    /// Fills a rectangle.
+    fn UG_FillFrame(x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> i32;
+    /// Fills a rectangle with rounded corners.
+    fn UG_FillRoundFrame(x1: i32, y1: i32, x2: i32, y2: i32, radius: i32, color: u32) -> i32;
+    /// Fills a circle.
+    fn UG_FillCircle(x: i32, y: i32, radius: i32, color: u32) -> i32;
+    /// Draws a dotted rectangle.
+    fn UG_DrawMesh(x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> i32;
+    /// Draws a rectangle outline.
+    fn UG_DrawFrame(x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> i32;
+    /// Draws a rectangle outline with rounded corners.
+    fn UG_DrawRoundFrame(x1: i32, y1: i32, x2: i32, y2: i32, radius: i32, color: u32) -> i32;
+    /// Draws one pixel through uGUI.
+    fn UG_DrawPixel(x: i32, y: i32, color: u32) -> i32;
+    /// Draws a circle outline.
+    fn UG_DrawCircle(x: i32, y: i32, radius: i32, color: u32) -> i32;
+    /// Draws the octants of a circle selected by the `sections` bit mask.
+    fn UG_DrawArc(x: i32, y: i32, radius: i32, sections: i32, color: u32) -> i32;
+    /// Draws a line.
+    fn UG_DrawLine(x1: i32, y1: i32, x2: i32, y2: i32, color: u32) -> i32;
+}
+
+// MPU6500 vendor helpers
+bindings! {
+    /// Opens the I2C bus and initializes the MPU6500 with the DMP enabled.
+    fn mpu6500_open() -> i32;
+    /// Initializes the DMP on an already opened MPU6500.
+    fn mpu6500_dmp_init() -> i32;
+    /// Reads the acceleration in g into 3 floats.
+    fn mpu6500_Get_Accel(accel: *mut f32) -> i32;
+    /// Reads the angular velocity in °/s into 3 floats.
+    fn mpu6500_Get_Gyro(gyro: *mut f32) -> i32;
+    /// Reads pitch, roll and yaw in degrees into 3 floats.
+    fn mpu6500_Get_Attitude(attitude: *mut f32) -> i32;
+    /// Reads one register. Returns the byte value, or -1 on failure.
+    fn mpu6500_read_byte(register: u8) -> i32;
+    /// Writes one register.
+    fn mpu6500_write_byte(register: u8, value: u8) -> i32;
+    /// Reads `length` bytes starting at `register` over I2C.
+    fn MPU_I2C_readBytes(address: u8, register: u8, length: u8, data: *mut u8) -> i32;
+    /// Writes `length` bytes starting at `register` over I2C.
+    fn MPU_I2C_writeBytes(address: u8, register: u8, length: u8, data: *const u8) -> i32;
+}
+
+// InvenSense motion driver (inv_mpu.h)
+bindings! {
+    /// Initializes the chip. `int_param` may be null for polled operation.
+    fn mpu_init(int_param: *mut c_void) -> i32;
+    /// Enables or disables the I2C bypass to the auxiliary bus.
+    fn mpu_set_bypass(enable: u8) -> i32;
+    /// Enters low-power accelerometer-only mode at the given rate in Hz (0 to leave it).
+    fn mpu_lp_accel_mode(rate: u16) -> i32;
+    /// Enables the wake-on-motion interrupt.
+    fn mpu_lp_motion_interrupt(threshold_mg: u16, duration_ms: u8, rate: u16) -> i32;
+    /// Sets the interrupt pin polarity (0 = active high).
+    fn mpu_set_int_level(active_low: u8) -> i32;
+    /// Enables or disables latched interrupts.
+    fn mpu_set_int_latched(enable: u8) -> i32;
+    /// Enables or disables the DMP.
+    fn mpu_set_dmp_state(enable: u8) -> i32;
+    /// Writes whether the DMP is enabled to `enabled`.
+    fn mpu_get_dmp_state(enabled: *mut u8) -> i32;
+    /// Writes the digital low-pass filter cutoff in Hz to `lpf`.
+    fn mpu_get_lpf(lpf: *mut u16) -> i32;
+    /// Sets the digital low-pass filter cutoff in Hz.
+    fn mpu_set_lpf(lpf: u16) -> i32;
+    /// Writes the gyroscope full-scale range in °/s to `fsr`.
+    fn mpu_get_gyro_fsr(fsr: *mut u16) -> i32;
+    /// Sets the gyroscope full-scale range in °/s.
+    fn mpu_set_gyro_fsr(fsr: u16) -> i32;
+    /// Writes the accelerometer full-scale range in g to `fsr`.
+    fn mpu_get_accel_fsr(fsr: *mut u8) -> i32;
+    /// Sets the accelerometer full-scale range in g.
+    fn mpu_set_accel_fsr(fsr: u8) -> i32;
+    /// Writes the gyroscope sensitivity in LSB per °/s to `sensitivity`.
+    fn mpu_get_gyro_sens(sensitivity: *mut f32) -> i32;
+    /// Writes the accelerometer sensitivity in LSB per g to `sensitivity`.
+    fn mpu_get_accel_sens(sensitivity: *mut u16) -> i32;
+    /// Writes the sample rate in Hz to `rate`.
+    fn mpu_get_sample_rate(rate: *mut u16) -> i32;
+    /// Sets the sample rate in Hz.
+    fn mpu_set_sample_rate(rate: u16) -> i32;
+    /// Writes the sensors routed to the FIFO to `sensors`.
+    fn mpu_get_fifo_config(sensors: *mut u8) -> i32;
+    /// Selects the sensors routed to the FIFO.
+    fn mpu_configure_fifo(sensors: u8) -> i32;
+    /// Writes whether the chip is powered on to `power_on`.
+    fn mpu_get_power_state(power_on: *mut u8) -> i32;
+    /// Turns individual sensors on or off.
+    fn mpu_set_sensors(sensors: u8) -> i32;
+    /// Reads the factory accelerometer bias of an MPU6500 into 3 longs.
+    fn mpu_read_6500_accel_bias(bias: *mut c_long) -> i32;
+    /// Reads the gyroscope bias registers of an MPU6500 into 3 longs.
+    fn mpu_read_6500_gyro_bias(bias: *mut c_long) -> i32;
+    /// Writes 3 longs to the gyroscope bias registers.
+    fn mpu_set_gyro_bias_reg(bias: *mut c_long) -> i32;
+    /// Writes 3 longs to the accelerometer bias registers of an MPU6500.
+    fn mpu_set_accel_bias_6500_reg(bias: *const c_long) -> i32;
+    /// Reads the raw gyroscope registers into 3 shorts.
+    fn mpu_get_gyro_reg(data: *mut i16, timestamp: *mut c_ulong) -> i32;
+    /// Reads the raw accelerometer registers into 3 shorts.
+    fn mpu_get_accel_reg(data: *mut i16, timestamp: *mut c_ulong) -> i32;
+    /// Reads the die temperature in q16 degrees Celsius.
+    fn mpu_get_temperature(data: *mut c_long, timestamp: *mut c_ulong) -> i32;
+    /// Reads the interrupt status registers.
+    fn mpu_get_int_status(status: *mut i16) -> i32;
+    /// Pops one packet from the FIFO when the DMP is disabled.
+    fn mpu_read_fifo(gyro: *mut i16, accel: *mut i16, timestamp: *mut c_ulong, sensors: *mut u8, more: *mut u8) -> i32;
+    /// Pops one `length`-byte packet from the FIFO when the DMP is enabled.
+    fn mpu_read_fifo_stream(length: u16, data: *mut u8, more: *mut u8) -> i32;
+    /// Resets the FIFO.
+    fn mpu_reset_fifo() -> i32;
+    /// Writes `length` bytes to DMP memory.
+    fn mpu_write_mem(address: u16, length: u16, data: *mut u8) -> i32;
+    /// Reads `length` bytes from DMP memory.
+    fn mpu_read_mem(address: u16, length: u16, data: *mut u8) -> i32;
+    /// Loads and verifies DMP firmware.
+    fn mpu_load_firmware(length: u16, firmware: *const u8, start_address: u16, sample_rate: u16) -> i32;
+    /// Logs all registers through the library's own output.
+    fn mpu_reg_dump() -> i32;
+    /// Reads one register through the motion driver.
+    fn mpu_read_reg(register: u8, data: *mut u8) -> i32;
+    /// Runs the MPU6500 self-test, writing 3 gyroscope and 3 accelerometer biases.
+    fn mpu_run_6500_self_test(gyro: *mut c_long, accel: *mut c_long, debug: u8) -> i32;
+}
+
+// InvenSense DMP (inv_mpu_dmp_motion_driver.h)
+bindings! {
+    /// Loads the DMP firmware image shipped with the library.
+    fn dmp_load_motion_driver_firmware() -> i32;
+    /// Sets the DMP output rate in Hz.
+    fn dmp_set_fifo_rate(rate: u16) -> i32;
+    /// Writes the DMP output rate in Hz to `rate`.
+    fn dmp_get_fifo_rate(rate: *mut u16) -> i32;
+    /// Enables the DMP features selected by the `DMP_FEATURE_*` bit mask.
+    fn dmp_enable_feature(mask: u16) -> i32;
+    /// Writes the enabled `DMP_FEATURE_*` bit mask to `mask`.
+    fn dmp_get_enabled_features(mask: *mut u16) -> i32;
+    /// Selects continuous (0) or gesture (1) interrupts.
+    fn dmp_set_interrupt_mode(mode: u8) -> i32;
+    /// Sets the mounting orientation as an orientation scalar.
+    fn dmp_set_orientation(orientation: u16) -> i32;
+    /// Pushes 3 gyroscope biases to the DMP.
+    fn dmp_set_gyro_bias(bias: *mut c_long) -> i32;
+    /// Pushes 3 accelerometer biases to the DMP.
+    fn dmp_set_accel_bias(bias: *mut c_long) -> i32;
+    /// Registers the tap callback.
+    fn dmp_register_tap_cb(callback: TapCallback) -> i32;
+    /// Registers the orientation change callback.
+    fn dmp_register_android_orient_cb(callback: OrientationCallback) -> i32;
+    /// Sets the tap threshold in mg/ms for the given axes.
+    fn dmp_set_tap_thresh(axis: u8, threshold: u16) -> i32;
+    /// Selects the axes on which taps are detected.
+    fn dmp_set_tap_axes(axis: u8) -> i32;
+    /// Sets the minimum number of consecutive taps reported.
+    fn dmp_set_tap_count(min_taps: u8) -> i32;
+    /// Sets the time in ms between taps.
+    fn dmp_set_tap_time(time: u16) -> i32;
+    /// Sets the maximum time in ms between taps of a multi-tap.
+    fn dmp_set_tap_time_multi(time: u16) -> i32;
+    /// Sets the shake rejection threshold in °/s.
+    fn dmp_set_shake_reject_thresh(sensitivity: c_long, threshold: u16) -> i32;
+    /// Sets the shake rejection time in ms.
+    fn dmp_set_shake_reject_time(time: u16) -> i32;
+    /// Sets the shake rejection timeout in ms.
+    fn dmp_set_shake_reject_timeout(time: u16) -> i32;
+    /// Writes the pedometer step count to `count`.
+    fn dmp_get_pedometer_step_count(count: *mut c_ulong) -> i32;
+    /// Overwrites the pedometer step count.
+    fn dmp_set_pedometer_step_count(count: c_ulong) -> i32;
+    /// Writes the pedometer walk time in ms to `time`.
+    fn dmp_get_pedometer_walk_time(time: *mut c_ulong) -> i32;
+    /// Overwrites the pedometer walk time in ms.
+    fn dmp_set_pedometer_walk_time(time: c_ulong) -> i32;
+    /// Enables or disables continuous gyroscope calibration.
+    fn dmp_enable_gyro_cal(enable: u8) -> i32;
+    /// Enables or disables the 3-axis low-power quaternion.
+    fn dmp_enable_lp_quat(enable: u8) -> i32;
+    /// Enables or disables the 6-axis low-power quaternion.
+    fn dmp_enable_6x_lp_quat(enable: u8) -> i32;
+    /// Pops one DMP packet: 3 gyro and 3 accel shorts, a 4-long quaternion and the sensor mask.
+    fn dmp_read_fifo(gyro: *mut i16, accel: *mut i16, quat: *mut c_long, timestamp: *mut c_ulong, sensors: *mut i16, more: *mut u8) -> i32;
+    /// Converts a 3×3 orientation matrix of -1/0/1 into a DMP orientation scalar.
+    fn inv_orientation_matrix_to_scalar(matrix: *const i8) -> u16;
+    /// Encodes one row of an orientation matrix.
+    fn inv_row_2_scale(row: *const i8) -> u16;
+}
+
+// Bus servos
+bindings! {
+    /// Opens the bus servo serial port. Returns a negative value on failure.
+    fn cds_servo_open() -> i32;
+    /// Closes the bus servo serial port.
+    fn cds_servo_close();
+    /// Switches servo `id` between servo (0) and continuous rotation (1) mode.
+    fn cds_servo_SetMode(id: u8, mode: u32);
+    /// Moves servo `id` to `angle` (0–1023) at `speed` (0–1023).
+    fn cds_servo_SetAngle(id: u8, angle: u32, speed: u32);
+    /// Sets the rotation speed of servo `id` in continuous mode (-1023–1023).
+    fn cds_servo_SetSpeed(id: u8, speed: i32);
+    /// Reads the position of servo `id`, or -1 on failure.
+    fn cds_servo_GetPos(id: u8) -> i32;
+}
+
+// Serial ports and timing
+bindings! {
+    /// Opens a serial device at the given baud rate. Returns the file descriptor or -1.
+    fn drv_serial_open(device: *const c_char, baud_rate: i32) -> i32;
+    /// Closes a serial port opened with [`drv_serial_open`].
+    fn drv_serial_close(fd: i32);
+    /// Reads up to `length` bytes. Returns the number read, or -1.
+    fn drv_serial_read(fd: i32, buffer: *mut u8, length: i32) -> i16;
+    /// Writes `length` bytes. Returns the number written, or -1.
+    fn drv_serial_write(fd: i32, buffer: *const u8, length: i32) -> i32;
+    /// Sleeps for `ms` milliseconds, resuming after signals.
+    fn msleep_s(ms: u32);
+    /// Sleeps for `us` microseconds, resuming after signals.
+    fn usleep_s(us: u32);
+}
@@ -0,0 +1,29 @@
+//! Which peripherals have run their C initialization sequence.
+//!
+//! `lcd_open` and `mpu6500_dmp_init` reset their device, so running them again while another
+//! part of the program uses it disturbs that part. The wrappers consult these flags to share an
+//! initialized device instead, and the `try_` constructors to report
+//! [`UptechError::AlreadyInitialized`](crate::UptechError::AlreadyInitialized).
+
+use crate::display::ScreenDirection;
+use std::sync::Mutex;
+
+/// The direction the LCD was opened in, `None` while it is closed.
+static LCD: Mutex<Option<ScreenDirection>> = Mutex::new(None);
+
+/// `true` once `mpu6500_dmp_init` succeeded, until ADC-IO is closed.
+pub(crate) static MPU: Mutex<bool> = Mutex::new(false);
+
+/// Returns the direction the LCD is open in, if it is.
+pub(crate) fn lcd() -> Option<ScreenDirection> {
+    *LCD.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn set_lcd(direction: Option<ScreenDirection>) {
+    *LCD.lock().unwrap_or_else(|e| e.into_inner()) = direction;
+}
+
+/// Returns `true` if the MPU6500 has been initialized.
+pub(crate) fn mpu() -> bool {
+    *MPU.lock().unwrap_or_else(|e| e.into_inner())
+}
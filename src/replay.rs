@@ -0,0 +1,637 @@
+//! Record-and-replay of sensor sessions.
+//!
+//! A [`Recorder`] wraps another [`Backend`] (normally the [`FfiBackend`](crate::backend::FfiBackend))
+//! and appends every ADC, IO and MPU sample read through it to a compact binary file. The
+//! resulting session can later be fed back through the very same public APIs by installing a
+//! [`ReplayBackend`], which makes it possible to debug control algorithms offline against real
+//! captured runs.
+//!
+//! # File Format
+//!
+//! A session file starts with the 4-byte magic `UPRP` followed by a format version byte.
+//! Each record is laid out as:
+//!
+//! | Field     | Size        | Description                                  |
+//! |-----------|-------------|----------------------------------------------|
+//! | timestamp | 8 bytes     | Microseconds since recording started (LE)    |
+//! | kind      | 1 byte      | Sample kind tag, see [`Sample`]               |
+//! | payload   | 1-40 bytes  | Little-endian sample data                    |
+//!
+//! # Examples
+//!
+//! Recording a session:
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use uptechstar_rs::backend::{self, FfiBackend};
+//! use uptechstar_rs::replay::Recorder;
+//! use uptechstar_rs::mpu;
+//!
+//! let recorder = Arc::new(Recorder::create("run.uprp", FfiBackend).unwrap());
+//! backend::set_backend(recorder.clone());
+//!
+//! let mut accel = [0.0f32; 3];
+//! for _ in 0..1000 {
+//!     mpu::mpu6500_get_accel(&mut accel);
+//! }
+//!
+//! recorder.flush().unwrap();
+//! ```
+//!
+//! Replaying it later, without any hardware attached:
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use uptechstar_rs::backend;
+//! use uptechstar_rs::replay::ReplayBackend;
+//! use uptechstar_rs::mpu;
+//!
+//! let replay = ReplayBackend::open("run.uprp").unwrap().with_realtime(true);
+//! backend::set_backend(Arc::new(replay));
+//!
+//! let mut accel = [0.0f32; 3];
+//! while mpu::mpu6500_get_accel(&mut accel) == 0 {
+//!     println!("{:?}", accel);
+//! }
+//! ```
+
+use crate::backend::Backend;
+use crate::telemetry::TelemetryFrame;
+use log::{debug, info};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Magic bytes identifying a session file.
+const MAGIC: &[u8; 4] = b"UPRP";
+
+/// Current session file format version.
+const VERSION: u8 = 1;
+
+/// A single sensor reading captured in a session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sample {
+    /// All 10 ADC channels, as returned by `ADC_GetAll`. Tag `0`.
+    Adc([i32; 10]),
+    /// The IO input level bitmask. Tag `1`.
+    Io(u8),
+    /// Accelerometer data in g. Tag `2`.
+    Accel([f32; 3]),
+    /// Gyroscope data in degrees per second. Tag `3`.
+    Gyro([f32; 3]),
+    /// Attitude (pitch, roll, yaw) in degrees. Tag `4`.
+    Attitude([f32; 3]),
+}
+
+impl Sample {
+    /// Number of distinct sample kinds.
+    const KINDS: usize = 5;
+
+    /// Returns the tag identifying this sample kind in a session file.
+    pub fn kind(&self) -> u8 {
+        match self {
+            Sample::Adc(_) => 0,
+            Sample::Io(_) => 1,
+            Sample::Accel(_) => 2,
+            Sample::Gyro(_) => 3,
+            Sample::Attitude(_) => 4,
+        }
+    }
+
+    fn write_payload<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        match self {
+            Sample::Adc(channels) => {
+                for value in channels {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                Ok(())
+            }
+            Sample::Io(levels) => writer.write_all(&[*levels]),
+            Sample::Accel(axes) | Sample::Gyro(axes) | Sample::Attitude(axes) => {
+                for value in axes {
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn read_payload<R: Read>(kind: u8, reader: &mut R) -> io::Result<Self> {
+        fn read_axes<R: Read>(reader: &mut R) -> io::Result<[f32; 3]> {
+            let mut axes = [0.0f32; 3];
+            let mut buffer = [0u8; 4];
+            for value in axes.iter_mut() {
+                reader.read_exact(&mut buffer)?;
+                *value = f32::from_le_bytes(buffer);
+            }
+            Ok(axes)
+        }
+
+        match kind {
+            0 => {
+                let mut channels = [0i32; 10];
+                let mut buffer = [0u8; 4];
+                for value in channels.iter_mut() {
+                    reader.read_exact(&mut buffer)?;
+                    *value = i32::from_le_bytes(buffer);
+                }
+                Ok(Sample::Adc(channels))
+            }
+            1 => {
+                let mut buffer = [0u8; 1];
+                reader.read_exact(&mut buffer)?;
+                Ok(Sample::Io(buffer[0]))
+            }
+            2 => Ok(Sample::Accel(read_axes(reader)?)),
+            3 => Ok(Sample::Gyro(read_axes(reader)?)),
+            4 => Ok(Sample::Attitude(read_axes(reader)?)),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown sample kind {} in session file", other),
+            )),
+        }
+    }
+}
+
+/// A timestamped [`Sample`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Record {
+    /// Time elapsed since the recording started.
+    pub at: Duration,
+    /// The captured sample.
+    pub sample: Sample,
+}
+
+impl Record {
+    /// Encodes the record into `writer` using the session file layout.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use uptechstar_rs::replay::{Record, Sample};
+    ///
+    /// let record = Record {
+    ///     at: Duration::from_millis(5),
+    ///     sample: Sample::Io(0b1010_0001),
+    /// };
+    ///
+    /// let mut buffer = Vec::new();
+    /// record.write_to(&mut buffer).unwrap();
+    /// assert_eq!(buffer.len(), 10);
+    /// assert_eq!(Record::read_from(&mut buffer.as_slice()).unwrap(), Some(record));
+    /// ```
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&(self.at.as_micros() as u64).to_le_bytes())?;
+        writer.write_all(&[self.sample.kind()])?;
+        self.sample.write_payload(writer)
+    }
+
+    /// Decodes the next record from `reader`.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<Option<Record>>` - `Ok(None)` on a clean end of file.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+        let mut timestamp = [0u8; 8];
+        match reader.read_exact(&mut timestamp) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let mut kind = [0u8; 1];
+        reader.read_exact(&mut kind)?;
+
+        Ok(Some(Record {
+            at: Duration::from_micros(u64::from_le_bytes(timestamp)),
+            sample: Sample::read_payload(kind[0], reader)?,
+        }))
+    }
+}
+
+/// Writes the session file header.
+fn write_header<W: Write>(writer: &mut W) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])
+}
+
+/// Reads and validates the session file header.
+fn read_header<R: Read>(reader: &mut R) -> io::Result<()> {
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header)?;
+
+    if &header[..4] != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Not an uptech session file"));
+    }
+    if header[4] != VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unsupported session file version {}", header[4]),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reads all records of a session file into memory.
+///
+/// # Arguments
+///
+/// * `path` - Path of a file produced by a [`Recorder`].
+///
+/// # Returns
+///
+/// * `io::Result<Vec<Record>>` - The records in the order they were captured.
+pub fn read_session<P: AsRef<Path>>(path: P) -> io::Result<Vec<Record>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    read_header(&mut reader)?;
+
+    let mut records = Vec::new();
+    while let Some(record) = Record::read_from(&mut reader)? {
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+/// A [`Backend`] that forwards to an inner backend and records every sample it reads.
+///
+/// Only successful reads are recorded. Writes (IO levels, modes, FSR changes) are forwarded
+/// untouched and are not part of the session.
+pub struct Recorder<B: Backend> {
+    inner: B,
+    started: Instant,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl<B: Backend> Recorder<B> {
+    /// Creates a new session file at `path` and starts recording samples read through `inner`.
+    pub fn create<P: AsRef<Path>>(path: P, inner: B) -> io::Result<Self> {
+        info!("Recording sensor session to {}", path.as_ref().display());
+        Self::from_writer(BufWriter::new(File::create(path)?), inner)
+    }
+
+    /// Starts recording into an arbitrary writer, e.g. a network stream or an in-memory buffer.
+    pub fn from_writer<W: Write + Send + 'static>(mut writer: W, inner: B) -> io::Result<Self> {
+        write_header(&mut writer)?;
+
+        Ok(Recorder {
+            inner,
+            started: Instant::now(),
+            writer: Mutex::new(Box::new(writer)),
+        })
+    }
+
+    /// Flushes buffered records to the underlying writer.
+    pub fn flush(&self) -> io::Result<()> {
+        self.writer.lock().unwrap_or_else(|e| e.into_inner()).flush()
+    }
+
+    fn record(&self, sample: Sample) {
+        let record = Record {
+            at: self.started.elapsed(),
+            sample,
+        };
+
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = record.write_to(&mut *writer) {
+            debug!("Failed to record sample: {}", e);
+        }
+    }
+}
+
+impl<B: Backend> Drop for Recorder<B> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl<B: Backend> Backend for Recorder<B> {
+    fn adc_io_open(&self) -> i32 {
+        self.inner.adc_io_open()
+    }
+
+    fn adc_io_close(&self) -> i32 {
+        self.inner.adc_io_close()
+    }
+
+    fn adc_get_all(&self, adc_data: &mut [i32; 10]) -> i32 {
+        let result = self.inner.adc_get_all(adc_data);
+        if result == 0 {
+            self.record(Sample::Adc(*adc_data));
+        }
+        result
+    }
+
+    fn io_get_all(&self) -> u8 {
+        let levels = self.inner.io_get_all();
+        self.record(Sample::Io(levels));
+        levels
+    }
+
+    fn io_set_all(&self, levels: u32) -> i32 {
+        self.inner.io_set_all(levels)
+    }
+
+    fn io_flip(&self, index: u32) -> i32 {
+        self.inner.io_flip(index)
+    }
+
+    fn io_mode_get_all(&self, modes: &mut u8) -> i32 {
+        self.inner.io_mode_get_all(modes)
+    }
+
+    fn io_mode_set(&self, index: u32, mode: i32) -> i32 {
+        self.inner.io_mode_set(index, mode)
+    }
+
+    fn mpu_init(&self) -> i32 {
+        self.inner.mpu_init()
+    }
+
+    fn mpu_get_accel(&self, accel_data: &mut [f32; 3]) -> i32 {
+        let result = self.inner.mpu_get_accel(accel_data);
+        if result == 0 {
+            self.record(Sample::Accel(*accel_data));
+        }
+        result
+    }
+
+    fn mpu_get_gyro(&self, gyro_data: &mut [f32; 3]) -> i32 {
+        let result = self.inner.mpu_get_gyro(gyro_data);
+        if result == 0 {
+            self.record(Sample::Gyro(*gyro_data));
+        }
+        result
+    }
+
+    fn mpu_get_attitude(&self, attitude_data: &mut [f32; 3]) -> i32 {
+        let result = self.inner.mpu_get_attitude(attitude_data);
+        if result == 0 {
+            self.record(Sample::Attitude(*attitude_data));
+        }
+        result
+    }
+
+    fn mpu_get_gyro_fsr(&self, fsr: &mut u16) -> i32 {
+        self.inner.mpu_get_gyro_fsr(fsr)
+    }
+
+    fn mpu_get_accel_fsr(&self, fsr: &mut u8) -> i32 {
+        self.inner.mpu_get_accel_fsr(fsr)
+    }
+
+    fn mpu_set_gyro_fsr(&self, fsr: u32) -> i32 {
+        self.inner.mpu_set_gyro_fsr(fsr)
+    }
+
+    fn mpu_set_accel_fsr(&self, fsr: i32) -> i32 {
+        self.inner.mpu_set_accel_fsr(fsr)
+    }
+}
+
+/// A [`Backend`] serving previously recorded samples.
+///
+/// Each sample kind is replayed independently. In the default sequential mode every read
+/// returns the next recorded sample of that kind, so the application sees exactly the
+/// captured sequence regardless of how fast it polls. In realtime mode (see
+/// [`with_realtime`](ReplayBackend::with_realtime)) each read returns the latest sample whose
+/// timestamp, counted from the earliest sample of the session, has elapsed since the first
+/// read, reproducing the original timing across kinds.
+///
+/// Once a kind is exhausted, or in realtime mode before its first sample is due, its reads
+/// fail with `-1`, like a disconnected sensor. IO input reads return the last levels served
+/// instead, or all low before the first. All writes succeed without effect, and FSR
+/// queries report the MPU defaults (±2000°/s, ±8g).
+pub struct ReplayBackend {
+    streams: [Vec<Record>; Sample::KINDS],
+    cursors: Mutex<[usize; Sample::KINDS]>,
+    started: Mutex<Option<Instant>>,
+    /// Timestamp of the earliest sample over all kinds, which realtime pacing counts from.
+    start: Duration,
+    realtime: bool,
+}
+
+impl ReplayBackend {
+    /// Loads a session file produced by a [`Recorder`].
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        info!("Replaying sensor session from {}", path.as_ref().display());
+        Ok(Self::from_records(read_session(path)?))
+    }
+
+    /// Builds a replay backend from records already in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use uptechstar_rs::backend::Backend;
+    /// use uptechstar_rs::replay::{Record, ReplayBackend, Sample};
+    ///
+    /// let replay = ReplayBackend::from_records(vec![Record {
+    ///     at: Duration::ZERO,
+    ///     sample: Sample::Accel([0.0, 0.0, 1.0]),
+    /// }]);
+    ///
+    /// let mut accel = [0.0f32; 3];
+    /// assert_eq!(replay.mpu_get_accel(&mut accel), 0);
+    /// assert_eq!(accel, [0.0, 0.0, 1.0]);
+    /// assert_eq!(replay.mpu_get_accel(&mut accel), -1);
+    /// ```
+    pub fn from_records<I: IntoIterator<Item = Record>>(records: I) -> Self {
+        let mut streams: [Vec<Record>; Sample::KINDS] = Default::default();
+        for record in records {
+            streams[record.sample.kind() as usize].push(record);
+        }
+        let start = streams
+            .iter()
+            .filter_map(|stream| stream.first().map(|record| record.at))
+            .min()
+            .unwrap_or(Duration::ZERO);
+
+        ReplayBackend {
+            streams,
+            cursors: Mutex::new([0; Sample::KINDS]),
+            started: Mutex::new(None),
+            start,
+            realtime: false,
+        }
+    }
+
+    /// Builds a replay backend from [`TelemetryFrame`]s, such as those of a
+    /// [`LogFormat::Frames`](crate::logging::LogFormat::Frames) log. Every section a frame
+    /// carries becomes a sample at the frame's timestamp.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use uptechstar_rs::adc_io::IoFrame;
+    /// use uptechstar_rs::backend::Backend;
+    /// use uptechstar_rs::replay::ReplayBackend;
+    /// use uptechstar_rs::telemetry::TelemetryFrame;
+    ///
+    /// let replay = ReplayBackend::from_frames(vec![TelemetryFrame { io: Some(IoFrame(3)), ..Default::default() }]);
+    ///
+    /// assert_eq!(replay.io_get_all(), 3);
+    /// ```
+    pub fn from_frames<I: IntoIterator<Item = TelemetryFrame>>(frames: I) -> Self {
+        Self::from_records(frames.into_iter().flat_map(|frame| {
+            let at = frame.timestamp;
+            let samples = [
+                frame.adc.map(|adc| Sample::Adc(adc.0)),
+                frame.io.map(|io| Sample::Io(io.0)),
+                frame.mpu.map(|mpu| Sample::Accel(mpu.accel)),
+                frame.mpu.map(|mpu| Sample::Gyro(mpu.gyro)),
+                frame.mpu.map(|mpu| Sample::Attitude(mpu.attitude)),
+            ];
+            samples.into_iter().flatten().map(move |sample| Record { at, sample })
+        }))
+    }
+
+    /// Enables or disables realtime pacing.
+    pub fn with_realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+
+    /// Returns `true` once every recorded sample has been served.
+    pub fn is_finished(&self) -> bool {
+        let cursors = self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+        self.streams
+            .iter()
+            .zip(cursors.iter())
+            .all(|(stream, &cursor)| cursor >= stream.len())
+    }
+
+    /// Rewinds all streams to the beginning of the session.
+    pub fn rewind(&self) {
+        *self.cursors.lock().unwrap_or_else(|e| e.into_inner()) = [0; Sample::KINDS];
+        *self.started.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    fn next(&self, kind: u8) -> Option<Sample> {
+        let stream = &self.streams[kind as usize];
+        let mut cursors = self.cursors.lock().unwrap_or_else(|e| e.into_inner());
+        let cursor = &mut cursors[kind as usize];
+
+        if self.realtime {
+            let elapsed = self
+                .started
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get_or_insert_with(Instant::now)
+                .elapsed();
+
+            let due = stream.partition_point(|record| record.at.saturating_sub(self.start) <= elapsed);
+            if due == 0 || (due >= stream.len() && *cursor >= stream.len()) {
+                return None;
+            }
+            *cursor = due;
+            return Some(stream[due - 1].sample);
+        }
+
+        let record = stream.get(*cursor)?;
+        *cursor += 1;
+        Some(record.sample)
+    }
+
+    fn next_axes(&self, kind: u8, data: &mut [f32; 3]) -> i32 {
+        match self.next(kind) {
+            Some(Sample::Accel(axes)) | Some(Sample::Gyro(axes)) | Some(Sample::Attitude(axes)) => {
+                *data = axes;
+                0
+            }
+            _ => -1,
+        }
+    }
+}
+
+impl Backend for ReplayBackend {
+    fn adc_io_open(&self) -> i32 {
+        1
+    }
+
+    fn adc_io_close(&self) -> i32 {
+        0
+    }
+
+    fn adc_get_all(&self, adc_data: &mut [i32; 10]) -> i32 {
+        match self.next(0) {
+            Some(Sample::Adc(channels)) => {
+                *adc_data = channels;
+                0
+            }
+            _ => -1,
+        }
+    }
+
+    fn io_get_all(&self) -> u8 {
+        match self.next(1) {
+            Some(Sample::Io(levels)) => levels,
+            _ => {
+                let cursor = self.cursors.lock().unwrap_or_else(|e| e.into_inner())[1];
+                match cursor.checked_sub(1).and_then(|served| self.streams[1].get(served)) {
+                    Some(Record {
+                        sample: Sample::Io(levels),
+                        ..
+                    }) => *levels,
+                    _ => 0,
+                }
+            }
+        }
+    }
+
+    fn io_set_all(&self, _levels: u32) -> i32 {
+        0
+    }
+
+    fn io_flip(&self, _index: u32) -> i32 {
+        0
+    }
+
+    fn io_mode_get_all(&self, modes: &mut u8) -> i32 {
+        *modes = 0;
+        0
+    }
+
+    fn io_mode_set(&self, _index: u32, _mode: i32) -> i32 {
+        0
+    }
+
+    fn mpu_init(&self) -> i32 {
+        0
+    }
+
+    fn mpu_get_accel(&self, accel_data: &mut [f32; 3]) -> i32 {
+        self.next_axes(2, accel_data)
+    }
+
+    fn mpu_get_gyro(&self, gyro_data: &mut [f32; 3]) -> i32 {
+        self.next_axes(3, gyro_data)
+    }
+
+    fn mpu_get_attitude(&self, attitude_data: &mut [f32; 3]) -> i32 {
+        self.next_axes(4, attitude_data)
+    }
+
+    fn mpu_get_gyro_fsr(&self, fsr: &mut u16) -> i32 {
+        *fsr = 2000;
+        0
+    }
+
+    fn mpu_get_accel_fsr(&self, fsr: &mut u8) -> i32 {
+        *fsr = 8;
+        0
+    }
+
+    fn mpu_set_gyro_fsr(&self, _fsr: u32) -> i32 {
+        0
+    }
+
+    fn mpu_set_accel_fsr(&self, _fsr: i32) -> i32 {
+        0
+    }
+}
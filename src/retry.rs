@@ -0,0 +1,183 @@
+//! Retrying of transient hardware read failures.
+//!
+//! The MPU6500 sits on an I2C bus that occasionally NAKs or times out, and the ADC channel
+//! shares a serial link with the IO controller. A single failed read is usually followed by a
+//! successful one, so the read wrappers ([`mpu6500_get_accel`](crate::mpu::mpu6500_get_accel),
+//! [`mpu6500_get_gyro`](crate::mpu::mpu6500_get_gyro),
+//! [`mpu6500_get_attitude`](crate::mpu::mpu6500_get_attitude) and
+//! [`adc_get_all_channels`](crate::adc_io::adc_get_all_channels)) retry according to a
+//! [`RetryPolicy`] before reporting a failure.
+//!
+//! The process-wide policy is set with [`set_policy`] and defaults to [`RetryPolicy::none`],
+//! which keeps the single-attempt behaviour. [`with_policy`] overrides it for the calls made
+//! inside a closure on the current thread.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use uptechstar_rs::{mpu, retry};
+//! use uptechstar_rs::retry::RetryPolicy;
+//!
+//! // Up to 3 attempts for every read, waiting 1ms, then 2ms between them
+//! retry::set_policy(RetryPolicy::new(3).with_backoff(Duration::from_millis(1)));
+//!
+//! // A single attempt for this one latency-sensitive read
+//! let mut accel = [0.0f32; 3];
+//! let result = retry::with_policy(RetryPolicy::none(), || mpu::mpu6500_get_accel(&mut accel));
+//! ```
+
+use log::debug;
+use once_cell::sync::Lazy;
+use std::cell::Cell;
+use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+
+/// How often and how patiently a failing hardware call is retried.
+///
+/// The delay before the first retry is the `backoff`; it doubles for every further retry, up
+/// to `max_backoff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    attempts: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a policy making up to `attempts` calls in total, without delay between them.
+    ///
+    /// An `attempts` of 0 is treated as 1.
+    pub fn new(attempts: u32) -> Self {
+        RetryPolicy {
+            attempts: attempts.max(1),
+            backoff: Duration::ZERO,
+            max_backoff: Duration::from_millis(100),
+        }
+    }
+
+    /// A policy making exactly one attempt.
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Caps the delay between two attempts. Defaults to 100ms.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// The total number of attempts.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// The delay before retry number `retry` (starting at 0).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use uptechstar_rs::retry::RetryPolicy;
+    ///
+    /// let policy = RetryPolicy::new(5)
+    ///     .with_backoff(Duration::from_millis(2))
+    ///     .with_max_backoff(Duration::from_millis(5));
+    ///
+    /// assert_eq!(policy.delay(0), Duration::from_millis(2));
+    /// assert_eq!(policy.delay(1), Duration::from_millis(4));
+    /// assert_eq!(policy.delay(2), Duration::from_millis(5));
+    /// ```
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(1u32.checked_shl(retry).unwrap_or(u32::MAX))
+            .min(self.max_backoff)
+    }
+
+    /// Calls `operation` until it returns `0` or the attempts are used up.
+    ///
+    /// Returns the status code of the last attempt.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::retry::RetryPolicy;
+    ///
+    /// let mut calls = 0;
+    /// let result = RetryPolicy::new(3).run(|| {
+    ///     calls += 1;
+    ///     if calls < 2 { -1 } else { 0 }
+    /// });
+    ///
+    /// assert_eq!(result, 0);
+    /// assert_eq!(calls, 2);
+    /// ```
+    pub fn run<F: FnMut() -> i32>(&self, mut operation: F) -> i32 {
+        let mut result = operation();
+
+        for retry in 0..self.attempts - 1 {
+            if result == 0 {
+                break;
+            }
+            debug!("Hardware call failed with status {}, retrying ({}/{})", result, retry + 1, self.attempts - 1);
+
+            let delay = self.delay(retry);
+            if !delay.is_zero() {
+                thread::sleep(delay);
+            }
+            result = operation();
+        }
+
+        result
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+static POLICY: Lazy<RwLock<RetryPolicy>> = Lazy::new(|| RwLock::new(RetryPolicy::none()));
+
+thread_local! {
+    static OVERRIDE: Cell<Option<RetryPolicy>> = const { Cell::new(None) };
+}
+
+/// Returns the policy in effect on the current thread.
+pub fn policy() -> RetryPolicy {
+    OVERRIDE
+        .with(Cell::get)
+        .unwrap_or_else(|| *POLICY.read().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Sets the process-wide policy used by the read wrappers.
+///
+/// # Returns
+///
+/// * `RetryPolicy` - The previous policy, so it can be restored later.
+pub fn set_policy(policy: RetryPolicy) -> RetryPolicy {
+    let mut guard = POLICY.write().unwrap_or_else(|e| e.into_inner());
+    std::mem::replace(&mut *guard, policy)
+}
+
+/// Runs `f` with `policy` in place of the process-wide one for calls on the current thread.
+pub fn with_policy<T, F: FnOnce() -> T>(policy: RetryPolicy, f: F) -> T {
+    struct Restore(Option<RetryPolicy>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            OVERRIDE.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let _restore = Restore(OVERRIDE.with(|cell| cell.replace(Some(policy))));
+    f()
+}
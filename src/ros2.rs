@@ -0,0 +1,196 @@
+//! ROS 2 message conversions for a bridge node.
+//!
+//! A ROS 2 node is built with `rclrs` inside a colcon workspace, against the message crates
+//! generated there, so this crate does not depend on it. Instead, this module does the
+//! board-specific half of a bridge:
+//!
+//! - [`Imu`] converts [`MpuSample`]s into the fields of `sensor_msgs/msg/Imu`: SI units, an
+//!   orientation quaternion and REP 145 covariances,
+//! - ADC frames go out as `std_msgs/msg/Int32MultiArray` with `data` set to
+//!   [`AdcFrame`]`.0`,
+//! - [`CommandHandler`] applies `std_msgs/msg/ColorRGBA` LED commands and
+//!   `std_msgs/msg/String` display commands to a [`Screen`].
+//!
+//! The node itself is the `uptech_bridge` package in the `ros2/` directory of the repository.
+//! Copy or link it into the `src/` directory of a colcon workspace with `colcon-ros-cargo` set
+//! up, build it with `colcon build --packages-select uptech_bridge` and start it with
+//! `ros2 run uptech_bridge uptech_bridge`. It publishes [`IMU_TOPIC`] and [`ADC_TOPIC`] at
+//! 50 Hz and subscribes to [`LED_TOPIC`]`/0`, [`LED_TOPIC`]`/1` and [`DISPLAY_TOPIC`]. The
+//! topic names are suggestions; remap them as usual.
+
+use crate::adc_io::AdcFrame;
+use crate::display::{Color, Screen};
+use crate::mpu::{AccelUnit, AngleUnit, AngularRateUnit, MpuSample, Units};
+
+/// Suggested topic for `sensor_msgs/msg/Imu`.
+pub const IMU_TOPIC: &str = "uptech/imu";
+/// Suggested topic for the ADC channels as `std_msgs/msg/Int32MultiArray`.
+pub const ADC_TOPIC: &str = "uptech/adc";
+/// Suggested topic prefix for `std_msgs/msg/ColorRGBA` LED commands, followed by the LED index,
+/// e.g. `uptech/led/0`.
+pub const LED_TOPIC: &str = "uptech/led";
+/// Suggested topic for `std_msgs/msg/String` display commands.
+pub const DISPLAY_TOPIC: &str = "uptech/display";
+
+/// Standard gravity in m/s².
+const STANDARD_GRAVITY: f64 = 9.80665;
+
+/// The content of a `sensor_msgs/msg/Imu` message, without the header.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::mpu::{MpuSample, Units};
+/// use uptechstar_rs::ros2::Imu;
+///
+/// let sample = MpuSample { accel: [0.0, 0.0, 1.0], gyro: [0.0, 0.0, 90.0], attitude: [0.0, 0.0, 90.0] };
+/// let imu = Imu::from_sample(&sample, Units::default());
+///
+/// assert!((imu.linear_acceleration[2] - 9.80665).abs() < 1e-6);
+/// assert!((imu.angular_velocity[2] - std::f64::consts::FRAC_PI_2).abs() < 1e-6);
+/// // A quarter turn about Z.
+/// let [x, y, z, w] = imu.orientation;
+/// assert!(x.abs() < 1e-6 && y.abs() < 1e-6);
+/// assert!((z - w).abs() < 1e-6 && (w - 0.5f64.sqrt()).abs() < 1e-6);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Imu {
+    /// Orientation quaternion as `x`, `y`, `z`, `w`.
+    pub orientation: [f64; 4],
+    /// Row-major covariance of the orientation about X, Y and Z in rad².
+    pub orientation_covariance: [f64; 9],
+    /// Angular velocity in rad/s.
+    pub angular_velocity: [f64; 3],
+    /// Row-major covariance of the angular velocity in (rad/s)².
+    pub angular_velocity_covariance: [f64; 9],
+    /// Linear acceleration in m/s², including gravity.
+    pub linear_acceleration: [f64; 3],
+    /// Row-major covariance of the linear acceleration in (m/s²)².
+    pub linear_acceleration_covariance: [f64; 9],
+}
+
+impl Imu {
+    /// Converts `sample`, reported in `units`, usually [`mpu::units()`](crate::mpu::units).
+    ///
+    /// The covariances are all zero, which REP 145 defines as "unknown"; set them with
+    /// [`with_variances`](Self::with_variances) if the sensor has been characterized.
+    pub fn from_sample(sample: &MpuSample, units: Units) -> Self {
+        let accel = sample.accel.map(|a| match units.accel {
+            AccelUnit::G => a as f64 * STANDARD_GRAVITY,
+            AccelUnit::MetersPerSecondSquared => a as f64,
+        });
+        let gyro = sample.gyro.map(|w| match units.gyro {
+            AngularRateUnit::DegreesPerSecond => (w as f64).to_radians(),
+            AngularRateUnit::RadiansPerSecond => w as f64,
+        });
+        let attitude = sample.attitude.map(|angle| match units.angle {
+            AngleUnit::Degrees => (angle as f64).to_radians(),
+            AngleUnit::Radians => angle as f64,
+        });
+
+        Imu {
+            orientation: attitude_to_quaternion(attitude),
+            orientation_covariance: [0.0; 9],
+            angular_velocity: gyro,
+            angular_velocity_covariance: [0.0; 9],
+            linear_acceleration: accel,
+            linear_acceleration_covariance: [0.0; 9],
+        }
+    }
+
+    /// Sets diagonal covariances from the per-axis variances of orientation, angular velocity
+    /// and linear acceleration, in the units of the message.
+    pub fn with_variances(mut self, orientation: f64, angular_velocity: f64, linear_acceleration: f64) -> Self {
+        self.orientation_covariance = diagonal(orientation);
+        self.angular_velocity_covariance = diagonal(angular_velocity);
+        self.linear_acceleration_covariance = diagonal(linear_acceleration);
+        self
+    }
+
+    /// Marks the orientation as not provided, as REP 145 asks of IMUs without an orientation
+    /// estimate, e.g. when the DMP is not running.
+    pub fn without_orientation(mut self) -> Self {
+        self.orientation = [0.0; 4];
+        self.orientation_covariance[0] = -1.0;
+        self
+    }
+}
+
+fn diagonal(variance: f64) -> [f64; 9] {
+    let mut covariance = [0.0; 9];
+    covariance[0] = variance;
+    covariance[4] = variance;
+    covariance[8] = variance;
+    covariance
+}
+
+/// Converts pitch about X, roll about Y and yaw about Z in radians, as reported by the MPU6500,
+/// to an `x`, `y`, `z`, `w` quaternion.
+pub fn attitude_to_quaternion(attitude: [f64; 3]) -> [f64; 4] {
+    let [(sx, cx), (sy, cy), (sz, cz)] = attitude.map(|angle| (angle / 2.0).sin_cos());
+
+    [
+        sx * cy * cz - cx * sy * sz,
+        cx * sy * cz + sx * cy * sz,
+        cx * cy * sz - sx * sy * cz,
+        cx * cy * cz + sx * sy * sz,
+    ]
+}
+
+/// Returns the `data` of the `std_msgs/msg/Int32MultiArray` for an ADC frame.
+pub fn adc_data(frame: &AdcFrame) -> Vec<i32> {
+    frame.0.to_vec()
+}
+
+/// Applies LED and display commands received from ROS to the board.
+pub struct CommandHandler {
+    screen: Screen,
+}
+
+impl CommandHandler {
+    /// Creates a handler drawing on `screen`.
+    pub fn new(screen: Screen) -> Self {
+        CommandHandler { screen }
+    }
+
+    /// Sets LED `index` from the components of a `std_msgs/msg/ColorRGBA`, each from 0 to 1.
+    /// The alpha component dims the color.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn set_led(&mut self, index: i32, r: f32, g: f32, b: f32, a: f32) -> &mut Self {
+        self.screen.set_led_color(index, rgba_to_color(r, g, b, a));
+        self
+    }
+
+    /// Clears the screen and shows `text` from a `std_msgs/msg/String`.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn show_text(&mut self, text: &str) -> &mut Self {
+        self.screen.fill_screen(Color::BLACK).print(text).refresh();
+        self
+    }
+
+    /// Returns the screen, e.g. to draw more than text.
+    pub fn screen(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
+}
+
+/// Converts `std_msgs/msg/ColorRGBA` components, each from 0 to 1, to a color value, using
+/// alpha as brightness.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::display::Color;
+/// use uptechstar_rs::ros2::rgba_to_color;
+///
+/// assert_eq!(rgba_to_color(1.0, 0.0, 0.0, 1.0), Color::RED);
+/// assert_eq!(rgba_to_color(1.0, 1.0, 1.0, 0.0), Color::BLACK);
+/// ```
+pub fn rgba_to_color(r: f32, g: f32, b: f32, a: f32) -> u32 {
+    let channel = |value: f32| ((value * a).clamp(0.0, 1.0) * 255.0).round() as u8;
+    Color::new_color(channel(r), channel(g), channel(b))
+}
@@ -0,0 +1,86 @@
+//! Real-time scheduling for latency-sensitive threads.
+//!
+//! Under the default Linux scheduler, a control loop competes with every other thread, and
+//! a burst of display or logging work can delay it by several milliseconds. Threads moved to
+//! `SCHED_FIFO` preempt all normal threads as soon as they become runnable.
+//!
+//! The [`Sampler`](crate::sampler::Sampler) and
+//! [`RateScheduler`](crate::scheduler::RateScheduler) promote their own threads when
+//! configured `with_realtime_priority`.
+//!
+//! Real-time priorities need root or the `CAP_SYS_NICE` capability (or an `RLIMIT_RTPRIO`
+//! limit, see `ulimit -r`). A real-time thread that never blocks starves the rest of the
+//! system, so only promote loops that sleep between ticks.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::rt;
+//!
+//! if let Err(e) = rt::promote_current_thread(50) {
+//!     eprintln!("Running without real-time priority: {}", e);
+//! }
+//! ```
+
+use log::{info, warn};
+use std::io;
+
+/// Moves the calling thread to `SCHED_FIFO` with `priority`, clamped to the range the system
+/// supports (1–99 on Linux). Higher priorities preempt lower ones.
+pub fn promote_current_thread(priority: i32) -> io::Result<()> {
+    let (min, max) = unsafe {
+        (
+            libc::sched_get_priority_min(libc::SCHED_FIFO),
+            libc::sched_get_priority_max(libc::SCHED_FIFO),
+        )
+    };
+    let priority = priority.clamp(min, max);
+
+    set_current_thread(libc::SCHED_FIFO, priority)?;
+    info!("Thread {:?} promoted to SCHED_FIFO priority {}", std::thread::current().name(), priority);
+    Ok(())
+}
+
+/// Moves the calling thread back to the default time-sharing scheduler.
+pub fn demote_current_thread() -> io::Result<()> {
+    set_current_thread(libc::SCHED_OTHER, 0)
+}
+
+/// Returns the `SCHED_FIFO` priority of the calling thread, or `None` if it is not a
+/// real-time thread.
+pub fn current_priority() -> io::Result<Option<i32>> {
+    let mut policy = 0;
+    let mut param = libc::sched_param { sched_priority: 0 };
+
+    let code = unsafe { libc::pthread_getschedparam(libc::pthread_self(), &mut policy, &mut param) };
+    if code != 0 {
+        return Err(io::Error::from_raw_os_error(code));
+    }
+
+    Ok((policy == libc::SCHED_FIFO).then_some(param.sched_priority))
+}
+
+fn set_current_thread(policy: i32, priority: i32) -> io::Result<()> {
+    let param = libc::sched_param { sched_priority: priority };
+
+    let code = unsafe { libc::pthread_setschedparam(libc::pthread_self(), policy, &param) };
+    if code != 0 {
+        return Err(io::Error::from_raw_os_error(code));
+    }
+    Ok(())
+}
+
+/// Promotes the calling thread if a priority is configured, logging instead of failing, so a
+/// worker thread keeps running without the privilege.
+pub(crate) fn promote_or_warn(priority: Option<i32>) {
+    if let Some(priority) = priority
+        && let Err(e) = promote_current_thread(priority)
+    {
+        warn!(
+            "Failed to set real-time priority {} for thread {:?}: {}",
+            priority,
+            std::thread::current().name(),
+            e
+        );
+    }
+}
@@ -0,0 +1,416 @@
+//! Background sampling of board sensors.
+//!
+//! A [`Sampler`] owns a thread that polls the enabled sources (ADC, IO inputs, MPU) at a fixed
+//! rate and fans the readings out to any number of subscribers over channels. Consumers such as
+//! the [`SensorLogger`](crate::logging::SensorLogger) share one sampling thread instead of each
+//! hammering the FFI separately.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::sampler::{Reading, Sampler};
+//!
+//! let mut sampler = Sampler::new(100.0).with_adc(true).with_mpu(true);
+//! let readings = sampler.subscribe();
+//! sampler.start();
+//!
+//! for reading in readings.iter().take(200) {
+//!     match reading.value {
+//!         Reading::Adc(frame) => println!("{:?} ADC {:?}", reading.timestamp, frame.0),
+//!         Reading::Mpu(sample) => println!("{:?} yaw {:.1}", reading.timestamp, sample.attitude[2]),
+//!         Reading::Io(levels) => println!("{:?} IO {:08b}", reading.timestamp, levels.0),
+//!     }
+//! }
+//! ```
+//!
+//! # Allocation
+//!
+//! Readings are built from the fixed-size, `Copy` frame types [`AdcFrame`],
+//! [`IoFrame`] and [`MpuFrame`](crate::mpu::MpuFrame), so reading the hardware and turning the
+//! result into a [`Reading`] or an encoded [`TelemetryFrame`](crate::telemetry::TelemetryFrame)
+//! never touches the heap once the first calls have initialized the crate's bookkeeping. Only
+//! the channels the readings are sent over allocate, in blocks of many messages.
+//!
+//! ```rust
+//! use std::alloc::{GlobalAlloc, Layout, System};
+//! use std::sync::Arc;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use std::time::Duration;
+//! use uptechstar_rs::adc_io::{self, AdcFrame, IoFrame};
+//! use uptechstar_rs::backend::set_backend;
+//! use uptechstar_rs::mpu::{self, MpuSample};
+//! use uptechstar_rs::replay::ReplayBackend;
+//! use uptechstar_rs::sampler::{Reading, Timestamped};
+//! use uptechstar_rs::telemetry::TelemetryFrame;
+//!
+//! struct Counting;
+//!
+//! static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! unsafe impl GlobalAlloc for Counting {
+//!     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+//!         ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+//!         unsafe { System.alloc(layout) }
+//!     }
+//!
+//!     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+//!         unsafe { System.dealloc(ptr, layout) }
+//!     }
+//! }
+//!
+//! #[global_allocator]
+//! static GLOBAL: Counting = Counting;
+//!
+//! fn main() {
+//!     let frame = TelemetryFrame {
+//!         adc: Some(AdcFrame([512; 10])),
+//!         io: Some(IoFrame(0b1010)),
+//!         mpu: Some(MpuSample { accel: [0.0, 0.0, 1.0], ..Default::default() }),
+//!         ..Default::default()
+//!     };
+//!     set_backend(Arc::new(ReplayBackend::from_frames(vec![frame; 101])));
+//!
+//!     let mut bytes = Vec::with_capacity(256);
+//!     let mut tick = |n: u64| {
+//!         let readings = [
+//!             adc_io::adc_get_frame().ok().map(Reading::Adc),
+//!             Some(Reading::Io(adc_io::io_get_frame())),
+//!             mpu::mpu6500_get_sample().ok().map(Reading::Mpu),
+//!         ];
+//!         for value in readings.into_iter().flatten() {
+//!             bytes.clear();
+//!             TelemetryFrame::from(&Timestamped { timestamp: Duration::from_millis(n), value }).encode_into(&mut bytes);
+//!         }
+//!     };
+//!
+//!     tick(0);
+//!     let before = ALLOCATIONS.load(Ordering::Relaxed);
+//!     for n in 1..=100 {
+//!         tick(n);
+//!     }
+//!     assert_eq!(ALLOCATIONS.load(Ordering::Relaxed), before);
+//! }
+//! ```
+
+use crate::adc_io::{self, AdcFrame, IoFrame};
+use crate::mpu::{self, MpuSample};
+use log::{debug, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A value tagged with the monotonic time at which it was sampled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamped<T> {
+    /// Time elapsed since the producing sampler was created.
+    pub timestamp: Duration,
+    /// The sampled value.
+    pub value: T,
+}
+
+/// Paces a loop at a fixed period using absolute deadlines, so the rate does not drift.
+pub(crate) struct Ticker {
+    period: Duration,
+    deadline: Instant,
+}
+
+impl Ticker {
+    /// Creates a ticker whose first deadline is one period from now.
+    pub(crate) fn new(period: Duration) -> Self {
+        Ticker {
+            period,
+            deadline: Instant::now() + period,
+        }
+    }
+
+    /// Sleeps until the next deadline.
+    ///
+    /// If the loop overran, the schedule is reset to now instead of bursting to catch up.
+    pub(crate) fn wait(&mut self) {
+        let now = Instant::now();
+        if self.deadline > now {
+            thread::sleep(self.deadline - now);
+        } else {
+            self.deadline = now;
+        }
+        self.deadline += self.period;
+    }
+}
+
+/// Converts a rate in Hz into a period, panicking on nonsensical rates.
+pub(crate) fn period_from_rate(rate_hz: f32) -> Duration {
+    assert!(
+        rate_hz.is_finite() && rate_hz > 0.0,
+        "Sampling rate must be positive, got {}",
+        rate_hz
+    );
+    Duration::from_secs_f32(1.0 / rate_hz)
+}
+
+/// A single reading delivered by a [`Sampler`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Reading {
+    /// All 10 ADC channels.
+    Adc(AdcFrame),
+    /// The IO input levels.
+    Io(IoFrame),
+    /// Acceleration, angular velocity and attitude.
+    Mpu(MpuSample),
+}
+
+/// A fixed-rate background sampler publishing [`Reading`]s to its subscribers.
+///
+/// Each tick the enabled sources are read in the order ADC, IO, MPU and every successful
+/// reading is sent to all subscribers. Failed reads are skipped; the wrapper functions already
+/// log them. Subscribers whose receiver has been dropped are removed automatically.
+///
+/// The sampling thread is stopped, and all subscriptions are closed, when
+/// [`stop`](Sampler::stop) is called or the sampler is dropped.
+pub struct Sampler {
+    period: Duration,
+    adc: bool,
+    io: bool,
+    mpu: bool,
+    started: Instant,
+    #[cfg(all(feature = "rt", target_os = "linux"))]
+    priority: Option<i32>,
+    subscribers: Arc<Mutex<Vec<Sender<Timestamped<Reading>>>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Sampler {
+    /// Creates a stopped sampler ticking at `rate_hz` with no sources enabled.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn new(rate_hz: f32) -> Self {
+        Sampler {
+            period: period_from_rate(rate_hz),
+            adc: false,
+            io: false,
+            mpu: false,
+            started: Instant::now(),
+            #[cfg(all(feature = "rt", target_os = "linux"))]
+            priority: None,
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Enables or disables sampling of the 10 ADC channels.
+    pub fn with_adc(mut self, enabled: bool) -> Self {
+        self.adc = enabled;
+        self
+    }
+
+    /// Enables or disables sampling of the IO input levels.
+    pub fn with_io(mut self, enabled: bool) -> Self {
+        self.io = enabled;
+        self
+    }
+
+    /// Enables or disables sampling of the MPU6500.
+    pub fn with_mpu(mut self, enabled: bool) -> Self {
+        self.mpu = enabled;
+        self
+    }
+
+    /// Runs the sampling thread with `SCHED_FIFO` real-time `priority`, see [`rt`](crate::rt).
+    /// If the priority cannot be set, the thread logs a warning and samples normally.
+    #[cfg(all(feature = "rt", target_os = "linux"))]
+    pub fn with_realtime_priority(mut self, priority: i32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Returns the sampling period.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the instant all timestamps of this sampler are relative to.
+    pub fn epoch(&self) -> Instant {
+        self.started
+    }
+
+    /// Registers a new subscriber and returns its receiving end.
+    ///
+    /// Subscribing works both before and after [`start`](Sampler::start).
+    pub fn subscribe(&self) -> Receiver<Timestamped<Reading>> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    /// Returns `true` while the sampling thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts the sampling thread. Does nothing if it is already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!(
+            "Starting sampler at {:.1} Hz (adc: {}, io: {}, mpu: {})",
+            1.0 / self.period.as_secs_f32(),
+            self.adc,
+            self.io,
+            self.mpu
+        );
+
+        self.running.store(true, Ordering::Release);
+
+        let period = self.period;
+        let (adc, io, mpu) = (self.adc, self.io, self.mpu);
+        let started = self.started;
+        #[cfg(all(feature = "rt", target_os = "linux"))]
+        let priority = self.priority;
+        let subscribers = Arc::clone(&self.subscribers);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-sampler".into())
+                .spawn(move || {
+                    #[cfg(all(feature = "rt", target_os = "linux"))]
+                    crate::rt::promote_or_warn(priority);
+
+                    let mut ticker = Ticker::new(period);
+
+                    while running.load(Ordering::Acquire) {
+                        let readings = [
+                            adc.then(adc_io::adc_get_frame).and_then(Result::ok).map(Reading::Adc),
+                            io.then(adc_io::io_get_frame).map(Reading::Io),
+                            mpu.then(mpu::mpu6500_get_sample).and_then(Result::ok).map(Reading::Mpu),
+                        ];
+
+                        let timestamp = started.elapsed();
+                        let mut subscribers = subscribers.lock().unwrap_or_else(|e| e.into_inner());
+                        for value in readings.into_iter().flatten() {
+                            subscribers.retain(|subscriber| {
+                                subscriber.send(Timestamped { timestamp, value }).is_ok()
+                            });
+                        }
+                        drop(subscribers);
+
+                        ticker.wait();
+                    }
+
+                    debug!("Sampler thread exited");
+                })
+                .expect("Failed to spawn sampler thread"),
+        );
+
+        self
+    }
+
+    /// Stops the sampling thread and waits for it to exit.
+    ///
+    /// Stopping closes all current subscriptions, so consumers iterating over their receiver
+    /// finish cleanly. Subscribe again before restarting the sampler.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Sampler stopped");
+        }
+
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+
+        self
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Spawns a dedicated polling thread feeding an async stream.
+///
+/// `read` is called at `rate_hz` and every `Some` value is forwarded. Samples are dropped
+/// rather than queued when the consumer falls more than `capacity` samples behind, so a slow
+/// task never delays the sampling cadence. The thread exits once the stream is dropped.
+#[cfg(feature = "async")]
+pub(crate) fn spawn_stream<T, F>(
+    name: &str,
+    rate_hz: f32,
+    capacity: usize,
+    read: F,
+) -> tokio_stream::wrappers::ReceiverStream<Timestamped<T>>
+where
+    T: Send + 'static,
+    F: FnMut() -> Option<T> + Send + 'static,
+{
+    let mut ticker = Ticker::new(period_from_rate(rate_hz));
+    spawn_paced_stream(name, capacity, move || ticker.wait(), read)
+}
+
+/// Like [`spawn_stream`], but calls `pace` between two reads instead of waiting for the next
+/// tick, for sources that signal when new data is available.
+#[cfg(feature = "async")]
+pub(crate) fn spawn_paced_stream<T, P, F>(
+    name: &str,
+    capacity: usize,
+    mut pace: P,
+    mut read: F,
+) -> tokio_stream::wrappers::ReceiverStream<Timestamped<T>>
+where
+    T: Send + 'static,
+    P: FnMut() + Send + 'static,
+    F: FnMut() -> Option<T> + Send + 'static,
+{
+    use tokio::sync::mpsc::error::TrySendError;
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(capacity);
+
+    thread::Builder::new()
+        .name(name.into())
+        .spawn(move || {
+            let started = Instant::now();
+
+            loop {
+                if let Some(value) = read() {
+                    let timestamp = started.elapsed();
+                    match sender.try_send(Timestamped { timestamp, value }) {
+                        Ok(()) => {}
+                        Err(TrySendError::Full(_)) => debug!("Stream consumer lagging, sample dropped"),
+                        Err(TrySendError::Closed(_)) => break,
+                    }
+                } else if sender.is_closed() {
+                    break;
+                }
+
+                pace();
+            }
+
+            debug!("Stream thread exited");
+        })
+        .expect("Failed to spawn stream thread");
+
+    tokio_stream::wrappers::ReceiverStream::new(receiver)
+}
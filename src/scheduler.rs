@@ -0,0 +1,297 @@
+//! Fixed-rate execution of control loop tasks.
+//!
+//! A [`RateScheduler`] runs closures at their own frequencies, such as reading the IMU at
+//! 200 Hz, updating the display at 10 Hz and logging at 50 Hz, on one or more threads.
+//! Deadlines are absolute, so rates do not drift, and tasks on a thread are run earliest
+//! deadline first, so a slow task delays the others instead of starving them.
+//!
+//! A task that is still running when its next deadline passes is counted as an overrun and
+//! the ticks it missed are skipped rather than run back to back. The timing of every task is
+//! available as [`TaskStats`].
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::thread;
+//! use std::time::Duration;
+//! use uptechstar_rs::{mpu, scheduler::RateScheduler};
+//!
+//! let mut scheduler = RateScheduler::new()
+//!     .with_task("imu", 200.0, || {
+//!         let mut accel = [0.0f32; 3];
+//!         mpu::mpu6500_get_accel(&mut accel);
+//!     })
+//!     .with_task("display", 10.0, || { /* redraw */ })
+//!     .with_threads(2);
+//!
+//! scheduler.start();
+//! thread::sleep(Duration::from_secs(10));
+//! scheduler.stop();
+//!
+//! for (name, stats) in scheduler.stats() {
+//!     println!(
+//!         "{}: {} runs, {} overruns, mean jitter {:?}, max jitter {:?}",
+//!         name,
+//!         stats.runs,
+//!         stats.overruns,
+//!         stats.mean_jitter(),
+//!         stats.max_jitter
+//!     );
+//! }
+//! ```
+
+use crate::sampler::period_from_rate;
+use log::{debug, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Timing statistics of one task.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskStats {
+    /// Number of completed runs.
+    pub runs: u64,
+    /// Runs that did not finish before the next deadline.
+    pub overruns: u64,
+    /// Ticks dropped after overruns.
+    pub skipped: u64,
+    /// Sum of the delays between deadlines and the actual start of the runs.
+    pub total_jitter: Duration,
+    /// The largest start delay.
+    pub max_jitter: Duration,
+    /// Sum of all run durations.
+    pub total_duration: Duration,
+    /// The longest run.
+    pub max_duration: Duration,
+}
+
+impl TaskStats {
+    /// The mean delay between deadlines and the start of the runs, or zero before the first run.
+    pub fn mean_jitter(&self) -> Duration {
+        self.total_jitter.checked_div(self.runs as u32).unwrap_or_default()
+    }
+
+    /// The mean run duration, or zero before the first run.
+    pub fn mean_duration(&self) -> Duration {
+        self.total_duration.checked_div(self.runs as u32).unwrap_or_default()
+    }
+}
+
+type TaskFn = Box<dyn FnMut() + Send>;
+
+#[derive(Clone)]
+struct Task {
+    name: String,
+    period: Duration,
+    run: Arc<Mutex<TaskFn>>,
+    stats: Arc<Mutex<TaskStats>>,
+}
+
+/// Runs registered closures at fixed rates on a pool of threads.
+///
+/// Tasks are distributed over the threads when the scheduler starts, balancing the sum of
+/// their rates; tasks on the same thread never run concurrently. The threads are stopped when
+/// the scheduler is dropped.
+pub struct RateScheduler {
+    tasks: Vec<Task>,
+    threads: usize,
+    #[cfg(all(feature = "rt", target_os = "linux"))]
+    priority: Option<i32>,
+    running: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Default for RateScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateScheduler {
+    /// Creates a scheduler without tasks that runs on a single thread.
+    pub fn new() -> Self {
+        RateScheduler {
+            tasks: Vec::new(),
+            threads: 1,
+            #[cfg(all(feature = "rt", target_os = "linux"))]
+            priority: None,
+            running: Arc::new(AtomicBool::new(false)),
+            handles: Vec::new(),
+        }
+    }
+
+    /// Registers `task` to run at `rate_hz`. Takes effect on the next
+    /// [`start`](RateScheduler::start).
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn with_task<F>(mut self, name: impl Into<String>, rate_hz: f32, task: F) -> Self
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.tasks.push(Task {
+            name: name.into(),
+            period: period_from_rate(rate_hz),
+            run: Arc::new(Mutex::new(Box::new(task))),
+            stats: Arc::new(Mutex::new(TaskStats::default())),
+        });
+        self
+    }
+
+    /// Sets the number of threads the tasks are distributed over. Extra threads beyond the
+    /// number of tasks are not started.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Runs all scheduler threads with `SCHED_FIFO` real-time `priority`, see
+    /// [`rt`](crate::rt). If the priority cannot be set, the threads log a warning and run
+    /// normally.
+    #[cfg(all(feature = "rt", target_os = "linux"))]
+    pub fn with_realtime_priority(mut self, priority: i32) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Returns the timing statistics of every task, in registration order.
+    pub fn stats(&self) -> Vec<(&str, TaskStats)> {
+        self.tasks
+            .iter()
+            .map(|task| (task.name.as_str(), *task.stats.lock().unwrap_or_else(|e| e.into_inner())))
+            .collect()
+    }
+
+    /// Clears the statistics of all tasks.
+    pub fn reset_stats(&self) {
+        for task in &self.tasks {
+            *task.stats.lock().unwrap_or_else(|e| e.into_inner()) = TaskStats::default();
+        }
+    }
+
+    /// Returns `true` while the scheduler threads are running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts the scheduler threads. Every task first runs immediately, then at its rate.
+    /// Does nothing if the scheduler is already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        // Greedily place the fastest tasks first, each on the least loaded thread.
+        let mut order: Vec<usize> = (0..self.tasks.len()).collect();
+        order.sort_by_key(|&index| self.tasks[index].period);
+        let mut groups: Vec<(f64, Vec<usize>)> = vec![(0.0, Vec::new()); self.threads.min(self.tasks.len())];
+        for index in order {
+            let group = groups
+                .iter_mut()
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .expect("at least one thread");
+            group.0 += 1.0 / self.tasks[index].period.as_secs_f64();
+            group.1.push(index);
+        }
+
+        info!("Starting scheduler with {} tasks on {} threads", self.tasks.len(), groups.len());
+        self.running.store(true, Ordering::Release);
+
+        for (number, (_, indices)) in groups.into_iter().enumerate() {
+            let tasks: Vec<Task> = indices.iter().map(|&index| self.tasks[index].clone()).collect();
+            let running = Arc::clone(&self.running);
+            #[cfg(all(feature = "rt", target_os = "linux"))]
+            let priority = self.priority;
+
+            self.handles.push(
+                thread::Builder::new()
+                    .name(format!("uptech-sched-{}", number))
+                    .spawn(move || {
+                        #[cfg(all(feature = "rt", target_os = "linux"))]
+                        crate::rt::promote_or_warn(priority);
+
+                        run_thread(tasks, running)
+                    })
+                    .expect("Failed to spawn scheduler thread"),
+            );
+        }
+
+        self
+    }
+
+    /// Stops the scheduler threads after their current runs and waits for them to exit.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        for handle in &self.handles {
+            handle.thread().unpark();
+        }
+        if !self.handles.is_empty() {
+            for handle in self.handles.drain(..) {
+                let _ = handle.join();
+            }
+            info!("Scheduler stopped");
+        }
+
+        self
+    }
+}
+
+impl Drop for RateScheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Runs `tasks` earliest deadline first until `running` is cleared.
+fn run_thread(tasks: Vec<Task>, running: Arc<AtomicBool>) {
+    let started = Instant::now();
+    let mut deadlines = vec![started; tasks.len()];
+
+    while running.load(Ordering::Acquire) {
+        let (index, deadline) = deadlines
+            .iter()
+            .copied()
+            .enumerate()
+            .min_by_key(|&(_, deadline)| deadline)
+            .expect("scheduler thread without tasks");
+
+        let now = Instant::now();
+        if deadline > now {
+            // Parking instead of sleeping lets `stop` wake the thread early.
+            thread::park_timeout(deadline - now);
+            continue;
+        }
+
+        let Task { name, period, run, stats } = &tasks[index];
+        let jitter = now - deadline;
+        (run.lock().unwrap_or_else(|e| e.into_inner()))();
+        let finished = Instant::now();
+        let duration = finished - now;
+
+        let behind = ((finished - deadline).as_nanos() / period.as_nanos()) as u64;
+        deadlines[index] = deadline + Duration::from_nanos((period.as_nanos() * (behind as u128 + 1)) as u64);
+
+        let mut stats = stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.runs += 1;
+        stats.total_jitter += jitter;
+        stats.max_jitter = stats.max_jitter.max(jitter);
+        stats.total_duration += duration;
+        stats.max_duration = stats.max_duration.max(duration);
+        if behind > 0 {
+            stats.overruns += 1;
+            stats.skipped += behind;
+            debug!("Task '{}' overran by {} periods, taking {:?}", name, behind, duration);
+        }
+    }
+
+    debug!("Scheduler thread exited");
+}
@@ -0,0 +1,400 @@
+//! Serial ports framed onto the event bus.
+//!
+//! Many robots built on the board drive a secondary motor controller or sensor hub over a
+//! `/dev/ttyS*` or `/dev/ttyUSB*` port. [`SerialLink`] opens such a port, splits what it
+//! receives into frames on a background thread and publishes every frame as an
+//! [`Event::SerialFrame`](crate::events::Event::SerialFrame), so the rest of the program
+//! handles them like any other event. [`FrameDecoder`] does the framing without any I/O.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::events::{self, Event};
+//! use uptechstar_rs::serial::{Framing, SerialLink};
+//!
+//! let events = events::subscribe();
+//! let mut link = SerialLink::open("/dev/ttyUSB0", 115_200).unwrap().with_framing(Framing::Lines);
+//! link.start();
+//! link.send(b"SPEED 100 100").unwrap();
+//!
+//! for event in events {
+//!     if let Event::SerialFrame { port, data } = event {
+//!         println!("{}: {}", port, String::from_utf8_lossy(&data));
+//!     }
+//! }
+//! ```
+
+use crate::events::{self, Event, EventBus};
+use log::{debug, info, warn};
+use serialport::SerialPort;
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long a read waits before the reader thread checks whether it should stop.
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How a byte stream is split into frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Framing {
+    /// Text lines ending in `\n`, with an optional `\r` before it. Empty lines are skipped.
+    #[default]
+    Lines,
+    /// Frames ending in the given byte. Empty frames are skipped.
+    Delimited(u8),
+    /// Frames preceded by their length as a big-endian `u16`.
+    LengthPrefixed,
+}
+
+impl Framing {
+    /// Returns `payload` framed for sending.
+    ///
+    /// Fails if `payload` contains the delimiter, or is longer than 65535 bytes with
+    /// [`Framing::LengthPrefixed`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use uptechstar_rs::serial::Framing;
+    ///
+    /// assert_eq!(Framing::Lines.encode(b"PING"), Ok(b"PING\n".to_vec()));
+    /// assert_eq!(Framing::LengthPrefixed.encode(&[7, 8]), Ok(vec![0, 2, 7, 8]));
+    /// assert!(Framing::Delimited(0).encode(&[1, 0, 2]).is_err());
+    /// ```
+    pub fn encode(&self, payload: &[u8]) -> Result<Vec<u8>, &'static str> {
+        match *self {
+            Framing::Lines | Framing::Delimited(_) => {
+                let delimiter = self.delimiter();
+                if payload.contains(&delimiter) {
+                    return Err("Payload contains the frame delimiter");
+                }
+                let mut frame = Vec::with_capacity(payload.len() + 1);
+                frame.extend_from_slice(payload);
+                frame.push(delimiter);
+                Ok(frame)
+            }
+            Framing::LengthPrefixed => {
+                let len = u16::try_from(payload.len()).map_err(|_| "Payload is longer than 65535 bytes")?;
+                let mut frame = Vec::with_capacity(payload.len() + 2);
+                frame.extend_from_slice(&len.to_be_bytes());
+                frame.extend_from_slice(payload);
+                Ok(frame)
+            }
+        }
+    }
+
+    fn delimiter(&self) -> u8 {
+        match *self {
+            Framing::Delimited(delimiter) => delimiter,
+            _ => b'\n',
+        }
+    }
+}
+
+/// Splits received bytes into frames.
+///
+/// Bytes are buffered until a frame is complete, so data can be pushed in whatever chunks the
+/// port returns. Frames longer than the [maximum length](FrameDecoder::with_max_len) are
+/// dropped and counted. A delimited stream picks up again after the next delimiter; a
+/// length-prefixed one skips a byte at a time until the length prefix fits again.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::serial::{FrameDecoder, Framing};
+///
+/// let mut decoder = FrameDecoder::new(Framing::Lines);
+/// assert!(decoder.push(b"OK 12").is_empty());
+/// assert_eq!(decoder.push(b"0\r\n\nERR\n"), [b"OK 120".to_vec(), b"ERR".to_vec()]);
+///
+/// let mut decoder = FrameDecoder::new(Framing::LengthPrefixed);
+/// assert_eq!(decoder.push(&[0, 3, 1, 2, 3, 0, 1]), [vec![1, 2, 3]]);
+/// assert_eq!(decoder.push(&[9]), [vec![9]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    framing: Framing,
+    max_len: usize,
+    buffer: Vec<u8>,
+    discarding: bool,
+    dropped: u64,
+}
+
+impl FrameDecoder {
+    /// Creates a decoder with a maximum frame length of 4096 bytes.
+    pub fn new(framing: Framing) -> Self {
+        FrameDecoder {
+            framing,
+            max_len: 4096,
+            buffer: Vec::new(),
+            discarding: false,
+            dropped: 0,
+        }
+    }
+
+    /// Sets the maximum frame length in bytes, without the delimiter or length prefix.
+    ///
+    /// # Panics
+    ///
+    /// If `max_len` is zero.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        assert!(max_len > 0, "Maximum frame length must not be zero");
+        self.max_len = max_len;
+        self
+    }
+
+    /// Returns the framing.
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Returns the number of frames dropped for being too long.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Discards a partially received frame.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.discarding = false;
+    }
+
+    /// Feeds received bytes and returns the frames they complete.
+    pub fn push(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        match self.framing {
+            Framing::Lines | Framing::Delimited(_) => self.push_delimited(data),
+            Framing::LengthPrefixed => self.push_length_prefixed(data),
+        }
+    }
+
+    fn push_delimited(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        let delimiter = self.framing.delimiter();
+        let mut frames = Vec::new();
+
+        for &byte in data {
+            if byte != delimiter {
+                if self.discarding {
+                    continue;
+                }
+                if self.buffer.len() >= self.max_len {
+                    // Allow for the `\r` of a line that is exactly the maximum length.
+                    if !(self.framing == Framing::Lines && byte == b'\r') {
+                        self.dropped += 1;
+                        self.buffer.clear();
+                        self.discarding = true;
+                        continue;
+                    }
+                }
+                self.buffer.push(byte);
+                continue;
+            }
+
+            if self.discarding {
+                self.discarding = false;
+                continue;
+            }
+            if self.framing == Framing::Lines && self.buffer.last() == Some(&b'\r') {
+                self.buffer.pop();
+            }
+            if !self.buffer.is_empty() {
+                frames.push(std::mem::take(&mut self.buffer));
+            }
+        }
+        frames
+    }
+
+    fn push_length_prefixed(&mut self, data: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+        let mut frames = Vec::new();
+        let mut start = 0;
+
+        while self.buffer.len() - start >= 2 {
+            let len = u16::from_be_bytes([self.buffer[start], self.buffer[start + 1]]) as usize;
+            if len > self.max_len {
+                self.dropped += 1;
+                start += 1;
+                continue;
+            }
+            if self.buffer.len() - start < 2 + len {
+                break;
+            }
+            frames.push(self.buffer[start + 2..start + 2 + len].to_vec());
+            start += 2 + len;
+        }
+
+        self.buffer.drain(..start);
+        frames
+    }
+}
+
+/// Lists the serial ports of the system, such as `/dev/ttyS1` and `/dev/ttyUSB0`.
+pub fn list_ports() -> crate::Result<Vec<String>> {
+    let ports = serialport::available_ports().map_err(io::Error::from)?;
+    Ok(ports.into_iter().map(|port| port.port_name).collect())
+}
+
+/// A serial port whose received frames are published on an [`EventBus`].
+///
+/// The port is opened for 8N1 without flow control. Received frames are published as
+/// [`Event::SerialFrame`] with the port name while the reader thread is
+/// [running](SerialLink::start); [`send`](SerialLink::send) works either way.
+pub struct SerialLink {
+    name: String,
+    port: Box<dyn SerialPort>,
+    reader: Arc<Mutex<Box<dyn SerialPort>>>,
+    framing: Framing,
+    max_frame_len: usize,
+    bus: EventBus,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SerialLink {
+    /// Opens `path` at `baud_rate` with [`Framing::Lines`], publishing on the
+    /// [process-wide bus](crate::events::global). The reader thread is not started.
+    pub fn open(path: &str, baud_rate: u32) -> crate::Result<Self> {
+        let port = serialport::new(path, baud_rate).timeout(READ_TIMEOUT).open().map_err(io::Error::from)?;
+        let reader = port.try_clone().map_err(io::Error::from)?;
+        info!("Opened serial port {} at {} baud", path, baud_rate);
+
+        Ok(SerialLink {
+            name: path.to_string(),
+            port,
+            reader: Arc::new(Mutex::new(reader)),
+            framing: Framing::Lines,
+            max_frame_len: 4096,
+            bus: events::global().clone(),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        })
+    }
+
+    /// Sets how received bytes are split into frames and how sent payloads are framed.
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Sets the maximum length of a received frame, see [`FrameDecoder::with_max_len`].
+    ///
+    /// # Panics
+    ///
+    /// If `max_len` is zero.
+    pub fn with_max_frame_len(mut self, max_len: usize) -> Self {
+        assert!(max_len > 0, "Maximum frame length must not be zero");
+        self.max_frame_len = max_len;
+        self
+    }
+
+    /// Publishes on `bus` instead of the process-wide bus.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Returns the path the port was opened with.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the framing.
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Frames `payload` and writes it to the port.
+    pub fn send(&mut self, payload: &[u8]) -> crate::Result<()> {
+        let frame = self
+            .framing
+            .encode(payload)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        self.port.write_all(&frame)?;
+        self.port.flush()?;
+        Ok(())
+    }
+
+    /// Returns `true` while the reader thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts reading frames and publishing them. Does nothing if it is already running.
+    ///
+    /// The thread stops by itself if the port fails, for example when a USB adapter is
+    /// unplugged.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!("Starting serial reader on {}", self.name);
+        self.running.store(true, Ordering::Release);
+
+        let name = self.name.clone();
+        let mut decoder = FrameDecoder::new(self.framing).with_max_len(self.max_frame_len);
+        let reader = Arc::clone(&self.reader);
+        let bus = self.bus.clone();
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-serial".into())
+                .spawn(move || {
+                    let mut port = reader.lock().unwrap_or_else(|e| e.into_inner());
+                    let mut chunk = [0u8; 256];
+
+                    while running.load(Ordering::Acquire) {
+                        let count = match port.read(&mut chunk) {
+                            Ok(count) => count,
+                            Err(e) if e.kind() == io::ErrorKind::Interrupted || e.kind() == io::ErrorKind::TimedOut => continue,
+                            Err(e) => {
+                                warn!("Serial port {} failed: {}", name, e);
+                                running.store(false, Ordering::Release);
+                                break;
+                            }
+                        };
+
+                        let dropped = decoder.dropped();
+                        for data in decoder.push(&chunk[..count]) {
+                            bus.publish(Event::SerialFrame { port: name.clone(), data });
+                        }
+                        if decoder.dropped() > dropped {
+                            debug!("Dropped an oversized frame from {}", name);
+                        }
+                    }
+
+                    debug!("Serial reader thread exited");
+                })
+                .expect("Failed to spawn serial reader thread"),
+        );
+
+        self
+    }
+
+    /// Stops the reader thread, discarding a partially received frame.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Serial reader on {} stopped", self.name);
+        }
+
+        self
+    }
+}
+
+impl Drop for SerialLink {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
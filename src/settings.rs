@@ -0,0 +1,472 @@
+//! Typed, persistent tuning parameters with an on-screen editor.
+//!
+//! Parameters such as PID gains or sensor thresholds are declared as [`Key`]s with a name, a
+//! type and a default. A [`Settings`] store holds their current values, announces changes as
+//! [`Event::SettingChanged`] on the event bus and, with the `config` feature, persists them to
+//! a TOML file. [`Settings::editor`] turns the store into a [`Pager`](crate::display::Pager)
+//! page for changing the values on the robot with three buttons.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use uptechstar_rs::settings::{Key, Settings};
+//!
+//! const KP: Key<f32> = Key::new("pid.kp", 1.2).with_range(0.0, 10.0, 0.1);
+//! const LINE_THRESHOLD: Key<i64> = Key::new("line.threshold", 2048).with_range(0.0, 4095.0, 16.0);
+//! const LOG_RUNS: Key<bool> = Key::new("log_runs", false);
+//!
+//! let settings = Settings::new().with_key(&KP).with_key(&LINE_THRESHOLD).with_key(&LOG_RUNS);
+//!
+//! assert_eq!(settings.get(&KP), 1.2);
+//! settings.set(&KP, 25.0);
+//! assert_eq!(settings.get(&KP), 10.0); // clamped to the range
+//! ```
+
+use crate::error::{Result, UptechError};
+use crate::events::{self, Event, EventBus};
+use log::{debug, warn};
+#[cfg(feature = "config")]
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+mod editor;
+
+pub use editor::SettingsEditor;
+
+/// The value of a setting.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum SettingValue {
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    Text(String),
+}
+
+impl SettingValue {
+    /// Converts to the kind of `like`, allowing integers for floats and vice versa.
+    fn coerce(self, like: &SettingValue) -> Option<SettingValue> {
+        match (self, like) {
+            (SettingValue::Int(value), SettingValue::Float(_)) => Some(SettingValue::Float(value as f32)),
+            (SettingValue::Float(value), SettingValue::Int(_)) => Some(SettingValue::Int(value.round() as i64)),
+            (value, like) if std::mem::discriminant(&value) == std::mem::discriminant(like) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Clamps numbers to `range`, `[min, max, step]`.
+    fn clamp(self, range: Option<[f32; 3]>) -> SettingValue {
+        match (self, range) {
+            (SettingValue::Int(value), Some([min, max, _])) => {
+                SettingValue::Int(value.clamp(min.ceil() as i64, max.floor() as i64))
+            }
+            (SettingValue::Float(value), Some([min, max, _])) => SettingValue::Float(value.clamp(min, max)),
+            (value, _) => value,
+        }
+    }
+}
+
+impl std::fmt::Display for SettingValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingValue::Bool(value) => write!(f, "{}", if *value { "on" } else { "off" }),
+            SettingValue::Int(value) => write!(f, "{}", value),
+            SettingValue::Float(value) => write!(f, "{}", value),
+            SettingValue::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Types that can be stored in [`Settings`].
+pub trait SettingType: Sized {
+    /// Wraps the value.
+    fn to_value(&self) -> SettingValue;
+
+    /// Unwraps a value of this type.
+    fn from_value(value: &SettingValue) -> Option<Self>;
+}
+
+impl SettingType for bool {
+    fn to_value(&self) -> SettingValue {
+        SettingValue::Bool(*self)
+    }
+
+    fn from_value(value: &SettingValue) -> Option<Self> {
+        match value {
+            SettingValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl SettingType for i64 {
+    fn to_value(&self) -> SettingValue {
+        SettingValue::Int(*self)
+    }
+
+    fn from_value(value: &SettingValue) -> Option<Self> {
+        match value {
+            SettingValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl SettingType for f32 {
+    fn to_value(&self) -> SettingValue {
+        SettingValue::Float(*self)
+    }
+
+    fn from_value(value: &SettingValue) -> Option<Self> {
+        match value {
+            SettingValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl SettingType for String {
+    fn to_value(&self) -> SettingValue {
+        SettingValue::Text(self.clone())
+    }
+
+    fn from_value(value: &SettingValue) -> Option<Self> {
+        match value {
+            SettingValue::Text(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl SettingType for &'static str {
+    fn to_value(&self) -> SettingValue {
+        SettingValue::Text(self.to_string())
+    }
+
+    // Text is read as a `String` through `Settings::get_text`.
+    fn from_value(_value: &SettingValue) -> Option<Self> {
+        None
+    }
+}
+
+/// A named, typed setting with a default value.
+///
+/// Keys are usually constants. Text keys are declared as `Key<&str>` and read with
+/// [`Settings::get_text`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Key<T> {
+    name: &'static str,
+    default: T,
+    range: Option<[f32; 3]>,
+}
+
+impl<T> Key<T> {
+    /// Declares the setting `name` with a `default` value.
+    pub const fn new(name: &'static str, default: T) -> Self {
+        Key {
+            name,
+            default,
+            range: None,
+        }
+    }
+
+    /// Limits a number to `min..=max` and sets the step of the on-screen editor.
+    pub const fn with_range(mut self, min: f32, max: f32, step: f32) -> Self {
+        self.range = Some([min, max, step]);
+        self
+    }
+
+    /// Returns the name.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the default value.
+    pub fn default_value(&self) -> &T {
+        &self.default
+    }
+}
+
+struct Entry {
+    name: String,
+    value: SettingValue,
+    default: SettingValue,
+    range: Option<[f32; 3]>,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: Vec<Entry>,
+    /// File the settings are saved to after every change.
+    #[cfg(feature = "config")]
+    path: Option<PathBuf>,
+}
+
+/// A store of setting values.
+///
+/// `Settings` is a cheap handle; clones share the same values, so the control loop and the
+/// on-screen editor can use the same store.
+#[derive(Clone)]
+pub struct Settings {
+    inner: Arc<Mutex<Inner>>,
+    bus: EventBus,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings::new()
+    }
+}
+
+impl Settings {
+    /// Creates an empty store publishing changes on the
+    /// [process-wide bus](crate::events::global).
+    pub fn new() -> Self {
+        Settings {
+            inner: Arc::new(Mutex::new(Inner::default())),
+            bus: events::global().clone(),
+        }
+    }
+
+    /// Publishes changes on `bus` instead of the process-wide bus.
+    pub fn with_bus(mut self, bus: EventBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Registers `key`, see [`register`](Self::register).
+    pub fn with_key<T: SettingType>(self, key: &Key<T>) -> Self {
+        self.register(key);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Adds `key` to the store, in the order the editor lists it.
+    ///
+    /// A value loaded from a file before the key was registered is kept if it has a matching
+    /// type, and replaced by the default otherwise.
+    pub fn register<T: SettingType>(&self, key: &Key<T>) {
+        let default = key.default.to_value();
+        let mut inner = self.lock();
+
+        match inner.entries.iter_mut().find(|entry| entry.name == key.name) {
+            Some(entry) => {
+                entry.value = match entry.value.clone().coerce(&default) {
+                    Some(value) => value.clamp(key.range),
+                    None => {
+                        warn!("Setting '{}' has the wrong type, using the default", key.name);
+                        default.clone()
+                    }
+                };
+                entry.default = default;
+                entry.range = key.range;
+            }
+            None => inner.entries.push(Entry {
+                name: key.name.to_string(),
+                value: default.clone(),
+                default,
+                range: key.range,
+            }),
+        }
+    }
+
+    /// Returns the value of `key`, or its default if it is not registered.
+    pub fn get<T: SettingType + Clone>(&self, key: &Key<T>) -> T {
+        self.value(key.name)
+            .and_then(|value| T::from_value(&value))
+            .unwrap_or_else(|| key.default.clone())
+    }
+
+    /// Returns the value of a text key, or its default if it is not registered.
+    pub fn get_text(&self, key: &Key<&'static str>) -> String {
+        match self.value(key.name) {
+            Some(SettingValue::Text(text)) => text,
+            _ => key.default.to_string(),
+        }
+    }
+
+    /// Returns the value of the setting `name`.
+    pub fn value(&self, name: &str) -> Option<SettingValue> {
+        self.lock()
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Returns the names of all settings in registration order.
+    pub fn names(&self) -> Vec<String> {
+        self.lock().entries.iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    /// Returns the range of the setting `name`, as `[min, max, step]`.
+    pub fn range(&self, name: &str) -> Option<[f32; 3]> {
+        self.lock()
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .and_then(|entry| entry.range)
+    }
+
+    /// Sets `key` to `value`, registering it first if needed. Numbers are clamped to the
+    /// range of the key.
+    pub fn set<T: SettingType>(&self, key: &Key<T>, value: T) {
+        if self.value(key.name).is_none() {
+            self.register(key);
+        }
+        if let Err(e) = self.set_value(key.name, value.to_value()) {
+            warn!("{}", e);
+        }
+    }
+
+    /// Sets the registered setting `name`.
+    ///
+    /// A changed value is published as [`Event::SettingChanged`] and, if the store was
+    /// [opened](Self::open) from a file, saved.
+    ///
+    /// Returns:
+    ///   [`UptechError::Config`] if there is no such setting or `value` has the wrong type.
+    pub fn set_value(&self, name: &str, value: SettingValue) -> Result<()> {
+        let mut inner = self.lock();
+        let entry = inner
+            .entries
+            .iter_mut()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| UptechError::Config(format!("no setting named '{}'", name)))?;
+
+        let value = value
+            .coerce(&entry.default)
+            .ok_or_else(|| UptechError::Config(format!("setting '{}' expects a {:?}", name, entry.default)))?
+            .clamp(entry.range);
+        if entry.value == value {
+            return Ok(());
+        }
+
+        debug!("Setting '{}' changed from {} to {}", name, entry.value, value);
+        entry.value = value.clone();
+
+        #[cfg(feature = "config")]
+        if let Some(path) = inner.path.clone() {
+            save_entries(&inner.entries, &path)?;
+        }
+        drop(inner);
+
+        self.bus.publish(Event::SettingChanged {
+            key: name.to_string(),
+            value,
+        });
+        Ok(())
+    }
+
+    /// Restores the default of every setting.
+    pub fn reset(&self) {
+        let entries: Vec<(String, SettingValue)> = self
+            .lock()
+            .entries
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.default.clone()))
+            .collect();
+        for (name, default) in entries {
+            if let Err(e) = self.set_value(&name, default) {
+                warn!("{}", e);
+            }
+        }
+    }
+
+    /// Returns a [`Pager`](crate::display::Pager) page for editing the settings.
+    pub fn editor(&self) -> SettingsEditor {
+        SettingsEditor::new(self.clone())
+    }
+}
+
+#[cfg(feature = "config")]
+fn to_toml(value: &SettingValue) -> toml::Value {
+    match value {
+        SettingValue::Bool(value) => toml::Value::Boolean(*value),
+        SettingValue::Int(value) => toml::Value::Integer(*value),
+        SettingValue::Float(value) => toml::Value::Float(*value as f64),
+        SettingValue::Text(value) => toml::Value::String(value.clone()),
+    }
+}
+
+#[cfg(feature = "config")]
+fn from_toml(value: &toml::Value) -> Option<SettingValue> {
+    match value {
+        toml::Value::Boolean(value) => Some(SettingValue::Bool(*value)),
+        toml::Value::Integer(value) => Some(SettingValue::Int(*value)),
+        toml::Value::Float(value) => Some(SettingValue::Float(*value as f32)),
+        toml::Value::String(value) => Some(SettingValue::Text(value.clone())),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "config")]
+fn save_entries(entries: &[Entry], path: &std::path::Path) -> Result<()> {
+    let table: toml::Table = entries
+        .iter()
+        .map(|entry| (entry.name.clone(), to_toml(&entry.value)))
+        .collect();
+    let text = toml::to_string(&table).map_err(|e| UptechError::Config(e.to_string()))?;
+
+    // Write a sibling file first so a power loss never leaves a truncated file behind.
+    let temporary = path.with_extension("toml.tmp");
+    std::fs::write(&temporary, text)?;
+    std::fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+#[cfg(feature = "config")]
+impl Settings {
+    /// Creates a store backed by the TOML file at `path`, loading it if it exists. Every
+    /// change is saved back to it.
+    ///
+    /// The file holds one `"name" = value` pair per setting. Values of keys registered later
+    /// are taken from the file; unknown entries are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::settings::{Key, Settings};
+    ///
+    /// const SPEED: Key<i64> = Key::new("drive.speed", 600).with_range(0.0, 1000.0, 50.0);
+    ///
+    /// let settings = Settings::open("/home/robot/settings.toml").unwrap().with_key(&SPEED);
+    /// println!("speed: {}", settings.get(&SPEED));
+    /// ```
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        let path = path.into();
+        let settings = Settings::new();
+
+        if path.exists() {
+            log::info!("Loading settings from {}", path.display());
+            let text = std::fs::read_to_string(&path)?;
+            let table: toml::Table =
+                toml::from_str(&text).map_err(|e| UptechError::Config(format!("{}: {}", path.display(), e)))?;
+
+            let mut inner = settings.lock();
+            for (name, value) in &table {
+                match from_toml(value) {
+                    Some(value) => inner.entries.push(Entry {
+                        name: name.clone(),
+                        default: value.clone(),
+                        value,
+                        range: None,
+                    }),
+                    None => warn!("Ignoring setting '{}' of unsupported type in {}", name, path.display()),
+                }
+            }
+        }
+
+        settings.lock().path = Some(path);
+        Ok(settings)
+    }
+
+    /// Writes all settings to the TOML file at `path`.
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        save_entries(&self.lock().entries, path.as_ref())
+    }
+}
@@ -0,0 +1,316 @@
+use super::{SettingValue, Settings};
+use crate::display::scene::Widget;
+use crate::display::widgets::{InputResponse, NumberSpinner, TextInput};
+use crate::display::{Color, FontSize, Nav, Page, Screen};
+use log::warn;
+
+/// Longest text entered in the editor.
+const MAX_TEXT_LEN: usize = 16;
+
+/// Label of the entry that hands the buttons back to the pager.
+const EXIT_LABEL: &str = "Exit";
+
+/// The input of the setting being edited.
+enum Edit {
+    Number { spinner: NumberSpinner, integer: bool },
+    Bool(bool),
+    Text(TextInput),
+}
+
+/// Everything that is drawn, to skip renders without changes.
+#[derive(PartialEq)]
+struct Snapshot {
+    rows: Vec<String>,
+    selected: usize,
+    active: bool,
+    cursor: Option<usize>,
+}
+
+/// A [`Page`] listing the settings of a store, for editing them with the pager buttons.
+///
+/// While inactive, the buttons switch pages as usual and [`Nav::Select`] enters the editor.
+/// Inside, [`Nav::Next`] and [`Nav::Previous`] move through the settings and `Select` edits the
+/// highlighted one: numbers with a [`NumberSpinner`] in the range of their key, switches by
+/// toggling and text with a [`TextInput`]. Confirming stores the value. The last entry,
+/// `Exit`, returns the buttons to the pager.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use uptechstar_rs::display::{Color, Pager, Screen, ScreenDirection};
+/// use uptechstar_rs::events;
+/// use uptechstar_rs::settings::{Key, Settings};
+///
+/// const KP: Key<f32> = Key::new("kp", 1.2).with_range(0.0, 5.0, 0.1);
+/// const KD: Key<f32> = Key::new("kd", 0.3).with_range(0.0, 5.0, 0.05);
+///
+/// let settings = Settings::new().with_key(&KP).with_key(&KD);
+/// let mut screen = Screen::new(Some(ScreenDirection::Horizontal));
+///
+/// let mut pager = Pager::new(Color::BLACK)
+///     .with_page("settings", settings.editor())
+///     .with_buttons(0, 1, 2);
+/// pager.run(&mut screen, &events::subscribe(), Duration::from_millis(100));
+/// ```
+pub struct SettingsEditor {
+    settings: Settings,
+    selected: usize,
+    /// First row shown when the list is longer than the screen.
+    top: usize,
+    active: bool,
+    editing: Option<Edit>,
+    font: FontSize,
+    color: u32,
+    background: u32,
+    drawn: Option<Snapshot>,
+}
+
+impl SettingsEditor {
+    /// Creates an editor for `settings`. [`Settings::editor`] is the shorthand.
+    pub fn new(settings: Settings) -> Self {
+        SettingsEditor {
+            settings,
+            selected: 0,
+            top: 0,
+            active: false,
+            editing: None,
+            font: FontSize::Font6x8,
+            color: Color::WHITE,
+            background: Color::BLACK,
+            drawn: None,
+        }
+    }
+
+    /// Sets the font. The default is [`FontSize::Font6x8`], fitting 8 settings on the
+    /// horizontal screen.
+    pub fn with_font(mut self, font: FontSize) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets the text and background colors. The default is white on black; the highlighted
+    /// entry is drawn inverted.
+    pub fn with_colors(mut self, color: u32, background: u32) -> Self {
+        self.color = color;
+        self.background = background;
+        self
+    }
+
+    /// Returns `true` while the editor has the buttons.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Formats a value for the list, with as many decimals as the step of its range needs.
+    fn format_value(value: &SettingValue, range: Option<[f32; 3]>) -> String {
+        match (value, range) {
+            (SettingValue::Float(value), Some([_, _, step])) => {
+                format!("{:.*}", decimals(step), value)
+            }
+            (value, _) => value.to_string(),
+        }
+    }
+
+    /// The text of each row, including the exit entry.
+    fn rows(&self, names: &[String]) -> Vec<String> {
+        let mut rows: Vec<String> = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| match &self.editing {
+                Some(Edit::Number { spinner, .. }) if index == self.selected => spinner.text(),
+                Some(Edit::Bool(value)) if index == self.selected => {
+                    format!("{}: {}", name, SettingValue::Bool(*value))
+                }
+                Some(Edit::Text(input)) if index == self.selected => format!("{}: {}", name, input.text()),
+                _ => {
+                    let value = self.settings.value(name).unwrap_or(SettingValue::Bool(false));
+                    format!("{}: {}", name, Self::format_value(&value, self.settings.range(name)))
+                }
+            })
+            .collect();
+        rows.push(EXIT_LABEL.to_string());
+        rows
+    }
+
+    fn begin_edit(&mut self, name: &str, y: i32) {
+        let range = self.settings.range(name);
+        self.editing = match self.settings.value(name) {
+            Some(SettingValue::Bool(value)) => Some(Edit::Bool(value)),
+            Some(SettingValue::Int(value)) => {
+                let [min, max, step] = range.unwrap_or([i64::MIN as f32, i64::MAX as f32, 1.0]);
+                let spinner = NumberSpinner::new(0, y, name, value as f32, min, max, step.round().max(1.0))
+                    .with_decimals(0)
+                    .with_font(self.font);
+                Some(Edit::Number { spinner, integer: true })
+            }
+            Some(SettingValue::Float(value)) => {
+                let [min, max, step] = range.unwrap_or([f32::MIN, f32::MAX, 0.1]);
+                let spinner = NumberSpinner::new(0, y, name, value, min, max, step)
+                    .with_decimals(decimals(step))
+                    .with_font(self.font);
+                Some(Edit::Number { spinner, integer: false })
+            }
+            Some(SettingValue::Text(value)) => {
+                let x = (name.chars().count() as i32 + 2) * self.font.column_width();
+                let input = TextInput::new(x, y, MAX_TEXT_LEN)
+                    .with_text(&value)
+                    .with_font(self.font)
+                    .with_colors(self.background, self.color);
+                Some(Edit::Text(input))
+            }
+            None => None,
+        };
+    }
+
+    /// Passes `nav` to the input being edited, storing the value once it is confirmed.
+    fn edit(&mut self, name: &str, nav: Nav) {
+        let Some(edit) = &mut self.editing else {
+            return;
+        };
+
+        let confirmed = match edit {
+            Edit::Number { spinner, integer } => (spinner.navigate(nav) == InputResponse::Confirmed).then(|| {
+                if *integer {
+                    SettingValue::Int(spinner.value().round() as i64)
+                } else {
+                    SettingValue::Float(spinner.value())
+                }
+            }),
+            Edit::Bool(value) => match nav {
+                Nav::Select => Some(SettingValue::Bool(*value)),
+                _ => {
+                    *value = !*value;
+                    None
+                }
+            },
+            Edit::Text(input) => {
+                (input.navigate(nav) == InputResponse::Confirmed).then(|| SettingValue::Text(input.text()))
+            }
+        };
+
+        if let Some(value) = confirmed {
+            if let Err(e) = self.settings.set_value(name, value) {
+                warn!("{}", e);
+            }
+            self.editing = None;
+        }
+    }
+
+    /// Number of rows that fit on `screen`.
+    fn visible_rows(&self, screen: &Screen) -> usize {
+        (screen.size().1 / self.font.row_height()).max(1) as usize
+    }
+}
+
+/// Decimals needed to show multiples of `step`, at most 4.
+fn decimals(step: f32) -> usize {
+    (0..4)
+        .find(|&decimals| {
+            let scaled = step * 10f32.powi(decimals as i32);
+            (scaled - scaled.round()).abs() < 1e-3
+        })
+        .unwrap_or(4)
+}
+
+impl Page for SettingsEditor {
+    fn render(&mut self, screen: &mut Screen) {
+        let names = self.settings.names();
+        let rows = self.rows(&names);
+        self.selected = self.selected.min(rows.len() - 1);
+
+        let visible = self.visible_rows(screen);
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + visible {
+            self.top = self.selected + 1 - visible;
+        }
+
+        let cursor = match &self.editing {
+            Some(Edit::Text(input)) => Some(input.cursor()),
+            _ => None,
+        };
+        let snapshot = Snapshot {
+            rows,
+            selected: self.selected,
+            active: self.active,
+            cursor,
+        };
+        if self.drawn.as_ref() == Some(&snapshot) {
+            return;
+        }
+
+        let previous_font = screen.font();
+        let row_height = self.font.row_height();
+        screen.fill_screen(self.background).set_font_size(self.font);
+
+        for (index, row) in snapshot.rows.iter().enumerate().skip(self.top).take(visible) {
+            let y = (index - self.top) as i32 * row_height;
+            let highlighted = self.active && index == self.selected;
+            let (color, background) = if highlighted {
+                (self.background, self.color)
+            } else {
+                (self.color, self.background)
+            };
+
+            match &self.editing {
+                Some(Edit::Text(input)) if index == self.selected => {
+                    screen.set_fore_color(color).set_back_color(background).put_string(
+                        0,
+                        y,
+                        &format!("{}: ", names[index]),
+                    );
+                    input.draw(screen);
+                }
+                _ => {
+                    let (width, _) = screen.size();
+                    if highlighted {
+                        screen.fill_frame(0, y, width - 1, y + row_height - 1, background);
+                    }
+                    screen.set_fore_color(color).set_back_color(background).put_string(0, y, row);
+                }
+            }
+        }
+
+        screen.set_font_size(previous_font).refresh();
+        self.drawn = Some(snapshot);
+    }
+
+    fn on_enter(&mut self) {
+        self.drawn = None;
+    }
+
+    fn handle_nav(&mut self, nav: Nav) -> bool {
+        if !self.active {
+            if nav == Nav::Select {
+                self.active = true;
+                self.selected = 0;
+                return true;
+            }
+            return false;
+        }
+
+        let names = self.settings.names();
+        let count = names.len() + 1;
+
+        if self.editing.is_some() {
+            if let Some(name) = names.get(self.selected) {
+                self.edit(name, nav);
+            }
+            return true;
+        }
+
+        match nav {
+            Nav::Next => self.selected = (self.selected + 1) % count,
+            Nav::Previous => self.selected = (self.selected + count - 1) % count,
+            Nav::Select => match names.get(self.selected) {
+                Some(name) => {
+                    let y = (self.selected.saturating_sub(self.top)) as i32 * self.font.row_height();
+                    self.begin_edit(name, y);
+                }
+                None => self.active = false,
+            },
+        }
+        true
+    }
+}
@@ -0,0 +1,206 @@
+//! Graceful shutdown on Ctrl-C and `SIGTERM`.
+//!
+//! Killing a program leaves the board as it was: LEDs lit, the last frame on the LCD and IO
+//! outputs driven. [`run`] installs `SIGINT`/`SIGTERM` handlers, hands the application a
+//! [`ShutdownToken`] to poll, and once the application returns - normally, with an error or by
+//! panicking - puts the hardware into a safe state with [`release_hardware`].
+//!
+//! The application's own samplers, schedulers and animations are stopped by dropping them,
+//! which happens when the closure returns and so before the hardware is released. A second
+//! Ctrl-C while shutting down exits immediately with status 130.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use uptechstar_rs::sampler::Sampler;
+//!
+//! uptechstar_rs::run(|shutdown| {
+//!     let mut sampler = Sampler::new(100.0).with_mpu(true);
+//!     let readings = sampler.subscribe();
+//!     sampler.start();
+//!
+//!     while !shutdown.is_requested() {
+//!         if let Ok(reading) = readings.recv_timeout(Duration::from_millis(100)) {
+//!             println!("{:?}", reading);
+//!         }
+//!     }
+//!     Ok(())
+//! })
+//! .unwrap();
+//! ```
+
+use crate::adc_io;
+use crate::display::{Color, Screen};
+use crate::registry;
+use log::{info, warn};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Set by the signal handler; shared by every token that [listens](ShutdownToken::install)
+/// for signals.
+static SIGNALLED: AtomicBool = AtomicBool::new(false);
+
+/// How often [`ShutdownToken::wait_timeout`] checks for a request.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A cheap, cloneable flag telling threads that the program is shutting down.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::ShutdownToken;
+///
+/// let token = ShutdownToken::new();
+/// let worker = {
+///     let token = token.clone();
+///     std::thread::spawn(move || {
+///         let mut ticks = 0;
+///         while !token.wait_timeout(Duration::from_millis(5)) {
+///             ticks += 1;
+///         }
+///         ticks
+///     })
+/// };
+///
+/// token.request();
+/// assert!(token.is_requested());
+/// worker.join().unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownToken {
+    requested: Arc<AtomicBool>,
+    signals: bool,
+}
+
+impl ShutdownToken {
+    /// Creates a token that is only triggered by [`request`](ShutdownToken::request).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Installs `SIGINT` and `SIGTERM` handlers and creates a token that is also triggered by
+    /// them. Installing more than once is harmless.
+    ///
+    /// # Errors
+    ///
+    /// If the handlers cannot be installed.
+    pub fn install() -> crate::Result<Self> {
+        install_handlers()?;
+        Ok(ShutdownToken {
+            requested: Arc::new(AtomicBool::new(false)),
+            signals: true,
+        })
+    }
+
+    /// Asks every holder of this token, and its clones, to shut down.
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` once shutdown has been requested or, for installed tokens, a signal has
+    /// arrived.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::Acquire) || (self.signals && SIGNALLED.load(Ordering::Acquire))
+    }
+
+    /// Sleeps for up to `timeout`, returning early with `true` if shutdown is requested in the
+    /// meantime. Use it in place of [`thread::sleep`] in loops that should stop promptly.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.is_requested() {
+                return true;
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            thread::sleep(left.min(POLL_INTERVAL));
+        }
+    }
+
+    /// Blocks until shutdown is requested.
+    pub fn wait(&self) {
+        while !self.wait_timeout(Duration::from_secs(1)) {}
+    }
+}
+
+#[cfg(target_os = "linux")]
+extern "C" fn on_signal(_signal: libc::c_int) {
+    // Only async-signal-safe calls are allowed here.
+    if SIGNALLED.swap(true, Ordering::AcqRel) {
+        unsafe { libc::_exit(130) };
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn install_handlers() -> crate::Result<()> {
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+            return Err(std::io::Error::last_os_error().into());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn install_handlers() -> crate::Result<()> {
+    warn!("Signal handlers are only installed on Linux; Ctrl-C will not shut down gracefully");
+    Ok(())
+}
+
+/// Puts the board into a safe state: turns both LEDs off, blanks and closes the LCD if it was
+/// opened and closes ADC-IO. Stop every thread that still uses the hardware first.
+pub fn release_hardware() {
+    info!("Releasing hardware");
+
+    let mut screen = Screen::new(None);
+    screen.set_all_leds_off();
+    if registry::lcd().is_some() {
+        screen.fill_screen(Color::BLACK).refresh().close();
+    }
+
+    if adc_io::adc_close() != 0 {
+        warn!("ADC-IO was not closed cleanly");
+    }
+}
+
+/// Releases the hardware when dropped, including while unwinding from a panic.
+struct ReleaseOnDrop;
+
+impl Drop for ReleaseOnDrop {
+    fn drop(&mut self) {
+        release_hardware();
+    }
+}
+
+/// Runs `app` with a [`ShutdownToken`] triggered by Ctrl-C and `SIGTERM`, then calls
+/// [`release_hardware`] and returns the result of `app`.
+///
+/// `app` should poll the token and return soon after it is triggered. Anything it owns, such as
+/// a [`Sampler`](crate::sampler::Sampler) or
+/// [`RateScheduler`](crate::scheduler::RateScheduler), is dropped - and its thread stopped -
+/// before the hardware is released. The hardware is also released if `app` panics.
+///
+/// # Errors
+///
+/// If the signal handlers cannot be installed, in which case `app` is not run, or if `app`
+/// fails.
+pub fn run<F>(app: F) -> crate::Result<()>
+where
+    F: FnOnce(&ShutdownToken) -> crate::Result<()>,
+{
+    let token = ShutdownToken::install()?;
+    let _release = ReleaseOnDrop;
+
+    let result = app(&token);
+    if token.is_requested() {
+        info!("Shutdown requested, releasing hardware");
+    }
+    result
+}
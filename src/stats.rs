@@ -0,0 +1,227 @@
+//! Call counts, error counts and latency histograms of the FFI wrappers.
+//!
+//! Every call the safe wrappers make into `libuptech.so` is timed, including the symbol lookup
+//! that precedes it, and accounted to the name of the C function. A failed lookup and a
+//! negative status code both count as errors.
+//!
+//! # Tracing
+//!
+//! With the `tracing` feature, every call additionally runs inside a `TRACE`-level `ffi` span
+//! carrying the fields `subsystem` (`adc_io`, `mpu` or `display`), `function`, `duration_us`
+//! and `code` (absent when the call had no status or never happened). Sensor pipelines can then
+//! be profiled with `tracing-subscriber` layers such as `tracing-flame`. The crate's own
+//! messages still go through `log`; `tracing-log` forwards them into the same subscriber.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::{mpu, stats};
+//!
+//! let mut accel = [0.0f32; 3];
+//! for _ in 0..1000 {
+//!     mpu::mpu6500_get_accel(&mut accel);
+//! }
+//!
+//! for (function, stats) in stats::snapshot() {
+//!     println!(
+//!         "{}: {} calls, {} errors, mean {:?}, p99 {:?}",
+//!         function,
+//!         stats.calls,
+//!         stats.errors,
+//!         stats.mean(),
+//!         stats.percentile(0.99)
+//!     );
+//! }
+//! ```
+
+use once_cell::sync::Lazy;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Number of latency histogram buckets.
+///
+/// Bucket 0 counts calls under 1µs, bucket `i` calls from 2<sup>i-1</sup> up to 2<sup>i</sup>µs,
+/// and the last bucket everything slower.
+pub const LATENCY_BUCKETS: usize = 20;
+
+/// Statistics of one C function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FunctionStats {
+    /// Number of calls, including failed ones.
+    pub calls: u64,
+    /// Calls whose symbol lookup failed or that returned a negative status.
+    pub errors: u64,
+    /// Sum of all call latencies.
+    pub total: Duration,
+    /// The slowest call.
+    pub max: Duration,
+    /// Call counts per latency bucket, see [`LATENCY_BUCKETS`].
+    pub histogram: [u64; LATENCY_BUCKETS],
+}
+
+impl FunctionStats {
+    /// The mean latency, or zero if there were no calls.
+    pub fn mean(&self) -> Duration {
+        match self.calls {
+            0 => Duration::ZERO,
+            calls => Duration::from_nanos((self.total.as_nanos() / calls as u128) as u64),
+        }
+    }
+
+    /// An upper bound for the latency below which a `quantile` (0.0–1.0) of the calls
+    /// completed, taken from the histogram and capped at [`max`](Self::max).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use uptechstar_rs::stats::FunctionStats;
+    ///
+    /// let mut stats = FunctionStats::default();
+    /// stats.calls = 100;
+    /// stats.histogram[3] = 90; // 4µs..8µs
+    /// stats.histogram[10] = 10; // 512µs..1024µs
+    /// stats.max = Duration::from_micros(700);
+    ///
+    /// assert_eq!(stats.percentile(0.5), Duration::from_micros(8));
+    /// assert_eq!(stats.percentile(0.99), Duration::from_micros(700));
+    /// ```
+    pub fn percentile(&self, quantile: f64) -> Duration {
+        let target = (self.calls as f64 * quantile.clamp(0.0, 1.0)).ceil() as u64;
+        let mut seen = 0;
+
+        for (index, count) in self.histogram.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                return bucket_upper_bound(index).min(self.max);
+            }
+        }
+
+        self.max
+    }
+
+    fn record(&mut self, elapsed: Duration, failed: bool) {
+        self.calls += 1;
+        if failed {
+            self.errors += 1;
+        }
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+        self.histogram[bucket(elapsed)] += 1;
+    }
+}
+
+/// The exclusive upper latency bound of histogram bucket `index`.
+pub fn bucket_upper_bound(index: usize) -> Duration {
+    if index + 1 >= LATENCY_BUCKETS {
+        Duration::MAX
+    } else {
+        Duration::from_micros(1 << index)
+    }
+}
+
+fn bucket(elapsed: Duration) -> usize {
+    let micros = elapsed.as_micros();
+    if micros == 0 {
+        0
+    } else {
+        ((u128::BITS - micros.leading_zeros()) as usize).min(LATENCY_BUCKETS - 1)
+    }
+}
+
+static STATS: Lazy<Mutex<HashMap<&'static str, FunctionStats>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the statistics of every C function called so far, sorted by name.
+pub fn snapshot() -> BTreeMap<&'static str, FunctionStats> {
+    STATS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(function, stats)| (*function, *stats))
+        .collect()
+}
+
+/// Clears all statistics.
+pub fn reset() {
+    STATS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Times one call of `function`.
+///
+/// Dropping the returned [`Call`] without finishing it, as happens when the symbol lookup
+/// returns early, records a failed call.
+pub(crate) fn start(function: &'static str) -> Call {
+    Call {
+        function,
+        started: Instant::now(),
+        finished: false,
+        #[cfg(feature = "tracing")]
+        span: tracing::trace_span!(
+            "ffi",
+            subsystem = subsystem(function),
+            function,
+            duration_us = tracing::field::Empty,
+            code = tracing::field::Empty
+        )
+        .entered(),
+    }
+}
+
+/// The subsystem a C function belongs to, as reported in trace spans and error messages.
+pub(crate) fn subsystem(function: &str) -> &'static str {
+    if function.starts_with("mpu") {
+        "mpu"
+    } else if function.starts_with("adc") || function.starts_with("ADC") {
+        "adc_io"
+    } else {
+        "display"
+    }
+}
+
+/// An FFI call in progress, see [`start`].
+pub(crate) struct Call {
+    function: &'static str,
+    started: Instant,
+    finished: bool,
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+}
+
+impl Call {
+    /// Records the call with its status code, counting negative codes as errors.
+    pub(crate) fn finish(mut self, code: i32) -> i32 {
+        #[cfg(feature = "tracing")]
+        self.span.record("code", code);
+        self.record(code < 0);
+        code
+    }
+
+    /// Records a call whose return value carries no status.
+    pub(crate) fn done(mut self) {
+        self.record(false);
+    }
+
+    fn record(&mut self, failed: bool) {
+        let elapsed = self.started.elapsed();
+        self.finished = true;
+
+        #[cfg(feature = "tracing")]
+        self.span.record("duration_us", elapsed.as_micros() as u64);
+
+        STATS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(self.function)
+            .or_default()
+            .record(elapsed, failed);
+    }
+}
+
+impl Drop for Call {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.record(true);
+        }
+    }
+}
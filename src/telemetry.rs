@@ -0,0 +1,359 @@
+//! Shared publication of the latest board state.
+//!
+//! A [`StateHub`] owns a single sampling thread that reads the configured sources at a fixed
+//! rate and publishes one [`BoardState`] snapshot per tick. Any number of consumers (UI, control
+//! loop, logger) can read the most recent snapshot through a cheap [`StateReader`], or, with the
+//! `async` feature, await changes through a `tokio::sync::watch` channel.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::telemetry::StateHub;
+//!
+//! let mut hub = StateHub::new(100.0).with_adc(true).with_io(true).with_mpu(true);
+//! hub.start();
+//!
+//! let reader = hub.reader();
+//! std::thread::spawn(move || loop {
+//!     let state = reader.latest();
+//!     if let Some(mpu) = state.mpu {
+//!         println!("#{} yaw {:.1}", state.sequence, mpu.attitude[2]);
+//!     }
+//!     std::thread::sleep(std::time::Duration::from_millis(100));
+//! });
+//! ```
+
+pub mod frame;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(target_os = "linux")]
+pub mod shm;
+pub mod udp;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+pub use frame::TelemetryFrame;
+#[cfg(feature = "http")]
+pub use http::{HttpServer, serve_http};
+
+use crate::adc_io::{self, AdcFrame, IoFrame};
+use crate::gps::{Fix, Gps};
+use crate::mpu::{self, MpuSample};
+use crate::sampler::{Ticker, period_from_rate};
+use log::{debug, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A snapshot of everything the hub samples, taken during one tick.
+///
+/// Sources that are not enabled, or whose read failed during the tick, keep the value from
+/// the last successful read; they are `None` only until that first success.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardState {
+    /// Number of ticks published so far, starting at `1` for the first snapshot.
+    pub sequence: u64,
+    /// Time elapsed since the hub was created.
+    pub timestamp: Duration,
+    /// Latest ADC channel values.
+    pub adc: Option<AdcFrame>,
+    /// Latest IO input levels.
+    pub io: Option<IoFrame>,
+    /// Latest MPU6500 reading.
+    pub mpu: Option<MpuSample>,
+    /// Latest GPS fix; `None` while the receiver has no fix.
+    pub gps: Option<Fix>,
+}
+
+/// A cloneable handle to the latest [`BoardState`] published by a [`StateHub`].
+#[derive(Debug, Clone)]
+pub struct StateReader {
+    state: Arc<RwLock<BoardState>>,
+}
+
+impl StateReader {
+    /// Returns a copy of the most recent snapshot.
+    pub fn latest(&self) -> BoardState {
+        *self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Continuously samples board sources and publishes the latest [`BoardState`].
+pub struct StateHub {
+    period: Duration,
+    adc: bool,
+    io: bool,
+    mpu: bool,
+    gps: Option<Arc<RwLock<Option<Fix>>>>,
+    started: Instant,
+    state: Arc<RwLock<BoardState>>,
+    #[cfg(feature = "async")]
+    watch: tokio::sync::watch::Sender<BoardState>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StateHub {
+    /// Creates a stopped hub ticking at `rate_hz` with no sources enabled.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn new(rate_hz: f32) -> Self {
+        StateHub {
+            period: period_from_rate(rate_hz),
+            adc: false,
+            io: false,
+            mpu: false,
+            gps: None,
+            started: Instant::now(),
+            state: Arc::new(RwLock::new(BoardState::default())),
+            #[cfg(feature = "async")]
+            watch: tokio::sync::watch::Sender::new(BoardState::default()),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Enables or disables sampling of the 10 ADC channels.
+    pub fn with_adc(mut self, enabled: bool) -> Self {
+        self.adc = enabled;
+        self
+    }
+
+    /// Enables or disables sampling of the IO input levels.
+    pub fn with_io(mut self, enabled: bool) -> Self {
+        self.io = enabled;
+        self
+    }
+
+    /// Enables or disables sampling of the MPU6500.
+    pub fn with_mpu(mut self, enabled: bool) -> Self {
+        self.mpu = enabled;
+        self
+    }
+
+    /// Publishes the fix of `gps` in every snapshot.
+    pub fn with_gps(mut self, gps: &Gps) -> Self {
+        self.gps = Some(gps.shared());
+        self
+    }
+
+    /// Returns a handle for reading the latest snapshot from any thread.
+    pub fn reader(&self) -> StateReader {
+        StateReader {
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    /// Returns the most recent snapshot.
+    pub fn latest(&self) -> BoardState {
+        self.reader().latest()
+    }
+
+    /// Returns a watch receiver notified on every published snapshot.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use uptechstar_rs::telemetry::StateHub;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut hub = StateHub::new(50.0).with_mpu(true);
+    ///     hub.start();
+    ///
+    ///     let mut states = hub.watch();
+    ///     while states.changed().await.is_ok() {
+    ///         let state = *states.borrow_and_update();
+    ///         println!("{:?}", state.mpu);
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn watch(&self) -> tokio::sync::watch::Receiver<BoardState> {
+        self.watch.subscribe()
+    }
+
+    /// Returns `true` while the sampling thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Starts the sampling thread. Does nothing if it is already running.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn start(&mut self) -> &mut Self {
+        if self.is_running() {
+            return self;
+        }
+
+        info!(
+            "Starting state hub at {:.1} Hz (adc: {}, io: {}, mpu: {}, gps: {})",
+            1.0 / self.period.as_secs_f32(),
+            self.adc,
+            self.io,
+            self.mpu,
+            self.gps.is_some()
+        );
+
+        self.running.store(true, Ordering::Release);
+
+        let period = self.period;
+        let (adc, io, mpu) = (self.adc, self.io, self.mpu);
+        let gps = self.gps.clone();
+        let started = self.started;
+        let state = Arc::clone(&self.state);
+        #[cfg(feature = "async")]
+        let watch = self.watch.clone();
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-state-hub".into())
+                .spawn(move || {
+                    let mut ticker = Ticker::new(period);
+                    let mut snapshot = *state.read().unwrap_or_else(|e| e.into_inner());
+
+                    while running.load(Ordering::Acquire) {
+                        if adc && let Ok(frame) = adc_io::adc_get_frame() {
+                            snapshot.adc = Some(frame);
+                        }
+                        if io {
+                            snapshot.io = Some(adc_io::io_get_frame());
+                        }
+                        if mpu && let Ok(sample) = mpu::mpu6500_get_sample() {
+                            snapshot.mpu = Some(sample);
+                        }
+                        if let Some(gps) = &gps {
+                            snapshot.gps = *gps.read().unwrap_or_else(|e| e.into_inner());
+                        }
+
+                        snapshot.sequence += 1;
+                        snapshot.timestamp = started.elapsed();
+
+                        *state.write().unwrap_or_else(|e| e.into_inner()) = snapshot;
+                        #[cfg(feature = "async")]
+                        watch.send_replace(snapshot);
+
+                        ticker.wait();
+                    }
+
+                    debug!("State hub thread exited");
+                })
+                .expect("Failed to spawn state hub thread"),
+        );
+
+        self
+    }
+
+    /// Stops the sampling thread and waits for it to exit. The last snapshot stays readable.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("State hub stopped");
+        }
+
+        self
+    }
+}
+
+impl Drop for StateHub {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// JSON payload builders shared by the network publishers.
+#[cfg(any(feature = "http", feature = "mqtt", feature = "websocket"))]
+pub(crate) mod json {
+    use super::BoardState;
+    use serde_json::{Value, json};
+
+    /// `{"sequence":..,"timestamp_ms":..,"adc":[..10 values..]}`
+    #[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+    pub(crate) fn adc(state: &BoardState) -> Option<Value> {
+        state.adc.map(|frame| {
+            json!({
+                "sequence": state.sequence,
+                "timestamp_ms": state.timestamp.as_millis() as u64,
+                "adc": frame.0,
+            })
+        })
+    }
+
+    /// `{"sequence":..,"timestamp_ms":..,"io":<bitmask>,"levels":[..8 booleans..]}`
+    #[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+    pub(crate) fn io(state: &BoardState) -> Option<Value> {
+        state.io.map(|levels| {
+            let pins: Vec<bool> = (0..8).map(|index| levels.level(index)).collect();
+            json!({
+                "sequence": state.sequence,
+                "timestamp_ms": state.timestamp.as_millis() as u64,
+                "io": levels.0,
+                "levels": pins,
+            })
+        })
+    }
+
+    /// `{"sequence":..,"timestamp_ms":..,"accel":[..],"gyro":[..],"attitude":[..]}`
+    #[cfg_attr(not(feature = "http"), allow(dead_code))]
+    pub(crate) fn mpu(state: &BoardState) -> Option<Value> {
+        state.mpu.map(|sample| {
+            json!({
+                "sequence": state.sequence,
+                "timestamp_ms": state.timestamp.as_millis() as u64,
+                "accel": sample.accel,
+                "gyro": sample.gyro,
+                "attitude": sample.attitude,
+            })
+        })
+    }
+
+    /// Every available source of `state` in one object; missing sources are `null`.
+    #[cfg_attr(not(any(feature = "http", feature = "websocket")), allow(dead_code))]
+    pub(crate) fn state(state: &BoardState) -> Value {
+        json!({
+            "sequence": state.sequence,
+            "timestamp_ms": state.timestamp.as_millis() as u64,
+            "adc": state.adc.map(|frame| frame.0),
+            "io": state.io,
+            "mpu": state.mpu.map(|sample| json!({
+                "accel": sample.accel,
+                "gyro": sample.gyro,
+                "attitude": sample.attitude,
+            })),
+            "gps": state.gps.map(|fix| json!({
+                "lat": fix.lat,
+                "lon": fix.lon,
+                "speed": fix.speed,
+                "hdop": fix.hdop,
+                "time": fix.time.to_string(),
+            })),
+        })
+    }
+
+    /// `{"sequence":..,"timestamp_ms":..,"pitch":..,"roll":..,"yaw":..}`
+    #[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+    pub(crate) fn attitude(state: &BoardState) -> Option<Value> {
+        state.mpu.map(|sample| {
+            json!({
+                "sequence": state.sequence,
+                "timestamp_ms": state.timestamp.as_millis() as u64,
+                "pitch": sample.attitude[0],
+                "roll": sample.attitude[1],
+                "yaw": sample.attitude[2],
+            })
+        })
+    }
+}
@@ -0,0 +1,326 @@
+//! A compact binary frame for exchanging board state between tools.
+//!
+//! A [`TelemetryFrame`] carries one snapshot: any of the ADC channels, the IO levels and the
+//! MPU6500 reading, plus application-defined values. The same encoding is used by the
+//! [`LogFormat::Frames`](crate::logging::LogFormat::Frames) sensor logs and the network
+//! transports, so a frame written by one can be read by any other, and a frame log can be
+//! replayed with [`ReplayBackend::from_frames`](crate::replay::ReplayBackend::from_frames).
+//!
+//! # Wire Format
+//!
+//! All multi-byte values are little-endian. Sections whose flag is clear are left out.
+//!
+//! | Field      | Size      | Description                                         |
+//! |------------|-----------|-----------------------------------------------------|
+//! | magic      | 2 bytes   | `UT`                                                |
+//! | version    | 1 byte    | Format version, currently `1`                       |
+//! | flags      | 1 byte    | Bit 0: ADC, bit 1: IO, bit 2: MPU                   |
+//! | user count | 1 byte    | Number of user values                               |
+//! | sequence   | 8 bytes   | Snapshot counter                                    |
+//! | timestamp  | 8 bytes   | Microseconds since the producer started             |
+//! | adc        | 40 bytes  | 10 × `i32`                                          |
+//! | io         | 1 byte    | IO input level bitmask                              |
+//! | mpu        | 36 bytes  | Accel, gyro and attitude, 9 × `f32`                 |
+//! | user       | 4 × n     | `f32` user values                                   |
+//! | crc        | 2 bytes   | [CRC-16/CCITT-FALSE](crc16) of all preceding bytes  |
+
+use super::BoardState;
+use crate::adc_io::{AdcFrame, IoFrame};
+use crate::mpu::MpuSample;
+use crate::sampler::{Reading, Timestamped};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// Magic bytes starting every frame.
+const MAGIC: &[u8; 2] = b"UT";
+
+/// Current frame format version.
+pub const VERSION: u8 = 1;
+
+/// Bytes before the first optional section: magic, version, flags, user count, sequence and
+/// timestamp.
+const HEADER_LEN: usize = 21;
+
+const FLAG_ADC: u8 = 1 << 0;
+const FLAG_IO: u8 = 1 << 1;
+const FLAG_MPU: u8 = 1 << 2;
+
+/// Computes the CRC-16/CCITT-FALSE checksum (polynomial `0x1021`, initial value `0xFFFF`)
+/// of `data`.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::telemetry::frame::crc16;
+///
+/// assert_eq!(crc16(b"123456789"), 0x29B1);
+/// ```
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// One snapshot of board state in the shared telemetry format.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use uptechstar_rs::adc_io::IoFrame;
+/// use uptechstar_rs::telemetry::TelemetryFrame;
+///
+/// let frame = TelemetryFrame {
+///     sequence: 42,
+///     timestamp: Duration::from_millis(1500),
+///     io: Some(IoFrame(0b0000_0101)),
+///     user: vec![0.25, -3.0],
+///     ..Default::default()
+/// };
+///
+/// let bytes = frame.encode();
+/// assert_eq!(bytes.len(), frame.encoded_len());
+/// assert_eq!(TelemetryFrame::decode(&bytes), Ok(frame.clone()));
+/// assert_eq!(TelemetryFrame::read_from(&mut bytes.as_slice()).unwrap(), Some(frame));
+///
+/// let mut corrupted = bytes.clone();
+/// corrupted[22] ^= 0x01;
+/// assert!(TelemetryFrame::decode(&corrupted).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TelemetryFrame {
+    /// Snapshot counter of the producer, `0` if it does not count.
+    pub sequence: u64,
+    /// Time elapsed since the producer started, with microsecond resolution on the wire.
+    pub timestamp: Duration,
+    /// ADC channel values.
+    pub adc: Option<AdcFrame>,
+    /// IO input levels, one byte on the wire.
+    pub io: Option<IoFrame>,
+    /// MPU6500 reading.
+    pub mpu: Option<MpuSample>,
+    /// Application-defined values, such as controller setpoints. At most 255 are encoded.
+    pub user: Vec<f32>,
+}
+
+impl TelemetryFrame {
+    /// Returns the size of the encoded frame in bytes.
+    pub fn encoded_len(&self) -> usize {
+        body_len(self.flags(), self.user_count()) + 2
+    }
+
+    /// Encodes the frame. User values past the 255th are dropped.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+        self.encode_into(&mut bytes);
+        bytes
+    }
+
+    /// Appends the encoded frame to `bytes`.
+    pub fn encode_into(&self, bytes: &mut Vec<u8>) {
+        let start = bytes.len();
+        let user_count = self.user_count();
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[VERSION, self.flags(), user_count as u8]);
+        bytes.extend_from_slice(&self.sequence.to_le_bytes());
+        bytes.extend_from_slice(&(self.timestamp.as_micros() as u64).to_le_bytes());
+        if let Some(frame) = &self.adc {
+            for value in frame.0 {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        if let Some(levels) = self.io {
+            bytes.push(levels.0);
+        }
+        if let Some(sample) = &self.mpu {
+            for value in sample.accel.iter().chain(&sample.gyro).chain(&sample.attitude) {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        for value in &self.user[..user_count] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let crc = crc16(&bytes[start..]);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Encodes the frame into `writer`.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.encode())
+    }
+
+    /// Decodes a buffer holding exactly one frame.
+    ///
+    /// Fails on a wrong magic, an unsupported version, unknown flags, a length that does not
+    /// match the flags or a CRC mismatch.
+    pub fn decode(bytes: &[u8]) -> Result<Self, &'static str> {
+        let (flags, user_count) = parse_header(bytes)?;
+        if bytes.len() != body_len(flags, user_count) + 2 {
+            return Err("Telemetry frame length does not match its header");
+        }
+
+        let (body, crc) = bytes.split_at(bytes.len() - 2);
+        if crc16(body) != u16::from_le_bytes([crc[0], crc[1]]) {
+            return Err("Telemetry frame CRC mismatch");
+        }
+
+        let mut fields = Fields(&body[5..]);
+        let mut frame = TelemetryFrame {
+            sequence: u64::from_le_bytes(fields.take()),
+            timestamp: Duration::from_micros(u64::from_le_bytes(fields.take())),
+            ..Default::default()
+        };
+        if flags & FLAG_ADC != 0 {
+            frame.adc = Some(AdcFrame(std::array::from_fn(|_| i32::from_le_bytes(fields.take()))));
+        }
+        if flags & FLAG_IO != 0 {
+            frame.io = Some(IoFrame(fields.take::<1>()[0]));
+        }
+        if flags & FLAG_MPU != 0 {
+            let mut axes = || std::array::from_fn(|_| f32::from_le_bytes(fields.take()));
+            frame.mpu = Some(MpuSample {
+                accel: axes(),
+                gyro: axes(),
+                attitude: axes(),
+            });
+        }
+        frame.user = (0..user_count).map(|_| f32::from_le_bytes(fields.take())).collect();
+
+        Ok(frame)
+    }
+
+    /// Decodes the next frame from `reader`.
+    ///
+    /// # Returns
+    ///
+    /// * `io::Result<Option<TelemetryFrame>>` - `Ok(None)` on a clean end of stream; malformed
+    ///   frames fail with [`io::ErrorKind::InvalidData`].
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Option<Self>> {
+        let mut bytes = vec![0u8; 5];
+        match reader.read_exact(&mut bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let (flags, user_count) = parse_header(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        bytes.resize(body_len(flags, user_count) + 2, 0);
+        reader.read_exact(&mut bytes[5..])?;
+
+        Self::decode(&bytes)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.adc.is_some() {
+            flags |= FLAG_ADC;
+        }
+        if self.io.is_some() {
+            flags |= FLAG_IO;
+        }
+        if self.mpu.is_some() {
+            flags |= FLAG_MPU;
+        }
+        flags
+    }
+
+    fn user_count(&self) -> usize {
+        self.user.len().min(u8::MAX as usize)
+    }
+}
+
+impl From<&BoardState> for TelemetryFrame {
+    /// Takes the ADC, IO and MPU values of `state`; the GPS fix is not part of the format.
+    fn from(state: &BoardState) -> Self {
+        TelemetryFrame {
+            sequence: state.sequence,
+            timestamp: state.timestamp,
+            adc: state.adc,
+            io: state.io,
+            mpu: state.mpu,
+            user: Vec::new(),
+        }
+    }
+}
+
+impl From<&Timestamped<Reading>> for TelemetryFrame {
+    /// Creates a frame holding just the one reading.
+    fn from(reading: &Timestamped<Reading>) -> Self {
+        let mut frame = TelemetryFrame {
+            timestamp: reading.timestamp,
+            ..Default::default()
+        };
+        match reading.value {
+            Reading::Adc(adc) => frame.adc = Some(adc),
+            Reading::Io(levels) => frame.io = Some(levels),
+            Reading::Mpu(sample) => frame.mpu = Some(sample),
+        }
+        frame
+    }
+}
+
+/// Reads all frames of a file, such as a [`LogFormat::Frames`](crate::logging::LogFormat::Frames)
+/// sensor log, into memory.
+pub fn read_frames<P: AsRef<Path>>(path: P) -> io::Result<Vec<TelemetryFrame>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut frames = Vec::new();
+    while let Some(frame) = TelemetryFrame::read_from(&mut reader)? {
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// Validates the first 5 bytes and returns the flags and the user value count.
+fn parse_header(bytes: &[u8]) -> Result<(u8, usize), &'static str> {
+    if bytes.len() < 5 {
+        return Err("Truncated telemetry frame");
+    }
+    if &bytes[..2] != MAGIC {
+        return Err("Not a telemetry frame");
+    }
+    if bytes[2] != VERSION {
+        return Err("Unsupported telemetry frame version");
+    }
+    if bytes[3] & !(FLAG_ADC | FLAG_IO | FLAG_MPU) != 0 {
+        return Err("Unknown telemetry frame flags");
+    }
+    Ok((bytes[3], bytes[4] as usize))
+}
+
+/// Length of a frame without its CRC.
+fn body_len(flags: u8, user_count: usize) -> usize {
+    let mut len = HEADER_LEN + 4 * user_count;
+    if flags & FLAG_ADC != 0 {
+        len += 40;
+    }
+    if flags & FLAG_IO != 0 {
+        len += 1;
+    }
+    if flags & FLAG_MPU != 0 {
+        len += 36;
+    }
+    len
+}
+
+/// Reads fixed-size fields from a buffer whose length was already checked.
+struct Fields<'a>(&'a [u8]);
+
+impl Fields<'_> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let (field, rest) = self.0.split_at(N);
+        self.0 = rest;
+        field.try_into().unwrap()
+    }
+}
@@ -0,0 +1,254 @@
+//! Minimal HTTP/JSON interface to the board.
+//!
+//! [`serve_http`] binds a small blocking server on a background thread. Every `GET` samples the
+//! hardware on demand, so no [`StateHub`](super::StateHub) is required:
+//!
+//! | Request        | Response / body                                                          |
+//! |----------------|--------------------------------------------------------------------------|
+//! | `GET /adc`     | `{"sequence":..,"timestamp_ms":..,"adc":[..10 values..]}`                |
+//! | `GET /io`      | `{"sequence":..,"timestamp_ms":..,"io":5,"levels":[..8 booleans..]}`     |
+//! | `GET /mpu`     | `{"sequence":..,"timestamp_ms":..,"accel":[..],"gyro":[..],"attitude":[..]}` |
+//! | `GET /metrics` | All of the above in one object; failed sources are `null`                |
+//! | `POST /io`     | Body `{"levels":5}` sets all IO output levels from a bitmask             |
+//! | `POST /led`    | Body `{"index":0,"color":[255,0,0]}` or `{"index":0,"color":16711680}`   |
+//!
+//! Errors are reported as `{"error":"..."}` with a matching status code.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::telemetry::serve_http;
+//!
+//! let server = serve_http("0.0.0.0:8080").unwrap();
+//! println!("Serving on http://{}", server.addr());
+//!
+//! std::thread::sleep(std::time::Duration::from_secs(600));
+//! server.stop();
+//! ```
+
+use super::{BoardState, json};
+use crate::adc_io;
+use crate::display::{Color, Screen};
+use crate::mpu;
+use log::{debug, error, info, warn};
+use serde_json::{Value, json};
+use std::io::{self, Read};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// How often the server thread checks whether it should shut down.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Upper bound on accepted request bodies.
+const MAX_BODY: u64 = 4096;
+
+/// A running HTTP server started by [`serve_http`].
+///
+/// The server is shut down when [`stop`](HttpServer::stop) is called or the handle is dropped.
+pub struct HttpServer {
+    addr: SocketAddr,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HttpServer {
+    /// Returns the address the server is bound to.
+    ///
+    /// Useful when binding to port `0` to let the OS pick a free port.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stops accepting requests and waits for the server thread to exit.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("HTTP server on {} stopped", self.addr);
+        }
+    }
+}
+
+impl Drop for HttpServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Starts serving board data over HTTP on `addr`.
+///
+/// Requests are handled one at a time on a dedicated thread.
+///
+/// # Errors
+///
+/// If `addr` cannot be resolved or bound.
+pub fn serve_http<A: ToSocketAddrs>(addr: A) -> io::Result<HttpServer> {
+    let server = Server::http(addr).map_err(io::Error::other)?;
+    let addr = server
+        .server_addr()
+        .to_ip()
+        .ok_or_else(|| io::Error::other("HTTP server is not bound to an IP address"))?;
+
+    info!("Serving board telemetry on http://{}", addr);
+
+    let running = Arc::new(AtomicBool::new(true));
+    let thread = {
+        let running = Arc::clone(&running);
+
+        thread::Builder::new()
+            .name("uptech-http".into())
+            .spawn(move || {
+                let mut board = OnDemand::new();
+
+                while running.load(Ordering::Acquire) {
+                    match server.recv_timeout(POLL_INTERVAL) {
+                        Ok(Some(request)) => board.handle(request),
+                        Ok(None) => {}
+                        Err(e) => {
+                            error!("HTTP server error: {}", e);
+                            break;
+                        }
+                    }
+                }
+
+                debug!("HTTP server thread exited");
+            })
+            .expect("Failed to spawn HTTP server thread")
+    };
+
+    Ok(HttpServer {
+        addr,
+        running,
+        thread: Some(thread),
+    })
+}
+
+/// Samples the hardware for each request and applies control requests.
+struct OnDemand {
+    started: Instant,
+    sequence: u64,
+    screen: Screen,
+}
+
+impl OnDemand {
+    fn new() -> Self {
+        OnDemand {
+            started: Instant::now(),
+            sequence: 0,
+            screen: Screen::new(None),
+        }
+    }
+
+    fn sample(&mut self, adc: bool, io: bool, mpu: bool) -> BoardState {
+        self.sequence += 1;
+        BoardState {
+            sequence: self.sequence,
+            timestamp: self.started.elapsed(),
+            adc: if adc { adc_io::adc_get_frame().ok() } else { None },
+            io: io.then(adc_io::io_get_frame),
+            mpu: if mpu { mpu::mpu6500_get_sample().ok() } else { None },
+            gps: None,
+        }
+    }
+
+    fn handle(&mut self, mut request: Request) {
+        debug!("{} {}", request.method(), request.url());
+
+        let path = request.url().split('?').next().unwrap_or_default().to_string();
+        let (status, body) = match (request.method(), path.as_str()) {
+            (Method::Get, "/adc") => unavailable(json::adc(&self.sample(true, false, false)), "ADC"),
+            (Method::Get, "/io") => unavailable(json::io(&self.sample(false, true, false)), "IO"),
+            (Method::Get, "/mpu") => unavailable(json::mpu(&self.sample(false, false, true)), "MPU"),
+            (Method::Get, "/metrics") => (200, json::state(&self.sample(true, true, true))),
+            (Method::Post, "/io") => match read_body(&mut request) {
+                Ok(body) => self.set_io(&body),
+                Err(e) => e,
+            },
+            (Method::Post, "/led") => match read_body(&mut request) {
+                Ok(body) => self.set_led(&body),
+                Err(e) => e,
+            },
+            (_, "/adc" | "/io" | "/mpu" | "/metrics" | "/led") => failure(405, "method not allowed"),
+            _ => failure(404, "not found"),
+        };
+
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(
+                "Content-Type: application/json"
+                    .parse::<Header>()
+                    .expect("static header is valid"),
+            );
+        if let Err(e) = request.respond(response) {
+            warn!("Failed to send HTTP response: {}", e);
+        }
+    }
+
+    fn set_io(&mut self, body: &Value) -> (u16, Value) {
+        let Some(levels) = body.get("levels").and_then(Value::as_u64).filter(|levels| *levels <= 0xFF) else {
+            return failure(400, "expected {\"levels\": <0-255>}");
+        };
+
+        match adc_io::set_all_io_levels(levels as u32) {
+            0 => (200, json!({ "levels": levels })),
+            code => failure(500, &format!("set_all_io_levels returned {}", code)),
+        }
+    }
+
+    fn set_led(&mut self, body: &Value) -> (u16, Value) {
+        let Some(index) = body.get("index").and_then(Value::as_u64).filter(|index| *index <= 1) else {
+            return failure(400, "expected \"index\" to be 0 or 1");
+        };
+        let Some(color) = body.get("color").and_then(parse_color) else {
+            return failure(400, "expected \"color\" as [r, g, b] or a 24-bit integer");
+        };
+
+        self.screen.set_led_color(index as i32, color);
+        (200, json!({ "index": index, "color": color }))
+    }
+}
+
+/// Parses `[r, g, b]` or a packed `0xRRGGBB` integer.
+fn parse_color(value: &Value) -> Option<u32> {
+    if let Some(packed) = value.as_u64() {
+        return (packed <= 0xFF_FFFF).then_some(packed as u32);
+    }
+
+    let channels = value.as_array().filter(|channels| channels.len() == 3)?;
+    let mut rgb = [0u8; 3];
+    for (channel, value) in rgb.iter_mut().zip(channels) {
+        *channel = u8::try_from(value.as_u64()?).ok()?;
+    }
+    Some(Color::new_color(rgb[0], rgb[1], rgb[2]))
+}
+
+fn read_body(request: &mut Request) -> Result<Value, (u16, Value)> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY)
+        .read_to_string(&mut body)
+        .map_err(|e| failure(400, &e.to_string()))?;
+
+    serde_json::from_str(&body).map_err(|e| failure(400, &format!("invalid JSON: {}", e)))
+}
+
+fn unavailable(payload: Option<Value>, source: &str) -> (u16, Value) {
+    match payload {
+        Some(payload) => (200, payload),
+        None => failure(503, &format!("{} read failed", source)),
+    }
+}
+
+fn failure(status: u16, message: &str) -> (u16, Value) {
+    (status, json!({ "error": message }))
+}
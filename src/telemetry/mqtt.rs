@@ -0,0 +1,248 @@
+//! MQTT telemetry publisher.
+//!
+//! [`MqttPublisher`] periodically takes the latest [`BoardState`](super::BoardState) from a
+//! [`StateHub`](super::StateHub) and publishes it as JSON to topics below a configurable
+//! prefix:
+//!
+//! | Topic                 | Payload                                                     |
+//! |-----------------------|-------------------------------------------------------------|
+//! | `<prefix>/adc`        | `{"sequence":..,"timestamp_ms":..,"adc":[..]}`              |
+//! | `<prefix>/io`         | `{"sequence":..,"timestamp_ms":..,"io":5,"levels":[..]}`    |
+//! | `<prefix>/attitude`   | `{"sequence":..,"timestamp_ms":..,"pitch":..,"roll":..,"yaw":..}` |
+//! | `<prefix>/status`     | `online`, or `offline` via the broker's last will (retained) |
+//!
+//! Reconnection is handled automatically; snapshots produced while the broker is unreachable
+//! are dropped rather than queued.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use uptechstar_rs::telemetry::StateHub;
+//! use uptechstar_rs::telemetry::mqtt::{MqttConfig, MqttPublisher};
+//!
+//! let mut hub = StateHub::new(50.0).with_adc(true).with_io(true).with_mpu(true);
+//! hub.start();
+//!
+//! let config = MqttConfig::new("broker.local", 1883)
+//!     .with_topic_prefix("robots/uptech-01")
+//!     .with_interval(Duration::from_millis(200));
+//!
+//! let publisher = MqttPublisher::start(config, hub.reader());
+//! std::thread::sleep(Duration::from_secs(60));
+//! publisher.stop();
+//! ```
+
+use super::{StateReader, json};
+use log::{debug, info, warn};
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Connection and publication settings for an [`MqttPublisher`].
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    host: String,
+    port: u16,
+    client_id: String,
+    credentials: Option<(String, String)>,
+    topic_prefix: String,
+    interval: Duration,
+    qos: QoS,
+    keep_alive: Duration,
+}
+
+impl MqttConfig {
+    /// Creates a configuration for the broker at `host:port`.
+    ///
+    /// Defaults: client id `uptech-<pid>`, topic prefix `uptech`, 1 s interval, QoS 0 and a
+    /// 30 s keep-alive.
+    pub fn new<H: Into<String>>(host: H, port: u16) -> Self {
+        MqttConfig {
+            host: host.into(),
+            port,
+            client_id: format!("uptech-{}", std::process::id()),
+            credentials: None,
+            topic_prefix: "uptech".into(),
+            interval: Duration::from_secs(1),
+            qos: QoS::AtMostOnce,
+            keep_alive: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets the MQTT client identifier.
+    pub fn with_client_id<S: Into<String>>(mut self, client_id: S) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    /// Authenticates with a username and password.
+    pub fn with_credentials<U: Into<String>, P: Into<String>>(mut self, username: U, password: P) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Sets the prefix all topics are published under. Trailing slashes are removed.
+    pub fn with_topic_prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.topic_prefix = prefix.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the publication interval.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the quality of service used for sensor topics.
+    pub fn with_qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    /// Sets the keep-alive interval negotiated with the broker.
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.topic_prefix, name)
+    }
+}
+
+/// Publishes board telemetry to an MQTT broker on background threads.
+///
+/// One thread drives the network connection, another publishes the latest snapshot every
+/// interval, skipping ticks where the hub has not produced anything new.
+pub struct MqttPublisher {
+    client: Client,
+    status_topic: String,
+    running: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl MqttPublisher {
+    /// Connects to the configured broker and starts publishing snapshots read from `state`.
+    pub fn start(config: MqttConfig, state: StateReader) -> Self {
+        info!(
+            "Publishing telemetry to mqtt://{}:{} under '{}'",
+            config.host, config.port, config.topic_prefix
+        );
+
+        let status_topic = config.topic("status");
+
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        options.set_keep_alive(config.keep_alive);
+        options.set_last_will(LastWill::new(&status_topic, "offline", QoS::AtLeastOnce, true));
+        if let Some((username, password)) = &config.credentials {
+            options.set_credentials(username, password);
+        }
+
+        let (client, mut connection) = Client::new(options, 32);
+        let running = Arc::new(AtomicBool::new(true));
+
+        let connection_thread = {
+            let running = Arc::clone(&running);
+            let client = client.clone();
+            let status_topic = status_topic.clone();
+
+            thread::Builder::new()
+                .name("uptech-mqtt-connection".into())
+                .spawn(move || {
+                    for event in connection.iter() {
+                        if !running.load(Ordering::Acquire) {
+                            break;
+                        }
+
+                        match event {
+                            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                                info!("Connected to MQTT broker");
+                                let _ = client.try_publish(&status_topic, QoS::AtLeastOnce, true, "online");
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                warn!("MQTT connection error: {}, retrying", e);
+                                thread::sleep(Duration::from_secs(1));
+                            }
+                        }
+                    }
+                    debug!("MQTT connection thread exited");
+                })
+                .expect("Failed to spawn MQTT connection thread")
+        };
+
+        let publish_thread = {
+            let running = Arc::clone(&running);
+            let client = client.clone();
+
+            thread::Builder::new()
+                .name("uptech-mqtt-publisher".into())
+                .spawn(move || {
+                    let topics = [
+                        (config.topic("adc"), json::adc as fn(&_) -> _),
+                        (config.topic("io"), json::io),
+                        (config.topic("attitude"), json::attitude),
+                    ];
+                    let mut last_sequence = 0;
+
+                    while running.load(Ordering::Acquire) {
+                        let snapshot = state.latest();
+
+                        if snapshot.sequence != last_sequence {
+                            last_sequence = snapshot.sequence;
+
+                            for (topic, payload) in &topics {
+                                if let Some(payload) = payload(&snapshot)
+                                    && let Err(e) =
+                                        client.try_publish(topic, config.qos, false, payload.to_string())
+                                {
+                                    debug!("Dropped telemetry for {}: {}", topic, e);
+                                }
+                            }
+                        }
+
+                        thread::sleep(config.interval);
+                    }
+                    debug!("MQTT publisher thread exited");
+                })
+                .expect("Failed to spawn MQTT publisher thread")
+        };
+
+        MqttPublisher {
+            client,
+            status_topic,
+            running,
+            threads: vec![connection_thread, publish_thread],
+        }
+    }
+
+    /// Publishes `offline` to the status topic, disconnects and joins the background threads.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if !self.running.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        let _ = self
+            .client
+            .try_publish(&self.status_topic, QoS::AtLeastOnce, true, "offline");
+        let _ = self.client.try_disconnect();
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+        info!("MQTT publisher stopped");
+    }
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -0,0 +1,371 @@
+//! Board state in shared memory.
+//!
+//! A [`ShmExporter`] keeps the latest [`BoardState`](super::BoardState) of a
+//! [`StateHub`](super::StateHub) in a small memory-mapped file, by default
+//! [`/dev/shm/uptech_state`](DEFAULT_PATH). Other processes on the board, such as a Python
+//! dashboard, map the same file and read the state with a few memory loads instead of a socket
+//! round trip.
+//!
+//! # Layout
+//!
+//! The region is [`REGION_LEN`] bytes, little-endian:
+//!
+//! | Offset | Size | Content |
+//! |--------|------|---------|
+//! | 0      | 4    | Magic `UTSM` |
+//! | 4      | 2    | Layout version, currently [`LAYOUT_VERSION`] |
+//! | 6      | 2    | Reserved, zero |
+//! | 8      | 4    | Update counter, odd while an update is being written |
+//! | 12     | 4    | Length of the frame that follows |
+//! | 16     | ...  | The state as an encoded [`TelemetryFrame`] |
+//!
+//! The update counter works as a sequence lock: read it, skip the read if it is odd, copy the
+//! frame, then read the counter again and retry if it changed. Give up after a bounded number
+//! of attempts, as the counter stays odd if the writer dies in the middle of an update. In
+//! Python:
+//!
+//! ```python
+//! import mmap, struct
+//!
+//! with open("/dev/shm/uptech_state", "rb") as f:
+//!     region = mmap.mmap(f.fileno(), 0, access=mmap.ACCESS_READ)
+//!
+//! def latest():
+//!     for _ in range(500):
+//!         before, length = struct.unpack_from("<II", region, 8)
+//!         frame = bytes(region[16:16 + length])
+//!         after, = struct.unpack_from("<I", region, 8)
+//!         if before == after and before % 2 == 0:
+//!             return frame
+//!     return None
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::telemetry::StateHub;
+//! use uptechstar_rs::telemetry::shm::{DEFAULT_PATH, ShmExporter};
+//!
+//! let mut hub = StateHub::new(100.0).with_adc(true).with_mpu(true);
+//! hub.start();
+//!
+//! let exporter = ShmExporter::start(DEFAULT_PATH, 100.0, hub.reader()).unwrap();
+//! std::thread::sleep(std::time::Duration::from_secs(600));
+//! exporter.stop();
+//! ```
+
+use super::{StateReader, TelemetryFrame};
+use crate::sampler::{Ticker, period_from_rate};
+use log::{debug, info};
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::ptr::{self, NonNull};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering, fence};
+use std::thread::{self, JoinHandle};
+
+/// Where [`ShmExporter`]s usually publish.
+pub const DEFAULT_PATH: &str = "/dev/shm/uptech_state";
+
+/// Size of the shared region in bytes.
+pub const REGION_LEN: usize = 4096;
+
+/// Version of the region layout.
+pub const LAYOUT_VERSION: u16 = 1;
+
+const MAGIC: &[u8; 4] = b"UTSM";
+const COUNTER_OFFSET: usize = 8;
+const LENGTH_OFFSET: usize = 12;
+const FRAME_OFFSET: usize = 16;
+/// How often [`ShmReader::latest`] tries to read the frame before giving up on a writer that
+/// stays in the middle of an update.
+const READ_ATTEMPTS: usize = 500;
+
+/// A shared mapping of a region file.
+struct Region {
+    base: NonNull<u8>,
+}
+
+// The mapping is plain memory shared with other processes; all access goes through the
+// sequence lock.
+unsafe impl Send for Region {}
+unsafe impl Sync for Region {}
+
+impl Region {
+    fn map(file: &File, writable: bool) -> io::Result<Self> {
+        let protection = if writable { libc::PROT_READ | libc::PROT_WRITE } else { libc::PROT_READ };
+        let base = unsafe {
+            libc::mmap(ptr::null_mut(), REGION_LEN, protection, libc::MAP_SHARED, file.as_raw_fd(), 0)
+        };
+        if base == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Region {
+            base: NonNull::new(base.cast()).expect("mmap returned a null mapping"),
+        })
+    }
+
+    fn counter(&self) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(self.base.as_ptr().add(COUNTER_OFFSET).cast()) }
+    }
+
+    fn length(&self) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(self.base.as_ptr().add(LENGTH_OFFSET).cast()) }
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> *mut u8 {
+        assert!(offset + len <= REGION_LEN, "Access beyond the shared region");
+        unsafe { self.base.as_ptr().add(offset) }
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.base.as_ptr().cast(), REGION_LEN) };
+    }
+}
+
+/// Publishes [`TelemetryFrame`]s to a shared region.
+///
+/// # Examples
+///
+/// ```rust
+/// use uptechstar_rs::adc_io::IoFrame;
+/// use uptechstar_rs::telemetry::TelemetryFrame;
+/// use uptechstar_rs::telemetry::shm::{ShmReader, ShmWriter};
+///
+/// let dir = tempfile::tempdir().unwrap();
+/// let path = dir.path().join("uptech_state");
+///
+/// let mut writer = ShmWriter::create(&path).unwrap();
+/// let reader = ShmReader::open(&path).unwrap();
+/// assert_eq!(reader.latest(), None);
+///
+/// let frame = TelemetryFrame { sequence: 3, io: Some(IoFrame(0b11)), ..Default::default() };
+/// writer.write(&frame).unwrap();
+/// assert_eq!(reader.latest(), Some(frame));
+/// assert_eq!(reader.updates(), 1);
+/// ```
+pub struct ShmWriter {
+    region: Region,
+    path: PathBuf,
+    bytes: Vec<u8>,
+}
+
+impl ShmWriter {
+    /// Creates the region file at `path`, or reuses an existing one, maps it and writes the
+    /// header, with nothing published yet.
+    ///
+    /// An existing file is resized in place rather than truncated, so readers that still have
+    /// it mapped, for example from before the exporting program restarted, keep working and
+    /// see the new frames.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+        file.set_len(REGION_LEN as u64)?;
+
+        let region = Region::map(&file, true)?;
+        region.counter().store(0, Ordering::Release);
+        region.length().store(0, Ordering::Release);
+        unsafe {
+            ptr::copy_nonoverlapping(MAGIC.as_ptr(), region.bytes(0, 4), 4);
+            ptr::copy_nonoverlapping(LAYOUT_VERSION.to_le_bytes().as_ptr(), region.bytes(4, 2), 2);
+            ptr::write_bytes(region.bytes(6, 2), 0, 2);
+        }
+
+        Ok(ShmWriter {
+            region,
+            path,
+            bytes: Vec::with_capacity(REGION_LEN - FRAME_OFFSET),
+        })
+    }
+
+    /// Returns the path of the region file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Replaces the published frame with `frame`.
+    ///
+    /// # Errors
+    ///
+    /// [`ErrorKind::InvalidInput`] if the encoded frame does not fit into the region.
+    pub fn write(&mut self, frame: &TelemetryFrame) -> io::Result<()> {
+        self.bytes.clear();
+        frame.encode_into(&mut self.bytes);
+        if self.bytes.len() > REGION_LEN - FRAME_OFFSET {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "Frame does not fit into the shared region"));
+        }
+
+        let counter = self.region.counter();
+        let start = counter.load(Ordering::Relaxed);
+        counter.store(start.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        unsafe {
+            ptr::copy_nonoverlapping(
+                self.bytes.as_ptr(),
+                self.region.bytes(FRAME_OFFSET, self.bytes.len()),
+                self.bytes.len(),
+            );
+        }
+        self.region.length().store(self.bytes.len() as u32, Ordering::Relaxed);
+
+        counter.store(start.wrapping_add(2), Ordering::Release);
+        Ok(())
+    }
+}
+
+/// Reads the frames published by a [`ShmWriter`] or [`ShmExporter`], possibly in another
+/// process.
+pub struct ShmReader {
+    region: Region,
+}
+
+impl ShmReader {
+    /// Maps the region file at `path` read-only.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be opened or mapped, or [`ErrorKind::InvalidData`] if it is not a
+    /// region of this layout version.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        if file.metadata()?.len() < REGION_LEN as u64 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Shared region is too short"));
+        }
+
+        let region = Region::map(&file, false)?;
+        let mut header = [0u8; 6];
+        unsafe { ptr::copy_nonoverlapping(region.bytes(0, 6), header.as_mut_ptr(), 6) };
+        if &header[..4] != MAGIC || u16::from_le_bytes([header[4], header[5]]) != LAYOUT_VERSION {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Not an uptech shared state region"));
+        }
+
+        Ok(ShmReader { region })
+    }
+
+    /// Returns how many frames have been published since the region was created.
+    pub fn updates(&self) -> u32 {
+        self.region.counter().load(Ordering::Acquire) / 2
+    }
+
+    /// Returns the latest published frame, or `None` if nothing has been published yet or no
+    /// consistent frame could be read, as when the writer died in the middle of an update.
+    pub fn latest(&self) -> Option<TelemetryFrame> {
+        let mut bytes = [0u8; REGION_LEN - FRAME_OFFSET];
+        let counter = self.region.counter();
+
+        for _ in 0..READ_ATTEMPTS {
+            let before = counter.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                thread::yield_now();
+                continue;
+            }
+            if before == 0 {
+                return None;
+            }
+
+            let len = (self.region.length().load(Ordering::Relaxed) as usize).min(bytes.len());
+            unsafe { ptr::copy_nonoverlapping(self.region.bytes(FRAME_OFFSET, len), bytes.as_mut_ptr(), len) };
+            fence(Ordering::Acquire);
+
+            if counter.load(Ordering::Relaxed) == before {
+                return TelemetryFrame::decode(&bytes[..len]).ok();
+            }
+        }
+
+        debug!("No consistent shared state after {} attempts", READ_ATTEMPTS);
+        None
+    }
+}
+
+/// Keeps the latest board state in a shared region on a background thread.
+pub struct ShmExporter {
+    path: PathBuf,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ShmExporter {
+    /// Creates the region at `path` and starts copying snapshots read from `state` into it at
+    /// up to `rate_hz` times per second. A snapshot is only copied if it is new.
+    ///
+    /// # Errors
+    ///
+    /// If the region cannot be created.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn start<P: AsRef<Path>>(path: P, rate_hz: f32, state: StateReader) -> io::Result<Self> {
+        let period = period_from_rate(rate_hz);
+        let mut writer = ShmWriter::create(path)?;
+        let path = writer.path().to_path_buf();
+
+        info!("Exporting board state to {} at {:.1} Hz", path.display(), rate_hz);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = Arc::clone(&running);
+
+            thread::Builder::new()
+                .name("uptech-shm-export".into())
+                .spawn(move || {
+                    let mut ticker = Ticker::new(period);
+                    let mut last_sequence = 0;
+
+                    while running.load(Ordering::Acquire) {
+                        let snapshot = state.latest();
+                        if snapshot.sequence != last_sequence {
+                            last_sequence = snapshot.sequence;
+                            // Board state frames carry no user values and always fit.
+                            let _ = writer.write(&TelemetryFrame::from(&snapshot));
+                        }
+
+                        ticker.wait();
+                    }
+
+                    debug!("Shared memory export thread exited");
+                })
+                .expect("Failed to spawn shared memory export thread")
+        };
+
+        Ok(ShmExporter {
+            path,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the path of the region file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stops exporting and waits for the thread to exit. The region file is left in place
+    /// with the last state, so readers can tell the state is stale from the update counter.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if !self.running.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        info!("Board state export to {} stopped", self.path.display());
+    }
+}
+
+impl Drop for ShmExporter {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -0,0 +1,240 @@
+//! UDP broadcast of board state.
+//!
+//! A [`Broadcaster`] sends the latest [`BoardState`](super::BoardState) of a
+//! [`StateHub`](super::StateHub) as one [`TelemetryFrame`] datagram per tick, by default to the
+//! broadcast address of the local network. Any machine on that network can pick the frames up
+//! with a [`Receiver`] bound to the same port, without knowing the board's address or
+//! connecting to it first.
+//!
+//! Datagrams can be lost or reordered; the frame [`sequence`](TelemetryFrame::sequence) shows
+//! both.
+//!
+//! # Examples
+//!
+//! On the board:
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::telemetry::StateHub;
+//! use uptechstar_rs::telemetry::udp::{Broadcaster, DEFAULT_PORT};
+//!
+//! let mut hub = StateHub::new(100.0).with_adc(true).with_mpu(true);
+//! hub.start();
+//!
+//! let broadcaster = Broadcaster::start(("255.255.255.255", DEFAULT_PORT), 50.0, hub.reader()).unwrap();
+//! std::thread::sleep(std::time::Duration::from_secs(600));
+//! broadcaster.stop();
+//! ```
+//!
+//! On the laptop:
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::telemetry::udp::{DEFAULT_PORT, Receiver};
+//!
+//! let mut receiver = Receiver::bind(DEFAULT_PORT).unwrap();
+//! loop {
+//!     let (frame, from) = receiver.recv().unwrap();
+//!     if let Some(mpu) = frame.mpu {
+//!         println!("{} #{} yaw {:.1}", from, frame.sequence, mpu.attitude[2]);
+//!     }
+//! }
+//! ```
+
+use super::{StateReader, TelemetryFrame};
+use crate::sampler::{Ticker, period_from_rate};
+use log::{debug, info, warn};
+use std::io::{self, ErrorKind};
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The port [`Broadcaster`] and [`Receiver`] are usually used with.
+pub const DEFAULT_PORT: u16 = 47_800;
+
+/// The largest datagram a [`Receiver`] accepts; a frame with 255 user values fits.
+const MAX_DATAGRAM: usize = 2048;
+
+/// Sends board state as UDP datagrams on a background thread.
+///
+/// A frame is only sent when the hub has published something new since the last one. Send
+/// errors, such as a missing network while the robot is out of range, are logged and the
+/// next tick is tried again.
+pub struct Broadcaster {
+    target: SocketAddr,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Broadcaster {
+    /// Starts sending snapshots read from `state` to `target` at up to `rate_hz` frames per
+    /// second. `target` may be a broadcast, multicast or unicast address.
+    ///
+    /// # Errors
+    ///
+    /// If `target` cannot be resolved or no socket can be bound.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn start<A: ToSocketAddrs>(target: A, rate_hz: f32, state: StateReader) -> io::Result<Self> {
+        let period = period_from_rate(rate_hz);
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "No address to broadcast telemetry to"))?;
+
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.set_broadcast(true)?;
+
+        info!("Broadcasting telemetry to udp://{} at {:.1} Hz", target, rate_hz);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = Arc::clone(&running);
+
+            thread::Builder::new()
+                .name("uptech-udp-telemetry".into())
+                .spawn(move || {
+                    let mut ticker = Ticker::new(period);
+                    let mut last_sequence = 0;
+                    let mut bytes = Vec::with_capacity(128);
+                    let mut failing = false;
+
+                    while running.load(Ordering::Acquire) {
+                        let snapshot = state.latest();
+                        if snapshot.sequence != last_sequence {
+                            last_sequence = snapshot.sequence;
+
+                            bytes.clear();
+                            TelemetryFrame::from(&snapshot).encode_into(&mut bytes);
+                            match socket.send_to(&bytes, target) {
+                                Ok(_) => failing = false,
+                                // Only log the first failure of a run, not one per tick.
+                                Err(e) if !failing => {
+                                    warn!("Failed to send telemetry to {}: {}", target, e);
+                                    failing = true;
+                                }
+                                Err(_) => {}
+                            }
+                        }
+
+                        ticker.wait();
+                    }
+
+                    debug!("UDP telemetry thread exited");
+                })
+                .expect("Failed to spawn UDP telemetry thread")
+        };
+
+        Ok(Broadcaster {
+            target,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the address frames are sent to.
+    pub fn target(&self) -> SocketAddr {
+        self.target
+    }
+
+    /// Stops sending and waits for the thread to exit.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if !self.running.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        info!("Telemetry broadcast to {} stopped", self.target);
+    }
+}
+
+impl Drop for Broadcaster {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Receives [`TelemetryFrame`] datagrams sent by a [`Broadcaster`].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::net::UdpSocket;
+/// use uptechstar_rs::adc_io::IoFrame;
+/// use uptechstar_rs::telemetry::TelemetryFrame;
+/// use uptechstar_rs::telemetry::udp::Receiver;
+///
+/// let mut receiver = Receiver::bind(0).unwrap();
+/// let port = receiver.local_addr().unwrap().port();
+///
+/// let frame = TelemetryFrame { sequence: 7, io: Some(IoFrame(1)), ..Default::default() };
+/// let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// sender.send_to(b"noise", ("127.0.0.1", port)).unwrap();
+/// sender.send_to(&frame.encode(), ("127.0.0.1", port)).unwrap();
+///
+/// let (received, from) = receiver.recv().unwrap();
+/// assert_eq!(received, frame);
+/// assert_eq!(from, sender.local_addr().unwrap());
+/// assert_eq!(receiver.rejected(), 1);
+/// ```
+pub struct Receiver {
+    socket: UdpSocket,
+    buffer: Box<[u8]>,
+    rejected: u64,
+}
+
+impl Receiver {
+    /// Binds `port` on all IPv4 interfaces. Port `0` picks a free port.
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        info!("Receiving telemetry on udp://{}", socket.local_addr()?);
+
+        Ok(Receiver {
+            socket,
+            buffer: vec![0u8; MAX_DATAGRAM].into_boxed_slice(),
+            rejected: 0,
+        })
+    }
+
+    /// Sets how long [`recv`](Receiver::recv) waits for a frame before failing with
+    /// [`ErrorKind::WouldBlock`] or [`ErrorKind::TimedOut`], depending on the platform. `None`,
+    /// the default, waits forever.
+    pub fn with_timeout(self, timeout: Option<Duration>) -> io::Result<Self> {
+        self.socket.set_read_timeout(timeout)?;
+        Ok(self)
+    }
+
+    /// Returns the address the receiver is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Returns the number of datagrams dropped because they were not valid frames.
+    pub fn rejected(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Waits for the next valid frame and returns it with the address of its sender.
+    /// Datagrams that are not valid frames are skipped and counted as
+    /// [rejected](Receiver::rejected).
+    pub fn recv(&mut self) -> io::Result<(TelemetryFrame, SocketAddr)> {
+        loop {
+            let (len, from) = self.socket.recv_from(&mut self.buffer)?;
+            match TelemetryFrame::decode(&self.buffer[..len]) {
+                Ok(frame) => return Ok((frame, from)),
+                Err(e) => {
+                    debug!("Rejected datagram from {}: {}", from, e);
+                    self.rejected += 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! WebSocket live streaming of board state.
+//!
+//! [`WebSocketServer`] accepts browser connections and pushes the latest
+//! [`BoardState`](super::BoardState) of a [`StateHub`](super::StateHub) to every client at a
+//! fixed rate. Each frame is one object:
+//!
+//! ```text
+//! {"sequence":..,"timestamp_ms":..,"adc":[..10 values..],"io":5,
+//!  "mpu":{"accel":[..],"gyro":[..],"attitude":[..]}}
+//! ```
+//!
+//! Sources the hub does not sample are `null`. Frames are sent as JSON text messages by
+//! default, or as CBOR binary messages with [`FrameEncoding::Cbor`]. A client can override the
+//! server default by connecting with `?encoding=json` or `?encoding=cbor`.
+//!
+//! Frames are only sent when the hub has published something new since the last frame, and a
+//! client that cannot keep up is disconnected rather than buffered for.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use uptechstar_rs::telemetry::StateHub;
+//! use uptechstar_rs::telemetry::websocket::{FrameEncoding, WebSocketConfig, WebSocketServer};
+//!
+//! let mut hub = StateHub::new(100.0).with_adc(true).with_mpu(true);
+//! hub.start();
+//!
+//! let config = WebSocketConfig::new().with_rate(30.0).with_encoding(FrameEncoding::Json);
+//! let server = WebSocketServer::start("0.0.0.0:9001", config, hub.reader()).unwrap();
+//! println!("Dashboard feed on ws://{}", server.addr());
+//!
+//! std::thread::sleep(std::time::Duration::from_secs(600));
+//! server.stop();
+//! ```
+
+use super::{StateReader, json};
+use crate::sampler::{Ticker, period_from_rate};
+use log::{debug, info, warn};
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tungstenite::handshake::server::{Request, Response};
+use tungstenite::{Message, WebSocket};
+
+/// How long a client may take to complete the WebSocket handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a client may take to accept one frame before it is considered stalled.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Wire encoding of streamed frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameEncoding {
+    /// JSON text messages.
+    Json,
+    /// CBOR binary messages with the same structure as the JSON frames.
+    Cbor,
+}
+
+impl FrameEncoding {
+    fn from_query(query: &str) -> Option<Self> {
+        query
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix("encoding="))
+            .find_map(|value| match value {
+                "json" => Some(FrameEncoding::Json),
+                "cbor" => Some(FrameEncoding::Cbor),
+                _ => None,
+            })
+    }
+}
+
+/// Streaming settings for a [`WebSocketServer`].
+#[derive(Debug, Clone)]
+pub struct WebSocketConfig {
+    period: Duration,
+    encoding: FrameEncoding,
+}
+
+impl WebSocketConfig {
+    /// Creates a configuration streaming JSON frames at 20 Hz.
+    pub fn new() -> Self {
+        WebSocketConfig {
+            period: period_from_rate(20.0),
+            encoding: FrameEncoding::Json,
+        }
+    }
+
+    /// Sets the per-client frame rate.
+    ///
+    /// # Panics
+    ///
+    /// If `rate_hz` is not a positive, finite number.
+    pub fn with_rate(mut self, rate_hz: f32) -> Self {
+        self.period = period_from_rate(rate_hz);
+        self
+    }
+
+    /// Sets the encoding used for clients that do not request one.
+    pub fn with_encoding(mut self, encoding: FrameEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Streams board state to WebSocket clients on background threads.
+///
+/// One thread accepts connections and every client gets its own streaming thread. All of them
+/// are shut down when [`stop`](WebSocketServer::stop) is called or the server is dropped.
+pub struct WebSocketServer {
+    addr: SocketAddr,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WebSocketServer {
+    /// Binds `addr` and starts streaming snapshots read from `state`.
+    ///
+    /// # Errors
+    ///
+    /// If `addr` cannot be resolved or bound.
+    pub fn start<A: ToSocketAddrs>(addr: A, config: WebSocketConfig, state: StateReader) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+
+        info!(
+            "Streaming telemetry over ws://{} at {:.1} Hz as {:?}",
+            addr,
+            1.0 / config.period.as_secs_f32(),
+            config.encoding
+        );
+
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let running = Arc::clone(&running);
+
+            thread::Builder::new()
+                .name("uptech-ws-accept".into())
+                .spawn(move || {
+                    let mut clients = Vec::new();
+
+                    for stream in listener.incoming() {
+                        if !running.load(Ordering::Acquire) {
+                            break;
+                        }
+
+                        match stream {
+                            Ok(stream) => {
+                                clients.retain(|client: &JoinHandle<()>| !client.is_finished());
+                                clients.push(spawn_client(stream, &config, &state, &running));
+                            }
+                            Err(e) => warn!("Failed to accept WebSocket connection: {}", e),
+                        }
+                    }
+
+                    for client in clients {
+                        let _ = client.join();
+                    }
+                    debug!("WebSocket accept thread exited");
+                })
+                .expect("Failed to spawn WebSocket accept thread")
+        };
+
+        Ok(WebSocketServer {
+            addr,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Returns the address the server is bound to.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Closes all client connections and waits for the server threads to exit.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if !self.running.swap(false, Ordering::AcqRel) {
+            return;
+        }
+
+        // Wake the accept thread blocked in `incoming()` so it can observe the flag.
+        let _ = TcpStream::connect(self.addr);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        info!("WebSocket server on {} stopped", self.addr);
+    }
+}
+
+impl Drop for WebSocketServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn spawn_client(
+    stream: TcpStream,
+    config: &WebSocketConfig,
+    state: &StateReader,
+    running: &Arc<AtomicBool>,
+) -> JoinHandle<()> {
+    let config = config.clone();
+    let state = state.clone();
+    let running = Arc::clone(running);
+
+    thread::Builder::new()
+        .name("uptech-ws-client".into())
+        .spawn(move || {
+            let peer = stream.peer_addr().ok();
+            match stream_to(stream, &config, &state, &running) {
+                Ok(()) => debug!("WebSocket client {:?} disconnected", peer),
+                Err(e) => debug!("WebSocket client {:?} dropped: {}", peer, e),
+            }
+        })
+        .expect("Failed to spawn WebSocket client thread")
+}
+
+// The handshake callback's error type is dictated by tungstenite.
+#[allow(clippy::result_large_err)]
+fn stream_to(stream: TcpStream, config: &WebSocketConfig, state: &StateReader, running: &AtomicBool) -> io::Result<()> {
+    // A silent client must not be able to hold up `stop` by stalling the handshake.
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+    let mut encoding = config.encoding;
+    let mut socket = tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+        if let Some(requested) = request.uri().query().and_then(FrameEncoding::from_query) {
+            encoding = requested;
+        }
+        Ok(response)
+    })
+    .map_err(|e| io::Error::new(ErrorKind::ConnectionAborted, e.to_string()))?;
+
+    // Reads only drain control frames between ticks, so they must never block.
+    socket.get_ref().set_read_timeout(Some(Duration::from_millis(1)))?;
+    socket.get_ref().set_write_timeout(Some(WRITE_TIMEOUT))?;
+
+    info!("WebSocket client connected, streaming {:?}", encoding);
+
+    let mut ticker = Ticker::new(config.period);
+    let mut last_sequence = 0;
+
+    while running.load(Ordering::Acquire) {
+        if !drain_incoming(&mut socket)? {
+            return Ok(());
+        }
+
+        let snapshot = state.latest();
+        if snapshot.sequence != last_sequence {
+            last_sequence = snapshot.sequence;
+
+            let frame = json::state(&snapshot);
+            let message = match encoding {
+                FrameEncoding::Json => Message::text(frame.to_string()),
+                FrameEncoding::Cbor => {
+                    let mut bytes = Vec::with_capacity(192);
+                    ciborium::into_writer(&frame, &mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+                    Message::binary(bytes)
+                }
+            };
+            socket.send(message).map_err(into_io)?;
+        }
+
+        ticker.wait();
+    }
+
+    let _ = socket.close(None);
+    let _ = socket.flush();
+    Ok(())
+}
+
+/// Handles pending control frames. Returns `false` once the client closed the connection.
+fn drain_incoming(socket: &mut WebSocket<TcpStream>) -> io::Result<bool> {
+    loop {
+        match socket.read() {
+            Ok(Message::Close(_)) => return Ok(false),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                return Ok(true);
+            }
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(false),
+            Err(e) => return Err(into_io(e)),
+        }
+    }
+}
+
+fn into_io(error: tungstenite::Error) -> io::Error {
+    match error {
+        tungstenite::Error::Io(e) => e,
+        other => io::Error::other(other.to_string()),
+    }
+}
@@ -0,0 +1,345 @@
+//! A watchdog that is only fed while the robot is healthy.
+//!
+//! A control program that hangs in an FFI call keeps whatever motor outputs it last set. A
+//! [`Watchdog`] prevents that by feeding the Linux watchdog device (`/dev/watchdog`) from a
+//! thread of its own, and only while every registered source of liveness checks out:
+//!
+//! - a [`Heartbeat`] that the control loop beats every iteration,
+//! - a [`StateReader`] whose hub keeps publishing new snapshots, and
+//! - optionally the [`health`] counters of the ADC/IO and MPU subsystems.
+//!
+//! Once a check fails the watchdog is starved and the kernel resets the board when the
+//! driver's timeout runs out. Boards without a watchdog device can use a
+//! [software watchdog](Watchdog::software) instead, which runs a callback and aborts the
+//! process. It cannot reset the board, so it does not help when the motor controller keeps
+//! its last command on its own.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use uptechstar_rs::telemetry::StateHub;
+//! use uptechstar_rs::watchdog::Watchdog;
+//!
+//! let mut hub = StateHub::new(100.0).with_adc(true).with_mpu(true);
+//! hub.start();
+//!
+//! let mut watchdog = Watchdog::device("/dev/watchdog").with_max_failures(20);
+//! watchdog.watch_state("state-hub", hub.reader(), Duration::from_millis(200));
+//! let control = watchdog.register("control", Duration::from_millis(100));
+//! watchdog.start().unwrap();
+//!
+//! loop {
+//!     // ... read sensors, drive motors ...
+//!     control.beat();
+//!     std::thread::sleep(Duration::from_millis(10));
+//! }
+//! ```
+
+use crate::health;
+use crate::telemetry::StateReader;
+use log::{debug, error, info, warn};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A liveness signal for a [`Watchdog`], beaten by the code it watches.
+///
+/// `Heartbeat` is a cheap handle; clones beat the same heartbeat.
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    last: Arc<Mutex<Instant>>,
+}
+
+impl Heartbeat {
+    /// Signals that the watched code is still making progress.
+    pub fn beat(&self) {
+        *self.last.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.last.lock().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+}
+
+enum Source {
+    Heartbeat(Heartbeat),
+    State {
+        reader: StateReader,
+        sequence: u64,
+        changed: Instant,
+    },
+}
+
+struct Watched {
+    name: String,
+    max_silence: Duration,
+    source: Source,
+}
+
+impl Watched {
+    /// Returns why this source is unhealthy, or `None` if it is fine.
+    fn check(&mut self) -> Option<String> {
+        let silence = match &mut self.source {
+            Source::Heartbeat(heartbeat) => heartbeat.elapsed(),
+            Source::State { reader, sequence, changed } => {
+                let latest = reader.latest().sequence;
+                if latest != *sequence {
+                    *sequence = latest;
+                    *changed = Instant::now();
+                }
+                changed.elapsed()
+            }
+        };
+
+        (silence > self.max_silence).then(|| format!("{} silent for {:?}", self.name, silence))
+    }
+}
+
+enum Kind {
+    Device(PathBuf),
+    Software {
+        timeout: Duration,
+        on_expire: Option<Box<dyn FnOnce() + Send>>,
+    },
+}
+
+/// Feeds a hardware or software watchdog while all watched sources are healthy.
+pub struct Watchdog {
+    kind: Kind,
+    period: Duration,
+    max_failures: Option<u32>,
+    watched: Arc<Mutex<Vec<Watched>>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Creates a stopped watchdog feeding the watchdog device at `path`, usually
+    /// `/dev/watchdog`, every 500ms.
+    ///
+    /// The device is opened by [`start`](Watchdog::start), which arms it. The reset timeout is
+    /// that of the driver, typically set with its `timeout` module parameter.
+    pub fn device<P: Into<PathBuf>>(path: P) -> Self {
+        Self::new(Kind::Device(path.into()))
+    }
+
+    /// Creates a stopped software watchdog that expires once the watched sources have been
+    /// unhealthy for `timeout`. On expiry it logs the reason, runs the
+    /// [expiry callback](Watchdog::with_on_expire) and aborts the process.
+    pub fn software(timeout: Duration) -> Self {
+        Self::new(Kind::Software { timeout, on_expire: None })
+    }
+
+    fn new(kind: Kind) -> Self {
+        Watchdog {
+            kind,
+            period: Duration::from_millis(500),
+            max_failures: None,
+            watched: Arc::new(Mutex::new(Vec::new())),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Sets how often the watched sources are checked and the watchdog is fed. Keep it well
+    /// below the watchdog timeout.
+    pub fn with_period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Also starves the watchdog when the ADC/IO or MPU subsystem has failed `count` calls in a
+    /// row, see [`health::report`].
+    pub fn with_max_failures(mut self, count: u32) -> Self {
+        self.max_failures = Some(count.max(1));
+        self
+    }
+
+    /// Sets a callback a software watchdog runs right before aborting the process, for
+    /// example to stop the motors through a path that does not depend on the hung code.
+    /// Ignored for device watchdogs.
+    pub fn with_on_expire<F: FnOnce() + Send + 'static>(mut self, on_expire: F) -> Self {
+        if let Kind::Software { on_expire: slot, .. } = &mut self.kind {
+            *slot = Some(Box::new(on_expire));
+        }
+        self
+    }
+
+    /// Registers a heartbeat that must be beaten at least every `max_silence`. It counts as
+    /// beaten when it is registered, so registering works while the watchdog is running.
+    pub fn register(&self, name: &str, max_silence: Duration) -> Heartbeat {
+        let heartbeat = Heartbeat {
+            last: Arc::new(Mutex::new(Instant::now())),
+        };
+        self.watch(name, max_silence, Source::Heartbeat(heartbeat.clone()));
+        heartbeat
+    }
+
+    /// Watches a [`StateHub`](crate::telemetry::StateHub) through `reader`, requiring a new
+    /// snapshot at least every `max_silence`.
+    pub fn watch_state(&self, name: &str, reader: StateReader, max_silence: Duration) {
+        let sequence = reader.latest().sequence;
+        self.watch(
+            name,
+            max_silence,
+            Source::State {
+                reader,
+                sequence,
+                changed: Instant::now(),
+            },
+        );
+    }
+
+    fn watch(&self, name: &str, max_silence: Duration, source: Source) {
+        debug!("Watchdog watching {} (max silence {:?})", name, max_silence);
+        self.watched.lock().unwrap_or_else(|e| e.into_inner()).push(Watched {
+            name: name.to_string(),
+            max_silence,
+            source,
+        });
+    }
+
+    /// Returns `true` while the watchdog thread is running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Opens the watchdog device, if any, and starts checking and feeding it. Does nothing if
+    /// it is already running. A software watchdog can only be started once, since its expiry
+    /// callback is consumed.
+    ///
+    /// # Errors
+    ///
+    /// If the watchdog device cannot be opened.
+    pub fn start(&mut self) -> crate::Result<&mut Self> {
+        if self.is_running() {
+            return Ok(self);
+        }
+
+        let mut device = match &self.kind {
+            Kind::Device(path) => {
+                let file = OpenOptions::new().write(true).open(path)?;
+                info!("Feeding watchdog {} every {:?}", path.display(), self.period);
+                Some(file)
+            }
+            Kind::Software { timeout, .. } => {
+                info!("Starting software watchdog with a {:?} timeout", timeout);
+                None
+            }
+        };
+        let (timeout, mut on_expire) = match &mut self.kind {
+            Kind::Device(_) => (None, None),
+            Kind::Software { timeout, on_expire } => (Some(*timeout), on_expire.take()),
+        };
+
+        self.running.store(true, Ordering::Release);
+
+        let period = self.period;
+        let max_failures = self.max_failures;
+        let watched = Arc::clone(&self.watched);
+        let running = Arc::clone(&self.running);
+
+        self.thread = Some(
+            thread::Builder::new()
+                .name("uptech-watchdog".into())
+                .spawn(move || {
+                    let mut healthy_since = Instant::now();
+                    let mut starving = false;
+
+                    while running.load(Ordering::Acquire) {
+                        let problem = check(&watched, max_failures);
+
+                        match &problem {
+                            None => {
+                                if starving {
+                                    info!("Watchdog sources healthy again");
+                                    starving = false;
+                                }
+                                healthy_since = Instant::now();
+                                if let Some(file) = &mut device
+                                    && let Err(e) = file.write_all(b"\0").and_then(|_| file.flush())
+                                {
+                                    warn!("Failed to feed the watchdog: {}", e);
+                                }
+                            }
+                            Some(reason) if !starving => {
+                                error!("Starving the watchdog: {}", reason);
+                                starving = true;
+                            }
+                            Some(_) => {}
+                        }
+
+                        if let (Some(timeout), Some(reason)) = (timeout, &problem)
+                            && healthy_since.elapsed() >= timeout
+                        {
+                            error!("Software watchdog expired: {}", reason);
+                            if let Some(on_expire) = on_expire.take() {
+                                on_expire();
+                            }
+                            std::process::abort();
+                        }
+
+                        thread::sleep(period);
+                    }
+
+                    if let Some(mut file) = device {
+                        disarm(&mut file);
+                    }
+                    debug!("Watchdog thread exited");
+                })
+                .expect("Failed to spawn watchdog thread"),
+        );
+
+        Ok(self)
+    }
+
+    /// Stops feeding and disarms the watchdog. Device watchdogs are disarmed with the magic
+    /// close character, which drivers built with `nowayout` ignore; the board then resets when
+    /// the timeout runs out.
+    ///
+    /// Returns:
+    ///   Self for chainable calls.
+    pub fn stop(&mut self) -> &mut Self {
+        self.running.store(false, Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+            info!("Watchdog stopped");
+        }
+
+        self
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Returns why the watched sources are unhealthy, or `None` if they are all fine.
+fn check(watched: &Mutex<Vec<Watched>>, max_failures: Option<u32>) -> Option<String> {
+    let mut watched = watched.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(problem) = watched.iter_mut().find_map(Watched::check) {
+        return Some(problem);
+    }
+
+    let max_failures = max_failures?;
+    let report = health::report();
+    [("ADC/IO", report.adc_io), ("MPU", report.mpu)]
+        .into_iter()
+        .find(|(_, health)| health.consecutive_failures >= max_failures)
+        .map(|(name, health)| format!("{} failed {} calls in a row", name, health.consecutive_failures))
+}
+
+/// Writes the magic close character so the driver disarms the watchdog on close.
+fn disarm(file: &mut File) {
+    if let Err(e) = file.write_all(b"V").and_then(|_| file.flush()) {
+        warn!("Failed to disarm the watchdog: {}", e);
+    }
+}